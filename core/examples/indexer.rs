@@ -24,14 +24,16 @@ fn main() -> anyhow::Result<()> {
         output_path: path.to_str().unwrap().to_string(),
         limit_warc_files: None,
         skip_warc_files: None,
-        warc_source: neos::config::WarcSource::Local(neos::config::LocalConfig {
+        source: neos::config::WarcSource::Local(neos::config::LocalConfig {
             folder: ".".to_string(),
             names: vec![data_path
                 .join("sample.warc.gz")
                 .to_str()
                 .unwrap()
                 .to_string()],
-        }),
+            date_filter: None,
+        })
+        .into(),
         page_webgraph: Some(IndexerGraphConfig::Local {
             path: data_path
                 .join("webgraph_page")
@@ -49,7 +51,12 @@ fn main() -> anyhow::Result<()> {
                 .to_string(),
         ),
         safety_classifier_path: None,
+        safety_classifier_threshold: None,
         minimum_clean_words: None,
+        minimum_content_density: None,
+        adservers_path: None,
+        simhash_dedup_threshold: None,
+        append_to: None,
         batch_size: 512,
         autocommit_after_num_inserts:
             neos::config::defaults::Indexing::autocommit_after_num_inserts(),
@@ -59,6 +66,7 @@ fn main() -> anyhow::Result<()> {
                 model_path: p,
                 page_centrality_rank_threshold: Some(1_000_000),
             }),
+        region_overrides: Default::default(),
     })?;
 
     println!("Indexing took {:?}", start.elapsed());
@@ -29,10 +29,12 @@ pub async fn main() {
         lambda_model_path: None,
         dual_encoder_model_path: None,
         bangs_path: Some("data/bangs.json".to_string()),
+        user_bangs_paths: Vec::new(),
         query_store_db_host: None,
         gossip_seed_nodes: None,
         gossip_addr: "0.0.0.0:8002".parse().unwrap(),
         collector: collector_conf.clone(),
+        compound_terms: Default::default(),
         thresholds: ApiThresholds::default(),
         widgets: WidgetsConfig {
             thesaurus_paths: vec!["data/english-wordnet-2022-subset.ttl".to_string()],
@@ -45,6 +47,7 @@ pub async fn main() {
         max_concurrent_searches: defaults::Api::max_concurrent_searches(),
         max_similar_hosts: defaults::Api::max_similar_hosts(),
         top_phrases_for_autosuggest: defaults::Api::top_phrases_for_autosuggest(),
+        query_log_path: None,
     };
 
     let mut searcher = LocalSearcher::new(index);
@@ -5,12 +5,13 @@
 
 use async_stream::stream;
 use bloom::fast_stable_hash_64;
-use futures::Stream;
+use futures::{Stream, StreamExt};
 use rand::seq::SliceRandom;
 use std::{
     collections::BTreeMap,
     net::SocketAddr,
     ops::{Bound, Range},
+    time::Duration,
 };
 
 use crate::{distributed::member::ShardId, Result};
@@ -112,6 +113,35 @@ impl Node {
         self.api.batch_set(table, values).await
     }
 
+    /// Set the value of the key so it is removed after `ttl`.
+    pub async fn set_with_ttl(
+        &self,
+        table: Table,
+        key: Key,
+        value: Value,
+        ttl: Duration,
+    ) -> Result<()> {
+        self.api.set_with_ttl(table, key, value, Some(ttl)).await
+    }
+
+    /// Set the values where the values with a ttl are removed after their ttl.
+    pub async fn batch_set_with_ttl(
+        &self,
+        table: Table,
+        values: Vec<(Key, Value, Option<Duration>)>,
+    ) -> Result<()> {
+        let mut ttls = Vec::with_capacity(values.len());
+        let values = values
+            .into_iter()
+            .map(|(key, value, ttl)| {
+                ttls.push(ttl);
+                (key, value)
+            })
+            .collect();
+
+        self.api.batch_set_with_ttl(table, values, ttls).await
+    }
+
     pub async fn upsert<F: Into<UpsertEnum>>(
         &self,
         table: Table,
@@ -122,6 +152,19 @@ impl Node {
         self.api.upsert(table, upsert, key, value).await
     }
 
+    /// Set the key to `new` if its current value is `expected` (`None` if the key
+    /// doesn't exist). Returns whether the value was swapped. The swap might have
+    /// happened even if an error is returned.
+    pub async fn compare_and_swap(
+        &self,
+        table: Table,
+        key: Key,
+        expected: Option<Value>,
+        new: Value,
+    ) -> Result<bool> {
+        self.api.compare_and_swap(table, key, expected, new).await
+    }
+
     pub async fn batch_upsert<F: Into<UpsertEnum>>(
         &self,
         table: Table,
@@ -148,18 +191,23 @@ impl Node {
     }
 
     pub fn stream(&self, table: Table) -> impl Stream<Item = Result<(Key, Value)>> + '_ {
+        self.range_stream(table, Bound::Unbounded..Bound::Unbounded)
+    }
+
+    /// Stream all the key-value pairs in the range in batches.
+    pub fn range_stream(
+        &self,
+        table: Table,
+        range: Range<Bound<Key>>,
+    ) -> impl Stream<Item = Result<(Key, Value)>> + '_ {
         const STREAM_BATCH_SIZE: usize = 1024;
         stream! {
-            let mut prev_key = None;
+            let mut start = range.start;
 
             loop {
                 let batch = self.range_get(
                     table.clone(),
-                    prev_key
-                        .as_ref()
-                        .cloned()
-                        .map_or(Bound::Unbounded, Bound::Excluded)
-                        ..Bound::Unbounded,
+                    start.clone()..range.end.clone(),
                     Some(STREAM_BATCH_SIZE),
                 ).await?;
 
@@ -168,14 +216,44 @@ impl Node {
                 }
 
                 for (key, value) in batch {
-                    yield Ok((key.clone(), value));
-                    prev_key = Some(key);
+                    start = Bound::Excluded(key.clone());
+                    yield Ok((key, value));
                 }
             }
         }
     }
 }
 
+/// The range of string keys that start with `prefix`.
+fn prefix_range(prefix: &[u8]) -> Result<Range<Bound<Key>>> {
+    let prefix = std::str::from_utf8(prefix)?;
+
+    // strings are ordered by their utf-8 bytes, which is the same as ordering by chars.
+    // the smallest string after all strings with the prefix is therefore the prefix
+    // with its last char incremented (dropping trailing chars that can't be incremented).
+    let mut upper: Vec<char> = prefix.chars().collect();
+    let end = loop {
+        match upper.pop() {
+            Some(c) => {
+                let next = match c {
+                    '\u{D7FF}' => Some('\u{E000}'),
+                    c => char::from_u32(c as u32 + 1),
+                };
+
+                if let Some(next) = next {
+                    upper.push(next);
+                    break Bound::Excluded(Key::String(upper.into_iter().collect()));
+                }
+            }
+            // there are no strings after the prefix, but all other key types are
+            // ordered after strings, so we stop before the smallest of them.
+            None => break Bound::Excluded(Key::NodeID(0u64.into())),
+        }
+    };
+
+    Ok(Bound::Included(Key::String(prefix.to_string()))..end)
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode)]
 pub struct Shard {
     nodes: Vec<Node>,
@@ -220,6 +298,24 @@ impl Shard {
         self.node().batch_set(table, values).await
     }
 
+    pub async fn set_with_ttl(
+        &self,
+        table: Table,
+        key: Key,
+        value: Value,
+        ttl: Duration,
+    ) -> Result<()> {
+        self.node().set_with_ttl(table, key, value, ttl).await
+    }
+
+    pub async fn batch_set_with_ttl(
+        &self,
+        table: Table,
+        values: Vec<(Key, Value, Option<Duration>)>,
+    ) -> Result<()> {
+        self.node().batch_set_with_ttl(table, values).await
+    }
+
     pub async fn upsert<F: Into<UpsertEnum>>(
         &self,
         table: Table,
@@ -230,6 +326,18 @@ impl Shard {
         self.node().upsert(table, upsert, key, value).await
     }
 
+    pub async fn compare_and_swap(
+        &self,
+        table: Table,
+        key: Key,
+        expected: Option<Value>,
+        new: Value,
+    ) -> Result<bool> {
+        self.node()
+            .compare_and_swap(table, key, expected, new)
+            .await
+    }
+
     pub async fn batch_upsert<F: Into<UpsertEnum>>(
         &self,
         table: Table,
@@ -242,11 +350,26 @@ impl Shard {
     pub fn stream(&self, table: Table) -> impl Stream<Item = Result<(Key, Value)>> + '_ {
         self.node().stream(table)
     }
+
+    pub fn range_stream(
+        &self,
+        table: Table,
+        range: Range<Bound<Key>>,
+    ) -> impl Stream<Item = Result<(Key, Value)>> + '_ {
+        self.node().range_stream(table, range)
+    }
 }
 
+/// The number of points each shard has on the hash ring. More points
+/// spread the keys more evenly between the shards.
+const VIRTUAL_NODES_PER_SHARD: u64 = 256;
+
 #[derive(Clone, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Debug)]
 pub struct Client {
-    ids: Vec<ShardId>,
+    /// Consistent hashing ring. A key belongs to the shard of the first point
+    /// at or after the hash of the key, so adding a shard only moves the keys
+    /// between its points and their predecessors.
+    ring: BTreeMap<u64, ShardId>,
     shards: BTreeMap<ShardId, Shard>,
 }
 
@@ -261,9 +384,24 @@ impl Client {
                 .add_node(*host);
         }
 
-        let ids = shards.keys().cloned().collect();
+        let ring = Self::ring(&shards);
+
+        Self { shards, ring }
+    }
+
+    fn ring(shards: &BTreeMap<ShardId, Shard>) -> BTreeMap<u64, ShardId> {
+        let mut ring = BTreeMap::new();
 
-        Self { shards, ids }
+        for shard_id in shards.keys() {
+            for vnode in 0..VIRTUAL_NODES_PER_SHARD {
+                let mut point = shard_id.as_u64().to_le_bytes().to_vec();
+                point.extend_from_slice(&vnode.to_le_bytes());
+
+                ring.insert(fast_stable_hash_64(&point), *shard_id);
+            }
+        }
+
+        ring
     }
 
     pub fn shards(&self) -> &BTreeMap<ShardId, Shard> {
@@ -273,17 +411,18 @@ impl Client {
     pub fn add_node(&mut self, shard_id: ShardId, addr: SocketAddr) {
         self.shards.entry(shard_id).or_default().add_node(addr);
 
-        self.ids = self.shards.keys().cloned().collect();
+        self.ring = Self::ring(&self.shards);
     }
 
     fn shard_id_for_key(&self, key: &[u8]) -> Result<&ShardId> {
-        if self.ids.is_empty() {
-            return Err(anyhow::anyhow!("No shards"));
-        }
-
         let hash = fast_stable_hash_64(key);
 
-        Ok(&self.ids[hash as usize % self.ids.len()])
+        self.ring
+            .range(hash..)
+            .next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, shard_id)| shard_id)
+            .ok_or_else(|| anyhow::anyhow!("No shards"))
     }
 
     fn shard_for_key(&self, key: &[u8]) -> Result<&Shard> {
@@ -341,6 +480,45 @@ impl Client {
         Ok(())
     }
 
+    pub async fn set_with_ttl(
+        &self,
+        table: Table,
+        key: Key,
+        value: Value,
+        ttl: Duration,
+    ) -> Result<()> {
+        self.shard_for_key(&key.as_bytes())?
+            .set_with_ttl(table, key, value, ttl)
+            .await
+    }
+
+    pub async fn batch_set_with_ttl(
+        &self,
+        table: Table,
+        values: Vec<(Key, Value, Option<Duration>)>,
+    ) -> Result<()> {
+        let mut shard_values: BTreeMap<ShardId, Vec<(Key, Value, Option<Duration>)>> =
+            BTreeMap::new();
+
+        for (key, value, ttl) in values {
+            let shard = self.shard_id_for_key(&key.as_bytes())?;
+            shard_values
+                .entry(*shard)
+                .or_default()
+                .push((key, value, ttl));
+        }
+
+        let mut futures = Vec::with_capacity(shard_values.len());
+
+        for (shard_id, values) in shard_values {
+            futures.push(self.shards[&shard_id].batch_set_with_ttl(table.clone(), values));
+        }
+
+        futures::future::try_join_all(futures).await?;
+
+        Ok(())
+    }
+
     pub async fn num_keys(&self, table: Table) -> Result<u64> {
         let mut total = 0;
 
@@ -363,6 +541,18 @@ impl Client {
             .await
     }
 
+    pub async fn compare_and_swap(
+        &self,
+        table: Table,
+        key: Key,
+        expected: Option<Value>,
+        new: Value,
+    ) -> Result<bool> {
+        self.shard_for_key(&key.as_bytes())?
+            .compare_and_swap(table, key, expected, new)
+            .await
+    }
+
     pub async fn batch_upsert<F: Into<UpsertEnum> + Clone>(
         &self,
         table: Table,
@@ -446,4 +636,68 @@ impl Client {
 
         futures::stream::select_all(streams)
     }
+
+    /// Stream the (string) keys that start with `prefix` from all shards.
+    /// The keys are not ordered across shards.
+    pub fn prefix_get(
+        &self,
+        table: Table,
+        prefix: &[u8],
+        limit: Option<usize>,
+    ) -> impl Stream<Item = Result<(Key, Value)>> + '_ {
+        let range = match prefix_range(prefix) {
+            Ok(range) => range,
+            Err(err) => return futures::stream::once(async move { Err(err) }).left_stream(),
+        };
+
+        let mut streams = Vec::new();
+        for shard in self.shards.values() {
+            streams.push(Box::pin(shard.range_stream(table.clone(), range.clone())));
+        }
+
+        futures::stream::select_all(streams)
+            .take(limit.unwrap_or(usize::MAX))
+            .right_stream()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adding_shard_moves_few_keys() {
+        let members: Vec<_> = (0..3)
+            .map(|i| (ShardId::new(i), ([127, 0, 0, 1], 10_000 + i as u16).into()))
+            .collect();
+        let mut client = Client::new(&members);
+
+        let keys: Vec<_> = (0..10_000u64).map(|i| i.to_le_bytes()).collect();
+        let before: Vec<_> = keys
+            .iter()
+            .map(|key| *client.shard_id_for_key(key).unwrap())
+            .collect();
+
+        for shard in 0..3 {
+            let num_keys = before.iter().filter(|s| s.as_u64() == shard).count();
+            assert!(num_keys > keys.len() / 6);
+        }
+
+        let new_shard = ShardId::new(3);
+        client.add_node(new_shard, ([127, 0, 0, 1], 10_003).into());
+
+        let mut moved = 0;
+        for (key, old_shard) in keys.iter().zip(before) {
+            let new = *client.shard_id_for_key(key).unwrap();
+
+            if new != old_shard {
+                // keys only move to the new shard
+                assert_eq!(new, new_shard);
+                moved += 1;
+            }
+        }
+
+        assert!(moved > 0);
+        assert!(moved < keys.len() / 2);
+    }
 }
@@ -9,11 +9,10 @@
 //! It is intended to be deployed across multiple nodes
 //! with multiple shards. Each shard cluster
 //! is a Raft cluster, and each key is then routed to the correct
-//! cluster based on hash(key) % number_of_shards. The keys
-//! are currently *not* rebalanced if the number of shards change, so
-//! if an entire shard becomes unavailable or a new shard is added, all
-//! keys in the entire DHT is essentially lost as the
-//! keys might hash incorrectly.
+//! cluster using a consistent hashing ring over the shards. The keys
+//! are currently *not* moved if the number of shards change, so
+//! if an entire shard becomes unavailable or a new shard is added, the
+//! keys that now hash to a different shard are essentially lost.
 //!
 //! Heavily inspired by https://github.com/datafuselabs/openraft/blob/main/examples/raft-kv-memstore/
 
@@ -26,7 +25,8 @@ pub mod upsert;
 pub mod value;
 
 use network::api::{
-    AllTables, BatchSet, BatchUpsert, CloneTable, CreateTable, DropTable, Set, Upsert,
+    AllTables, BatchSet, BatchUpsert, CloneTable, CompareAndSwap, CreateTable, DropTable, Set,
+    Upsert,
 };
 
 use std::fmt::Debug;
@@ -76,7 +76,7 @@ impl BasicNode {
 openraft::declare_raft_types!(
     /// Declare the type configuration for example K/V store.
     pub TypeConfig:
-        D = Request,
+        D = LogRequest,
         R = Response,
         NodeId = NodeId,
         Node = BasicNode,
@@ -112,6 +112,26 @@ macro_rules! raft_sonic_request_response {
     }
 }
 
+/// A request in the raft log together with the time (milliseconds since the unix epoch)
+/// the request was written to the log. The state machine uses this time instead of
+/// the local clock so all replicas apply the log to the same state.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct LogRequest {
+    pub now: u64,
+    pub request: Request,
+}
+
+impl LogRequest {
+    /// Stamps the request with the local time. Only writes on the leader make it
+    /// into the log, so the entries are always stamped with the leader's clock.
+    pub fn new(request: impl Into<Request>) -> Self {
+        Self {
+            now: store::unix_millis(),
+            request: request.into(),
+        }
+    }
+}
+
 raft_sonic_request_response!(
     Server,
     [
@@ -122,7 +142,8 @@ raft_sonic_request_response!(
         CreateTable,
         DropTable,
         AllTables,
-        CloneTable
+        CloneTable,
+        CompareAndSwap
     ]
 );
 
@@ -439,6 +460,263 @@ pub mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn test_ttl() -> anyhow::Result<()> {
+        let (raft, server, addr) = server(1).await?;
+
+        tokio::spawn(async move {
+            loop {
+                server.accept().await.unwrap();
+            }
+        });
+
+        let members: BTreeMap<u64, _> = vec![(1, addr)]
+            .into_iter()
+            .map(|(id, addr)| (id, BasicNode::new(addr)))
+            .collect();
+
+        if let Err(e) = raft.initialize(members.clone()).await {
+            match e {
+                openraft::error::RaftError::APIError(e) => match e {
+                    InitializeError::NotAllowed(_) => {}
+                    InitializeError::NotInMembers(_) => panic!("{:?}", e),
+                },
+                openraft::error::RaftError::Fatal(_) => panic!("{:?}", e),
+            }
+        };
+
+        let client = dht::client::Node::new(addr);
+        let table = Table::from("test");
+
+        client
+            .set_with_ttl(
+                table.clone(),
+                "hello".to_string().into(),
+                "world".to_string().into(),
+                std::time::Duration::from_secs(0),
+            )
+            .await?;
+        client
+            .set(
+                table.clone(),
+                "permanent".to_string().into(),
+                "world".to_string().into(),
+            )
+            .await?;
+
+        assert_eq!(
+            client
+                .get(table.clone(), "hello".to_string().into())
+                .await?,
+            None
+        );
+        assert_eq!(
+            client
+                .get(table.clone(), "permanent".to_string().into())
+                .await?,
+            Some("world".to_string().into())
+        );
+
+        client
+            .batch_set_with_ttl(
+                table.clone(),
+                vec![
+                    (
+                        "a".to_string().into(),
+                        1u64.into(),
+                        Some(std::time::Duration::from_secs(0)),
+                    ),
+                    (
+                        "b".to_string().into(),
+                        2u64.into(),
+                        Some(std::time::Duration::from_secs(3600)),
+                    ),
+                    ("c".to_string().into(), 3u64.into(), None),
+                ],
+            )
+            .await?;
+
+        let res = client
+            .batch_get(
+                table.clone(),
+                vec![
+                    "a".to_string().into(),
+                    "b".to_string().into(),
+                    "c".to_string().into(),
+                ],
+            )
+            .await?;
+
+        assert_eq!(
+            res,
+            vec![
+                ("b".to_string().into(), 2u64.into()),
+                ("c".to_string().into(), 3u64.into()),
+            ]
+        );
+        assert_eq!(client.num_keys(table.clone()).await?, 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_compare_and_swap() -> anyhow::Result<()> {
+        let (raft, server, addr) = server(1).await?;
+
+        tokio::spawn(async move {
+            loop {
+                server.accept().await.unwrap();
+            }
+        });
+
+        let members: BTreeMap<u64, _> = vec![(1, addr)]
+            .into_iter()
+            .map(|(id, addr)| (id, BasicNode::new(addr)))
+            .collect();
+
+        if let Err(e) = raft.initialize(members.clone()).await {
+            match e {
+                openraft::error::RaftError::APIError(e) => match e {
+                    InitializeError::NotAllowed(_) => {}
+                    InitializeError::NotInMembers(_) => panic!("{:?}", e),
+                },
+                openraft::error::RaftError::Fatal(_) => panic!("{:?}", e),
+            }
+        };
+
+        let client = dht::client::Client::new(&[(ShardId::new(1), addr)]);
+        let table = Table::from("test");
+        let key: Key = "counter".to_string().into();
+
+        // the key doesn't exist yet
+        assert!(
+            client
+                .compare_and_swap(table.clone(), key.clone(), None, 1u64.into())
+                .await?
+        );
+        assert!(
+            !client
+                .compare_and_swap(table.clone(), key.clone(), None, 2u64.into())
+                .await?
+        );
+
+        assert!(
+            client
+                .compare_and_swap(table.clone(), key.clone(), Some(1u64.into()), 2u64.into())
+                .await?
+        );
+        assert_eq!(
+            client.get(table.clone(), key.clone()).await?,
+            Some(2u64.into())
+        );
+
+        // mismatched expected value
+        assert!(
+            !client
+                .compare_and_swap(table.clone(), key.clone(), Some(1u64.into()), 3u64.into())
+                .await?
+        );
+        assert_eq!(
+            client.get(table.clone(), key.clone()).await?,
+            Some(2u64.into())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_prefix_get() -> anyhow::Result<()> {
+        let mut members = Vec::new();
+
+        for id in 1..=2 {
+            let (raft, server, addr) = server(id).await?;
+
+            tokio::spawn(async move {
+                loop {
+                    server.accept().await.unwrap();
+                }
+            });
+
+            let raft_members: BTreeMap<u64, _> =
+                vec![(id, BasicNode::new(addr))].into_iter().collect();
+
+            if let Err(e) = raft.initialize(raft_members).await {
+                match e {
+                    openraft::error::RaftError::APIError(e) => match e {
+                        InitializeError::NotAllowed(_) => {}
+                        InitializeError::NotInMembers(_) => panic!("{:?}", e),
+                    },
+                    openraft::error::RaftError::Fatal(_) => panic!("{:?}", e),
+                }
+            };
+
+            members.push((crate::distributed::member::ShardId::new(id), addr));
+        }
+
+        let client = dht::client::Client::new(&members);
+        let table = Table::from("test");
+        client.create_table(table.clone()).await?;
+
+        // the keys are spread over both shards by their hash
+        let mut expected = Vec::new();
+        let mut values = Vec::new();
+        for i in 0..100u64 {
+            let ns = if i % 2 == 0 { "a" } else { "b" };
+            let key: Key = format!("{ns}/{i}").into();
+            let value: Value = i.into();
+
+            if ns == "a" {
+                expected.push((key.clone(), value.clone()));
+            }
+
+            values.push((key, value));
+        }
+        values.push(("a".to_string().into(), 1000u64.into()));
+        values.push((1u64.into(), 1001u64.into()));
+        client.batch_set(table.clone(), values).await?;
+
+        let stream = client.prefix_get(table.clone(), b"a/", None);
+        pin_mut!(stream);
+
+        let mut res = Vec::new();
+        while let Some((k, v)) = stream.try_next().await? {
+            res.push((k, v));
+        }
+
+        res.sort_by_key(|(k, _)| k.clone());
+        expected.sort_by_key(|(k, _)| k.clone());
+        assert_eq!(res, expected);
+
+        let stream = client.prefix_get(table.clone(), b"a/", Some(10));
+        pin_mut!(stream);
+
+        let mut res = Vec::new();
+        while let Some((k, v)) = stream.try_next().await? {
+            res.push((k, v));
+        }
+
+        assert_eq!(res.len(), 10);
+        assert!(res
+            .iter()
+            .all(|(k, _)| expected.iter().any(|(e, _)| e == k)));
+
+        // the empty prefix matches all string keys, but no other keys
+        let stream = client.prefix_get(table.clone(), b"", None);
+        pin_mut!(stream);
+
+        let mut num_res = 0;
+        while let Some((k, _)) = stream.try_next().await? {
+            assert!(matches!(k, Key::String(_)));
+            num_res += 1;
+        }
+        assert_eq!(num_res, 101);
+
+        Ok(())
+    }
+
     #[tokio::test]
     #[traced_test]
     #[ignore = "comitted logs must be stored in stable storage for raft to be able to recover from a node crash"]
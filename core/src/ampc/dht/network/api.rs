@@ -23,7 +23,8 @@ use std::{
 
 use crate::{
     ampc::dht::{
-        key::Key, store::Table, upsert::UpsertEnum, value::Value, BasicNode, UpsertAction,
+        key::Key, store::Table, upsert::UpsertEnum, value::Value, BasicNode, LogRequest,
+        UpsertAction,
     },
     distributed::retry_strategy::RandomBackoff,
     Result,
@@ -41,12 +42,17 @@ pub struct Set {
     pub table: Table,
     pub key: Key,
     pub value: Value,
+    /// The key expires this long after the request is written to the log.
+    /// The key never expires if `None`.
+    pub ttl: Option<Duration>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode)]
 pub struct BatchSet {
     pub table: Table,
     pub values: Arc<Vec<(Key, Value)>>,
+    /// Either empty if no keys expire, or the ttl of each value (see [`Set::ttl`]).
+    pub ttls: Arc<Vec<Option<Duration>>>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode)]
@@ -64,6 +70,15 @@ pub struct BatchUpsert {
     pub upsert_fn: UpsertEnum,
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode)]
+pub struct CompareAndSwap {
+    pub table: Table,
+    pub key: Key,
+    /// The value the key must have for the swap to happen. `None` means the key must not exist.
+    pub expected: Option<Value>,
+    pub new: Value,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode)]
 pub struct Get {
     pub table: Table,
@@ -116,7 +131,7 @@ impl sonic::service::Message<Server> for Set {
     async fn handle(self, server: &Server) -> Self::Response {
         tracing::debug!("received set request: {:?}", self);
 
-        match server.raft.client_write(self.into()).await {
+        match server.raft.client_write(LogRequest::new(self)).await {
             Ok(_) => Ok(()),
             Err(e) => Err(crate::bincode_utils::SerdeCompat(e)),
         }
@@ -132,7 +147,7 @@ impl sonic::service::Message<Server> for BatchSet {
     async fn handle(self, server: &Server) -> Self::Response {
         tracing::debug!("received batch set request: {:?}", self);
 
-        match server.raft.client_write(self.into()).await {
+        match server.raft.client_write(LogRequest::new(self)).await {
             Ok(_) => Ok(()),
             Err(e) => Err(crate::bincode_utils::SerdeCompat(e)),
         }
@@ -148,7 +163,7 @@ impl sonic::service::Message<Server> for Upsert {
     async fn handle(self, server: &Server) -> Self::Response {
         tracing::debug!("received upsert request: {:?}", self);
 
-        match server.raft.client_write(self.into()).await {
+        match server.raft.client_write(LogRequest::new(self)).await {
             Ok(res) => match res.data {
                 crate::ampc::dht::Response::Upsert(res) => res,
                 _ => panic!("unexpected response from raft"),
@@ -167,7 +182,7 @@ impl sonic::service::Message<Server> for BatchUpsert {
     async fn handle(self, server: &Server) -> Self::Response {
         tracing::debug!("received batch upsert request: {:?}", self);
 
-        match server.raft.client_write(self.into()).await {
+        match server.raft.client_write(LogRequest::new(self)).await {
             Ok(res) => match res.data {
                 crate::ampc::dht::Response::BatchUpsert(res) => res,
                 _ => panic!("unexpected response from raft"),
@@ -177,6 +192,25 @@ impl sonic::service::Message<Server> for BatchUpsert {
     }
 }
 
+impl sonic::service::Message<Server> for CompareAndSwap {
+    type Response = Result<
+        bool,
+        crate::bincode_utils::SerdeCompat<RaftError<NodeId, ClientWriteError<NodeId, BasicNode>>>,
+    >;
+
+    async fn handle(self, server: &Server) -> Self::Response {
+        tracing::debug!("received compare and swap request: {:?}", self);
+
+        match server.raft.client_write(LogRequest::new(self)).await {
+            Ok(res) => match res.data {
+                crate::ampc::dht::Response::CompareAndSwap(res) => res,
+                _ => panic!("unexpected response from raft"),
+            },
+            Err(e) => Err(crate::bincode_utils::SerdeCompat(e)),
+        }
+    }
+}
+
 impl sonic::service::Message<Server> for Get {
     type Response = Option<Value>;
 
@@ -226,7 +260,7 @@ impl sonic::service::Message<Server> for DropTable {
     >;
 
     async fn handle(self, server: &Server) -> Self::Response {
-        match server.raft.client_write(self.into()).await {
+        match server.raft.client_write(LogRequest::new(self)).await {
             Ok(_) => Ok(()),
             Err(e) => Err(crate::bincode_utils::SerdeCompat(e)),
         }
@@ -240,7 +274,7 @@ impl sonic::service::Message<Server> for CreateTable {
     >;
 
     async fn handle(self, server: &Server) -> Self::Response {
-        match server.raft.client_write(self.into()).await {
+        match server.raft.client_write(LogRequest::new(self)).await {
             Ok(_) => Ok(()),
             Err(e) => Err(crate::bincode_utils::SerdeCompat(e)),
         }
@@ -254,7 +288,7 @@ impl sonic::service::Message<Server> for AllTables {
     >;
 
     async fn handle(self, server: &Server) -> Self::Response {
-        match server.raft.client_write(self.into()).await {
+        match server.raft.client_write(LogRequest::new(self)).await {
             Ok(res) => match res.data {
                 crate::ampc::dht::Response::AllTables(tables) => tables,
                 _ => panic!("unexpected response from raft"),
@@ -271,7 +305,7 @@ impl sonic::service::Message<Server> for CloneTable {
     >;
 
     async fn handle(self, server: &Server) -> Self::Response {
-        match server.raft.client_write(self.into()).await {
+        match server.raft.client_write(LogRequest::new(self)).await {
             Ok(_) => Ok(()),
             Err(e) => Err(crate::bincode_utils::SerdeCompat(e)),
         }
@@ -325,6 +359,16 @@ impl RemoteClient {
     }
 
     pub async fn set(&self, table: Table, key: Key, value: Value) -> Result<()> {
+        self.set_with_ttl(table, key, value, None).await
+    }
+
+    pub async fn set_with_ttl(
+        &self,
+        table: Table,
+        key: Key,
+        value: Value,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
         for backoff in Self::retry_strat() {
             let res = self
                 .likely_leader
@@ -337,6 +381,7 @@ impl RemoteClient {
                         table: table.clone(),
                         key: key.clone(),
                         value: value.clone(),
+                        ttl,
                     },
                     Duration::from_secs(60),
                 )
@@ -374,7 +419,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -392,7 +437,26 @@ impl RemoteClient {
     }
 
     pub async fn batch_set(&self, table: Table, values: Vec<(Key, Value)>) -> Result<()> {
+        self.batch_set_with_ttl(table, values, Vec::new()).await
+    }
+
+    pub async fn batch_set_with_ttl(
+        &self,
+        table: Table,
+        values: Vec<(Key, Value)>,
+        ttls: Vec<Option<Duration>>,
+    ) -> Result<()> {
+        // the state machine cannot reject a request once it is in the log
+        if !ttls.is_empty() && ttls.len() != values.len() {
+            return Err(anyhow!(
+                "got {} ttls for {} values, expected one ttl per value or none",
+                ttls.len(),
+                values.len()
+            ));
+        }
+
         let values = Arc::new(values);
+        let ttls = Arc::new(ttls);
 
         for backoff in Self::retry_strat() {
             let res = self
@@ -405,6 +469,7 @@ impl RemoteClient {
                     BatchSet {
                         table: table.clone(),
                         values: values.clone(),
+                        ttls: ttls.clone(),
                     },
                     Duration::from_secs(60),
                 )
@@ -442,7 +507,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -475,7 +540,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -509,7 +574,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -545,7 +610,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -610,7 +675,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -675,7 +740,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -735,7 +800,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -801,7 +866,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -877,7 +942,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -894,6 +959,84 @@ impl RemoteClient {
         Err(anyhow!("failed to perform upsert"))
     }
 
+    /// Unlike the other writes, a compare and swap is not retried once the request
+    /// might have reached the leader as it is not idempotent. The caller should read
+    /// the key again before deciding whether to retry.
+    pub async fn compare_and_swap(
+        &self,
+        table: Table,
+        key: Key,
+        expected: Option<Value>,
+        new: Value,
+    ) -> Result<bool> {
+        for backoff in Self::retry_strat() {
+            let res = self
+                .likely_leader
+                .read()
+                .await
+                .as_ref()
+                .unwrap_or(&self.self_remote)
+                .send_with_timeout(
+                    CompareAndSwap {
+                        table: table.clone(),
+                        key: key.clone(),
+                        expected: expected.clone(),
+                        new: new.clone(),
+                    },
+                    Duration::from_secs(60),
+                )
+                .await;
+
+            match res {
+                Ok(res) => match res {
+                    Ok(res) => return Ok(res),
+                    Err(crate::bincode_utils::SerdeCompat(RaftError::APIError(e))) => match e {
+                        ClientWriteError::ForwardToLeader(ForwardToLeader {
+                            leader_id: _,
+                            leader_node,
+                        }) => match leader_node {
+                            Some(leader_node) => {
+                                let mut likely_leader = self.likely_leader.write().await;
+                                *likely_leader = Some(sonic::replication::RemoteClient::new(
+                                    leader_node
+                                        .addr
+                                        .parse()
+                                        .expect("node addr should always be valid addr"),
+                                ));
+                            }
+                            None => {
+                                tokio::time::sleep(backoff).await;
+                            }
+                        },
+                        ClientWriteError::ChangeMembershipError(_) => {
+                            unreachable!(".compare_and_swap() should not change membership")
+                        }
+                    },
+                    Err(crate::bincode_utils::SerdeCompat(RaftError::Fatal(e))) => {
+                        return Err(e.into())
+                    }
+                },
+                Err(e) => match e {
+                    // the request was never sent
+                    sonic::Error::ConnectionTimeout | sonic::Error::PoolGet => {
+                        tokio::time::sleep(backoff).await;
+                    }
+                    // the leader might have applied the swap, and applying it again
+                    // could swap a value another client has set in the meantime
+                    sonic::Error::IO(_) | sonic::Error::Timeout => return Err(e.into()),
+                    sonic::Error::BadRequest
+                    | sonic::Error::BodyTooLarge {
+                        body_size: _,
+                        max_size: _,
+                    }
+                    | sonic::Error::Application(_) => return Err(e.into()),
+                },
+            }
+        }
+
+        Err(anyhow!("failed to perform compare and swap"))
+    }
+
     pub async fn batch_upsert<F: Into<UpsertEnum>>(
         &self,
         table: Table,
@@ -952,7 +1095,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -995,7 +1138,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -1012,3 +1155,23 @@ impl RemoteClient {
         Err(anyhow!("failed to perform range get"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn batch_set_with_mismatched_ttls() {
+        let client = RemoteClient::new("127.0.0.1:1".parse().unwrap());
+
+        let res = client
+            .batch_set_with_ttl(
+                Table::from("test"),
+                vec![(1u64.into(), 1u64.into()), (2u64.into(), 2u64.into())],
+                vec![Some(Duration::from_secs(3600))],
+            )
+            .await;
+
+        assert!(res.is_err());
+    }
+}
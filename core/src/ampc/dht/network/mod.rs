@@ -18,8 +18,8 @@ pub mod api;
 pub mod raft;
 
 use api::{
-    AllTables, BatchGet, BatchSet, BatchUpsert, CloneTable, CreateTable, DropTable, Get, NumKeys,
-    RangeGet, Set, Upsert,
+    AllTables, BatchGet, BatchSet, BatchUpsert, CloneTable, CompareAndSwap, CreateTable, DropTable,
+    Get, NumKeys, RangeGet, Set, Upsert,
 };
 use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
 
@@ -78,6 +78,7 @@ pub struct Metrics;
 
 sonic_service!(
     Server,
+    version = 1,
     [
         AppendEntries,
         InstallSnapshot,
@@ -97,6 +98,7 @@ sonic_service!(
         AllTables,
         CloneTable,
         RangeGet,
+        CompareAndSwap,
     ]
 );
 
@@ -228,7 +228,7 @@ impl RemoteClient {
         conn.send_with_timeout(rpc, option.soft_ttl())
             .await
             .map_err(|e| match e {
-                sonic::Error::ConnectionTimeout | sonic::Error::RequestTimeout => {
+                sonic::Error::ConnectionTimeout | sonic::Error::Timeout => {
                     crate::bincode_utils::SerdeCompat(RPCError::Unreachable(
                         openraft::error::Unreachable::new(&e),
                     ))
@@ -287,7 +287,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -346,7 +346,7 @@ impl RemoteClient {
                 Err(e) => match e {
                     sonic::Error::IO(_)
                     | sonic::Error::ConnectionTimeout
-                    | sonic::Error::RequestTimeout
+                    | sonic::Error::Timeout
                     | sonic::Error::PoolGet => {
                         tokio::time::sleep(backoff).await;
                     }
@@ -13,13 +13,14 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
 use std::io::Cursor;
 use std::ops::Bound;
 use std::ops::Range;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Duration;
 
 use openraft::storage::RaftStateMachine;
 use openraft::storage::Snapshot;
@@ -44,7 +45,7 @@ use super::BasicNode;
 use super::NodeId;
 use super::TypeConfig;
 use super::UpsertAction;
-use super::{Request, Response};
+use super::{LogRequest, Request, Response};
 
 #[derive(
     serde::Serialize,
@@ -79,15 +80,92 @@ impl From<&str> for Table {
     }
 }
 
+/// Milliseconds since the unix epoch.
+pub fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// The expiry times of the keys in a table that were set with a ttl.
+#[derive(
+    serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Debug, Default, Clone,
+)]
+struct Expiries {
+    by_key: BTreeMap<Key, u64>,
+    by_time: BTreeSet<(u64, Key)>,
+}
+
+impl Expiries {
+    fn set(&mut self, key: &Key, expires_at: Option<u64>) {
+        if let Some(old) = self.by_key.remove(key) {
+            self.by_time.remove(&(old, key.clone()));
+        }
+
+        if let Some(expires_at) = expires_at {
+            self.by_key.insert(key.clone(), expires_at);
+            self.by_time.insert((expires_at, key.clone()));
+        }
+    }
+
+    fn is_expired(&self, key: &Key, now: u64) -> bool {
+        self.by_key
+            .get(key)
+            .is_some_and(|expires_at| *expires_at <= now)
+    }
+
+    fn num_expired(&self, now: u64) -> usize {
+        self.by_time
+            .iter()
+            .take_while(|(expires_at, _)| *expires_at <= now)
+            .count()
+    }
+
+    fn pop_expired(&mut self, now: u64) -> Vec<Key> {
+        let mut res = Vec::new();
+
+        while self
+            .by_time
+            .first()
+            .is_some_and(|(expires_at, _)| *expires_at <= now)
+        {
+            let (_, key) = self.by_time.pop_first().unwrap();
+            self.by_key.remove(&key);
+            res.push(key);
+        }
+
+        res
+    }
+}
+
 #[derive(
     serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Debug, Default, Clone,
 )]
 pub struct Db {
     data: BTreeMap<Table, BTreeMap<Key, Value>>,
+    expiries: BTreeMap<Table, Expiries>,
+    /// The time the leader stamped into the last applied log entry (milliseconds since the
+    /// unix epoch). Expiry is checked against this instead of the local clock, so every
+    /// replica that has applied the same log agrees on which keys have expired. Reads
+    /// only see a key expire once an entry stamped after its expiry has been applied.
+    now: u64,
 }
 
 impl Db {
+    /// Move the clock of the database forward to `now`. The clock never moves backwards,
+    /// even if a new leader's clock is behind the previous leader's.
+    pub fn advance_clock(&mut self, now: u64) {
+        self.now = self.now.max(now);
+    }
+
+    fn expires_at(&self, ttl: Duration) -> u64 {
+        self.now.saturating_add(ttl.as_millis() as u64)
+    }
+
     pub fn drop_table(&mut self, table: &Table) {
+        self.expiries.remove(table);
+
         let table = self.data.remove(table);
         if let Some(table) = table {
             // drop in background as some tables can be large
@@ -97,15 +175,82 @@ impl Db {
         }
     }
 
+    fn is_expired(&self, table: &Table, key: &Key, now: u64) -> bool {
+        self.expiries
+            .get(table)
+            .is_some_and(|expiries| expiries.is_expired(key, now))
+    }
+
+    /// Expired entries are skipped when reading and dropped
+    /// the next time the table is written to.
+    fn drop_expired(&mut self, table: &Table) {
+        if let Some(expiries) = self.expiries.get_mut(table) {
+            let expired = expiries.pop_expired(self.now);
+
+            if let Some(data) = self.data.get_mut(table) {
+                for key in expired {
+                    data.remove(&key);
+                }
+            }
+        }
+    }
+
     pub fn get(&self, table: &Table, key: &Key) -> Option<Value> {
+        if self.is_expired(table, key, self.now) {
+            return None;
+        }
+
         self.data.get(table).and_then(|m| m.get(key).cloned())
     }
 
-    pub fn set(&mut self, table: Table, key: Key, value: Value) {
+    /// Set the value of the key. The key expires `ttl` after the current time of the database
+    /// if set, otherwise it is kept until it is overwritten or the table is dropped.
+    pub fn set(&mut self, table: Table, key: Key, value: Value, ttl: Option<Duration>) {
+        self.drop_expired(&table);
+        let expires_at = ttl.map(|ttl| self.expires_at(ttl));
+
+        if expires_at.is_some() || self.expiries.contains_key(&table) {
+            self.expiries
+                .entry(table.clone())
+                .or_default()
+                .set(&key, expires_at);
+        }
+
         self.data.entry(table).or_default().insert(key, value);
     }
 
-    pub fn batch_set(&mut self, table: Table, values: Vec<(Key, Value)>) {
+    /// Set the values of the keys. `ttls` is either empty if no keys expire
+    /// or has the ttl of each value (see [`Db::set`]).
+    ///
+    /// # Panics
+    /// If `ttls` is neither empty nor the same length as `values`.
+    pub fn batch_set(
+        &mut self,
+        table: Table,
+        values: Vec<(Key, Value)>,
+        ttls: &[Option<Duration>],
+    ) {
+        assert!(
+            ttls.is_empty() || ttls.len() == values.len(),
+            "got {} ttls for {} values",
+            ttls.len(),
+            values.len()
+        );
+
+        self.drop_expired(&table);
+
+        if !ttls.is_empty() || self.expiries.contains_key(&table) {
+            let expires_at: Vec<_> = ttls
+                .iter()
+                .map(|ttl| ttl.map(|ttl| self.expires_at(ttl)))
+                .collect();
+            let expiries = self.expiries.entry(table.clone()).or_default();
+
+            for (i, (key, _)) in values.iter().enumerate() {
+                expiries.set(key, expires_at.get(i).copied().flatten());
+            }
+        }
+
         let table = self.data.entry(table).or_default();
 
         // for some reason, the entry API seems to be faster than using extend or inserts
@@ -122,7 +267,17 @@ impl Db {
     }
 
     pub fn num_keys(&self, table: &Table) -> usize {
-        self.data.get(table).map(|m| m.len()).unwrap_or(0)
+        let num_expired = self
+            .expiries
+            .get(table)
+            .map(|expiries| expiries.num_expired(self.now))
+            .unwrap_or(0);
+
+        self.data
+            .get(table)
+            .map(|m| m.len())
+            .unwrap_or(0)
+            .saturating_sub(num_expired)
     }
 
     pub fn upsert(
@@ -132,6 +287,7 @@ impl Db {
         key: Key,
         value: Value,
     ) -> UpsertAction {
+        self.drop_expired(&table);
         let table = self.data.entry(table).or_default();
 
         match table.get_mut(&key) {
@@ -155,12 +311,38 @@ impl Db {
         }
     }
 
+    /// Set the key to `new` if its current value is `expected` (`None` if the key
+    /// doesn't exist). Returns whether the value was swapped.
+    pub fn compare_and_swap(
+        &mut self,
+        table: Table,
+        key: Key,
+        expected: Option<&Value>,
+        new: Value,
+    ) -> bool {
+        self.drop_expired(&table);
+
+        let current = self.data.get(&table).and_then(|data| data.get(&key));
+        if current != expected {
+            return false;
+        }
+
+        if let Some(expiries) = self.expiries.get_mut(&table) {
+            expiries.set(&key, None);
+        }
+
+        self.data.entry(table).or_default().insert(key, new);
+
+        true
+    }
+
     pub fn batch_upsert(
         &mut self,
         table: Table,
         upsert_fn: &UpsertEnum,
         values: Vec<(Key, Value)>,
     ) -> Vec<(Key, UpsertAction)> {
+        self.drop_expired(&table);
         let table = self.data.entry(table).or_default();
         let mut res = Vec::with_capacity(values.len());
 
@@ -190,10 +372,21 @@ impl Db {
 
     pub fn clone_table(&mut self, from: &Table, to: Table) {
         let data = self.data.get(from).cloned().unwrap_or_default();
+
+        match self.expiries.get(from).cloned() {
+            Some(expiries) => {
+                self.expiries.insert(to.clone(), expiries);
+            }
+            None => {
+                self.expiries.remove(&to);
+            }
+        }
+
         self.data.insert(to, data);
     }
 
     pub fn new_table(&mut self, table: Table) {
+        self.expiries.remove(&table);
         self.data.insert(table, BTreeMap::new());
     }
 
@@ -204,9 +397,10 @@ impl Db {
     pub fn batch_get(&self, table: &Table, keys: &[Key]) -> Vec<(Key, Value)> {
         match self.data.get(table) {
             None => Vec::new(),
-            Some(table) => keys
+            Some(data) => keys
                 .iter()
-                .filter_map(|key| table.get(key).map(|value| (key.clone(), value.clone())))
+                .filter(|key| !self.is_expired(table, key, self.now))
+                .filter_map(|key| data.get(key).map(|value| (key.clone(), value.clone())))
                 .collect(),
         }
     }
@@ -219,17 +413,17 @@ impl Db {
     ) -> Vec<(Key, Value)> {
         match self.data.get(table) {
             None => Vec::new(),
-            Some(table) => match limit {
-                None => table
+            Some(data) => {
+                let it = data
                     .range((range.start, range.end))
-                    .map(|(key, value)| (key.clone(), value.clone()))
-                    .collect(),
-                Some(limit) => table
-                    .range((range.start, range.end))
-                    .take(limit)
-                    .map(|(key, value)| (key.clone(), value.clone()))
-                    .collect(),
-            },
+                    .filter(|(key, _)| !self.is_expired(table, key, self.now))
+                    .map(|(key, value)| (key.clone(), value.clone()));
+
+                match limit {
+                    None => it.collect(),
+                    Some(limit) => it.take(limit).collect(),
+                }
+            }
         }
     }
 }
@@ -353,51 +547,76 @@ impl RaftStateMachine<TypeConfig> for Arc<StateMachineStore> {
 
             match entry.payload {
                 EntryPayload::Blank => res.push(Response::Empty),
-                EntryPayload::Normal(ref req) => match req {
-                    Request::Set(api::Set { table, key, value }) => {
-                        sm.db.set(table.clone(), key.clone(), value.clone());
-                        res.push(Response::Set(Ok(())))
-                    }
-                    Request::BatchSet(api::BatchSet { table, values }) => {
-                        sm.db.batch_set(table.clone(), values.as_ref().clone());
-                        res.push(Response::Set(Ok(())))
-                    }
-                    Request::Upsert(api::Upsert {
-                        table,
-                        key,
-                        value,
-                        upsert_fn,
-                    }) => res.push(Response::Upsert(Ok(sm.db.upsert(
-                        table.clone(),
-                        upsert_fn,
-                        key.clone(),
-                        value.clone(),
-                    )))),
-                    Request::BatchUpsert(api::BatchUpsert {
-                        table,
-                        upsert_fn,
-                        values,
-                    }) => res.push(Response::BatchUpsert(Ok(sm.db.batch_upsert(
-                        table.clone(),
-                        upsert_fn,
-                        values.as_ref().clone(),
-                    )))),
-                    Request::CreateTable(api::CreateTable { table }) => {
-                        sm.db.new_table(table.clone());
-                        res.push(Response::CreateTable(Ok(())))
-                    }
-                    Request::DropTable(api::DropTable { table }) => {
-                        sm.db.drop_table(table);
-                        res.push(Response::DropTable(Ok(())))
-                    }
-                    Request::AllTables(api::AllTables) => {
-                        res.push(Response::AllTables(Ok(sm.db.tables())))
-                    }
-                    Request::CloneTable(api::CloneTable { from, to }) => {
-                        sm.db.clone_table(from, to.clone());
-                        res.push(Response::CloneTable(Ok(())))
+                EntryPayload::Normal(LogRequest { now, ref request }) => {
+                    sm.db.advance_clock(now);
+
+                    match request {
+                        Request::Set(api::Set {
+                            table,
+                            key,
+                            value,
+                            ttl,
+                        }) => {
+                            sm.db.set(table.clone(), key.clone(), value.clone(), *ttl);
+                            res.push(Response::Set(Ok(())))
+                        }
+                        Request::BatchSet(api::BatchSet {
+                            table,
+                            values,
+                            ttls,
+                        }) => {
+                            sm.db
+                                .batch_set(table.clone(), values.as_ref().clone(), ttls);
+                            res.push(Response::Set(Ok(())))
+                        }
+                        Request::Upsert(api::Upsert {
+                            table,
+                            key,
+                            value,
+                            upsert_fn,
+                        }) => res.push(Response::Upsert(Ok(sm.db.upsert(
+                            table.clone(),
+                            upsert_fn,
+                            key.clone(),
+                            value.clone(),
+                        )))),
+                        Request::BatchUpsert(api::BatchUpsert {
+                            table,
+                            upsert_fn,
+                            values,
+                        }) => res.push(Response::BatchUpsert(Ok(sm.db.batch_upsert(
+                            table.clone(),
+                            upsert_fn,
+                            values.as_ref().clone(),
+                        )))),
+                        Request::CompareAndSwap(api::CompareAndSwap {
+                            table,
+                            key,
+                            expected,
+                            new,
+                        }) => res.push(Response::CompareAndSwap(Ok(sm.db.compare_and_swap(
+                            table.clone(),
+                            key.clone(),
+                            expected.as_ref(),
+                            new.clone(),
+                        )))),
+                        Request::CreateTable(api::CreateTable { table }) => {
+                            sm.db.new_table(table.clone());
+                            res.push(Response::CreateTable(Ok(())))
+                        }
+                        Request::DropTable(api::DropTable { table }) => {
+                            sm.db.drop_table(table);
+                            res.push(Response::DropTable(Ok(())))
+                        }
+                        Request::AllTables(api::AllTables) => {
+                            res.push(Response::AllTables(Ok(sm.db.tables())))
+                        }
+                        Request::CloneTable(api::CloneTable { from, to }) => {
+                            sm.db.clone_table(from, to.clone());
+                            res.push(Response::CloneTable(Ok(())))
+                        }
                     }
-                },
+                }
                 EntryPayload::Membership(ref mem) => {
                     sm.last_membership = StoredMembership::new(Some(entry.log_id), mem.clone());
                     res.push(Response::Empty)
@@ -496,4 +715,100 @@ mod tests {
         Suite::test_all(MemStoreBuilder {})?;
         Ok(())
     }
+
+    #[test]
+    fn expired_values() {
+        let mut db = Db::default();
+        db.advance_clock(1_000);
+
+        let table = Table::from("test");
+        let expired = Some(Duration::ZERO);
+        let later = Some(Duration::from_secs(3600));
+
+        db.set(table.clone(), 1u64.into(), 1u64.into(), expired);
+        db.set(table.clone(), 2u64.into(), 2u64.into(), later);
+        db.batch_set(
+            table.clone(),
+            vec![(3u64.into(), 3u64.into()), (4u64.into(), 4u64.into())],
+            &[expired, None],
+        );
+
+        assert_eq!(db.get(&table, &1u64.into()), None);
+        assert_eq!(db.get(&table, &2u64.into()), Some(2u64.into()));
+        assert_eq!(db.get(&table, &3u64.into()), None);
+        assert_eq!(db.get(&table, &4u64.into()), Some(4u64.into()));
+        assert_eq!(db.num_keys(&table), 2);
+        assert_eq!(
+            db.range_get(&table, Bound::Unbounded..Bound::Unbounded, None),
+            vec![(2u64.into(), 2u64.into()), (4u64.into(), 4u64.into())]
+        );
+
+        // overwriting a key without a ttl makes it permanent
+        db.set(table.clone(), 2u64.into(), 5u64.into(), None);
+        assert!(db.expiries[&table].by_key.is_empty());
+
+        // the expired values are dropped on the next write
+        assert_eq!(db.data[&table].len(), 2);
+        assert!(db.expiries[&table].by_time.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn batch_set_with_missing_ttls() {
+        let mut db = Db::default();
+
+        db.batch_set(
+            Table::from("test"),
+            vec![(1u64.into(), 1u64.into()), (2u64.into(), 2u64.into())],
+            &[Some(Duration::from_secs(3600))],
+        );
+    }
+
+    #[test]
+    fn expiry_follows_log_clock() {
+        let mut db = Db::default();
+        db.advance_clock(1_000);
+
+        let table = Table::from("test");
+        db.set(
+            table.clone(),
+            1u64.into(),
+            1u64.into(),
+            Some(Duration::from_millis(10)),
+        );
+
+        // the wall clock is far ahead of the log, but only the log decides expiry
+        assert_eq!(db.get(&table, &1u64.into()), Some(1u64.into()));
+
+        // a leader with a clock that is behind doesn't move the clock backwards
+        db.advance_clock(500);
+        db.advance_clock(1_009);
+        assert_eq!(db.get(&table, &1u64.into()), Some(1u64.into()));
+
+        db.advance_clock(1_010);
+        assert_eq!(db.get(&table, &1u64.into()), None);
+        assert_eq!(db.num_keys(&table), 0);
+    }
+
+    #[test]
+    fn compare_and_swap_expired_key() {
+        let mut db = Db::default();
+        db.advance_clock(1_000);
+
+        let table = Table::from("test");
+        db.set(
+            table.clone(),
+            1u64.into(),
+            1u64.into(),
+            Some(Duration::from_millis(10)),
+        );
+
+        assert!(!db.compare_and_swap(table.clone(), 1u64.into(), None, 2u64.into()));
+
+        // the key has expired by the time of the next entry, so it no longer exists
+        db.advance_clock(1_010);
+        assert!(!db.compare_and_swap(table.clone(), 1u64.into(), Some(&1u64.into()), 2u64.into()));
+        assert!(db.compare_and_swap(table.clone(), 1u64.into(), None, 2u64.into()));
+        assert_eq!(db.get(&table, &1u64.into()), Some(2u64.into()));
+    }
 }
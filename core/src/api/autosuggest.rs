@@ -24,7 +24,10 @@ use crate::highlighted::HighlightedFragment;
 
 use super::State;
 
+/// Highlight the part of the suggestion after the longest common prefix with the query.
+/// For suggestions that correct a typo, everything from the typo onwards is highlighted.
 fn highlight(query: &str, suggestion: &str) -> Vec<HighlightedFragment> {
+    let query = query.to_ascii_lowercase();
     let idx = suggestion
         .chars()
         .zip(query.chars())
@@ -147,4 +150,20 @@ mod tests {
             format!("test{HIGHLIGHTED_PREFIX}{HIGHLIGHTED_POSTFIX}")
         );
     }
+
+    #[test]
+    fn typo_highlight() {
+        assert_eq!(
+            highlight_fragments(&highlight("reciep", "recipe")),
+            format!("reci{HIGHLIGHTED_PREFIX}pe{HIGHLIGHTED_POSTFIX}")
+        );
+        assert_eq!(
+            highlight_fragments(&highlight("Tset", "test")),
+            format!("t{HIGHLIGHTED_PREFIX}est{HIGHLIGHTED_POSTFIX}")
+        );
+        assert_eq!(
+            highlight_fragments(&highlight("xest", "test")),
+            format!("{HIGHLIGHTED_PREFIX}test{HIGHLIGHTED_POSTFIX}")
+        );
+    }
 }
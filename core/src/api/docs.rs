@@ -39,6 +39,7 @@ use utoipa_swagger_ui::SwaggerUi;
         components(
             schemas(
                 crate::webpage::region::Region,
+                crate::webpage::GeoPoint,
                 optics::HostRankings,
                 search::ApiSearchQuery,
                 search::ApiSearchResult,
@@ -48,12 +49,15 @@ use utoipa_swagger_ui::SwaggerUi;
                 search::ReturnBody,
                 crate::searcher::WebsitesResult,
                 crate::search_prettifier::HighlightedSpellCorrection,
+                crate::web_spell::TermAlternatives,
+                crate::web_spell::ScoredCorrection,
                 crate::search_prettifier::DisplayedWebpage,
                 crate::search_prettifier::DisplayedEntity,
                 crate::search_prettifier::DisplayedAnswer,
                 crate::search_prettifier::DisplayedSidebar,
                 crate::search_prettifier::Snippet,
                 crate::search_prettifier::RichSnippet,
+                crate::search_prettifier::FaqPair,
                 crate::search_prettifier::StackOverflowAnswer,
                 crate::search_prettifier::StackOverflowQuestion,
                 crate::search_prettifier::CodeOrText,
@@ -79,6 +83,8 @@ use utoipa_swagger_ui::SwaggerUi;
 
                 crate::ranking::SignalEnumDiscriminants,
                 crate::ranking::SignalScore,
+                crate::ranking::SignalContribution,
+                crate::ranking::Explanation,
                 
                 crate::bangs::BangHit,
                 crate::bangs::Bang,
@@ -101,6 +107,10 @@ use utoipa_swagger_ui::SwaggerUi;
                 crate::search_prettifier::Property,
 
                 crate::collector::approx_count::Count,
+                crate::collector::facets::Facets,
+                crate::collector::facets::HostFacet,
+                crate::collector::facets::RegionFacet,
+                crate::collector::facets::YearFacet,
             ),
         ),
         modifiers(&ApiModifier),
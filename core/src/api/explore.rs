@@ -65,7 +65,9 @@ pub async fn explore_export_optic(
             ..Default::default()
         },
         rules: vec![rule],
+        preferred_languages: Vec::new(),
         discard_non_matching: true,
+        secure_only: false,
     };
 
     Ok(optic.to_string())
@@ -30,7 +30,7 @@ use crate::{
     improvement::{store_improvements_loop, ImprovementEvent},
     leaky_queue::LeakyQueue,
     models::dual_encoder::DualEncoder,
-    ranking::models::lambdamart::LambdaMART,
+    ranking::models::lambdamart::ReloadableLambdaMART,
     searcher::{api::ApiSearcher, live::LiveSearcher, DistributedSearcher, SearchClient},
     similar_hosts::SimilarHostsFinder,
     webgraph::remote::{Host, Page, RemoteWebgraph},
@@ -150,12 +150,8 @@ pub async fn router(
     config: &ApiConfig,
     counters: Counters,
     cluster: Arc<Cluster>,
+    lambda_model: Option<ReloadableLambdaMART>,
 ) -> Result<Router> {
-    let lambda_model = match &config.lambda_model_path {
-        Some(path) => Some(LambdaMART::open(path)?),
-        None => None,
-    };
-
     let dual_encoder_model = match &config.dual_encoder_model_path {
         Some(path) => Some(DualEncoder::open(path)?),
         None => None,
@@ -167,10 +163,12 @@ pub async fn router(
         query_store_queue
     });
 
-    let bangs = match &config.bangs_path {
-        Some(bangs_path) => Bangs::from_path(bangs_path),
-        None => Bangs::empty(),
-    };
+    let bangs_paths: Vec<_> = config
+        .bangs_path
+        .iter()
+        .chain(config.user_bangs_paths.iter())
+        .collect();
+    let bangs = Bangs::from_paths(&bangs_paths);
 
     let host_webgraph = RemoteWebgraph::<Host>::new(cluster.clone()).await;
     let page_webgraph = RemoteWebgraph::<Page>::new(cluster.clone()).await;
@@ -190,11 +188,14 @@ pub async fn router(
     }
 
     log::info!("Building autosuggest");
-    let autosuggest = Autosuggest::from_key_phrases(
-        dist_searcher
-            .top_key_phrases(config.top_phrases_for_autosuggest)
-            .await,
-    )?;
+    let autosuggest = match &config.query_log_path {
+        Some(path) => Autosuggest::from_query_log(path)?,
+        None => Autosuggest::from_key_phrases(
+            dist_searcher
+                .top_key_phrases(config.top_phrases_for_autosuggest)
+                .await,
+        )?,
+    };
 
     let state = {
         let mut cross_encoder = None;
@@ -224,7 +225,7 @@ pub async fn router(
         }) {
             log::info!("Warming up searchers");
             searcher
-                .warmup(autosuggest.scores().keys().take(WARMUP_QUERIES).cloned())
+                .warmup(autosuggest.popular_queries(WARMUP_QUERIES).into_iter())
                 .await;
         }
 
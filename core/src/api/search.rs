@@ -21,7 +21,7 @@ use crate::{
 };
 use http::StatusCode;
 use optics::{HostRankings, Optic};
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 use utoipa::ToSchema;
 
 use axum::Json;
@@ -30,7 +30,7 @@ use axum_macros::debug_handler;
 use crate::{
     bangs::BangHit,
     searcher::{self, SearchQuery, SearchResult, WebsitesResult},
-    webpage::region::Region,
+    webpage::{region::Region, GeoPoint},
 };
 
 use super::State;
@@ -62,16 +62,39 @@ pub struct ApiSearchQuery {
     pub query: String,
     pub page: Option<usize>,
     pub num_results: Option<usize>,
+
+    /// The `nextCursor` of the previous page. Fetches the results ranked
+    /// after it instead of using `page`.
+    pub after: Option<String>,
+
     pub selected_region: Option<Region>,
+
+    /// Results close to this point are ranked higher if the pages
+    /// have schema.org coordinates.
+    pub geo_point: Option<GeoPoint>,
+
+    /// Results are reranked by the cosine similarity between this embedding
+    /// and their stored embeddings. It must have the same dimension as the
+    /// embeddings in the index, otherwise the query is rejected.
+    pub query_embedding: Option<Vec<f32>>,
+
     pub optic: Option<String>,
     pub host_rankings: Option<HostRankings>,
     pub safe_search: Option<bool>,
+    pub secure_only: Option<bool>,
 
-    pub signal_coefficients: Option<HashMap<SignalEnumDiscriminants, f64>>,
+    /// Overrides the default coefficients of the named signals (e.g. `{"host_centrality": 5.0}`)
+    /// for this query. Signals that are not mentioned keep their default coefficient
+    /// and unknown signal names are ignored.
+    pub signal_coefficients: Option<HashMap<String, f64>>,
 
     #[serde(default = "defaults::SearchQuery::return_ranking_signals")]
     pub return_ranking_signals: bool,
 
+    /// Attach a breakdown of the score of each result into its ranking signals.
+    #[serde(default = "defaults::SearchQuery::explain")]
+    pub explain: bool,
+
     #[serde(default = "defaults::SearchQuery::flatten_response")]
     pub flatten_response: bool,
 
@@ -81,6 +104,9 @@ pub struct ApiSearchQuery {
     #[serde(default = "defaults::SearchQuery::return_structured_data")]
     pub return_structured_data: bool,
 
+    #[serde(default = "defaults::SearchQuery::return_facets")]
+    pub return_facets: bool,
+
     #[cfg(feature = "return_body")]
     pub return_body: Option<ReturnBody>,
 }
@@ -89,6 +115,34 @@ impl TryFrom<ApiSearchQuery> for SearchQuery {
     type Error = anyhow::Error;
 
     fn try_from(api: ApiSearchQuery) -> Result<Self, Self::Error> {
+        Self::from_api(api, None)
+    }
+}
+
+impl SearchQuery {
+    /// Convert the query from the api. If `embedding_dim` is known, a query
+    /// embedding with a different dimension is rejected instead of failing
+    /// to score every result.
+    pub fn from_api(api: ApiSearchQuery, embedding_dim: Option<usize>) -> anyhow::Result<Self> {
+        if let Some(embedding) = &api.query_embedding {
+            if embedding.is_empty() {
+                anyhow::bail!("the query embedding is empty");
+            }
+
+            if embedding.iter().any(|v| !v.is_finite()) {
+                anyhow::bail!("the query embedding contains non-finite values");
+            }
+
+            if let Some(dim) = embedding_dim {
+                if embedding.len() != dim {
+                    anyhow::bail!(
+                        "the query embedding has dimension {} but the index embeddings have dimension {dim}",
+                        embedding.len()
+                    );
+                }
+            }
+        }
+
         let optic = if let Some(optic) = &api.optic {
             Some(Optic::parse(optic)?)
         } else {
@@ -99,22 +153,37 @@ impl TryFrom<ApiSearchQuery> for SearchQuery {
             api.signal_coefficients.map(|coefficients| {
                 coefficients
                     .into_iter()
-                    .map(|(signal, coefficient)| (signal.into(), coefficient))
+                    .filter_map(|(name, coefficient)| {
+                        match SignalEnumDiscriminants::from_str(&name) {
+                            Ok(signal) => Some((signal.into(), coefficient)),
+                            Err(_) => {
+                                tracing::debug!("ignoring coefficient for unknown signal '{name}'");
+                                None
+                            }
+                        }
+                    })
                     .collect::<EnumMap<SignalEnum, f64>>()
                     .into()
             });
 
+        let after = api.after.as_deref().map(str::parse).transpose()?;
+
         let default = SearchQuery::default();
 
         Ok(SearchQuery {
             query: api.query,
             page: api.page.unwrap_or(default.page),
             num_results: api.num_results.unwrap_or(default.num_results),
+            after,
             selected_region: api.selected_region,
+            geo_point: api.geo_point,
+            query_embedding: api.query_embedding,
             optic,
             host_rankings: api.host_rankings,
             return_ranking_signals: api.return_ranking_signals,
+            explain: api.explain,
             safe_search: api.safe_search.unwrap_or(default.safe_search),
+            secure_only: api.secure_only.unwrap_or(default.secure_only),
             count_results_exact: api.count_results_exact,
             signal_coefficients: signal_coefficients.unwrap_or(default.signal_coefficients),
             #[cfg(feature = "return_body")]
@@ -122,6 +191,8 @@ impl TryFrom<ApiSearchQuery> for SearchQuery {
             #[cfg(not(feature = "return_body"))]
             return_body: None,
             return_structured_data: api.return_structured_data,
+            return_facets: api.return_facets,
+            compound_terms: default.compound_terms,
         })
     }
 }
@@ -159,7 +230,7 @@ pub async fn search(
 ) -> Result<impl IntoResponse, StatusCode> {
     tracing::debug!(?query);
     let flatten_result = query.flatten_response;
-    let query = SearchQuery::try_from(query);
+    let query = SearchQuery::from_api(query, state.searcher.embedding_dim());
 
     if let Err(err) = query {
         tracing::error!("{:?}", err);
@@ -241,24 +312,34 @@ pub async fn sidebar(
 #[derive(
     Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, ToSchema,
 )]
+#[serde(rename_all = "camelCase")]
 pub struct SpellcheckQuery {
     pub query: String,
+    /// The number of alternative corrections to return for each corrected term.
+    #[serde(default)]
+    pub num_alternatives: usize,
 }
 
+const MAX_SPELL_ALTERNATIVES: usize = 10;
+
 #[debug_handler]
 #[utoipa::path(
     post,
     path = "/beta/api/search/spellcheck",
     request_body(content = SpellcheckQuery),
     responses(
-        (status = 200, description = "The corrected string with the changes highlighted using <b>...<\\b> elements. Returns empty response if there is no correction to be made.", body = Option<HighlightedSpellCorrection>),
+        (status = 200, description = "The corrected string with the changes highlighted using <b>...<\\b> elements and the requested number of alternatives for each corrected term, best first. Returns empty response if there is no correction to be made.", body = Option<HighlightedSpellCorrection>),
     )
 )]
 pub async fn spellcheck(
     extract::State(state): extract::State<Arc<State>>,
     extract::Json(req): extract::Json<SpellcheckQuery>,
 ) -> impl IntoResponse {
-    Json(state.searcher.spell_check(&req.query))
+    Json(
+        state
+            .searcher
+            .spell_check(&req.query, req.num_alternatives.min(MAX_SPELL_ALTERNATIVES)),
+    )
 }
 
 #[derive(
@@ -304,3 +385,26 @@ pub async fn entity_image(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_query(body: &str) -> ApiSearchQuery {
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[test]
+    fn query_embedding_dimension() {
+        let body = r#"{"query": "example", "queryEmbedding": [1.0, 0.0, 0.0]}"#;
+
+        assert!(SearchQuery::from_api(api_query(body), Some(3)).is_ok());
+        assert!(SearchQuery::from_api(api_query(body), Some(2)).is_err());
+        assert!(SearchQuery::try_from(api_query(body)).is_ok());
+
+        assert!(
+            SearchQuery::try_from(api_query(r#"{"query": "example", "queryEmbedding": []}"#))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,215 @@
+// Stract is an open source web search engine.
+// Copyright (C) 2023 Stract ApS
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Autosuggest provides the functionality for the little dropdown that appears
+//! when you type something into the search bar and queries are suggested.
+//!
+//! The suggestions either come from the key phrases of the index or from a query log.
+//! Key phrases are stored in a finite state transducer (fst) and suggestions are found
+//! by a prefix search on the fst. A query log is stored in a prefix trie, see [`QueryLogTrie`].
+
+mod query_log;
+
+use std::{collections::HashMap, path::Path};
+
+use fst::{
+    automaton::{Levenshtein, Str},
+    Automaton, IntoStreamer,
+};
+use itertools::Itertools;
+
+use crate::{inverted_index::KeyPhrase, Result};
+
+pub use query_log::QueryLogTrie;
+
+const NUM_SUGGESTIONS: usize = 10;
+
+/// Also look for suggestions within one typo of the query
+/// when there are fewer prefix matches than this.
+const MIN_PREFIX_SUGGESTIONS: usize = 3;
+
+/// Shorter queries are within one typo of too many suggestions to be useful.
+const MIN_FUZZY_QUERY_CHARS: usize = 3;
+
+const FUZZY_EDIT_DISTANCE: u32 = 1;
+
+enum Suggester {
+    KeyPhrases {
+        queries: fst::Set<Vec<u8>>,
+        scores: HashMap<String, f64>,
+    },
+    QueryLog(QueryLogTrie),
+}
+
+pub struct Autosuggest {
+    suggester: Suggester,
+}
+
+impl Autosuggest {
+    /// Suggest the most frequent queries from the query log at `path`.
+    /// See [`QueryLogTrie::parse`] for the format of the log.
+    pub fn from_query_log<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self {
+            suggester: Suggester::QueryLog(QueryLogTrie::from_path(path, NUM_SUGGESTIONS)?),
+        })
+    }
+
+    pub fn from_key_phrases(key_phrases: Vec<KeyPhrase>) -> Result<Self> {
+        let mut queries: Vec<String> = Vec::new();
+        let mut scores: HashMap<String, f64> = HashMap::new();
+
+        for key_phrase in key_phrases {
+            queries.push(key_phrase.text().to_string());
+            scores.insert(key_phrase.text().to_string(), key_phrase.score());
+        }
+
+        queries.sort();
+        queries.dedup();
+
+        let queries = fst::Set::from_iter(queries)?;
+
+        Ok(Self {
+            suggester: Suggester::KeyPhrases { queries, scores },
+        })
+    }
+
+    /// Queries starting with `query`. If there are only a few of those, they are followed
+    /// by queries starting with a string within one typo of `query`.
+    pub fn suggestions(&self, query: &str) -> Result<Vec<String>> {
+        let query = query.to_lowercase();
+
+        let mut suggestions = match &self.suggester {
+            Suggester::KeyPhrases { queries, scores } => {
+                let mut suggestions =
+                    Self::key_phrase_suggestions(queries, scores, Str::new(&query).starts_with())?;
+                suggestions.sort();
+                suggestions
+            }
+            Suggester::QueryLog(trie) => trie
+                .complete(&query)
+                .into_iter()
+                .map(|s| s.to_string())
+                .collect(),
+        };
+
+        if suggestions.len() < MIN_PREFIX_SUGGESTIONS
+            && query.chars().count() >= MIN_FUZZY_QUERY_CHARS
+        {
+            let fuzzy = match &self.suggester {
+                Suggester::KeyPhrases { queries, scores } => {
+                    match Levenshtein::new(&query, FUZZY_EDIT_DISTANCE) {
+                        Ok(automaton) => {
+                            Self::key_phrase_suggestions(queries, scores, automaton.starts_with())?
+                        }
+                        Err(_) => Vec::new(),
+                    }
+                }
+                Suggester::QueryLog(trie) => trie
+                    .fuzzy_complete(&query, FUZZY_EDIT_DISTANCE)
+                    .into_iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            };
+
+            for suggestion in fuzzy {
+                if suggestions.len() >= NUM_SUGGESTIONS {
+                    break;
+                }
+
+                if !suggestions.contains(&suggestion) {
+                    suggestions.push(suggestion);
+                }
+            }
+        }
+
+        Ok(suggestions)
+    }
+
+    /// The highest scoring key phrases matched by `automaton`.
+    fn key_phrase_suggestions<A: Automaton>(
+        queries: &fst::Set<Vec<u8>>,
+        scores: &HashMap<String, f64>,
+        automaton: A,
+    ) -> Result<Vec<String>> {
+        let mut candidates: Vec<(String, f64)> = queries
+            .search(automaton)
+            .into_stream()
+            .into_strs()?
+            .into_iter()
+            .take(64)
+            .map(|s| {
+                let score = scores.get(&s).unwrap_or(&0.0);
+                (s, *score)
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+        Ok(candidates
+            .into_iter()
+            .map(|(s, _)| s)
+            .take(NUM_SUGGESTIONS)
+            .collect())
+    }
+
+    /// Up to `n` of the most popular queries known to the suggester.
+    pub fn popular_queries(&self, n: usize) -> Vec<String> {
+        match &self.suggester {
+            Suggester::KeyPhrases { scores, .. } => scores
+                .iter()
+                .sorted_by(|(_, a), (_, b)| b.total_cmp(a))
+                .take(n)
+                .map(|(query, _)| query.clone())
+                .collect(),
+            Suggester::QueryLog(trie) => trie
+                .complete("")
+                .into_iter()
+                .take(n)
+                .map(|s| s.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_fallback() {
+        let autosuggest = Autosuggest::from_key_phrases(vec![
+            KeyPhrase::new("recipe".to_string(), 2.0),
+            KeyPhrase::new("chocolate cake".to_string(), 1.0),
+        ])
+        .unwrap();
+
+        assert_eq!(autosuggest.suggestions("reciep").unwrap(), vec!["recipe"]);
+        assert_eq!(autosuggest.suggestions("reci").unwrap(), vec!["recipe"]);
+        assert!(autosuggest.suggestions("xyz").unwrap().is_empty());
+    }
+
+    #[test]
+    fn query_log_non_ascii_case() {
+        let autosuggest = Autosuggest {
+            suggester: Suggester::QueryLog(QueryLogTrie::new(
+                vec![("Émile Zola".to_string(), 1)],
+                10,
+            )),
+        };
+
+        assert_eq!(autosuggest.suggestions("ÉMI").unwrap(), vec!["émile zola"]);
+    }
+}
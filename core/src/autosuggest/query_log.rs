@@ -0,0 +1,243 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+//! Suggestions from a log of past queries. The log is a file where each line
+//! is a query and the number of times it was searched, separated by a tab.
+
+use std::{collections::BTreeMap, collections::HashMap, path::Path};
+
+use anyhow::anyhow;
+use fst::{automaton::Levenshtein, Automaton, IntoStreamer, Streamer};
+
+use crate::Result;
+
+/// The number of fuzzy matches that are ranked by frequency. The fst is walked
+/// in lexicographic order, so the search stops after this many matches.
+const MAX_FUZZY_CANDIDATES: usize = 64;
+
+#[derive(Debug, Default)]
+struct Node {
+    children: BTreeMap<char, usize>,
+    /// The most frequent queries starting with the prefix of the node,
+    /// ordered by descending frequency.
+    top: Vec<usize>,
+}
+
+/// A prefix trie over the queries of a query log. Each node keeps the
+/// `top_k` most frequent queries below it, so a lookup only has to walk the prefix.
+/// The queries are also stored in an fst for fuzzy lookups.
+pub struct QueryLogTrie {
+    nodes: Vec<Node>,
+    queries: Vec<(String, u64)>,
+    dictionary: fst::Map<Vec<u8>>,
+    top_k: usize,
+}
+
+impl QueryLogTrie {
+    pub fn new(queries: impl IntoIterator<Item = (String, u64)>, top_k: usize) -> Self {
+        let mut counts: HashMap<String, u64> = HashMap::new();
+        for (query, count) in queries {
+            *counts.entry(query.trim().to_lowercase()).or_default() += count;
+        }
+
+        let mut queries: Vec<_> = counts
+            .into_iter()
+            .filter(|(query, _)| !query.is_empty())
+            .collect();
+
+        // inserting in order of frequency means the top lists of the nodes
+        // are already sorted and can simply be truncated
+        queries.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+
+        let mut trie = Self {
+            nodes: vec![Node::default()],
+            queries: Vec::new(),
+            dictionary: fst::Map::default(),
+            top_k,
+        };
+
+        for (query, count) in queries {
+            trie.insert(query, count);
+        }
+
+        let mut dictionary: Vec<_> = trie
+            .queries
+            .iter()
+            .enumerate()
+            .map(|(idx, (query, _))| (query.as_str(), idx as u64))
+            .collect();
+        dictionary.sort();
+
+        trie.dictionary = fst::Map::from_iter(dictionary).expect("queries are sorted and unique");
+
+        trie
+    }
+
+    /// Parse a query log with lines of `query\tcount`. Blank lines are skipped.
+    pub fn parse(log: &str, top_k: usize) -> Result<Self> {
+        let mut queries = Vec::new();
+
+        for (i, line) in log.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (query, count) = line
+                .rsplit_once('\t')
+                .ok_or_else(|| anyhow!("line {} of the query log has no count", i + 1))?;
+
+            let count = count
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("line {} of the query log has an invalid count", i + 1))?;
+
+            queries.push((query.to_string(), count));
+        }
+
+        Ok(Self::new(queries, top_k))
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P, top_k: usize) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?, top_k)
+    }
+
+    fn insert(&mut self, query: String, count: u64) {
+        let idx = self.queries.len();
+        let mut node = 0;
+
+        self.push_top(node, idx);
+        for c in query.chars() {
+            node = match self.nodes[node].children.get(&c) {
+                Some(child) => *child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[node].children.insert(c, child);
+                    child
+                }
+            };
+
+            self.push_top(node, idx);
+        }
+
+        self.queries.push((query, count));
+    }
+
+    fn push_top(&mut self, node: usize, query: usize) {
+        let top = &mut self.nodes[node].top;
+
+        if top.len() < self.top_k {
+            top.push(query);
+        }
+    }
+
+    /// The most frequent queries starting with `prefix`, ordered by descending frequency.
+    /// An empty prefix gives the most frequent queries overall.
+    pub fn complete(&self, prefix: &str) -> Vec<&str> {
+        let mut node = 0;
+
+        for c in prefix.chars() {
+            match self.nodes[node].children.get(&c) {
+                Some(child) => node = *child,
+                None => return Vec::new(),
+            }
+        }
+
+        self.nodes[node]
+            .top
+            .iter()
+            .map(|idx| self.queries[*idx].0.as_str())
+            .collect()
+    }
+
+    /// The most frequent queries that start with a string within `max_edit_distance`
+    /// of `prefix`, ordered by descending frequency. Only the first
+    /// [`MAX_FUZZY_CANDIDATES`] matches in lexicographic order are considered.
+    pub fn fuzzy_complete(&self, prefix: &str, max_edit_distance: u32) -> Vec<&str> {
+        let Ok(automaton) = Levenshtein::new(prefix, max_edit_distance) else {
+            return Vec::new();
+        };
+
+        let mut matches = Vec::new();
+        let mut stream = self
+            .dictionary
+            .search(automaton.starts_with())
+            .into_stream();
+        while let Some((_, idx)) = stream.next() {
+            matches.push(idx as usize);
+
+            if matches.len() >= MAX_FUZZY_CANDIDATES {
+                break;
+            }
+        }
+
+        // queries were inserted in order of frequency
+        matches.sort();
+
+        matches
+            .into_iter()
+            .take(self.top_k)
+            .map(|idx| self.queries[idx].0.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "rust\t50
+rust book\t30
+rust analyzer\t80
+ruby\t10
+python\t100
+
+rust book\t5
+";
+
+    #[test]
+    fn prefix_completion_order() {
+        let trie = QueryLogTrie::parse(LOG, 10).unwrap();
+
+        assert_eq!(
+            trie.complete("rus"),
+            vec!["rust analyzer", "rust", "rust book"]
+        );
+        assert_eq!(
+            trie.complete("ru"),
+            vec!["rust analyzer", "rust", "rust book", "ruby"]
+        );
+        assert_eq!(trie.complete("rust b"), vec!["rust book"]);
+        assert!(trie.complete("go").is_empty());
+
+        let trie = QueryLogTrie::parse(LOG, 2).unwrap();
+        assert_eq!(trie.complete("ru"), vec!["rust analyzer", "rust"]);
+    }
+
+    #[test]
+    fn empty_prefix() {
+        let trie = QueryLogTrie::parse(LOG, 3).unwrap();
+
+        assert_eq!(trie.complete(""), vec!["python", "rust analyzer", "rust"]);
+    }
+
+    #[test]
+    fn fuzzy_prefix() {
+        let trie =
+            QueryLogTrie::parse("recipe\t10\nrecipes for dinner\t20\nreceipt\t5\n", 10).unwrap();
+
+        assert!(trie.complete("reciep").is_empty());
+        assert_eq!(
+            trie.fuzzy_complete("reciep", 1),
+            vec!["recipes for dinner", "recipe"]
+        );
+    }
+
+    #[test]
+    fn invalid_log() {
+        assert!(QueryLogTrie::parse("rust", 10).is_err());
+        assert!(QueryLogTrie::parse("rust\tmany", 10).is_err());
+    }
+}
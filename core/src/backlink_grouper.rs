@@ -108,60 +108,70 @@ mod tests {
             to: NodeDatum::new(1u64, 0u64),
             label: String::new(),
             rel: Default::default(),
+            weight: 1.0,
         });
         grouper.add(Edge {
             from: NodeDatum::new(0u64, 1u64),
             to: NodeDatum::new(1u64, 0u64),
             label: String::new(),
             rel: Default::default(),
+            weight: 1.0,
         });
         grouper.add(Edge {
             from: NodeDatum::new(0u64, 2u64),
             to: NodeDatum::new(1u64, 0u64),
             label: String::new(),
             rel: Default::default(),
+            weight: 1.0,
         });
         grouper.add(Edge {
             from: NodeDatum::new(0u64, 3u64),
             to: NodeDatum::new(1u64, 0u64),
             label: String::new(),
             rel: Default::default(),
+            weight: 1.0,
         });
         grouper.add(Edge {
             from: NodeDatum::new(0u64, 4u64),
             to: NodeDatum::new(1u64, 0u64),
             label: String::new(),
             rel: Default::default(),
+            weight: 1.0,
         });
         grouper.add(Edge {
             from: NodeDatum::new(0u64, 5u64),
             to: NodeDatum::new(1u64, 0u64),
             label: String::new(),
             rel: Default::default(),
+            weight: 1.0,
         });
         grouper.add(Edge {
             from: NodeDatum::new(0u64, 6u64),
             to: NodeDatum::new(1u64, 0u64),
             label: String::new(),
             rel: Default::default(),
+            weight: 1.0,
         });
         grouper.add(Edge {
             from: NodeDatum::new(0u64, 7u64),
             to: NodeDatum::new(1u64, 0u64),
             label: String::new(),
             rel: Default::default(),
+            weight: 1.0,
         });
         grouper.add(Edge {
             from: NodeDatum::new(0u64, 8u64),
             to: NodeDatum::new(1u64, 0u64),
             label: String::new(),
             rel: Default::default(),
+            weight: 1.0,
         });
         grouper.add(Edge {
             from: NodeDatum::new(0u64, 9u64),
             to: NodeDatum::new(1u64, 0u64),
             label: String::new(),
             rel: Default::default(),
+            weight: 1.0,
         });
 
         {
@@ -23,6 +23,7 @@ use std::{
 
 use itertools::intersperse;
 
+use unicode_normalization::UnicodeNormalization;
 use url::Url;
 use utoipa::ToSchema;
 
@@ -117,6 +118,55 @@ pub struct BangHit {
     pub redirect_to: UrlWrapper,
 }
 
+/// Replace the query placeholders in a bang url:
+/// `{{{s}}}` is the url-encoded query, `{{{s_raw}}}` is the query as typed
+/// and `{{{s_plus}}}` is the url-encoded query with spaces as `+`.
+///
+/// The template is scanned once, so placeholders that appear in the query
+/// itself are not substituted.
+fn substitute_query(template: &str, query: &str) -> String {
+    let encoded = crate::urlencode(query);
+    let plus = encoded.replace('+', "%2B").replace("%20", "+");
+
+    let placeholders = [
+        ("{{{s}}}", encoded.as_str()),
+        ("{{{s_raw}}}", query),
+        ("{{{s_plus}}}", plus.as_str()),
+    ];
+
+    let mut res = String::with_capacity(template.len() + query.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{{") {
+        res.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        match placeholders
+            .iter()
+            .find(|(placeholder, _)| rest.starts_with(placeholder))
+        {
+            Some((placeholder, value)) => {
+                res.push_str(value);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                res.push_str("{{{");
+                rest = &rest[3..];
+            }
+        }
+    }
+
+    res.push_str(rest);
+
+    res
+}
+
+/// Bang tags are matched case-insensitively and fullwidth characters
+/// match their ascii counterparts, so `!TY` and `！ｔｙ` both resolve to `ty`.
+fn normalize_tag(tag: &str) -> String {
+    tag.nfkc().collect::<String>().to_lowercase()
+}
+
 pub struct Bangs {
     bangs: HashMap<String, Bang>,
 }
@@ -134,17 +184,57 @@ impl Bangs {
         Self::from_json(json.as_str())
     }
 
+    /// Load the bangs from multiple files. If several files define a bang with
+    /// the same tag, the bang from the last file wins.
+    pub fn from_paths<P: AsRef<Path>>(paths: &[P]) -> Self {
+        let mut bangs = Self::empty();
+
+        for path in paths {
+            let path = path.as_ref();
+            let json = fs::read_to_string(path).unwrap();
+            let overrides: Vec<Bang> = serde_json::from_str(&json).unwrap();
+
+            bangs.merge(overrides, &path.display().to_string());
+        }
+
+        bangs
+    }
+
     pub fn from_json(json: &str) -> Self {
         let all_bangs: Vec<Bang> = serde_json::from_str(json).unwrap();
 
         Self {
             bangs: all_bangs
                 .into_iter()
-                .map(|bang| (bang.tag.clone(), bang))
+                .map(|mut bang| {
+                    bang.tag = normalize_tag(&bang.tag);
+                    (bang.tag.clone(), bang)
+                })
                 .collect(),
         }
     }
 
+    /// Insert the bangs, replacing any existing bang with the same tag.
+    pub fn with_overrides(&mut self, overrides: Vec<Bang>) {
+        self.merge(overrides, "overrides");
+    }
+
+    fn merge(&mut self, bangs: Vec<Bang>, source: &str) {
+        for mut bang in bangs {
+            bang.tag = normalize_tag(&bang.tag);
+
+            if self.bangs.contains_key(&bang.tag) {
+                tracing::debug!(
+                    "bang !{} from {} overrides earlier definition",
+                    bang.tag,
+                    source
+                );
+            }
+
+            self.bangs.insert(bang.tag.clone(), bang);
+        }
+    }
+
     pub fn get(&self, terms: &[Term]) -> Option<BangHit> {
         for possible_bang in terms.iter().filter_map(|term| {
             if let Term::PossibleBang { prefix: _, bang } = term {
@@ -153,7 +243,7 @@ impl Bangs {
                 None
             }
         }) {
-            if let Some(bang) = self.bangs.get(possible_bang) {
+            if let Some(bang) = self.bangs.get(&normalize_tag(possible_bang)) {
                 let query = intersperse(
                     terms
                         .iter()
@@ -169,8 +259,7 @@ impl Bangs {
                 )
                 .collect::<String>();
 
-                let query = crate::urlencode(query.as_str());
-                let url = bang.url.replace("{{{s}}}", query.as_str());
+                let url = substitute_query(&bang.url, &query);
 
                 return Url::parse(url.as_str())
                     .or_else(|_| Url::parse(&format!("https://{}", url)))
@@ -228,4 +317,126 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn case_insensitive_tags() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "t": "TY",
+            "u": "https://www.youtube.com/results?search_query={{{s}}}"
+        }]"#,
+        );
+
+        let hit = bangs.get(&parse("!ty bangs").unwrap());
+        assert!(hit.is_some());
+        assert_eq!(hit.as_ref().unwrap().bang.tag, "ty");
+
+        assert_eq!(bangs.get(&parse("!TY bangs").unwrap()), hit);
+        assert_eq!(bangs.get(&parse("！TY bangs").unwrap()), hit);
+        assert_eq!(bangs.get(&parse("！ｔｙ bangs").unwrap()), hit);
+        assert_eq!(bangs.get(&parse("bangs !Ty").unwrap()), hit);
+    }
+
+    #[test]
+    fn placeholders() {
+        let bangs = Bangs::from_json(
+            r#"[{
+            "c": "Tech",
+            "d": "search.example.com",
+            "s": "Example",
+            "t": "ex",
+            "u": "https://search.example.com/{{{s_raw}}}?q={{{s}}}&alt={{{s_plus}}}"
+        }]"#,
+        );
+
+        assert_eq!(
+            bangs.get(&parse("!ex rust bangs").unwrap()),
+            Some(BangHit {
+                bang: Bang {
+                    category: Some("Tech".to_string()),
+                    sub_category: None,
+                    domain: Some("search.example.com".to_string()),
+                    ranking: None,
+                    site: Some("Example".to_string()),
+                    tag: "ex".to_string(),
+                    url: "https://search.example.com/{{{s_raw}}}?q={{{s}}}&alt={{{s_plus}}}"
+                        .to_string()
+                },
+                redirect_to: Url::parse(
+                    "https://search.example.com/rust%20bangs?q=rust%20bangs&alt=rust+bangs"
+                )
+                .unwrap()
+                .into()
+            })
+        );
+
+        assert_eq!(
+            substitute_query("https://a.com/?q={{{s_plus}}}", "c++ bangs"),
+            "https://a.com/?q=c%2B%2B+bangs"
+        );
+        assert_eq!(
+            substitute_query("https://a.com/?q={{{s}}}&{{{unknown}}}", "{{{s_raw}}} a"),
+            "https://a.com/?q={{{s_raw}}}%20a&{{{unknown}}}"
+        );
+    }
+
+    #[test]
+    fn overrides() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let default_path = dir.as_ref().join("bangs.json");
+        let user_path = dir.as_ref().join("user_bangs.json");
+
+        fs::write(
+            &default_path,
+            r#"[
+                {"t": "ty", "u": "https://www.youtube.com/results?search_query={{{s}}}"},
+                {"t": "w", "u": "https://en.wikipedia.org/w/index.php?search={{{s}}}"}
+            ]"#,
+        )
+        .unwrap();
+        fs::write(
+            &user_path,
+            r#"[
+                {"t": "ty", "u": "https://invidious.example.com/search?q={{{s}}}"},
+                {"t": "gh", "u": "https://github.com/search?q={{{s}}}"}
+            ]"#,
+        )
+        .unwrap();
+
+        let redirect = |bangs: &Bangs, query: &str| {
+            bangs
+                .get(&parse(query).unwrap())
+                .map(|hit| hit.redirect_to.to_string())
+        };
+
+        let mut bangs = Bangs::from_paths(&[&default_path, &user_path]);
+
+        assert_eq!(
+            redirect(&bangs, "!ty bangs").as_deref(),
+            Some("https://invidious.example.com/search?q=bangs")
+        );
+        assert_eq!(
+            redirect(&bangs, "!w bangs").as_deref(),
+            Some("https://en.wikipedia.org/w/index.php?search=bangs")
+        );
+        assert_eq!(
+            redirect(&bangs, "!gh bangs").as_deref(),
+            Some("https://github.com/search?q=bangs")
+        );
+
+        bangs.with_overrides(vec![Bang {
+            category: None,
+            sub_category: None,
+            domain: None,
+            ranking: None,
+            site: None,
+            tag: "w".to_string(),
+            url: "https://da.wikipedia.org/w/index.php?search={{{s}}}".to_string(),
+        }]);
+
+        assert_eq!(
+            redirect(&bangs, "!w bangs").as_deref(),
+            Some("https://da.wikipedia.org/w/index.php?search=bangs")
+        );
+    }
 }
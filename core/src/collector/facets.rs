@@ -0,0 +1,670 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+//! Facet counts (matching documents grouped by host, region and year of publication)
+//! for the current query.
+//!
+//! The number of distinct hosts tracked is capped at [`MAX_TRACKED_HOSTS`] to keep
+//! memory bounded. Documents from hosts seen after the cap has been reached are
+//! only accounted for in [`Facets::untracked_hosts`].
+
+use std::{collections::HashMap, sync::Arc};
+
+use bloom::combine_u64s;
+use chrono::{DateTime, Datelike};
+use tantivy::{
+    collector::{Collector, SegmentCollector},
+    DocId, SegmentOrdinal,
+};
+
+use crate::{
+    inverted_index::DocAddress,
+    numericalfield_reader,
+    prehashed::Prehashed,
+    schema::{numerical_field, NumericalFieldEnum},
+    webpage::region::Region,
+};
+
+/// Maximum number of distinct hosts tracked per collector.
+pub const MAX_TRACKED_HOSTS: usize = 10_000;
+
+/// Number of hosts returned in the facets.
+pub const NUM_TOP_HOSTS: usize = 10;
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct HostFacet {
+    pub host: String,
+    pub count: u64,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionFacet {
+    pub region: Region,
+    pub count: u64,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct YearFacet {
+    pub year: i32,
+    pub count: u64,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct Facets {
+    /// The hosts with the most matching documents, sorted by descending count.
+    pub hosts: Vec<HostFacet>,
+    /// Matching documents per region, sorted by descending count.
+    pub regions: Vec<RegionFacet>,
+    /// Matching documents per year of publication, newest year first.
+    /// Documents without a known publication date are not counted.
+    pub years: Vec<YearFacet>,
+    /// Number of matching documents from hosts that were not tracked
+    /// because the cardinality cap was reached.
+    pub untracked_hosts: u64,
+}
+
+impl Facets {
+    /// Merge facets from different shards. As each shard only returns its top hosts,
+    /// the merged host counts are a lower bound of the true counts.
+    pub fn merge(self, other: Self) -> Self {
+        let mut hosts: HashMap<String, u64> = HashMap::new();
+        for facet in self.hosts.into_iter().chain(other.hosts) {
+            *hosts.entry(facet.host).or_default() += facet.count;
+        }
+
+        let mut regions: HashMap<Region, u64> = HashMap::new();
+        for facet in self.regions.into_iter().chain(other.regions) {
+            *regions.entry(facet.region).or_default() += facet.count;
+        }
+
+        let mut years: HashMap<i32, u64> = HashMap::new();
+        for facet in self.years.into_iter().chain(other.years) {
+            *years.entry(facet.year).or_default() += facet.count;
+        }
+
+        let mut hosts: Vec<_> = hosts
+            .into_iter()
+            .map(|(host, count)| HostFacet { host, count })
+            .collect();
+        hosts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.host.cmp(&b.host)));
+        hosts.truncate(NUM_TOP_HOSTS);
+
+        Self {
+            hosts,
+            regions: sorted_regions(regions),
+            years: sorted_years(years),
+            untracked_hosts: self.untracked_hosts + other.untracked_hosts,
+        }
+    }
+}
+
+fn sorted_regions(regions: HashMap<Region, u64>) -> Vec<RegionFacet> {
+    let mut regions: Vec<_> = regions
+        .into_iter()
+        .map(|(region, count)| RegionFacet { region, count })
+        .collect();
+    regions.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.region.id().cmp(&b.region.id()))
+    });
+
+    regions
+}
+
+fn sorted_years(years: HashMap<i32, u64>) -> Vec<YearFacet> {
+    let mut years: Vec<_> = years
+        .into_iter()
+        .map(|(year, count)| YearFacet { year, count })
+        .collect();
+    years.sort_by(|a, b| b.year.cmp(&a.year));
+
+    years
+}
+
+#[derive(Debug, Clone, Copy)]
+struct HostCount {
+    count: u64,
+    sample: DocAddress,
+}
+
+/// Raw facet counts as collected from the index. Hosts are identified by
+/// their site hash together with the address of a sample document, which
+/// can be used to resolve the host name.
+#[derive(Debug, Default)]
+pub struct FacetCounts {
+    hosts: HashMap<Prehashed, HostCount>,
+    regions: HashMap<u64, u64>,
+    years: HashMap<i32, u64>,
+    untracked_hosts: u64,
+    max_tracked_hosts: usize,
+}
+
+impl FacetCounts {
+    fn new(max_tracked_hosts: usize) -> Self {
+        Self {
+            max_tracked_hosts,
+            ..Default::default()
+        }
+    }
+
+    fn insert_host(&mut self, site: Prehashed, count: u64, sample: DocAddress) {
+        if let Some(host) = self.hosts.get_mut(&site) {
+            host.count += count;
+        } else if self.hosts.len() < self.max_tracked_hosts {
+            self.hosts.insert(site, HostCount { count, sample });
+        } else {
+            self.untracked_hosts += count;
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        for (site, host) in other.hosts {
+            self.insert_host(site, host.count, host.sample);
+        }
+
+        for (region, count) in other.regions {
+            *self.regions.entry(region).or_default() += count;
+        }
+
+        for (year, count) in other.years {
+            *self.years.entry(year).or_default() += count;
+        }
+
+        self.untracked_hosts += other.untracked_hosts;
+    }
+
+    /// Number of distinct hosts being tracked.
+    pub fn num_tracked_hosts(&self) -> usize {
+        self.hosts.len()
+    }
+
+    /// Convert the counts into facets with the `top_n` hosts.
+    /// `host_name` resolves the host name from the address of a sample document.
+    pub fn into_facets<F>(self, top_n: usize, host_name: F) -> Facets
+    where
+        F: Fn(DocAddress) -> Option<String>,
+    {
+        let mut hosts: Vec<_> = self.hosts.into_values().collect();
+        hosts.sort_by(|a, b| b.count.cmp(&a.count));
+
+        let hosts = hosts
+            .into_iter()
+            .filter_map(|host| {
+                host_name(host.sample).map(|name| HostFacet {
+                    host: name,
+                    count: host.count,
+                })
+            })
+            .take(top_n)
+            .collect();
+
+        let regions = self
+            .regions
+            .into_iter()
+            .map(|(id, count)| (Region::from_id(id), count))
+            .fold(HashMap::new(), |mut acc, (region, count)| {
+                *acc.entry(region).or_default() += count;
+                acc
+            });
+
+        Facets {
+            hosts,
+            regions: sorted_regions(regions),
+            years: sorted_years(self.years),
+            untracked_hosts: self.untracked_hosts,
+        }
+    }
+}
+
+pub struct FacetCollector {
+    max_tracked_hosts: usize,
+    columnfield_reader: numericalfield_reader::NumericalFieldReader,
+}
+
+impl FacetCollector {
+    pub fn new(columnfield_reader: numericalfield_reader::NumericalFieldReader) -> Self {
+        Self {
+            max_tracked_hosts: MAX_TRACKED_HOSTS,
+            columnfield_reader,
+        }
+    }
+
+    pub fn with_max_tracked_hosts(mut self, max_tracked_hosts: usize) -> Self {
+        self.max_tracked_hosts = max_tracked_hosts;
+        self
+    }
+}
+
+impl Collector for FacetCollector {
+    type Fruit = FacetCounts;
+    type Child = SegmentFacetCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &tantivy::SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        Ok(SegmentFacetCollector {
+            columnfield_segment_reader: self.columnfield_reader.get_segment(&segment.segment_id()),
+            segment_ord: segment_local_id,
+            counts: FacetCounts::new(self.max_tracked_hosts),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> tantivy::Result<Self::Fruit> {
+        let mut res = FacetCounts::new(self.max_tracked_hosts);
+
+        for fruit in segment_fruits {
+            res.merge(fruit);
+        }
+
+        Ok(res)
+    }
+}
+
+pub struct SegmentFacetCollector {
+    columnfield_segment_reader: Arc<numericalfield_reader::SegmentReader>,
+    segment_ord: SegmentOrdinal,
+    counts: FacetCounts,
+}
+
+impl SegmentFacetCollector {
+    fn get_u64(&self, doc: DocId, field: NumericalFieldEnum) -> Option<u64> {
+        self.columnfield_segment_reader
+            .get_field_reader(doc)
+            .get(field)
+            .and_then(|val| val.as_u64())
+    }
+}
+
+impl SegmentCollector for SegmentFacetCollector {
+    type Fruit = FacetCounts;
+
+    fn collect(&mut self, doc: DocId, _: tantivy::Score) {
+        if let (Some(hash1), Some(hash2)) = (
+            self.get_u64(doc, numerical_field::SiteHash1.into()),
+            self.get_u64(doc, numerical_field::SiteHash2.into()),
+        ) {
+            let site: Prehashed = combine_u64s([hash1, hash2]).into();
+            self.counts.insert_host(
+                site,
+                1,
+                DocAddress {
+                    segment: self.segment_ord,
+                    doc_id: doc,
+                },
+            );
+        }
+
+        let region = self
+            .get_u64(doc, numerical_field::Region.into())
+            .unwrap_or_else(|| Region::All.id());
+        *self.counts.regions.entry(region).or_default() += 1;
+
+        // a published date of 0 means that the date is unknown
+        if let Some(year) = self
+            .get_u64(doc, numerical_field::PublishedDate.into())
+            .filter(|timestamp| *timestamp > 0)
+            .and_then(|timestamp| DateTime::from_timestamp(timestamp as i64, 0))
+            .map(|date| date.year())
+        {
+            *self.counts.years.entry(year).or_default() += 1;
+        }
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        self.counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        index::Index,
+        searcher::{LocalSearcher, SearchQuery},
+        webpage::{Html, Webpage},
+    };
+
+    use super::*;
+
+    fn index_with_distribution(distribution: &[(&str, usize)]) -> Index {
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (host, count) in distribution {
+            for i in 0..*count {
+                index
+                    .insert(&Webpage {
+                        html: Html::parse(
+                            &format!(
+                                r#"
+                            <html>
+                                <head>
+                                    <title>Facet test page</title>
+                                </head>
+                                <body>
+                                    example {}
+                                </body>
+                            </html>
+                            "#,
+                                crate::rand_words(100)
+                            ),
+                            &format!("https://{host}/page{i}"),
+                        )
+                        .unwrap(),
+                        ..Default::default()
+                    })
+                    .expect("failed to insert webpage");
+            }
+        }
+
+        index.commit().unwrap();
+        index
+    }
+
+    #[test]
+    fn host_facets_match_distribution() {
+        let distribution = [("a.com", 7), ("b.com", 4), ("c.com", 2), ("d.com", 1)];
+        let index = index_with_distribution(&distribution);
+        let searcher = LocalSearcher::from(index);
+
+        let res = searcher
+            .search(&SearchQuery {
+                query: "example".to_string(),
+                return_facets: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let facets = res.facets.expect("facets were requested");
+
+        assert_eq!(
+            facets.hosts,
+            distribution
+                .iter()
+                .map(|(host, count)| HostFacet {
+                    host: host.to_string(),
+                    count: *count as u64,
+                })
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(facets.untracked_hosts, 0);
+        assert_eq!(
+            facets.regions.iter().map(|r| r.count).sum::<u64>(),
+            distribution.iter().map(|(_, c)| *c as u64).sum::<u64>()
+        );
+
+        let res = searcher
+            .search(&SearchQuery {
+                query: "example".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert!(res.facets.is_none());
+    }
+
+    #[test]
+    fn region_and_year_facets() {
+        let pages = [
+            (Region::US, Some("2021-03-01"), 3),
+            (Region::Denmark, Some("2023-06-15"), 2),
+            (Region::Germany, None, 4),
+        ];
+
+        let mut index = Index::temporary().expect("Unable to open index");
+
+        for (region, published, count) in pages {
+            for i in 0..count {
+                let meta = published
+                    .map(|date| {
+                        format!(r#"<meta property="article:published_time" content="{date}">"#)
+                    })
+                    .unwrap_or_default();
+
+                index
+                    .insert(&Webpage {
+                        html: Html::parse(
+                            &format!(
+                                r#"
+                            <html>
+                                <head>
+                                    <title>Facet test page</title>
+                                    {meta}
+                                </head>
+                                <body>
+                                    example {}
+                                </body>
+                            </html>
+                            "#,
+                                crate::rand_words(100)
+                            ),
+                            &format!("https://site{}.com/page{i}", region.id()),
+                        )
+                        .unwrap(),
+                        region_override: Some(region),
+                        ..Default::default()
+                    })
+                    .expect("failed to insert webpage");
+            }
+        }
+
+        index.commit().unwrap();
+        let searcher = LocalSearcher::from(index);
+
+        let facets = searcher
+            .search(&SearchQuery {
+                query: "example".to_string(),
+                return_facets: true,
+                ..Default::default()
+            })
+            .unwrap()
+            .facets
+            .expect("facets were requested");
+
+        assert_eq!(
+            facets.regions,
+            vec![
+                RegionFacet {
+                    region: Region::Germany,
+                    count: 4,
+                },
+                RegionFacet {
+                    region: Region::US,
+                    count: 3,
+                },
+                RegionFacet {
+                    region: Region::Denmark,
+                    count: 2,
+                },
+            ]
+        );
+        assert_eq!(
+            facets.years,
+            vec![
+                YearFacet {
+                    year: 2023,
+                    count: 2,
+                },
+                YearFacet {
+                    year: 2021,
+                    count: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn capped_cardinality() {
+        let mut counts = FacetCounts::new(2);
+        let address = DocAddress {
+            segment: 0,
+            doc_id: 0,
+        };
+
+        counts.insert_host(Prehashed(1), 3, address);
+        counts.insert_host(Prehashed(2), 2, address);
+        counts.insert_host(Prehashed(3), 5, address);
+        counts.insert_host(Prehashed(1), 1, address);
+
+        assert_eq!(counts.num_tracked_hosts(), 2);
+        assert_eq!(counts.untracked_hosts, 5);
+
+        let facets = counts.into_facets(NUM_TOP_HOSTS, |_| Some("host".to_string()));
+        assert_eq!(
+            facets.hosts.iter().map(|h| h.count).collect::<Vec<_>>(),
+            vec![4, 2]
+        );
+        assert_eq!(facets.untracked_hosts, 5);
+    }
+
+    #[test]
+    fn merge_facets() {
+        let a = Facets {
+            hosts: vec![
+                HostFacet {
+                    host: "a.com".to_string(),
+                    count: 3,
+                },
+                HostFacet {
+                    host: "b.com".to_string(),
+                    count: 1,
+                },
+            ],
+            regions: vec![RegionFacet {
+                region: Region::All,
+                count: 4,
+            }],
+            years: vec![YearFacet {
+                year: 2023,
+                count: 4,
+            }],
+            untracked_hosts: 0,
+        };
+
+        let b = Facets {
+            hosts: vec![HostFacet {
+                host: "b.com".to_string(),
+                count: 5,
+            }],
+            regions: vec![
+                RegionFacet {
+                    region: Region::All,
+                    count: 2,
+                },
+                RegionFacet {
+                    region: Region::US,
+                    count: 3,
+                },
+            ],
+            years: vec![
+                YearFacet {
+                    year: 2023,
+                    count: 1,
+                },
+                YearFacet {
+                    year: 2024,
+                    count: 2,
+                },
+            ],
+            untracked_hosts: 1,
+        };
+
+        let merged = a.merge(b);
+
+        assert_eq!(
+            merged.hosts,
+            vec![
+                HostFacet {
+                    host: "b.com".to_string(),
+                    count: 6,
+                },
+                HostFacet {
+                    host: "a.com".to_string(),
+                    count: 3,
+                },
+            ]
+        );
+        assert_eq!(
+            merged.regions,
+            vec![
+                RegionFacet {
+                    region: Region::All,
+                    count: 6,
+                },
+                RegionFacet {
+                    region: Region::US,
+                    count: 3,
+                },
+            ]
+        );
+        assert_eq!(
+            merged.years,
+            vec![
+                YearFacet {
+                    year: 2024,
+                    count: 2,
+                },
+                YearFacet {
+                    year: 2023,
+                    count: 5,
+                },
+            ]
+        );
+        assert_eq!(merged.untracked_hosts, 1);
+    }
+}
@@ -6,6 +6,7 @@
 use crate::{prehashed::Prehashed, ranking::initial::InitialScoreTweaker, simhash};
 
 pub mod approx_count;
+pub mod facets;
 mod top_docs;
 
 pub use top_docs::{BucketCollector, TopDocs};
@@ -26,11 +26,13 @@ use tantivy::{
 
 use crate::{
     config::CollectorConfig,
+    distributed::member::ShardId,
     inverted_index::{DocAddress, WebpagePointer},
     numericalfield_reader,
     prehashed::Prehashed,
     ranking::initial::{InitialScoreTweaker, Score},
     schema::{numerical_field, NumericalFieldEnum},
+    searcher::Cursor,
     simhash,
 };
 
@@ -39,10 +41,12 @@ use super::{Doc, Hashes, MainCollector, MaxDocsConsidered};
 pub struct TopDocs {
     top_n: usize,
     offset: usize,
+    after: Option<(Cursor, Option<ShardId>)>,
     max_docs: Option<MaxDocsConsidered>,
     columnfield_reader: numericalfield_reader::NumericalFieldReader,
     de_rank_similar: bool,
     collector_config: CollectorConfig,
+    overfetch: Option<usize>,
 }
 
 impl TopDocs {
@@ -53,10 +57,12 @@ impl TopDocs {
         Self {
             top_n,
             offset: 0,
+            after: None,
             max_docs: None,
             de_rank_similar: false,
             columnfield_reader,
             collector_config: CollectorConfig::default(),
+            overfetch: None,
         }
     }
 
@@ -69,6 +75,13 @@ impl TopDocs {
         self
     }
 
+    /// Only collect the documents that come after the cursor, given that the
+    /// documents are from `shard`. This is used instead of an offset.
+    pub fn and_after(mut self, cursor: Cursor, shard: Option<ShardId>) -> Self {
+        self.after = Some((cursor, shard));
+        self
+    }
+
     pub fn and_max_docs(mut self, max_docs: MaxDocsConsidered) -> Self {
         self.max_docs = Some(max_docs);
         self
@@ -79,17 +92,39 @@ impl TopDocs {
         self
     }
 
+    pub fn collector_config(&self) -> &CollectorConfig {
+        &self.collector_config
+    }
+
     pub fn and_collector_config(mut self, collector_config: CollectorConfig) -> Self {
         self.collector_config = collector_config;
         self
     }
 
+    /// Only keep the best `overfetch * (top_n + offset)` documents while collecting.
+    /// See [`BucketCollector::new_bounded`].
+    pub fn and_approximate(mut self, overfetch: usize) -> Self {
+        self.overfetch = Some(overfetch);
+        self
+    }
+
     pub fn main_collector(self, score_tweaker: InitialScoreTweaker) -> MainCollector {
         MainCollector::new(score_tweaker, self)
     }
 }
 
 impl TopDocs {
+    fn bucket_collector<T: Doc>(&self) -> BucketCollector<T> {
+        let top_n = self.top_n + self.offset;
+
+        match self.overfetch {
+            Some(overfetch) => {
+                BucketCollector::new_bounded(top_n, overfetch, self.collector_config.clone())
+            }
+            None => BucketCollector::new(top_n, self.collector_config.clone()),
+        }
+    }
+
     fn for_segment(
         &self,
         segment_local_id: tantivy::SegmentOrdinal,
@@ -104,11 +139,9 @@ impl TopDocs {
             columnfield_segment_reader: self.columnfield_reader.get_segment(&segment.segment_id()),
             max_docs,
             num_docs_taken: 0,
+            after: self.after,
             segment_ord: segment_local_id,
-            bucket_collector: BucketCollector::new(
-                self.top_n + self.offset,
-                self.collector_config.clone(),
-            ),
+            bucket_collector: self.bucket_collector(),
         })
     }
 }
@@ -117,6 +150,7 @@ pub struct TopSegmentCollector {
     columnfield_segment_reader: Arc<numericalfield_reader::SegmentReader>,
     max_docs: Option<usize>,
     num_docs_taken: usize,
+    after: Option<(Cursor, Option<ShardId>)>,
     segment_ord: SegmentOrdinal,
     bucket_collector: BucketCollector<SegmentDoc>,
 }
@@ -154,6 +188,17 @@ impl TopSegmentCollector {
 
         self.num_docs_taken += 1;
 
+        if let Some((cursor, shard)) = &self.after {
+            let address = DocAddress {
+                segment: self.segment_ord,
+                doc_id: doc,
+            };
+
+            if !cursor.is_after(score.total, *shard, address) {
+                return;
+            }
+        }
+
         let simhash: Option<u64> = self
             .columnfield_segment_reader
             .get_field_reader(doc)
@@ -276,6 +321,7 @@ pub struct BucketCollector<T: Doc> {
     count: BucketCount,
     documents: MinMaxHeap<ScoredDoc<T>>,
     top_n: usize,
+    bound: Option<usize>,
 }
 
 impl<T: Doc> BucketCollector<T> {
@@ -286,12 +332,42 @@ impl<T: Doc> BucketCollector<T> {
             top_n,
             documents: MinMaxHeap::with_capacity(config.max_docs_considered + 1),
             count: BucketCount::new(config),
+            bound: None,
+        }
+    }
+
+    /// Keep at most `top_n * overfetch` documents and discard the lowest scoring
+    /// documents as new ones arrive. This bounds the memory usage, but documents
+    /// that would only have made it into the top `top_n` after the similar documents
+    /// above them were de-ranked might be lost.
+    pub fn new_bounded(top_n: usize, overfetch: usize, config: CollectorConfig) -> Self {
+        assert!(top_n > 0);
+
+        let bound = top_n.saturating_mul(overfetch.max(1));
+
+        Self {
+            top_n,
+            documents: MinMaxHeap::with_capacity(bound.min(config.max_docs_considered) + 1),
+            count: BucketCount::new(config),
+            bound: Some(bound),
         }
     }
 
     pub fn insert(&mut self, doc: T) {
         let mut scored_doc: ScoredDoc<T> = doc.into();
         self.count.adjust_score(&mut scored_doc);
+
+        if let Some(bound) = self.bound {
+            if self.documents.len() >= bound {
+                match self.documents.peek_min() {
+                    Some(worst) if *worst < scored_doc => {
+                        self.documents.pop_min();
+                    }
+                    _ => return,
+                }
+            }
+        }
+
         self.documents.push(scored_doc);
     }
 
@@ -420,10 +496,7 @@ where
         &self,
         segment_fruits: Vec<<Self::Child as tantivy::collector::SegmentCollector>::Fruit>,
     ) -> tantivy::Result<Self::Fruit> {
-        let mut collector = BucketCollector::new(
-            self.top_docs.top_n + self.top_docs.offset,
-            self.top_docs.collector_config.clone(),
-        );
+        let mut collector = self.top_docs.bucket_collector();
 
         for docs in segment_fruits {
             for doc in docs {
@@ -736,4 +809,60 @@ mod tests {
             &[(5.0, 127), (3.1, 126), (3.0, 125)],
         );
     }
+
+    #[test]
+    fn approximate_overlaps_exact() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let top_n = 20;
+        let overfetch = 4;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let docs: Vec<_> = (0..10_000u32)
+            .map(|id| SegmentDoc {
+                hashes: Hashes {
+                    site: (id as u128 % 50).into(),
+                    title: (id as u128).into(),
+                    url: (id as u128).into(),
+                    url_without_tld: (id as u128).into(),
+                    simhash: id as u64 + 1,
+                },
+                id,
+                // few documents with high scores and a long tail
+                score: Score {
+                    total: rng.gen::<f64>().powi(4),
+                },
+                segment: 0,
+            })
+            .collect();
+
+        let mut exact = BucketCollector::new(top_n, CollectorConfig::default());
+        let mut approx = BucketCollector::new_bounded(top_n, overfetch, CollectorConfig::default());
+
+        for doc in &docs {
+            exact.insert(doc.clone());
+            approx.insert(doc.clone());
+            assert!(approx.documents.len() <= top_n * overfetch);
+        }
+
+        let exact: Vec<_> = exact
+            .into_sorted_vec(true)
+            .into_iter()
+            .map(|doc| doc.id)
+            .collect();
+        let approx: Vec<_> = approx
+            .into_sorted_vec(true)
+            .into_iter()
+            .map(|doc| doc.id)
+            .collect();
+
+        assert_eq!(exact.len(), top_n);
+        assert_eq!(approx.len(), top_n);
+
+        let overlap = approx.iter().filter(|id| exact.contains(id)).count();
+        assert!(overlap as f64 >= 0.9 * top_n as f64);
+
+        // the best document can never be discarded
+        assert_eq!(approx[0], exact[0]);
+    }
 }
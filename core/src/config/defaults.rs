@@ -16,6 +16,22 @@
 
 use std::time::Duration;
 
+pub struct CompoundTerms;
+
+impl CompoundTerms {
+    pub fn max_terms() -> usize {
+        crate::query::MAX_TERMS_FOR_NGRAM_LOOKUPS
+    }
+
+    pub fn min_window() -> usize {
+        2
+    }
+
+    pub fn max_window() -> usize {
+        3
+    }
+}
+
 pub struct Collector;
 
 impl Collector {
@@ -38,6 +54,10 @@ impl Collector {
     pub fn max_docs_considered() -> usize {
         250_000
     }
+
+    pub fn freshness_half_life_hours() -> f64 {
+        24.0 * 30.0
+    }
 }
 
 pub struct Api;
@@ -143,10 +163,18 @@ impl SearchQuery {
         false
     }
 
+    pub fn explain() -> bool {
+        false
+    }
+
     pub fn safe_search() -> bool {
         false
     }
 
+    pub fn secure_only() -> bool {
+        false
+    }
+
     pub fn count_results_exact() -> bool {
         false
     }
@@ -154,6 +182,10 @@ impl SearchQuery {
     pub fn return_structured_data() -> bool {
         false
     }
+
+    pub fn return_facets() -> bool {
+        false
+    }
 }
 
 pub struct Correction;
@@ -170,6 +202,14 @@ impl Correction {
     pub fn lm_prob_weight() -> f64 {
         5.77
     }
+
+    pub fn use_fallback_lang() -> bool {
+        false
+    }
+
+    pub fn fallback_lang() -> whatlang::Lang {
+        whatlang::Lang::Eng
+    }
 }
 
 pub struct Widgets;
@@ -243,6 +283,14 @@ impl LiveCrawler {
     pub fn init_crawl_db() -> bool {
         true
     }
+
+    pub fn host_crawl_delay() -> Duration {
+        Duration::from_secs(30)
+    }
+
+    pub fn robots_txt_ttl() -> Duration {
+        Duration::from_secs(24 * 60 * 60) // daily
+    }
 }
 
 pub struct HarmonicNearestSeed;
@@ -251,3 +299,10 @@ impl HarmonicNearestSeed {
         0.5
     }
 }
+
+pub struct WarcSource;
+impl WarcSource {
+    pub fn date_pattern() -> String {
+        r"(?P<year>\d{4})/(?P<month>\d{2})/(?P<day>\d{2})".to_string()
+    }
+}
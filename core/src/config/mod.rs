@@ -8,13 +8,18 @@ pub mod defaults;
 use super::Result;
 use crate::ampc::dht;
 use crate::distributed::member::ShardId;
+use crate::webpage::region::RegionOverrides;
 
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufRead};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::time::Duration;
 
+use chrono::NaiveDate;
+use regex::Regex;
+
 pub fn parse_duration<'de, D: serde::de::Deserializer<'de>>(
     deserializer: D,
 ) -> Result<Duration, D::Error> {
@@ -46,19 +51,56 @@ pub fn parse_duration<'de, D: serde::de::Deserializer<'de>>(
     Ok(ret)
 }
 
+/// Same as [`parse_duration`] for each value of a map.
+pub fn parse_duration_map<'de, D: serde::de::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, Duration>, D::Error> {
+    #[derive(serde::Deserialize)]
+    struct ParsedDuration(#[serde(deserialize_with = "parse_duration")] Duration);
+
+    let map: HashMap<String, ParsedDuration> = serde::de::Deserialize::deserialize(deserializer)?;
+
+    Ok(map
+        .into_iter()
+        .map(|(key, ParsedDuration(duration))| (key, duration))
+        .collect())
+}
+
 #[derive(Debug, serde::Deserialize, Clone)]
 pub struct IndexerConfig {
     pub output_path: String,
     pub limit_warc_files: Option<usize>,
     pub skip_warc_files: Option<usize>,
-    pub warc_source: WarcSource,
+    #[serde(flatten)]
+    pub source: DocumentSource,
     pub page_webgraph: Option<IndexerGraphConfig>,
     pub host_centrality_threshold: Option<f64>,
     pub host_centrality_store_path: String,
     pub page_centrality_store_path: Option<String>,
     pub safety_classifier_path: Option<String>,
+    /// Pages that the safety classifier is less confident about
+    /// than this are labelled as unknown and downranked.
+    pub safety_classifier_threshold: Option<f32>,
     pub minimum_clean_words: Option<usize>,
 
+    /// Drop pages where less than this fraction of the extracted text
+    /// is clean text. See [`crate::webpage::Html::content_density`].
+    pub minimum_content_density: Option<f64>,
+
+    /// Replace the built-in list of ad servers with the list at this path.
+    /// The list has one host per line.
+    pub adservers_path: Option<String>,
+
+    /// Drop pages whose simhash is within this many bits of a page
+    /// with a higher pre-computed score when the indexes are merged.
+    /// At most 7.
+    pub simhash_dedup_threshold: Option<u32>,
+
+    /// Insert the pages into the existing index at this path instead of
+    /// building a new index in `output_path`. Pages whose url is already in the
+    /// index replace the old version.
+    pub append_to: Option<String>,
+
     #[serde(default = "defaults::Indexing::batch_size")]
     pub batch_size: usize,
 
@@ -66,6 +108,10 @@ pub struct IndexerConfig {
     pub autocommit_after_num_inserts: usize,
 
     pub dual_encoder: Option<IndexerDualEncoderConfig>,
+
+    /// Regions to assign to specific hosts instead of the detected region.
+    #[serde(default)]
+    pub region_overrides: RegionOverrides,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
@@ -97,6 +143,46 @@ pub struct WebgraphConstructConfig {
 
     #[serde(default = "defaults::Webgraph::merge_all_segments")]
     pub merge_all_segments: bool,
+
+    /// Leave links marked as `nofollow`, `sponsored` or `ugc` out of the host graph.
+    #[serde(default)]
+    pub exclude_nofollow_host_links: bool,
+
+    /// Leave links marked as `nofollow`, `sponsored` or `ugc` out of the page graph.
+    #[serde(default)]
+    pub exclude_nofollow_page_links: bool,
+}
+
+/// Where the indexer reads the webpages from. Either a `[warc_source]`
+/// or an `[ndjson_source]` table is expected in the config.
+#[derive(Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone)]
+pub enum DocumentSource {
+    #[serde(rename = "warc_source")]
+    Warc(WarcSource),
+    #[serde(rename = "ndjson_source")]
+    Ndjson(NdjsonSource),
+}
+
+impl DocumentSource {
+    pub fn paths(&self) -> Result<Vec<String>> {
+        match self {
+            DocumentSource::Warc(source) => source.paths(),
+            DocumentSource::Ndjson(source) => Ok(source.paths.clone()),
+        }
+    }
+}
+
+impl From<WarcSource> for DocumentSource {
+    fn from(source: WarcSource) -> Self {
+        DocumentSource::Warc(source)
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone)]
+pub struct NdjsonSource {
+    /// Files with one `{"url", "body", "fetch_time_ms"}` object per line.
+    /// A path of `-` reads from stdin.
+    pub paths: Vec<String>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone)]
@@ -162,7 +248,81 @@ impl WarcSource {
             }
         }
 
-        Ok(warc_paths)
+        match self.date_filter() {
+            Some(filter) => filter.filter(warc_paths),
+            None => Ok(warc_paths),
+        }
+    }
+
+    pub fn date_filter(&self) -> Option<&WarcDateFilter> {
+        match self {
+            WarcSource::HTTP(config) => config.date_filter.as_ref(),
+            WarcSource::Local(config) => config.date_filter.as_ref(),
+            WarcSource::S3(config) => config.date_filter.as_ref(),
+        }
+    }
+}
+
+/// Only keep the warc files that were crawled within a date range.
+/// The crawl date is parsed from the path of the warc file
+/// (e.g. `crawl/2024/10/02/...`) using a configurable regex.
+#[derive(Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone)]
+pub struct WarcDateFilter {
+    /// Regex with the named groups `year`, `month` and `day`
+    /// that extracts the crawl date from the path.
+    #[serde(default = "defaults::WarcSource::date_pattern")]
+    pub pattern: String,
+
+    /// Inclusive lower bound
+    #[bincode(with_serde)]
+    pub from: Option<NaiveDate>,
+
+    /// Inclusive upper bound
+    #[bincode(with_serde)]
+    pub to: Option<NaiveDate>,
+
+    /// Whether or not to keep paths where no date could be parsed.
+    #[serde(default)]
+    pub include_unparseable: bool,
+}
+
+impl WarcDateFilter {
+    pub fn filter(&self, paths: Vec<String>) -> Result<Vec<String>> {
+        let regex = Regex::new(&self.pattern)?;
+
+        Ok(paths
+            .into_iter()
+            .filter(|path| match Self::parse_date(&regex, path) {
+                Some(date) => self.contains(&date),
+                None => self.include_unparseable,
+            })
+            .collect())
+    }
+
+    fn parse_date(regex: &Regex, path: &str) -> Option<NaiveDate> {
+        let captures = regex.captures(path)?;
+
+        let year = captures.name("year")?.as_str().parse().ok()?;
+        let month = captures.name("month")?.as_str().parse().ok()?;
+        let day = captures.name("day")?.as_str().parse().ok()?;
+
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    fn contains(&self, date: &NaiveDate) -> bool {
+        if let Some(from) = self.from {
+            if *date < from {
+                return false;
+            }
+        }
+
+        if let Some(to) = self.to {
+            if *date > to {
+                return false;
+            }
+        }
+
+        true
     }
 }
 
@@ -170,12 +330,18 @@ impl WarcSource {
 pub struct LocalConfig {
     pub folder: String,
     pub names: Vec<String>,
+
+    #[serde(default)]
+    pub date_filter: Option<WarcDateFilter>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone)]
 pub struct HttpConfig {
     pub base_url: String,
     pub warc_paths_file: String,
+
+    #[serde(default)]
+    pub date_filter: Option<WarcDateFilter>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone)]
@@ -185,6 +351,9 @@ pub struct S3Config {
     pub access_key: String,
     pub secret_key: String,
     pub endpoint: String,
+
+    #[serde(default)]
+    pub date_filter: Option<WarcDateFilter>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
@@ -203,6 +372,95 @@ pub struct CollectorConfig {
 
     #[serde(default = "defaults::Collector::max_docs_considered")]
     pub max_docs_considered: usize,
+
+    /// Number of hours after which the freshness signal of a page is halved.
+    #[serde(default = "defaults::Collector::freshness_half_life_hours")]
+    pub freshness_half_life_hours: f64,
+
+    /// If set, each segment only keeps the best `overfetch * num_results` documents
+    /// while collecting instead of up to `max_docs_considered`. This bounds the memory
+    /// usage on large shards at the cost of slightly worse recall.
+    #[serde(default)]
+    pub approximate_top_k_overfetch: Option<usize>,
+
+    /// Estimate the number of distinct sites among all matching documents.
+    #[serde(default)]
+    pub estimate_distinct_sites: bool,
+}
+
+/// How simple query terms are expanded with compounds of their adjacent terms,
+/// so e.g. `data base` also matches `database`. Larger windows and more terms
+/// improve recall at the cost of more expensive queries.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+#[serde(try_from = "UncheckedCompoundTermsConfig")]
+pub struct CompoundTermsConfig {
+    /// Queries with more terms than this are not expanded.
+    /// This also bounds the number of terms that are looked up in the n-gram fields when ranking.
+    pub max_terms: usize,
+
+    /// The range of window sizes around each term that the compounds are built from.
+    /// A compound needs at least two terms, so `2 <= min_window <= max_window`.
+    pub min_window: usize,
+
+    pub max_window: usize,
+}
+
+#[derive(serde::Deserialize)]
+struct UncheckedCompoundTermsConfig {
+    #[serde(default = "defaults::CompoundTerms::max_terms")]
+    max_terms: usize,
+
+    #[serde(default = "defaults::CompoundTerms::min_window")]
+    min_window: usize,
+
+    #[serde(default = "defaults::CompoundTerms::max_window")]
+    max_window: usize,
+}
+
+impl TryFrom<UncheckedCompoundTermsConfig> for CompoundTermsConfig {
+    type Error = String;
+
+    fn try_from(config: UncheckedCompoundTermsConfig) -> Result<Self, Self::Error> {
+        if config.min_window < 2 {
+            return Err(format!(
+                "compound terms min_window must be at least 2, got {}",
+                config.min_window
+            ));
+        }
+
+        if config.min_window > config.max_window {
+            return Err(format!(
+                "compound terms min_window ({}) is larger than max_window ({})",
+                config.min_window, config.max_window
+            ));
+        }
+
+        Ok(Self {
+            max_terms: config.max_terms,
+            min_window: config.min_window,
+            max_window: config.max_window,
+        })
+    }
+}
+
+impl Default for CompoundTermsConfig {
+    fn default() -> Self {
+        Self {
+            max_terms: defaults::CompoundTerms::max_terms(),
+            min_window: defaults::CompoundTerms::min_window(),
+            max_window: defaults::CompoundTerms::max_window(),
+        }
+    }
 }
 
 impl Default for CollectorConfig {
@@ -213,6 +471,9 @@ impl Default for CollectorConfig {
             url_penalty: defaults::Collector::url_penalty(),
             url_without_tld_penalty: defaults::Collector::url_without_tld_penalty(),
             max_docs_considered: defaults::Collector::max_docs_considered(),
+            freshness_half_life_hours: defaults::Collector::freshness_half_life_hours(),
+            approximate_top_k_overfetch: None,
+            estimate_distinct_sites: false,
         }
     }
 }
@@ -251,6 +512,10 @@ pub struct ApiConfig {
     pub lambda_model_path: Option<String>,
     pub dual_encoder_model_path: Option<String>,
     pub bangs_path: Option<String>,
+    /// Additional bang files loaded after `bangs_path`. Bangs in later
+    /// files replace earlier bangs with the same tag.
+    #[serde(default)]
+    pub user_bangs_paths: Vec<String>,
     pub query_store_db_host: Option<String>,
     pub gossip_seed_nodes: Option<Vec<SocketAddr>>,
     pub gossip_addr: SocketAddr,
@@ -263,6 +528,10 @@ pub struct ApiConfig {
     #[serde(default = "defaults::Api::top_phrases_for_autosuggest")]
     pub top_phrases_for_autosuggest: usize,
 
+    /// Suggest queries from this query log instead of the key phrases of the index.
+    /// Each line is a query and its count separated by a tab.
+    pub query_log_path: Option<String>,
+
     pub spell_check: Option<ApiSpellCheck>,
 
     #[serde(default)]
@@ -274,6 +543,9 @@ pub struct ApiConfig {
     #[serde(default)]
     pub collector: CollectorConfig,
 
+    #[serde(default)]
+    pub compound_terms: CompoundTermsConfig,
+
     #[serde(default = "defaults::Api::max_concurrent_searches")]
     pub max_concurrent_searches: Option<usize>,
 }
@@ -327,6 +599,9 @@ pub struct SearchServerConfig {
     pub linear_model_path: Option<String>,
     pub dual_encoder_model_path: Option<String>,
     pub host: SocketAddr,
+    /// Also accept connections on this unix domain socket, so services
+    /// on the same host can skip TCP.
+    pub unix_socket: Option<PathBuf>,
 
     #[serde(default)]
     pub collector: CollectorConfig,
@@ -386,6 +661,37 @@ pub struct CrawlerConfig {
     pub router_hosts: Vec<String>,
 }
 
+impl CrawlerConfig {
+    /// A config for crawling in tests without any delays between fetches.
+    #[cfg(test)]
+    pub fn for_tests() -> Self {
+        Self {
+            num_worker_threads: 1,
+            user_agent: UserAgent {
+                full: "Neos".to_string(),
+                token: "Neos".to_string(),
+            },
+            robots_txt_cache_sec: 60,
+            min_politeness_factor: 0,
+            start_politeness_factor: 0,
+            min_crawl_delay_ms: 0,
+            max_crawl_delay_ms: 0,
+            max_politeness_factor: 0,
+            max_url_slowdown_retry: 0,
+            timeout_seconds: 1,
+            s3: S3Config {
+                bucket: String::new(),
+                folder: String::new(),
+                access_key: String::new(),
+                secret_key: String::new(),
+                endpoint: String::new(),
+                date_filter: None,
+            },
+            router_hosts: vec![],
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct DailyLiveIndexCrawlerBudget {
     #[serde(default = "defaults::LiveCrawler::blogs_budget")]
@@ -449,6 +755,35 @@ pub struct LiveCrawlerConfig {
     pub daily_budget: DailyLiveIndexCrawlerBudget,
     #[serde(default = "defaults::LiveCrawler::init_crawl_db")]
     pub init_crawl_db: bool,
+    #[serde(default)]
+    pub host_crawl_delay: HostCrawlDelay,
+    #[serde(
+        deserialize_with = "parse_duration",
+        default = "defaults::LiveCrawler::robots_txt_ttl"
+    )]
+    pub robots_txt_ttl: Duration,
+}
+
+/// The minimum time between two fetches from the same host in the live crawler.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct HostCrawlDelay {
+    #[serde(
+        deserialize_with = "parse_duration",
+        default = "defaults::LiveCrawler::host_crawl_delay"
+    )]
+    pub default: Duration,
+    /// Delays for specific hosts that override the default.
+    #[serde(default, deserialize_with = "parse_duration_map")]
+    pub overrides: HashMap<String, Duration>,
+}
+
+impl Default for HostCrawlDelay {
+    fn default() -> Self {
+        Self {
+            default: defaults::LiveCrawler::host_crawl_delay(),
+            overrides: HashMap::new(),
+        }
+    }
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
@@ -559,6 +894,7 @@ pub struct LiveIndexConfig {
     pub host_centrality_store_path: String,
     pub page_centrality_store_path: Option<String>,
     pub safety_classifier_path: Option<String>,
+    pub safety_classifier_threshold: Option<f32>,
     pub host_centrality_threshold: Option<f64>,
     pub minimum_clean_words: Option<usize>,
     pub gossip_seed_nodes: Option<Vec<SocketAddr>>,
@@ -608,6 +944,14 @@ pub struct CorrectionConfig {
     /// corrected
     #[serde(default = "defaults::Correction::correction_threshold")]
     pub correction_threshold: f64,
+
+    /// Use the spell checker of `fallback_lang` when there is no
+    /// spell checker for the detected language
+    #[serde(default = "defaults::Correction::use_fallback_lang")]
+    pub use_fallback_lang: bool,
+
+    #[serde(default = "defaults::Correction::fallback_lang")]
+    pub fallback_lang: whatlang::Lang,
 }
 
 impl Default for CorrectionConfig {
@@ -616,6 +960,8 @@ impl Default for CorrectionConfig {
             misspelled_prob: defaults::Correction::misspelled_prob(),
             lm_prob_weight: defaults::Correction::lm_prob_weight(),
             correction_threshold: defaults::Correction::correction_threshold(),
+            use_fallback_lang: defaults::Correction::use_fallback_lang(),
+            fallback_lang: defaults::Correction::fallback_lang(),
         }
     }
 }
@@ -690,3 +1036,123 @@ pub struct HarmonicNearestSeedConfig {
     #[serde(default = "defaults::HarmonicNearestSeed::discount_factor")]
     pub discount_factor: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths() -> Vec<String> {
+        vec![
+            "crawl/2024/09/30/0.warc.gz".to_string(),
+            "crawl/2024/10/01/0.warc.gz".to_string(),
+            "crawl/2024/10/02/0.warc.gz".to_string(),
+            "crawl/2024/10/02/1.warc.gz".to_string(),
+            "crawl/2024/10/03/0.warc.gz".to_string(),
+            "crawl/2024/10/04/0.warc.gz".to_string(),
+            "crawl/latest/0.warc.gz".to_string(),
+        ]
+    }
+
+    fn filter(from: Option<NaiveDate>, to: Option<NaiveDate>) -> WarcDateFilter {
+        WarcDateFilter {
+            pattern: defaults::WarcSource::date_pattern(),
+            from,
+            to,
+            include_unparseable: false,
+        }
+    }
+
+    #[test]
+    fn compound_terms_windows_are_validated() {
+        let config: CompoundTermsConfig = toml::from_str("").unwrap();
+        assert_eq!(config, CompoundTermsConfig::default());
+
+        let config: CompoundTermsConfig = toml::from_str("min_window = 3\nmax_window = 3").unwrap();
+        assert_eq!(config.min_window, 3);
+
+        assert!(toml::from_str::<CompoundTermsConfig>("min_window = 1").is_err());
+        assert!(toml::from_str::<CompoundTermsConfig>("min_window = 3\nmax_window = 2").is_err());
+    }
+
+    #[test]
+    fn date_filter_sub_range() {
+        let filter = filter(
+            NaiveDate::from_ymd_opt(2024, 10, 1),
+            NaiveDate::from_ymd_opt(2024, 10, 2),
+        );
+
+        assert_eq!(
+            filter.filter(paths()).unwrap(),
+            vec![
+                "crawl/2024/10/01/0.warc.gz".to_string(),
+                "crawl/2024/10/02/0.warc.gz".to_string(),
+                "crawl/2024/10/02/1.warc.gz".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn date_filter_open_ended() {
+        let filter = filter(NaiveDate::from_ymd_opt(2024, 10, 3), None);
+
+        assert_eq!(
+            filter.filter(paths()).unwrap(),
+            vec![
+                "crawl/2024/10/03/0.warc.gz".to_string(),
+                "crawl/2024/10/04/0.warc.gz".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn date_filter_unparseable() {
+        let mut filter = filter(None, NaiveDate::from_ymd_opt(2024, 9, 30));
+
+        assert_eq!(
+            filter.filter(paths()).unwrap(),
+            vec!["crawl/2024/09/30/0.warc.gz".to_string()]
+        );
+
+        filter.include_unparseable = true;
+
+        assert_eq!(
+            filter.filter(paths()).unwrap(),
+            vec![
+                "crawl/2024/09/30/0.warc.gz".to_string(),
+                "crawl/latest/0.warc.gz".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn date_filter_custom_pattern() {
+        let filter = WarcDateFilter {
+            pattern: r"CC-MAIN-(?P<year>\d{4})(?P<month>\d{2})(?P<day>\d{2})".to_string(),
+            from: NaiveDate::from_ymd_opt(2024, 1, 1),
+            to: None,
+            include_unparseable: false,
+        };
+
+        assert_eq!(
+            filter
+                .filter(vec![
+                    "CC-MAIN-20231231-0.warc.gz".to_string(),
+                    "CC-MAIN-20240101-0.warc.gz".to_string(),
+                ])
+                .unwrap(),
+            vec!["CC-MAIN-20240101-0.warc.gz".to_string()]
+        );
+    }
+
+    #[test]
+    fn date_filter_invalid_pattern() {
+        let filter = WarcDateFilter {
+            pattern: "(".to_string(),
+            from: None,
+            to: None,
+            include_unparseable: true,
+        };
+
+        assert!(filter.filter(paths()).is_err());
+    }
+}
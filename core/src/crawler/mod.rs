@@ -14,7 +14,13 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{collections::VecDeque, future::Future, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    future::Future,
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
 
 type HashMap<K, V> = std::collections::HashMap<K, V, ahash::RandomState>;
 
@@ -25,9 +31,12 @@ use url::Url;
 use crate::{config::CrawlerConfig, warc, webpage::url_ext::UrlExt};
 
 use self::{warc_writer::WarcWriter, worker::WorkerThread};
+pub use rate_limiter::HostRateLimiter;
+pub use robots_txt::RobotsTxtManager;
 pub use worker::JobExecutor;
 
 pub mod coordinator;
+mod rate_limiter;
 mod robots_txt;
 pub mod router;
 pub use router::Router;
@@ -253,6 +262,9 @@ pub struct CrawlDatum {
     pub payload_type: warc::PayloadType,
     pub body: String,
     pub fetch_time_ms: u64,
+    pub status_code: u16,
+    /// See [`warc::Response::headers`].
+    pub headers: BTreeMap<String, String>,
 }
 
 pub struct Crawler {
@@ -323,6 +335,26 @@ pub fn reqwest_client(config: &CrawlerConfig) -> Result<reqwest::Client> {
         .map_err(|e| Error::from(anyhow!(e)))
 }
 
+/// The response headers in the form they are stored in a [`warc::Response`].
+pub fn response_headers(headers: &reqwest::header::HeaderMap) -> BTreeMap<String, String> {
+    let mut res: BTreeMap<String, String> = BTreeMap::new();
+
+    for (name, value) in headers {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+
+        res.entry(name.as_str().to_lowercase())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+
+    res
+}
+
 pub async fn encoded_body(res: reqwest::Response) -> Result<String> {
     let content_type = res
         .headers()
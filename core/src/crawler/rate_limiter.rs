@@ -0,0 +1,274 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::config::HostCrawlDelay;
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Spaces out the fetches to each host. Every fetch reserves the next free slot
+/// of its host, so concurrent executors crawling the same host queue up behind
+/// each other instead of fetching at the same time.
+///
+/// The delay of a host is the configured delay, or the `Crawl-delay` from its
+/// robots.txt if that is longer.
+pub struct HostRateLimiter<C = SystemClock> {
+    clock: C,
+    default_delay: Duration,
+    overrides: HashMap<String, Duration>,
+    hosts: Mutex<Hosts>,
+}
+
+/// Hosts are dropped once their last slot is more than their delay ago,
+/// since their next fetch can then happen right away.
+/// The map is only scanned when it has doubled in size since the last scan.
+struct Hosts {
+    states: HashMap<String, HostState>,
+    prune_at: usize,
+}
+
+#[derive(Default)]
+struct HostState {
+    last_acquired: Option<Instant>,
+    robots_delay: Option<Duration>,
+}
+
+const MIN_PRUNE_AT: usize = 1024;
+
+impl HostRateLimiter {
+    pub fn new(config: &HostCrawlDelay) -> Self {
+        Self::with_clock(config, SystemClock)
+    }
+}
+
+impl<C: Clock> HostRateLimiter<C> {
+    pub fn with_clock(config: &HostCrawlDelay, clock: C) -> Self {
+        Self {
+            clock,
+            default_delay: config.default,
+            overrides: config.overrides.clone(),
+            hosts: Mutex::new(Hosts {
+                states: HashMap::new(),
+                prune_at: MIN_PRUNE_AT,
+            }),
+        }
+    }
+
+    fn delay(&self, host: &str, state: &HostState) -> Duration {
+        let configured = self
+            .overrides
+            .get(host)
+            .copied()
+            .unwrap_or(self.default_delay);
+
+        match state.robots_delay {
+            Some(robots) => configured.max(robots),
+            None => configured,
+        }
+    }
+
+    /// Set the `Crawl-delay` the host asks for in its robots.txt.
+    ///
+    /// The delay is forgotten together with the last slot of the host,
+    /// so it must be set again before each fetch.
+    pub fn set_robots_delay(&self, host: &str, delay: Duration) {
+        self.hosts
+            .lock()
+            .unwrap()
+            .states
+            .entry(host.to_string())
+            .or_default()
+            .robots_delay = Some(delay);
+    }
+
+    /// Reserve the next fetch slot of `host` and return how long the caller
+    /// must wait before fetching.
+    pub fn reserve(&self, host: &str) -> Duration {
+        let now = self.clock.now();
+        let mut hosts = self.hosts.lock().unwrap();
+
+        if hosts.states.len() >= hosts.prune_at {
+            self.prune(&mut hosts, now);
+        }
+
+        let state = hosts.states.entry(host.to_string()).or_default();
+
+        let slot = match state.last_acquired {
+            Some(last) => (last + self.delay(host, state)).max(now),
+            None => now,
+        };
+
+        state.last_acquired = Some(slot);
+
+        slot - now
+    }
+
+    fn prune(&self, hosts: &mut Hosts, now: Instant) {
+        hosts
+            .states
+            .retain(|host, state| match state.last_acquired {
+                Some(last) => last + self.delay(host, state) > now,
+                None => true,
+            });
+
+        hosts.prune_at = (hosts.states.len() * 2).max(MIN_PRUNE_AT);
+    }
+
+    #[cfg(test)]
+    fn num_hosts(&self) -> usize {
+        self.hosts.lock().unwrap().states.len()
+    }
+
+    /// Wait until `host` may be fetched again.
+    pub async fn acquire(&self, host: &str) {
+        let wait = self.reserve(host);
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct MockClock(Arc<Mutex<Instant>>);
+
+    impl MockClock {
+        fn new() -> Self {
+            Self(Arc::new(Mutex::new(Instant::now())))
+        }
+
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    const ZERO: Duration = Duration::ZERO;
+
+    #[test]
+    fn spaced_by_delay() {
+        let clock = MockClock::new();
+        let config = HostCrawlDelay {
+            default: Duration::from_secs(10),
+            overrides: [("slow.com".to_string(), Duration::from_secs(60))]
+                .into_iter()
+                .collect(),
+        };
+        let limiter = HostRateLimiter::with_clock(&config, clock.clone());
+
+        assert_eq!(limiter.reserve("a.com"), ZERO);
+        assert_eq!(limiter.reserve("b.com"), ZERO);
+        assert_eq!(limiter.reserve("slow.com"), ZERO);
+
+        clock.advance(Duration::from_secs(4));
+        assert_eq!(limiter.reserve("a.com"), Duration::from_secs(6));
+        assert_eq!(limiter.reserve("c.com"), ZERO);
+
+        clock.advance(Duration::from_secs(6));
+        assert_eq!(limiter.reserve("b.com"), ZERO);
+        assert_eq!(limiter.reserve("slow.com"), Duration::from_secs(50));
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(limiter.reserve("a.com"), ZERO);
+        assert_eq!(limiter.reserve("slow.com"), ZERO);
+    }
+
+    #[test]
+    fn concurrent_fetches_queue_up() {
+        let clock = MockClock::new();
+        let config = HostCrawlDelay {
+            default: Duration::from_secs(1),
+            overrides: HashMap::new(),
+        };
+        let limiter = HostRateLimiter::with_clock(&config, clock.clone());
+
+        // three executors fetching from the same host at the same time
+        assert_eq!(limiter.reserve("a.com"), ZERO);
+        assert_eq!(limiter.reserve("a.com"), Duration::from_secs(1));
+        assert_eq!(limiter.reserve("a.com"), Duration::from_secs(2));
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(limiter.reserve("a.com"), Duration::from_millis(2500));
+    }
+
+    #[test]
+    fn robots_crawl_delay() {
+        let clock = MockClock::new();
+        let config = HostCrawlDelay {
+            default: Duration::from_secs(10),
+            overrides: HashMap::new(),
+        };
+        let limiter = HostRateLimiter::with_clock(&config, clock.clone());
+
+        assert_eq!(limiter.reserve("a.com"), ZERO);
+        limiter.set_robots_delay("a.com", Duration::from_secs(30));
+        limiter.set_robots_delay("b.com", Duration::from_secs(1));
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(limiter.reserve("a.com"), Duration::from_secs(20));
+
+        assert_eq!(limiter.reserve("b.com"), ZERO);
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(limiter.reserve("b.com"), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn idle_hosts_are_evicted() {
+        let clock = MockClock::new();
+        let config = HostCrawlDelay {
+            default: Duration::from_secs(10),
+            overrides: HashMap::new(),
+        };
+        let limiter = HostRateLimiter::with_clock(&config, clock.clone());
+
+        limiter.set_robots_delay("slow.com", Duration::from_secs(60));
+        assert_eq!(limiter.reserve("slow.com"), ZERO);
+
+        for i in 0..MIN_PRUNE_AT {
+            assert_eq!(limiter.reserve(&format!("{i}.com")), ZERO);
+        }
+
+        // the other hosts are still within their delay
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(limiter.reserve("a.com"), ZERO);
+        assert_eq!(limiter.num_hosts(), MIN_PRUNE_AT + 2);
+
+        clock.advance(Duration::from_secs(5));
+        for i in 0..MIN_PRUNE_AT {
+            assert_eq!(limiter.reserve(&format!("new-{i}.com")), ZERO);
+        }
+
+        // the hosts that could be fetched right away were dropped
+        assert_eq!(limiter.num_hosts(), MIN_PRUNE_AT + 2);
+        assert_eq!(limiter.reserve("slow.com"), Duration::from_secs(50));
+        assert_eq!(limiter.reserve("0.com"), ZERO);
+    }
+}
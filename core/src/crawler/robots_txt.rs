@@ -14,7 +14,12 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{collections::BTreeMap, panic, time::Duration};
+use std::{
+    collections::BTreeMap,
+    panic,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use url::Url;
 
@@ -24,18 +29,51 @@ use super::{encoded_body, Result, Site};
 
 const RETRY_ROBOTSTXT_UNREACHABLE: bool = false;
 
+/// Unreachable robots.txt files disallow everything, so they are
+/// retried sooner than the robots.txt files that could be fetched.
+const UNREACHABLE_EXPIRATION: Duration = Duration::from_secs(10 * 60);
+
 #[derive(Debug)]
 enum Lookup<T> {
     Found(T),
-    /// 404
+    /// 404 or a robots.txt that couldn't be parsed
     Unavailable,
     /// 5xx
     Unreachable,
 }
 
+struct CachedLookup {
+    lookup: Lookup<RobotsTxt>,
+    fetched_at: Instant,
+}
+
+impl CachedLookup {
+    fn new(lookup: Lookup<RobotsTxt>) -> Self {
+        Self {
+            lookup,
+            fetched_at: Instant::now(),
+        }
+    }
+
+    fn is_expired(&self, expiration: &Duration) -> bool {
+        let expiration = match self.lookup {
+            Lookup::Unreachable => (*expiration).min(UNREACHABLE_EXPIRATION),
+            Lookup::Found(_) | Lookup::Unavailable => *expiration,
+        };
+
+        self.fetched_at.elapsed() > expiration
+    }
+}
+
+struct Cache {
+    lookups: BTreeMap<Site, Arc<CachedLookup>>,
+    last_prune: Instant,
+}
+
+/// Fetches and caches the robots.txt of each site. Clones share the same cache.
+#[derive(Clone)]
 pub struct RobotsTxtManager {
-    cache: BTreeMap<Site, Lookup<RobotsTxt>>,
-    last_prune: std::time::Instant,
+    cache: Arc<Mutex<Cache>>,
     client: reqwest::Client,
     cache_expiration: Duration,
     user_agent: String,
@@ -54,24 +92,26 @@ impl RobotsTxtManager {
         Self {
             client,
             cache_expiration,
-            last_prune: std::time::Instant::now(),
-            cache: BTreeMap::new(),
+            cache: Arc::new(Mutex::new(Cache {
+                lookups: BTreeMap::new(),
+                last_prune: Instant::now(),
+            })),
             user_agent: user_agent.to_string(),
             min_crawl_delay,
             max_crawl_delay,
         }
     }
 
-    pub async fn is_allowed(&mut self, url: &Url) -> bool {
-        match self.get_mut(url).await {
+    pub async fn is_allowed(&self, url: &Url) -> bool {
+        match &self.get(url).await.lookup {
             Lookup::Found(robots_txt) => robots_txt.is_allowed(url),
             Lookup::Unavailable => true,
             Lookup::Unreachable => false,
         }
     }
 
-    pub async fn crawl_delay(&mut self, url: &Url) -> Option<Duration> {
-        match self.get_mut(url).await {
+    pub async fn crawl_delay(&self, url: &Url) -> Option<Duration> {
+        match &self.get(url).await.lookup {
             Lookup::Found(robots_txt) => robots_txt.robots.crawl_delay(),
             Lookup::Unavailable | Lookup::Unreachable => None,
         }
@@ -101,7 +141,7 @@ impl RobotsTxtManager {
                 let self_user_agent = self.user_agent.clone();
                 match panic::catch_unwind(|| RobotsTxt::new(&self_user_agent, body)) {
                     Ok(Ok(r)) => Lookup::Found(r),
-                    _ => Lookup::Unreachable,
+                    _ => Lookup::Unavailable,
                 }
             }
             Err(_) => Lookup::Unreachable,
@@ -159,39 +199,59 @@ impl RobotsTxtManager {
         Lookup::Unreachable
     }
 
-    fn maybe_prune(&mut self) {
-        if self.last_prune.elapsed() < Duration::from_secs(60) {
-            return;
-        }
+    fn cached(&self, site: &Site) -> Option<Arc<CachedLookup>> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
 
-        self.cache.retain(|_, v| match v {
-            Lookup::Found(robots_txt) => !robots_txt.is_expired(&self.cache_expiration),
-            _ => true,
-        });
+        if cache.last_prune.elapsed() >= Duration::from_secs(60) {
+            cache
+                .lookups
+                .retain(|_, lookup| !lookup.is_expired(&self.cache_expiration));
+            cache.last_prune = Instant::now();
+        }
 
-        self.last_prune = std::time::Instant::now();
+        cache
+            .lookups
+            .get(site)
+            .filter(|lookup| !lookup.is_expired(&self.cache_expiration))
+            .cloned()
     }
 
-    async fn get_mut(&mut self, url: &Url) -> &mut Lookup<RobotsTxt> {
-        self.maybe_prune();
+    async fn get(&self, url: &Url) -> Arc<CachedLookup> {
         let site = Site(url.host_str().unwrap_or_default().to_string());
 
-        let cache_should_update = match self.cache.get_mut(&site) {
-            Some(Lookup::Found(robots_txt)) => robots_txt.is_expired(&self.cache_expiration),
-            Some(Lookup::Unavailable) | Some(Lookup::Unreachable) => false,
-            None => true,
-        };
-
-        if cache_should_update {
-            self.cache
-                .insert(site.clone(), self.fetch_robots_txt(&site).await);
+        if let Some(lookup) = self.cached(&site) {
+            return lookup;
         }
 
-        self.cache.get_mut(&site).unwrap()
+        // the lock is not held while fetching, so other sites can be looked up in the meantime
+        let lookup = Arc::new(CachedLookup::new(self.fetch_robots_txt(&site).await));
+
+        self.cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .lookups
+            .insert(site, Arc::clone(&lookup));
+
+        lookup
     }
 
-    pub async fn sitemaps(&mut self, url: &Url) -> Vec<Url> {
-        match self.get_mut(url).await {
+    /// Cache the robots.txt `body` of `site` as if it had been fetched.
+    #[cfg(test)]
+    pub fn insert(&self, site: &str, body: &str) {
+        let lookup = match RobotsTxt::new(&self.user_agent, body.to_string()) {
+            Ok(robots_txt) => Lookup::Found(robots_txt),
+            Err(_) => Lookup::Unavailable,
+        };
+
+        self.cache
+            .lock()
+            .unwrap()
+            .lookups
+            .insert(Site(site.to_string()), Arc::new(CachedLookup::new(lookup)));
+    }
+
+    pub async fn sitemaps(&self, url: &Url) -> Vec<Url> {
+        match &self.get(url).await.lookup {
             Lookup::Found(robotstxt) => robotstxt
                 .sitemaps()
                 .iter()
@@ -205,7 +265,6 @@ impl RobotsTxtManager {
 
 #[derive(Debug)]
 struct RobotsTxt {
-    download_time: std::time::Instant,
     robots: robotstxt::Robots,
 }
 
@@ -213,14 +272,9 @@ impl RobotsTxt {
     fn new(user_agent: &str, body: String) -> Result<Self> {
         Ok(Self {
             robots: robotstxt::Robots::parse(user_agent, &body)?,
-            download_time: std::time::Instant::now(),
         })
     }
 
-    fn is_expired(&self, expiration: &Duration) -> bool {
-        self.download_time.elapsed() > *expiration
-    }
-
     fn is_allowed(&self, url: &Url) -> bool {
         self.robots.is_allowed(url)
     }
@@ -234,6 +288,26 @@ impl RobotsTxt {
 mod tests {
     use super::*;
 
+    #[test]
+    fn unreachable_expires_sooner() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let fetched_at = Instant::now()
+            .checked_sub(UNREACHABLE_EXPIRATION + Duration::from_secs(1))
+            .unwrap();
+
+        let unreachable = CachedLookup {
+            lookup: Lookup::Unreachable,
+            fetched_at,
+        };
+        assert!(unreachable.is_expired(&day));
+
+        let unavailable = CachedLookup {
+            lookup: Lookup::Unavailable,
+            fetched_at,
+        };
+        assert!(!unavailable.is_expired(&day));
+    }
+
     #[test]
     fn simple() {
         let ua_token = "NeosBot";
@@ -116,12 +116,15 @@ async fn writer_task(mut rx: tokio::sync::mpsc::Receiver<WarcWriterMessage>, s3:
                                 url: datum.url.to_string(),
                             },
                             response: warc::Response {
-                                body: datum.body,
+                                body: datum.body.into(),
                                 payload_type: Some(datum.payload_type),
+                                status_code: Some(datum.status_code),
+                                headers: datum.headers,
                             },
                             metadata: warc::Metadata {
                                 fetch_time_ms: datum.fetch_time_ms,
                             },
+                            date: Some(chrono::Utc::now()),
                         };
 
                         w.write(&warc_record).unwrap();
@@ -38,7 +38,7 @@ use crate::{
 };
 
 use super::{
-    encoded_body, reqwest_client, robots_txt::RobotsTxtManager,
+    encoded_body, reqwest_client, response_headers, robots_txt::RobotsTxtManager,
     wander_prirotiser::WanderPrioritiser, CrawlDatum, DatumStream, Domain, Error, Result,
     RetrieableUrl, Site, WarcWriter, WeightedUrl, WorkerJob, MAX_CONTENT_LENGTH,
     MAX_OUTGOING_URLS_PER_PAGE,
@@ -133,6 +133,7 @@ pub struct JobExecutor<S: DatumStream> {
     has_gotten_429_response: bool,
     politeness_factor: u32,
     robotstxt: RobotsTxtManager,
+    rate_limiter: Option<Arc<HostRateLimiter>>,
     crawled_urls: HashSet<Url>,
     crawled_sitemaps: HashSet<Site>,
     sitemap_urls: HashSet<Url>,
@@ -158,6 +159,7 @@ impl<S: DatumStream> JobExecutor<S> {
             politeness_factor: config.start_politeness_factor,
             min_politeness_factor: config.min_politeness_factor,
             robotstxt: RobotsTxtManager::new(&config),
+            rate_limiter: None,
             client,
             crawled_urls: HashSet::new(),
             crawled_sitemaps: HashSet::new(),
@@ -173,6 +175,18 @@ impl<S: DatumStream> JobExecutor<S> {
         }
     }
 
+    /// Share the robots.txt cache with other executors instead of starting with an empty one.
+    pub fn with_robots_txt(mut self, robotstxt: RobotsTxtManager) -> Self {
+        self.robotstxt = robotstxt;
+        self
+    }
+
+    /// Space out the fetches to each host across all executors sharing the rate limiter.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<HostRateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
     pub async fn run(mut self) {
         tracing::info!("Processing job: {:?}", self.job.domain);
         for site in self
@@ -317,6 +331,12 @@ impl<S: DatumStream> JobExecutor<S> {
                 if delay > self.min_crawl_delay {
                     self.min_crawl_delay = delay.min(self.max_crawl_delay);
                 }
+
+                if let (Some(rate_limiter), Some(host)) =
+                    (&self.rate_limiter, retryable_url.url().host_str())
+                {
+                    rate_limiter.set_robots_delay(host, delay.min(self.max_crawl_delay));
+                }
             }
 
             let res = self.process_url(retryable_url.url().clone()).await;
@@ -559,6 +579,8 @@ impl<S: DatumStream> JobExecutor<S> {
                 payload_type,
                 body: String::new(),
                 fetch_time_ms: 0,
+                status_code,
+                headers: response_headers(res.headers()),
             }))
         } else {
             Ok(None)
@@ -586,6 +608,7 @@ impl<S: DatumStream> JobExecutor<S> {
             });
         }
 
+        let headers = response_headers(res.headers());
         let body = encoded_body(res).await;
 
         self.crawled_urls.insert(url.clone());
@@ -599,6 +622,8 @@ impl<S: DatumStream> JobExecutor<S> {
             body: body?,
             payload_type: payload_type?,
             fetch_time_ms: 0,
+            status_code: status_code.as_u16(),
+            headers,
         })
     }
 
@@ -610,6 +635,10 @@ impl<S: DatumStream> JobExecutor<S> {
             return Err(Error::from(anyhow!("url already crawled: {}", url)));
         }
 
+        if let (Some(rate_limiter), Some(host)) = (&self.rate_limiter, url.host_str()) {
+            rate_limiter.acquire(host).await;
+        }
+
         let start = Instant::now();
         let res = self.unpolite_crawl_url(url).await;
         let fetch_time = start.elapsed();
@@ -666,3 +695,88 @@ impl<S: DatumStream> JobExecutor<S> {
         urls
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Serve `ok` to every request and record when each request arrived.
+    async fn serve(listener: TcpListener, requests: Arc<std::sync::Mutex<Vec<Instant>>>) {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let requests = requests.clone();
+
+            tokio::spawn(async move {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+
+                // the executor first tries https, which shows up as a tls handshake
+                if !buf[..n].starts_with(b"GET") {
+                    return;
+                }
+
+                requests.lock().unwrap().push(Instant::now());
+
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                    )
+                    .await;
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn fetches_are_rate_limited_across_executors() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        tokio::spawn(serve(listener, requests.clone()));
+
+        let delay = Duration::from_millis(500);
+        let rate_limiter = Arc::new(HostRateLimiter::new(&crate::config::HostCrawlDelay {
+            default: delay,
+            overrides: Default::default(),
+        }));
+        let config = Arc::new(CrawlerConfig::for_tests());
+
+        let executors = ["a", "b", "c"].map(|path| {
+            let url = Url::parse(&format!("http://{addr}/{path}")).unwrap();
+            let executor = JobExecutor::new(
+                WorkerJob {
+                    domain: Domain::from(&url),
+                    urls: VecDeque::new(),
+                    wandering_urls: 0,
+                },
+                reqwest::Client::new(),
+                config.clone(),
+                Arc::new(tokio::sync::Mutex::new(Vec::<CrawlDatum>::new())),
+            )
+            .with_rate_limiter(rate_limiter.clone());
+
+            (executor, url)
+        });
+
+        let handles = executors.map(|(mut executor, url)| {
+            tokio::spawn(async move { executor.polite_crawl_url(url).await.is_ok() })
+        });
+
+        for handle in handles {
+            assert!(handle.await.unwrap());
+        }
+
+        let mut requests = requests.lock().unwrap().clone();
+        requests.sort();
+
+        assert_eq!(requests.len(), 3);
+        for pair in requests.windows(2) {
+            // allow for some scheduling jitter
+            assert!(pair[1] - pair[0] >= delay - Duration::from_millis(50));
+        }
+    }
+}
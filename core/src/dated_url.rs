@@ -3,7 +3,7 @@
 //
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use url::Url;
 
 #[derive(
@@ -23,3 +23,18 @@ pub struct DatedUrl {
     #[bincode(with_serde)]
     pub last_modified: Option<DateTime<Utc>>,
 }
+
+/// Parse either an RFC 3339 timestamp or a plain `YYYY-MM-DD` date.
+pub fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    let date = date.trim();
+
+    DateTime::parse_from_rfc3339(date)
+        .map(|time| time.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc())
+        })
+}
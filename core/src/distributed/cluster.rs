@@ -25,6 +25,7 @@ use crate::distributed::member::{Member, Service};
 const CLUSTER_ID: &str = "stract-cluster";
 const GOSSIP_INTERVAL: Duration = Duration::from_secs(1);
 const SERVICE_KEY: &str = "service";
+const DRAINED_KEY: &str = "drained";
 
 type Result<T> = std::result::Result<T, anyhow::Error>;
 
@@ -33,7 +34,12 @@ fn snapshot_members(snapshot: ClusterStateSnapshot) -> Vec<Member> {
     for (id, state) in snapshot.node_states {
         if let Some(service) = state.get(SERVICE_KEY) {
             if let Ok(service) = serde_json::from_str(service) {
-                res.push(Member { service, id });
+                let drained = state.get(DRAINED_KEY) == Some("true");
+                res.push(Member {
+                    service,
+                    id,
+                    drained,
+                });
             }
         }
     }
@@ -173,6 +179,17 @@ impl Cluster {
         Ok(())
     }
 
+    /// Advertise whether this node should be taken out of rotation.
+    pub async fn set_drained(&self, drained: bool) -> Result<()> {
+        self.chitchat
+            .lock()
+            .await
+            .self_node_state()
+            .set(DRAINED_KEY, drained.to_string());
+
+        Ok(())
+    }
+
     #[cfg(test)]
     pub async fn remove_service(&self) -> Result<()> {
         self.chitchat
@@ -174,15 +174,32 @@ impl Service {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Debug, bincode::Encode, bincode::Decode)]
+#[derive(
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Debug,
+    bincode::Encode,
+    bincode::Decode,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub struct Member {
     pub id: String,
     pub service: Service,
+    /// Drained members still answer requests sent directly to them,
+    /// but clients no longer route new requests to them.
+    pub drained: bool,
 }
 
 impl Member {
     pub fn new(service: Service) -> Self {
         let id = uuid::Uuid::new_v4().to_string();
-        Self { id, service }
+        Self {
+            id,
+            service,
+            drained: false,
+        }
     }
 }
@@ -3,24 +3,26 @@
 //
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
-use std::net::SocketAddr;
+use std::time::Duration;
 
 use crate::Result;
 use deadpool::managed;
 
-use super::service::Service;
+use super::{service::Service, Address};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
 
 pub trait Connection {
     type Manager: managed::Manager;
 
-    fn new_manager(addr: SocketAddr) -> Self::Manager;
+    fn new_manager(addr: Address) -> Self::Manager;
 }
 
 pub struct ConnectionPool<C>
 where
     C: Connection,
 {
-    addr: SocketAddr,
+    addr: Address,
     pool: managed::Pool<C::Manager>,
 }
 
@@ -39,8 +41,10 @@ impl<C> ConnectionPool<C>
 where
     C: Connection,
 {
-    pub fn new(addr: SocketAddr) -> Result<Self> {
-        let manager = C::new_manager(addr);
+    /// The connections are made over TCP or a unix domain socket depending on `addr`.
+    pub fn new(addr: impl Into<Address>) -> Result<Self> {
+        let addr = addr.into();
+        let manager = C::new_manager(addr.clone());
         let pool = managed::Pool::builder(manager).build()?;
 
         Ok(Self { addr, pool })
@@ -53,18 +57,37 @@ where
             .map_err(|_| anyhow::anyhow!("Failed to get connection from pool"))
     }
 
-    pub fn addr(&self) -> SocketAddr {
-        self.addr
+    pub fn addr(&self) -> &Address {
+        &self.addr
+    }
+
+    /// The number of connections in the pool and how many of them are in use.
+    pub fn status(&self) -> deadpool::Status {
+        self.pool.status()
+    }
+
+    /// Open up to `n` connections ahead of time so the first requests
+    /// don't have to wait for a connection to be established.
+    /// The connections are returned to the pool when the warmup is done.
+    pub async fn warmup(&self, n: usize) -> Result<()> {
+        let n = n.min(self.pool.status().max_size);
+        let mut conns = Vec::with_capacity(n);
+
+        for _ in 0..n {
+            conns.push(self.get().await?);
+        }
+
+        Ok(())
     }
 }
 
 pub struct Manager<Req, Res> {
-    addr: SocketAddr,
+    addr: Address,
     _marker: std::marker::PhantomData<(Req, Res)>,
 }
 
 impl<Req, Res> Manager<Req, Res> {
-    pub fn new(addr: SocketAddr) -> Self {
+    pub fn new(addr: Address) -> Self {
         Self {
             addr,
             _marker: std::marker::PhantomData,
@@ -79,7 +102,7 @@ where
 {
     type Manager = Manager<Req, Res>;
 
-    fn new_manager(addr: SocketAddr) -> Self::Manager {
+    fn new_manager(addr: Address) -> Self::Manager {
         Manager::new(addr)
     }
 }
@@ -93,7 +116,12 @@ where
     type Error = anyhow::Error;
 
     async fn create(&self) -> Result<Self::Type, Self::Error> {
-        Ok(super::Connection::connect(self.addr).await?)
+        Ok(super::Connection::create_with_address(
+            &self.addr,
+            CONNECT_TIMEOUT,
+            super::default_protocol_version::<Req, Res>(),
+        )
+        .await?)
     }
 
     async fn recycle(
@@ -116,12 +144,12 @@ where
 }
 
 pub struct ServiceManager<S> {
-    addr: SocketAddr,
+    addr: Address,
     _marker: std::marker::PhantomData<S>,
 }
 
 impl<S> ServiceManager<S> {
-    pub fn new(addr: SocketAddr) -> Self {
+    pub fn new(addr: Address) -> Self {
         Self {
             addr,
             _marker: std::marker::PhantomData,
@@ -135,7 +163,7 @@ where
 {
     type Manager = ServiceManager<S>;
 
-    fn new_manager(addr: SocketAddr) -> Self::Manager {
+    fn new_manager(addr: Address) -> Self::Manager {
         ServiceManager::new(addr)
     }
 }
@@ -148,7 +176,7 @@ where
     type Error = anyhow::Error;
 
     async fn create(&self) -> Result<Self::Type, Self::Error> {
-        Ok(super::service::Connection::create(self.addr).await?)
+        Ok(super::service::Connection::create_with_address(&self.addr, CONNECT_TIMEOUT).await?)
     }
 
     async fn recycle(
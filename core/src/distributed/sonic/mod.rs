@@ -6,20 +6,27 @@
 pub mod connection_pool;
 pub mod replication;
 pub mod service;
+mod transport;
 
 pub use connection_pool::ConnectionPool;
+pub use transport::Address;
 
-use std::{marker::PhantomData, time::Duration};
+use std::{marker::PhantomData, path::Path, time::Duration};
 
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
-    net::{TcpListener, TcpStream, ToSocketAddrs},
+    net::ToSocketAddrs,
 };
 
+use transport::{Listener, Stream};
+
 pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
 
 const MAX_BODY_SIZE_BYTES: usize = 1024 * 1024 * 1024 * 1024; // 1TB
 const MAX_CONNECTION_TTL: Duration = Duration::from_secs(60);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long to wait for a response when the caller doesn't specify a timeout.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(90);
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -29,8 +36,10 @@ pub enum Error {
     #[error("Failed to connect to peer: connection timeout")]
     ConnectionTimeout,
 
-    #[error("Failed to get response for request: connection timeout")]
-    RequestTimeout,
+    /// The peer didn't respond in time. The connection is shut down
+    /// since a late response would otherwise be read by the next request.
+    #[error("Failed to get response for request: request timed out")]
+    Timeout,
 
     #[error("Could not get connection from pool")]
     PoolGet,
@@ -43,10 +52,54 @@ pub enum Error {
 
     #[error("An application error occurred: {0}")]
     Application(#[from] anyhow::Error),
+
+    #[error("Incompatible peer: local protocol version is {local} but the peer uses {remote}")]
+    VersionMismatch { local: u64, remote: u64 },
+}
+
+/// Derive a protocol version from a description of the messages
+/// that are sent over a connection (FNV-1a hash).
+pub const fn protocol_version(schema: &str) -> u64 {
+    let bytes = schema.as_bytes();
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+        i += 1;
+    }
+
+    hash
+}
+
+fn default_protocol_version<Req, Res>() -> u64 {
+    protocol_version(std::any::type_name::<(Req, Res)>())
+}
+
+/// Both sides send their protocol version and check that the peer
+/// sent the same, so incompatible peers are rejected before any messages are decoded.
+async fn handshake(stream: &mut Stream, version: u64) -> Result<()> {
+    let local = Handshake { version };
+    stream.write_all(bytemuck::bytes_of(&local)).await?;
+    stream.flush().await?;
+
+    let mut buf = vec![0; std::mem::size_of::<Handshake>()];
+    stream.read_exact(&mut buf).await?;
+    let remote: Handshake = bytemuck::pod_read_unaligned(&buf);
+
+    if remote.version != version {
+        return Err(Error::VersionMismatch {
+            local: version,
+            remote: remote.version,
+        });
+    }
+
+    Ok(())
 }
 
 pub struct Connection<Req, Res> {
-    stream: TcpStream,
+    stream: Stream,
     created: std::time::Instant,
     marker: PhantomData<(Req, Res)>,
     awaiting_res: bool,
@@ -69,18 +122,61 @@ where
         server: impl ToSocketAddrs,
         timeout: Duration,
     ) -> Result<Self> {
-        match tokio::time::timeout(timeout, TcpStream::connect(server)).await {
-            Ok(stream) => {
-                let stream = stream?;
-                stream.set_nodelay(true)?;
-
-                Ok(Connection {
-                    stream,
-                    awaiting_res: false,
-                    created: std::time::Instant::now(),
-                    marker: PhantomData,
-                })
-            }
+        Self::create_with_version(server, timeout, default_protocol_version::<Req, Res>()).await
+    }
+
+    pub async fn create_with_version(
+        server: impl ToSocketAddrs,
+        timeout: Duration,
+        version: u64,
+    ) -> Result<Self> {
+        Self::establish(Stream::connect_tcp(server), timeout, version).await
+    }
+
+    pub async fn connect_unix(path: impl AsRef<Path>) -> Result<Self> {
+        Self::create_unix_with_version(
+            path,
+            Duration::from_secs(30),
+            default_protocol_version::<Req, Res>(),
+        )
+        .await
+    }
+
+    pub async fn create_unix_with_version(
+        path: impl AsRef<Path>,
+        timeout: Duration,
+        version: u64,
+    ) -> Result<Self> {
+        Self::establish(Stream::connect_unix(path), timeout, version).await
+    }
+
+    pub async fn create_with_address(
+        addr: &Address,
+        timeout: Duration,
+        version: u64,
+    ) -> Result<Self> {
+        Self::establish(Stream::connect(addr), timeout, version).await
+    }
+
+    async fn establish(
+        stream: impl std::future::Future<Output = std::io::Result<Stream>>,
+        timeout: Duration,
+        version: u64,
+    ) -> Result<Self> {
+        let connect = async {
+            let mut stream = stream.await?;
+            handshake(&mut stream, version).await?;
+
+            Ok::<_, Error>(stream)
+        };
+
+        match tokio::time::timeout(timeout, connect).await {
+            Ok(stream) => Ok(Connection {
+                stream: stream?,
+                awaiting_res: false,
+                created: std::time::Instant::now(),
+                marker: PhantomData,
+            }),
             Err(_) => Err(Error::ConnectionTimeout),
         }
     }
@@ -90,16 +186,34 @@ where
         timeout: Duration,
         retry: impl Iterator<Item = Duration>,
     ) -> Result<Self> {
-        let mut conn = Connection::create_with_timeout(server.clone(), timeout).await;
+        Self::create_with_version_retry(
+            server,
+            timeout,
+            retry,
+            default_protocol_version::<Req, Res>(),
+        )
+        .await
+    }
+
+    pub async fn create_with_version_retry(
+        server: impl ToSocketAddrs + Clone,
+        timeout: Duration,
+        retry: impl Iterator<Item = Duration>,
+        version: u64,
+    ) -> Result<Self> {
+        let mut conn = Connection::create_with_version(server.clone(), timeout, version).await;
         let mut retry = retry;
 
         loop {
             match conn {
                 Ok(conn) => return Ok(conn),
+                // retrying will not make the peer compatible
+                Err(err @ Error::VersionMismatch { .. }) => return Err(err),
                 Err(_) => {
                     if let Some(timeout) = retry.next() {
                         tokio::time::sleep(timeout).await;
-                        conn = Connection::create_with_timeout(server.clone(), timeout).await;
+                        conn =
+                            Connection::create_with_version(server.clone(), timeout, version).await;
                     } else {
                         return Err(Error::ConnectionTimeout);
                     }
@@ -144,7 +258,7 @@ where
     }
 
     pub async fn send(&mut self, request: &Req) -> Result<Res> {
-        self.send_with_timeout(request, Duration::from_secs(90))
+        self.send_with_timeout(request, DEFAULT_REQUEST_TIMEOUT)
             .await
     }
 
@@ -152,8 +266,10 @@ where
         match tokio::time::timeout(timeout, self.send_without_timeout(request)).await {
             Ok(res) => res,
             Err(_) => {
-                self.stream.shutdown().await?;
-                Err(Error::RequestTimeout)
+                // the timeout is what the caller needs to know about,
+                // not whether the stream could be shut down cleanly
+                self.stream.shutdown().await.ok();
+                Err(Error::Timeout)
             }
         }
     }
@@ -181,8 +297,15 @@ struct Header {
     body_size: usize,
 }
 
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct Handshake {
+    version: u64,
+}
+
 pub struct Server<Req, Res> {
-    listener: TcpListener,
+    listener: Listener,
+    version: u64,
     marker: PhantomData<(Req, Res)>,
 }
 
@@ -191,23 +314,52 @@ where
     Req: bincode::Decode,
 {
     pub async fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
-        let listener = TcpListener::bind(addr).await?;
-        Ok(Server {
+        Self::bind_with_version(addr, default_protocol_version::<Req, Res>()).await
+    }
+
+    pub async fn bind_with_version(addr: impl ToSocketAddrs, version: u64) -> Result<Self> {
+        Ok(Self::new(Listener::bind_tcp(addr).await?, version))
+    }
+
+    pub fn bind_unix(path: impl AsRef<Path>) -> Result<Self> {
+        Self::bind_unix_with_version(path, default_protocol_version::<Req, Res>())
+    }
+
+    pub fn bind_unix_with_version(path: impl AsRef<Path>, version: u64) -> Result<Self> {
+        Ok(Self::new(Listener::bind_unix(path)?, version))
+    }
+
+    pub async fn bind_address(addr: &Address, version: u64) -> Result<Self> {
+        Ok(Self::new(Listener::bind(addr).await?, version))
+    }
+
+    fn new(listener: Listener, version: u64) -> Self {
+        Server {
             listener,
+            version,
             marker: PhantomData,
-        })
+        }
+    }
+
+    pub fn local_addr(&self) -> Result<Address> {
+        Ok(self.listener.local_addr()?)
     }
 
+    /// Accept a connection. The handshake is only done by the first [`ServerConnection::request`],
+    /// so a client that stalls the handshake doesn't hold up accepting other connections.
     pub async fn accept(&self) -> Result<ServerConnection<Req, Res>> {
         let (stream, client) = self.listener.accept().await?;
         tracing::debug!(?client, "accepted connection");
 
-        Ok(ServerConnection::new(stream))
+        Ok(ServerConnection::new(stream, client, self.version))
     }
 }
 
 pub struct ServerConnection<Req, Res> {
-    stream: TcpStream,
+    stream: Stream,
+    client: String,
+    /// The protocol version the client has to agree on before its first request.
+    pending_handshake: Option<u64>,
     marker: PhantomData<(Req, Res)>,
 }
 
@@ -215,14 +367,36 @@ impl<Req, Res> ServerConnection<Req, Res>
 where
     Req: bincode::Decode,
 {
-    fn new(stream: TcpStream) -> Self {
+    fn new(stream: Stream, client: String, version: u64) -> Self {
         ServerConnection {
             stream,
+            client,
+            pending_handshake: Some(version),
             marker: PhantomData,
         }
     }
 
+    async fn ensure_handshake(&mut self) -> Result<()> {
+        let Some(version) = self.pending_handshake else {
+            return Ok(());
+        };
+
+        let res = tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake(&mut self.stream, version))
+            .await
+            .unwrap_or(Err(Error::ConnectionTimeout));
+
+        if let Err(err) = &res {
+            tracing::warn!(client = ?self.client, "rejected connection: {}", err);
+        } else {
+            self.pending_handshake = None;
+        }
+
+        res
+    }
+
     pub async fn request(&mut self) -> Result<Request<'_, Req, Res>> {
+        self.ensure_handshake().await?;
+
         let mut header_buf = vec![0; std::mem::size_of::<Header>()];
         self.stream.read_exact(&mut header_buf).await?;
         let header: Header = *bytemuck::from_bytes(&header_buf);
@@ -274,11 +448,11 @@ where
 
     pub async fn respond(self, response: Res) -> Result<()> {
         tokio::time::timeout(
-            Duration::from_secs(90),
+            DEFAULT_REQUEST_TIMEOUT,
             self.respond_without_timeout(response),
         )
         .await
-        .map_err(|_| Error::RequestTimeout)?
+        .map_err(|_| Error::Timeout)?
     }
 
     pub fn body(&self) -> &Req {
@@ -318,10 +492,13 @@ mod tests {
             .block_on(async move {
                 let addr = free_socket_addr();
                 let server = Server::bind(addr).await.unwrap();
-                let connection = Connection::create(addr).await.unwrap();
 
                 let svr_task = tokio::spawn(async move { svr_fn(server).await });
-                let con_task = tokio::spawn(async move { con_fn(connection).await });
+                let con_task = tokio::spawn(async move {
+                    // the handshake needs the server to accept the connection
+                    let connection = Connection::create(addr).await.unwrap();
+                    con_fn(connection).await
+                });
 
                 let (svr_res, con_res) = tokio::join!(svr_task, con_task);
                 (
@@ -351,6 +528,113 @@ mod tests {
         }
     }
 
+    #[test]
+    fn version_mismatch() {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async move {
+                let addr = free_socket_addr();
+                let server: Server<Message, Message> =
+                    Server::bind_with_version(addr, 1).await.unwrap();
+
+                let svr_task = tokio::spawn(async move {
+                    // the connection is accepted, but rejected before its first request
+                    let mut conn = server.accept().await?;
+                    conn.request().await?;
+
+                    Ok::<_, Error>(())
+                });
+
+                let res = Connection::<Message, Message>::create_with_version(
+                    addr,
+                    Duration::from_secs(5),
+                    2,
+                )
+                .await;
+
+                assert!(matches!(
+                    res,
+                    Err(Error::VersionMismatch {
+                        local: 2,
+                        remote: 1
+                    })
+                ));
+
+                let svr_res = svr_task.await.unwrap();
+                assert!(matches!(
+                    svr_res,
+                    Err(Error::VersionMismatch {
+                        local: 1,
+                        remote: 2
+                    })
+                ));
+                assert_eq!(
+                    svr_res.err().unwrap().to_string(),
+                    "Incompatible peer: local protocol version is 1 but the peer uses 2"
+                );
+            });
+    }
+
+    #[test]
+    fn stalled_handshake_does_not_block_accept() {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async move {
+                let addr = free_socket_addr();
+                let server: Server<Message, Message> =
+                    Server::bind_with_version(addr, 1).await.unwrap();
+
+                tokio::spawn(async move {
+                    loop {
+                        let mut conn = server.accept().await.unwrap();
+
+                        tokio::spawn(async move {
+                            while let Ok(req) = conn.request().await {
+                                let res = req.body().clone();
+                                req.respond(res).await.unwrap();
+                            }
+                        });
+                    }
+                });
+
+                // never sends its side of the handshake
+                let _stalled = tokio::net::TcpStream::connect(addr).await.unwrap();
+
+                let mut conn = Connection::<Message, Message>::create_with_version(
+                    addr,
+                    Duration::from_secs(5),
+                    1,
+                )
+                .await
+                .unwrap();
+
+                let msg = Message {
+                    text: "hello".to_string(),
+                    other: HashMap::new(),
+                };
+                let res = conn
+                    .send_with_timeout(&msg, Duration::from_secs(5))
+                    .await
+                    .unwrap();
+
+                assert_eq!(res, msg);
+            });
+    }
+
+    #[test]
+    fn protocol_versions() {
+        assert_eq!(protocol_version("a"), protocol_version("a"));
+        assert_ne!(protocol_version("a"), protocol_version("b"));
+        assert_ne!(
+            default_protocol_version::<Message, Message>(),
+            default_protocol_version::<Message, String>()
+        );
+    }
+
     proptest! {
         #[test]
         fn basic_arb(a1: Message, b1: Message) {
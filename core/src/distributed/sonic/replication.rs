@@ -14,11 +14,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use futures::future::join_all;
+use futures::{future::join_all, Stream};
 use rand::seq::IteratorRandom;
 
 use super::Result;
-use crate::distributed::{cluster::Cluster, retry_strategy::ExponentialBackoff, sonic};
+use crate::distributed::{
+    cluster::Cluster,
+    retry_strategy::ExponentialBackoff,
+    sonic,
+    streaming_response::{self, TaskResponse},
+};
 use std::{net::SocketAddr, ops::DerefMut, sync::Arc, time::Duration};
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
@@ -40,7 +45,11 @@ where
     S: sonic::service::Service,
 {
     fn clone(&self) -> Self {
-        Self::create(self.addr)
+        Self {
+            addr: self.addr,
+            pool: sonic::ConnectionPool::new(self.pool.addr().clone()).unwrap(),
+            _phantom: std::marker::PhantomData,
+        }
     }
 }
 
@@ -60,6 +69,13 @@ where
         }
     }
 
+    /// Connect over the unix domain socket at `path` instead of TCP,
+    /// for when the service runs on the same host. The client is still identified by its `addr`.
+    pub fn with_unix_socket(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.pool = sonic::ConnectionPool::new(sonic::Address::Unix(path.into())).unwrap();
+        self
+    }
+
     pub fn addr(&self) -> SocketAddr {
         self.addr
     }
@@ -154,6 +170,7 @@ pub trait ReplicaSelector<S: sonic::service::Service> {
     fn select<'a>(&self, replicas: &'a [RemoteClient<S>]) -> Vec<&'a RemoteClient<S>>;
 }
 
+#[derive(Clone, Copy)]
 pub struct RandomReplicaSelector;
 
 impl<S> ReplicaSelector<S> for RandomReplicaSelector
@@ -440,6 +457,57 @@ where
             .await
     }
 
+    /// Send the request to the selected shards and yield the response of
+    /// each shard as soon as it arrives, so a slow shard doesn't hold back the others.
+    /// Shards that fail to respond within `timeout` are skipped.
+    pub fn stream<Req, SSel, RSel>(
+        self: &Arc<Self>,
+        req: Req,
+        shard_selector: &SSel,
+        replica_selector: RSel,
+        timeout: Duration,
+    ) -> impl Stream<Item = (Id, Vec<(SocketAddr, Req::Response)>)>
+    where
+        Self: Send + Sync + 'static,
+        Id: Send + Unpin + 'static,
+        Req: sonic::service::Wrapper<S> + Clone + Send + 'static,
+        Req::Response: Send + Unpin + 'static,
+        SSel: ShardSelector<S, Id>,
+        RSel: ReplicaSelector<S> + Clone + Send + Sync + 'static,
+    {
+        let responses: Vec<_> = shard_selector
+            .select(&self.shards)
+            .into_iter()
+            .map(|shard| {
+                let client = Arc::clone(self);
+                let id = shard.id.clone();
+                let req = req.clone();
+                let replica_selector = replica_selector.clone();
+
+                TaskResponse::spawn(async move {
+                    let shard = client
+                        .shards
+                        .iter()
+                        .find(|shard| shard.id == id)
+                        .expect("shard was selected from this client");
+
+                    match client
+                        .send_single(req, shard, &replica_selector, timeout)
+                        .await
+                    {
+                        Ok(res) => Ok(vec![res]),
+                        Err(e) => {
+                            tracing::error!("Failed to send request: {:?}", e);
+                            Err(e.into())
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        streaming_response::merge(responses)
+    }
+
     async fn batch_send_single<Req, Sel>(
         &self,
         reqs: &[Req],
@@ -532,3 +600,164 @@ where
         self.client.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio::sync::Notify;
+
+    use super::*;
+    use crate::distributed::sonic::service::{sonic_service, Message};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct ShardNo(u64);
+
+    impl ShardIdentifier for ShardNo {}
+
+    /// A shard that only responds once it is released, if it has a release.
+    struct MockShard {
+        id: u64,
+        release: Option<Arc<Notify>>,
+    }
+
+    sonic_service!(MockShard, version = 1, [Search]);
+
+    #[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+    struct Search;
+
+    impl Message<MockShard> for Search {
+        type Response = u64;
+
+        async fn handle(self, server: &MockShard) -> Self::Response {
+            if let Some(release) = &server.release {
+                release.notified().await;
+            }
+
+            server.id
+        }
+    }
+
+    async fn client(shards: Vec<MockShard>) -> Arc<ShardedClient<MockShard, ShardNo>> {
+        let mut clients = Vec::new();
+
+        for shard in shards {
+            let id = ShardNo(shard.id);
+            let server = shard.bind("127.0.0.1:0").await.unwrap();
+            let addr = match server.local_addr().unwrap() {
+                sonic::Address::Tcp(addr) => addr,
+                sonic::Address::Unix(_) => unreachable!(),
+            };
+
+            tokio::spawn(async move {
+                loop {
+                    let _ = server.accept().await;
+                }
+            });
+
+            clients.push(Shard::new(
+                id,
+                ReplicatedClient::new(vec![RemoteClient::new(addr)]),
+            ));
+        }
+
+        Arc::new(ShardedClient::new(clients))
+    }
+
+    #[tokio::test]
+    async fn unix_socket_client() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = dir.as_ref().join("shard.sock");
+
+        let server = sonic::service::Server::bind_unix(
+            MockShard {
+                id: 3,
+                release: None,
+            },
+            &path,
+        )
+        .unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let _ = server.accept().await;
+            }
+        });
+
+        // nothing listens on the tcp address, so the request has to go over the socket
+        let client =
+            RemoteClient::<MockShard>::new("127.0.0.1:1".parse().unwrap()).with_unix_socket(&path);
+
+        assert_eq!(client.send(Search).await.unwrap(), 3);
+        assert_eq!(client.clone().send(Search).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn stream_yields_shards_as_they_respond() {
+        let release = Arc::new(Notify::new());
+        let client = client(vec![
+            MockShard {
+                id: 0,
+                release: Some(Arc::clone(&release)),
+            },
+            MockShard {
+                id: 1,
+                release: None,
+            },
+        ])
+        .await;
+
+        let mut stream = Box::pin(client.stream(
+            Search,
+            &AllShardsSelector,
+            RandomReplicaSelector,
+            DEFAULT_TIMEOUT,
+        ));
+
+        // the delayed shard is only released after the other shard has been yielded
+        let (id, res) = stream.next().await.unwrap();
+        assert_eq!(id, ShardNo(1));
+        assert_eq!(
+            res.into_iter().map(|(_, res)| res).collect::<Vec<_>>(),
+            vec![1]
+        );
+
+        release.notify_one();
+
+        let (id, res) = stream.next().await.unwrap();
+        assert_eq!(id, ShardNo(0));
+        assert_eq!(
+            res.into_iter().map(|(_, res)| res).collect::<Vec<_>>(),
+            vec![0]
+        );
+
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn stream_skips_shards_that_time_out() {
+        let client = client(vec![
+            MockShard {
+                id: 0,
+                release: Some(Arc::new(Notify::new())),
+            },
+            MockShard {
+                id: 1,
+                release: None,
+            },
+        ])
+        .await;
+
+        let ids: Vec<_> = client
+            .stream(
+                Search,
+                &AllShardsSelector,
+                RandomReplicaSelector,
+                Duration::from_millis(500),
+            )
+            .map(|(id, _)| id)
+            .collect()
+            .await;
+
+        assert_eq!(ids, vec![ShardNo(1)]);
+    }
+}
@@ -14,18 +14,21 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{sync::Arc, time::Duration};
+use std::{path::Path, sync::Arc, time::Duration};
 
 use tokio::net::ToSocketAddrs;
 
 use crate::OneOrMany;
 
-use super::Result;
+use super::{Address, Result, DEFAULT_REQUEST_TIMEOUT};
 
 pub trait Service: Sized + Send + Sync + 'static {
     type Request: bincode::Encode + bincode::Decode + Send + Sync;
     type Response: bincode::Encode + bincode::Decode + Send + Sync;
 
+    /// Peers are only allowed to connect if they agree on the version.
+    const VERSION: u64;
+
     fn handle(
         req: Self::Request,
         server: &Self,
@@ -49,10 +52,38 @@ pub struct Server<S: Service> {
 impl<S: Service> Server<S> {
     pub async fn bind(service: S, addr: impl ToSocketAddrs) -> Result<Self> {
         Ok(Server {
-            inner: super::Server::bind(addr).await?,
+            inner: super::Server::bind_with_version(addr, S::VERSION).await?,
             service: Arc::new(service),
         })
     }
+
+    pub fn bind_unix(service: S, path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Server {
+            inner: super::Server::bind_unix_with_version(path, S::VERSION)?,
+            service: Arc::new(service),
+        })
+    }
+
+    pub async fn bind_address(service: S, addr: &Address) -> Result<Self> {
+        Ok(Server {
+            inner: super::Server::bind_address(addr, S::VERSION).await?,
+            service: Arc::new(service),
+        })
+    }
+
+    /// Listen on another address as well. Requests from both
+    /// servers are handled by the same service.
+    pub async fn bind_additional(&self, addr: &Address) -> Result<Self> {
+        Ok(Server {
+            inner: super::Server::bind_address(addr, S::VERSION).await?,
+            service: Arc::clone(&self.service),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<Address> {
+        self.inner.local_addr()
+    }
+
     pub async fn accept(&self) -> Result<()> {
         let mut conn = self.inner.accept().await?;
 
@@ -86,17 +117,18 @@ impl<S: Service> Server<S> {
     }
 }
 
+/// A connection to a service. Dropping the future of an in-flight request
+/// cancels it, after which the connection is left awaiting a response
+/// and will not be reused by a [`super::ConnectionPool`].
 pub struct Connection<S: Service> {
     await_res: bool,
+    default_timeout: Duration,
     inner: super::Connection<OneOrMany<S::Request>, OneOrMany<S::Response>>,
 }
 
 impl<S: Service> Connection<S> {
     pub async fn create(server: impl ToSocketAddrs) -> Result<Connection<S>> {
-        Ok(Connection {
-            await_res: false,
-            inner: super::Connection::create(server).await?,
-        })
+        Self::create_with_timeout(server, Duration::from_secs(30)).await
     }
 
     pub async fn create_with_timeout(
@@ -105,7 +137,29 @@ impl<S: Service> Connection<S> {
     ) -> Result<Connection<S>> {
         Ok(Connection {
             await_res: false,
-            inner: super::Connection::create_with_timeout(server, timeout).await?,
+            default_timeout: DEFAULT_REQUEST_TIMEOUT,
+            inner: super::Connection::create_with_version(server, timeout, S::VERSION).await?,
+        })
+    }
+
+    pub async fn create_unix(path: impl AsRef<Path>) -> Result<Connection<S>> {
+        Ok(Connection {
+            await_res: false,
+            default_timeout: DEFAULT_REQUEST_TIMEOUT,
+            inner: super::Connection::create_unix_with_version(
+                path,
+                Duration::from_secs(30),
+                S::VERSION,
+            )
+            .await?,
+        })
+    }
+
+    pub async fn create_with_address(addr: &Address, timeout: Duration) -> Result<Connection<S>> {
+        Ok(Connection {
+            await_res: false,
+            default_timeout: DEFAULT_REQUEST_TIMEOUT,
+            inner: super::Connection::create_with_address(addr, timeout, S::VERSION).await?,
         })
     }
 
@@ -116,10 +170,18 @@ impl<S: Service> Connection<S> {
     ) -> Result<Connection<S>> {
         Ok(Connection {
             await_res: false,
-            inner: super::Connection::create_with_timeout_retry(server, timeout, retry).await?,
+            default_timeout: DEFAULT_REQUEST_TIMEOUT,
+            inner: super::Connection::create_with_version_retry(server, timeout, retry, S::VERSION)
+                .await?,
         })
     }
 
+    /// Use `timeout` for all requests sent with [`Connection::send`].
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = timeout;
+        self
+    }
+
     pub async fn send_without_timeout<R: Wrapper<S>>(&mut self, request: R) -> Result<R::Response> {
         self.await_res = true;
         let res = Ok(R::unwrap_response(
@@ -134,20 +196,12 @@ impl<S: Service> Connection<S> {
         res
     }
 
+    /// Send a request using the default timeout of the connection.
     pub async fn send<R: Wrapper<S>>(&mut self, request: R) -> Result<R::Response> {
-        self.await_res = true;
-        let res = Ok(R::unwrap_response(
-            self.inner
-                .send(&OneOrMany::One(R::wrap_request(request)))
-                .await?
-                .one()
-                .expect("response is missing"),
-        )
-        .unwrap());
-        self.await_res = false;
-        res
+        self.send_with_timeout(request, self.default_timeout).await
     }
 
+    /// Returns [`super::Error::Timeout`] if no response is received within `timeout`.
     pub async fn send_with_timeout<R: Wrapper<S>>(
         &mut self,
         request: R,
@@ -201,8 +255,12 @@ impl<S: Service> Connection<S> {
     }
 }
 
+/// Implement [`Service`] for `$service` with a request for each of the messages.
+///
+/// Peers only agree on the names of the messages and the `version` before decoding them,
+/// so the version has to be bumped whenever a message or its response changes.
 macro_rules! sonic_service {
-    ($service:ident, [$($req:ident),*$(,)?]) => {
+    ($service:ident, version = $version:literal, [$($req:ident),*$(,)?]) => {
         mod service_impl__ {
             #![allow(dead_code)]
 
@@ -237,6 +295,13 @@ macro_rules! sonic_service {
                 type Request = Request;
                 type Response = Response;
 
+                const VERSION: u64 = sonic::protocol_version(concat!(
+                    stringify!($service),
+                    $("::", stringify!($req),)*
+                    "@",
+                    stringify!($version),
+                ));
+
                 // NOTE: This is a workaround for the fact that async functions
                 // don't have a Send bound by default, and there's currently no
                 // way of specifying that.
@@ -255,6 +320,9 @@ macro_rules! sonic_service {
                 pub async fn bind(self, addr: impl ::tokio::net::ToSocketAddrs) -> sonic::Result<sonic::service::Server<Self>> {
                     sonic::service::Server::bind(self, addr).await
                 }
+                pub async fn bind_address(self, addr: &sonic::Address) -> sonic::Result<sonic::service::Server<Self>> {
+                    sonic::service::Server::bind_address(self, addr).await
+                }
             }
         }
     };
@@ -266,21 +334,21 @@ pub(crate) use sonic_service;
 mod tests {
     use proptest::prelude::*;
 
-    use std::{marker::PhantomData, net::SocketAddr, sync::atomic::AtomicI32};
+    use std::{marker::PhantomData, net::SocketAddr, sync::atomic::AtomicI32, time::Duration};
 
-    use crate::distributed::sonic::{service, ConnectionPool};
+    use crate::distributed::sonic::{self, service, Address, ConnectionPool};
 
     use super::{Server, Service, Wrapper};
     use futures::Future;
 
     struct ConnectionBuilder<S> {
-        addr: SocketAddr,
+        addr: Address,
         marker: PhantomData<S>,
     }
 
     impl<S: Service> ConnectionBuilder<S> {
         async fn conn(&self) -> Result<super::Connection<S>, anyhow::Error> {
-            Ok(super::Connection::create(self.addr).await?)
+            Ok(super::Connection::create_with_address(&self.addr, Duration::from_secs(30)).await?)
         }
 
         async fn send<R: Wrapper<S>>(&self, req: R) -> Result<R::Response, anyhow::Error> {
@@ -288,7 +356,10 @@ mod tests {
         }
 
         fn addr(&self) -> SocketAddr {
-            self.addr
+            match &self.addr {
+                Address::Tcp(addr) => *addr,
+                Address::Unix(path) => panic!("{} is not a tcp address", path.display()),
+            }
         }
     }
 
@@ -300,6 +371,22 @@ mod tests {
         service: S,
         con_fn: impl FnOnce(ConnectionBuilder<S>) -> Y + Send + 'static,
     ) -> Result<B, TestCaseError>
+    where
+        S::Request: Send + Sync + 'static,
+        S::Response: Send + Sync + 'static,
+    {
+        fixture_at(service, "127.0.0.1:0".parse().unwrap(), con_fn)
+    }
+
+    fn fixture_at<
+        S: Service + Send + Sync + 'static,
+        B: Send + Sync + 'static,
+        Y: Future<Output = Result<B, TestCaseError>> + Send,
+    >(
+        service: S,
+        addr: Address,
+        con_fn: impl FnOnce(ConnectionBuilder<S>) -> Y + Send + 'static,
+    ) -> Result<B, TestCaseError>
     where
         S::Request: Send + Sync + 'static,
         S::Response: Send + Sync + 'static,
@@ -309,13 +396,14 @@ mod tests {
             .build()
             .unwrap()
             .block_on(async move {
-                let server = Server::bind(service, ("127.0.0.1", 0)).await.unwrap();
-                let addr = server.inner.listener.local_addr().unwrap();
+                let server = Server::bind_address(service, &addr).await.unwrap();
+                let addr = server.local_addr().unwrap();
 
                 let svr_task: tokio::task::JoinHandle<Result<(), anyhow::Error>> =
                     tokio::spawn(async move {
                         loop {
-                            server.accept().await?;
+                            // incompatible peers are rejected without stopping the server
+                            let _ = server.accept().await;
                         }
                     });
                 let con_res = tokio::spawn(async move {
@@ -342,7 +430,7 @@ mod tests {
             pub counter: AtomicI32,
         }
 
-        sonic_service!(CounterService, [Change, Reset]);
+        sonic_service!(CounterService, version = 1, [Change, Reset, Delayed]);
 
         #[derive(
             Debug, Clone, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode,
@@ -376,6 +464,23 @@ mod tests {
             }
         }
 
+        /// Responds with the counter after sleeping for `millis`.
+        #[derive(
+            Debug, Clone, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode,
+        )]
+        pub struct Delayed {
+            pub millis: u64,
+        }
+
+        impl Message<CounterService> for Delayed {
+            type Response = i32;
+
+            async fn handle(self, server: &CounterService) -> Self::Response {
+                tokio::time::sleep(std::time::Duration::from_millis(self.millis)).await;
+                server.counter.load(std::sync::atomic::Ordering::SeqCst)
+            }
+        }
+
         impl Message<CounterService> for Reset {
             type Response = ();
 
@@ -385,6 +490,27 @@ mod tests {
         }
     }
 
+    mod counter_service_v2 {
+        use super::super::Message;
+
+        pub struct CounterService;
+
+        sonic_service!(CounterService, version = 2, [Change]);
+
+        #[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+        pub struct Change {
+            pub amount: i32,
+        }
+
+        impl Message<CounterService> for Change {
+            type Response = i32;
+
+            async fn handle(self, _: &CounterService) -> Self::Response {
+                self.amount
+            }
+        }
+    }
+
     use counter_service::*;
 
     #[test]
@@ -457,6 +583,100 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn request_timeout() {
+        fixture(
+            CounterService {
+                counter: AtomicI32::new(0),
+            },
+            |b| async move {
+                let mut conn = b
+                    .conn()
+                    .await
+                    .unwrap()
+                    .with_default_timeout(Duration::from_millis(50));
+
+                let res = conn.send(Delayed { millis: 1_000 }).await;
+                assert!(matches!(res, Err(sonic::Error::Timeout)));
+                // the stream was shut down, so the pool must not reuse it
+                assert!(conn.awaiting_response());
+
+                let mut conn = b.conn().await.unwrap();
+                let res = conn
+                    .send_with_timeout(Delayed { millis: 1_000 }, Duration::from_millis(50))
+                    .await;
+                assert!(matches!(res, Err(sonic::Error::Timeout)));
+
+                // an explicit timeout overrides the default
+                let mut conn = b
+                    .conn()
+                    .await
+                    .unwrap()
+                    .with_default_timeout(Duration::from_millis(50));
+                let val = conn
+                    .send_with_timeout(Delayed { millis: 100 }, Duration::from_secs(30))
+                    .await
+                    .map_err(|e| TestCaseError::Fail(e.to_string().into()))?;
+                assert_eq!(val, 0);
+                assert!(!conn.awaiting_response());
+
+                // other requests are unaffected by a slow one
+                let val = b
+                    .send(Change { amount: 15 })
+                    .await
+                    .map_err(|e| TestCaseError::Fail(e.to_string().into()))?;
+                assert_eq!(val, 15);
+
+                Ok(())
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn unix_socket_service() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = dir.as_ref().join("counter.sock");
+
+        fixture_at(
+            CounterService {
+                counter: AtomicI32::new(0),
+            },
+            Address::Unix(path.clone()),
+            |b| async move {
+                let mut conn = super::Connection::<CounterService>::create_unix(&path)
+                    .await
+                    .map_err(|e| TestCaseError::Fail(e.to_string().into()))?;
+
+                let val = conn
+                    .send(Change { amount: 15 })
+                    .await
+                    .map_err(|e| TestCaseError::Fail(e.to_string().into()))?;
+                assert_eq!(val, 15);
+
+                let val = conn
+                    .send(Change { amount: 15 })
+                    .await
+                    .map_err(|e| TestCaseError::Fail(e.to_string().into()))?;
+                assert_eq!(val, 30);
+
+                conn.send(Reset)
+                    .await
+                    .map_err(|e| TestCaseError::Fail(e.to_string().into()))?;
+
+                // send in a new connection
+                let val = b
+                    .send(Change { amount: 15 })
+                    .await
+                    .map_err(|e| TestCaseError::Fail(e.to_string().into()))?;
+                assert_eq!(val, 15);
+
+                Ok(())
+            },
+        )
+        .unwrap();
+    }
+
     #[test]
     fn test_connection_pool() {
         fixture(
@@ -491,6 +711,76 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn test_connection_pool_warmup() {
+        fixture(
+            CounterService {
+                counter: AtomicI32::new(0),
+            },
+            |b| async move {
+                let pool: ConnectionPool<service::Connection<CounterService>> =
+                    ConnectionPool::new(b.addr()).unwrap();
+
+                assert_eq!(pool.status().size, 0);
+                assert_eq!(pool.status().available, 0);
+
+                pool.warmup(3)
+                    .await
+                    .map_err(|e| TestCaseError::Fail(e.to_string().into()))?;
+
+                assert_eq!(pool.status().size, 3);
+                assert_eq!(pool.status().available, 3);
+
+                // requests use the warm connections
+                let val = pool
+                    .get()
+                    .await
+                    .unwrap()
+                    .send(Change { amount: 15 })
+                    .await
+                    .map_err(|e| TestCaseError::Fail(e.to_string().into()))?;
+                assert_eq!(val, 15);
+                assert_eq!(pool.status().size, 3);
+
+                Ok(())
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn incompatible_service_version() {
+        assert_ne!(
+            <CounterService as Service>::VERSION,
+            <counter_service_v2::CounterService as Service>::VERSION
+        );
+
+        fixture(
+            CounterService {
+                counter: AtomicI32::new(0),
+            },
+            |b| async move {
+                let res: Result<super::Connection<counter_service_v2::CounterService>, _> =
+                    super::Connection::create(b.addr()).await;
+
+                assert!(matches!(
+                    res,
+                    Err(crate::distributed::sonic::Error::VersionMismatch { .. })
+                ));
+
+                // compatible peers can still connect
+                let val = b
+                    .send(Change { amount: 15 })
+                    .await
+                    .map_err(|e| TestCaseError::Fail(e.to_string().into()))?;
+                assert_eq!(val, 15);
+
+                Ok(())
+            },
+        )
+        .unwrap();
+    }
+
     proptest! {
         #[test]
         fn ref_serialization(a: Change) {
@@ -0,0 +1,221 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+//! The byte streams sonic messages are sent over. TCP is used between hosts,
+//! while services running on the same host can skip the network stack
+//! by using a unix domain socket.
+
+use std::{
+    fmt::Display,
+    io,
+    net::SocketAddr,
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+    pin::Pin,
+    str::FromStr,
+    task::{Context, Poll},
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpListener, TcpStream, ToSocketAddrs, UnixListener, UnixStream},
+};
+
+const UNIX_PREFIX: &str = "unix:";
+
+/// Where a sonic server listens. Parsed from `host:port` for TCP
+/// and `unix:<path>` for a unix domain socket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Address {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl From<SocketAddr> for Address {
+    fn from(addr: SocketAddr) -> Self {
+        Address::Tcp(addr)
+    }
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Address::Tcp(addr) => write!(f, "{addr}"),
+            Address::Unix(path) => write!(f, "{UNIX_PREFIX}{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = std::net::AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(UNIX_PREFIX) {
+            Some(path) => Ok(Address::Unix(PathBuf::from(path))),
+            None => Ok(Address::Tcp(s.parse()?)),
+        }
+    }
+}
+
+impl From<Address> for String {
+    fn from(addr: Address) -> Self {
+        addr.to_string()
+    }
+}
+
+impl TryFrom<String> for Address {
+    type Error = std::net::AddrParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+pub(super) enum Stream {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl Stream {
+    pub async fn connect_tcp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+
+        Ok(Stream::Tcp(stream))
+    }
+
+    pub async fn connect_unix(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Stream::Unix(UnixStream::connect(path).await?))
+    }
+
+    pub async fn connect(addr: &Address) -> io::Result<Self> {
+        match addr {
+            Address::Tcp(addr) => Self::connect_tcp(addr).await,
+            Address::Unix(path) => Self::connect_unix(path).await,
+        }
+    }
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            Stream::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Stream::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            Stream::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            Stream::Unix(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            Stream::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+pub(super) enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+impl Listener {
+    pub async fn bind_tcp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Listener::Tcp(TcpListener::bind(addr).await?))
+    }
+
+    /// A socket file left behind by a previous server on the same path
+    /// is removed before binding. Any other kind of file is left untouched
+    /// and makes the bind fail.
+    pub fn bind_unix(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if metadata.file_type().is_socket() {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        Ok(Listener::Unix(UnixListener::bind(path)?))
+    }
+
+    pub async fn bind(addr: &Address) -> io::Result<Self> {
+        match addr {
+            Address::Tcp(addr) => Self::bind_tcp(addr).await,
+            Address::Unix(path) => Self::bind_unix(path),
+        }
+    }
+
+    /// Accept a connection. The peer is described for logging, as unix
+    /// domain socket clients are usually unnamed.
+    pub async fn accept(&self) -> io::Result<(Stream, String)> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, peer) = listener.accept().await?;
+                Ok((Stream::Tcp(stream), peer.to_string()))
+            }
+            Listener::Unix(listener) => {
+                let (stream, peer) = listener.accept().await?;
+                Ok((Stream::Unix(stream), format!("{peer:?}")))
+            }
+        }
+    }
+
+    pub fn local_addr(&self) -> io::Result<Address> {
+        match self {
+            Listener::Tcp(listener) => Ok(Address::Tcp(listener.local_addr()?)),
+            Listener::Unix(listener) => listener
+                .local_addr()?
+                .as_pathname()
+                .map(|path| Address::Unix(path.to_path_buf()))
+                .ok_or_else(|| io::Error::other("unnamed unix socket")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_address() {
+        assert_eq!(
+            "127.0.0.1:8080".parse::<Address>().unwrap(),
+            Address::Tcp("127.0.0.1:8080".parse().unwrap())
+        );
+        assert_eq!(
+            "unix:/tmp/neos.sock".parse::<Address>().unwrap(),
+            Address::Unix(PathBuf::from("/tmp/neos.sock"))
+        );
+        assert!("localhost".parse::<Address>().is_err());
+
+        for addr in ["127.0.0.1:8080", "unix:/tmp/neos.sock"] {
+            assert_eq!(addr.parse::<Address>().unwrap().to_string(), addr);
+        }
+    }
+}
@@ -3,7 +3,8 @@
 //
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
-use futures::{pin_mut, Stream};
+use futures::{pin_mut, stream::select_all, Stream};
+use tokio::task::JoinHandle;
 
 use crate::Result;
 use std::{
@@ -94,6 +95,60 @@ where
     }
 }
 
+/// A response that is produced as a single batch by a spawned task,
+/// e.g. the reply of a single shard. The task is aborted if the response is dropped.
+pub struct TaskResponse<T> {
+    handle: Option<JoinHandle<Result<Vec<T>>>>,
+}
+
+impl<T> TaskResponse<T>
+where
+    T: Send + 'static,
+{
+    pub fn spawn(fut: impl Future<Output = Result<Vec<T>>> + Send + 'static) -> Self {
+        Self {
+            handle: Some(tokio::spawn(fut)),
+        }
+    }
+}
+
+impl<T> StreamingResponse for TaskResponse<T>
+where
+    T: Unpin,
+{
+    type Item = T;
+
+    async fn next_batch(&mut self) -> Result<Vec<Self::Item>> {
+        // awaiting the handle by reference keeps the task running
+        // if the stream drops this future before it completes
+        let batch = match self.handle.as_mut() {
+            Some(handle) => handle.await??,
+            None => return Ok(Vec::new()),
+        };
+
+        self.handle = None;
+
+        Ok(batch)
+    }
+}
+
+impl<T> Drop for TaskResponse<T> {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+/// Merge the responses into a single stream that yields items
+/// from each response as soon as they are available.
+pub fn merge<T>(responses: impl IntoIterator<Item = T>) -> impl Stream<Item = T::Item>
+where
+    T: StreamingResponse,
+{
+    select_all(responses.into_iter().map(StreamingResponseStream::new))
+}
+
 #[cfg(test)]
 mod tests {
     use futures::StreamExt;
@@ -147,4 +202,36 @@ mod tests {
 
         assert_eq!(stream.next().await, None);
     }
+
+    #[tokio::test]
+    async fn test_merge_yields_as_shards_respond() {
+        let (release_slow, slow_released) = tokio::sync::oneshot::channel::<()>();
+
+        let fast = TaskResponse::spawn(async { Ok(vec!["fast".to_string()]) });
+        let slow = TaskResponse::spawn(async move {
+            slow_released.await?;
+            Ok(vec!["slow 1".to_string(), "slow 2".to_string()])
+        });
+
+        let mut stream = merge(vec![slow, fast]);
+
+        // the slow shard only responds after we have seen the result of the fast one
+        assert_eq!(stream.next().await, Some("fast".to_string()));
+        release_slow.send(()).unwrap();
+
+        let mut rest = vec![stream.next().await.unwrap(), stream.next().await.unwrap()];
+        rest.sort();
+        assert_eq!(rest, vec!["slow 1".to_string(), "slow 2".to_string()]);
+        assert_eq!(stream.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_merge_skips_failed_shards() {
+        let ok = TaskResponse::spawn(async { Ok(vec![1]) });
+        let failed = TaskResponse::spawn(async { Err(anyhow::anyhow!("shard is down")) });
+
+        let items: Vec<_> = merge(vec![failed, ok]).collect().await;
+
+        assert_eq!(items, vec![1]);
+    }
 }
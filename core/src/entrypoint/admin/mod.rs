@@ -74,21 +74,42 @@ pub fn init(host: SocketAddr) -> Result<()> {
     Ok(())
 }
 
-pub async fn status() -> Result<()> {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable text or csv.
+    #[default]
+    Text,
+    /// The response serialized as a single line of json.
+    Json,
+}
+
+impl OutputFormat {
+    fn print<T: serde::Serialize>(self, value: &T, text: impl FnOnce(&T)) -> Result<()> {
+        match self {
+            OutputFormat::Text => text(value),
+            OutputFormat::Json => println!("{}", serde_json::to_string(value)?),
+        }
+
+        Ok(())
+    }
+}
+
+pub async fn status(format: OutputFormat) -> Result<()> {
     let config = Config::load()?;
     let mut conn = sonic::service::Connection::create(config.host).await?;
 
     let status = conn.send_without_timeout(api::ClusterStatus).await?;
 
-    println!("Members:");
-    for member in status.members {
-        println!("  - {}: {}", member.id, member.service);
-    }
-
-    Ok(())
+    format.print(&status, |status| {
+        println!("Members:");
+        for member in &status.members {
+            let drained = if member.drained { " (drained)" } else { "" };
+            println!("  - {}: {}{}", member.id, member.service, drained);
+        }
+    })
 }
 
-pub async fn top_keyphrases(top: usize) -> Result<()> {
+pub async fn top_keyphrases(top: usize, format: OutputFormat) -> Result<()> {
     let config = Config::load()?;
     let mut conn = sonic::service::Connection::create(config.host).await?;
 
@@ -96,22 +117,136 @@ pub async fn top_keyphrases(top: usize) -> Result<()> {
         .send_without_timeout(api::TopKeyphrases { top })
         .await?;
 
-    println!("id,text,score");
-    for (i, keyphrase) in keyphrases.iter().enumerate() {
-        println!("{},{},{}", i + 1, keyphrase.text(), keyphrase.score());
-    }
-
-    Ok(())
+    format.print(&keyphrases, |keyphrases| {
+        println!("id,text,score");
+        for (i, keyphrase) in keyphrases.iter().enumerate() {
+            println!("{},{},{}", i + 1, keyphrase.text(), keyphrase.score());
+        }
+    })
 }
 
-pub async fn index_size() -> Result<()> {
+pub async fn index_size(format: OutputFormat) -> Result<()> {
     let config = Config::load()?;
     let mut conn: sonic::service::Connection<api::ManagementService> =
         sonic::service::Connection::create(config.host).await?;
 
     let size: api::SizeResponse = conn.send_without_timeout(api::Size).await?;
 
-    println!("Number of pages in index: {}", size.pages);
+    format.print(&size, |size| {
+        println!("Number of pages in index: {}", size.pages);
+    })
+}
 
-    Ok(())
+pub async fn index_size_by_field(format: OutputFormat) -> Result<()> {
+    let config = Config::load()?;
+    let mut conn: sonic::service::Connection<api::ManagementService> =
+        sonic::service::Connection::create(config.host).await?;
+
+    let sizes = conn.send_without_timeout(api::SizeByField).await?;
+
+    format.print(&sizes, |sizes| {
+        println!("field,postings,fast,stored,total");
+        for (name, size) in &sizes.fields {
+            println!(
+                "{},{},{},{},{}",
+                name,
+                size.postings,
+                size.fast,
+                size.stored,
+                size.total()
+            );
+        }
+    })
+}
+
+#[derive(Debug, serde::Serialize)]
+struct DrainStatus {
+    member: String,
+    drained: bool,
+}
+
+pub async fn drain(member: String, format: OutputFormat) -> Result<()> {
+    let config = Config::load()?;
+    let mut conn: sonic::service::Connection<api::ManagementService> =
+        sonic::service::Connection::create(config.host).await?;
+
+    conn.send(api::Drain {
+        member: member.clone(),
+    })
+    .await??;
+
+    format.print(
+        &DrainStatus {
+            member,
+            drained: true,
+        },
+        |status| println!("Drained {}", status.member),
+    )
+}
+
+pub async fn undrain(member: String, format: OutputFormat) -> Result<()> {
+    let config = Config::load()?;
+    let mut conn: sonic::service::Connection<api::ManagementService> =
+        sonic::service::Connection::create(config.host).await?;
+
+    conn.send(api::Undrain {
+        member: member.clone(),
+    })
+    .await??;
+
+    format.print(
+        &DrainStatus {
+            member,
+            drained: false,
+        },
+        |status| println!("Undrained {}", status.member),
+    )
+}
+
+#[derive(Debug, serde::Serialize)]
+struct ReloadStatus {
+    path: String,
+}
+
+pub async fn reload_lambda_model(path: String, format: OutputFormat) -> Result<()> {
+    let config = Config::load()?;
+    let mut conn: sonic::service::Connection<api::ManagementService> =
+        sonic::service::Connection::create(config.host).await?;
+
+    conn.send(api::ReloadLambdaModel { path: path.clone() })
+        .await??;
+
+    format.print(&ReloadStatus { path }, |_| {
+        println!("Reloaded lambdamart model")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::distributed::member::{Member, Service, ShardId};
+
+    use super::*;
+
+    #[test]
+    fn status_json_roundtrip() {
+        let mut drained = Member::new(Service::Searcher {
+            host: "127.0.0.1:3000".parse().unwrap(),
+            shard: ShardId::new(1),
+        });
+        drained.drained = true;
+
+        let status = api::Status {
+            members: vec![
+                Member::new(Service::Api {
+                    host: "127.0.0.1:8000".parse().unwrap(),
+                }),
+                drained,
+            ],
+        };
+
+        let json = serde_json::to_string(&status).unwrap();
+        let parsed: api::Status = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.members, status.members);
+    }
 }
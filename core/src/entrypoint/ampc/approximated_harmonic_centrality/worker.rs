@@ -81,6 +81,7 @@ impl_worker!(ApproxCentralityJob, RemoteApproxCentralityWorker => ApproxCentrali
 #[derive(Clone)]
 pub struct RemoteApproxCentralityWorker {
     shard: ShardId,
+    addr: SocketAddr,
     pool: Arc<sonic::ConnectionPool<JobConn<ApproxCentralityJob>>>,
 }
 
@@ -88,6 +89,7 @@ impl RemoteApproxCentralityWorker {
     pub fn new(shard: ShardId, addr: SocketAddr) -> Result<Self> {
         Ok(Self {
             shard,
+            addr,
             pool: Arc::new(sonic::ConnectionPool::new(addr)?),
         })
     }
@@ -97,7 +99,7 @@ impl RemoteApproxCentralityWorker {
     }
 
     pub fn addr(&self) -> SocketAddr {
-        self.pool.addr()
+        self.addr
     }
 
     pub fn num_nodes(&self) -> u64 {
@@ -115,7 +115,9 @@ pub async fn run<C: Into<Config>>(config: C) -> Result<()> {
     };
 
     loop {
-        server.accept().await?;
+        if let Err(e) = server.accept().await {
+            tracing::error!("{:?}", e);
+        }
     }
 }
 
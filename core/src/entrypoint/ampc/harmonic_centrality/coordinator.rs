@@ -18,7 +18,10 @@ use crate::{
     ampc::{dht::ShardId, prelude::*, Coordinator, DhtConn},
     config::HarmonicCoordinatorConfig,
     distributed::member::Member,
-    webgraph::centrality::{store_csv, store_harmonic, top_nodes, TopNodes},
+    webgraph::{
+        self,
+        centrality::{store_csv, store_harmonic, top_nodes, TopNodes},
+    },
     Result,
 };
 use std::{collections::BTreeMap, net::SocketAddr, path::Path};
@@ -28,7 +31,10 @@ use crate::{
     distributed::{cluster::Cluster, member::Service},
 };
 
-use super::{CentralityJob, CentralityMapper, CentralityTables, Meta, RemoteCentralityWorker};
+use super::{
+    CentralityJob, CentralityMapper, CentralityTables, IncrementalScope, Meta,
+    RemoteCentralityWorker,
+};
 
 pub struct CentralitySetup {
     dht: DhtConn<CentralityTables>,
@@ -124,6 +130,28 @@ pub fn build(
     dht: &[(ShardId, SocketAddr)],
     workers: Vec<RemoteCentralityWorker>,
 ) -> Coordinator<CentralityJob> {
+    build_with_scope(dht, workers, None)
+}
+
+/// Only recompute the centralities of the nodes affected by the changed edges in `scope`.
+/// Use [`merge_incremental`] to combine the result with the previous centralities.
+pub fn build_incremental(
+    dht: &[(ShardId, SocketAddr)],
+    workers: Vec<RemoteCentralityWorker>,
+    scope: IncrementalScope,
+) -> Coordinator<CentralityJob> {
+    build_with_scope(dht, workers, Some(scope))
+}
+
+fn build_with_scope(
+    dht: &[(ShardId, SocketAddr)],
+    workers: Vec<RemoteCentralityWorker>,
+    scope: Option<IncrementalScope>,
+) -> Coordinator<CentralityJob> {
+    for worker in &workers {
+        worker.set_scope(scope.clone());
+    }
+
     let setup = CentralitySetup::new_for_dht_members(dht, workers.clone());
 
     Coordinator::new(setup, workers)
@@ -133,6 +161,33 @@ pub fn build(
         .with_mapper(CentralityMapper::SaveBloom)
         .with_mapper(CentralityMapper::UpdateBloom)
         .with_mapper(CentralityMapper::Centralities)
+        .with_mapper(CentralityMapper::EdgeWeights)
+}
+
+/// Replace the `previous` centralities of the affected nodes with the ones from an incremental run.
+/// Both the previous and the returned centralities are normalized by `num_nodes - 1`.
+pub fn merge_incremental(
+    previous: impl Iterator<Item = (webgraph::NodeID, f64)>,
+    scope: &IncrementalScope,
+    res: &CentralityTables,
+    num_nodes: u64,
+) -> BTreeMap<webgraph::NodeID, f64> {
+    let norm = num_nodes.saturating_sub(1).max(1) as f64;
+
+    let mut centralities: BTreeMap<_, _> = previous.collect();
+
+    // affected nodes that no longer have any ingoing edges are not in the result
+    for node in scope.affected() {
+        centralities.insert(*node, 0.0);
+    }
+
+    for (node, centrality) in res.centrality.iter() {
+        if scope.is_affected(&node) {
+            centralities.insert(node, f64::from(centrality) / norm);
+        }
+    }
+
+    centralities
 }
 
 struct ClusterInfo {
@@ -23,12 +23,16 @@ use bloom::U64BloomFilter;
 
 use crate::{
     ampc::{
-        dht::{HyperLogLog64Upsert, UpsertAction},
+        dht::{HyperLogLog64Upsert, KahanSumAdd, UpsertAction},
         prelude::*,
         DhtConn,
     },
     hyperloglog::HyperLogLog,
-    webgraph::{self, centrality::harmonic::SKIPPED_REL},
+    kahan_sum::KahanSum,
+    webgraph::{
+        self,
+        centrality::harmonic::{extra_edge_weight, SKIPPED_REL},
+    },
 };
 
 use super::{CentralityJob, CentralityTables, CentralityWorker};
@@ -43,6 +47,7 @@ pub enum CentralityMapper {
     Centralities,
     SaveBloom,
     UpdateBloom,
+    EdgeWeights,
 }
 
 impl CentralityMapper {
@@ -211,6 +216,23 @@ impl CentralityMapper {
             .batch_set(new_values.into_iter().collect());
     }
 
+    /// add the extra weight of the edges in the batch to the centrality of edge.to in dht.next
+    fn add_edge_weights(batch: &[webgraph::Edge<()>], dht: &DhtConn<CentralityTables>) {
+        let mut weights: BTreeMap<webgraph::NodeID, KahanSum> = BTreeMap::new();
+
+        for edge in batch {
+            *weights.entry(edge.to.node()).or_default() += extra_edge_weight(edge);
+        }
+
+        if weights.is_empty() {
+            return;
+        }
+
+        dht.next()
+            .centrality
+            .batch_upsert(KahanSumAdd, weights.into_iter().collect());
+    }
+
     fn map_setup_counters(worker: &CentralityWorker, dht: &DhtConn<CentralityTables>) {
         if worker.round() != 0 {
             return;
@@ -222,11 +244,17 @@ impl CentralityMapper {
 
         let pool = rayon::ThreadPoolBuilder::new().build().unwrap();
 
+        let scope = worker.scope();
+
         pool.scope(|s| {
             let mut batch = Vec::with_capacity(batch_size);
             let mut changed_nodes = worker.changed_nodes().lock().unwrap();
 
-            for node in worker.graph().nodes() {
+            for node in worker
+                .graph()
+                .nodes()
+                .filter(|n| scope.as_ref().map_or(true, |scope| scope.is_relevant(n)))
+            {
                 changed_nodes.insert(node.as_u64());
                 batch.push(node);
                 if batch.len() >= batch_size {
@@ -264,6 +292,7 @@ impl CentralityMapper {
         )));
 
         let pool = rayon::ThreadPoolBuilder::new().build().unwrap();
+        let scope = worker.scope();
 
         pool.scope(|s| {
             let mut batch = Vec::with_capacity(batch_size);
@@ -274,6 +303,11 @@ impl CentralityMapper {
                 .edges()
                 .filter(|e| !e.rel_flags().intersects(*SKIPPED_REL))
                 .filter(|e| changed_nodes.contains(e.from.node().as_u64()))
+                .filter(|e| {
+                    scope.as_ref().map_or(true, |scope| {
+                        scope.is_relevant(&e.from.node()) && scope.is_relevant(&e.to.node())
+                    })
+                })
             {
                 batch.push(edge);
                 if batch.len() >= batch_size {
@@ -309,6 +343,7 @@ impl CentralityMapper {
 
         let pool = rayon::ThreadPoolBuilder::new().build().unwrap();
         let round = worker.inc_round();
+        let scope = worker.scope();
 
         // count cardinality of hyperloglogs in dht.next and update count after all mappers are done
         pool.scope(|s| {
@@ -318,6 +353,7 @@ impl CentralityMapper {
                 .graph()
                 .nodes()
                 .filter(|n| changed_nodes.contains(n.as_u64()))
+                .filter(|n| scope.as_ref().map_or(true, |scope| scope.is_affected(n)))
             {
                 batch.push(node);
                 if batch.len() >= batch_size {
@@ -334,6 +370,50 @@ impl CentralityMapper {
         });
     }
 
+    /// The weights are added once, right after the centralities of the first round have been set.
+    /// Every round starts from the centralities of the previous one, so they are kept from then on.
+    /// This has to be its own step, as the centralities of a round overwrite what is in dht.next.
+    fn map_edge_weights(worker: &CentralityWorker, dht: &DhtConn<CentralityTables>) {
+        if worker.round() != 1 {
+            return;
+        }
+
+        // shards are the same for both prev and next
+        let num_shards = dht.prev().num_shards();
+        let batch_size = (num_shards * OPS_BATCH_PER_SHARD) as usize;
+
+        let pool = rayon::ThreadPoolBuilder::new().build().unwrap();
+        let scope = worker.scope();
+
+        pool.scope(|s| {
+            let mut batch = Vec::with_capacity(batch_size);
+
+            for edge in worker
+                .graph()
+                .edges()
+                .filter(|e| !e.rel_flags().intersects(*SKIPPED_REL))
+                .filter(|e| extra_edge_weight(e) != 0.0)
+                .filter(|e| {
+                    scope.as_ref().map_or(true, |scope| {
+                        scope.is_relevant(&e.from.node()) && scope.is_relevant(&e.to.node())
+                    })
+                })
+            {
+                batch.push(edge);
+                if batch.len() >= batch_size {
+                    let update_batch = batch.clone();
+                    s.spawn(move |_| Self::add_edge_weights(&update_batch, dht));
+
+                    batch.clear();
+                }
+            }
+
+            if !batch.is_empty() {
+                s.spawn(move |_| Self::add_edge_weights(&batch, dht));
+            }
+        });
+    }
+
     fn map_save_bloom(worker: &CentralityWorker, dht: &DhtConn<CentralityTables>) {
         let changed_nodes = worker.changed_nodes().lock().unwrap();
         dht.next()
@@ -366,6 +446,7 @@ impl Mapper for CentralityMapper {
             CentralityMapper::SaveBloom => Self::map_save_bloom(worker, dht),
             CentralityMapper::UpdateBloom => Self::map_update_bloom(worker, dht),
             CentralityMapper::Centralities => Self::map_centralities(worker, dht),
+            CentralityMapper::EdgeWeights => Self::map_edge_weights(worker, dht),
         }
     }
 }
@@ -14,6 +14,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>
 
+use std::collections::BTreeSet;
+
+use rayon::prelude::*;
+
 use crate::hyperloglog::HyperLogLog;
 use crate::{ampc::prelude::*, kahan_sum::KahanSum};
 
@@ -27,7 +31,7 @@ pub mod worker;
 use bloom::U64BloomFilter;
 pub use coordinator::{CentralityFinish, CentralitySetup};
 pub use mapper::CentralityMapper;
-pub use worker::{CentralityWorker, RemoteCentralityWorker};
+pub use worker::{CentralityWorker, Direction, RemoteCentralityWorker};
 
 #[derive(
     serde::Serialize,
@@ -63,6 +67,122 @@ impl_dht_tables!(
     [counters, meta, centrality, changed_nodes]
 );
 
+/// Limits a run to the neighbourhood of a set of changed edges, so the centralities
+/// can be updated without recomputing the entire graph.
+///
+/// Only the distances to nodes downstream of a changed edge can have changed, so these are the
+/// `affected` nodes whose centrality gets recomputed. Their centrality only depends on the
+/// nodes upstream of them, so the counters are only propagated between the `relevant` nodes.
+///
+/// Both neighbourhoods are cut off after `max_hops` and `max_nodes`, as a changed edge next to
+/// a hub would otherwise pull in most of the graph. The recomputed centralities are therefore
+/// approximate: nodes beyond the cut-off upstream are not counted in the centrality of the
+/// affected nodes, and nodes beyond the cut-off downstream keep their previous centrality.
+/// If [`IncrementalScope::is_truncated`], a full run is more accurate.
+#[derive(
+    serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Debug, Clone, Default,
+)]
+pub struct IncrementalScope {
+    affected: BTreeSet<webgraph::NodeID>,
+    relevant: BTreeSet<webgraph::NodeID>,
+    truncated: bool,
+}
+
+impl IncrementalScope {
+    /// `changed_edges` are the `(from, to)` pairs of the edges that have been
+    /// added or removed since the last run.
+    pub fn new(
+        workers: &[RemoteCentralityWorker],
+        changed_edges: &[(webgraph::NodeID, webgraph::NodeID)],
+        max_hops: usize,
+        max_nodes: usize,
+    ) -> Self {
+        let seeds = changed_edges.iter().map(|(_, to)| *to).collect();
+
+        let (affected, affected_truncated) =
+            Self::expand(workers, seeds, Direction::Outgoing, max_hops, max_nodes);
+        let (relevant, relevant_truncated) = Self::expand(
+            workers,
+            affected.clone(),
+            Direction::Ingoing,
+            max_hops,
+            max_nodes,
+        );
+
+        Self {
+            affected,
+            relevant,
+            truncated: affected_truncated || relevant_truncated,
+        }
+    }
+
+    /// All nodes within `max_hops` of `nodes` in the given direction, including `nodes` themselves,
+    /// but at most `max_nodes` of them. Also returns whether the neighbourhood
+    /// might have been cut off.
+    fn expand(
+        workers: &[RemoteCentralityWorker],
+        nodes: BTreeSet<webgraph::NodeID>,
+        direction: Direction,
+        max_hops: usize,
+        max_nodes: usize,
+    ) -> (BTreeSet<webgraph::NodeID>, bool) {
+        let mut res = nodes.clone();
+        let mut frontier: Vec<_> = nodes.into_iter().collect();
+        let mut truncated = false;
+
+        for _ in 0..max_hops {
+            if frontier.is_empty() {
+                break;
+            }
+
+            // the frontier is bounded by `max_nodes`, so each worker gets it in a single request.
+            // The replies are bounded as well, as the neighbours of a hub could be most of the graph.
+            let replies: Vec<_> = workers
+                .par_iter()
+                .map(|worker| worker.neighbours(frontier.clone(), direction, max_nodes))
+                .collect();
+
+            // a full reply might have left out some neighbours
+            truncated |= replies.iter().any(|reply| reply.len() >= max_nodes);
+            let neighbours: BTreeSet<_> = replies.into_iter().flatten().collect();
+
+            frontier.clear();
+
+            for node in neighbours {
+                if res.contains(&node) {
+                    continue;
+                }
+
+                if res.len() >= max_nodes {
+                    return (res, true);
+                }
+
+                res.insert(node);
+                frontier.push(node);
+            }
+        }
+
+        (res, truncated || !frontier.is_empty())
+    }
+
+    pub fn affected(&self) -> &BTreeSet<webgraph::NodeID> {
+        &self.affected
+    }
+
+    pub fn is_affected(&self, node: &webgraph::NodeID) -> bool {
+        self.affected.contains(node)
+    }
+
+    pub fn is_relevant(&self, node: &webgraph::NodeID) -> bool {
+        self.relevant.contains(node)
+    }
+
+    /// Whether the neighbourhoods were cut off by `max_hops` or `max_nodes`.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
 #[derive(bincode::Encode, bincode::Decode, Debug, Clone)]
 pub struct CentralityJob {
     shard: ShardId,
@@ -82,10 +202,15 @@ impl Job for CentralityJob {
 mod tests {
     use tracing_test::traced_test;
 
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
     use crate::{
         executor::Executor,
         free_socket_addr,
-        webgraph::{centrality::harmonic::HarmonicCentrality, Compression, WebgraphWriter},
+        webgraph::{
+            centrality::harmonic::HarmonicCentrality, Compression, Node, Webgraph, WebgraphWriter,
+        },
         webpage::html::links::RelFlags,
     };
 
@@ -186,4 +311,178 @@ mod tests {
             assert!((expected - actual).abs() < 0.0001);
         }
     }
+
+    fn graph<P: AsRef<Path>>(
+        path: P,
+        edges: impl IntoIterator<Item = (Node, Node, String)>,
+    ) -> Webgraph {
+        let mut writer = WebgraphWriter::new(
+            path,
+            Executor::single_thread(),
+            Compression::default(),
+            None,
+        );
+
+        for (from, to, label) in edges {
+            writer.insert(from, to, label, RelFlags::default());
+        }
+
+        writer.commit();
+        writer.finalize()
+    }
+
+    fn spawn_worker(shard: ShardId, graph: Webgraph) -> RemoteCentralityWorker {
+        let worker_addr = free_socket_addr();
+
+        // the worker is listening once it is bound, so it can be connected to right away
+        let mut server = CentralityWorker::new(shard, graph)
+            .bind(worker_addr)
+            .unwrap();
+
+        std::thread::spawn(move || {
+            server.run().unwrap();
+        });
+
+        RemoteCentralityWorker::new(shard, worker_addr).unwrap()
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_weighted_graph() {
+        let temp_dir = crate::gen_temp_dir().unwrap();
+
+        let weighted_graph = |path: std::path::PathBuf, edges: &[(Node, Node, String)]| {
+            let mut writer = WebgraphWriter::new(
+                path,
+                Executor::single_thread(),
+                Compression::default(),
+                None,
+            );
+
+            for (from, to, label) in edges.iter().cloned() {
+                if from == Node::from("D") {
+                    writer.insert_with_weight(from, to, label, RelFlags::default(), 4.0);
+                } else {
+                    writer.insert(from, to, label, RelFlags::default());
+                }
+            }
+
+            writer.commit();
+            writer.finalize()
+        };
+
+        let edges = crate::webgraph::tests::test_edges();
+        let a_edges: Vec<_> = edges.iter().step_by(2).cloned().collect();
+        let b_edges: Vec<_> = edges.iter().skip(1).step_by(2).cloned().collect();
+
+        let combined = weighted_graph(temp_dir.as_ref().join("combined"), &edges);
+        let unweighted = graph(temp_dir.as_ref().join("unweighted"), edges.clone());
+        let a = weighted_graph(temp_dir.as_ref().join("a"), &a_edges);
+        let b = weighted_graph(temp_dir.as_ref().join("b"), &b_edges);
+
+        let expected = HarmonicCentrality::calculate(&combined);
+        let num_nodes = combined.nodes().count();
+
+        let c = Node::from("C").id();
+        assert!(
+            expected.get(&c).unwrap() > HarmonicCentrality::calculate(&unweighted).get(&c).unwrap()
+        );
+
+        let workers = vec![spawn_worker(1.into(), a), spawn_worker(2.into(), b)];
+
+        let (dht_shard, dht_addr) = crate::entrypoint::ampc::dht::tests::setup();
+        let res = coordinator::build(&[(dht_shard, dht_addr)], workers)
+            .run(
+                vec![
+                    CentralityJob { shard: 1.into() },
+                    CentralityJob { shard: 2.into() },
+                ],
+                CentralityFinish,
+            )
+            .unwrap();
+
+        let actual: BTreeMap<_, _> = res
+            .centrality
+            .iter()
+            .map(|(n, s)| (n, f64::from(s) / ((num_nodes - 1) as f64)))
+            .collect();
+
+        for (node, expected) in expected.iter() {
+            let actual = actual.get(node).copied().unwrap_or(0.0);
+            assert!((expected - actual).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_incremental() {
+        let temp_dir = crate::gen_temp_dir().unwrap();
+
+        let mut old_edges = crate::webgraph::tests::test_edges();
+        old_edges.push((Node::from("E"), Node::from("F"), String::new()));
+
+        let added = (Node::from("D"), Node::from("E"), String::new());
+        let mut new_edges = old_edges.clone();
+        new_edges.push(added.clone());
+
+        let old = graph(temp_dir.as_ref().join("old"), old_edges);
+        let new = graph(temp_dir.as_ref().join("new"), new_edges.clone());
+        let a = graph(
+            temp_dir.as_ref().join("a"),
+            new_edges.iter().step_by(2).cloned(),
+        );
+        let b = graph(
+            temp_dir.as_ref().join("b"),
+            new_edges.iter().skip(1).step_by(2).cloned(),
+        );
+
+        let previous = HarmonicCentrality::calculate(&old);
+        let expected = HarmonicCentrality::calculate(&new);
+        let num_nodes = new.nodes().count() as u64;
+
+        let workers = vec![spawn_worker(1.into(), a), spawn_worker(2.into(), b)];
+        let changed = [(added.0.id(), added.1.id())];
+
+        // a bounded scope is cut off instead of growing with the graph
+        let bounded = IncrementalScope::new(&workers, &changed, 4, 2);
+        assert!(bounded.is_truncated());
+        assert_eq!(bounded.affected().len(), 2);
+
+        let scope = IncrementalScope::new(&workers, &changed, 4, 1_000);
+        assert!(!scope.is_truncated());
+
+        // only the nodes downstream of the new edge are recomputed
+        assert!(scope.is_affected(&Node::from("E").id()));
+        assert!(scope.is_affected(&Node::from("F").id()));
+        assert!(!scope.is_affected(&Node::from("A").id()));
+        assert!(!scope.is_affected(&Node::from("C").id()));
+
+        let (dht_shard, dht_addr) = crate::entrypoint::ampc::dht::tests::setup();
+        let res = coordinator::build_incremental(&[(dht_shard, dht_addr)], workers, scope.clone())
+            .run(
+                vec![
+                    CentralityJob { shard: 1.into() },
+                    CentralityJob { shard: 2.into() },
+                ],
+                CentralityFinish,
+            )
+            .unwrap();
+
+        let actual = coordinator::merge_incremental(
+            previous.iter().map(|(node, c)| (*node, c)),
+            &scope,
+            &res,
+            num_nodes,
+        );
+
+        assert!(
+            expected.get(&Node::from("F").id()).unwrap()
+                > previous.get(&Node::from("F").id()).unwrap()
+        );
+
+        for (node, expected) in expected.iter() {
+            let actual = actual.get(node).copied().unwrap_or(0.0);
+            assert!((expected - actual).abs() < 0.0001);
+        }
+    }
 }
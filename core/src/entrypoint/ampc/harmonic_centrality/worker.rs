@@ -23,7 +23,7 @@ use crate::{
         member::{Service, ShardId},
         sonic,
     },
-    webgraph::{self, Webgraph},
+    webgraph::{self, centrality::harmonic::SKIPPED_REL, EdgeLimit, Webgraph},
     Result,
 };
 use std::{
@@ -34,7 +34,7 @@ use std::{
     },
 };
 
-use super::CentralityJob;
+use super::{CentralityJob, IncrementalScope};
 
 pub struct CentralityWorker {
     shard: ShardId,
@@ -42,6 +42,7 @@ pub struct CentralityWorker {
     changed_nodes: Arc<Mutex<U64BloomFilter>>,
     round: AtomicU64,
     has_updated_meta_for_round: AtomicBool,
+    scope: Mutex<Option<Arc<IncrementalScope>>>,
 }
 
 impl CentralityWorker {
@@ -57,6 +58,7 @@ impl CentralityWorker {
             changed_nodes: Arc::new(Mutex::new(changed_nodes)),
             round: AtomicU64::new(0),
             has_updated_meta_for_round: AtomicBool::new(false),
+            scope: Mutex::new(None),
         }
     }
 
@@ -98,6 +100,25 @@ impl CentralityWorker {
         self.round
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
     }
+
+    /// The scope of the current run. `None` means the entire graph.
+    pub fn scope(&self) -> Option<Arc<IncrementalScope>> {
+        self.scope.lock().unwrap().clone()
+    }
+
+    /// Prepare the worker for a new run limited to `scope`.
+    pub fn set_scope(&self, scope: Option<IncrementalScope>) {
+        *self.scope.lock().unwrap() = scope.map(Arc::new);
+        self.round.store(0, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+#[derive(
+    serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Debug, Clone, Copy,
+)]
+pub enum Direction {
+    Ingoing,
+    Outgoing,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Debug, Clone)]
@@ -125,7 +146,47 @@ impl Message<CentralityWorker> for BatchId2Node {
     }
 }
 
-impl_worker!(CentralityJob, RemoteCentralityWorker => CentralityWorker, [NumNodes, BatchId2Node]);
+#[derive(serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Debug, Clone)]
+pub struct Neighbours {
+    nodes: Vec<webgraph::NodeID>,
+    direction: Direction,
+    limit: usize,
+}
+
+impl Message<CentralityWorker> for Neighbours {
+    type Response = Vec<webgraph::NodeID>;
+
+    fn handle(self, worker: &CentralityWorker) -> Self::Response {
+        let limit = EdgeLimit::Limit(self.limit);
+
+        self.nodes
+            .iter()
+            .flat_map(|node| match self.direction {
+                Direction::Ingoing => worker.graph.raw_ingoing_edges(node, limit),
+                Direction::Outgoing => worker.graph.raw_outgoing_edges(node, limit),
+            })
+            .filter(|edge| !edge.rel_flags().intersects(*SKIPPED_REL))
+            .map(|edge| match self.direction {
+                Direction::Ingoing => edge.from.node(),
+                Direction::Outgoing => edge.to.node(),
+            })
+            .take(self.limit)
+            .collect()
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Debug, Clone)]
+pub struct SetScope(Option<IncrementalScope>);
+
+impl Message<CentralityWorker> for SetScope {
+    type Response = ();
+
+    fn handle(self, worker: &CentralityWorker) -> Self::Response {
+        worker.set_scope(self.0)
+    }
+}
+
+impl_worker!(CentralityJob, RemoteCentralityWorker => CentralityWorker, [NumNodes, BatchId2Node, Neighbours, SetScope]);
 
 #[derive(Clone)]
 pub struct RemoteCentralityWorker {
@@ -155,6 +216,24 @@ impl RemoteCentralityWorker {
     ) -> Vec<(webgraph::NodeID, webgraph::Node)> {
         self.send(BatchId2Node(id))
     }
+
+    /// The neighbours of `nodes` in the given direction, but at most `limit` of them.
+    pub fn neighbours(
+        &self,
+        nodes: Vec<webgraph::NodeID>,
+        direction: Direction,
+        limit: usize,
+    ) -> Vec<webgraph::NodeID> {
+        self.send(Neighbours {
+            nodes,
+            direction,
+            limit,
+        })
+    }
+
+    pub fn set_scope(&self, scope: Option<IncrementalScope>) {
+        self.send(SetScope(scope))
+    }
 }
 
 impl RemoteWorker for RemoteCentralityWorker {
@@ -29,8 +29,9 @@ use crate::{
         member::{Member, Service},
         sonic::{self, service::sonic_service},
     },
-    inverted_index::KeyPhrase,
+    inverted_index::{FieldSizes, KeyPhrase},
     metrics::Label,
+    ranking::models::lambdamart::ReloadableLambdaMART,
     searcher::{DistributedSearcher, SearchClient},
 };
 
@@ -100,13 +101,33 @@ async fn cluster(config: &config::ApiConfig) -> Result<Cluster> {
 pub struct ManagementService {
     cluster: Arc<Cluster>,
     searcher: DistributedSearcher,
+    lambda_model: Option<ReloadableLambdaMART>,
 }
-sonic_service!(ManagementService, [TopKeyphrases, ClusterStatus, Size]);
+sonic_service!(
+    ManagementService,
+    version = 1,
+    [
+        TopKeyphrases,
+        ClusterStatus,
+        Size,
+        SizeByField,
+        Drain,
+        ReloadLambdaModel,
+        Undrain,
+    ]
+);
 
 impl ManagementService {
-    pub async fn new(cluster: Arc<Cluster>) -> Result<Self> {
+    pub async fn new(
+        cluster: Arc<Cluster>,
+        lambda_model: Option<ReloadableLambdaMART>,
+    ) -> Result<Self> {
         let searcher = DistributedSearcher::new(Arc::clone(&cluster)).await;
-        Ok(ManagementService { cluster, searcher })
+        Ok(ManagementService {
+            cluster,
+            searcher,
+            lambda_model,
+        })
     }
 }
 
@@ -121,7 +142,7 @@ impl sonic::service::Message<ManagementService> for TopKeyphrases {
     }
 }
 
-#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize)]
 pub struct Status {
     pub members: Vec<Member>,
 }
@@ -140,7 +161,7 @@ impl sonic::service::Message<ManagementService> for ClusterStatus {
 #[derive(Debug, Clone, Copy, bincode::Encode, bincode::Decode)]
 pub struct Size;
 
-#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize)]
 pub struct SizeResponse {
     pub pages: u64,
 }
@@ -186,8 +207,149 @@ impl sonic::service::Message<ManagementService> for Size {
     }
 }
 
-async fn run_management(addr: SocketAddr, cluster: Arc<Cluster>) -> Result<()> {
-    let server = ManagementService::new(cluster).await?.bind(addr).await?;
+/// On-disk size of each field summed over one replica of every shard.
+#[derive(Debug, Clone, Copy, bincode::Encode, bincode::Decode)]
+pub struct SizeByField;
+
+impl sonic::service::Message<ManagementService> for SizeByField {
+    type Response = FieldSizes;
+    async fn handle(self, server: &ManagementService) -> Self::Response {
+        let mut res = FieldSizes::default();
+
+        let mut checked_shards = std::collections::HashSet::new();
+
+        for member in server.cluster.members().await {
+            if let Service::Searcher { host, shard } = member.service {
+                if checked_shards.contains(&shard) {
+                    continue;
+                }
+
+                let mut client: sonic::service::Connection<SearchService> =
+                    sonic::service::Connection::create(host).await.unwrap();
+
+                if let Some(sizes) = client.send_without_timeout(SizeByField).await.unwrap() {
+                    res += sizes;
+                    checked_shards.insert(shard);
+                }
+            }
+        }
+
+        res
+    }
+}
+
+#[derive(thiserror::Error, Debug, bincode::Encode, bincode::Decode)]
+pub enum DrainError {
+    #[error("no member with id {0}")]
+    UnknownMember(String),
+
+    #[error("{0} can not be drained")]
+    Unsupported(String),
+
+    #[error("failed to drain the member: {0}")]
+    Failed(String),
+}
+
+/// Take a member out of rotation. Searches that are already running
+/// on the member finish as usual.
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+pub struct Drain {
+    pub member: String,
+}
+
+impl ManagementService {
+    /// Connect to the searcher that `member` identifies.
+    async fn searcher_connection(
+        &self,
+        member: &str,
+    ) -> Result<sonic::service::Connection<SearchService>, DrainError> {
+        let member = self
+            .cluster
+            .members()
+            .await
+            .into_iter()
+            .find(|m| m.id == member)
+            .ok_or_else(|| DrainError::UnknownMember(member.to_string()))?;
+
+        match member.service {
+            Service::Searcher { host, .. } => sonic::service::Connection::create(host)
+                .await
+                .map_err(|err| DrainError::Failed(err.to_string())),
+            service => Err(DrainError::Unsupported(service.to_string())),
+        }
+    }
+}
+
+impl sonic::service::Message<ManagementService> for Drain {
+    type Response = Result<(), DrainError>;
+    async fn handle(self, server: &ManagementService) -> Self::Response {
+        server
+            .searcher_connection(&self.member)
+            .await?
+            .send(self)
+            .await
+            .map_err(|err| DrainError::Failed(err.to_string()))?
+    }
+}
+
+/// Put a drained member back into rotation.
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+pub struct Undrain {
+    pub member: String,
+}
+
+impl sonic::service::Message<ManagementService> for Undrain {
+    type Response = Result<(), DrainError>;
+    async fn handle(self, server: &ManagementService) -> Self::Response {
+        server
+            .searcher_connection(&self.member)
+            .await?
+            .send(self)
+            .await
+            .map_err(|err| DrainError::Failed(err.to_string()))?
+    }
+}
+
+#[derive(thiserror::Error, Debug, bincode::Encode, bincode::Decode)]
+pub enum ReloadError {
+    #[error("the api was started without a lambdamart model")]
+    NoModel,
+
+    #[error("failed to load the model: {0}")]
+    Load(String),
+}
+
+/// Swap the lambdamart model used by the api for the model at `path`.
+/// Searches that are already running finish with the old model.
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+pub struct ReloadLambdaModel {
+    pub path: String,
+}
+
+impl sonic::service::Message<ManagementService> for ReloadLambdaModel {
+    type Response = Result<(), ReloadError>;
+    async fn handle(self, server: &ManagementService) -> Self::Response {
+        let model = server.lambda_model.as_ref().ok_or(ReloadError::NoModel)?;
+
+        model
+            .reload(&self.path)
+            .map_err(|err| ReloadError::Load(err.to_string()))?;
+
+        info!("reloaded lambdamart model from {}", self.path);
+
+        Ok(())
+    }
+}
+
+async fn run_management(
+    addr: SocketAddr,
+    cluster: Arc<Cluster>,
+    lambda_model: Option<ReloadableLambdaMART>,
+) -> Result<()> {
+    let server = ManagementService::new(cluster, lambda_model)
+        .await?
+        .bind(addr)
+        .await?;
 
     info!(
         "management interface is ready to accept requests on {}",
@@ -207,7 +369,12 @@ pub async fn run(config: config::ApiConfig) -> Result<()> {
 
     let cluster = Arc::new(cluster(&config).await?);
 
-    let app = router(&config, counters, cluster.clone()).await?;
+    let lambda_model = match &config.lambda_model_path {
+        Some(path) => Some(ReloadableLambdaMART::open(path)?),
+        None => None,
+    };
+
+    let app = router(&config, counters, cluster.clone(), lambda_model.clone()).await?;
     let metrics_app = metrics_router(registry);
 
     let addr = config.host;
@@ -229,7 +396,7 @@ pub async fn run(config: config::ApiConfig) -> Result<()> {
     .map_err(|e| e.into());
 
     let management = tokio::spawn(async move {
-        run_management(config.management_host, cluster)
+        run_management(config.management_host, cluster, lambda_model)
             .await
             .unwrap();
     })
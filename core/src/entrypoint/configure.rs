@@ -76,6 +76,7 @@ fn build_spellchecker() -> Result<()> {
                     .to_str()
                     .unwrap()
                     .to_string()],
+                date_filter: None,
             }),
             limit_warc_files: None,
             skip_warc_files: None,
@@ -104,6 +105,7 @@ fn create_webgraph() -> Result<()> {
         config: webgraph::JobConfig::Local(crate::config::LocalConfig {
             folder: ".".to_string(),
             names: vec![warc_path.to_str().unwrap().to_string()],
+            date_filter: None,
         }),
         warc_paths: vec![warc_path.to_str().unwrap().to_string()],
     };
@@ -112,6 +114,8 @@ fn create_webgraph() -> Result<()> {
         host_graph: Some(webgraph::open_host_graph_writer(&out_path_host, None)),
         page_graph: Some(webgraph::open_page_graph_writer(&out_path_page, None)),
         canonical_index: None,
+        exclude_nofollow_host_links: false,
+        exclude_nofollow_page_links: false,
     };
 
     worker.process_job(&job);
@@ -153,12 +157,15 @@ fn create_inverted_index() -> Result<()> {
         source_config: crate::config::WarcSource::Local(crate::config::LocalConfig {
             folder: ".".to_string(),
             names: vec![warc_path.to_str().unwrap().to_string()],
-        }),
-        warc_path: warc_path.to_str().unwrap().to_string(),
+            date_filter: None,
+        })
+        .into(),
+        path: warc_path.to_str().unwrap().to_string(),
         base_path: out_path.to_str().unwrap().to_string(),
         settings: JobSettings {
             host_centrality_threshold: None,
             minimum_clean_words: None,
+            minimum_content_density: None,
             batch_size: defaults::Indexing::batch_size(),
             autocommit_after_num_inserts: defaults::Indexing::autocommit_after_num_inserts(),
         },
@@ -184,16 +191,22 @@ fn create_inverted_index() -> Result<()> {
             output_path: out_path.to_str().unwrap().to_string(),
             limit_warc_files: None,
             skip_warc_files: None,
-            warc_source: job.source_config.clone(),
+            source: job.source_config.clone(),
             host_centrality_threshold: None,
             safety_classifier_path: None,
+            safety_classifier_threshold: None,
             minimum_clean_words: None,
+            minimum_content_density: None,
+            simhash_dedup_threshold: None,
+            adservers_path: None,
+            append_to: None,
             batch_size: defaults::Indexing::batch_size(),
             autocommit_after_num_inserts: defaults::Indexing::autocommit_after_num_inserts(),
             dual_encoder: dual_encoder_path.map(|p| IndexerDualEncoderConfig {
                 model_path: p.to_str().unwrap().to_string(),
                 page_centrality_rank_threshold: Some(100_000),
             }),
+            region_overrides: Default::default(),
         }
         .into(),
     ));
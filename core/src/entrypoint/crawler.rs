@@ -98,7 +98,7 @@ pub mod router {
         pub router: crawler::Router,
     }
 
-    sonic_service!(RouterService, [NewJob]);
+    sonic_service!(RouterService, version = 1, [NewJob]);
 
     #[derive(
         Debug, Clone, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode,
@@ -123,7 +123,7 @@ pub mod coordinator {
         pub coordinator: Arc<CrawlCoordinator>,
     }
 
-    sonic_service!(CoordinatorService, [GetJob]);
+    sonic_service!(CoordinatorService, version = 1, [GetJob]);
 
     #[derive(
         Debug, Clone, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode,
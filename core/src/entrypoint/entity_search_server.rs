@@ -27,7 +27,7 @@ use crate::{
 };
 use anyhow::Result;
 
-sonic_service!(SearchService, [Search, GetEntityImage]);
+sonic_service!(SearchService, version = 1, [Search, GetEntityImage]);
 
 pub struct SearchService {
     index: EntityIndex,
@@ -6,7 +6,7 @@
 use crate::crawler::CrawlDatum;
 use crate::warc::WarcRecord;
 
-#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode)]
 pub struct IndexableWebpage {
     pub url: String,
     pub body: String,
@@ -27,7 +27,7 @@ impl From<WarcRecord> for IndexableWebpage {
     fn from(record: WarcRecord) -> Self {
         Self {
             url: record.request.url,
-            body: record.response.body,
+            body: record.response.body.to_string(),
             fetch_time_ms: record.metadata.fetch_time_ms,
         }
     }
@@ -7,20 +7,20 @@ use std::path::Path;
 
 use itertools::Itertools;
 
-use tokio::pin;
 use tracing::{info, trace, warn};
 
 use crate::config;
-use crate::entrypoint::download_all_warc_files;
+use crate::entrypoint::stream_all_warc_files;
 use crate::index::Index;
 use crate::warc::PayloadType;
 
+use super::ndjson::{NdjsonReader, STDIN_PATH};
 use super::{IndexableWebpage, IndexingWorker};
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode)]
 pub struct Job {
-    pub source_config: config::WarcSource,
-    pub warc_path: String,
+    pub source_config: config::DocumentSource,
+    pub path: String,
     pub base_path: String,
     pub settings: JobSettings,
 }
@@ -31,79 +31,149 @@ pub struct Job {
 pub struct JobSettings {
     pub host_centrality_threshold: Option<f64>,
     pub minimum_clean_words: Option<usize>,
+    pub minimum_content_density: Option<f64>,
     pub batch_size: usize,
     pub autocommit_after_num_inserts: usize,
 }
 
+impl From<&config::IndexerConfig> for JobSettings {
+    fn from(config: &config::IndexerConfig) -> Self {
+        Self {
+            host_centrality_threshold: config.host_centrality_threshold,
+            minimum_clean_words: config.minimum_clean_words,
+            minimum_content_density: config.minimum_content_density,
+            batch_size: config.batch_size,
+            autocommit_after_num_inserts: config.autocommit_after_num_inserts,
+        }
+    }
+}
+
 impl Job {
+    fn name(&self) -> &str {
+        if self.path == STDIN_PATH {
+            "stdin"
+        } else {
+            self.path.split('/').last().unwrap()
+        }
+    }
+
     pub fn process(&self, worker: &IndexingWorker) -> Index {
-        let name = self.warc_path.split('/').last().unwrap();
+        let name = self.name();
 
-        let mut has_host_centrality = false;
-        let mut has_page_centrality = false;
-        let mut has_backlinks = false;
+        let mut index = Index::open_or_create(Path::new(&self.base_path).join(name)).unwrap();
+        self.insert_into(worker, &mut index, false);
 
-        info!("processing {}", name);
+        index.inverted_index.merge_into_max_segments(1).unwrap();
 
-        let mut index = Index::open(Path::new(&self.base_path).join(name)).unwrap();
-        index.prepare_writer().unwrap();
+        info!("{} done", name);
 
-        let paths = vec![self.warc_path.clone()];
-        let warc_files = download_all_warc_files(&paths, &self.source_config);
-        pin!(warc_files);
+        index
+    }
 
-        let mut num_inserts_since_commit = 0;
+    /// Insert the webpages of the job's file into an existing index.
+    /// A url is only inserted once per batch, as the old versions are only removed
+    /// from the segments that were in the index before.
+    pub fn process_into(&self, worker: &IndexingWorker, index: &mut Index) {
+        self.insert_into(worker, index, true);
+    }
 
-        for file in warc_files.by_ref() {
-            let mut batch = Vec::with_capacity(self.settings.batch_size);
-
-            for chunk in file
-                .records()
-                .flatten()
-                .filter(|record| match &record.response.payload_type {
-                    Some(payload_type) => matches!(payload_type, PayloadType::Html),
-                    None => true,
-                })
-                .filter(|record| !worker.see(&record.request.url))
-                .chunks(self.settings.batch_size)
-                .into_iter()
-            {
-                batch.clear();
-
-                for record in chunk {
-                    batch.push(IndexableWebpage::from(record));
+    fn insert_into(&self, worker: &IndexingWorker, index: &mut Index, dedup_urls: bool) {
+        info!("processing {}", self.name());
+
+        match &self.source_config {
+            config::DocumentSource::Warc(source) => {
+                let paths = vec![self.path.clone()];
+                let webpages = stream_all_warc_files(&paths, source)
+                    .flat_map(|records| records.flatten())
+                    .filter(|record| match &record.response.payload_type {
+                        Some(payload_type) => matches!(payload_type, PayloadType::Html),
+                        None => true,
+                    })
+                    .filter(|record| match record.response.status_code {
+                        Some(status_code) => (200..300).contains(&status_code),
+                        None => true,
+                    })
+                    .map(IndexableWebpage::from);
+
+                self.insert_webpages(worker, index, webpages, dedup_urls);
+            }
+            config::DocumentSource::Ndjson(_) => match NdjsonReader::open(&self.path) {
+                Ok(reader) => {
+                    let webpages = reader.filter_map(|webpage| match webpage {
+                        Ok(webpage) => Some(webpage),
+                        Err(err) => {
+                            warn!("skipping invalid line in {}: {}", self.name(), err);
+                            None
+                        }
+                    });
+
+                    self.insert_webpages(worker, index, webpages, dedup_urls);
                 }
+                Err(err) => warn!("failed to open {}: {}", self.path, err),
+            },
+        }
+    }
 
-                let prepared = crate::block_on(worker.prepare_webpages(&batch));
+    fn insert_webpages(
+        &self,
+        worker: &IndexingWorker,
+        index: &mut Index,
+        webpages: impl Iterator<Item = IndexableWebpage>,
+        dedup_urls: bool,
+    ) {
+        let name = self.name();
 
-                for webpage in &prepared {
-                    if webpage.host_centrality > 0.0 {
-                        has_host_centrality = true;
-                    }
+        let mut has_host_centrality = false;
+        let mut has_page_centrality = false;
+        let mut has_backlinks = false;
+
+        index.prepare_writer().unwrap();
 
-                    if webpage.page_centrality > 0.0 {
-                        has_page_centrality = true;
-                    }
+        let mut num_inserts_since_commit = 0;
 
-                    if !webpage.backlinks().is_empty() {
-                        has_backlinks = true;
-                    }
-                    trace!("inserting webpage: {:?}", webpage.html.url());
-                    trace!("title = {:?}", webpage.html.title());
-                    trace!("text = {:?}", webpage.html.clean_text());
+        for chunk in webpages
+            .filter(|webpage| !worker.see(&webpage.url))
+            .chunks(self.settings.batch_size)
+            .into_iter()
+        {
+            let batch = chunk.collect::<Vec<_>>();
+            let mut prepared = crate::block_on(worker.prepare_webpages(&batch));
+
+            if dedup_urls {
+                // urls that only differ before they are parsed are not caught by `worker.see`
+                prepared = prepared
+                    .into_iter()
+                    .unique_by(|webpage| webpage.html.url().clone())
+                    .collect();
+            }
 
-                    if let Err(err) = index.insert(webpage) {
-                        warn!("{:?}", err);
-                        panic!();
-                    }
+            for webpage in &prepared {
+                if webpage.host_centrality > 0.0 {
+                    has_host_centrality = true;
+                }
+
+                if webpage.page_centrality > 0.0 {
+                    has_page_centrality = true;
+                }
 
-                    num_inserts_since_commit += 1;
+                if !webpage.backlinks().is_empty() {
+                    has_backlinks = true;
                 }
+                trace!("inserting webpage: {:?}", webpage.html.url());
+                trace!("title = {:?}", webpage.html.title());
+                trace!("text = {:?}", webpage.html.clean_text());
 
-                if num_inserts_since_commit >= self.settings.autocommit_after_num_inserts {
-                    index.commit().unwrap();
-                    num_inserts_since_commit = 0;
+                if let Err(err) = index.insert(webpage) {
+                    warn!("{:?}", err);
+                    panic!();
                 }
+
+                num_inserts_since_commit += 1;
+            }
+
+            if num_inserts_since_commit >= self.settings.autocommit_after_num_inserts {
+                index.commit().unwrap();
+                num_inserts_since_commit = 0;
             }
         }
         index.commit().unwrap();
@@ -119,11 +189,5 @@ impl Job {
         if !has_backlinks && worker.page_webgraph().is_some() {
             warn!("no backlinks found in {}", name);
         }
-
-        index.inverted_index.merge_into_max_segments(1).unwrap();
-
-        info!("{} done", name);
-
-        index
     }
 }
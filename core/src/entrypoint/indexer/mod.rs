@@ -5,19 +5,23 @@
 
 pub mod indexable_webpage;
 pub mod job;
+pub mod ndjson;
 pub mod worker;
 
+use anyhow::anyhow;
 use rayon::prelude::*;
-use std::thread;
-
-use itertools::Itertools;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
 
 pub use crate::entrypoint::indexer::indexable_webpage::IndexableWebpage;
 pub use crate::entrypoint::indexer::job::{Job, JobSettings};
 pub use crate::entrypoint::indexer::worker::IndexingWorker;
 
-use crate::config::{self, WarcSource};
+use crate::config;
 use crate::index::Index;
+use crate::inverted_index::InvertedIndex;
+use crate::webpage::AdServers;
 use crate::Result;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode)]
@@ -30,28 +34,35 @@ impl From<String> for IndexPointer {
 }
 
 pub fn run(config: &config::IndexerConfig) -> Result<()> {
-    let warc_paths = config.warc_source.paths()?;
+    if let Some(path) = &config.adservers_path {
+        AdServers::reload(path)?;
+    }
 
-    let job_config: WarcSource = config.warc_source.clone();
+    let paths = config.source.paths()?;
+
+    if let Some(max_distance) = config.simhash_dedup_threshold {
+        InvertedIndex::check_near_duplicate_distance(max_distance)?;
+    }
+
+    let job_config: config::DocumentSource = config.source.clone();
 
     // sync block_on, to wait until the worker is initialized
     let worker = crate::block_on(IndexingWorker::new(config.clone().into()));
 
-    let indexes = warc_paths
-        .into_par_iter() // iterate over warc files in parallel
+    if let Some(append_to) = &config.append_to {
+        return append(config, append_to, &worker, paths);
+    }
+
+    let indexes = paths
+        .into_par_iter() // iterate over input files in parallel
         .skip(config.skip_warc_files.unwrap_or(0)) // if "skip_warc_files" is not set, use 0 as default
         .take(config.limit_warc_files.unwrap_or(usize::MAX)) // if "limit_warc_files" is not set, use usize::MAX as default
-        .map(|warc_path| Job {
-            // map each warc file to a Job instance
+        .map(|path| Job {
+            // map each input file to a Job instance
             source_config: job_config.clone(),
-            warc_path,
+            path,
             base_path: config.output_path.clone(),
-            settings: JobSettings {
-                host_centrality_threshold: config.host_centrality_threshold,
-                minimum_clean_words: config.minimum_clean_words,
-                batch_size: config.batch_size,
-                autocommit_after_num_inserts: config.autocommit_after_num_inserts,
-            },
+            settings: JobSettings::from(config),
         })
         .map(|job| {
             // map each Job instance to an IndexPointer instance
@@ -67,8 +78,47 @@ pub fn run(config: &config::IndexerConfig) -> Result<()> {
         .collect(); // collects the IndexPointer instances into a Vec
 
     // merge indexes (parallelized)
-    let index = merge(indexes)?;
-    crate::mv(index.path(), &config.output_path)?;
+    let merged = merge(indexes, config.simhash_dedup_threshold)?;
+    crate::mv(merged.index.path(), &config.output_path)?;
+
+    Ok(())
+}
+
+/// Insert the pages from the input files into the existing index at `path`.
+/// The files are processed one at a time, as they are all written to the same index.
+fn append(
+    config: &config::IndexerConfig,
+    path: &str,
+    worker: &IndexingWorker,
+    input_paths: Vec<String>,
+) -> Result<()> {
+    let mut index = Index::open(path)?;
+    let existing_segments = index.inverted_index.segment_ids();
+
+    for input_path in input_paths
+        .into_iter()
+        .skip(config.skip_warc_files.unwrap_or(0))
+        .take(config.limit_warc_files.unwrap_or(usize::MAX))
+    {
+        let job = Job {
+            source_config: config.source.clone(),
+            path: input_path,
+            base_path: path.to_string(),
+            settings: JobSettings::from(config),
+        };
+
+        job.process_into(worker, &mut index);
+    }
+
+    let num_replaced = index
+        .inverted_index
+        .remove_replaced_urls(&existing_segments)?;
+    tracing::info!("replaced {num_replaced} pages that were indexed again");
+
+    if let Some(max_distance) = config.simhash_dedup_threshold {
+        let num_removed = index.inverted_index.remove_near_duplicates(max_distance)?;
+        tracing::info!("removed {num_removed} near-duplicate pages");
+    }
 
     Ok(())
 }
@@ -80,59 +130,445 @@ pub fn run(config: &config::IndexerConfig) -> Result<()> {
 ///
 /// ## Arguments
 /// * `indexes` - A list of indexes to merge.
+/// * `simhash_dedup_threshold` - If set, pages whose simhash is within this many bits
+///   of a page with a higher pre-computed score are dropped from the merged index.
 /// ## Returns
-/// * A merged index.
-pub fn merge(indexes: Vec<IndexPointer>) -> Result<Index> {
-    let num_indexes = indexes.len();
-    let mut it = indexes.into_iter();
-    let num_cores = usize::from(std::thread::available_parallelism()?);
-
-    let mut threads = Vec::new();
-
-    for _ in 0..(num_cores + 1) {
-        let indexes = it
-            .by_ref()
-            .take(((num_indexes as f64) / (num_cores as f64)).ceil() as usize)
-            .collect_vec();
-
-        if indexes.is_empty() {
-            break;
+/// * A merged index of all the indexes that could be opened and merged,
+///   along with the paths of those that were skipped.
+pub fn merge(
+    indexes: Vec<IndexPointer>,
+    simhash_dedup_threshold: Option<u32>,
+) -> Result<MergedIndex> {
+    if let Some(max_distance) = simhash_dedup_threshold {
+        InvertedIndex::check_near_duplicate_distance(max_distance)?;
+    }
+
+    // rayon splits the indexes in halves, so they are merged as a balanced binary tree
+    // where all merges on the same level of the tree run concurrently.
+    let PartialMerge { index, skipped } = indexes
+        .into_par_iter()
+        .map(|pointer| PartialMerge::open(pointer.0))
+        .reduce_with(PartialMerge::merge)
+        .ok_or_else(|| anyhow!("no indexes to merge"))?;
+
+    let mut index = index.ok_or_else(|| anyhow!("none of the indexes could be merged"))?;
+
+    if !skipped.is_empty() {
+        tracing::warn!(
+            "skipped {} indexes that could not be merged: {:?}",
+            skipped.len(),
+            skipped
+        );
+    }
+
+    index.inverted_index.merge_into_max_segments(1)?;
+
+    if let Some(max_distance) = simhash_dedup_threshold {
+        let num_removed = index.inverted_index.remove_near_duplicates(max_distance)?;
+        tracing::info!("removed {num_removed} near-duplicate pages");
+    }
+
+    Ok(MergedIndex { index, skipped })
+}
+
+pub struct MergedIndex {
+    pub index: Index,
+    /// Paths of the indexes that failed to open or merge.
+    pub skipped: Vec<PathBuf>,
+}
+
+/// The result of merging a subset of the indexes. A corrupt index is
+/// skipped instead of failing the merge, so the work of all the other indexes is kept.
+struct PartialMerge {
+    index: Option<Index>,
+    skipped: Vec<PathBuf>,
+}
+
+impl PartialMerge {
+    fn open(path: String) -> Self {
+        match Index::open(&path) {
+            Ok(index) => Self {
+                index: Some(index),
+                skipped: Vec::new(),
+            },
+            Err(err) => {
+                tracing::error!("skipping index {path} that failed to open: {err:?}");
+
+                Self {
+                    index: None,
+                    skipped: vec![PathBuf::from(path)],
+                }
+            }
         }
+    }
 
-        threads.push(thread::spawn(move || {
-            let mut it = indexes.into_iter();
-            let mut index = Index::open(it.next().unwrap().0).unwrap();
+    fn merge(self, other: Self) -> Self {
+        let mut skipped = self.skipped;
+        skipped.extend(other.skipped);
 
-            for other in it {
-                let other_path = other.0;
-                let other = Index::open(&other_path).unwrap();
+        let index = match (self.index, other.index) {
+            (Some(index), Some(other)) => {
+                let index_path = index.path();
+                let other_path = other.path();
 
-                index = index.merge(other);
+                match merge_pair(index, other) {
+                    Ok(index) => Some(index),
+                    Err(err) => {
+                        tracing::error!(
+                            "skipping index {} that failed to merge: {err:?}",
+                            other_path.display()
+                        );
+                        skipped.push(other_path);
 
-                std::fs::remove_dir_all(other_path).unwrap();
+                        // the merge was rolled back, so `index` is as it was before the merge
+                        match Index::open(&index_path) {
+                            Ok(index) => Some(index),
+                            Err(err) => {
+                                tracing::error!(
+                                    "skipping index {} that failed to reopen: {err:?}",
+                                    index_path.display()
+                                );
+                                skipped.push(index_path);
+                                None
+                            }
+                        }
+                    }
+                }
             }
+            (index, None) | (None, index) => index,
+        };
+
+        Self { index, skipped }
+    }
+}
+
+/// Merge `other` into `index`, merge their segments into one and remove the directory of `other`.
+///
+/// Merging the segments of every pair rewrites each page once per level of the tree,
+/// so about log2(N) times for N indexes, where a single merge at the root would rewrite it once.
+/// This is deliberate: a single merge at the root would open every segment of every index at
+/// once and merge all of their term dictionaries in one pass on one thread. Merging per pair keeps
+/// each merge down to two segments, and the extra rewrites are spread over all threads
+/// since the pairs on the same level of the tree are merged concurrently.
+///
+/// If the merge fails, both indexes are rolled back to how they were before the merge.
+/// A panic during the merge is returned as an error, so it doesn't tear down
+/// the merges that are running concurrently in other threads.
+fn merge_pair(index: Index, other: Index) -> Result<Index> {
+    let other_path = other.path();
+
+    let index_snapshot = Snapshot::take(index.path())?;
+    let other_snapshot = match Snapshot::take(other.path()) {
+        Ok(snapshot) => snapshot,
+        Err(err) => {
+            index_snapshot.discard()?;
+            return Err(err);
+        }
+    };
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| -> Result<Index> {
+        let mut index = index.merge(other);
+        index.inverted_index.merge_into_max_segments(1)?;
+
+        Ok(index)
+    }))
+    .map_err(|_| anyhow!("failed to merge {}", other_path.display()))
+    .and_then(|res| res);
+
+    let index = match res {
+        Ok(index) => index,
+        Err(err) => {
+            index_snapshot.restore()?;
+            other_snapshot.restore()?;
+
+            return Err(err);
+        }
+    };
+
+    index_snapshot.discard()?;
+    other_snapshot.discard()?;
+
+    if other_path.exists() {
+        // the pages are already in `index`, so a leftover directory is not worth failing for
+        if let Err(err) = std::fs::remove_dir_all(&other_path) {
+            tracing::warn!("failed to remove {}: {err}", other_path.display());
+        }
+    }
+
+    Ok(index)
+}
+
+/// A copy of an index directory that is taken before the index is merged,
+/// so a failed merge can be rolled back.
+///
+/// The files are hard linked, which is cheap and safe as the segment files are never
+/// modified after they are written. The json files are copied as they may be
+/// rewritten in place.
+struct Snapshot {
+    original: PathBuf,
+    path: PathBuf,
+}
+
+impl Snapshot {
+    fn take(original: PathBuf) -> Result<Self> {
+        let mut path = original.components().as_path().as_os_str().to_owned();
+        path.push("_premerge");
+        let path = PathBuf::from(path);
+
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+
+        link_dir(&original, &path)?;
+
+        Ok(Self { original, path })
+    }
+
+    /// Replace the directory with the snapshot.
+    fn restore(self) -> Result<()> {
+        if self.original.exists() {
+            fs::remove_dir_all(&self.original)?;
+        }
+
+        fs::rename(&self.path, &self.original)?;
+
+        Ok(())
+    }
+
+    fn discard(self) -> Result<()> {
+        fs::remove_dir_all(&self.path)?;
+
+        Ok(())
+    }
+}
+
+fn link_dir(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)?;
+
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let from = entry.path();
+        let to = dst.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            link_dir(&from, &to)?;
+        } else if from.extension().is_some_and(|ext| ext == "json") {
+            fs::copy(&from, &to)?;
+        } else {
+            fs::hard_link(&from, &to)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::webpage::Webpage;
 
-            index.inverted_index.merge_into_max_segments(1).unwrap();
+    use super::*;
 
+    #[test]
+    fn merge_tree() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = |i: usize| dir.as_ref().join(format!("index_{i}"));
+
+        let mut pointers = Vec::new();
+        for i in 0..5 {
+            let mut index = Index::open_or_create(path(i)).unwrap();
+            index.prepare_writer().unwrap();
             index
-        }));
+                .insert(
+                    &Webpage::test_parse(
+                        &format!(
+                            r#"
+                            <html>
+                                <head>
+                                    <title>Page {i}</title>
+                                </head>
+                                <body>
+                                    this is the content of page number {i}
+                                </body>
+                            </html>
+                        "#
+                        ),
+                        &format!("https://www.example{i}.com/"),
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+            index.commit().unwrap();
+
+            pointers.push(IndexPointer::from(path(i).to_str().unwrap().to_string()));
+        }
+
+        let index = merge(pointers, None).unwrap().index;
+        assert_eq!(index.inverted_index.num_documents(), 5);
+        assert_eq!(index.inverted_index.num_segments(), 1);
+
+        // only the directory of the resulting index is left
+        assert_eq!((0..5).filter(|i| path(*i).exists()).count(), 1);
+
+        assert!(merge(Vec::new(), None).is_err());
+    }
+
+    #[test]
+    fn merge_pair_merges_segments() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = |i: usize| dir.as_ref().join(format!("index_{i}"));
+
+        let indexes = (0..2)
+            .map(|i| {
+                let mut index = Index::open_or_create(path(i)).unwrap();
+                index.prepare_writer().unwrap();
+
+                for j in 0..2 {
+                    index
+                        .insert(
+                            &Webpage::test_parse(
+                                &format!(
+                                    r#"
+                                    <html>
+                                        <head>
+                                            <title>Page {i} {j}</title>
+                                        </head>
+                                        <body>
+                                            this is the content of page number {i} {j}
+                                        </body>
+                                    </html>
+                                "#
+                                ),
+                                &format!("https://www.example{i}.com/{j}"),
+                            )
+                            .unwrap(),
+                        )
+                        .unwrap();
+                    index.commit().unwrap();
+                }
+
+                assert_eq!(index.inverted_index.num_segments(), 2);
+                index
+            })
+            .collect::<Vec<_>>();
+
+        let mut indexes = indexes.into_iter();
+        let index = merge_pair(indexes.next().unwrap(), indexes.next().unwrap()).unwrap();
+
+        assert_eq!(index.inverted_index.num_documents(), 4);
+        assert_eq!(index.inverted_index.num_segments(), 1);
+        assert!(!path(1).exists());
     }
 
-    let mut indexes = Vec::new();
-    for thread in threads {
-        indexes.push(thread.join().unwrap());
+    #[test]
+    fn snapshot_rolls_back_merge() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = |i: usize| dir.as_ref().join(format!("index_{i}"));
+
+        let indexes = (0..2)
+            .map(|i| {
+                let mut index = Index::open_or_create(path(i)).unwrap();
+                index.prepare_writer().unwrap();
+                index
+                    .insert(
+                        &Webpage::test_parse(
+                            &format!(
+                                r#"
+                                <html>
+                                    <head>
+                                        <title>Page {i}</title>
+                                    </head>
+                                    <body>
+                                        this is the content of page number {i}
+                                    </body>
+                                </html>
+                            "#
+                            ),
+                            &format!("https://www.example{i}.com/"),
+                        )
+                        .unwrap(),
+                    )
+                    .unwrap();
+                index.commit().unwrap();
+                index
+            })
+            .collect::<Vec<_>>();
+
+        let index_snapshot = Snapshot::take(path(0)).unwrap();
+        let other_snapshot = Snapshot::take(path(1)).unwrap();
+
+        let mut indexes = indexes.into_iter();
+        let index = indexes.next().unwrap().merge(indexes.next().unwrap());
+        assert_eq!(index.inverted_index.num_documents(), 2);
+        drop(index);
+
+        index_snapshot.restore().unwrap();
+        other_snapshot.restore().unwrap();
+
+        for i in 0..2 {
+            let index = Index::open(path(i)).unwrap();
+            assert_eq!(index.inverted_index.num_documents(), 1);
+        }
+
+        assert!(!dir.as_ref().join("index_0_premerge").exists());
     }
 
-    let mut it = indexes.into_iter();
-    let mut index = it.next().unwrap();
+    #[test]
+    fn open_missing_index() {
+        let dir = crate::gen_temp_dir().unwrap();
 
-    for other in it {
-        let other_path = other.path();
-        index = index.merge(other);
-        std::fs::remove_dir_all(other_path).unwrap();
+        assert!(Index::open(dir.as_ref().join("missing")).is_err());
+        assert!(!dir.as_ref().join("missing").exists());
     }
 
-    index.inverted_index.merge_into_max_segments(1).unwrap();
+    #[test]
+    fn merge_skips_corrupt_index() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = |i: usize| dir.as_ref().join(format!("index_{i}"));
 
-    Ok(index)
+        let mut pointers = Vec::new();
+        for i in 0..4 {
+            let mut index = Index::open_or_create(path(i)).unwrap();
+            index.prepare_writer().unwrap();
+            index
+                .insert(
+                    &Webpage::test_parse(
+                        &format!(
+                            r#"
+                            <html>
+                                <head>
+                                    <title>Page {i}</title>
+                                </head>
+                                <body>
+                                    this is the content of page number {i}
+                                </body>
+                            </html>
+                        "#
+                        ),
+                        &format!("https://www.example{i}.com/"),
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+            index.commit().unwrap();
+
+            pointers.push(IndexPointer::from(path(i).to_str().unwrap().to_string()));
+        }
+
+        // a file where an index directory is expected can't be opened
+        let bogus = dir.as_ref().join("bogus");
+        std::fs::write(&bogus, "not an index").unwrap();
+        pointers.insert(2, IndexPointer::from(bogus.to_str().unwrap().to_string()));
+
+        let merged = merge(pointers, None).unwrap();
+        assert_eq!(merged.index.inverted_index.num_documents(), 4);
+        assert_eq!(merged.index.inverted_index.num_segments(), 1);
+        assert_eq!(merged.skipped, vec![bogus.clone()]);
+        assert!(bogus.exists());
+
+        let merged = merge(
+            vec![IndexPointer::from(bogus.to_str().unwrap().to_string())],
+            None,
+        );
+        assert!(merged.is_err());
+    }
 }
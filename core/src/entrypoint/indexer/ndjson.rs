@@ -0,0 +1,146 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use crate::Result;
+
+use super::IndexableWebpage;
+
+/// The path that makes [`NdjsonReader::open`] read from stdin.
+pub const STDIN_PATH: &str = "-";
+
+/// Streams webpages from newline-delimited json, where each line is an
+/// object with the same fields as [`IndexableWebpage`]. Blank lines are skipped.
+pub struct NdjsonReader<R> {
+    lines: io::Lines<R>,
+}
+
+impl NdjsonReader<Box<dyn BufRead>> {
+    /// Open the file at `path`, or stdin if `path` is [`STDIN_PATH`].
+    pub fn open(path: &str) -> Result<Self> {
+        let reader: Box<dyn BufRead> = if path == STDIN_PATH {
+            Box::new(io::stdin().lock())
+        } else {
+            Box::new(BufReader::new(File::open(path)?))
+        };
+
+        Ok(Self::new(reader))
+    }
+}
+
+impl<R: BufRead> NdjsonReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = Result<IndexableWebpage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err.into())),
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            return Some(serde_json::from_str(&line).map_err(Into::into));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use file_store::temp::TempDir;
+
+    use crate::config::{DocumentSource, NdjsonSource};
+    use crate::entrypoint::indexer::{worker, IndexingWorker, Job, JobSettings};
+
+    use super::*;
+
+    #[test]
+    fn read_lines() {
+        let input = r#"{"url": "https://a.com/", "body": "<title>a</title>", "fetch_time_ms": 1}
+
+{"url": "https://b.com/", "body": "<title>b</title>", "fetch_time_ms": 2}
+"#;
+
+        let pages = NdjsonReader::new(input.as_bytes())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].url, "https://a.com/");
+        assert_eq!(pages[1].body, "<title>b</title>");
+        assert_eq!(pages[1].fetch_time_ms, 2);
+
+        assert!(NdjsonReader::new("not json".as_bytes())
+            .next()
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn index_ndjson_file() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let input_path = temp_dir.as_ref().join("pages.ndjson");
+        std::fs::write(
+            &input_path,
+            r#"{"url": "https://a.com/", "body": "<html><head><title>Homemade Heart Brownie Recipe</title></head><body>Example</body></html>", "fetch_time_ms": 0}
+{"url": "https://b.com/", "body": "<html><head><title>How To Use an iMac as a Monitor for a PC</title></head><body>Example</body></html>", "fetch_time_ms": 0}
+"#,
+        )
+        .unwrap();
+        let input_path = input_path.to_str().unwrap().to_string();
+
+        let worker = crate::block_on(IndexingWorker::new(worker::Config {
+            host_centrality_store_path: temp_dir
+                .as_ref()
+                .join("host_centrality")
+                .to_str()
+                .unwrap()
+                .to_string(),
+            page_centrality_store_path: None,
+            page_webgraph: None,
+            safety_classifier_path: None,
+            safety_classifier_threshold: None,
+            dual_encoder: None,
+            region_overrides: Default::default(),
+        }));
+
+        let job = Job {
+            source_config: DocumentSource::Ndjson(NdjsonSource {
+                paths: vec![input_path.clone()],
+            }),
+            path: input_path,
+            base_path: temp_dir
+                .as_ref()
+                .join("index")
+                .to_str()
+                .unwrap()
+                .to_string(),
+            settings: JobSettings {
+                host_centrality_threshold: None,
+                minimum_clean_words: None,
+                minimum_content_density: None,
+                batch_size: 1,
+                autocommit_after_num_inserts: 1,
+            },
+        };
+
+        let index = job.process(&worker);
+
+        assert_eq!(index.inverted_index.num_documents(), 2);
+    }
+}
@@ -23,7 +23,7 @@ use crate::index::Index;
 use crate::rake::RakeModel;
 use crate::ranking::SignalComputer;
 use crate::webgraph::{self, Edge, EdgeLimit, Node, NodeID};
-use crate::webpage::{safety_classifier, Html, Webpage};
+use crate::webpage::{region::RegionOverrides, safety_classifier, Html, Webpage};
 
 const MAX_BACKLINKS: EdgeLimit = EdgeLimit::Limit(1024);
 
@@ -51,7 +51,9 @@ pub struct Config {
     pub page_centrality_store_path: Option<String>,
     pub page_webgraph: Option<IndexerGraphConfig>,
     pub safety_classifier_path: Option<String>,
+    pub safety_classifier_threshold: Option<f32>,
     pub dual_encoder: Option<IndexerDualEncoderConfig>,
+    pub region_overrides: RegionOverrides,
 }
 
 impl From<IndexerConfig> for Config {
@@ -61,7 +63,9 @@ impl From<IndexerConfig> for Config {
             page_centrality_store_path: config.page_centrality_store_path,
             page_webgraph: config.page_webgraph.map(IndexerGraphConfig::from),
             safety_classifier_path: config.safety_classifier_path,
+            safety_classifier_threshold: config.safety_classifier_threshold,
             dual_encoder: config.dual_encoder,
+            region_overrides: config.region_overrides,
         }
     }
 }
@@ -172,6 +176,7 @@ pub struct IndexingWorker {
     job_settings: Option<JobSettings>,
     rake: RakeModel,
     dual_encoder: Option<DualEncoder>,
+    region_overrides: RegionOverrides,
     seen_urls: Mutex<bloom::BytesBloomFilter<String>>,
 }
 
@@ -199,10 +204,14 @@ impl IndexingWorker {
                 Some(graph) => Some(Webgraph::new(graph).await),
                 None => None,
             },
-            safety_classifier: config
-                .safety_classifier_path
-                .as_ref()
-                .map(|path| safety_classifier::Model::open(path).unwrap()),
+            safety_classifier: config.safety_classifier_path.as_ref().map(|path| {
+                let model = safety_classifier::Model::open(path).unwrap();
+
+                match config.safety_classifier_threshold {
+                    Some(threshold) => model.with_unknown_threshold(threshold),
+                    None => model,
+                }
+            }),
             job_settings: None,
             rake: RakeModel::default(),
             dual_encoder: config.dual_encoder.as_ref().map(|dual_encoder| {
@@ -216,6 +225,7 @@ impl IndexingWorker {
                     page_centrality_rank_threshold: dual_encoder.page_centrality_rank_threshold,
                 }
             }),
+            region_overrides: config.region_overrides,
             seen_urls: Mutex::new(bloom::BytesBloomFilter::new(10_000_000_000, 0.05)),
         }
     }
@@ -257,7 +267,7 @@ impl IndexingWorker {
             }
         };
 
-        if html.is_no_index() {
+        if html.robots_directives().noindex {
             return Err(anyhow::anyhow!("noindex"));
         }
 
@@ -327,6 +337,14 @@ impl IndexingWorker {
             }
         }
 
+        if let Some(minimum_content_density) =
+            self.job_settings.and_then(|s| s.minimum_content_density)
+        {
+            if page.html.content_density() < minimum_content_density {
+                return Err(anyhow::anyhow!("low content density"));
+            }
+        }
+
         Ok(())
     }
 
@@ -468,6 +486,7 @@ impl IndexingWorker {
             self.set_page_centralities(&mut webpage);
             self.set_keywords(&mut webpage);
             self.set_safety_classification(&mut webpage);
+            self.region_overrides.apply(&mut webpage);
 
             signal_computer.set_current_timestamp(Utc::now().timestamp().max(0) as usize);
             webpage.pre_computed_score = signal_computer.precompute_score(&webpage);
@@ -487,7 +506,7 @@ impl IndexingWorker {
 mod tests {
     use file_store::temp::TempDir;
 
-    use crate::config::WarcSource;
+    use crate::config::{DocumentSource, WarcSource};
 
     use super::*;
 
@@ -504,6 +523,7 @@ mod tests {
                 page_centrality_store_path: None,
                 page_webgraph: None,
                 safety_classifier_path: None,
+                safety_classifier_threshold: None,
                 dual_encoder: Some(IndexerDualEncoderConfig {
                     model_path: data_path.to_str().unwrap().to_string(),
                     page_centrality_rank_threshold: threshold,
@@ -516,15 +536,21 @@ mod tests {
                     .to_string(),
                 limit_warc_files: None,
                 skip_warc_files: None,
-                warc_source: WarcSource::Local(crate::config::LocalConfig {
+                source: DocumentSource::Warc(WarcSource::Local(crate::config::LocalConfig {
                     folder: temp_dir.as_ref().join("warc").to_str().unwrap().to_string(),
                     names: vec!["".to_string()],
-                }),
+                    date_filter: None,
+                })),
                 host_centrality_threshold: None,
                 minimum_clean_words: None,
+                minimum_content_density: None,
+                simhash_dedup_threshold: None,
+                adservers_path: None,
+                append_to: None,
                 batch_size: 10,
                 autocommit_after_num_inserts:
                     crate::config::defaults::Indexing::autocommit_after_num_inserts(),
+                region_overrides: Default::default(),
             }
             .into(),
         ));
@@ -532,6 +558,42 @@ mod tests {
         (worker, temp_dir)
     }
 
+    #[test]
+    fn skip_noindex_pages() {
+        let temp_dir = TempDir::new().unwrap();
+        let worker = crate::block_on(IndexingWorker::new(Config {
+            host_centrality_store_path: temp_dir
+                .as_ref()
+                .join("host_centrality")
+                .to_str()
+                .unwrap()
+                .to_string(),
+            page_centrality_store_path: None,
+            page_webgraph: None,
+            safety_classifier_path: None,
+            safety_classifier_threshold: None,
+            dual_encoder: None,
+            region_overrides: Default::default(),
+        }));
+
+        let page = |robots: Option<&str>| IndexableWebpage {
+            url: "https://a.com/".to_string(),
+            body: format!(
+                "<html><head><title>Example</title>{}</head><body>Example</body></html>",
+                robots
+                    .map(|content| format!(r#"<meta name="robots" content="{content}" />"#))
+                    .unwrap_or_default()
+            ),
+            fetch_time_ms: 0,
+        };
+
+        assert!(worker.prepare(&page(Some("noindex"))).is_err());
+        assert!(worker.prepare(&page(Some("none"))).is_err());
+        assert!(worker.prepare(&page(Some("nofollow"))).is_ok());
+        assert!(worker.prepare(&page(Some("noarchive"))).is_ok());
+        assert!(worker.prepare(&page(None)).is_ok());
+    }
+
     #[test]
     fn title_embeddings() {
         let data_path = Path::new("../../data/summarizer/dual_encoder");
@@ -41,6 +41,7 @@ const INDEXING_RETRIES: usize = 3;
 
 sonic_service!(
     LiveIndexService,
+    version = 1,
     [
         RetrieveWebsites,
         Search,
@@ -200,7 +201,9 @@ impl LiveIndexService {
                         cluster: cluster_handle.clone(),
                     }),
                     safety_classifier_path: config.safety_classifier_path.clone(),
+                    safety_classifier_threshold: config.safety_classifier_threshold,
                     dual_encoder: None,
+                    region_overrides: Default::default(),
                 },
             )
             .await?,
@@ -49,6 +49,7 @@ fn config<P: AsRef<Path>>(path: P) -> LiveIndexConfig {
             .to_string(),
         page_centrality_store_path: None,
         safety_classifier_path: None,
+        safety_classifier_threshold: None,
         host_centrality_threshold: None,
         minimum_clean_words: None,
         gossip_seed_nodes: None,
@@ -411,7 +412,9 @@ async fn test_meta_segments() -> Result<()> {
         page_centrality_store_path: config.page_centrality_store_path.clone(),
         page_webgraph: None,
         safety_classifier_path: None,
+        safety_classifier_threshold: None,
         dual_encoder: None,
+        region_overrides: Default::default(),
     };
 
     let index = LiveIndex::new(&config.index_path, indexer_config.clone()).await?;
@@ -450,7 +453,9 @@ async fn test_segment_compaction() -> Result<()> {
         page_centrality_store_path: config.page_centrality_store_path.clone(),
         page_webgraph: None,
         safety_classifier_path: None,
+        safety_classifier_threshold: None,
         dual_encoder: None,
+        region_overrides: Default::default(),
     };
 
     let index = Arc::new(LiveIndex::new(&config.index_path, indexer_config).await?);
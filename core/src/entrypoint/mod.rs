@@ -28,7 +28,12 @@ pub use webgraph::Webgraph;
 pub mod admin;
 pub mod live_index;
 
-use crate::{config, warc::WarcFile};
+use std::fs::File;
+
+use crate::{
+    config,
+    warc::{RecordIterator, WarcFile},
+};
 
 fn download_all_warc_files<'a>(
     warc_paths: &'a [String],
@@ -53,3 +58,22 @@ fn download_all_warc_files<'a>(
         Some(res.unwrap())
     })
 }
+
+/// Like [`download_all_warc_files`], but each file is streamed from disk
+/// so memory use doesn't grow with the size of the warc files.
+fn stream_all_warc_files<'a>(
+    warc_paths: &'a [String],
+    source: &'a config::WarcSource,
+) -> impl Iterator<Item = RecordIterator<File>> + 'a {
+    warc_paths.iter().filter_map(|warc_path| {
+        debug!("opening warc file {}", warc_path);
+
+        match WarcFile::download_streaming(source, warc_path) {
+            Ok(records) => Some(records),
+            Err(err) => {
+                error!("error while downloading: {:?}", err);
+                None
+            }
+        }
+    })
+}
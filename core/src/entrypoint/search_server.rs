@@ -23,10 +23,11 @@ use crate::{
     Result,
 };
 
-use super::api::{Size, SizeResponse};
+use super::api::{Drain, DrainError, Size, SizeByField, SizeResponse, Undrain};
 
 sonic_service!(
     SearchService,
+    version = 1,
     [
         RetrieveWebsites,
         Search,
@@ -35,13 +36,15 @@ sonic_service!(
         TopKeyPhrases,
         Size,
         GetSiteUrls,
+        SizeByField,
+        Drain,
+        Undrain,
     ]
 );
 
 pub struct SearchService {
     local_searcher: LocalSearcher<Index>,
     // dropping the handle leaves the cluster
-    #[allow(unused)]
     cluster_handle: Cluster,
 }
 
@@ -61,6 +64,7 @@ impl SearchService {
 
         local_searcher.set_collector_config(config.collector);
         local_searcher.set_snippet_config(config.snippet);
+        local_searcher.set_shard(config.shard);
 
         let cluster_handle = Cluster::join(
             Member::new(Service::Searcher {
@@ -151,8 +155,24 @@ impl sonic::service::Message<SearchService> for TopKeyPhrases {
 
 pub async fn run(config: config::SearchServerConfig) -> Result<()> {
     let addr = config.host;
+    let unix_socket = config.unix_socket.clone();
     let server = SearchService::new(config).await?.bind(addr).await.unwrap();
 
+    if let Some(path) = unix_socket {
+        let addr = sonic::Address::Unix(path);
+        let uds_server = server.bind_additional(&addr).await?;
+
+        info!("search server is ready to accept requests on {}", addr);
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = uds_server.accept().await {
+                    tracing::error!("{:?}", e);
+                }
+            }
+        });
+    }
+
     info!("search server is ready to accept requests on {}", addr);
 
     loop {
@@ -176,6 +196,57 @@ impl sonic::service::Message<SearchService> for Size {
     }
 }
 
+impl sonic::service::Message<SearchService> for SizeByField {
+    type Response = Option<inverted_index::FieldSizes>;
+    async fn handle(self, server: &SearchService) -> Self::Response {
+        server
+            .local_searcher
+            .index()
+            .guard()
+            .inverted_index()
+            .size_by_field()
+            .ok()
+    }
+}
+
+impl sonic::service::Message<SearchService> for Drain {
+    type Response = Result<(), DrainError>;
+    async fn handle(self, server: &SearchService) -> Self::Response {
+        if server.cluster_handle.self_node().map(|member| &member.id) != Some(&self.member) {
+            return Err(DrainError::UnknownMember(self.member));
+        }
+
+        server
+            .cluster_handle
+            .set_drained(true)
+            .await
+            .map_err(|err| DrainError::Failed(err.to_string()))?;
+
+        info!("drained {}", self.member);
+
+        Ok(())
+    }
+}
+
+impl sonic::service::Message<SearchService> for Undrain {
+    type Response = Result<(), DrainError>;
+    async fn handle(self, server: &SearchService) -> Self::Response {
+        if server.cluster_handle.self_node().map(|member| &member.id) != Some(&self.member) {
+            return Err(DrainError::UnknownMember(self.member));
+        }
+
+        server
+            .cluster_handle
+            .set_drained(false)
+            .await
+            .map_err(|err| DrainError::Failed(err.to_string()))?;
+
+        info!("undrained {}", self.member);
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
 pub struct GetSiteUrls {
     pub site: String,
@@ -201,3 +272,81 @@ impl sonic::service::Message<SearchService> for GetSiteUrls {
         SiteUrls { urls }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use file_store::gen_temp_dir;
+
+    use crate::{
+        distributed::{member::ShardId, sonic::replication::ReusableClientManager},
+        free_socket_addr,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn drained_member_is_not_routed() -> Result<()> {
+        let dir = gen_temp_dir()?;
+        let config = config::SearchServerConfig {
+            gossip_seed_nodes: None,
+            gossip_addr: free_socket_addr(),
+            shard: ShardId::new(0),
+            index_path: dir.as_ref().join("index").to_str().unwrap().to_string(),
+            linear_model_path: None,
+            dual_encoder_model_path: None,
+            host: free_socket_addr(),
+            unix_socket: None,
+            collector: Default::default(),
+            snippet: Default::default(),
+        };
+        let host = config.host;
+        let gossip_addr = config.gossip_addr;
+
+        let service = SearchService::new(config).await?;
+        let member = service.cluster_handle.self_node().unwrap().id.clone();
+        let server = service.bind(host).await.unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = server.accept().await {
+                    tracing::error!("{:?}", e);
+                }
+            }
+        });
+
+        let cluster = Cluster::join_as_spectator(free_socket_addr(), vec![gossip_addr]).await?;
+        cluster.await_member(|m| m.id == member).await;
+        assert!(!SearchService::new_client(&cluster).await.is_empty());
+
+        let mut conn: sonic::service::Connection<SearchService> =
+            sonic::service::Connection::create(host).await?;
+
+        assert!(conn
+            .send(Drain {
+                member: "unknown".to_string(),
+            })
+            .await?
+            .is_err());
+
+        conn.send(Drain {
+            member: member.clone(),
+        })
+        .await??;
+
+        cluster.await_member(|m| m.id == member && m.drained).await;
+        assert!(SearchService::new_client(&cluster).await.is_empty());
+
+        let size = conn.send(Size).await?;
+        assert_eq!(size.pages, 0);
+
+        conn.send(Undrain {
+            member: member.clone(),
+        })
+        .await??;
+
+        cluster.await_member(|m| m.id == member && !m.drained).await;
+        assert!(!SearchService::new_client(&cluster).await.is_empty());
+
+        Ok(())
+    }
+}
@@ -17,15 +17,15 @@ use crate::{
     canon_index::CanonicalIndex,
     config::{self, WarcSource, WebgraphConstructConfig},
     entrypoint::download_all_warc_files,
-    webgraph::{self, Node, NodeID, WebgraphWriter},
-    webpage::{url_ext::UrlExt, Html},
+    webgraph::{self, MergeControl, Node, NodeID, WebgraphWriter},
+    webpage::{html::links::RelFlags, url_ext::UrlExt, Html},
     Result,
 };
 use anyhow::bail;
 use itertools::Itertools;
 use url::Url;
 
-use std::{path::Path, sync::Arc};
+use std::{collections::HashSet, path::Path, sync::Arc};
 use tokio::pin;
 use tracing::{info, trace};
 
@@ -86,6 +86,11 @@ pub fn open_page_graph_writer<P: AsRef<Path>>(
     )
 }
 
+/// Links where the author does not vouch for the destination.
+const NOFOLLOW_RELS: RelFlags = RelFlags::NOFOLLOW
+    .union(RelFlags::SPONSORED)
+    .union(RelFlags::UGC);
+
 fn canonical_or_self(index: &CanonicalIndex, url: Url) -> Url {
     if let Some(url) = index.get(&url).unwrap() {
         url
@@ -98,9 +103,75 @@ pub struct WebgraphWorker {
     pub host_graph: Option<webgraph::WebgraphWriter>,
     pub page_graph: Option<webgraph::WebgraphWriter>,
     pub canonical_index: Option<Arc<CanonicalIndex>>,
+    pub exclude_nofollow_host_links: bool,
+    pub exclude_nofollow_page_links: bool,
 }
 
 impl WebgraphWorker {
+    /// `seen_page_edges` holds the page graph edges that have already been inserted
+    /// from the current warc file. Pages that canonicalize to the same url would otherwise
+    /// insert the same edges several times.
+    fn insert_links(
+        &mut self,
+        webpage: &Html,
+        seen_page_edges: &mut HashSet<(NodeID, NodeID, String)>,
+    ) {
+        if webpage.robots_directives().nofollow {
+            return;
+        }
+
+        for mut link in webpage
+            .anchor_links()
+            .into_iter()
+            .filter(|link| matches!(link.destination.scheme(), "http" | "https"))
+        {
+            let mut source = link.source.clone();
+            let mut destination = link.destination.clone();
+
+            if let Some(index) = &self.canonical_index {
+                source = canonical_or_self(index, source);
+                destination = canonical_or_self(index, destination);
+            }
+
+            let is_nofollow = link.rel.intersects(NOFOLLOW_RELS);
+
+            link.text = link.text.chars().take(128).collect();
+
+            let mut source = Node::from(source);
+
+            let mut destination = Node::from(destination);
+
+            trace!("inserting link {:?}", link);
+            let is_new_page_edge = self.page_graph.is_some()
+                && !(is_nofollow && self.exclude_nofollow_page_links)
+                && seen_page_edges.insert((source.id(), destination.id(), link.text.clone()));
+
+            if let Some(graph) = self.page_graph.as_mut().filter(|_| is_new_page_edge) {
+                graph.insert(
+                    source.clone(),
+                    destination.clone(),
+                    link.text.clone(),
+                    link.rel,
+                )
+            }
+
+            let dest_domain = link.destination.root_domain();
+            let source_domain = link.source.root_domain();
+            if dest_domain.is_some() && source_domain.is_some() && dest_domain != source_domain {
+                source = source.into_host();
+                destination = destination.into_host();
+
+                if let Some(graph) = self
+                    .host_graph
+                    .as_mut()
+                    .filter(|_| !(is_nofollow && self.exclude_nofollow_host_links))
+                {
+                    graph.insert(source, destination, link.text, link.rel)
+                }
+            }
+        }
+    }
+
     pub fn process_job(&mut self, job: &Job) {
         let name = job.warc_paths.first().unwrap().split('/').last().unwrap();
 
@@ -111,6 +182,8 @@ impl WebgraphWorker {
         let warc_files = download_all_warc_files(&job.warc_paths, &source);
         pin!(warc_files);
 
+        let mut seen_page_edges = HashSet::new();
+
         for file in warc_files.by_ref() {
             for record in file.records().flatten() {
                 let webpage =
@@ -122,49 +195,7 @@ impl WebgraphWorker {
                         }
                     };
 
-                for mut link in webpage
-                    .anchor_links()
-                    .into_iter()
-                    .filter(|link| matches!(link.destination.scheme(), "http" | "https"))
-                {
-                    let mut source = link.source.clone();
-                    let mut destination = link.destination.clone();
-
-                    if let Some(index) = &self.canonical_index {
-                        source = canonical_or_self(index, source);
-                        destination = canonical_or_self(index, destination);
-                    }
-
-                    link.text = link.text.chars().take(128).collect();
-
-                    let mut source = Node::from(source);
-
-                    let mut destination = Node::from(destination);
-
-                    trace!("inserting link {:?}", link);
-                    if let Some(graph) = self.page_graph.as_mut() {
-                        graph.insert(
-                            source.clone(),
-                            destination.clone(),
-                            link.text.clone(),
-                            link.rel,
-                        )
-                    }
-
-                    let dest_domain = link.destination.root_domain();
-                    let source_domain = link.source.root_domain();
-                    if dest_domain.is_some()
-                        && source_domain.is_some()
-                        && dest_domain != source_domain
-                    {
-                        source = source.into_host();
-                        destination = destination.into_host();
-
-                        if let Some(graph) = self.host_graph.as_mut() {
-                            graph.insert(source, destination, link.text, link.rel)
-                        }
-                    }
-                }
+                self.insert_links(&webpage, &mut seen_page_edges);
             }
 
             if let Some(graph) = self.host_graph.as_mut() {
@@ -173,6 +204,10 @@ impl WebgraphWorker {
             if let Some(graph) = self.page_graph.as_mut() {
                 graph.commit()
             }
+
+            // only remember the edges of a single file so the set doesn't
+            // grow with the size of the job
+            seen_page_edges.clear();
         }
 
         info!("{} done", name);
@@ -248,6 +283,8 @@ impl Webgraph {
                     .as_ref()
                     .map(|p| open_page_graph_writer(p, host_centrality_rank_store.clone())),
                 canonical_index: canonical_index.clone(),
+                exclude_nofollow_host_links: config.exclude_nofollow_host_links,
+                exclude_nofollow_page_links: config.exclude_nofollow_page_links,
             };
 
             let jobs = jobs.clone();
@@ -285,14 +322,23 @@ impl Webgraph {
         }
 
         if config.merge_all_segments {
+            let control = |name: &'static str| {
+                MergeControl::new().with_progress(move |progress| {
+                    info!(
+                        "merged {}/{} nodes of the {name} graph",
+                        progress.merged, progress.total
+                    );
+                })
+            };
+
             if let Some(host) = host_graph.as_mut() {
                 host.optimize_read(); // save space in id2node db
-                host.merge_all_segments(Default::default())?;
+                host.merge_all_segments_with(Default::default(), &control("host"))?;
             }
 
             if let Some(page) = page_graph.as_mut() {
                 page.optimize_read(); // save space in id2node db
-                page.merge_all_segments(Default::default())?;
+                page.merge_all_segments_with(Default::default(), &control("page"))?;
             }
         }
 
@@ -309,3 +355,185 @@ impl Webgraph {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::webgraph::EdgeLimit;
+
+    use super::*;
+
+    #[test]
+    fn exclude_nofollow_links() {
+        let html = Html::parse_without_text(
+            r#"
+            <html>
+                <body>
+                    <a href="https://b.com/">follow</a>
+                    <a href="https://c.com/" rel="nofollow">nofollow</a>
+                    <a href="https://d.com/" rel="sponsored">sponsored</a>
+                    <a href="https://e.com/" rel="ugc">ugc</a>
+                </body>
+            </html>
+            "#,
+            "https://a.com/",
+        )
+        .unwrap();
+
+        let temp_dir = crate::gen_temp_dir().unwrap();
+
+        for exclude in [false, true] {
+            let mut worker = WebgraphWorker {
+                host_graph: Some(open_host_graph_writer(
+                    temp_dir.as_ref().join(format!("host_{exclude}")),
+                    None,
+                )),
+                page_graph: Some(open_page_graph_writer(
+                    temp_dir.as_ref().join(format!("page_{exclude}")),
+                    None,
+                )),
+                canonical_index: None,
+                exclude_nofollow_host_links: exclude,
+                exclude_nofollow_page_links: false,
+            };
+
+            worker.insert_links(&html, &mut HashSet::new());
+
+            let host = worker.host_graph.unwrap().finalize();
+            let page = worker.page_graph.unwrap().finalize();
+
+            let host_edges = host.outgoing_edges(
+                Node::from("https://a.com/").into_host(),
+                EdgeLimit::Unlimited,
+            );
+            let page_edges =
+                page.outgoing_edges(Node::from("https://a.com/"), EdgeLimit::Unlimited);
+
+            assert_eq!(page_edges.len(), 4);
+
+            if exclude {
+                assert_eq!(host_edges.len(), 1);
+                assert_eq!(host_edges[0].to, Node::from("https://b.com/").into_host());
+            } else {
+                assert_eq!(host_edges.len(), 4);
+            }
+        }
+    }
+
+    #[test]
+    fn collapse_canonical_duplicates() {
+        let temp_dir = crate::gen_temp_dir().unwrap();
+
+        let mut index = CanonicalIndex::open(temp_dir.as_ref().join("canonical")).unwrap();
+        index
+            .insert(
+                Url::parse("https://a.com/article?ref=feed").unwrap(),
+                Url::parse("https://a.com/article").unwrap(),
+            )
+            .unwrap();
+        index.commit().unwrap();
+
+        let mut worker = WebgraphWorker {
+            host_graph: None,
+            page_graph: Some(open_page_graph_writer(temp_dir.as_ref().join("page"), None)),
+            canonical_index: Some(Arc::new(index)),
+            exclude_nofollow_host_links: false,
+            exclude_nofollow_page_links: false,
+        };
+
+        let mut seen_page_edges = HashSet::new();
+
+        for url in ["https://a.com/article", "https://a.com/article?ref=feed"] {
+            let html = Html::parse_without_text(
+                r#"<html><body><a href="https://b.com/">b</a></body></html>"#,
+                url,
+            )
+            .unwrap();
+
+            worker.insert_links(&html, &mut seen_page_edges);
+        }
+
+        assert_eq!(seen_page_edges.len(), 1);
+
+        let page = worker.page_graph.unwrap().finalize();
+        let edges = page.outgoing_edges(Node::from("https://a.com/article"), EdgeLimit::Unlimited);
+
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].to, Node::from("https://b.com/"));
+        assert!(page
+            .outgoing_edges(
+                Node::from("https://a.com/article?ref=feed"),
+                EdgeLimit::Unlimited
+            )
+            .is_empty());
+    }
+
+    #[test]
+    fn excluded_nofollow_edge_is_not_seen() {
+        let temp_dir = crate::gen_temp_dir().unwrap();
+
+        let mut worker = WebgraphWorker {
+            host_graph: None,
+            page_graph: Some(open_page_graph_writer(temp_dir.as_ref().join("page"), None)),
+            canonical_index: None,
+            exclude_nofollow_host_links: false,
+            exclude_nofollow_page_links: true,
+        };
+
+        let mut seen_page_edges = HashSet::new();
+
+        let html = Html::parse_without_text(
+            r#"<html><body><a rel="nofollow" href="https://b.com/">b</a><a href="https://b.com/">b</a></body></html>"#,
+            "https://a.com/",
+        )
+        .unwrap();
+
+        worker.insert_links(&html, &mut seen_page_edges);
+
+        let page = worker.page_graph.unwrap().finalize();
+        let edges = page.outgoing_edges(Node::from("https://a.com/"), EdgeLimit::Unlimited);
+
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].to, Node::from("https://b.com/"));
+    }
+
+    #[test]
+    fn robots_nofollow_page() {
+        let temp_dir = crate::gen_temp_dir().unwrap();
+
+        let mut worker = WebgraphWorker {
+            host_graph: None,
+            page_graph: Some(open_page_graph_writer(temp_dir.as_ref().join("page"), None)),
+            canonical_index: None,
+            exclude_nofollow_host_links: false,
+            exclude_nofollow_page_links: false,
+        };
+
+        let mut seen_page_edges = HashSet::new();
+
+        for (url, robots) in [
+            ("https://a.com/", "nofollow"),
+            ("https://c.com/", "noarchive"),
+        ] {
+            let html = Html::parse_without_text(
+                &format!(
+                    r#"<html><head><meta name="robots" content="{robots}" /></head><body><a href="https://b.com/">b</a></body></html>"#
+                ),
+                url,
+            )
+            .unwrap();
+
+            worker.insert_links(&html, &mut seen_page_edges);
+        }
+
+        let page = worker.page_graph.unwrap().finalize();
+
+        assert!(page
+            .outgoing_edges(Node::from("https://a.com/"), EdgeLimit::Unlimited)
+            .is_empty());
+        assert_eq!(
+            page.outgoing_edges(Node::from("https://c.com/"), EdgeLimit::Unlimited)
+                .len(),
+            1
+        );
+    }
+}
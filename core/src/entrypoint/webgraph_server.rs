@@ -49,6 +49,7 @@ pub struct WebGraphService {
 
 sonic_service!(
     WebGraphService,
+    version = 1,
     [
         GetNode,
         IngoingEdges,
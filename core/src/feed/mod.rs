@@ -5,6 +5,7 @@
 
 use std::str::FromStr;
 
+use chrono::{DateTime, Utc};
 use url::Url;
 
 mod parser;
@@ -61,6 +62,26 @@ pub struct Feed {
     pub kind: FeedKind,
 }
 
+/// An item (rss) or entry (atom) of a feed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedEntry {
+    pub url: Url,
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    /// When the entry was published. Atom entries without a `published`
+    /// date fall back to when they were last `updated`.
+    pub published: Option<DateTime<Utc>>,
+}
+
 pub struct ParsedFeed {
-    pub links: Vec<DatedUrl>,
+    pub entries: Vec<FeedEntry>,
+}
+
+impl ParsedFeed {
+    pub fn links(&self) -> impl Iterator<Item = DatedUrl> + '_ {
+        self.entries.iter().map(|entry| DatedUrl {
+            url: entry.url.clone(),
+            last_modified: entry.published,
+        })
+    }
 }
@@ -19,57 +19,101 @@ use chrono::{DateTime, Utc};
 use quick_xml::events::Event;
 use url::Url;
 
-use crate::dated_url::DatedUrl;
+use super::{FeedEntry, FeedKind, ParsedFeed};
 
-use super::{FeedKind, ParsedFeed};
+/// The fields of an item or entry seen so far. An entry is only kept
+/// if it has a valid url.
+#[derive(Default)]
+struct PartialEntry {
+    url: Option<Url>,
+    title: Option<String>,
+    summary: Option<String>,
+    published: Option<DateTime<Utc>>,
+    updated: Option<DateTime<Utc>>,
+}
+
+impl PartialEntry {
+    fn finish(self) -> Option<FeedEntry> {
+        Some(FeedEntry {
+            url: self.url?,
+            title: self.title,
+            summary: self.summary,
+            published: self.published.or(self.updated),
+        })
+    }
+}
+
+/// Read the text content of the element that was just started, up to and
+/// including its end tag. Text in nested elements and CDATA sections is included.
+/// Returns `None` if the element has no text.
+fn read_text(reader: &mut quick_xml::Reader<&[u8]>) -> Result<Option<String>> {
+    let mut buf = Vec::new();
+    let mut text = String::new();
+    let mut depth = 0;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            }
+            Event::Text(e) => text.push_str(&e.unescape()?),
+            Event::CData(e) => text.push_str(&String::from_utf8_lossy(&e)),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let text = text.trim();
+
+    if text.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(text.to_string()))
+    }
+}
 
 fn parse_rss(feed: &str) -> Result<ParsedFeed> {
     let mut reader = quick_xml::Reader::from_str(feed);
 
     let mut buf = Vec::new();
-    let mut links = Vec::new();
-    let mut inside_link = false;
-    let mut inside_item = false;
-    let mut current_link: Option<Url> = None;
-    let mut current_date: Option<DateTime<Utc>> = None;
+    let mut entries = Vec::new();
+    let mut current: Option<PartialEntry> = None;
 
     loop {
         match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) if e.name().as_ref() == b"link" => {
-                inside_link = true;
-            }
-            Ok(Event::End(ref e)) if e.name().as_ref() == b"link" => {
-                inside_link = false;
-            }
             Ok(Event::Start(ref e)) if e.name().as_ref() == b"item" => {
-                inside_item = true;
-                current_link = None;
-                current_date = None;
+                current = Some(PartialEntry::default());
             }
             Ok(Event::End(ref e)) if e.name().as_ref() == b"item" => {
-                inside_item = false;
-                if let Some(link) = current_link.take() {
-                    links.push(DatedUrl {
-                        url: link,
-                        last_modified: current_date,
-                    });
-                }
-            }
-            Ok(Event::Text(e)) if inside_item && inside_link => {
-                let link = e.unescape()?;
-                if let Ok(link) = Url::parse(&link) {
-                    current_link = Some(link);
+                if let Some(entry) = current.take().and_then(PartialEntry::finish) {
+                    entries.push(entry);
                 }
             }
-            Ok(Event::Start(ref e))
-                if e.name().as_ref() == b"pubDate" || e.name().as_ref() == b"lastBuildDate" =>
-            {
-                if inside_item {
-                    if let Ok(Event::Text(e)) = reader.read_event_into(&mut buf) {
-                        if let Ok(date) = DateTime::parse_from_rfc2822(&e.unescape()?) {
-                            current_date = Some(date.with_timezone(&Utc));
+            Ok(Event::Start(ref e)) if current.is_some() => {
+                let name = e.name().as_ref().to_vec();
+                let text = read_text(&mut reader)?;
+                let entry = current.as_mut().unwrap();
+
+                match name.as_slice() {
+                    b"link" => {
+                        if let Some(link) = text.and_then(|link| Url::parse(&link).ok()) {
+                            entry.url = Some(link);
+                        }
+                    }
+                    b"title" => entry.title = text,
+                    b"description" => entry.summary = text,
+                    b"pubDate" | b"lastBuildDate" => {
+                        if let Some(date) =
+                            text.and_then(|date| DateTime::parse_from_rfc2822(&date).ok())
+                        {
+                            entry.published = Some(date.with_timezone(&Utc));
                         }
                     }
+                    _ => {}
                 }
             }
             Err(e) => {
@@ -81,60 +125,62 @@ fn parse_rss(feed: &str) -> Result<ParsedFeed> {
         }
     }
 
-    Ok(ParsedFeed { links })
+    Ok(ParsedFeed { entries })
+}
+
+fn parse_rfc3339(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
 }
 
 fn parse_atom(feed: &str) -> Result<ParsedFeed> {
     let mut reader = quick_xml::Reader::from_str(feed);
 
     let mut buf = Vec::new();
-    let mut links = Vec::new();
-
-    let mut inside_entry = false;
-    let mut current_link: Option<Url> = None;
-    let mut current_date: Option<DateTime<Utc>> = None;
+    let mut entries = Vec::new();
+    let mut current: Option<PartialEntry> = None;
 
     loop {
         match reader.read_event_into(&mut buf) {
             Ok(Event::Start(ref e)) if e.name().as_ref() == b"entry" => {
-                inside_entry = true;
-                current_link = None;
-                current_date = None;
+                current = Some(PartialEntry::default());
             }
             Ok(Event::End(ref e)) if e.name().as_ref() == b"entry" => {
-                inside_entry = false;
-                if let Some(link) = current_link.take() {
-                    links.push(DatedUrl {
-                        url: link,
-                        last_modified: current_date,
-                    });
+                if let Some(entry) = current.take().and_then(PartialEntry::finish) {
+                    entries.push(entry);
                 }
             }
-            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
-                if inside_entry && e.name().as_ref() == b"link" {
-                    if let Some(Ok(href)) = e
-                        .attributes()
-                        .filter(std::result::Result::is_ok)
-                        .find(|attr| attr.as_ref().unwrap().key.as_ref() == b"href")
-                    {
-                        if let Ok(href) = href
-                            .unescape_value()
-                            .map_err(|e| anyhow!(e))
-                            .and_then(|v| Url::parse(&v).map_err(|e| anyhow!(e)))
-                        {
-                            current_link = Some(href);
-                        }
-                    }
-                } else if inside_entry
-                    && (e.name().as_ref() == b"updated" || e.name().as_ref() == b"published")
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e))
+                if current.is_some() && e.name().as_ref() == b"link" =>
+            {
+                if let Some(Ok(href)) = e
+                    .attributes()
+                    .filter(std::result::Result::is_ok)
+                    .find(|attr| attr.as_ref().unwrap().key.as_ref() == b"href")
                 {
-                    if let Ok(Event::Text(e)) = reader.read_event_into(&mut buf) {
-                        if let Ok(date) = DateTime::parse_from_rfc3339(&e.unescape()?) {
-                            current_date = Some(date.with_timezone(&Utc));
-                        }
+                    if let Ok(href) = href
+                        .unescape_value()
+                        .map_err(|e| anyhow!(e))
+                        .and_then(|v| Url::parse(&v).map_err(|e| anyhow!(e)))
+                    {
+                        current.as_mut().unwrap().url = Some(href);
                     }
                 }
             }
+            Ok(Event::Start(ref e)) if current.is_some() => {
+                let name = e.name().as_ref().to_vec();
+                let text = read_text(&mut reader)?;
+                let entry = current.as_mut().unwrap();
+
+                match name.as_slice() {
+                    b"title" => entry.title = text,
+                    b"summary" => entry.summary = text,
+                    b"published" => entry.published = text.and_then(|d| parse_rfc3339(&d)),
+                    b"updated" => entry.updated = text.and_then(|d| parse_rfc3339(&d)),
+                    _ => {}
+                }
+            }
             Err(e) => {
                 tracing::warn!("Error parsing feed: {}", e);
                 break;
@@ -144,7 +190,7 @@ fn parse_atom(feed: &str) -> Result<ParsedFeed> {
         }
     }
 
-    Ok(ParsedFeed { links })
+    Ok(ParsedFeed { entries })
 }
 
 pub fn parse(feed: &str, kind: FeedKind) -> Result<ParsedFeed> {
@@ -158,6 +204,8 @@ pub fn parse(feed: &str, kind: FeedKind) -> Result<ParsedFeed> {
 
 #[cfg(test)]
 mod tests {
+    use crate::dated_url::DatedUrl;
+
     use super::*;
 
     #[test]
@@ -183,7 +231,7 @@ mod tests {
         let parsed = parse_rss(feed).unwrap();
 
         assert_eq!(
-            parsed.links,
+            parsed.links().collect::<Vec<_>>(),
             vec![DatedUrl {
                 url: Url::parse("https://example.com/a").unwrap(),
                 last_modified: Some(
@@ -193,6 +241,49 @@ mod tests {
                 ),
             }]
         );
+
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries[0].title.as_deref(), Some("First title"));
+        assert_eq!(parsed.entries[0].summary.as_deref(), Some("First desc>"));
+    }
+
+    #[test]
+    fn rss_entry_text() {
+        let feed = r#"
+        <rss version="2.0">
+            <channel>
+                <item>
+                    <title>Fish &amp; chips</title>
+                    <description><![CDATA[<p>Served <b>hot</b></p>]]></description>
+                    <link>https://example.com/a</link>
+                </item>
+                <item>
+                    <title>   </title>
+                    <link>https://example.com/b</link>
+                </item>
+            </channel>
+        </rss>
+        "#;
+
+        let parsed = parse_rss(feed).unwrap();
+
+        assert_eq!(
+            parsed.entries,
+            vec![
+                FeedEntry {
+                    url: Url::parse("https://example.com/a").unwrap(),
+                    title: Some("Fish & chips".to_string()),
+                    summary: Some("<p>Served <b>hot</b></p>".to_string()),
+                    published: None,
+                },
+                FeedEntry {
+                    url: Url::parse("https://example.com/b").unwrap(),
+                    title: None,
+                    summary: None,
+                    published: None,
+                },
+            ]
+        );
     }
 
     #[test]
@@ -211,7 +302,7 @@ mod tests {
         let parsed = parse_atom(feed).unwrap();
 
         assert_eq!(
-            parsed.links,
+            parsed.links().collect::<Vec<_>>(),
             vec![DatedUrl {
                 url: Url::parse("https://example.com/a").unwrap(),
                 last_modified: Some(
@@ -222,4 +313,41 @@ mod tests {
             }]
         );
     }
+
+    #[test]
+    fn atom_entry_text() {
+        let feed = r#"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <feed xmlns="http://www.w3.org/2005/Atom">
+        <title>Example Feed</title>
+        <link href="https://example.com/"/>
+        <entry>
+            <title type="html">First &lt;em&gt;entry&lt;/em&gt;</title>
+            <link href="https://example.com/a"/>
+            <summary>A short summary</summary>
+            <published>2003-12-13T08:00:00Z</published>
+            <updated>2003-12-13T18:30:02Z</updated>
+        </entry>
+        <entry>
+            <title>No link</title>
+        </entry>
+        </feed>
+        "#;
+
+        let parsed = parse_atom(feed).unwrap();
+
+        assert_eq!(
+            parsed.entries,
+            vec![FeedEntry {
+                url: Url::parse("https://example.com/a").unwrap(),
+                title: Some("First <em>entry</em>".to_string()),
+                summary: Some("A short summary".to_string()),
+                published: Some(
+                    DateTime::parse_from_rfc3339("2003-12-13T08:00:00Z")
+                        .unwrap()
+                        .with_timezone(&Utc)
+                ),
+            }]
+        );
+    }
 }
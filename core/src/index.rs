@@ -40,7 +40,16 @@ pub struct Index {
 }
 
 impl Index {
+    /// Open the existing index at `path`.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        if !path.as_ref().join(INVERTED_INDEX_SUBFOLDER_NAME).exists() {
+            anyhow::bail!("there is no index at {}", path.as_ref().display());
+        }
+
+        Self::open_or_create(path)
+    }
+
+    pub fn open_or_create<P: AsRef<Path>>(path: P) -> Result<Self> {
         if !path.as_ref().exists() {
             fs::create_dir_all(path.as_ref())?;
         }
@@ -72,7 +81,7 @@ impl Index {
     #[cfg(test)]
     pub fn temporary() -> Result<(Self, file_store::temp::TempDir)> {
         let dir = crate::gen_temp_dir()?;
-        let mut s = Self::open(&dir)?;
+        let mut s = Self::open_or_create(&dir)?;
 
         s.prepare_writer()?;
 
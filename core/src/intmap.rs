@@ -14,6 +14,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use itertools::Itertools;
+
+/// The smallest number of bins in a map.
+const MIN_BINS: usize = 2;
+
 pub trait Key: PartialOrd + Ord + PartialEq + Copy + std::fmt::Debug {
     const BIG_PRIME: Self;
 
@@ -50,7 +55,7 @@ impl<K: Key, V: Clone> Clone for IntMap<K, V> {
 
 impl<K: Key, V> IntMap<K, V> {
     pub fn new() -> Self {
-        Self::with_capacity(2)
+        Self::with_capacity(MIN_BINS)
     }
 
     pub fn with_capacity(cap: usize) -> Self {
@@ -86,9 +91,14 @@ impl<K: Key, V> IntMap<K, V> {
     }
 
     fn grow(&mut self) {
-        let mut bins = Vec::new();
+        self.rehash((self.bins.len() as f64 * 1.5) as usize);
+    }
 
-        for _ in 0..(self.bins.len() as f64 * 1.5) as usize {
+    /// Move all entries into `num_bins` new bins.
+    fn rehash(&mut self, num_bins: usize) {
+        let mut bins = Vec::with_capacity(num_bins);
+
+        for _ in 0..num_bins {
             bins.push(Vec::new());
         }
 
@@ -106,6 +116,43 @@ impl<K: Key, V> IntMap<K, V> {
         }
     }
 
+    /// Remove the key from the map and return its value if it was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let bin_idx = self.bin_idx(key);
+        let bin = &mut self.bins[bin_idx];
+
+        // removing keeps the remaining keys in the bin sorted
+        match bin.binary_search_by(|(stored_key, _)| stored_key.cmp(key)) {
+            Ok(idx) => {
+                self.len -= 1;
+                Some(bin.remove(idx).1)
+            }
+            Err(_) => None,
+        }
+    }
+
+    /// Rehash into fewer bins if the map has more than twice as many bins
+    /// as entries, which can happen after many removals.
+    pub fn shrink_to_fit(&mut self) {
+        let num_bins = self.len.max(MIN_BINS);
+
+        if self.bins.len() > num_bins * 2 {
+            self.rehash(num_bins);
+        }
+
+        for bin in &mut self.bins {
+            bin.shrink_to_fit();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     pub fn get(&self, key: &K) -> Option<&V> {
         let bin = self.bin_idx(key);
         match self.bins[bin].binary_search_by(|(stored_key, _)| stored_key.cmp(key)) {
@@ -126,6 +173,23 @@ impl<K: Key, V> IntMap<K, V> {
         self.bins.iter().flat_map(|bin| bin.iter())
     }
 
+    /// Iterate the entries in ascending key order. The bins are already
+    /// sorted, so they are merged lazily instead of copied and sorted.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = &(K, V)> {
+        self.bins
+            .iter()
+            .map(|bin| bin.iter())
+            .kmerge_by(|(a, _), (b, _)| a < b)
+    }
+
+    /// The entries sorted by ascending key.
+    pub fn into_sorted_vec(self) -> Vec<(K, V)> {
+        self.bins
+            .into_iter()
+            .kmerge_by(|(a, _), (b, _)| a < b)
+            .collect()
+    }
+
     pub fn contains_key(&self, key: &K) -> bool {
         self.get(key).is_some()
     }
@@ -157,11 +221,28 @@ impl<K: Key, V> Default for IntMap<K, V> {
     }
 }
 
-#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Debug)]
+#[serde(transparent)]
 pub struct IntSet<K: Key = u64> {
     map: IntMap<K, ()>,
 }
 
+impl<K: Key> Clone for IntSet<K> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<K: Key> PartialEq for IntSet<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|key| other.contains(key))
+    }
+}
+
+impl<K: Key> Eq for IntSet<K> {}
+
 impl<K: Key> Default for IntSet<K> {
     fn default() -> Self {
         Self::new()
@@ -176,6 +257,22 @@ impl<K: Key> IntSet<K> {
     pub fn insert(&mut self, item: K) {
         self.map.insert(item, ());
     }
+
+    pub fn contains(&self, item: &K) -> bool {
+        self.map.contains_key(item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &K> {
+        self.map.iter().map(|(key, _)| key)
+    }
 }
 
 impl<K: Key> std::iter::FromIterator<K> for IntSet<K> {
@@ -217,4 +314,117 @@ mod tests {
 
         assert_eq!(map.len, 1000);
     }
+
+    #[test]
+    fn remove() {
+        let mut map: IntMap<u64, String> = (0..1000).map(|key| (key, key.to_string())).collect();
+
+        assert_eq!(map.remove(&42), Some("42".to_string()));
+        assert_eq!(map.get(&42), None);
+        assert_eq!(map.len(), 999);
+
+        assert_eq!(map.remove(&42), None);
+        assert_eq!(map.remove(&1000), None);
+        assert_eq!(map.len(), 999);
+
+        map.insert(42, "kage".to_string());
+        assert_eq!(map.get(&42), Some(&"kage".to_string()));
+        assert_eq!(map.len(), 1000);
+    }
+
+    #[test]
+    fn get_after_removals() {
+        let mut map: IntMap<u64, u64> = (0..1000).map(|key| (key, key)).collect();
+
+        for key in (0..1000).step_by(3) {
+            assert_eq!(map.remove(&key), Some(key));
+        }
+
+        for bin in &map.bins {
+            assert!(bin.windows(2).all(|w| w[0].0 < w[1].0));
+        }
+
+        for key in 0..1000 {
+            if key % 3 == 0 {
+                assert_eq!(map.get(&key), None);
+            } else {
+                assert_eq!(map.get(&key), Some(&key));
+            }
+        }
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let mut map: IntMap<u64, u64> = IntMap::new();
+
+        for key in 0..1000 {
+            map.insert(key, key);
+        }
+
+        for key in 10..1000 {
+            map.remove(&key);
+        }
+
+        let num_bins = map.bins.len();
+        map.shrink_to_fit();
+        assert!(map.bins.len() < num_bins);
+        assert_eq!(map.len(), 10);
+
+        for key in 0..1000 {
+            assert_eq!(map.get(&key), (key < 10).then_some(&key));
+        }
+
+        for bin in &map.bins {
+            assert!(bin.windows(2).all(|w| w[0].0 < w[1].0));
+        }
+
+        map.shrink_to_fit();
+        map.insert(1000, 1000);
+        assert_eq!(map.get(&1000), Some(&1000));
+    }
+
+    #[test]
+    fn iter_sorted() {
+        let mut map = IntMap::new();
+
+        for key in [42, 7, 1000, 3, 999, 0, 512, 13, 8, 1] {
+            map.insert(key, key * 2);
+        }
+
+        for key in (0..2000).rev().step_by(7) {
+            map.insert(key, key * 2);
+        }
+
+        let keys: Vec<u64> = map.iter_sorted().map(|(key, _)| *key).collect();
+        assert_eq!(keys.len(), map.len());
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert!(map.iter_sorted().all(|(key, value)| *value == key * 2));
+
+        let sorted = map.clone().into_sorted_vec();
+        assert_eq!(sorted.iter().map(|(key, _)| *key).collect::<Vec<_>>(), keys);
+
+        assert_eq!(IntMap::<u64, ()>::new().iter_sorted().count(), 0);
+    }
+
+    #[test]
+    fn set_roundtrip() {
+        let set: IntSet = [3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5].into_iter().collect();
+
+        assert_eq!(set.len(), 7);
+        assert!(set.contains(&9));
+        assert!(!set.contains(&7));
+
+        let bytes = bincode::encode_to_vec(&set, common::bincode_config()).unwrap();
+        let (decoded, _): (IntSet, _) =
+            bincode::decode_from_slice(&bytes, common::bincode_config()).unwrap();
+
+        assert_eq!(decoded, set);
+
+        let json = serde_json::to_string(&set).unwrap();
+        let decoded: IntSet = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(decoded, set);
+        assert_ne!(decoded, IntSet::new());
+        assert!(IntSet::<u64>::new().is_empty());
+    }
 }
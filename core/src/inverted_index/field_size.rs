@@ -0,0 +1,171 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+use std::collections::BTreeMap;
+
+use tantivy::space_usage::PerFieldSpaceUsage;
+
+use crate::Result;
+
+/// The document store compresses blocks of whole documents, so its size
+/// can't be split by field. It is reported as a field with this name instead.
+pub const STORE_FIELD_NAME: &str = "_store";
+
+/// On-disk bytes used by a single field.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    bincode::Encode,
+    bincode::Decode,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct FieldSize {
+    /// Term dictionary, postings, positions and fieldnorms.
+    pub postings: u64,
+    /// Column and row oriented fast fields.
+    pub fast: u64,
+    /// Stored documents. Only set for [`STORE_FIELD_NAME`].
+    pub stored: u64,
+}
+
+impl FieldSize {
+    pub fn total(&self) -> u64 {
+        self.postings + self.fast + self.stored
+    }
+}
+
+impl std::ops::AddAssign for FieldSize {
+    fn add_assign(&mut self, other: Self) {
+        self.postings += other.postings;
+        self.fast += other.fast;
+        self.stored += other.stored;
+    }
+}
+
+/// On-disk size of the index broken down by field and summed over all segments.
+#[derive(
+    Debug, Clone, Default, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize,
+)]
+pub struct FieldSizes {
+    pub fields: BTreeMap<String, FieldSize>,
+    /// The total size as reported by tantivy.
+    pub total: u64,
+}
+
+impl FieldSizes {
+    pub fn compute(reader: &tantivy::IndexReader) -> Result<Self> {
+        let searcher = reader.searcher();
+        let schema = searcher.schema();
+        let usage = searcher.space_usage()?;
+
+        let mut res = Self {
+            fields: BTreeMap::new(),
+            total: usage.total().get_bytes(),
+        };
+
+        for segment in usage.segments() {
+            let mut add = |usage: &PerFieldSpaceUsage, to_size: fn(u64) -> FieldSize| {
+                for (field, field_usage) in usage.fields() {
+                    *res.fields
+                        .entry(schema.get_field_name(*field).to_string())
+                        .or_default() += to_size(field_usage.total().get_bytes());
+                }
+            };
+
+            let postings = |bytes: u64| FieldSize {
+                postings: bytes,
+                ..Default::default()
+            };
+            let fast = |bytes: u64| FieldSize {
+                fast: bytes,
+                ..Default::default()
+            };
+
+            add(segment.termdict(), postings);
+            add(segment.postings(), postings);
+            add(segment.positions(), postings);
+            add(segment.fieldnorms(), postings);
+            add(segment.column_fields(), fast);
+            add(segment.row_fields(), fast);
+
+            *res.fields.entry(STORE_FIELD_NAME.to_string()).or_default() += FieldSize {
+                stored: segment.store().total().get_bytes(),
+                ..Default::default()
+            };
+        }
+
+        Ok(res)
+    }
+
+    /// Sum of the sizes of all fields.
+    pub fn fields_total(&self) -> u64 {
+        self.fields.values().map(FieldSize::total).sum()
+    }
+}
+
+impl std::ops::AddAssign for FieldSizes {
+    fn add_assign(&mut self, other: Self) {
+        for (name, size) in other.fields {
+            *self.fields.entry(name).or_default() += size;
+        }
+
+        self.total += other.total;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inverted_index::InvertedIndex,
+        schema::text_field::{self, TextField},
+        webpage::Webpage,
+    };
+
+    use super::*;
+
+    #[test]
+    fn total_matches_sum_of_fields() {
+        let (mut index, _dir) = InvertedIndex::temporary().expect("Unable to open index");
+
+        for i in 0..10 {
+            index
+                .insert(
+                    &Webpage::test_parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website {i}</title>
+                            </head>
+                            <body>
+                                this is the best example website ever number {i}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        &format!("https://www.example{i}.com"),
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+        }
+
+        index.commit().unwrap();
+
+        let sizes = index.size_by_field().unwrap();
+
+        assert!(sizes.total > 0);
+        let diff = sizes.total.abs_diff(sizes.fields_total());
+        assert!(diff <= sizes.total / 100, "{diff} of {}", sizes.total);
+
+        assert!(sizes.fields[text_field::Title.name()].postings > 0);
+        assert!(sizes.fields[STORE_FIELD_NAME].stored > 0);
+    }
+}
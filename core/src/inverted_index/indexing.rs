@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>
 
+use tantivy::common::BitSet;
+use tantivy::directory::MmapDirectory;
 use tantivy::index::SegmentId;
 use tantivy::merge_policy::NoMergePolicy;
 
@@ -21,14 +23,93 @@ use tantivy::{IndexWriter, SegmentMeta};
 
 use crate::numericalfield_reader::NumericalFieldReader;
 
+use crate::schema::{numerical_field, NumericalFieldEnum};
+use crate::simhash;
 use crate::webpage::Webpage;
 use crate::Result;
-use std::collections::HashSet;
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use super::InvertedIndex;
 
+/// Suffix of the directory an index is rewritten into before it replaces the index.
+const REWRITE_SUFFIX: &str = "_rewrite";
+/// Suffix the replaced index is moved to until the rewritten index is in place.
+const REPLACED_SUFFIX: &str = "_replaced";
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    // normalizes away a trailing slash, so the sibling is not created inside `path`
+    let mut sibling = path.components().as_path().as_os_str().to_owned();
+    sibling.push(suffix);
+    PathBuf::from(sibling)
+}
+
+fn sync_parent_dir(path: &Path) -> Result<()> {
+    if let Some(parent) = path.components().as_path().parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::File::open(parent)?.sync_all()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace the index at `path` with the fully written index at `rewrite_path`.
+///
+/// The old index is moved aside before the rewritten index is moved into its place,
+/// so there is always a complete index on disk. If the process dies halfway,
+/// [`recover_interrupted_rewrite`] finishes the swap when the index is opened again.
+fn swap_in_rewrite(path: &Path, rewrite_path: &Path) -> Result<()> {
+    let replaced_path = sibling_path(path, REPLACED_SUFFIX);
+
+    fs::rename(path, &replaced_path)?;
+    fs::rename(rewrite_path, path)?;
+    sync_parent_dir(path)?;
+
+    fs::remove_dir_all(&replaced_path)?;
+
+    Ok(())
+}
+
+/// Clean up after a rewrite of the index at `path` that was interrupted.
+///
+/// * A rewrite that was never swapped in is incomplete and is removed.
+/// * If the old index was moved aside but the rewrite not yet moved into place,
+///   the rewrite is complete and the swap is finished.
+/// * An old index that was moved aside after a completed swap is removed.
+pub(super) fn recover_interrupted_rewrite(path: &Path) -> Result<()> {
+    let rewrite_path = sibling_path(path, REWRITE_SUFFIX);
+    let replaced_path = sibling_path(path, REPLACED_SUFFIX);
+
+    if !path.exists() && replaced_path.exists() {
+        if rewrite_path.exists() {
+            tracing::warn!("finishing interrupted rewrite of {}", path.display());
+            fs::rename(&rewrite_path, path)?;
+        } else {
+            tracing::warn!(
+                "restoring index {} after interrupted rewrite",
+                path.display()
+            );
+            fs::rename(&replaced_path, path)?;
+        }
+
+        sync_parent_dir(path)?;
+    }
+
+    if rewrite_path.exists() {
+        tracing::warn!("removing incomplete rewrite of {}", path.display());
+        fs::remove_dir_all(&rewrite_path)?;
+    }
+
+    if replaced_path.exists() {
+        fs::remove_dir_all(&replaced_path)?;
+    }
+
+    Ok(())
+}
+
 struct SegmentMergeCandidate {
     num_docs: u32,
     segments: Vec<SegmentMeta>,
@@ -153,6 +234,265 @@ impl InvertedIndex {
         Ok(())
     }
 
+    /// Check that `max_distance` can be used to [`Self::remove_near_duplicates`], so
+    /// an invalid threshold is caught before the indexes are built.
+    pub fn check_near_duplicate_distance(max_distance: u32) -> Result<()> {
+        if max_distance as usize > simhash::MAX_DISTANCE {
+            anyhow::bail!(
+                "simhash dedup threshold {max_distance} is larger than the maximum of {}",
+                simhash::MAX_DISTANCE
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Remove the documents whose simhash is within `max_distance` bits of a document
+    /// with a higher pre-computed score. The remaining documents are merged into a single segment.
+    ///
+    /// The segments are sorted by pre-computed score, so the documents are streamed
+    /// from all segments in score order instead of being collected and sorted first.
+    ///
+    /// Returns the number of removed documents.
+    pub fn remove_near_duplicates(&mut self, max_distance: u32) -> Result<usize> {
+        Self::check_near_duplicate_distance(max_distance)?;
+        self.commit()?;
+
+        let searcher = self.reader.searcher();
+        let simhash_field = NumericalFieldEnum::from(numerical_field::SimHash);
+        let score_field = NumericalFieldEnum::from(numerical_field::PreComputedScore);
+
+        // the best scoring document of a group of near duplicates is the one we keep.
+        let docs = searcher
+            .segment_readers()
+            .iter()
+            .map(|segment| {
+                let fields = self
+                    .columnfield_reader
+                    .borrow_segment(&segment.segment_id());
+
+                segment.doc_ids().map(move |doc| {
+                    let field_reader = fields.get_field_reader(doc);
+                    let hash = field_reader
+                        .get(simhash_field)
+                        .and_then(|val| val.as_u64())
+                        .unwrap_or_default();
+                    let score = field_reader
+                        .get(score_field)
+                        .and_then(|val| val.as_f64())
+                        .unwrap_or_default();
+
+                    (score, segment.segment_id(), doc, hash)
+                })
+            })
+            .kmerge_by(|(a, ..), (b, ..)| a.total_cmp(b).is_gt());
+
+        let mut table = simhash::Table::new(max_distance as usize);
+        let mut alive: HashMap<_, _> = searcher
+            .segment_readers()
+            .iter()
+            .map(|segment| {
+                (
+                    segment.segment_id(),
+                    BitSet::with_max_value(segment.max_doc()),
+                )
+            })
+            .collect();
+        let mut num_removed = 0;
+
+        for (_, segment_id, doc, hash) in docs {
+            // pages without any text all have a simhash of 0.
+            if hash != 0 {
+                if table.contains(&hash) {
+                    num_removed += 1;
+                    continue;
+                }
+
+                table.insert(hash);
+            }
+
+            if let Some(alive) = alive.get_mut(&segment_id) {
+                alive.insert(doc);
+            }
+        }
+
+        if num_removed > 0 {
+            self.rewrite_with_alive_docs(alive)?;
+        }
+
+        Ok(num_removed)
+    }
+
+    /// Remove the documents in the `outdated` segments whose url is also found in
+    /// one of the other segments, so pages that are indexed again replace their old version.
+    /// Only the outdated segments that lose documents are rewritten.
+    ///
+    /// Returns the number of removed documents.
+    pub fn remove_replaced_urls(&mut self, outdated: &[SegmentId]) -> Result<usize> {
+        self.commit()?;
+
+        let searcher = self.reader.searcher();
+        let outdated: HashSet<_> = outdated.iter().copied().collect();
+        let url_hash_fields = [
+            NumericalFieldEnum::from(numerical_field::UrlHash1),
+            NumericalFieldEnum::from(numerical_field::UrlHash2),
+        ];
+        let url_hash = |segment: &tantivy::SegmentReader, doc| {
+            let field_reader = self
+                .columnfield_reader
+                .borrow_segment(&segment.segment_id())
+                .get_field_reader(doc);
+
+            url_hash_fields.map(|field| {
+                field_reader
+                    .get(field)
+                    .and_then(|val| val.as_u64())
+                    .unwrap_or_default()
+            })
+        };
+
+        let mut new_urls = HashSet::new();
+        for segment in searcher
+            .segment_readers()
+            .iter()
+            .filter(|segment| !outdated.contains(&segment.segment_id()))
+        {
+            for doc in segment.doc_ids() {
+                new_urls.insert(url_hash(segment, doc));
+            }
+        }
+
+        let mut alive = HashMap::new();
+        let mut num_removed = 0;
+
+        for segment in searcher
+            .segment_readers()
+            .iter()
+            .filter(|segment| outdated.contains(&segment.segment_id()))
+        {
+            let mut segment_alive = BitSet::with_max_value(segment.max_doc());
+            let mut segment_removed = 0;
+
+            for doc in segment.doc_ids() {
+                if new_urls.contains(&url_hash(segment, doc)) {
+                    segment_removed += 1;
+                } else {
+                    segment_alive.insert(doc);
+                }
+            }
+
+            if segment_removed > 0 {
+                alive.insert(segment.segment_id(), segment_alive);
+                num_removed += segment_removed;
+            }
+        }
+
+        if num_removed > 0 {
+            self.rewrite_segments_with_alive_docs(alive)?;
+        }
+
+        Ok(num_removed)
+    }
+
+    /// Rewrite the index into a single segment that only contains the alive documents.
+    /// Segments without an entry in `alive` keep all their documents.
+    fn rewrite_with_alive_docs(&mut self, mut alive: HashMap<SegmentId, BitSet>) -> Result<()> {
+        let segments = self.tantivy_index.searchable_segments()?;
+        let alive_bitsets = segments
+            .iter()
+            .map(|segment| alive.remove(&segment.id()))
+            .collect();
+
+        let path = PathBuf::from(&self.path);
+        let rewrite_path = sibling_path(&path, REWRITE_SUFFIX);
+        if rewrite_path.exists() {
+            fs::remove_dir_all(&rewrite_path)?;
+        }
+        fs::create_dir_all(&rewrite_path)?;
+
+        tantivy::indexer::merge_filtered_segments(
+            &segments,
+            self.tantivy_index.settings().clone(),
+            alive_bitsets,
+            MmapDirectory::open(&rewrite_path)?,
+        )?;
+
+        if let Some(writer) = self.writer.take() {
+            writer.wait_merging_threads()?;
+        }
+
+        swap_in_rewrite(&path, &rewrite_path)?;
+        self.re_open()?;
+
+        Ok(())
+    }
+
+    /// Rewrite the segments in `alive` into a single new segment that only contains their
+    /// alive documents. All other segments are left untouched.
+    ///
+    /// The new segment is written through the directory of the index before the metadata
+    /// is updated, so a crash leaves an index that refers to either the old segments or the
+    /// new one, and the files of the unused segments are garbage collected.
+    fn rewrite_segments_with_alive_docs(
+        &mut self,
+        mut alive: HashMap<SegmentId, BitSet>,
+    ) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer.wait_merging_threads()?;
+        }
+
+        let segments: Vec<_> = self
+            .tantivy_index
+            .searchable_segments()?
+            .into_iter()
+            .filter(|segment| alive.contains_key(&segment.id()))
+            .collect();
+
+        if segments.is_empty() {
+            return Ok(());
+        }
+
+        let alive_bitsets = segments
+            .iter()
+            .map(|segment| alive.remove(&segment.id()))
+            .collect();
+
+        let rewritten = tantivy::indexer::merge_filtered_segments_into(
+            &self.tantivy_index,
+            &segments,
+            alive_bitsets,
+        )?;
+
+        let replaced: HashSet<_> = segments.iter().map(|segment| segment.id()).collect();
+        let replaced_files: HashSet<_> = segments
+            .iter()
+            .flat_map(|segment| segment.meta().list_files())
+            .collect();
+
+        let mut meta = self.tantivy_index.load_metas()?;
+        meta.segments
+            .retain(|segment| !replaced.contains(&segment.id()));
+        meta.segments.push(rewritten);
+        meta.segments
+            .sort_by_key(|a| std::cmp::Reverse(a.max_doc()));
+        self.tantivy_index.save_metas(&meta)?;
+
+        let living_files: HashSet<_> = self
+            .tantivy_index
+            .directory()
+            .list_managed_files()
+            .difference(&replaced_files)
+            .cloned()
+            .collect();
+        self.tantivy_index
+            .directory_mut()
+            .garbage_collect(|| living_files)?;
+
+        self.re_open()?;
+
+        Ok(())
+    }
+
     #[allow(clippy::missing_panics_doc)] // should not panic as writer is prepared
     pub fn merge_segments_by_id(&mut self, segments: &[SegmentId]) -> Result<Option<SegmentId>> {
         self.prepare_writer()?;
@@ -15,7 +15,7 @@ use crate::{
 
 const NON_ALPHABETIC_CHAR_THRESHOLD: f64 = 0.25;
 
-#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize)]
 pub struct KeyPhrase {
     phrase: String,
     score: f64,
@@ -16,11 +16,13 @@
 //! This allows us to perform more advanced queries than just term lookups,
 //! but the principle is the same.
 
+mod field_size;
 mod indexing;
 mod key_phrase;
 mod retrieved_webpage;
 mod search;
 
+pub use field_size::{FieldSize, FieldSizes, STORE_FIELD_NAME};
 pub use indexing::merge_tantivy_segments;
 pub use key_phrase::KeyPhrase;
 pub use retrieved_webpage::RetrievedWebpage;
@@ -32,15 +34,22 @@ use tantivy::schema::Schema;
 use tantivy::tokenizer::TokenizerManager;
 use tantivy::{IndexReader, IndexWriter};
 
-use crate::collector::{approx_count, Hashes};
+use crate::collector::{
+    approx_count::{self, DistinctCount},
+    facets::Facets,
+    Hashes,
+};
 use crate::config::SnippetConfig;
 use crate::numericalfield_reader::NumericalFieldReader;
 
 use crate::ranking::initial::Score;
 
-use crate::schema::{numerical_field, Field, NumericalFieldEnum};
+use crate::schema::{
+    numerical_field, text_field::TextField, Field, NumericalFieldEnum, TextFieldEnum,
+};
 use crate::tokenizer::fields::{
-    BigramTokenizer, Identity, JsonField, NewlineTokenizer, Stemmed, TrigramTokenizer, UrlTokenizer,
+    BigramTokenizer, CamelCaseTokenizer, DefaultTokenizer, Identity, JsonField, NewlineTokenizer,
+    PhoneticTokenizer, Stemmed, TrigramTokenizer, UrlTokenizer,
 };
 use crate::Result;
 use crate::{schema::create_schema, tokenizer::FieldTokenizer};
@@ -52,6 +61,8 @@ use std::sync::Arc;
 pub struct InitialSearchResult {
     pub num_websites: approx_count::Count,
     pub top_websites: Vec<WebpagePointer>,
+    pub facets: Option<Facets>,
+    pub distinct_sites: Option<DistinctCount>,
 }
 
 #[derive(
@@ -100,19 +111,34 @@ fn register_tokenizers(manager: &TokenizerManager) {
     let tokenizer = FieldTokenizer::default();
     manager.register(tokenizer.as_str(), tokenizer);
 
+    let tokenizer = FieldTokenizer::Default(DefaultTokenizer::default().remove_stopwords(None));
+    manager.register(tokenizer.as_str(), tokenizer);
+
     let tokenizer = FieldTokenizer::Stemmed(Stemmed::default());
     manager.register(tokenizer.as_str(), tokenizer);
 
     let tokenizer = FieldTokenizer::Identity(Identity::default());
     manager.register(tokenizer.as_str(), tokenizer);
 
+    let tokenizer = FieldTokenizer::Identity(Identity::lowercase());
+    manager.register(tokenizer.as_str(), tokenizer);
+
     let tokenizer = FieldTokenizer::Bigram(BigramTokenizer::default());
     manager.register(tokenizer.as_str(), tokenizer);
 
+    let tokenizer = FieldTokenizer::Bigram(BigramTokenizer::skip_stopwords(None));
+    manager.register(tokenizer.as_str(), tokenizer);
+
     let tokenizer = FieldTokenizer::Trigram(TrigramTokenizer::default());
     manager.register(tokenizer.as_str(), tokenizer);
 
-    let tokenizer = FieldTokenizer::Url(UrlTokenizer);
+    let tokenizer = FieldTokenizer::Trigram(TrigramTokenizer::with_padding(true));
+    manager.register(tokenizer.as_str(), tokenizer);
+
+    let tokenizer = FieldTokenizer::Url(UrlTokenizer::default());
+    manager.register(tokenizer.as_str(), tokenizer);
+
+    let tokenizer = FieldTokenizer::Url(UrlTokenizer::with_query(true));
     manager.register(tokenizer.as_str(), tokenizer);
 
     let tokenizer = FieldTokenizer::Json(JsonField);
@@ -120,6 +146,44 @@ fn register_tokenizers(manager: &TokenizerManager) {
 
     let tokenizer = FieldTokenizer::Newline(NewlineTokenizer::default());
     manager.register(tokenizer.as_str(), tokenizer);
+
+    let tokenizer = FieldTokenizer::CamelCase(CamelCaseTokenizer);
+    manager.register(tokenizer.as_str(), tokenizer);
+
+    let tokenizer = FieldTokenizer::Phonetic(PhoneticTokenizer);
+    manager.register(tokenizer.as_str(), tokenizer);
+}
+
+/// Text fields are pretokenized when they are indexed, so the query tokenizer of a field
+/// only matches an index that was built with the same field tokenizer. Changing how a field
+/// is tokenized (e.g. its stopword policy) therefore requires the index to be rebuilt.
+fn check_field_tokenizers(stored: &Schema) -> Result<()> {
+    for field in TextFieldEnum::all() {
+        let Some(tv_field) = field.tantivy_field(stored) else {
+            continue;
+        };
+
+        let indexed_with = match stored.get_field_entry(tv_field).field_type() {
+            tantivy::schema::FieldType::Str(options) => options
+                .get_indexing_options()
+                .map(|indexing| indexing.tokenizer().to_string()),
+            _ => None,
+        };
+
+        let expected = field.field_tokenizer(None);
+
+        if let Some(indexed_with) = indexed_with {
+            if indexed_with != expected.as_str() {
+                anyhow::bail!(
+                    "field `{}` was indexed with the `{indexed_with}` tokenizer but expects `{}`. The index must be rebuilt.",
+                    field.name(),
+                    expected.as_str()
+                );
+            }
+        }
+    }
+
+    Ok(())
 }
 
 pub struct InvertedIndex {
@@ -135,10 +199,14 @@ pub struct InvertedIndex {
 impl InvertedIndex {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
         let schema = create_schema();
+        indexing::recover_interrupted_rewrite(path.as_ref())?;
 
         let tantivy_index = if path.as_ref().exists() {
             let mmap_directory = MmapDirectory::open(&path)?;
-            tantivy::Index::open(mmap_directory)?
+            let index = tantivy::Index::open(mmap_directory)?;
+            check_field_tokenizers(&index.schema())?;
+
+            index
         } else {
             let index_settings = tantivy::IndexSettings {
                 sort_by_field: Some(tantivy::IndexSortByField {
@@ -210,6 +278,10 @@ impl InvertedIndex {
         self.reader.searcher().num_docs()
     }
 
+    pub fn size_by_field(&self) -> Result<FieldSizes> {
+        FieldSizes::compute(&self.reader)
+    }
+
     #[cfg(test)]
     pub fn temporary() -> Result<(Self, file_store::temp::TempDir)> {
         let dir = crate::gen_temp_dir()?;
@@ -239,6 +311,7 @@ mod tests {
         query::Query,
         ranking::{LocalRanker, SignalComputer},
         search_ctx::Ctx,
+        search_prettifier::DisplayedWebpage,
         searcher::SearchQuery,
         webgraph::{Edge, NodeDatum},
         webpage::{schema_org, Html, Webpage},
@@ -247,6 +320,23 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn reject_index_with_other_field_tokenizer() {
+        assert!(check_field_tokenizers(&create_schema()).is_ok());
+
+        // an index from before the body removed stopwords
+        let mut builder = Schema::builder();
+        builder.add_text_field(
+            crate::schema::text_field::CleanBody.name(),
+            tantivy::schema::TextOptions::default().set_indexing_options(
+                tantivy::schema::TextFieldIndexing::default()
+                    .set_tokenizer(FieldTokenizer::default().as_str()),
+            ),
+        );
+
+        assert!(check_field_tokenizers(&builder.build()).is_err());
+    }
+
     const CONTENT: &str = "this is the best example website ever this is the best example website ever this is the best example website ever this is the best example website ever this is the best example website ever this is the best example website ever";
 
     pub fn search(
@@ -520,6 +610,7 @@ mod tests {
             to: NodeDatum::new(1u64, 0),
             label: "B site is great".to_string(),
             rel: Default::default(),
+            weight: 1.0,
         }]);
 
         index.insert(&webpage).expect("failed to insert webpage");
@@ -986,6 +1077,148 @@ mod tests {
         assert_eq!(webpage.url, "https://www.example.com/".to_string());
     }
 
+    #[test]
+    fn favicon_stored() {
+        let (mut index, _dir) = InvertedIndex::temporary().expect("Unable to open index");
+
+        index
+            .insert(
+                &Webpage::test_parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Website with icon</title>
+                            <link rel="icon" sizes="32x32" href="/icon.png" />
+                        </head>
+                        <body>
+                            {CONTENT} test
+                        </body>
+                    </html>
+                "#
+                    ),
+                    "https://www.example.com/page",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+
+        index
+            .insert(
+                &Webpage::test_parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Website without icon</title>
+                        </head>
+                        <body>
+                            {CONTENT} test
+                        </body>
+                    </html>
+                "#
+                    ),
+                    "https://www.other.com/page",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+
+        index.commit().expect("failed to commit index");
+
+        let webpage = index.get_webpage("https://www.example.com/page").unwrap();
+        assert_eq!(
+            webpage.favicon,
+            Some("https://www.example.com/icon.png".to_string())
+        );
+
+        let webpage = index.get_webpage("https://www.other.com/page").unwrap();
+        assert_eq!(
+            webpage.favicon,
+            Some("https://www.other.com/favicon.ico".to_string())
+        );
+    }
+
+    #[test]
+    fn opengraph_preferred_in_result_card() {
+        let (mut index, _dir) = InvertedIndex::temporary().expect("Unable to open index");
+
+        index
+            .insert(
+                &Webpage::test_parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Scraped title</title>
+                            <meta name="description" content="Scraped description" />
+                            <meta property="og:title" content="OpenGraph title" />
+                            <meta property="og:description" content="OpenGraph description" />
+                            <meta property="og:image" content="/cover.png" />
+                            <meta property="og:type" content="article" />
+                        </head>
+                        <body>
+                            {CONTENT} test
+                        </body>
+                    </html>
+                "#
+                    ),
+                    "https://www.example.com/article",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+
+        index
+            .insert(
+                &Webpage::test_parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Plain title</title>
+                            <meta name="description" content="Plain description" />
+                        </head>
+                        <body>
+                            {CONTENT} test
+                        </body>
+                    </html>
+                "#
+                    ),
+                    "https://www.other.com/page",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+
+        index.commit().expect("failed to commit index");
+
+        let webpage = index
+            .get_webpage("https://www.example.com/article")
+            .unwrap();
+        assert_eq!(webpage.title, "Scraped title".to_string());
+        assert_eq!(
+            webpage.opengraph.as_ref().and_then(|og| og.kind.clone()),
+            Some("article".to_string())
+        );
+
+        let card = DisplayedWebpage::new(webpage, &SearchQuery::default());
+        assert_eq!(card.title, "OpenGraph title".to_string());
+        assert_eq!(card.description, Some("OpenGraph description".to_string()));
+        assert_eq!(
+            card.image,
+            Some("https://www.example.com/cover.png".to_string())
+        );
+
+        let webpage = index.get_webpage("https://www.other.com/page").unwrap();
+        assert_eq!(webpage.opengraph, None);
+
+        let card = DisplayedWebpage::new(webpage, &SearchQuery::default());
+        assert_eq!(card.title, "Plain title".to_string());
+        assert_eq!(card.description, Some("Plain description".to_string()));
+        assert_eq!(card.image, None);
+    }
+
     #[test]
     fn test_title_embeddings_stored() {
         let (mut index, _dir) = InvertedIndex::temporary().expect("Unable to open index");
@@ -1132,6 +1365,67 @@ mod tests {
         assert_eq!(res.num_websites, approx_count::Count::Approximate(1_000));
     }
 
+    #[test]
+    fn distinct_sites_are_not_short_circuited() {
+        let (mut index, _dir) = InvertedIndex::temporary().expect("Unable to open index");
+
+        for i in 0..1_000 {
+            let webpage = Webpage::test_parse(
+                &format!(
+                    r#"
+                <html>
+                    <head>
+                        <title>Test website</title>
+                    </head>
+                    <body>
+                        {CONTENT} test
+                    </body>
+                </html>
+            "#,
+                    CONTENT = crate::rand_words(100)
+                ),
+                &format!("https://www.site{i}.com"),
+            )
+            .unwrap();
+
+            index.insert(&webpage).unwrap();
+        }
+
+        index.commit().expect("failed to commit index");
+
+        let ctx = index.local_search_ctx();
+
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "test".to_string(),
+                ..Default::default()
+            },
+            &index,
+        )
+        .expect("Failed to parse query");
+
+        let collector_config = CollectorConfig {
+            estimate_distinct_sites: true,
+            ..Default::default()
+        };
+
+        // only 100 documents are collected, but all the sites are counted
+        let ranker = LocalRanker::new(
+            SignalComputer::new(Some(&query)),
+            ctx.columnfield_reader.clone(),
+            collector_config,
+        )
+        .with_max_docs(100, index.num_segments());
+
+        let res = index
+            .search_initial(&query, &ctx, ranker.collector(ctx.clone()))
+            .unwrap();
+
+        let estimate = res.distinct_sites.unwrap().estimate() as f64;
+        assert!((estimate - 1_000.0).abs() / 1_000.0 < 0.05);
+    }
+
     #[test]
     fn test_search_special_characters() {
         let (mut index, _dir) = InvertedIndex::temporary().expect("Unable to open index");
@@ -1252,4 +1546,327 @@ mod tests {
         test("æble");
         test("æble café");
     }
+
+    #[test]
+    fn near_duplicates_are_removed() {
+        let (mut index, _dir) = InvertedIndex::temporary().expect("Unable to open index");
+
+        let page = |updated: &str, url: &str, score: f64| {
+            let mut webpage = Webpage::test_parse(
+                &format!(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Test website</title>
+                        </head>
+                        <body>
+                            {CONTENT} {CONTENT} {CONTENT}
+                            Last updated: {updated}
+                        </body>
+                    </html>
+                "#
+                ),
+                url,
+            )
+            .unwrap();
+            webpage.pre_computed_score = score;
+            webpage
+        };
+
+        index
+            .insert(&page("2024-01-05 10:31", "https://www.a.com/", 1.0))
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        index
+            .insert(&page("2024-03-17 08:02", "https://www.b.com/", 5.0))
+            .expect("failed to insert webpage");
+        index
+            .insert(
+                &Webpage::test_parse(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Gardening</title>
+                        </head>
+                        <body>
+                            tomatoes need plenty of sun and water during the summer months
+                        </body>
+                    </html>
+                "#,
+                    "https://www.c.com/",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+        assert_eq!(index.num_documents(), 3);
+
+        assert_eq!(index.remove_near_duplicates(3).unwrap(), 1);
+        assert_eq!(index.num_documents(), 2);
+        assert_eq!(index.num_segments(), 1);
+
+        let ctx = index.local_search_ctx();
+        let query = Query::parse(
+            &ctx,
+            &SearchQuery {
+                query: "website".to_string(),
+                ..Default::default()
+            },
+            &index,
+        )
+        .expect("Failed to parse query");
+        let ranker = LocalRanker::new(
+            SignalComputer::new(Some(&query)),
+            ctx.columnfield_reader.clone(),
+            CollectorConfig::default(),
+        );
+
+        let result =
+            search(&index, &query, &ctx, ranker.collector(ctx.clone())).expect("Search failed");
+        assert_eq!(result.documents.len(), 1);
+        assert_eq!(result.documents[0].url, "https://www.b.com/");
+
+        // nothing is left to remove
+        assert_eq!(index.remove_near_duplicates(3).unwrap(), 0);
+    }
+
+    #[test]
+    fn interrupted_rewrite_is_recovered() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = dir.as_ref().join("index");
+        let rewrite_path = dir.as_ref().join("index_rewrite");
+        let replaced_path = dir.as_ref().join("index_replaced");
+
+        let page = |url: &str| {
+            Webpage::test_parse(
+                r#"
+                    <html>
+                        <head>
+                            <title>Test website</title>
+                        </head>
+                        <body>
+                            some text
+                        </body>
+                    </html>
+                "#,
+                url,
+            )
+            .unwrap()
+        };
+
+        let write_index = |path: &Path, urls: &[&str]| {
+            let mut index = InvertedIndex::open(path).unwrap();
+            index.prepare_writer().unwrap();
+            for url in urls {
+                index.insert(&page(url)).unwrap();
+            }
+            index.commit().unwrap();
+        };
+
+        // the process died after the old index was moved aside
+        write_index(&replaced_path, &["https://www.a.com/"]);
+        write_index(&rewrite_path, &["https://www.a.com/", "https://www.b.com/"]);
+
+        let index = InvertedIndex::open(&path).unwrap();
+        assert_eq!(index.num_documents(), 2);
+        assert!(!rewrite_path.exists());
+        assert!(!replaced_path.exists());
+        drop(index);
+
+        // the process died while the rewrite was being written
+        fs::create_dir_all(&rewrite_path).unwrap();
+        fs::write(rewrite_path.join("meta.json"), "{").unwrap();
+
+        let index = InvertedIndex::open(&path).unwrap();
+        assert_eq!(index.num_documents(), 2);
+        assert!(!rewrite_path.exists());
+    }
+
+    #[test]
+    fn reindexed_url_replaces_old_version() {
+        let (mut index, _dir) = InvertedIndex::temporary().expect("Unable to open index");
+
+        let page = |body: &str, url: &str| {
+            Webpage::test_parse(
+                &format!(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Test website</title>
+                        </head>
+                        <body>
+                            {body}
+                        </body>
+                    </html>
+                "#
+                ),
+                url,
+            )
+            .unwrap()
+        };
+
+        index
+            .insert(&page("the old recipe for apple pie", "https://www.a.com/"))
+            .expect("failed to insert webpage");
+        index
+            .insert(&page(
+                "a page that is not crawled again",
+                "https://www.b.com/",
+            ))
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+        index
+            .insert(&page(
+                "a segment without new versions",
+                "https://www.c.com/",
+            ))
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        let existing_segments = index.segment_ids();
+        let untouched_segment = index
+            .reader
+            .searcher()
+            .segment_readers()
+            .iter()
+            .find(|segment| segment.max_doc() == 1)
+            .unwrap()
+            .segment_id();
+
+        index
+            .insert(&page(
+                "the new recipe for banana bread",
+                "https://www.a.com/",
+            ))
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+        assert_eq!(index.num_documents(), 4);
+
+        assert_eq!(index.remove_replaced_urls(&existing_segments).unwrap(), 1);
+        assert_eq!(index.num_documents(), 3);
+
+        // only the segment with the old version is rewritten
+        assert_eq!(index.num_segments(), 3);
+        assert!(index.segment_ids().contains(&untouched_segment));
+
+        let search_urls = |index: &InvertedIndex, query: &str| {
+            let ctx = index.local_search_ctx();
+            let query = Query::parse(
+                &ctx,
+                &SearchQuery {
+                    query: query.to_string(),
+                    ..Default::default()
+                },
+                index,
+            )
+            .expect("Failed to parse query");
+            let ranker = LocalRanker::new(
+                SignalComputer::new(Some(&query)),
+                ctx.columnfield_reader.clone(),
+                CollectorConfig::default(),
+            );
+
+            search(index, &query, &ctx, ranker.collector(ctx.clone()))
+                .expect("Search failed")
+                .documents
+                .into_iter()
+                .map(|doc| doc.url)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(search_urls(&index, "apple").is_empty());
+        assert_eq!(search_urls(&index, "banana"), vec!["https://www.a.com/"]);
+        assert_eq!(search_urls(&index, "crawled"), vec!["https://www.b.com/"]);
+
+        // the old version is already gone
+        assert_eq!(index.remove_replaced_urls(&existing_segments).unwrap(), 0);
+    }
+
+    #[test]
+    fn replaced_urls_leave_no_files_behind() {
+        let (mut index, _dir) = InvertedIndex::temporary().expect("Unable to open index");
+
+        let page = |body: &str, url: &str| {
+            Webpage::test_parse(
+                &format!(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Test website</title>
+                        </head>
+                        <body>
+                            {body}
+                        </body>
+                    </html>
+                "#
+                ),
+                url,
+            )
+            .unwrap()
+        };
+
+        // files in the index directory that are not part of any searchable segment
+        let leaked_files = |index: &InvertedIndex| {
+            let segment_files: std::collections::HashSet<_> = index
+                .tantivy_index
+                .searchable_segments()
+                .unwrap()
+                .into_iter()
+                .flat_map(|segment| segment.meta().list_files())
+                .collect();
+
+            fs::read_dir(&index.path)
+                .unwrap()
+                .map(|entry| std::path::PathBuf::from(entry.unwrap().file_name()))
+                .filter(|file| {
+                    let name = file.to_str().unwrap();
+                    !name.starts_with('.') && name != "meta.json"
+                })
+                .filter(|file| !segment_files.contains(file))
+                .collect::<Vec<_>>()
+        };
+
+        index
+            .insert(&page("the old recipe for apple pie", "https://www.a.com/"))
+            .expect("failed to insert webpage");
+        index
+            .insert(&page(
+                "a page that is not crawled again",
+                "https://www.b.com/",
+            ))
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        let existing_segments = index.segment_ids();
+
+        index
+            .insert(&page(
+                "the new recipe for banana bread",
+                "https://www.a.com/",
+            ))
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        assert_eq!(index.remove_replaced_urls(&existing_segments).unwrap(), 1);
+        assert!(leaked_files(&index).is_empty());
+
+        // the rewritten segment must be known to the managed directory,
+        // otherwise its files are never garbage collected
+        let managed = index.tantivy_index.directory().list_managed_files();
+        for segment in index.tantivy_index.searchable_segments().unwrap() {
+            for file in segment.meta().list_files() {
+                if Path::new(&index.path).join(&file).exists() {
+                    assert!(managed.contains(&file), "{file:?} is not managed");
+                }
+            }
+        }
+
+        index.merge_into_max_segments(1).unwrap();
+        index.commit().expect("failed to commit index");
+
+        assert_eq!(index.num_segments(), 1);
+        assert_eq!(index.num_documents(), 2);
+        assert!(leaked_files(&index).is_empty());
+    }
 }
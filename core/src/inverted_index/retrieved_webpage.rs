@@ -7,12 +7,13 @@ use chrono::{DateTime, NaiveDateTime};
 use tantivy::{schema::Value, TantivyDocument};
 
 use crate::{
+    highlighted::HighlightedFragment,
     schema::{
         text_field::{self, TextField},
         Field, NumericalFieldEnum, TextFieldEnum,
     },
     snippet::TextSnippet,
-    webpage::{schema_org, Region},
+    webpage::{html::OpenGraph, schema_org, Region},
 };
 
 #[derive(
@@ -30,6 +31,7 @@ pub struct RetrievedWebpage {
     pub url: String,
     pub body: String,
     pub snippet: TextSnippet,
+    pub highlighted_title: Vec<HighlightedFragment>,
     pub dirty_body: String,
     pub description: Option<String>,
     pub dmoz_description: Option<String>,
@@ -41,6 +43,9 @@ pub struct RetrievedWebpage {
     pub likely_has_paywall: bool,
     pub recipe_first_ingredient_tag_id: Option<String>,
     pub keywords: Vec<String>,
+    pub favicon: Option<String>,
+    #[bincode(with_serde)]
+    pub opengraph: Option<OpenGraph>,
 }
 impl RetrievedWebpage {
     pub fn description(&self) -> Option<&String> {
@@ -117,6 +122,18 @@ impl From<TantivyDocument> for RetrievedWebpage {
                     let keywords = str_value(text_field::Keywords.name(), &value);
                     webpage.keywords = keywords.split('\n').map(|s| s.to_string()).collect();
                 }
+                Some(Field::Text(TextFieldEnum::Favicon(_))) => {
+                    let favicon = str_value(text_field::Favicon.name(), &value);
+                    if !favicon.is_empty() {
+                        webpage.favicon = Some(favicon);
+                    }
+                }
+                Some(Field::Text(TextFieldEnum::OpenGraphJson(_))) => {
+                    let json = str_value(text_field::OpenGraphJson.name(), &value);
+                    if !json.is_empty() {
+                        webpage.opengraph = serde_json::from_str(&json).ok();
+                    }
+                }
                 _ => {}
             }
         }
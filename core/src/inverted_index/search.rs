@@ -24,7 +24,8 @@ use tantivy::schema::Value;
 use tantivy::TantivyDocument;
 use url::Url;
 
-use crate::collector::approx_count::ApproxCount;
+use crate::collector::approx_count::{ApproxCount, DistinctCount, DistinctSites};
+use crate::collector::facets::{self, FacetCollector, FacetCounts, Facets};
 use crate::collector::{approx_count, MainCollector};
 
 use crate::highlighted::HighlightedFragment;
@@ -50,13 +51,32 @@ impl InvertedIndex {
         ctx: &Ctx,
         collector: MainCollector,
     ) -> Result<InitialSearchResult> {
+        let facet_collector = if query.return_facets() {
+            Some(FacetCollector::new(ctx.columnfield_reader.clone()))
+        } else {
+            None
+        };
+
+        let distinct_sites_collector = if collector
+            .top_docs()
+            .collector_config()
+            .estimate_distinct_sites
+        {
+            Some(DistinctSites::new(ctx.columnfield_reader.clone()))
+        } else {
+            None
+        };
+
         if query.count_results_exact() {
-            let collector = (Count, collector);
-            let (count, pointers) = ctx.tv_searcher.search(query, &collector)?;
+            let collector = (Count, collector, facet_collector, distinct_sites_collector);
+            let (count, pointers, facets, distinct_sites) =
+                ctx.tv_searcher.search(query, &collector)?;
 
             return Ok(InitialSearchResult {
                 num_websites: approx_count::Count::Exact(count as u64),
                 top_websites: pointers,
+                facets: self.resolve_facets(facets, &ctx.tv_searcher),
+                distinct_sites,
             });
         }
 
@@ -68,31 +88,65 @@ impl InvertedIndex {
                 return Ok(InitialSearchResult {
                     num_websites: approx_count::Count::Exact(0),
                     top_websites: vec![],
+                    facets: facet_collector.map(|_| Facets::default()),
+                    distinct_sites: distinct_sites_collector.map(|_| DistinctCount::default()),
                 });
             }
 
             let docs_per_segment = (limit.total_docs / limit.segments) as u64;
+
+            // the short circuit stops each segment after `docs_per_segment` documents,
+            // so the distinct sites are estimated over all matching documents instead.
+            let distinct_sites = distinct_sites_collector
+                .map(|distinct_sites| ctx.tv_searcher.search(&query, &distinct_sites))
+                .transpose()?;
+
             query = Box::new(ShortCircuitQuery::new(query, docs_per_segment));
 
-            let (count, pointers) = ctx.tv_searcher.search(
+            let (count, pointers, facets) = ctx.tv_searcher.search(
                 &query,
-                &(ApproxCount::new(docs_per_segment, simple_terms), collector),
+                &(
+                    ApproxCount::new(docs_per_segment, simple_terms),
+                    collector,
+                    facet_collector,
+                ),
             )?;
 
             Ok(InitialSearchResult {
                 num_websites: count,
                 top_websites: pointers,
+                facets: self.resolve_facets(facets, &ctx.tv_searcher),
+                distinct_sites,
             })
         } else {
-            let (count, pointers) = ctx.tv_searcher.search(&query, &(Count, collector))?;
+            let (count, pointers, facets, distinct_sites) = ctx.tv_searcher.search(
+                &query,
+                &(Count, collector, facet_collector, distinct_sites_collector),
+            )?;
 
             Ok(InitialSearchResult {
                 num_websites: approx_count::Count::Approximate(count as u64),
                 top_websites: pointers,
+                facets: self.resolve_facets(facets, &ctx.tv_searcher),
+                distinct_sites,
             })
         }
     }
 
+    fn resolve_facets(
+        &self,
+        counts: Option<FacetCounts>,
+        tv_searcher: &tantivy::Searcher,
+    ) -> Option<Facets> {
+        counts.map(|counts| {
+            counts.into_facets(facets::NUM_TOP_HOSTS, |address| {
+                let page = self.retrieve_doc(address, tv_searcher).ok()?;
+                let url = Url::parse(&page.url).ok()?;
+                url.normalized_host().map(|host| host.to_string())
+            })
+        })
+    }
+
     pub fn local_search_ctx(&self) -> Ctx {
         let tv_searcher = self.tv_searcher();
         Ctx {
@@ -194,6 +248,12 @@ impl InvertedIndex {
             let url = Url::parse(&page.url).ok()?;
             Some((url, page))
         }) {
+            page.highlighted_title = if query.simple_terms().is_empty() {
+                vec![HighlightedFragment::new_unhighlighted(page.title.clone())]
+            } else {
+                snippet::highlight_title(query, &page.title, &page.region)
+            };
+
             if query.simple_terms().is_empty() {
                 let snippet = if let Some(description) = page.description.as_deref() {
                     let snip = description
@@ -37,7 +37,7 @@ impl Checker for Feeds {
             let text = resp.text().await?;
             let parsed_feed = parse(&text, feed.kind)?;
 
-            for link in parsed_feed.links {
+            for link in parsed_feed.links() {
                 urls.push(CrawlableUrl::from(link));
             }
 
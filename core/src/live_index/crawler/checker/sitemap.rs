@@ -3,7 +3,7 @@
 //
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
-use std::time::Duration;
+use std::{collections::HashSet, future::Future, time::Duration};
 
 use url::Url;
 
@@ -49,51 +49,56 @@ impl Sitemap {
     }
 
     async fn urls_from_sitemap(&self, sitemap: Url) -> Vec<DatedUrl> {
-        let mut stack = vec![(sitemap, 0)];
-        let mut urls = vec![];
-
-        while let Some((url, depth)) = stack.pop() {
-            if depth >= MAX_SITEMAP_DEPTH {
-                continue;
-            }
-
+        walk_sitemaps(sitemap, |url| async move {
             let res = self.client.get(url).send().await;
             tokio::time::sleep(SITEMAP_DELAY).await;
 
-            if res.is_err() {
-                continue;
-            }
-
-            let res = res.unwrap();
+            let res = res.ok()?;
 
             if res.status() != reqwest::StatusCode::OK {
-                continue;
+                return None;
             }
 
-            let body = res.text().await;
-
-            if body.is_err() {
-                continue;
-            }
+            res.text().await.ok()
+        })
+        .await
+    }
+}
 
-            let body = body.unwrap();
+/// Collect the urls of the sitemap at `root`, following sitemap indexes up to
+/// [`MAX_SITEMAP_DEPTH`] levels deep. Each sitemap is fetched at most once, so
+/// indexes that refer to each other don't loop forever.
+async fn walk_sitemaps<F, Fut>(root: Url, fetch: F) -> Vec<DatedUrl>
+where
+    F: Fn(Url) -> Fut,
+    Fut: Future<Output = Option<String>>,
+{
+    let mut stack = vec![(root, 0)];
+    let mut visited = HashSet::new();
+    let mut urls = vec![];
+
+    while let Some((url, depth)) = stack.pop() {
+        if depth >= MAX_SITEMAP_DEPTH || !visited.insert(url.clone()) {
+            continue;
+        }
 
-            let entries = parse_sitemap(&body);
+        let Some(body) = fetch(url).await else {
+            continue;
+        };
 
-            for entry in entries {
-                match entry {
-                    SitemapEntry::Url(url) => {
-                        urls.push(url);
-                    }
-                    SitemapEntry::Sitemap(url) => {
-                        stack.push((url, depth + 1));
-                    }
+        for entry in parse_sitemap(&body) {
+            match entry {
+                SitemapEntry::Url(url) => {
+                    urls.push(url);
+                }
+                SitemapEntry::Sitemap(url) => {
+                    stack.push((url, depth + 1));
                 }
             }
         }
-
-        urls
     }
+
+    urls
 }
 
 impl Checker for Sitemap {
@@ -119,3 +124,85 @@ impl Checker for Sitemap {
         self.last_check.elapsed() > interval.sitemap
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn nested_sitemap_index() {
+        let sitemaps: HashMap<Url, String> = [
+            (
+                "https://example.com/sitemap.xml",
+                r#"<sitemapindex>
+                    <sitemap><loc>https://example.com/sitemap-posts.xml</loc></sitemap>
+                    <sitemap><loc>https://example.com/sitemap-pages.xml</loc></sitemap>
+                </sitemapindex>"#,
+            ),
+            (
+                "https://example.com/sitemap-posts.xml",
+                r#"<sitemapindex>
+                    <sitemap><loc>https://example.com/sitemap-posts-2024.xml</loc></sitemap>
+                    <sitemap><loc>https://example.com/sitemap.xml</loc></sitemap>
+                </sitemapindex>"#,
+            ),
+            (
+                "https://example.com/sitemap-posts-2024.xml",
+                r#"<urlset>
+                    <url>
+                        <loc>https://example.com/posts/1</loc>
+                        <lastmod>2024-01-02</lastmod>
+                    </url>
+                    <url>
+                        <loc>https://example.com/posts/2</loc>
+                    </url>
+                </urlset>"#,
+            ),
+            (
+                "https://example.com/sitemap-pages.xml",
+                r#"<urlset>
+                    <url>
+                        <loc>https://example.com/about</loc>
+                        <lastmod>2024-01-03T10:00:00+00:00</lastmod>
+                    </url>
+                </urlset>"#,
+            ),
+        ]
+        .into_iter()
+        .map(|(url, body)| (Url::parse(url).unwrap(), body.to_string()))
+        .collect();
+
+        let fetched = std::sync::Mutex::new(Vec::new());
+
+        let mut urls = walk_sitemaps(
+            Url::parse("https://example.com/sitemap.xml").unwrap(),
+            |url| {
+                fetched.lock().unwrap().push(url.clone());
+                let body = sitemaps.get(&url).cloned();
+                async move { body }
+            },
+        )
+        .await;
+
+        // the index that refers back to the root is not fetched again
+        assert_eq!(fetched.lock().unwrap().len(), 4);
+
+        urls.sort_by(|a, b| a.url.cmp(&b.url));
+
+        assert_eq!(
+            urls.iter().map(|url| url.url.as_str()).collect::<Vec<_>>(),
+            vec![
+                "https://example.com/about",
+                "https://example.com/posts/1",
+                "https://example.com/posts/2",
+            ]
+        );
+        assert_eq!(
+            urls[1].last_modified,
+            Some("2024-01-02T00:00:00+00:00".parse().unwrap())
+        );
+        assert_eq!(urls[2].last_modified, None);
+    }
+}
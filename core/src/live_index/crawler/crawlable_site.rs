@@ -28,7 +28,7 @@ use url::Url;
 
 use crate::{
     config::{CheckIntervals, CrawlerConfig},
-    crawler,
+    crawler::{self, HostRateLimiter},
     entrypoint::{
         indexer::IndexableWebpage,
         site_stats::{self, FinalSiteStats},
@@ -190,7 +190,12 @@ impl Drop for CrawlableSiteGuard {
 }
 
 impl CrawlableSiteGuard {
-    pub async fn crawl(self, client: &Client, interval: &CheckIntervals) -> Result<()> {
+    pub async fn crawl(
+        self,
+        client: &Client,
+        rate_limiter: &Arc<HostRateLimiter>,
+        interval: &CheckIntervals,
+    ) -> Result<()> {
         let mut site = self.site.inner.lock().await;
         let mut urls = Vec::new();
 
@@ -220,6 +225,9 @@ impl CrawlableSiteGuard {
 
         urls.retain(|url| !self.crawled_db.has_crawled(&url.url).unwrap_or(false));
 
+        // disallowed urls should neither use the budget nor be marked as crawled
+        urls = retain_allowed(client.robots_txt(), urls).await;
+
         order_urls(&mut urls);
 
         let budget = site.budget.min(urls.len() as u64);
@@ -265,7 +273,9 @@ impl CrawlableSiteGuard {
             client.reqwest().clone(),
             self.config.clone(),
             Arc::clone(&crawl_data),
-        );
+        )
+        .with_robots_txt(client.robots_txt().clone())
+        .with_rate_limiter(rate_limiter.clone());
 
         executor.run().await;
 
@@ -286,6 +296,23 @@ impl CrawlableSiteGuard {
     }
 }
 
+async fn retain_allowed(
+    robots_txt: &crawler::RobotsTxtManager,
+    urls: Vec<CrawlableUrl>,
+) -> Vec<CrawlableUrl> {
+    let mut allowed = Vec::with_capacity(urls.len());
+
+    for url in urls {
+        if robots_txt.is_allowed(&url.url).await {
+            allowed.push(url);
+        } else {
+            tracing::debug!("{} is disallowed by robots.txt", url.url);
+        }
+    }
+
+    allowed
+}
+
 fn order_urls(urls: &mut [CrawlableUrl]) {
     urls.sort_by(|a, b| match (a.last_modified, b.last_modified) {
         (Some(a), Some(b)) => a.cmp(&b).reverse(),
@@ -342,4 +369,29 @@ mod tests {
             Url::parse("https://example.com/page1").unwrap()
         );
     }
+
+    #[tokio::test]
+    async fn skip_disallowed_urls() {
+        let robots_txt = crawler::RobotsTxtManager::new(&CrawlerConfig::for_tests());
+        robots_txt.insert("example.com", "User-agent: *\nDisallow: /private\n");
+
+        let urls = [
+            "https://example.com/private/1",
+            "https://example.com/public/1",
+        ]
+        .into_iter()
+        .map(|url| CrawlableUrl {
+            url: Url::parse(url).unwrap(),
+            last_modified: None,
+        })
+        .collect();
+
+        let urls = retain_allowed(&robots_txt, urls).await;
+
+        assert_eq!(urls.len(), 1);
+        assert_eq!(
+            urls[0].url,
+            Url::parse("https://example.com/public/1").unwrap()
+        );
+    }
 }
@@ -33,7 +33,10 @@ use crate::distributed::sonic::replication::{
 use crate::distributed::streaming_response::StreamingResponse;
 use crate::entrypoint::search_server;
 use crate::entrypoint::site_stats::FinalSiteStats;
-use crate::{crawler, Result};
+use crate::{
+    crawler::{self, HostRateLimiter},
+    Result,
+};
 use crate::{
     distributed::sonic::replication::ReusableShardedClient,
     entrypoint::{indexer::IndexableWebpage, live_index},
@@ -57,6 +60,7 @@ struct Client {
     live_index: Mutex<ReusableShardedClient<live_index::LiveIndexService>>,
     search: Mutex<ReusableShardedClient<search_server::SearchService>>,
     reqwest: reqwest::Client,
+    robots_txt: crawler::RobotsTxtManager,
 }
 
 impl Client {
@@ -68,6 +72,7 @@ impl Client {
             live_index,
             search,
             reqwest: crawler::reqwest_client(crawler_config)?,
+            robots_txt: crawler::RobotsTxtManager::new(crawler_config),
         })
     }
 
@@ -75,6 +80,10 @@ impl Client {
         &self.reqwest
     }
 
+    pub fn robots_txt(&self) -> &crawler::RobotsTxtManager {
+        &self.robots_txt
+    }
+
     async fn live_conn(&self) -> Arc<ShardedClient<live_index::LiveIndexService, ShardId>> {
         self.live_index.lock().await.conn().await
     }
@@ -143,7 +152,7 @@ impl From<LiveCrawlerConfig> for CrawlerConfig {
         Self {
             num_worker_threads: 1,
             user_agent: config.user_agent,
-            robots_txt_cache_sec: crate::config::defaults::Crawler::robots_txt_cache_sec(),
+            robots_txt_cache_sec: config.robots_txt_ttl.as_secs(),
             min_politeness_factor: 0,
             start_politeness_factor: 1,
             min_crawl_delay_ms: MIN_CRAWL_DELAY.as_millis() as u64,
@@ -157,6 +166,7 @@ impl From<LiveCrawlerConfig> for CrawlerConfig {
                 access_key: String::new(),
                 secret_key: String::new(),
                 endpoint: String::new(),
+                date_filter: None,
             },
             router_hosts: vec![],
         }
@@ -198,6 +208,7 @@ pub struct Crawler {
     num_worker_threads: usize,
     check_intervals: CheckIntervals,
     crawler_config: Arc<CrawlerConfig>,
+    rate_limiter: Arc<HostRateLimiter>,
 }
 
 impl Crawler {
@@ -249,6 +260,7 @@ impl Crawler {
             num_worker_threads: config.num_worker_threads,
             check_intervals: config.check_intervals,
             crawler_config,
+            rate_limiter: Arc::new(HostRateLimiter::new(&config.host_crawl_delay)),
         })
     }
 
@@ -273,6 +285,7 @@ impl Crawler {
                 if site.should_crawl(&self.check_intervals).await {
                     tracing::debug!("Site {} should crawl", site.site().as_str());
                     let client = self.client.clone();
+                    let rate_limiter = self.rate_limiter.clone();
                     let intervals = self.check_intervals.clone();
                     let guard = CrawlableSiteGuard::new(
                         site.clone(),
@@ -286,7 +299,7 @@ impl Crawler {
                         let _permit = semaphore.acquire().await.unwrap();
                         let url = guard.url();
 
-                        if let Err(e) = guard.crawl(&client, &intervals).await {
+                        if let Err(e) = guard.crawl(&client, &rate_limiter, &intervals).await {
                             if let Ok(url) = url {
                                 tracing::error!("Failed to crawl site {}: {:?}", url, e);
                             } else {
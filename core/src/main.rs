@@ -116,6 +116,10 @@ enum Commands {
 
     /// Commands for the admin interface to manage stract.
     Admin {
+        /// Print responses as json instead of text.
+        #[clap(long, global = true)]
+        json: bool,
+
         #[clap(subcommand)]
         options: AdminOptions,
     },
@@ -153,6 +157,23 @@ enum AdminOptions {
         top: usize,
     },
 
+    /// Stop routing new searches to a member while letting running searches finish.
+    /// The member id is listed by `status`.
+    Drain {
+        member: String,
+    },
+
+    /// Route searches to a drained member again.
+    Undrain {
+        member: String,
+    },
+
+    /// Replace the lambdamart model of the api without restarting it.
+    /// The path is read by the api server.
+    ReloadLambdaModel {
+        path: String,
+    },
+
     #[clap(subcommand)]
     Index(AdminIndexOptions),
 }
@@ -160,7 +181,11 @@ enum AdminOptions {
 #[derive(Subcommand)]
 enum AdminIndexOptions {
     /// Get the size of the index
-    Size,
+    Size {
+        /// Print the on-disk size of each field as csv instead of the number of pages.
+        #[clap(long)]
+        by_field: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -255,6 +280,11 @@ enum IndexingOptions {
     MergeSearch {
         #[clap(required = true)]
         paths: Vec<String>,
+
+        /// Drop pages whose simhash is within this many bits of a page with a higher score.
+        /// At most 7.
+        #[clap(long)]
+        simhash_dedup_threshold: Option<u32>,
     },
 
     /// Create the entity index. Used in the sidebar of the search UI.
@@ -305,12 +335,15 @@ fn main() -> Result<()> {
                 wikipedia_dump_path,
                 output_path,
             } => entrypoint::EntityIndexer::run(wikipedia_dump_path, output_path)?,
-            IndexingOptions::MergeSearch { paths } => {
+            IndexingOptions::MergeSearch {
+                paths,
+                simhash_dedup_threshold,
+            } => {
                 let pointers = paths
                     .into_iter()
                     .map(entrypoint::indexer::IndexPointer::from)
                     .collect::<Vec<_>>();
-                entrypoint::indexer::merge(pointers)?;
+                entrypoint::indexer::merge(pointers, simhash_dedup_threshold)?;
             }
             IndexingOptions::Canonical { config_path } => {
                 let config: config::CanonicalIndexConfig = load_toml_config(config_path);
@@ -499,34 +532,68 @@ fn main() -> Result<()> {
             }
         },
 
-        Commands::Admin { options } => match options {
-            AdminOptions::Init { host } => {
-                entrypoint::admin::init(host)?;
-            }
+        Commands::Admin { json, options } => {
+            let format = if json {
+                entrypoint::admin::OutputFormat::Json
+            } else {
+                entrypoint::admin::OutputFormat::Text
+            };
 
-            AdminOptions::Status => {
-                tokio::runtime::Builder::new_current_thread()
-                    .enable_all()
-                    .build()?
-                    .block_on(entrypoint::admin::status())?;
-            }
+            match options {
+                AdminOptions::Init { host } => {
+                    entrypoint::admin::init(host)?;
+                }
 
-            AdminOptions::TopKeyphrases { top } => {
-                tokio::runtime::Builder::new_current_thread()
-                    .enable_all()
-                    .build()?
-                    .block_on(entrypoint::admin::top_keyphrases(top))?;
-            }
+                AdminOptions::Status => {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?
+                        .block_on(entrypoint::admin::status(format))?;
+                }
 
-            AdminOptions::Index(index_options) => match index_options {
-                AdminIndexOptions::Size => {
+                AdminOptions::TopKeyphrases { top } => {
                     tokio::runtime::Builder::new_current_thread()
                         .enable_all()
                         .build()?
-                        .block_on(entrypoint::admin::index_size())?;
+                        .block_on(entrypoint::admin::top_keyphrases(top, format))?;
                 }
-            },
-        },
+
+                AdminOptions::Drain { member } => {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?
+                        .block_on(entrypoint::admin::drain(member, format))?;
+                }
+
+                AdminOptions::Undrain { member } => {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?
+                        .block_on(entrypoint::admin::undrain(member, format))?;
+                }
+
+                AdminOptions::ReloadLambdaModel { path } => {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?
+                        .block_on(entrypoint::admin::reload_lambda_model(path, format))?;
+                }
+
+                AdminOptions::Index(index_options) => match index_options {
+                    AdminIndexOptions::Size { by_field } => {
+                        let runtime = tokio::runtime::Builder::new_current_thread()
+                            .enable_all()
+                            .build()?;
+
+                        if by_field {
+                            runtime.block_on(entrypoint::admin::index_size_by_field(format))?;
+                        } else {
+                            runtime.block_on(entrypoint::admin::index_size(format))?;
+                        }
+                    }
+                },
+            }
+        }
     }
 
     Ok(())
@@ -15,6 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::intmap::IntMap;
+use crate::Result;
 use ahash::{HashMapExt, HashSetExt};
 use itertools::Itertools;
 use std::hash::Hash;
@@ -37,6 +38,9 @@ pub struct TfIdf {
 struct TfidfVectorizer {
     vocabulary: HashMap<String, u64>,
     idf: IntMap<u64, f32>,
+    doc_freq: IntMap<u64, u64>,
+    num_docs: u64,
+    next_term_id: u64,
 }
 
 impl TfidfVectorizer {
@@ -44,12 +48,65 @@ impl TfidfVectorizer {
         Self {
             vocabulary: HashMap::new(),
             idf: IntMap::new(),
+            doc_freq: IntMap::new(),
+            num_docs: 0,
+            next_term_id: 0,
         }
     }
 
-    pub fn fit(&mut self, corpus: &[String]) {
+    /// Adds the documents to the vocabulary and document frequencies
+    /// without discarding the documents that have previously been seen.
+    pub fn update(&mut self, corpus: &[String]) {
         let doc_freq = self.calculate_document_frequencies(corpus);
-        self.build_vocabulary_and_idf(corpus, &doc_freq);
+
+        let new_words: Vec<String> = doc_freq
+            .keys()
+            .filter(|word| !self.vocabulary.contains_key(*word))
+            .cloned()
+            .sorted()
+            .collect();
+
+        for word in new_words {
+            self.vocabulary.insert(word, self.next_term_id);
+            self.next_term_id += 1;
+        }
+
+        for (word, freq) in doc_freq {
+            let term_id = self.vocabulary[&word];
+            let current = self.doc_freq.get(&term_id).copied().unwrap_or_default();
+            self.doc_freq.insert(term_id, current + freq as u64);
+        }
+
+        self.num_docs += corpus.len() as u64;
+        self.calculate_idf();
+    }
+
+    /// Removes previously seen documents from the document frequencies.
+    /// Words that no longer occur in any document are removed from the vocabulary.
+    pub fn forget(&mut self, corpus: &[String]) {
+        let doc_freq = self.calculate_document_frequencies(corpus);
+
+        for (word, freq) in doc_freq {
+            if let Some(term_id) = self.vocabulary.get(&word) {
+                if let Some(current) = self.doc_freq.get_mut(term_id) {
+                    *current = current.saturating_sub(freq as u64);
+                }
+            }
+        }
+
+        self.num_docs = self.num_docs.saturating_sub(corpus.len() as u64);
+
+        let doc_freq = &self.doc_freq;
+        self.vocabulary
+            .retain(|_, term_id| doc_freq.get(term_id).copied().unwrap_or_default() > 0);
+        self.doc_freq = self
+            .doc_freq
+            .iter()
+            .filter(|(_, freq)| *freq > 0)
+            .copied()
+            .collect();
+
+        self.calculate_idf();
     }
 
     /// Transforms a document into TF-IDF features
@@ -74,18 +131,15 @@ impl TfidfVectorizer {
         doc_freq
     }
 
-    /// Helper function to build vocabulary and idf
-    fn build_vocabulary_and_idf(&mut self, corpus: &[String], doc_freq: &HashMap<String, usize>) {
-        let mut vocab: Vec<String> = doc_freq.keys().cloned().collect();
-        vocab.sort();
-        for (term_id, word) in vocab.iter().enumerate() {
-            let term_id = term_id as u64;
-            self.vocabulary.insert(word.to_owned(), term_id);
-            self.idf.insert(
-                term_id,
-                ((corpus.len() as f32) / (1.0 + doc_freq[word] as f32)).ln() + 1.0,
-            );
-        }
+    /// Helper function to calculate idf from the document frequencies
+    fn calculate_idf(&mut self) {
+        let num_docs = self.num_docs as f32;
+
+        self.idf = self
+            .doc_freq
+            .iter()
+            .map(|(term_id, freq)| (*term_id, (num_docs / (1.0 + *freq as f32)).ln() + 1.0))
+            .collect();
     }
 
     /// Helper function to calculate term frequencies for a document
@@ -123,6 +177,14 @@ pub struct Datapoint<L: Label> {
     pub label: L,
 }
 
+/// Aggregated feature counts for all the datapoints of a class.
+pub struct ClassStats<L: Label> {
+    pub label: L,
+    /// Not necessarily a whole number, as the counts of a [`Pipeline`] can be decayed.
+    pub num_datapoints: f32,
+    pub feature_counts: IntMap<u64, f32>,
+}
+
 #[derive(Debug)]
 pub struct Prediction<L> {
     pub label: L,
@@ -156,9 +218,53 @@ impl<L: Label> NaiveBayes<L> {
         if datapoints.is_empty() {
             return;
         }
-        self.classes = self.extract_unique_classes(datapoints);
+        let classes = self.extract_unique_classes(datapoints);
         let class_counts = self.count_classes(datapoints);
-        self.calculate_feature_log_prob(&class_counts, datapoints);
+
+        let class_stats = classes
+            .into_iter()
+            .map(|class| {
+                let mut counts: IntMap<u64, f32> = IntMap::new();
+                for datapoint in datapoints.iter().filter(|d| d.label == class) {
+                    for feature in &datapoint.features {
+                        let count = counts.get(&feature.term_id).copied().unwrap_or_default();
+                        counts.insert(feature.term_id, count + feature.value);
+                    }
+                }
+
+                ClassStats {
+                    num_datapoints: class_counts[&class] as f32,
+                    label: class,
+                    feature_counts: counts,
+                }
+            })
+            .collect();
+
+        self.fit_aggregated(class_stats);
+    }
+
+    /// Fits the classifier from statistics that have already been aggregated
+    /// per class. The stats must be sorted by label.
+    pub fn fit_aggregated(&mut self, class_stats: Vec<ClassStats<L>>) {
+        let num_datapoints: f32 = class_stats.iter().map(|stats| stats.num_datapoints).sum();
+
+        self.classes = Vec::with_capacity(class_stats.len());
+        self.class_prior = Vec::with_capacity(class_stats.len());
+        self.feature_log_prob = Vec::with_capacity(class_stats.len());
+
+        for stats in class_stats {
+            let total_count: f32 = stats.feature_counts.iter().map(|(_, count)| count).sum();
+
+            let mut feature_log_prob = IntMap::new();
+            for (term_id, count) in stats.feature_counts.iter() {
+                let log_prob = ((count + ALPHA) / (total_count + ALPHA)).ln();
+                feature_log_prob.insert(*term_id, log_prob);
+            }
+
+            self.classes.push(stats.label);
+            self.class_prior.push(stats.num_datapoints / num_datapoints);
+            self.feature_log_prob.push(feature_log_prob);
+        }
     }
 
     /// Predicts the class for a given sample
@@ -203,39 +309,6 @@ impl<L: Label> NaiveBayes<L> {
         class_counts
     }
 
-    /// Helper function to calculate feature log probabilities for each class
-    fn calculate_feature_log_prob(
-        &mut self,
-        class_counts: &HashMap<L, usize>,
-        datapoints: &[Datapoint<L>],
-    ) {
-        let num_classes = self.classes.len();
-        let mut feature_log_prob = vec![IntMap::new(); num_classes];
-        for (class_id, class) in self.classes.iter().enumerate() {
-            let mut counts: IntMap<u64, f32> = IntMap::new();
-            let mut total_count: f32 = 0.0;
-            for datapoint in datapoints {
-                if &datapoint.label == class {
-                    for feature in &datapoint.features {
-                        let count = counts.get(&feature.term_id).copied().unwrap_or_default();
-                        counts.insert(feature.term_id, count + feature.value);
-                        total_count += feature.value;
-                    }
-                }
-            }
-            for (term_id, count) in counts.iter() {
-                let log_prob = ((count + ALPHA) / (total_count + ALPHA)).ln();
-                feature_log_prob[class_id].insert(*term_id, log_prob);
-            }
-        }
-        self.class_prior = self
-            .classes
-            .iter()
-            .map(|class| class_counts[class] as f32 / datapoints.len() as f32)
-            .collect();
-        self.feature_log_prob = feature_log_prob;
-    }
-
     /// Helper function to calculate the log probabilities for each class
     fn calculate_class_log_probs<'a>(
         &'a self,
@@ -263,10 +336,110 @@ impl<L: Label> NaiveBayes<L> {
     }
 }
 
+/// Raw term frequencies summed over all the documents of a class.
+/// As opposed to the tf-idf features, these are additive which allows
+/// us to update the pipeline without refitting it from scratch.
+#[derive(bincode::Encode, bincode::Decode)]
+struct ClassTermFreqs<L: Label> {
+    label: L,
+    /// A float so the counts can be decayed without rounding them away.
+    num_docs: f32,
+    term_freqs: IntMap<u64, f32>,
+}
+
+impl<L: Label> ClassTermFreqs<L> {
+    fn new(label: L) -> Self {
+        Self {
+            label,
+            num_docs: 0.0,
+            term_freqs: IntMap::new(),
+        }
+    }
+
+    fn add(&mut self, term_freqs: &IntMap<u64, f32>) {
+        self.num_docs += 1.0;
+
+        for (term_id, tf) in term_freqs.iter() {
+            let current = self.term_freqs.get(term_id).copied().unwrap_or_default();
+            self.term_freqs.insert(*term_id, current + tf);
+        }
+    }
+
+    fn remove(&mut self, term_freqs: &IntMap<u64, f32>) {
+        self.num_docs = (self.num_docs - 1.0).max(0.0);
+
+        for (term_id, tf) in term_freqs.iter() {
+            if let Some(current) = self.term_freqs.get_mut(term_id) {
+                *current = (*current - tf).max(0.0);
+            }
+        }
+
+        self.term_freqs = self
+            .term_freqs
+            .iter()
+            .filter(|(_, tf)| *tf > 0.0)
+            .copied()
+            .collect();
+    }
+
+    fn decay(&mut self, factor: f32) {
+        self.num_docs *= factor;
+
+        self.term_freqs = self
+            .term_freqs
+            .iter()
+            .map(|(term_id, tf)| (*term_id, tf * factor))
+            .filter(|(_, tf)| *tf > 0.0)
+            .collect();
+    }
+}
+
 #[derive(bincode::Encode, bincode::Decode)]
 pub struct Pipeline<L: Label> {
     vectorizer: TfidfVectorizer,
     classifier: NaiveBayes<L>,
+    /// sorted by label
+    class_term_freqs: Vec<ClassTermFreqs<L>>,
+}
+
+/// The encoding of [`TfidfVectorizer`] from before it kept document frequencies.
+#[derive(bincode::Decode)]
+struct TfidfVectorizerV0 {
+    vocabulary: HashMap<String, u64>,
+    idf: IntMap<u64, f32>,
+}
+
+/// The encoding of [`Pipeline`] from before it could be updated incrementally.
+#[derive(bincode::Decode)]
+pub struct PipelineV0<L: Label> {
+    vectorizer: TfidfVectorizerV0,
+    classifier: NaiveBayes<L>,
+}
+
+/// The fitted classifier and idf weights are kept, so the pipeline predicts
+/// exactly as before. The counts it was fitted from were never stored, so the
+/// converted pipeline cannot be updated, forgotten from or decayed until it is refitted.
+impl<L: Label> From<PipelineV0<L>> for Pipeline<L> {
+    fn from(pipeline: PipelineV0<L>) -> Self {
+        let next_term_id = pipeline
+            .vectorizer
+            .vocabulary
+            .values()
+            .max()
+            .map_or(0, |term_id| term_id + 1);
+
+        Self {
+            vectorizer: TfidfVectorizer {
+                vocabulary: pipeline.vectorizer.vocabulary,
+                idf: pipeline.vectorizer.idf,
+                doc_freq: IntMap::new(),
+                num_docs: 0,
+                next_term_id,
+            },
+            classifier: pipeline.classifier,
+            class_term_freqs: Vec::new(),
+        }
+    }
 }
 
 impl Default for Pipeline<String> {
@@ -280,22 +453,140 @@ impl<L: Label> Pipeline<L> {
         Self {
             vectorizer: TfidfVectorizer::new(),
             classifier: NaiveBayes::new(),
+            class_term_freqs: Vec::new(),
         }
     }
 
     pub fn fit(&mut self, data: &[(String, L)]) {
-        let (corpus, labels): (Vec<_>, Vec<_>) = data.iter().cloned().unzip();
+        *self = Self::new();
+        self.learn(data);
+    }
+
+    /// Pipelines converted from [`PipelineV0`] have a fitted classifier
+    /// but none of the counts it was fitted from.
+    fn has_counts(&self) -> bool {
+        self.classifier.classes.is_empty() || !self.class_term_freqs.is_empty()
+    }
 
-        self.vectorizer.fit(&corpus);
-        let datapoints: Vec<Datapoint<_>> = corpus
+    fn ensure_counts(&self) -> Result<()> {
+        if !self.has_counts() {
+            anyhow::bail!(
+                "the pipeline was loaded from a format without class counts and must be refitted"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Folds the new labelled documents into the existing counts
+    /// without discarding what the pipeline has previously been trained on.
+    pub fn update(&mut self, data: &[(String, L)]) -> Result<()> {
+        self.ensure_counts()?;
+        self.learn(data);
+
+        Ok(())
+    }
+
+    fn learn(&mut self, data: &[(String, L)]) {
+        if data.is_empty() {
+            return;
+        }
+
+        let corpus: Vec<_> = data.iter().map(|(doc, _)| doc.clone()).collect();
+        self.vectorizer.update(&corpus);
+
+        for (doc, label) in data {
+            let term_freqs = self.vectorizer.calculate_term_frequencies(doc);
+
+            let idx = match self
+                .class_term_freqs
+                .binary_search_by(|class| class.label.cmp(label))
+            {
+                Ok(idx) => idx,
+                Err(idx) => {
+                    self.class_term_freqs
+                        .insert(idx, ClassTermFreqs::new(label.clone()));
+                    idx
+                }
+            };
+
+            self.class_term_freqs[idx].add(&term_freqs);
+        }
+
+        self.fit_classifier();
+    }
+
+    /// Removes previously learned documents from the counts.
+    /// This can be used to correct mislabeled documents by forgetting
+    /// them and updating with the correct label.
+    pub fn forget(&mut self, data: &[(String, L)]) -> Result<()> {
+        self.ensure_counts()?;
+
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        for (doc, label) in data {
+            let term_freqs = self.vectorizer.calculate_term_frequencies(doc);
+
+            if let Ok(idx) = self
+                .class_term_freqs
+                .binary_search_by(|class| class.label.cmp(label))
+            {
+                self.class_term_freqs[idx].remove(&term_freqs);
+            }
+        }
+
+        self.class_term_freqs.retain(|class| class.num_docs > 0.0);
+
+        let corpus: Vec<_> = data.iter().map(|(doc, _)| doc.clone()).collect();
+        self.vectorizer.forget(&corpus);
+
+        self.fit_classifier();
+
+        Ok(())
+    }
+
+    /// Scales the class counts by `factor`, which must be in `(0, 1]`, so documents
+    /// learned before the decay weigh less than documents learned after it.
+    /// The vocabulary and idf weights are left untouched.
+    pub fn decay(&mut self, factor: f32) -> Result<()> {
+        if factor.is_nan() || factor <= 0.0 || factor > 1.0 {
+            anyhow::bail!("decay factor must be in (0, 1], got {factor}");
+        }
+
+        self.ensure_counts()?;
+
+        for class in &mut self.class_term_freqs {
+            class.decay(factor);
+        }
+
+        self.fit_classifier();
+
+        Ok(())
+    }
+
+    fn fit_classifier(&mut self) {
+        let class_stats = self
+            .class_term_freqs
             .iter()
-            .zip_eq(labels)
-            .map(|(doc, label)| Datapoint {
-                features: self.vectorizer.transform(doc),
-                label: label.clone(),
+            .map(|class| ClassStats {
+                label: class.label.clone(),
+                num_datapoints: class.num_docs,
+                feature_counts: class
+                    .term_freqs
+                    .iter()
+                    .filter_map(|(term_id, tf)| {
+                        self.vectorizer
+                            .idf
+                            .get(term_id)
+                            .map(|idf| (*term_id, tf * idf))
+                    })
+                    .collect(),
             })
             .collect();
-        self.classifier.fit(&datapoints);
+
+        self.classifier.fit_aggregated(class_stats);
     }
 
     pub fn predict(&self, doc: &str) -> Prediction<L> {
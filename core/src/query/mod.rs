@@ -15,19 +15,26 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
+    config::CompoundTermsConfig,
     inverted_index::InvertedIndex,
     query::parser::TermCompound,
     ranking::SignalCoefficients,
-    schema::text_field,
+    schema::{
+        numerical_field::{self, NumericalField},
+        text_field, TextFieldEnum,
+    },
     search_ctx::Ctx,
     searcher::SearchQuery,
-    webpage::{region::Region, safety_classifier},
+    webpage::{region::Region, safety_classifier, GeoPoint},
     Error, Result,
 };
 
 use optics::{HostRankings, Optic};
 
-use tantivy::query::{BooleanQuery, Occur, QueryClone};
+use tantivy::{
+    query::{BooleanQuery, Occur, QueryClone, TermQuery},
+    schema::IndexRecordOption,
+};
 
 mod const_query;
 pub mod intersection;
@@ -37,39 +44,73 @@ mod pattern_query;
 mod plan;
 pub mod shortcircuit;
 pub mod union;
+mod url_regex;
 
 use self::{optic::AsMultipleTantivyQuery, parser::SimpleOrPhrase};
 use parser::Term;
 
+/// The default maximum number of terms in a query for it to be expanded with compound terms
+/// and for the ngram fields to be used in ranking.
 pub const MAX_TERMS_FOR_NGRAM_LOOKUPS: usize = 16;
 
+/// A rough estimate of how expensive the search query is to execute, based on the
+/// number of terms and phrase words it expands into. The index is not accessed.
+/// Returns `None` if the query can't be parsed or has no searchable terms.
+pub fn estimated_cost(query: &SearchQuery) -> Option<usize> {
+    let terms = parser::truncate(parser::parse(&query.query).ok()?);
+
+    if terms.iter().all(|t| matches!(t, Term::PossibleBang { .. })) {
+        return None;
+    }
+
+    plan::initial(terms, &query.compound_terms).map(|plan| plan.into_query().estimated_cost())
+}
+
+/// The text of a `<field>:<term>^<boost>` query term.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldTermText {
+    pub field: TextFieldEnum,
+    pub text: String,
+    pub boost: f32,
+}
+
 #[derive(Debug)]
 pub struct Query {
     simple_terms_text: Vec<String>,
+    field_terms: Vec<FieldTermText>,
+    phrases: Vec<Vec<String>>,
     tantivy_query: Box<dyn tantivy::query::Query>,
     host_rankings: HostRankings,
     offset: usize,
     region: Option<Region>,
+    geo_point: Option<GeoPoint>,
     optics: Vec<Optic>,
     top_n: usize,
     count_results_exact: bool,
+    return_facets: bool,
     signal_coefficients: SignalCoefficients,
     lang: Option<whatlang::Lang>,
+    compound_terms: CompoundTermsConfig,
 }
 
 impl Clone for Query {
     fn clone(&self) -> Self {
         Self {
             simple_terms_text: self.simple_terms_text.clone(),
+            field_terms: self.field_terms.clone(),
+            phrases: self.phrases.clone(),
             tantivy_query: self.tantivy_query.box_clone(),
             host_rankings: self.host_rankings.clone(),
             offset: self.offset,
             region: self.region,
+            geo_point: self.geo_point,
             optics: self.optics.clone(),
             top_n: self.top_n,
             count_results_exact: self.count_results_exact,
+            return_facets: self.return_facets,
             signal_coefficients: self.signal_coefficients.clone(),
             lang: self.lang,
+            compound_terms: self.compound_terms,
         }
     }
 }
@@ -104,17 +145,39 @@ impl Query {
             })
             .collect();
 
-        let mut plan = plan::initial(parsed_terms).expect("terms are not empty and not all bangs");
+        let field_terms = parsed_terms
+            .iter()
+            .filter_map(|term| term.as_field_text())
+            .collect();
+
+        let phrases = parsed_terms
+            .iter()
+            .filter_map(|term| match term {
+                Term::SimpleOrPhrase(
+                    SimpleOrPhrase::Phrase(phrase, _) | SimpleOrPhrase::PhrasePrefix(phrase),
+                ) if phrase.len() > 1 => Some(phrase.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let mut plan = plan::initial(parsed_terms, &query.compound_terms)
+            .expect("terms are not empty and not all bangs");
 
         let schema = index.schema();
 
         if query.safe_search {
-            plan = plan.and(plan::Node::Not(Box::new(plan::Node::Term(
-                plan::Term::new(
-                    parser::SimpleTerm::from(safety_classifier::Label::NSFW.to_string()).into(),
-                    text_field::SafetyClassification.into(),
-                ),
-            ))));
+            // pages the classifier was not confident about might be unsafe
+            for label in [
+                safety_classifier::Label::NSFW,
+                safety_classifier::Label::Unknown,
+            ] {
+                plan = plan.and(plan::Node::Not(Box::new(plan::Node::Term(
+                    plan::Term::new(
+                        parser::SimpleTerm::from(label.to_string()).into(),
+                        text_field::SafetyClassification.into(),
+                    ),
+                ))));
+            }
         }
 
         let mut tantivy_query = plan
@@ -122,6 +185,24 @@ impl Query {
             .as_tantivy(lang.as_ref(), &schema)
             .expect("there should at least be one field in the index");
 
+        let secure_only =
+            query.secure_only || query.optic.as_ref().is_some_and(|optic| optic.secure_only);
+
+        if secure_only {
+            let field = numerical_field::IsHttps.tantivy_field(&schema);
+
+            tantivy_query = Box::new(BooleanQuery::new(vec![
+                (Occur::Must, tantivy_query),
+                (
+                    Occur::Must,
+                    Box::new(TermQuery::new(
+                        tantivy::Term::from_field_bool(field, true),
+                        IndexRecordOption::Basic,
+                    )),
+                ),
+            ]));
+        }
+
         let mut optics = Vec::new();
         if let Some(site_rankigns_optic) = query.host_rankings.clone().map(|sr| sr.into_optic()) {
             optics.push(site_rankigns_optic);
@@ -143,14 +224,19 @@ impl Query {
                 acc
             }),
             simple_terms_text,
+            field_terms,
+            phrases,
             tantivy_query,
             optics,
-            offset: query.num_results * query.page,
+            offset: query.offset(),
             region: query.selected_region,
+            geo_point: query.geo_point,
             top_n: query.num_results,
             count_results_exact: query.count_results_exact,
+            return_facets: query.return_facets,
             signal_coefficients: query.signal_coefficients(),
             lang,
+            compound_terms: query.compound_terms,
         })
     }
 
@@ -158,10 +244,26 @@ impl Query {
         self.count_results_exact
     }
 
+    pub fn return_facets(&self) -> bool {
+        self.return_facets
+    }
+
     pub fn simple_terms(&self) -> &[String] {
         &self.simple_terms_text
     }
 
+    /// The terms that target a single text field. They are not part of
+    /// [`Query::simple_terms`].
+    pub fn field_terms(&self) -> &[FieldTermText] {
+        &self.field_terms
+    }
+
+    /// The phrases of the query with more than one word.
+    /// The words are also part of [`Query::simple_terms`].
+    pub fn phrases(&self) -> &[Vec<String>] {
+        &self.phrases
+    }
+
     pub fn optics(&self) -> &[Optic] {
         &self.optics
     }
@@ -178,6 +280,14 @@ impl Query {
         self.region.as_ref()
     }
 
+    pub fn geo_point(&self) -> Option<GeoPoint> {
+        self.geo_point
+    }
+
+    pub fn compound_terms(&self) -> &CompoundTermsConfig {
+        &self.compound_terms
+    }
+
     pub fn host_rankings(&self) -> &HostRankings {
         &self.host_rankings
     }
@@ -849,6 +959,147 @@ mod tests {
         assert_eq!(result.webpages[0].url, "https://www.the-first.com/");
     }
 
+    #[test]
+    fn site_operator_matches_subdomains() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        for url in [
+            "https://example.com",
+            "https://blog.example.com/user/login",
+            "https://notexample.com",
+            "https://example.com.org",
+        ] {
+            index
+                .insert(
+                    &Webpage::test_parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                This is a test website {}
+                            </body>
+                        </html>
+                    "#,
+                            rand_words(1000)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                )
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let search = |query: &str| {
+            let mut urls: Vec<_> = searcher
+                .search(&SearchQuery {
+                    query: query.to_string(),
+                    ..Default::default()
+                })
+                .expect("Search failed")
+                .webpages
+                .into_iter()
+                .map(|page| page.url)
+                .collect();
+            urls.sort();
+            urls
+        };
+
+        assert_eq!(
+            search("test site:example.com"),
+            vec![
+                "https://blog.example.com/user/login".to_string(),
+                "https://example.com/".to_string(),
+            ]
+        );
+        assert_eq!(
+            search("test site:*.example.com"),
+            vec!["https://blog.example.com/user/login".to_string()]
+        );
+        assert_eq!(
+            search("test site:blog.example.com"),
+            vec!["https://blog.example.com/user/login".to_string()]
+        );
+        assert_eq!(
+            search("test site:example.com."),
+            vec!["https://example.com/".to_string()]
+        );
+        assert_eq!(
+            search("test site:blog.example.com."),
+            vec!["https://blog.example.com/user/login".to_string()]
+        );
+        assert_eq!(
+            search("test inurl:login"),
+            vec!["https://blog.example.com/user/login".to_string()]
+        );
+        assert_eq!(
+            search("test site:example.com -inurl:login"),
+            vec!["https://example.com/".to_string()]
+        );
+    }
+
+    #[test]
+    fn accent_insensitive() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        for (url, word) in [
+            ("https://www.accent.com", "café"),
+            ("https://www.plain.com", "cafe"),
+        ] {
+            index
+                .insert(
+                    &Webpage::test_parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                The best {word} in town {}
+                            </body>
+                        </html>
+                    "#,
+                            rand_words(1000)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                )
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        for query in ["cafe", "café", "CAFÉ"] {
+            let mut urls: Vec<_> = searcher
+                .search(&SearchQuery {
+                    query: query.to_string(),
+                    ..Default::default()
+                })
+                .expect("Search failed")
+                .webpages
+                .into_iter()
+                .map(|page| page.url)
+                .collect();
+            urls.sort();
+
+            assert_eq!(
+                urls,
+                vec![
+                    "https://www.accent.com/".to_string(),
+                    "https://www.plain.com/".to_string(),
+                ]
+            );
+        }
+    }
+
     #[test]
     fn phrase_query() {
         let (mut index, _dir) = Index::temporary().expect("Unable to open index");
@@ -914,6 +1165,135 @@ mod tests {
         assert_eq!(result.webpages.len(), 0);
     }
 
+    #[test]
+    fn phrase_slop_query() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(
+                &Webpage::test_parse(
+                    &format!(
+                        r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                The quick brown fox {}
+                            </body>
+                        </html>
+                    "#,
+                        rand_words(1000)
+                    ),
+                    "https://www.first.com",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let query = SearchQuery {
+            query: "\"quick fox\"".to_string(),
+            ..Default::default()
+        };
+        let result = searcher.search(&query).expect("Search failed");
+        assert_eq!(result.webpages.len(), 0);
+
+        let query = SearchQuery {
+            query: "\"quick fox\"~1".to_string(),
+            ..Default::default()
+        };
+        let result = searcher.search(&query).expect("Search failed");
+        assert_eq!(result.webpages.len(), 1);
+        assert_eq!(result.webpages[0].url, "https://www.first.com/");
+    }
+
+    #[test]
+    fn phrase_prefix_query() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(
+                &Webpage::test_parse(
+                    &format!(
+                        r#"
+                        <html>
+                            <head>
+                                <title>New York Times</title>
+                            </head>
+                            <body>
+                                Breaking news from the new york times {}
+                            </body>
+                        </html>
+                    "#,
+                        rand_words(1000)
+                    ),
+                    "https://www.first.com",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+        index
+            .insert(
+                &Webpage::test_parse(
+                    &format!(
+                        r#"
+                        <html>
+                            <head>
+                                <title>Times are new in York</title>
+                            </head>
+                            <body>
+                                The times are new in york {}
+                            </body>
+                        </html>
+                    "#,
+                        rand_words(1000)
+                    ),
+                    "https://www.second.com",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let query = SearchQuery {
+            query: "\"new york t*\"".to_string(),
+            ..Default::default()
+        };
+        let result = searcher.search(&query).expect("Search failed");
+        assert_eq!(result.webpages.len(), 1);
+        assert_eq!(result.webpages[0].url, "https://www.first.com/");
+
+        let query = SearchQuery {
+            query: "\"new york ti*\"".to_string(),
+            ..Default::default()
+        };
+        let result = searcher.search(&query).expect("Search failed");
+        assert_eq!(result.webpages.len(), 1);
+        assert_eq!(result.webpages[0].url, "https://www.first.com/");
+
+        let query = SearchQuery {
+            query: "\"new york tu*\"".to_string(),
+            ..Default::default()
+        };
+        let result = searcher.search(&query).expect("Search failed");
+        assert_eq!(result.webpages.len(), 0);
+
+        let query = SearchQuery {
+            query: "\"new * times\"".to_string(),
+            ..Default::default()
+        };
+        assert!(searcher.search(&query).is_err());
+
+        let query = SearchQuery {
+            query: "\"new york *\"".to_string(),
+            ..Default::default()
+        };
+        assert!(searcher.search(&query).is_err());
+    }
+
     #[test]
     fn match_compound_words() {
         let (mut index, _dir) = Index::temporary().expect("Unable to open index");
@@ -1044,6 +1424,29 @@ mod tests {
 
         index.insert(&webpage).expect("failed to insert webpage");
 
+        let mut webpage = Webpage::test_parse(
+            &format!(
+                r#"
+                <html>
+                    <head>
+                        <title>Test website</title>
+                    </head>
+                    <body>
+                        This is a test website {}
+                    </body>
+                </html>
+            "#,
+                rand_words(1000)
+            ),
+            "https://www.unknown.com",
+        )
+        .unwrap();
+
+        webpage.safety_classification = Some(safety_classifier::Label::Unknown);
+        webpage.html.set_clean_text("unknown".to_string());
+
+        index.insert(&webpage).expect("failed to insert webpage");
+
         index.commit().expect("failed to commit index");
         let searcher = LocalSearcher::from(index);
 
@@ -1054,7 +1457,7 @@ mod tests {
         };
 
         let result = searcher.search(&query).expect("Search failed");
-        assert_eq!(result.webpages.len(), 2);
+        assert_eq!(result.webpages.len(), 3);
 
         let query = SearchQuery {
             query: "test".to_string(),
@@ -1260,6 +1663,155 @@ mod tests {
         assert_eq!(result.webpages.len(), 0);
     }
 
+    #[test]
+    fn numeric_range_query() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        for (url, fetch_time_ms) in [
+            ("https://www.first.com", 1000),
+            ("https://www.second.com", 2000),
+            ("https://www.third.com", 3000),
+        ] {
+            let mut webpage = Webpage::test_parse(
+                &format!(
+                    r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                This is a test website {}
+                            </body>
+                        </html>
+                    "#,
+                    rand_words(1000)
+                ),
+                url,
+            )
+            .unwrap();
+            webpage.fetch_time_ms = fetch_time_ms;
+
+            index.insert(&webpage).expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let urls = |query: &str| {
+            let query = SearchQuery {
+                query: query.to_string(),
+                ..Default::default()
+            };
+            let mut urls: Vec<_> = searcher
+                .search(&query)
+                .expect("Search failed")
+                .webpages
+                .into_iter()
+                .map(|webpage| webpage.url)
+                .collect();
+            urls.sort();
+            urls
+        };
+
+        // closed bounds are inclusive
+        assert_eq!(
+            urls("test fetch_time_ms:1000..2000"),
+            vec!["https://www.first.com/", "https://www.second.com/"]
+        );
+        assert_eq!(
+            urls("test fetch_time_ms:1500..2500"),
+            vec!["https://www.second.com/"]
+        );
+        assert!(urls("test fetch_time_ms:1001..1999").is_empty());
+
+        // open bounds
+        assert_eq!(
+            urls("test fetch_time_ms:2000.."),
+            vec!["https://www.second.com/", "https://www.third.com/"]
+        );
+        assert_eq!(
+            urls("test fetch_time_ms:..1500"),
+            vec!["https://www.first.com/"]
+        );
+        assert_eq!(urls("test fetch_time_ms:..").len(), 3);
+    }
+
+    #[test]
+    fn inserted_at_date_range() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        for (url, inserted_at) in [
+            ("https://www.old.com", "2023-06-01T12:00:00Z"),
+            ("https://www.recent.com", "2024-03-15T08:30:00Z"),
+        ] {
+            let mut webpage = Webpage::test_parse(
+                &format!(
+                    r#"
+                        <html>
+                            <head>
+                                <title>Test website</title>
+                            </head>
+                            <body>
+                                This is a test website {}
+                            </body>
+                        </html>
+                    "#,
+                    rand_words(1000)
+                ),
+                url,
+            )
+            .unwrap();
+            webpage.inserted_at = inserted_at.parse().unwrap();
+
+            index.insert(&webpage).expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let urls = |query: &str| {
+            let query = SearchQuery {
+                query: query.to_string(),
+                ..Default::default()
+            };
+            let mut urls: Vec<_> = searcher
+                .search(&query)
+                .expect("Search failed")
+                .webpages
+                .into_iter()
+                .map(|webpage| webpage.url)
+                .collect();
+            urls.sort();
+            urls
+        };
+
+        assert_eq!(
+            urls("test inserted_at:2024-01-01.."),
+            vec!["https://www.recent.com/"]
+        );
+        assert_eq!(
+            urls("test inserted_at:..2024-01-01"),
+            vec!["https://www.old.com/"]
+        );
+        assert_eq!(urls("test inserted_at:2023-06-01..2024-03-15").len(), 2);
+        assert!(urls("test inserted_at:2024-03-16..").is_empty());
+
+        // an upper bound includes the whole day
+        assert_eq!(
+            urls("test inserted_at:2024-03-15..2024-03-15"),
+            vec!["https://www.recent.com/"]
+        );
+        assert!(urls("test inserted_at:..2024-03-14")
+            .iter()
+            .all(|url| url != "https://www.recent.com/"));
+
+        // unix timestamps work as well
+        assert_eq!(
+            urls("test inserted_at:1710491400..1710491400"),
+            vec!["https://www.recent.com/"]
+        );
+    }
+
     #[test]
     fn mix_phrase_term_query() {
         let (mut index, _dir) = Index::temporary().expect("Unable to open index");
@@ -18,18 +18,21 @@ use itertools::Itertools;
 use optics::{Action, MatchLocation, Matching, Optic, PatternPart, Rule};
 use std::iter;
 use tantivy::{
-    query::{BooleanQuery, Occur, QueryClone},
-    schema::Schema,
+    query::{BooleanQuery, EmptyQuery, Occur, QueryClone, TermQuery},
+    schema::{IndexRecordOption, Schema},
 };
 use url::Url;
 
 use crate::{
     numericalfield_reader::NumericalFieldReader,
-    schema::text_field,
+    schema::text_field::{self, TextField},
     webpage::{schema_org, url_ext::UrlExt},
 };
 
-use super::{const_query::ConstQuery, pattern_query::PatternQuery, union::UnionQuery};
+use super::{
+    const_query::ConstQuery, pattern_query::PatternQuery, union::UnionQuery,
+    url_regex::UrlRegexQuery,
+};
 
 pub trait AsTantivyQuery {
     fn as_tantivy(
@@ -73,6 +76,7 @@ impl AsMultipleTantivyQuery for Optic {
                 .iter()
                 .filter(|rule| matches!(rule.action, Action::Discard))
                 .chain(iter::once(&self.host_rankings.rules()))
+                .chain(self.language_rule().as_ref())
                 .filter_map(|rule| rule.as_searchable_rule(schema, columnfields))
                 .map(|(occur, rule)| (occur, rule.query))
                 .chain(iter::once(block))
@@ -81,6 +85,7 @@ impl AsMultipleTantivyQuery for Optic {
             self.rules
                 .iter()
                 .chain(iter::once(&self.host_rankings.rules()))
+                .chain(self.language_rule().as_ref())
                 .filter_map(|rule| rule.as_searchable_rule(schema, columnfields))
                 .map(|(occur, rule)| (occur, rule.query))
                 .collect()
@@ -177,6 +182,24 @@ impl AsTantivyQuery for Matching {
         let field = match &self.location {
             MatchLocation::Site => text_field::UrlForSiteOperator.into(),
             MatchLocation::Url => text_field::Url.into(),
+            MatchLocation::UrlRegex => {
+                let Some(field) = text_field::UrlNoTokenizer.tantivy_field(schema) else {
+                    return Box::new(EmptyQuery);
+                };
+
+                // the regex is validated when the optic is parsed, so this only
+                // fails for a hand-built optic or a regex with a too large DFA
+                return match self.pattern.as_slice() {
+                    [PatternPart::Raw(regex)] => match UrlRegexQuery::new(regex, field) {
+                        Ok(query) => Box::new(ConstQuery::new(Box::new(query), 1.0)),
+                        Err(err) => {
+                            tracing::debug!("ignoring url regex: {err}");
+                            Box::new(EmptyQuery)
+                        }
+                    },
+                    _ => Box::new(EmptyQuery),
+                };
+            }
             MatchLocation::Domain => {
                 // if pattern is "|raw|" and `raw` is actually a site
                 // instead of a domain, change match location to site
@@ -209,6 +232,38 @@ impl AsTantivyQuery for Matching {
             MatchLocation::Description => text_field::Description.into(),
             MatchLocation::Content => text_field::CleanBody.into(),
             MatchLocation::MicroformatTag => text_field::MicroformatTags.into(),
+            MatchLocation::Language => {
+                return match (
+                    self.pattern.as_slice(),
+                    text_field::Language.tantivy_field(schema),
+                ) {
+                    ([PatternPart::Raw(lang)], Some(field)) => Box::new(ConstQuery::new(
+                        Box::new(TermQuery::new(
+                            tantivy::Term::from_field_text(field, lang),
+                            IndexRecordOption::Basic,
+                        )),
+                        1.0,
+                    )),
+                    _ => Box::new(EmptyQuery),
+                };
+            }
+            MatchLocation::SchemaPath => {
+                // the path is validated when the optic is parsed and looked up
+                // directly, so nested itemtypes along the path are skipped.
+                return match (
+                    self.pattern.as_slice(),
+                    text_field::SchemaOrgPaths.tantivy_field(schema),
+                ) {
+                    ([PatternPart::Raw(path)], Some(field)) => Box::new(ConstQuery::new(
+                        Box::new(TermQuery::new(
+                            tantivy::Term::from_field_text(field, path),
+                            IndexRecordOption::Basic,
+                        )),
+                        1.0,
+                    )),
+                    _ => Box::new(EmptyQuery),
+                };
+            }
             MatchLocation::Schema => {
                 let mut pattern = self.pattern.clone();
                 // add TYPE_PREFIX to first term in pattern to ensure
@@ -940,6 +995,201 @@ mod tests {
         assert_eq!(res[0].url, "https://www.b.com/");
     }
 
+    #[test]
+    fn schema_org_paths() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        for (url, schema) in [
+            (
+                "https://www.a.com/",
+                r#"{
+                    "@context": "https://schema.org",
+                    "@type": "Product",
+                    "name": "Running shoe",
+                    "offers": {
+                        "@type": "Offer",
+                        "price": "55.00",
+                        "priceCurrency": "EUR"
+                    }
+                }"#,
+            ),
+            (
+                "https://www.b.com/",
+                r#"{
+                    "@context": "https://schema.org",
+                    "@type": "Product",
+                    "name": "Hiking boot"
+                }"#,
+            ),
+            (
+                "https://www.c.com/",
+                r#"{
+                    "@context": "https://schema.org",
+                    "@type": "Recipe",
+                    "name": "Pancakes"
+                }"#,
+            ),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                    <html>
+                        <head>
+                            <title>Website</title>
+                            <script type="application/ld+json">{schema}</script>
+                        </head>
+                        <body>
+                            {CONTENT} {}
+                        </body>
+                    </html>
+                "#,
+                            crate::rand_words(100)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+        let searcher = LocalSearcher::from(index);
+
+        let search = |location: &str, schema: &str| {
+            let mut urls: Vec<_> = searcher
+                .search(&SearchQuery {
+                    query: "website".to_string(),
+                    optic: Some(
+                        Optic::parse(&format!(
+                            r#"
+                        DiscardNonMatching;
+                        Rule {{
+                            Matches {{
+                                {location}("{schema}")
+                            }}
+                        }}
+                    "#
+                        ))
+                        .unwrap(),
+                    ),
+                    ..Default::default()
+                })
+                .unwrap()
+                .webpages
+                .into_iter()
+                .map(|webpage| webpage.url)
+                .collect();
+
+            urls.sort();
+            urls
+        };
+
+        assert_eq!(
+            search("Schema", "Product"),
+            vec!["https://www.a.com/", "https://www.b.com/"]
+        );
+        assert_eq!(search("Schema", "Recipe"), vec!["https://www.c.com/"]);
+        // `Schema` still matches nested itemtypes anywhere in the flattened json
+        assert_eq!(search("Schema", "Offer"), vec!["https://www.a.com/"]);
+
+        assert_eq!(
+            search("SchemaPath", "Product"),
+            vec!["https://www.a.com/", "https://www.b.com/"]
+        );
+        assert_eq!(
+            search("SchemaPath", "Product.offers.price"),
+            vec!["https://www.a.com/"]
+        );
+        assert_eq!(
+            search("SchemaPath", "Offer.priceCurrency"),
+            vec!["https://www.a.com/"]
+        );
+        assert!(search("SchemaPath", "Recipe.offers").is_empty());
+    }
+
+    #[test]
+    fn prefer_language() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(&Webpage {
+                html: Html::parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Website A</title>
+                        </head>
+                        <body>
+                            <p>{CONTENT}</p>
+                        </body>
+                    </html>
+                "#
+                    ),
+                    "https://www.a.com/",
+                )
+                .unwrap(),
+                host_centrality: 0.5,
+                fetch_time_ms: 500,
+                ..Default::default()
+            })
+            .expect("failed to insert webpage");
+        index
+            .insert(&Webpage {
+                html: Html::parse(
+                    r#"
+                    <html>
+                        <head>
+                            <title>Website B</title>
+                        </head>
+                        <body>
+                            <p>Dies ist die beste Website, die es jemals gegeben hat. Wir schreiben hier
+                            viele Wörter auf Deutsch, damit die Sprache der Seite sicher erkannt werden kann.
+                            Die Website enthält Informationen über das Wetter, die Stadt und die Menschen,
+                            die hier schon seit vielen Jahren leben und arbeiten.</p>
+                        </body>
+                    </html>
+                "#,
+                    "https://www.b.com/",
+                )
+                .unwrap(),
+                host_centrality: 0.3,
+                fetch_time_ms: 500,
+                ..Default::default()
+            })
+            .expect("failed to insert webpage");
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let search = |optic: Option<Optic>| {
+            searcher
+                .search(&SearchQuery {
+                    query: "website".to_string(),
+                    signal_coefficients: enum_map! {
+                        crate::ranking::SignalEnum::from(crate::ranking::signals::HostCentrality) => 1_000_000.0
+                    }
+                    .into(),
+                    optic,
+                    ..Default::default()
+                })
+                .unwrap()
+                .webpages
+        };
+
+        let res = search(None);
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].url, "https://www.a.com/");
+
+        let res = search(Some(Optic::parse(r#"Prefer(Language("de"));"#).unwrap()));
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].url, "https://www.b.com/");
+    }
+
     #[test]
     fn pattern_same_phrase() {
         let (mut index, _dir) = Index::temporary().expect("Unable to open index");
@@ -1987,4 +2237,90 @@ mod tests {
             .webpages;
         assert_eq!(res.len(), 0);
     }
+
+    #[test]
+    fn url_regex() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        for url in [
+            "https://www.a.com/posts/123",
+            "https://www.a.com/posts/about",
+            "https://www.b.com/posts/456",
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                    <html>
+                        <head>
+                            <title>Website</title>
+                        </head>
+                        <body>
+                            {CONTENT} {}
+                        </body>
+                    </html>
+                "#,
+                            crate::rand_words(100)
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let res = searcher
+            .search(&SearchQuery {
+                query: "website".to_string(),
+                optic: Some(
+                    Optic::parse(
+                        r#"
+                    DiscardNonMatching;
+                    Rule {
+                        Matches {
+                            UrlRegex("^https://www\.a\.com/posts/\d+$")
+                        }
+                    };
+                "#,
+                    )
+                    .unwrap(),
+                ),
+                ..Default::default()
+            })
+            .unwrap()
+            .webpages;
+
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].url, "https://www.a.com/posts/123");
+
+        let res = searcher
+            .search(&SearchQuery {
+                query: "website".to_string(),
+                optic: Some(
+                    Optic::parse(
+                        r#"
+                    Rule {
+                        Matches {
+                            UrlRegex("/posts/\d+$")
+                        },
+                        Action(Discard)
+                    };
+                "#,
+                    )
+                    .unwrap(),
+                ),
+                ..Default::default()
+            })
+            .unwrap()
+            .webpages;
+
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].url, "https://www.a.com/posts/about");
+    }
 }
@@ -15,8 +15,19 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 pub const MAX_TERMS_PER_QUERY: usize = 32;
+const WILDCARD: char = '*';
+/// Larger slops are clamped, as the cost of a sloppy phrase query grows with the slop.
+const MAX_PHRASE_SLOP: u32 = 16;
 
-use crate::{bangs::BANG_PREFIXES, webpage::url_ext::UrlExt};
+use std::ops::Bound;
+
+use crate::{
+    bangs::BANG_PREFIXES,
+    schema::{
+        numerical_field::NumericalField, text_field::TextField, NumericalFieldEnum, TextFieldEnum,
+    },
+    webpage::url_ext::UrlExt,
+};
 
 mod term;
 
@@ -63,10 +74,61 @@ fn phrase_helper(
     let (input, _) = nom::character::complete::char(start_quote)(input)?;
     let (input, output) = nom::bytes::complete::take_until(end_quote.to_string().as_str())(input)?;
     let (input, _) = nom::character::complete::char(end_quote)(input)?;
-    Ok((
-        input,
-        SimpleOrPhrase::Phrase(output.split_whitespace().map(|s| s.to_string()).collect()),
-    ))
+
+    let mut words: Vec<String> = output.split_whitespace().map(|s| s.to_string()).collect();
+
+    // only a wildcard at the end of the last word turns it into a prefix (`"new york t*"`).
+    // A standalone wildcard (`"new york *"` or `"*"`) is not a prefix of any word.
+    let prefix = words
+        .last()
+        .and_then(|word| word.strip_suffix(WILDCARD))
+        .filter(|prefix| !prefix.is_empty())
+        .map(|prefix| prefix.to_string());
+
+    let is_prefix = prefix.is_some();
+    if let Some(prefix) = prefix {
+        words.pop();
+        words.push(prefix);
+    }
+
+    // wildcards are only supported in the trailing position
+    if words.iter().any(|word| word.contains(WILDCARD)) {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            output,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+
+    if is_prefix {
+        Ok((input, SimpleOrPhrase::PhrasePrefix(words)))
+    } else {
+        let (input, slop) = phrase_slop(input);
+        Ok((input, SimpleOrPhrase::Phrase(words, slop)))
+    }
+}
+
+/// Parses an optional `~N` suffix after a phrase (e.g. `"quick fox"~1`),
+/// which allows up to `N` words between the words of the phrase.
+/// `N` is clamped to [`MAX_PHRASE_SLOP`].
+fn phrase_slop(input: &str) -> (&str, Option<u32>) {
+    let Some(rest) = input.strip_prefix('~') else {
+        return (input, None);
+    };
+
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+
+    if end == 0 {
+        return (input, None);
+    }
+
+    // numbers too large for a u32 are also above the maximum
+    let slop = rest[..end]
+        .parse::<u32>()
+        .map_or(MAX_PHRASE_SLOP, |slop| slop.min(MAX_PHRASE_SLOP));
+
+    (&rest[end..], Some(slop))
 }
 
 fn phrase(input: &str) -> nom::IResult<&str, SimpleOrPhrase> {
@@ -111,10 +173,25 @@ fn bang(input: &str) -> nom::IResult<&str, Term> {
     )))
 }
 
-fn site_field(input: &str) -> nom::IResult<&str, Term> {
-    // parse 'site:' and then a simple term
-    let (input, _) = nom::bytes::complete::tag("site:")(input)?;
-    let (input, output) = simple_str(input)?;
+fn site_field(orig_input: &str) -> nom::IResult<&str, Term> {
+    // parse 'site:' directly followed by a host. 'site:' on its own
+    // or followed by a space is parsed as a simple term instead.
+    let (input, _) = nom::bytes::complete::tag("site:")(orig_input)?;
+    let (input, output) = until_space_or_end(input)?;
+
+    // 'site:*.example.com' only matches subdomains of example.com,
+    // which is the same as 'site:.example.com'
+    let output = output.strip_prefix(WILDCARD).unwrap_or(output);
+
+    // 'site:example.com.' only matches example.com and not its subdomains,
+    // so a host can't both start and end with a dot.
+    let host = output.trim_matches('.');
+    if host.is_empty() || (output.starts_with('.') && output.ends_with('.')) {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            orig_input,
+            nom::error::ErrorKind::Fail,
+        )));
+    }
 
     Ok((input, Term::Site(output.to_string())))
 }
@@ -173,6 +250,134 @@ fn url_field(input: &str) -> nom::IResult<&str, Term> {
     Ok((input, Term::Url(output)))
 }
 
+/// Dates (e.g. `2024-01-31`) are unix timestamps in UTC and cover the whole day,
+/// so a lower bound starts at midnight and an upper bound excludes the next midnight.
+fn range_bound(input: &str, is_upper: bool) -> Option<Bound<f64>> {
+    if input.is_empty() {
+        return Some(Bound::Unbounded);
+    }
+
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return if is_upper {
+            date.succ_opt()?
+                .and_hms_opt(0, 0, 0)
+                .map(|date| Bound::Excluded(date.and_utc().timestamp() as f64))
+        } else {
+            date.and_hms_opt(0, 0, 0)
+                .map(|date| Bound::Included(date.and_utc().timestamp() as f64))
+        };
+    }
+
+    input
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .map(Bound::Included)
+}
+
+fn numeric_range(orig_input: &str) -> nom::IResult<&str, Term> {
+    let fail = || {
+        nom::Err::Error(nom::error::Error::new(
+            orig_input,
+            nom::error::ErrorKind::Fail,
+        ))
+    };
+
+    // parse '<field>:<lower>..<upper>' where the field is a range queryable numerical field
+    let (input, name) =
+        nom::bytes::complete::take_while1(|c: char| c != ':' && c != ' ')(orig_input)?;
+    let (input, _) = nom::character::complete::char(':')(input)?;
+
+    let field = NumericalFieldEnum::all()
+        .find(|field| field.is_range_queryable() && field.name() == name)
+        .ok_or_else(fail)?;
+
+    let (input, range) = until_space_or_end(input)?;
+    let (lower, upper) = range.split_once("..").ok_or_else(fail)?;
+
+    let lower = range_bound(lower, false).ok_or_else(fail)?;
+    let upper = range_bound(upper, true).ok_or_else(fail)?;
+
+    Ok((
+        input,
+        Term::NumericRange(NumericRange {
+            field,
+            lower,
+            upper,
+        }),
+    ))
+}
+
+fn parse_boost(boost: &str) -> Option<f32> {
+    boost
+        .parse::<f32>()
+        .ok()
+        .filter(|boost| boost.is_finite() && *boost > 0.0)
+}
+
+/// Splits a `^N` suffix from a simple term (e.g. `rust^3`).
+/// The term is returned as is if the suffix is not a valid boost.
+fn split_boost(term: &str) -> (&str, Option<f32>) {
+    match term.rsplit_once('^') {
+        Some((text, boost)) if !text.is_empty() => match parse_boost(boost) {
+            Some(boost) => (text, Some(boost)),
+            None => (term, None),
+        },
+        _ => (term, None),
+    }
+}
+
+/// Parses an optional `^N` suffix after a phrase (e.g. `"quick fox"^2`).
+fn boost_suffix(input: &str) -> (&str, Option<f32>) {
+    let Some(rest) = input.strip_prefix('^') else {
+        return (input, None);
+    };
+
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rest.len());
+
+    match parse_boost(&rest[..end]) {
+        Some(boost) => (&rest[end..], Some(boost)),
+        None => (input, None),
+    }
+}
+
+fn field_term(orig_input: &str) -> nom::IResult<&str, Term> {
+    // parse '<field>:<term>^<boost>' where the field is a searchable text field
+    let (input, name) =
+        nom::bytes::complete::take_while1(|c: char| c != ':' && c != ' ')(orig_input)?;
+    let (input, _) = nom::character::complete::char(':')(input)?;
+
+    let field = TextFieldEnum::all()
+        .find(|field| field.is_searchable() && field.name() == name)
+        .ok_or_else(|| {
+            nom::Err::Error(nom::error::Error::new(
+                orig_input,
+                nom::error::ErrorKind::Fail,
+            ))
+        })?;
+
+    let (input, term, boost) = match phrase(input) {
+        Ok((input, phrase)) => {
+            let (input, boost) = boost_suffix(input);
+            (input, phrase, boost)
+        }
+        Err(nom::Err::Error(_)) => {
+            let (input, output) = simple_str(input)?;
+            let (text, boost) = split_boost(output);
+            (
+                input,
+                SimpleOrPhrase::Simple(text.to_string().into()),
+                boost,
+            )
+        }
+        Err(err) => return Err(err),
+    };
+
+    Ok((input, Term::Field(FieldTerm { field, term, boost })))
+}
+
 fn field_selector(input: &str) -> nom::IResult<&str, Term> {
     nom::branch::alt((
         site_field,
@@ -181,6 +386,8 @@ fn field_selector(input: &str) -> nom::IResult<&str, Term> {
         body_field,
         url_field,
         exact_url,
+        numeric_range,
+        field_term,
     ))(input)
 }
 
@@ -194,6 +401,15 @@ fn not(input: &str) -> nom::IResult<&str, Term> {
     }
 
     let (input, _) = nom::bytes::complete::tag("-")(input)?;
+
+    // a dash on its own (e.g. `this - that`) is just text
+    if input.starts_with(char::is_whitespace) {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Fail,
+        )));
+    }
+
     let (input, output) = term(input)?;
     Ok((input, Term::Not(Box::new(output))))
 }
@@ -235,7 +451,13 @@ pub fn parse(query: &str) -> anyhow::Result<Vec<Term>> {
 
     nom::multi::many1(term)(query)
         .map(|(_, res)| res)
-        .map_err(|e| anyhow::anyhow!("Failed to parse query: {:?}", e))
+        .map_err(|e| match e {
+            nom::Err::Failure(e) if e.code == nom::error::ErrorKind::Verify => anyhow::anyhow!(
+                "Failed to parse query: wildcards are only supported at the end of the last word of a phrase (\"{}\")",
+                e.input
+            ),
+            e => anyhow::anyhow!("Failed to parse query: {:?}", e),
+        })
 }
 
 pub fn truncate(terms: Vec<Term>) -> Vec<Term> {
@@ -248,9 +470,13 @@ pub fn truncate(terms: Vec<Term>) -> Vec<Term> {
 
 #[cfg(test)]
 mod tests {
+    use std::ops::Bound;
+
     use proptest::prelude::*;
 
-    use super::{SimpleOrPhrase, Term};
+    use crate::schema::{numerical_field, text_field};
+
+    use super::{FieldTerm, NumericRange, SimpleOrPhrase, Term};
 
     fn parse(input: &str) -> Vec<Term> {
         super::truncate(super::parse(input).unwrap())
@@ -275,6 +501,26 @@ mod tests {
                 Term::SimpleOrPhrase(SimpleOrPhrase::Simple("-".to_string().into()))
             ]
         );
+
+        assert_eq!(
+            parse("this - that"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("this".to_string().into())),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("-".to_string().into())),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("that".to_string().into()))
+            ]
+        );
+
+        assert_eq!(
+            parse("this -\"that thing\""),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("this".to_string().into())),
+                Term::Not(Box::new(Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(
+                    vec!["that".to_string(), "thing".to_string()],
+                    None
+                ))))
+            ]
+        );
     }
 
     #[test]
@@ -299,6 +545,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn site_and_inurl() {
+        assert_eq!(
+            parse("login site:example.com inurl:login"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("login".to_string().into())),
+                Term::Site("example.com".to_string()),
+                Term::Url(SimpleOrPhrase::Simple("login".to_string().into())),
+            ]
+        );
+
+        assert_eq!(
+            parse("site:*.example.com"),
+            vec![Term::Site(".example.com".to_string())]
+        );
+        assert_eq!(
+            parse("site:.example.com"),
+            vec![Term::Site(".example.com".to_string())]
+        );
+        assert_eq!(
+            parse("site:example.com."),
+            vec![Term::Site("example.com.".to_string())]
+        );
+    }
+
+    #[test]
+    fn site_without_host() {
+        assert_eq!(
+            parse("site:"),
+            vec![Term::SimpleOrPhrase(SimpleOrPhrase::Simple(
+                "site:".to_string().into()
+            ))]
+        );
+
+        assert_eq!(
+            parse("rust site: example.com"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("rust".to_string().into())),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("site:".to_string().into())),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("example.com".to_string().into())),
+            ]
+        );
+
+        for query in ["site:*.", "site:.", "site:.example.com."] {
+            assert_eq!(
+                parse(query),
+                vec![Term::SimpleOrPhrase(SimpleOrPhrase::Simple(
+                    query.to_string().into()
+                ))]
+            );
+        }
+    }
+
     #[test]
     fn title() {
         assert_eq!(
@@ -332,6 +631,131 @@ mod tests {
         );
     }
 
+    #[test]
+    fn numeric_range() {
+        assert_eq!(
+            parse("this fetch_time_ms:1000..2000.5"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("this".to_string().into())),
+                Term::NumericRange(NumericRange {
+                    field: numerical_field::FetchTimeMs.into(),
+                    lower: Bound::Included(1000.0),
+                    upper: Bound::Included(2000.5),
+                })
+            ]
+        );
+
+        assert_eq!(
+            parse("last_updated:1000.."),
+            vec![Term::NumericRange(NumericRange {
+                field: numerical_field::LastUpdated.into(),
+                lower: Bound::Included(1000.0),
+                upper: Bound::Unbounded,
+            })]
+        );
+
+        assert_eq!(
+            parse("inserted_at:..2000"),
+            vec![Term::NumericRange(NumericRange {
+                field: numerical_field::InsertedAt.into(),
+                lower: Bound::Unbounded,
+                upper: Bound::Included(2000.0),
+            })]
+        );
+
+        // dates are unix timestamps in UTC and an upper bound includes the whole day
+        assert_eq!(
+            parse("inserted_at:2024-01-01..2024-01-02"),
+            vec![Term::NumericRange(NumericRange {
+                field: numerical_field::InsertedAt.into(),
+                lower: Bound::Included(1704067200.0),
+                upper: Bound::Excluded(1704240000.0),
+            })]
+        );
+
+        // fields that are not range queryable and malformed ranges are just text
+        assert_eq!(
+            parse("host_centrality:1..2"),
+            vec![Term::SimpleOrPhrase(SimpleOrPhrase::Simple(
+                "host_centrality:1..2".to_string().into()
+            ))]
+        );
+        assert_eq!(
+            parse("fetch_time_ms:a..b"),
+            vec![Term::SimpleOrPhrase(SimpleOrPhrase::Simple(
+                "fetch_time_ms:a..b".to_string().into()
+            ))]
+        );
+        assert_eq!(
+            parse("fetch_time_ms:1000"),
+            vec![Term::SimpleOrPhrase(SimpleOrPhrase::Simple(
+                "fetch_time_ms:1000".to_string().into()
+            ))]
+        );
+    }
+
+    #[test]
+    fn field_boost() {
+        assert_eq!(
+            parse("title:foo^2 bar"),
+            vec![
+                Term::Field(FieldTerm {
+                    field: text_field::Title.into(),
+                    term: SimpleOrPhrase::Simple("foo".to_string().into()),
+                    boost: Some(2.0),
+                }),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("bar".to_string().into())),
+            ]
+        );
+
+        assert_eq!(
+            parse("title:foo"),
+            vec![Term::Field(FieldTerm {
+                field: text_field::Title.into(),
+                term: SimpleOrPhrase::Simple("foo".to_string().into()),
+                boost: None,
+            })]
+        );
+
+        assert_eq!(
+            parse("title:\"quick fox\"^1.5"),
+            vec![Term::Field(FieldTerm {
+                field: text_field::Title.into(),
+                term: SimpleOrPhrase::Phrase(vec!["quick".to_string(), "fox".to_string()], None),
+                boost: Some(1.5),
+            })]
+        );
+
+        // invalid boosts are part of the term
+        assert_eq!(
+            parse("title:foo^bar"),
+            vec![Term::Field(FieldTerm {
+                field: text_field::Title.into(),
+                term: SimpleOrPhrase::Simple("foo^bar".to_string().into()),
+                boost: None,
+            })]
+        );
+        assert_eq!(
+            parse("title:foo^0"),
+            vec![Term::Field(FieldTerm {
+                field: text_field::Title.into(),
+                term: SimpleOrPhrase::Simple("foo^0".to_string().into()),
+                boost: None,
+            })]
+        );
+
+        // unknown fields are just text
+        assert_eq!(
+            parse("notafield:foo^2"),
+            vec![Term::SimpleOrPhrase(SimpleOrPhrase::Simple(
+                "notafield:foo^2".to_string().into()
+            ))]
+        );
+
+        let terms = parse("title:foo^2");
+        assert_eq!(terms[0].to_string(), "title:foo^2");
+    }
+
     #[test]
     fn empty() {
         assert_eq!(parse(""), vec![]);
@@ -343,11 +767,10 @@ mod tests {
         assert_eq!(
             parse("\"this is a\" inurl:test"),
             vec![
-                Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(vec![
-                    "this".to_string(),
-                    "is".to_string(),
-                    "a".to_string()
-                ])),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(
+                    vec!["this".to_string(), "is".to_string(), "a".to_string()],
+                    None
+                )),
                 Term::Url(SimpleOrPhrase::Simple("test".to_string().into()))
             ]
         );
@@ -372,26 +795,28 @@ mod tests {
 
         assert_eq!(
             parse("\"this is a inurl:test\""),
-            vec![Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(vec![
-                "this".to_string(),
-                "is".to_string(),
-                "a".to_string(),
-                "inurl:test".to_string()
-            ]))]
+            vec![Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(
+                vec![
+                    "this".to_string(),
+                    "is".to_string(),
+                    "a".to_string(),
+                    "inurl:test".to_string()
+                ],
+                None
+            ))]
         );
 
         assert_eq!(
             parse("\"\""),
-            vec![Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(vec![]))]
+            vec![Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(vec![], None))]
         );
         assert_eq!(
             parse("“this is a“ inurl:test"),
             vec![
-                Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(vec![
-                    "this".to_string(),
-                    "is".to_string(),
-                    "a".to_string()
-                ])),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(
+                    vec!["this".to_string(), "is".to_string(), "a".to_string()],
+                    None
+                )),
                 Term::Url(SimpleOrPhrase::Simple("test".to_string().into()))
             ]
         );
@@ -406,12 +831,149 @@ mod tests {
 
         assert_eq!(
             parse("inbody:\"this should work\""),
-            vec![Term::Body(SimpleOrPhrase::Phrase(vec![
-                "this".to_string(),
-                "should".to_string(),
-                "work".to_string()
+            vec![Term::Body(SimpleOrPhrase::Phrase(
+                vec!["this".to_string(), "should".to_string(), "work".to_string()],
+                None
+            ))]
+        );
+    }
+
+    #[test]
+    fn phrase_slop() {
+        assert_eq!(
+            parse("\"quick fox\"~1 test"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(
+                    vec!["quick".to_string(), "fox".to_string()],
+                    Some(1)
+                )),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("test".to_string().into()))
+            ]
+        );
+
+        assert_eq!(
+            parse("intitle:\"quick fox\"~2"),
+            vec![Term::Title(SimpleOrPhrase::Phrase(
+                vec!["quick".to_string(), "fox".to_string()],
+                Some(2)
+            ))]
+        );
+
+        assert_eq!(
+            parse("\"quick fox\"~"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(
+                    vec!["quick".to_string(), "fox".to_string()],
+                    None
+                )),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("~".to_string().into()))
+            ]
+        );
+
+        let terms = parse("\"quick fox\"~1");
+        assert_eq!(terms[0].to_string(), "\"quick fox\"~1");
+    }
+
+    #[test]
+    fn phrase_slop_is_clamped() {
+        for query in [
+            format!("\"quick fox\"~{}", super::MAX_PHRASE_SLOP + 1),
+            "\"quick fox\"~4000000000".to_string(),
+            "\"quick fox\"~99999999999999999999".to_string(),
+        ] {
+            assert_eq!(
+                parse(&query),
+                vec![Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(
+                    vec!["quick".to_string(), "fox".to_string()],
+                    Some(super::MAX_PHRASE_SLOP)
+                ))]
+            );
+        }
+
+        assert_eq!(
+            parse(&format!("\"quick fox\"~{}", super::MAX_PHRASE_SLOP)),
+            vec![Term::SimpleOrPhrase(SimpleOrPhrase::Phrase(
+                vec!["quick".to_string(), "fox".to_string()],
+                Some(super::MAX_PHRASE_SLOP)
+            ))]
+        );
+    }
+
+    #[test]
+    fn phrase_prefix() {
+        assert_eq!(
+            parse("\"new york t*\""),
+            vec![Term::SimpleOrPhrase(SimpleOrPhrase::PhrasePrefix(vec![
+                "new".to_string(),
+                "york".to_string(),
+                "t".to_string(),
+            ]))]
+        );
+
+        assert_eq!(
+            parse("\"new york t*\" test"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::PhrasePrefix(vec![
+                    "new".to_string(),
+                    "york".to_string(),
+                    "t".to_string(),
+                ])),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("test".to_string().into()))
+            ]
+        );
+
+        assert_eq!(
+            parse("intitle:\"new york t*\""),
+            vec![Term::Title(SimpleOrPhrase::PhrasePrefix(vec![
+                "new".to_string(),
+                "york".to_string(),
+                "t".to_string(),
             ]))]
         );
+
+        assert_eq!(
+            parse("new york*"),
+            vec![
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("new".to_string().into())),
+                Term::SimpleOrPhrase(SimpleOrPhrase::Simple("york*".to_string().into()))
+            ]
+        );
+    }
+
+    #[test]
+    fn phrase_prefix_roundtrip() {
+        let terms = parse("\"new york t*\"");
+        let query = terms
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        assert_eq!(query, "\"new york t*\"");
+        assert_eq!(parse(&query), terms);
+    }
+
+    #[test]
+    fn mid_phrase_wildcard() {
+        let err = super::parse("\"new * times\"").unwrap_err();
+        assert!(err.to_string().contains("wildcards are only supported"));
+
+        assert!(super::parse("\"new y*rk times\"").is_err());
+        assert!(super::parse("\"new york * *\"").is_err());
+        assert!(super::parse("intitle:\"new * times\"").is_err());
+    }
+
+    #[test]
+    fn standalone_wildcard_in_phrase() {
+        let err = super::parse("\"new york *\"").unwrap_err();
+        assert!(err.to_string().contains("wildcards are only supported"));
+
+        assert!(super::parse("intitle:\"new york *\"").is_err());
+        assert!(super::parse("\"new york t**\"").is_err());
+
+        // a phrase of only a wildcard would otherwise be an empty phrase
+        assert!(super::parse("\"*\"").is_err());
+        assert!(super::parse("\" * \" test").is_err());
     }
 
     #[test]
@@ -14,6 +14,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>
 
+use std::hash::{Hash, Hasher};
+use std::ops::Bound;
+
+use crate::query::FieldTermText;
+use crate::schema::{
+    numerical_field::NumericalField, text_field::TextField, NumericalFieldEnum, TextFieldEnum,
+};
+
 const MAX_PHRASE_LENGTH: usize = 32;
 const MAX_TERM_LENGTH_CHARS: usize = 1024;
 
@@ -31,7 +39,11 @@ pub struct CompoundAwareTerm {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SimpleOrPhrase {
     Simple(SimpleTerm),
-    Phrase(Vec<String>),
+    /// A phrase with an optional slop (e.g. `"quick fox"~1`), which is the number
+    /// of other words allowed between the words of the phrase.
+    Phrase(Vec<String>, Option<u32>),
+    /// A phrase where the last word is only a prefix (e.g. `"new york t*"`).
+    PhrasePrefix(Vec<String>),
 }
 
 impl SimpleOrPhrase {
@@ -40,22 +52,31 @@ impl SimpleOrPhrase {
             SimpleOrPhrase::Simple(simple) => SimpleOrPhrase::Simple(SimpleTerm(
                 simple.0.chars().take(MAX_TERM_LENGTH_CHARS).collect(),
             )),
-            SimpleOrPhrase::Phrase(phrase) => SimpleOrPhrase::Phrase(
-                phrase
-                    .into_iter()
-                    .take(MAX_PHRASE_LENGTH)
-                    .map(|s| s.chars().take(MAX_TERM_LENGTH_CHARS).collect())
-                    .collect(),
-            ),
+            SimpleOrPhrase::Phrase(phrase, slop) => {
+                SimpleOrPhrase::Phrase(truncate_phrase(phrase), slop)
+            }
+            SimpleOrPhrase::PhrasePrefix(phrase) => {
+                SimpleOrPhrase::PhrasePrefix(truncate_phrase(phrase))
+            }
         }
     }
 }
 
+fn truncate_phrase(phrase: Vec<String>) -> Vec<String> {
+    phrase
+        .into_iter()
+        .take(MAX_PHRASE_LENGTH)
+        .map(|s| s.chars().take(MAX_TERM_LENGTH_CHARS).collect())
+        .collect()
+}
+
 impl SimpleOrPhrase {
     pub fn as_string(&self) -> String {
         match self {
             SimpleOrPhrase::Simple(simple) => simple.as_str().to_string(),
-            SimpleOrPhrase::Phrase(phrase) => phrase.join(" "),
+            SimpleOrPhrase::Phrase(phrase, _) | SimpleOrPhrase::PhrasePrefix(phrase) => {
+                phrase.join(" ")
+            }
         }
     }
 }
@@ -89,21 +110,106 @@ impl std::fmt::Display for SimpleOrPhrase {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             SimpleOrPhrase::Simple(s) => write!(f, "{}", s.as_str()),
-            SimpleOrPhrase::Phrase(p) => write!(f, "\"{}\"", p.join(" ")),
+            SimpleOrPhrase::Phrase(p, None) => write!(f, "\"{}\"", p.join(" ")),
+            SimpleOrPhrase::Phrase(p, Some(slop)) => write!(f, "\"{}\"~{}", p.join(" "), slop),
+            SimpleOrPhrase::PhrasePrefix(p) => write!(f, "\"{}*\"", p.join(" ")),
+        }
+    }
+}
+
+/// A range over a numerical field (e.g. `fetch_time_ms:1700000000000..`).
+/// The parser only produces inclusive or unbounded ends, and never NaN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumericRange {
+    pub field: NumericalFieldEnum,
+    pub lower: Bound<f64>,
+    pub upper: Bound<f64>,
+}
+
+impl Eq for NumericRange {}
+
+impl Hash for NumericRange {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.field.hash(state);
+        self.lower.map(f64::to_bits).hash(state);
+        self.upper.map(f64::to_bits).hash(state);
+    }
+}
+
+impl std::fmt::Display for NumericRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:", self.field.name())?;
+
+        if let Bound::Included(lower) | Bound::Excluded(lower) = self.lower {
+            write!(f, "{}", lower)?;
+        }
+
+        write!(f, "..")?;
+
+        if let Bound::Included(upper) | Bound::Excluded(upper) = self.upper {
+            write!(f, "{}", upper)?;
         }
+
+        Ok(())
+    }
+}
+
+/// A term searched for in a single text field, optionally with a boost
+/// that its score is multiplied by (e.g. `title:rust^3`).
+/// The parser only produces finite and positive boosts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldTerm {
+    pub field: TextFieldEnum,
+    pub term: SimpleOrPhrase,
+    pub boost: Option<f32>,
+}
+
+impl Eq for FieldTerm {}
+
+impl Hash for FieldTerm {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.field.hash(state);
+        self.term.hash(state);
+        self.boost.map(f32::to_bits).hash(state);
+    }
+}
+
+impl std::fmt::Display for FieldTerm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.field.name(), self.term)?;
+
+        if let Some(boost) = self.boost {
+            write!(f, "^{}", boost)?;
+        }
+
+        Ok(())
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Term {
     SimpleOrPhrase(SimpleOrPhrase),
+    /// `site:example.com` matches pages on example.com and all of its subdomains
+    /// (e.g. blog.example.com), but not hosts that merely contain the name
+    /// (e.g. notexample.com or example.com.org). A leading dot (`site:.example.com`)
+    /// restricts the match to subdomains.
     Site(String),
     LinkTo(String),
     Title(SimpleOrPhrase),
     Body(SimpleOrPhrase),
+    /// `inurl:login` matches pages where the term appears anywhere in the url.
     Url(SimpleOrPhrase),
     ExactUrl(String),
-    PossibleBang { prefix: char, bang: String },
+    /// `fetch_time_ms:1000..2000` matches pages where the field is between the bounds (inclusive).
+    /// Either bound can be left out for an open range.
+    NumericRange(NumericRange),
+    /// `<field>:<term>` with an optional `^<boost>`, where the field is the name
+    /// of a searchable text field (e.g. `title:rust^3`).
+    Field(FieldTerm),
+    PossibleBang {
+        prefix: char,
+        bang: String,
+    },
     Not(Box<Term>),
 }
 
@@ -118,6 +224,8 @@ impl std::fmt::Display for Term {
             Term::Body(body) => write!(f, "inbody:{}", body),
             Term::Url(url) => write!(f, "inurl:{}", url),
             Term::ExactUrl(url) => write!(f, "exacturl:{}", url),
+            Term::NumericRange(range) => write!(f, "{}", range),
+            Term::Field(field_term) => write!(f, "{}", field_term),
             Term::PossibleBang { prefix, bang } => write!(f, "{}{}", prefix, bang),
         }
     }
@@ -131,6 +239,17 @@ impl Term {
         }
     }
 
+    pub fn as_field_text(&self) -> Option<FieldTermText> {
+        match self {
+            Term::Field(field_term) => Some(FieldTermText {
+                field: field_term.field,
+                text: field_term.term.as_string(),
+                boost: field_term.boost.unwrap_or(1.0),
+            }),
+            _ => None,
+        }
+    }
+
     pub fn truncate(self) -> Term {
         match self {
             Term::SimpleOrPhrase(s) => Term::SimpleOrPhrase(s.truncate()),
@@ -140,6 +259,11 @@ impl Term {
             Term::Body(s) => Term::Body(s.truncate()),
             Term::Url(s) => Term::Url(s.truncate()),
             Term::ExactUrl(s) => Term::ExactUrl(s),
+            Term::NumericRange(r) => Term::NumericRange(r),
+            Term::Field(f) => Term::Field(FieldTerm {
+                term: f.term.truncate(),
+                ..f
+            }),
             Term::Not(n) => Term::Not(Box::new(n.truncate())),
             Term::PossibleBang { prefix, bang } => Term::PossibleBang {
                 prefix,
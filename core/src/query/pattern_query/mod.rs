@@ -100,7 +100,7 @@ impl PatternQuery {
                     let mut tokenizer = field
                         .as_text()
                         .unwrap()
-                        .tokenizer(Some(&whatlang::Lang::Eng));
+                        .field_tokenizer(Some(&whatlang::Lang::Eng));
                     let mut stream = tokenizer.token_stream(text);
                     let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
 
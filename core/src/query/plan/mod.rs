@@ -14,19 +14,25 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/
 
+use std::hash::{Hash, Hasher};
+use std::ops::Bound;
+
 use itertools::Itertools;
 use tantivy::tokenizer::Tokenizer as _;
 mod node;
 
 pub use node::Node;
 
-use crate::schema::{self, text_field::TextField, TextFieldEnum};
-
-use super::{
-    parser::{SimpleOrPhrase, SimpleTerm},
-    MAX_TERMS_FOR_NGRAM_LOOKUPS,
+use crate::{
+    config::CompoundTermsConfig,
+    schema::{
+        self, numerical_field::NumericalField, text_field::TextField, DataType, NumericalFieldEnum,
+        TextFieldEnum,
+    },
 };
 
+use super::parser::{SimpleOrPhrase, SimpleTerm};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Term {
     text: SimpleOrPhrase,
@@ -68,18 +74,75 @@ impl From<Occur> for tantivy::query::Occur {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Query {
     Term(Term),
-    Boolean { clauses: Vec<(Occur, Query)> },
+    Boolean {
+        clauses: Vec<(Occur, Query)>,
+    },
+    /// Matches documents where the numerical field is within the bounds.
+    NumericRange {
+        field: NumericalFieldEnum,
+        lower: Bound<f64>,
+        upper: Bound<f64>,
+    },
+    /// Multiplies the score of the inner query.
+    Boost {
+        query: Box<Query>,
+        boost: f32,
+    },
+}
+
+// the bounds and boosts are never NaN, so the queries can be compared and hashed by their bits
+impl Eq for Query {}
+
+impl Hash for Query {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            Query::Term(term) => term.hash(state),
+            Query::Boolean { clauses } => clauses.hash(state),
+            Query::NumericRange {
+                field,
+                lower,
+                upper,
+            } => {
+                field.hash(state);
+                lower.map(f64::to_bits).hash(state);
+                upper.map(f64::to_bits).hash(state);
+            }
+            Query::Boost { query, boost } => {
+                query.hash(state);
+                boost.to_bits().hash(state);
+            }
+        }
+    }
 }
 
 impl Query {
     #[cfg(test)]
     pub fn len(&self) -> usize {
         match self {
-            Query::Term(_) => 1,
+            Query::Term(_) | Query::NumericRange { .. } => 1,
             Query::Boolean { clauses } => clauses.iter().map(|(_, q)| q.len()).sum(),
+            Query::Boost { query, .. } => query.len(),
+        }
+    }
+
+    /// A rough estimate of how expensive the query is to execute, without looking at the index.
+    /// Each term counts once per word, so phrases cost more than single terms.
+    pub fn estimated_cost(&self) -> usize {
+        match self {
+            Query::Term(Term { text, .. }) => match text {
+                SimpleOrPhrase::Simple(_) => 1,
+                SimpleOrPhrase::Phrase(words, _) | SimpleOrPhrase::PhrasePrefix(words) => {
+                    words.len().max(1)
+                }
+            },
+            Query::NumericRange { .. } => 1,
+            Query::Boolean { clauses } => clauses.iter().map(|(_, q)| q.estimated_cost()).sum(),
+            Query::Boost { query, .. } => query.estimated_cost(),
         }
     }
 
@@ -117,7 +180,11 @@ impl Query {
                     clauses: new_clauses,
                 }
             }
-            Query::Term(term) => Query::Term(term),
+            Query::Boost { query, boost } => Query::Boost {
+                query: Box::new(query.compact()),
+                boost,
+            },
+            query @ (Query::Term(_) | Query::NumericRange { .. }) => query,
         }
     }
 
@@ -130,7 +197,11 @@ impl Query {
                     .unique()
                     .collect(),
             },
-            Query::Term(term) => Query::Term(term),
+            Query::Boost { query, boost } => Query::Boost {
+                query: Box::new(query.deduplicate()),
+                boost,
+            },
+            query @ (Query::Term(_) | Query::NumericRange { .. }) => query,
         }
     }
 
@@ -165,7 +236,7 @@ impl Query {
                         )))
                     }
                 }
-                SimpleOrPhrase::Phrase(p) => {
+                SimpleOrPhrase::Phrase(p, slop) => {
                     let phrase = p.join(" ");
                     let mut processed_terms = process_tantivy_term(&phrase, *field, lang, schema);
 
@@ -180,11 +251,35 @@ impl Query {
                             processed_terms.pop().unwrap(),
                             options,
                         )) as Box<dyn tantivy::query::Query>)
+                    } else if let Some(slop) = slop {
+                        Some(
+                            Box::new(tantivy::query::PhraseQuery::new_with_offset_and_slop(
+                                processed_terms.into_iter().enumerate().collect(),
+                                *slop,
+                            )) as Box<dyn tantivy::query::Query>,
+                        )
                     } else {
                         Some(Box::new(tantivy::query::PhraseQuery::new(processed_terms))
                             as Box<dyn tantivy::query::Query>)
                     }
                 }
+                SimpleOrPhrase::PhrasePrefix(p) => {
+                    let phrase = p.join(" ");
+                    let processed_terms = process_tantivy_term(&phrase, *field, lang, schema);
+
+                    if processed_terms.is_empty() {
+                        return None;
+                    }
+
+                    if processed_terms.len() > 1 && !field.record_option().has_positions() {
+                        return None;
+                    }
+
+                    Some(
+                        Box::new(tantivy::query::PhrasePrefixQuery::new(processed_terms))
+                            as Box<dyn tantivy::query::Query>,
+                    )
+                }
             },
             Query::Boolean { clauses } => {
                 let mut t_clauses = Vec::new();
@@ -196,10 +291,54 @@ impl Query {
 
                 Some(Box::new(tantivy::query::BooleanQuery::new(t_clauses)))
             }
+            Query::Boost { query, boost } => query.as_tantivy(lang, schema).map(|query| {
+                Box::new(tantivy::query::BoostQuery::new(query, *boost))
+                    as Box<dyn tantivy::query::Query>
+            }),
+            Query::NumericRange {
+                field,
+                lower,
+                upper,
+            } => {
+                let name = field.name().to_string();
+
+                match field.data_type() {
+                    DataType::U64 => Some(Box::new(tantivy::query::RangeQuery::new_u64_bounds(
+                        name,
+                        lower_u64_bound(*lower),
+                        upper_u64_bound(*upper),
+                    ))),
+                    DataType::F64 => Some(Box::new(tantivy::query::RangeQuery::new_f64_bounds(
+                        name, *lower, *upper,
+                    ))),
+                    DataType::Bool | DataType::Bytes => None,
+                }
+            }
         }
     }
 }
 
+/// The smallest inclusive `u64` bound that covers the same values as `bound`.
+fn lower_u64_bound(bound: Bound<f64>) -> Bound<u64> {
+    match bound {
+        Bound::Included(value) => Bound::Included(value.ceil().max(0.0) as u64),
+        Bound::Excluded(value) => Bound::Included((value.floor() + 1.0).max(0.0) as u64),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// The largest `u64` bound that covers the same values as `bound`.
+/// Negative upper bounds exclude every value.
+fn upper_u64_bound(bound: Bound<f64>) -> Bound<u64> {
+    match bound {
+        Bound::Included(value) if value < 0.0 => Bound::Excluded(0),
+        Bound::Included(value) => Bound::Included(value.floor() as u64),
+        Bound::Excluded(value) if value <= 0.0 => Bound::Excluded(0),
+        Bound::Excluded(value) => Bound::Excluded(value.ceil() as u64),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
 fn process_tantivy_term<T: TextField>(
     term: &str,
     field: T,
@@ -232,18 +371,18 @@ fn sliding_window(window_size: usize, i: usize) -> impl Iterator<Item = (usize,
         .filter(|(start, end)| end != start)
 }
 
-pub fn initial(terms: Vec<super::Term>) -> Option<Node> {
+pub fn initial(terms: Vec<super::Term>, compound_terms: &CompoundTermsConfig) -> Option<Node> {
     let mut nodes = Vec::new();
     let terms_for_adjacent = terms.clone();
 
-    let augment_with_adjacent = terms.len() <= MAX_TERMS_FOR_NGRAM_LOOKUPS;
+    let augment_with_adjacent = terms.len() <= compound_terms.max_terms;
 
     for (i, term) in terms.into_iter().enumerate() {
         let mut adjacent = Vec::new();
 
         if augment_with_adjacent {
             if let super::Term::SimpleOrPhrase(SimpleOrPhrase::Simple(_)) = &term {
-                for window_size in 2..=3 {
+                for window_size in compound_terms.min_window..=compound_terms.max_window {
                     for (start, end) in sliding_window(window_size, i) {
                         let mut compounds = Vec::new();
 
@@ -267,21 +406,27 @@ pub fn initial(terms: Vec<super::Term>) -> Option<Node> {
         let node = Node::from_term(term);
 
         if !adjacent.is_empty() {
+            // overlapping windows and repeated terms can give the same compound
+            // several times, so only the first occurrence of each compound is kept.
             match adjacent
                 .into_iter()
-                .flat_map(|compound| {
+                .map(|compound| -> String {
+                    compound
+                        .terms
+                        .iter()
+                        .map(|s| s.as_str().to_string())
+                        .collect()
+                })
+                .unique()
+                .flat_map(|compound_text| {
                     TextFieldEnum::all()
                         .filter(|f| f.is_searchable())
                         .filter(|f| f.is_compound_searchable())
                         .map(move |field| {
-                            let compound_text: String = compound
-                                .terms
-                                .iter()
-                                .map(|s| s.as_str().to_string())
-                                .collect();
-
                             Node::Term(Term {
-                                text: SimpleOrPhrase::Simple(SimpleTerm::from(compound_text)),
+                                text: SimpleOrPhrase::Simple(SimpleTerm::from(
+                                    compound_text.clone(),
+                                )),
                                 field,
                             })
                         })
@@ -301,7 +446,7 @@ pub fn initial(terms: Vec<super::Term>) -> Option<Node> {
 
 #[cfg(test)]
 mod tests {
-    use crate::schema::text_field;
+    use crate::schema::{numerical_field, text_field};
 
     use super::*;
 
@@ -416,6 +561,190 @@ mod tests {
         assert_eq!(query.into_query().compact(), expected);
     }
 
+    #[test]
+    fn phrase_prefix_as_tantivy() {
+        let schema = crate::schema::create_schema();
+
+        let query = Query::Term(Term {
+            text: SimpleOrPhrase::PhrasePrefix(vec!["new".to_string(), "york".to_string()]),
+            field: text_field::Title.into(),
+        });
+
+        let tantivy_query = query.as_tantivy(None, &schema).unwrap();
+        let tantivy_query = tantivy_query
+            .downcast_ref::<tantivy::query::PhrasePrefixQuery>()
+            .unwrap();
+
+        assert_eq!(tantivy_query.phrase_terms().len(), 1);
+    }
+
+    #[test]
+    fn phrase_prefix_plan() {
+        let terms = crate::query::parser::parse("\"new york t*\"").unwrap();
+        let query = initial(terms, &CompoundTermsConfig::default())
+            .unwrap()
+            .into_query();
+
+        let Query::Boolean { clauses } = query else {
+            panic!("expected boolean query");
+        };
+
+        assert!(!clauses.is_empty());
+        assert!(clauses.iter().all(|(occur, q)| {
+            *occur == Occur::Should
+                && matches!(
+                    q,
+                    Query::Term(Term {
+                        text: SimpleOrPhrase::PhrasePrefix(_),
+                        field,
+                    }) if field.is_phrase_searchable()
+                )
+        }));
+    }
+
+    #[test]
+    fn not_plan() {
+        fn is_term(query: &Query, text: &SimpleOrPhrase) -> bool {
+            match query {
+                Query::Term(term) => &term.text == text,
+                Query::Boolean { clauses } => clauses.iter().all(|(_, q)| is_term(q, text)),
+                Query::NumericRange { .. } => false,
+                Query::Boost { query, .. } => is_term(query, text),
+            }
+        }
+
+        let this = SimpleOrPhrase::Simple(SimpleTerm::from("this".to_string()));
+        let that = SimpleOrPhrase::Simple(SimpleTerm::from("that".to_string()));
+
+        let terms = crate::query::parser::parse("this -that").unwrap();
+        let Query::Boolean { clauses } = initial(terms, &CompoundTermsConfig::default())
+            .unwrap()
+            .into_query()
+        else {
+            panic!("expected boolean query");
+        };
+
+        assert_eq!(clauses.len(), 2);
+        assert!(clauses
+            .iter()
+            .any(|(occur, q)| *occur == Occur::Must && is_term(q, &this)));
+        assert!(clauses
+            .iter()
+            .any(|(occur, q)| *occur == Occur::MustNot && is_term(q, &that)));
+
+        let phrase = SimpleOrPhrase::Phrase(vec!["that".to_string(), "thing".to_string()], None);
+        let terms = crate::query::parser::parse("this -\"that thing\"").unwrap();
+        let Query::Boolean { clauses } = initial(terms, &CompoundTermsConfig::default())
+            .unwrap()
+            .into_query()
+        else {
+            panic!("expected boolean query");
+        };
+
+        assert!(clauses
+            .iter()
+            .any(|(occur, q)| *occur == Occur::MustNot && is_term(q, &phrase)));
+
+        // a dash on its own is searched for like any other term
+        let terms = crate::query::parser::parse("this - that").unwrap();
+        let query = initial(terms, &CompoundTermsConfig::default())
+            .unwrap()
+            .into_query();
+
+        fn occurs(query: &Query) -> Vec<Occur> {
+            match query {
+                Query::Term(_) | Query::NumericRange { .. } => vec![],
+                Query::Boolean { clauses } => clauses
+                    .iter()
+                    .flat_map(|(occur, q)| std::iter::once(*occur).chain(occurs(q)))
+                    .collect(),
+                Query::Boost { query, .. } => occurs(query),
+            }
+        }
+
+        assert!(!occurs(&query).contains(&Occur::MustNot));
+    }
+
+    #[test]
+    fn site_and_inurl_plan() {
+        fn terms(query: &Query) -> Vec<&Term> {
+            match query {
+                Query::Term(term) => vec![term],
+                Query::Boolean { clauses } => clauses.iter().flat_map(|(_, q)| terms(q)).collect(),
+                Query::NumericRange { .. } => vec![],
+                Query::Boost { query, .. } => terms(query),
+            }
+        }
+
+        let schema = crate::schema::create_schema();
+        let parsed = crate::query::parser::parse("site:example.com inurl:login").unwrap();
+        let query = initial(parsed, &CompoundTermsConfig::default())
+            .unwrap()
+            .into_query();
+
+        assert_eq!(
+            terms(&query),
+            vec![
+                &Term {
+                    text: SimpleOrPhrase::Simple(SimpleTerm::from("example.com".to_string())),
+                    field: text_field::UrlForSiteOperator.into(),
+                },
+                &Term {
+                    text: SimpleOrPhrase::Simple(SimpleTerm::from("login".to_string())),
+                    field: text_field::Url.into(),
+                },
+            ]
+        );
+
+        // the host is matched as a phrase of domain tokens so that
+        // subdomains match but hosts that merely contain the name do not
+        let site = Query::Term(terms(&query)[0].clone());
+        let tantivy_query = site.as_tantivy(None, &schema).unwrap();
+        let tantivy_query = tantivy_query
+            .downcast_ref::<tantivy::query::PhraseQuery>()
+            .unwrap();
+
+        let texts: Vec<_> = tantivy_query
+            .phrase_terms()
+            .into_iter()
+            .map(|term| term.value().as_str().unwrap().to_string())
+            .collect();
+
+        assert_eq!(texts, vec!["example", ".", "com ", "/"]);
+    }
+
+    #[test]
+    fn numeric_range_as_tantivy() {
+        let schema = crate::schema::create_schema();
+
+        let parsed = crate::query::parser::parse("fetch_time_ms:1000..2000").unwrap();
+        let query = initial(parsed, &CompoundTermsConfig::default())
+            .unwrap()
+            .into_query();
+
+        assert_eq!(
+            query,
+            Query::NumericRange {
+                field: numerical_field::FetchTimeMs.into(),
+                lower: Bound::Included(1000.0),
+                upper: Bound::Included(2000.0),
+            }
+        );
+
+        let tantivy_query = query.as_tantivy(None, &schema).unwrap();
+        let tantivy_query = tantivy_query
+            .downcast_ref::<tantivy::query::RangeQuery>()
+            .unwrap();
+        assert_eq!(tantivy_query.field(), "fetch_time_ms");
+
+        assert_eq!(lower_u64_bound(Bound::Included(1.5)), Bound::Included(2));
+        assert_eq!(lower_u64_bound(Bound::Excluded(1.0)), Bound::Included(2));
+        assert_eq!(lower_u64_bound(Bound::Included(-5.0)), Bound::Included(0));
+        assert_eq!(upper_u64_bound(Bound::Included(2.5)), Bound::Included(2));
+        assert_eq!(upper_u64_bound(Bound::Excluded(2.5)), Bound::Excluded(3));
+        assert_eq!(upper_u64_bound(Bound::Included(-1.0)), Bound::Excluded(0));
+    }
+
     #[test]
     fn test_sliding_window() {
         let window_size = 3;
@@ -439,4 +768,158 @@ mod tests {
 
         assert_eq!(sliding_window(window_size, i).collect::<Vec<_>>(), expected);
     }
+
+    #[test]
+    fn compound_window_config() {
+        fn num_terms(query: &Query) -> usize {
+            match query {
+                Query::Term(_) => 1,
+                Query::Boolean { clauses } => clauses.iter().map(|(_, q)| num_terms(q)).sum(),
+                Query::NumericRange { .. } => 0,
+                Query::Boost { query, .. } => num_terms(query),
+            }
+        }
+
+        let terms = crate::query::parser::parse("the new york times").unwrap();
+
+        let default = num_terms(
+            &initial(terms.clone(), &CompoundTermsConfig::default())
+                .unwrap()
+                .into_query(),
+        );
+
+        let smaller_window = num_terms(
+            &initial(
+                terms.clone(),
+                &CompoundTermsConfig {
+                    max_window: 2,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .into_query(),
+        );
+
+        let no_compounds = num_terms(
+            &initial(
+                terms,
+                &CompoundTermsConfig {
+                    max_terms: 3,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .into_query(),
+        );
+
+        assert!(smaller_window < default);
+        assert!(no_compounds < smaller_window);
+    }
+
+    #[test]
+    fn deduplicate_compounds() {
+        fn conjuncts(node: &Node) -> Vec<&Node> {
+            match node {
+                Node::And(left, right) => {
+                    let mut res = conjuncts(left);
+                    res.extend(conjuncts(right));
+                    res
+                }
+                node => vec![node],
+            }
+        }
+
+        fn terms(node: &Node) -> Vec<&Term> {
+            match node {
+                Node::Term(term) => vec![term],
+                Node::And(left, right) | Node::Or(left, right) => {
+                    let mut res = terms(left);
+                    res.extend(terms(right));
+                    res
+                }
+                Node::Not(node) | Node::Boost(node, _) => terms(node),
+                Node::NumericRange(_) => vec![],
+            }
+        }
+
+        let parsed = crate::query::parser::parse("new york new york").unwrap();
+        let plan = initial(parsed, &CompoundTermsConfig::default()).unwrap();
+
+        let nodes = conjuncts(&plan);
+        assert_eq!(nodes.len(), 4);
+
+        for node in &nodes {
+            let leaves = terms(node);
+            assert_eq!(leaves.iter().unique().count(), leaves.len());
+        }
+
+        let new_york = SimpleOrPhrase::Simple(SimpleTerm::from("newyork".to_string()));
+        let field = TextFieldEnum::all()
+            .find(|f| f.is_searchable() && f.is_compound_searchable())
+            .unwrap();
+
+        assert_eq!(
+            terms(nodes[0])
+                .into_iter()
+                .filter(|term| term.text == new_york && term.field == field)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn estimated_cost() {
+        let cost = |query: &str| {
+            let terms = crate::query::parser::parse(query).unwrap();
+            initial(terms, &CompoundTermsConfig::default())
+                .unwrap()
+                .into_query()
+                .estimated_cost()
+        };
+
+        let single = cost("york");
+        let multi = cost("the new york times");
+
+        assert!(single > 0);
+        assert!(multi > 4 * single);
+
+        assert!(cost("\"new york times\"") > cost("\"new york\""));
+    }
+
+    #[test]
+    fn field_boost_plan() {
+        let schema = crate::schema::create_schema();
+
+        let parsed = crate::query::parser::parse("title:foo^2").unwrap();
+        let query = initial(parsed, &CompoundTermsConfig::default())
+            .unwrap()
+            .into_query();
+
+        assert_eq!(
+            query,
+            Query::Boost {
+                query: Box::new(Query::Term(Term {
+                    text: SimpleOrPhrase::Simple(SimpleTerm::from("foo".to_string())),
+                    field: text_field::Title.into(),
+                })),
+                boost: 2.0,
+            }
+        );
+
+        let tantivy_query = query.as_tantivy(None, &schema).unwrap();
+        let boost_query = tantivy_query
+            .downcast_ref::<tantivy::query::BoostQuery>()
+            .unwrap();
+        assert!(format!("{boost_query:?}").contains("boost=2"));
+
+        // a field term without a boost is a plain term query
+        let parsed = crate::query::parser::parse("title:foo").unwrap();
+        let query = initial(parsed, &CompoundTermsConfig::default())
+            .unwrap()
+            .into_query();
+
+        assert!(
+            matches!(query, Query::Term(Term { field, .. }) if field == text_field::Title.into())
+        );
+    }
 }
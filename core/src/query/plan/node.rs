@@ -16,7 +16,7 @@
 
 use ahash::HashSetExt;
 
-use crate::query::parser::{SimpleOrPhrase, Term as ParserTerm};
+use crate::query::parser::{FieldTerm, NumericRange, SimpleOrPhrase, Term as ParserTerm};
 use crate::schema::text_field::{self, TextField as _};
 use crate::{query::parser::SimpleTerm, schema::TextFieldEnum};
 
@@ -27,18 +27,23 @@ use super::{Occur, Term};
 #[derive(Debug, Clone)]
 pub enum Node {
     Term(Term),
+    NumericRange(NumericRange),
     And(Box<Node>, Box<Node>),
     Or(Box<Node>, Box<Node>),
     Not(Box<Node>),
+    /// Multiplies the score of the inner node. The boost is never NaN.
+    Boost(Box<Node>, f32),
 }
 
 impl PartialEq for Node {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Node::Term(a), Node::Term(b)) => a == b,
+            (Node::NumericRange(a), Node::NumericRange(b)) => a == b,
             (Node::And(a, b), Node::And(c, d)) => (a == c && b == d) || (a == d && b == c),
             (Node::Or(a, b), Node::Or(c, d)) => (a == c && b == d) || (a == d && b == c),
             (Node::Not(a), Node::Not(b)) => a == b,
+            (Node::Boost(a, x), Node::Boost(b, y)) => a == b && x.to_bits() == y.to_bits(),
             _ => false,
         }
     }
@@ -50,6 +55,7 @@ impl std::hash::Hash for Node {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match self {
             Node::Term(term) => term.hash(state),
+            Node::NumericRange(range) => range.hash(state),
             Node::And(left, right) => {
                 left.hash(state);
                 right.hash(state);
@@ -59,6 +65,10 @@ impl std::hash::Hash for Node {
                 right.hash(state);
             }
             Node::Not(inner) => inner.hash(state),
+            Node::Boost(inner, boost) => {
+                inner.hash(state);
+                boost.to_bits().hash(state);
+            }
         }
     }
 }
@@ -77,6 +87,11 @@ impl Node {
     fn into_non_compacted_query(self) -> super::Query {
         match self {
             Node::Term(term) => super::Query::Term(term),
+            Node::NumericRange(range) => super::Query::NumericRange {
+                field: range.field,
+                lower: range.lower,
+                upper: range.upper,
+            },
             Node::And(left, right) => super::Query::Boolean {
                 clauses: vec![
                     (Occur::Must, left.into_non_compacted_query()),
@@ -92,6 +107,10 @@ impl Node {
             Node::Not(inner) => super::Query::Boolean {
                 clauses: vec![(Occur::MustNot, inner.into_non_compacted_query())],
             },
+            Node::Boost(inner, boost) => super::Query::Boost {
+                query: Box::new(inner.into_non_compacted_query()),
+                boost,
+            },
         }
     }
 
@@ -115,21 +134,25 @@ impl Node {
                     })
                     .reduce(|left, right| left.or(right))
                     .expect("fields should not be empty"),
-                SimpleOrPhrase::Phrase(p) => TextFieldEnum::all()
-                    .filter(|f| f.is_searchable() && f.is_phrase_searchable())
-                    .map(|field| {
-                        Node::Term(Term {
-                            text: SimpleOrPhrase::Phrase(p.clone()),
-                            field,
+                phrase @ (SimpleOrPhrase::Phrase(..) | SimpleOrPhrase::PhrasePrefix(_)) => {
+                    TextFieldEnum::all()
+                        .filter(|f| f.is_searchable() && f.is_phrase_searchable())
+                        .map(|field| {
+                            Node::Term(Term {
+                                text: phrase.clone(),
+                                field,
+                            })
                         })
-                    })
-                    .reduce(|left, right| left.or(right))
-                    .expect("fields should not be empty"),
+                        .reduce(|left, right| left.or(right))
+                        .expect("fields should not be empty")
+                }
+            },
+            ParserTerm::Site(s) => match s.strip_suffix('.') {
+                // the host matches 'example.com' but not '.example.com'
+                Some(host) => Node::site(host.to_string())
+                    .and(Node::Not(Box::new(Node::site(format!(".{host}"))))),
+                None => Node::site(s),
             },
-            ParserTerm::Site(s) => Node::Term(Term {
-                text: SimpleOrPhrase::Simple(SimpleTerm::from(s)),
-                field: text_field::UrlForSiteOperator.into(),
-            }),
             ParserTerm::LinkTo(s) => Node::Term(Term {
                 text: SimpleOrPhrase::Simple(SimpleTerm::from(s)),
                 field: text_field::Links.into(),
@@ -168,10 +191,28 @@ impl Node {
                     .reduce(|left, right| left.or(right))
                     .expect("fields should not be empty")
             }
+            ParserTerm::NumericRange(range) => Node::NumericRange(range),
+            ParserTerm::Field(FieldTerm { field, term, boost }) => {
+                let node = Node::Term(Term { text: term, field });
+
+                match boost {
+                    Some(boost) => Node::Boost(Box::new(node), boost),
+                    None => node,
+                }
+            }
             ParserTerm::Not(n) => Node::Not(Box::new(Node::from_term(*n))),
         }
     }
 
+    /// Matches pages on the host or any of its subdomains. Hosts starting
+    /// with a dot only match subdomains.
+    fn site(host: String) -> Self {
+        Node::Term(Term {
+            text: SimpleOrPhrase::Simple(SimpleTerm::from(host)),
+            field: text_field::UrlForSiteOperator.into(),
+        })
+    }
+
     fn or_children(self) -> HashSet<Node> {
         match self {
             Node::Or(left, right) => {
@@ -220,7 +261,9 @@ impl Optimisation for DistributiveLaw {
     fn optimise(&self, node: Node) -> Node {
         match node {
             Node::Term(term) => Node::Term(term),
+            Node::NumericRange(range) => Node::NumericRange(range),
             Node::Not(inner) => Node::Not(Box::new(self.optimise(*inner))),
+            Node::Boost(inner, boost) => Node::Boost(Box::new(self.optimise(*inner)), boost),
             Node::Or(left, right) => Node::Or(
                 Box::new(self.optimise(*left)),
                 Box::new(self.optimise(*right)),
@@ -279,7 +322,9 @@ impl Optimisation for Deduplicate {
     fn optimise(&self, node: Node) -> Node {
         match node {
             Node::Term(term) => Node::Term(term),
+            Node::NumericRange(range) => Node::NumericRange(range),
             Node::Not(inner) => Node::Not(Box::new(self.optimise(*inner))),
+            Node::Boost(inner, boost) => Node::Boost(Box::new(self.optimise(*inner)), boost),
             Node::Or(left, right) => {
                 let left = self.optimise(*left);
                 let right = self.optimise(*right);
@@ -0,0 +1,149 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+use std::sync::Arc;
+
+use regex_automata::{
+    dfa::{dense, Automaton as _},
+    util::{primitives::StateID, start},
+    Anchored,
+};
+use tantivy::{
+    query::{AutomatonWeight, EnableScoring, Query, Weight},
+    schema::Field,
+};
+
+/// Upper bound on the memory used by the DFA of a single regex.
+const DFA_SIZE_LIMIT: usize = 10 * (1 << 20);
+
+/// Matches the documents whose url matches a regex.
+///
+/// The regex is compiled to a DFA that is run directly on the term dictionary
+/// of an untokenized url field, like tantivy's `RegexQuery`, so branches of the
+/// dictionary that can't match are skipped. The field must contain the entire
+/// url as a single token.
+#[derive(Debug, Clone)]
+pub struct UrlRegexQuery {
+    automaton: Arc<RegexAutomaton>,
+    field: Field,
+}
+
+impl UrlRegexQuery {
+    /// Fails if the regex is invalid or its DFA would be too large.
+    pub fn new(regex: &str, field: Field) -> crate::Result<Self> {
+        let dfa = dense::Builder::new()
+            .configure(
+                dense::Config::new()
+                    .dfa_size_limit(Some(DFA_SIZE_LIMIT))
+                    .determinize_size_limit(Some(DFA_SIZE_LIMIT)),
+            )
+            .build(regex)?;
+
+        Ok(Self {
+            automaton: Arc::new(RegexAutomaton(dfa)),
+            field,
+        })
+    }
+}
+
+impl Query for UrlRegexQuery {
+    fn weight(&self, _enable_scoring: EnableScoring) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(AutomatonWeight::new(
+            self.field,
+            Arc::clone(&self.automaton),
+        )))
+    }
+}
+
+/// Unanchored regex search as an fst automaton. A term matches if the regex
+/// matches anywhere in it.
+#[derive(Debug)]
+struct RegexAutomaton(dense::DFA<Vec<u32>>);
+
+#[derive(Debug, Clone, Copy)]
+enum RegexState {
+    Searching(StateID),
+    Matched,
+    Failed,
+}
+
+impl fst::Automaton for RegexAutomaton {
+    type State = RegexState;
+
+    fn start(&self) -> Self::State {
+        match self
+            .0
+            .start_state(&start::Config::new().anchored(Anchored::No))
+        {
+            Ok(id) => RegexState::Searching(id),
+            Err(_) => RegexState::Failed,
+        }
+    }
+
+    fn is_match(&self, state: &Self::State) -> bool {
+        match *state {
+            RegexState::Searching(id) => self.0.is_match_state(self.0.next_eoi_state(id)),
+            RegexState::Matched => true,
+            RegexState::Failed => false,
+        }
+    }
+
+    fn can_match(&self, state: &Self::State) -> bool {
+        !matches!(state, RegexState::Failed)
+    }
+
+    fn will_always_match(&self, state: &Self::State) -> bool {
+        matches!(state, RegexState::Matched)
+    }
+
+    fn accept(&self, state: &Self::State, byte: u8) -> Self::State {
+        match *state {
+            RegexState::Searching(id) => {
+                // matches are reported one byte late, so this is a match
+                // that ends before `byte`
+                let next = self.0.next_state(id, byte);
+
+                if self.0.is_match_state(next) {
+                    RegexState::Matched
+                } else if self.0.is_dead_state(next) || self.0.is_quit_state(next) {
+                    RegexState::Failed
+                } else {
+                    RegexState::Searching(next)
+                }
+            }
+            state => state,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fst::Automaton;
+
+    use super::*;
+
+    fn matches(regex: &str, url: &str) -> bool {
+        let automaton = RegexAutomaton(dense::DFA::new(regex).unwrap());
+
+        let state = url.bytes().fold(automaton.start(), |state, byte| {
+            automaton.accept(&state, byte)
+        });
+
+        automaton.is_match(&state)
+    }
+
+    #[test]
+    fn unanchored_match() {
+        assert!(matches(r"/posts/\d+$", "https://a.com/posts/123"));
+        assert!(matches(r"/posts/\d+", "https://a.com/posts/123/comments"));
+        assert!(!matches(r"/posts/\d+$", "https://a.com/posts/about"));
+        assert!(matches(r"^https://a\.com/", "https://a.com/posts/123"));
+        assert!(!matches(
+            r"^https://a\.com/",
+            "https://b.com/https://a.com/"
+        ));
+        assert!(matches("", "https://a.com/"));
+    }
+}
@@ -101,6 +101,15 @@ impl MultiBm25Weight {
             .sum()
     }
 
+    /// Multiplies the score of each term by its boost. The idf of the terms is unchanged.
+    pub fn boost_by(mut self, boosts: &[Score]) -> Self {
+        for (weight, boost) in self.weights.iter_mut().zip_eq(boosts) {
+            *weight = weight.boost_by(*boost);
+        }
+
+        self
+    }
+
     pub fn idf(&self) -> impl Iterator<Item = f32> + '_ {
         self.weights.iter().map(|w| w.weight)
     }
@@ -109,6 +118,7 @@ impl MultiBm25Weight {
 #[derive(Clone)]
 pub struct Bm25Weight {
     weight: Score,
+    boost: Score,
     constants: Bm25Constants,
     cache: [Score; 256],
 }
@@ -127,14 +137,22 @@ impl Bm25Weight {
     pub fn new(weight: Score, average_fieldnorm: Score, constants: Bm25Constants) -> Bm25Weight {
         Bm25Weight {
             weight,
+            boost: 1.0,
             cache: compute_tf_cache(average_fieldnorm, constants),
             constants,
         }
     }
 
+    pub fn boost_by(&self, boost: Score) -> Bm25Weight {
+        Bm25Weight {
+            boost: self.boost * boost,
+            ..self.clone()
+        }
+    }
+
     #[inline]
     pub fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
-        self.weight * self.tf_factor(fieldnorm_id, term_freq)
+        self.weight * self.boost * self.tf_factor(fieldnorm_id, term_freq)
     }
 
     #[inline]
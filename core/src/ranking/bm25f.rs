@@ -89,11 +89,21 @@ impl MultiBm25FWeight {
             })
             .sum()
     }
+
+    /// Multiplies the score of each term by its boost. The idf of the terms is unchanged.
+    pub fn boost_by(mut self, boosts: &[Score]) -> Self {
+        for (weight, boost) in self.weights.iter_mut().zip_eq(boosts) {
+            *weight = weight.boost_by(*boost);
+        }
+
+        self
+    }
 }
 
 #[derive(Clone)]
 pub struct Bm25FWeight {
     weight: Score,
+    boost: Score,
     cache: [Score; 256],
     constants: Bm25Constants,
 }
@@ -112,14 +122,22 @@ impl Bm25FWeight {
     pub fn new(weight: Score, average_fieldnorm: Score, constants: Bm25Constants) -> Bm25FWeight {
         Bm25FWeight {
             weight,
+            boost: 1.0,
             cache: compute_tf_cache(average_fieldnorm, constants),
             constants,
         }
     }
 
+    pub fn boost_by(&self, boost: Score) -> Bm25FWeight {
+        Bm25FWeight {
+            boost: self.boost * boost,
+            ..self.clone()
+        }
+    }
+
     #[inline]
     pub fn score(&self, coefficient: Score, fieldnorm_id: u8, term_freq: u32) -> Score {
-        self.weight * (self.tf_factor(coefficient, fieldnorm_id, term_freq))
+        self.weight * self.boost * self.tf_factor(coefficient, fieldnorm_id, term_freq)
     }
 
     #[inline]
@@ -133,3 +151,28 @@ impl Bm25FWeight {
         (term_freq * (self.constants.k1 + 1.0)) / (term_freq + norm)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boost() {
+        let constants = Bm25Constants::default();
+        let weight = MultiBm25FWeight {
+            weights: vec![
+                Bm25FWeight::for_one_term(20, 100, 1.0, constants),
+                Bm25FWeight::for_one_term(20, 100, 1.0, constants),
+            ],
+        };
+        let boosted = weight.clone().boost_by(&[1.0, 3.0]);
+
+        let first = weight.score(1.0, vec![(0, 5), (0, 0)].into_iter());
+        let second = weight.score(1.0, vec![(0, 0), (0, 5)].into_iter());
+        assert_eq!(first, second);
+
+        assert_eq!(boosted.score(1.0, vec![(0, 5), (0, 0)].into_iter()), first);
+        let boosted_second = boosted.score(1.0, vec![(0, 0), (0, 5)].into_iter());
+        assert!((boosted_second - 3.0 * second).abs() < 1e-5);
+    }
+}
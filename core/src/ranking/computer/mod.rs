@@ -4,13 +4,14 @@
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
 use crate::query::optic::AsSearchableRule;
-use crate::query::{Query, MAX_TERMS_FOR_NGRAM_LOOKUPS};
+use crate::query::{FieldTermText, Query};
 use crate::ranking::bm25f::MultiBm25FWeight;
 use crate::schema::text_field::TextField;
 use crate::Result;
 use crate::{enum_map::EnumMap, numericalfield_reader, schema::TextFieldEnum, webpage::Webpage};
 
 use std::cell::RefCell;
+use std::collections::HashSet;
 
 use std::sync::Arc;
 
@@ -41,7 +42,9 @@ fn posting_contains(posting: &mut SegmentPostings, doc: DocId) -> bool {
 #[derive(Clone)]
 pub struct TextFieldData {
     postings: Vec<SegmentPostings>,
-    num_query_terms: usize,
+    /// Whether the term of each posting is the first occurrence of that term in the query.
+    distinct: Vec<bool>,
+    num_distinct_terms: usize,
     bm25: MultiBm25Weight,
     bm25f: MultiBm25FWeight,
     fieldnorm_reader: FieldNormReader,
@@ -64,6 +67,7 @@ impl TextFieldData {
             .collect()
     }
 
+    /// Fraction of the distinct query terms that occur in the field.
     pub fn coverage(&mut self, doc: DocId) -> f64 {
         if self.postings.is_empty() {
             return 0.0;
@@ -71,15 +75,16 @@ impl TextFieldData {
 
         self.postings
             .iter_mut()
-            .map(|posting| {
-                if posting_contains(posting, doc) {
+            .zip_eq(&self.distinct)
+            .map(|(posting, distinct)| {
+                if *distinct && posting_contains(posting, doc) {
                     1.0
                 } else {
                     0.0
                 }
             })
             .sum::<f64>()
-            / self.num_query_terms as f64
+            / self.num_distinct_terms as f64
     }
 
     pub fn bm25(&mut self, doc: DocId) -> f64 {
@@ -165,15 +170,23 @@ impl SegmentReader {
 #[derive(Clone)]
 pub struct QueryData {
     simple_terms: Vec<String>,
+    field_terms: Vec<FieldTermText>,
     optic_rules: Vec<optics::Rule>,
     selected_region: Option<crate::webpage::Region>,
+    geo_point: Option<crate::webpage::GeoPoint>,
     lang: Option<whatlang::Lang>,
+    /// Queries with more terms than this are not looked up in the n-gram fields.
+    max_ngram_terms: usize,
 }
 impl QueryData {
     pub fn selected_region(&self) -> Option<crate::webpage::Region> {
         self.selected_region
     }
 
+    pub fn geo_point(&self) -> Option<crate::webpage::GeoPoint> {
+        self.geo_point
+    }
+
     pub fn simple_terms(&self) -> &[String] {
         &self.simple_terms
     }
@@ -187,6 +200,7 @@ pub struct SignalComputer {
     update_time_cache: Vec<f64>,
     region_count: Option<Arc<RegionCount>>,
     current_timestamp: Option<usize>,
+    freshness_half_life_hours: f64,
     linear_regression: Option<Arc<LinearRegression>>,
     order: SignalComputeOrder,
 }
@@ -201,6 +215,7 @@ impl Clone for SignalComputer {
             update_time_cache: self.update_time_cache.clone(),
             region_count: self.region_count.clone(),
             current_timestamp: self.current_timestamp,
+            freshness_half_life_hours: self.freshness_half_life_hours,
             linear_regression: self.linear_regression.clone(),
             order: self.order.clone(),
         }
@@ -238,18 +253,20 @@ impl SignalComputer {
 
         let query = query.as_ref().map(|q| QueryData {
             simple_terms: q.simple_terms().to_vec(),
+            field_terms: q.field_terms().to_vec(),
             optic_rules: q
                 .optics()
                 .iter()
-                .flat_map(|o| o.rules.iter())
+                .flat_map(|o| o.rules.iter().cloned().chain(o.language_rule()))
                 .filter(|rule| match rule.action {
                     optics::Action::Downrank(b) | optics::Action::Boost(b) => b != 0,
                     optics::Action::Discard => false,
                 })
-                .cloned()
                 .collect(),
             selected_region: q.region().cloned(),
+            geo_point: q.geo_point(),
             lang: q.lang(),
+            max_ngram_terms: q.compound_terms().max_terms,
         });
 
         let mut s = Self {
@@ -259,6 +276,8 @@ impl SignalComputer {
             update_time_cache,
             region_count: None,
             current_timestamp: None,
+            freshness_half_life_hours:
+                crate::config::defaults::Collector::freshness_half_life_hours(),
             linear_regression: None,
             query_data: query,
             order: SignalComputeOrder::new(),
@@ -278,14 +297,14 @@ impl SignalComputer {
         let schema = tv_searcher.schema();
 
         if let Some(query) = &self.query_data {
-            if !query.simple_terms.is_empty() {
+            if !query.simple_terms.is_empty() || !query.field_terms.is_empty() {
                 for signal in CoreSignalEnum::all() {
                     if let Some((text_field, tv_field)) = signal
                         .as_textfield()
                         .and_then(|f| (f.tantivy_field(schema).map(|tv_field| (f, tv_field))))
                     {
                         if text_field.ngram_size() > 1
-                            && query.simple_terms.len() > MAX_TERMS_FOR_NGRAM_LOOKUPS
+                            && query.simple_terms.len() > query.max_ngram_terms
                         {
                             continue;
                         }
@@ -296,20 +315,39 @@ impl SignalComputer {
                         )
                         .collect::<String>();
 
+                        // terms targeting this field are scored like the other terms,
+                        // but with the boost they were given in the query
+                        let field_terms = query
+                            .field_terms
+                            .iter()
+                            .filter(|field_term| field_term.field == text_field)
+                            .map(|field_term| (field_term.text.as_str(), field_term.boost));
+
                         let mut terms = Vec::new();
+                        let mut boosts = Vec::new();
                         let mut tokenizer = text_field.query_tokenizer(query.lang.as_ref());
-                        let mut stream = tokenizer.token_stream(&simple_query);
-                        let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
 
-                        while let Some(token) = it.next() {
-                            let term = tantivy::Term::from_field_text(tv_field, &token.text);
-                            terms.push(term);
+                        for (text, boost) in
+                            std::iter::once((simple_query.as_str(), 1.0)).chain(field_terms)
+                        {
+                            let mut stream = tokenizer.token_stream(text);
+                            let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
+
+                            while let Some(token) = it.next() {
+                                let term = tantivy::Term::from_field_text(tv_field, &token.text);
+                                terms.push(term);
+                                boosts.push(boost);
+                            }
                         }
 
                         if terms.is_empty() {
                             continue;
                         }
 
+                        let mut seen = HashSet::with_capacity(terms.len());
+                        let distinct: Vec<_> =
+                            terms.iter().map(|term| seen.insert(term.clone())).collect();
+
                         let fieldnorm_reader = segment_reader.get_fieldnorms_reader(tv_field)?;
                         let inverted_index = segment_reader.inverted_index(tv_field)?;
 
@@ -327,12 +365,14 @@ impl SignalComputer {
                             tv_searcher,
                             &terms,
                             text_field.bm25_constants(),
-                        )?;
+                        )?
+                        .boost_by(&boosts);
                         let bm25f = MultiBm25FWeight::for_terms(
                             tv_searcher,
                             &terms,
                             text_field.bm25_constants(),
-                        );
+                        )
+                        .boost_by(&boosts);
 
                         text_fields.insert(
                             text_field,
@@ -342,7 +382,8 @@ impl SignalComputer {
                                 bm25f,
                                 fieldnorm_reader,
                                 signal_coefficient: self.coefficient(&signal.into()),
-                                num_query_terms: terms.len(),
+                                distinct,
+                                num_distinct_terms: seen.len(),
                             },
                         );
                     }
@@ -417,6 +458,10 @@ impl SignalComputer {
         self.current_timestamp = Some(current_timestamp);
     }
 
+    pub fn set_freshness_half_life_hours(&mut self, hours: f64) {
+        self.freshness_half_life_hours = hours;
+    }
+
     pub fn set_linear_model(&mut self, linear_model: Arc<LinearRegression>) {
         self.linear_regression = Some(linear_model);
     }
@@ -511,6 +556,10 @@ impl SignalComputer {
         &self.update_time_cache
     }
 
+    pub fn freshness_half_life_hours(&self) -> f64 {
+        self.freshness_half_life_hours
+    }
+
     pub fn region_count(&self) -> Option<&RegionCount> {
         self.region_count.as_deref()
     }
@@ -29,22 +29,24 @@ pub use computer::SignalComputer;
 use initial::InitialScoreTweaker;
 
 pub use signals::{
-    ComputedSignal, CoreSignal, CoreSignalEnum, Signal, SignalCalculation, SignalCoefficients,
-    SignalEnum, SignalEnumDiscriminants, SignalScore,
+    ComputedSignal, CoreSignal, CoreSignalEnum, Explanation, Signal, SignalCalculation,
+    SignalCoefficients, SignalContribution, SignalEnum, SignalEnumDiscriminants, SignalScore,
 };
 
 use crate::{
     collector::{MainCollector, MaxDocsConsidered, TopDocs},
     config::CollectorConfig,
+    distributed::member::ShardId,
     numericalfield_reader::NumericalFieldReader,
     search_ctx::Ctx,
-    searcher::NUM_RESULTS_PER_PAGE,
+    searcher::{Cursor, NUM_RESULTS_PER_PAGE},
 };
 
 #[derive(Clone)]
 pub struct LocalRanker {
     max_docs: Option<MaxDocsConsidered>,
     offset: Option<usize>,
+    after: Option<(Cursor, Option<ShardId>)>,
     computer: SignalComputer,
     columnfield_reader: NumericalFieldReader,
     de_rank_similar: bool,
@@ -60,6 +62,7 @@ impl LocalRanker {
     ) -> Self {
         LocalRanker {
             offset: None,
+            after: None,
             computer,
             max_docs: None,
             de_rank_similar: true,
@@ -74,6 +77,12 @@ impl LocalRanker {
         self
     }
 
+    /// Only rank the documents after the cursor, given that the index is `shard`.
+    pub fn with_after(mut self, cursor: Cursor, shard: Option<ShardId>) -> Self {
+        self.after = Some((cursor, shard));
+        self
+    }
+
     pub fn with_max_docs(mut self, total_docs: usize, segments: usize) -> Self {
         self.max_docs = Some(MaxDocsConsidered {
             total_docs,
@@ -114,10 +123,18 @@ impl LocalRanker {
             collector = collector.and_offset(offset);
         }
 
+        if let Some((cursor, shard)) = self.after {
+            collector = collector.and_after(cursor, shard);
+        }
+
         if let Some(max_docs) = &self.max_docs {
             collector = collector.and_max_docs(max_docs.clone());
         }
 
+        if let Some(overfetch) = self.collector_config.approximate_top_k_overfetch {
+            collector = collector.and_approximate(overfetch);
+        }
+
         collector = collector.and_collector_config(self.collector_config.clone());
 
         collector.main_collector(score_tweaker)
@@ -129,8 +146,10 @@ mod tests {
 
     use std::path::Path;
 
+    use optics::Optic;
+
     use crate::{
-        config::{IndexerConfig, IndexerDualEncoderConfig, WarcSource},
+        config::{DocumentSource, IndexerConfig, IndexerDualEncoderConfig, WarcSource},
         entrypoint::indexer::IndexingWorker,
         index::Index,
         models::dual_encoder::DualEncoder,
@@ -345,6 +364,123 @@ mod tests {
         assert_eq!(result.webpages[0].url, "https://www.new.com/");
     }
 
+    #[test]
+    fn inserted_at_freshness_ranking() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        for (url, inserted_at) in [
+            (
+                "https://www.old.com",
+                chrono::Utc::now() - chrono::Duration::days(365),
+            ),
+            ("https://www.new.com", chrono::Utc::now()),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Title</title>
+                            </head>
+                            <body>
+                                {CONTENT}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    inserted_at,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+        let result = searcher
+            .search(&SearchQuery {
+                query: "example".to_string(),
+                return_ranking_signals: true,
+                signal_coefficients: crate::enum_map! {
+                    crate::ranking::SignalEnum::from(crate::ranking::signals::Freshness) => 100_000.0,
+                }.into(),
+                ..Default::default()
+            })
+            .expect("Search failed");
+
+        assert_eq!(result.webpages.len(), 2);
+        assert_eq!(result.webpages[0].url, "https://www.new.com/");
+        assert_eq!(result.webpages[1].url, "https://www.old.com/");
+    }
+
+    #[test]
+    fn published_date_freshness_ranking() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        // the old article was inserted most recently, but the publication date wins
+        for (url, published, inserted_at) in [
+            (
+                "https://www.old.com",
+                "2000-01-01".to_string(),
+                chrono::Utc::now(),
+            ),
+            (
+                "https://www.new.com",
+                chrono::Utc::now().format("%Y-%m-%d").to_string(),
+                chrono::Utc::now() - chrono::Duration::days(365),
+            ),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                        <html>
+                            <head>
+                                <title>Title</title>
+                                <meta property="article:published_time" content="{published}" />
+                            </head>
+                            <body>
+                                {CONTENT}
+                            </body>
+                        </html>
+                    "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    inserted_at,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+        let result = searcher
+            .search(&SearchQuery {
+                query: "example".to_string(),
+                return_ranking_signals: true,
+                signal_coefficients: crate::enum_map! {
+                    crate::ranking::SignalEnum::from(crate::ranking::signals::Freshness) => 100_000.0,
+                }.into(),
+                ..Default::default()
+            })
+            .expect("Search failed");
+
+        assert_eq!(result.webpages.len(), 2);
+        assert_eq!(result.webpages[0].url, "https://www.new.com/");
+        assert_eq!(result.webpages[1].url, "https://www.old.com/");
+    }
+
     #[test]
     fn derank_trackers() {
         let (mut index, _dir) = Index::temporary().expect("Unable to open index");
@@ -443,6 +579,7 @@ mod tests {
             to: NodeDatum::new(1u64, 1),
             label: "test this is the best test site".to_string(),
             rel: Default::default(),
+            weight: 1.0,
         }]);
 
         index.insert(&webpage).expect("failed to insert webpage");
@@ -588,6 +725,80 @@ mod tests {
         assert_eq!(&res.webpages[0].url, "https://www.centrality.com/");
     }
 
+    #[test]
+    fn api_signal_coefficients() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(&Webpage {
+                html: Html::parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Website A</title>
+                        </head>
+                        <body>
+                            {CONTENT} {}
+                            example example example
+                        </body>
+                    </html>
+                "#,
+                        crate::rand_words(100)
+                    ),
+                    "https://www.a.com",
+                )
+                .unwrap(),
+                fetch_time_ms: 500,
+                ..Default::default()
+            })
+            .expect("failed to insert webpage");
+        index
+            .insert(&Webpage {
+                html: Html::parse(
+                    &format!(
+                        r#"
+                    <html>
+                        <head>
+                            <title>Website B</title>
+                        </head>
+                        <body>
+                            {CONTENT} {}
+                        </body>
+                    </html>
+                "#,
+                        crate::rand_words(100)
+                    ),
+                    "https://www.b.com",
+                )
+                .unwrap(),
+                host_centrality: 5.0,
+                fetch_time_ms: 500,
+                ..Default::default()
+            })
+            .expect("failed to insert webpage");
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let search = |body: &str| {
+            let query: crate::api::search::ApiSearchQuery = serde_json::from_str(body).unwrap();
+            searcher
+                .search(&SearchQuery::try_from(query).unwrap())
+                .expect("Search failed")
+        };
+
+        let result = search(r#"{"query": "example"}"#);
+        assert_eq!(result.webpages.len(), 2);
+        assert_eq!(result.webpages[0].url, "https://www.b.com/");
+
+        let result = search(
+            r#"{"query": "example", "signalCoefficients": {"host_centrality": 0.0, "not_a_signal": 1.0}}"#,
+        );
+        assert_eq!(result.webpages.len(), 2);
+        assert_eq!(result.webpages[0].url, "https://www.a.com/");
+    }
+
     #[test]
     fn fetch_time_ranking() {
         let (mut index, _dir) = Index::temporary().expect("Unable to open index");
@@ -657,6 +868,75 @@ mod tests {
         assert_eq!(result.webpages[1].url, "https://www.second.com/");
     }
 
+    #[test]
+    fn https_ranking() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+        let body = crate::rand_words(100);
+
+        // the http page is inserted first so it would win a tie
+        for url in ["http://www.example.com", "https://www.example.com"] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                            <html>
+                                <head>
+                                    <title>Test website</title>
+                                </head>
+                                <body>
+                                    {CONTENT} {body}
+                                </body>
+                            </html>
+                        "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::new(index);
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "test".to_string(),
+                ..Default::default()
+            })
+            .expect("Search failed");
+
+        assert_eq!(result.webpages.len(), 2);
+        assert_eq!(result.webpages[0].url, "https://www.example.com/");
+        assert_eq!(result.webpages[1].url, "http://www.example.com/");
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "test".to_string(),
+                secure_only: true,
+                ..Default::default()
+            })
+            .expect("Search failed");
+
+        assert_eq!(result.webpages.len(), 1);
+        assert_eq!(result.webpages[0].url, "https://www.example.com/");
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "test".to_string(),
+                optic: Some(Optic::parse("SecureOnly;").unwrap()),
+                ..Default::default()
+            })
+            .expect("Search failed");
+
+        assert_eq!(result.webpages.len(), 1);
+        assert_eq!(result.webpages[0].url, "https://www.example.com/");
+    }
+
     #[test]
     fn num_slashes_and_digits() {
         let (mut index, _dir) = Index::temporary().expect("Unable to open index");
@@ -768,6 +1048,7 @@ mod tests {
                 page_centrality_store_path: None,
                 page_webgraph: None,
                 safety_classifier_path: None,
+                safety_classifier_threshold: None,
                 dual_encoder: Some(IndexerDualEncoderConfig {
                     model_path: data_path.to_str().unwrap().to_string(),
                     page_centrality_rank_threshold: None,
@@ -780,15 +1061,21 @@ mod tests {
                     .to_string(),
                 limit_warc_files: None,
                 skip_warc_files: None,
-                warc_source: WarcSource::Local(crate::config::LocalConfig {
+                source: DocumentSource::Warc(WarcSource::Local(crate::config::LocalConfig {
                     folder: temp_dir.as_ref().join("warc").to_str().unwrap().to_string(),
                     names: vec!["".to_string()],
-                }),
+                    date_filter: None,
+                })),
                 host_centrality_threshold: None,
                 minimum_clean_words: None,
+                minimum_content_density: None,
+                simhash_dedup_threshold: None,
+                adservers_path: None,
+                append_to: None,
                 batch_size: 10,
                 autocommit_after_num_inserts:
                     crate::config::defaults::Indexing::autocommit_after_num_inserts(),
+                region_overrides: Default::default(),
             }
             .into(),
         ));
@@ -1038,6 +1325,85 @@ mod tests {
                 .value,
             0.5,
         );
+
+        // repeated query terms only count once
+        let result = searcher
+            .search(&SearchQuery {
+                query: "test test example".to_string(),
+                return_ranking_signals: true,
+                ..Default::default()
+            })
+            .expect("Search failed");
+
+        assert_eq!(result.webpages.len(), 1);
+        assert_eq!(
+            result.webpages[0]
+                .ranking_signals
+                .as_ref()
+                .unwrap()
+                .get(
+                    &crate::ranking::SignalEnum::from(crate::ranking::signals::TitleCoverage)
+                        .into()
+                )
+                .unwrap()
+                .value,
+            0.5,
+        );
+    }
+
+    #[test]
+    fn field_term_boost() {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        for (title, url) in [
+            ("apple apple banana", "https://www.a.com"),
+            ("apple banana banana", "https://www.b.com"),
+        ] {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        &format!(
+                            r#"
+                            <html>
+                                <head>
+                                    <title>{title}</title>
+                                </head>
+                                <body>
+                                    {CONTENT}
+                                </body>
+                            </html>
+                        "#
+                        ),
+                        url,
+                    )
+                    .unwrap(),
+                    host_centrality: 1.0,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().expect("failed to commit index");
+        let searcher = LocalSearcher::from(index);
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "title:apple^5 title:banana".to_string(),
+                ..Default::default()
+            })
+            .expect("Search failed");
+        assert_eq!(result.webpages.len(), 2);
+        assert_eq!(result.webpages[0].url, "https://www.a.com/");
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: "title:apple title:banana^5".to_string(),
+                ..Default::default()
+            })
+            .expect("Search failed");
+        assert_eq!(result.webpages.len(), 2);
+        assert_eq!(result.webpages[0].url, "https://www.b.com/");
     }
 
     #[test]
@@ -14,7 +14,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{path::Path, str::FromStr};
+use std::{path::Path, str::FromStr, sync::Arc};
+
+use arc_swap::ArcSwap;
 
 use crate::{
     enum_map::EnumMap,
@@ -308,6 +310,47 @@ impl LambdaMART {
     }
 }
 
+/// A [`LambdaMART`] model that can be swapped for a new one while it is in use.
+/// Readers take a snapshot of the current model with [`ReloadableLambdaMART::load`],
+/// so in-flight requests keep scoring with the model they started with.
+#[derive(Clone)]
+pub struct ReloadableLambdaMART {
+    model: Arc<ArcSwap<LambdaMART>>,
+}
+
+impl From<LambdaMART> for ReloadableLambdaMART {
+    fn from(model: LambdaMART) -> Self {
+        Self::new(model)
+    }
+}
+
+impl ReloadableLambdaMART {
+    pub fn new(model: LambdaMART) -> Self {
+        Self {
+            model: Arc::new(ArcSwap::from_pointee(model)),
+        }
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(LambdaMART::open(path)?))
+    }
+
+    pub fn load(&self) -> Arc<LambdaMART> {
+        self.model.load_full()
+    }
+
+    pub fn store(&self, model: LambdaMART) {
+        self.model.store(Arc::new(model));
+    }
+
+    /// Parse the model at `path` and atomically replace the current model with it.
+    /// The current model is kept if the new one fails to parse.
+    pub fn reload<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.store(LambdaMART::open(path)?);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ranking;
@@ -383,4 +426,36 @@ mod tests {
 
         assert_eq!((model.predict(&features) * 1000.0) as u64, 1050);
     }
+
+    fn single_split_model(left: f64, right: f64) -> String {
+        format!(
+            "tree\nfeature_names=host_centrality\n\nTree=0\nnum_leaves=2\nsplit_feature=0\nthreshold=0.5\nleft_child=-1\nright_child=-2\nleaf_value={left} {right}\n\nend of trees\n"
+        )
+    }
+
+    #[test]
+    fn reload() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = dir.as_ref().join("lambdamart.txt");
+
+        let mut features = EnumMap::new();
+        features.insert(ranking::signals::HostCentrality.into(), 0.8);
+
+        std::fs::write(&path, single_split_model(0.0, 1.0)).unwrap();
+        let model = ReloadableLambdaMART::open(&path).unwrap();
+
+        let in_flight = model.load();
+        let before = in_flight.predict(&features);
+
+        std::fs::write(&path, single_split_model(0.0, 5.0)).unwrap();
+        model.reload(&path).unwrap();
+
+        let after = model.load().predict(&features);
+        assert!(after > before);
+        assert_eq!(in_flight.predict(&features), before);
+
+        std::fs::write(&path, "tree\nfeature_names=host_centrality\n\n").unwrap();
+        assert!(model.reload(&path).is_err());
+        assert_eq!(model.load().predict(&features), after);
+    }
 }
@@ -3,4 +3,4 @@ pub mod lambdamart;
 pub mod linear;
 
 pub use cross_encoder::CrossEncoder;
-pub use lambdamart::LambdaMART;
+pub use lambdamart::{LambdaMART, ReloadableLambdaMART};
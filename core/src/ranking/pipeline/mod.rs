@@ -5,13 +5,15 @@
 
 use crate::{
     collector::{self},
+    distributed::member::ShardId,
     enum_map::EnumMap,
     searcher::SearchQuery,
 };
 
 use super::{
     models::lambdamart::{self},
-    SignalCalculation, SignalEnum, SignalScore,
+    Explanation, SignalCalculation, SignalCoefficients, SignalContribution, SignalEnum,
+    SignalScore,
 };
 
 mod modifiers;
@@ -19,6 +21,7 @@ mod scorers;
 mod stages;
 
 use modifiers::FullModifier;
+pub use modifiers::HostDiversity;
 pub use scorers::{FullRankingStage, ReRanker};
 pub use stages::{LocalRecallRankingWebpage, PrecisionRankingWebpage, RecallRankingWebpage};
 
@@ -32,9 +35,36 @@ pub trait RankableWebpage: collector::Doc + Send + Sync {
 
     fn as_local_recall(&self) -> &LocalRecallRankingWebpage;
 
+    /// The shard the webpage was found in. Doc addresses are only unique within a shard.
+    fn shard(&self) -> Option<ShardId> {
+        None
+    }
+
     fn score(&self) -> f64 {
         self.boost() * self.unboosted_score()
     }
+
+    /// Break the score down into the contribution of each signal, given the
+    /// coefficients the pipeline used to score the webpage. See [`Explanation`].
+    fn explain(&self, coefficients: &SignalCoefficients) -> Explanation {
+        let signals = SignalEnum::all()
+            .filter_map(|signal| {
+                self.signals()
+                    .get(signal)
+                    .map(|calc| SignalContribution::new(signal, *calc, coefficients.get(&signal)))
+            })
+            .collect::<Vec<_>>();
+
+        let explained: f64 = signals.iter().map(|s| s.contribution).sum();
+
+        Explanation {
+            score: self.score(),
+            unboosted_score: self.unboosted_score(),
+            boost: self.boost(),
+            residual: self.unboosted_score() - explained,
+            signals,
+        }
+    }
 }
 
 impl lambdamart::AsValue for SignalScore {
@@ -143,12 +173,35 @@ where
             stage_or_modifier.rank(webpages);
         }
 
+        let start = match &query.after {
+            Some(cursor) => cursor.start(&webpages),
+            None => query.offset(),
+        };
+
         webpages
             .into_iter()
-            .skip(query.offset())
+            .skip(start)
             .take(query.num_results())
             .collect()
     }
+
+    /// Same as [`RankingPipeline::apply`], but tags each webpage with its final
+    /// 0-based position in the ranking along with the signals used to score it.
+    /// Useful for logging ranking data that can later be joined with clicks.
+    pub fn apply_with_positions(
+        &self,
+        webpages: Vec<T>,
+        query: &SearchQuery,
+    ) -> Vec<(usize, T, EnumMap<SignalEnum, SignalCalculation>)> {
+        self.apply(webpages, query)
+            .into_iter()
+            .enumerate()
+            .map(|(i, webpage)| {
+                let signals = webpage.signals().clone();
+                (query.offset() + i, webpage, signals)
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -161,7 +214,7 @@ mod tests {
         inverted_index::{DocAddress, WebpagePointer},
         prehashed::Prehashed,
         ranking::{self, bitvec_similarity::BitVec, initial::Score},
-        searcher::api,
+        searcher::{api, Cursor},
     };
 
     use super::*;
@@ -173,12 +226,19 @@ mod tests {
     }
 
     fn sample_websites(n: usize) -> Vec<api::ScoredWebpagePointer> {
-        (0..n)
-            .map(|i| -> LocalRecallRankingWebpage {
+        sample_websites_from_sites(&vec![0; n])
+    }
+
+    /// One website per entry in `sites`, where each entry is the site hash of the website.
+    fn sample_websites_from_sites(sites: &[u128]) -> Vec<api::ScoredWebpagePointer> {
+        sites
+            .iter()
+            .enumerate()
+            .map(|(i, site)| -> LocalRecallRankingWebpage {
                 let pointer = WebpagePointer {
                     score: Score { total: 0.0 },
                     hashes: Hashes {
-                        site: Prehashed(0),
+                        site: Prehashed(*site),
                         title: Prehashed(0),
                         url: Prehashed(0),
                         url_without_tld: Prehashed(0),
@@ -236,4 +296,173 @@ mod tests {
 
         assert_eq!(res, expected);
     }
+
+    #[test]
+    fn host_diversity() {
+        let pipeline = pipeline().add_modifier(HostDiversity::new(2, 0.1));
+
+        let res: Vec<_> = pipeline
+            .apply(
+                sample_websites_from_sites(&[0, 0, 0, 1, 1]),
+                &SearchQuery {
+                    page: 0,
+                    num_results: 5,
+                    ..Default::default()
+                },
+            )
+            .into_iter()
+            .map(|w| w.as_ranking().pointer().address.doc_id)
+            .collect();
+
+        // the third result from site 0 is pushed below the results from site 1
+        assert_eq!(res, vec![0, 1, 3, 4, 2]);
+    }
+
+    #[test]
+    fn positions() {
+        let pipeline = pipeline();
+
+        for page in [0, 1] {
+            let query = SearchQuery {
+                page,
+                num_results: 10,
+                ..Default::default()
+            };
+
+            let expected: Vec<_> = pipeline
+                .apply(sample_websites(20), &query)
+                .into_iter()
+                .map(|w| w.as_ranking().pointer().address.doc_id)
+                .collect();
+
+            let res = pipeline.apply_with_positions(sample_websites(20), &query);
+            assert_eq!(res.len(), 10);
+
+            // the sample website with doc id `i` ranks at position `i` and has a host centrality of `i`
+            for ((pos, webpage, signals), doc_id) in res.iter().zip(expected) {
+                assert_eq!(webpage.as_ranking().pointer().address.doc_id, doc_id);
+                assert_eq!(*pos, doc_id as usize);
+                assert_eq!(
+                    signals
+                        .get(ranking::signals::HostCentrality.into())
+                        .map(|calc| calc.value),
+                    Some(doc_id as f64)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn explain_sums_to_unboosted_score() {
+        let pipeline = pipeline();
+        let query = SearchQuery {
+            page: 0,
+            num_results: 20,
+            ..Default::default()
+        };
+
+        // the first sample website has an infinite score
+        let sample = sample_websites(21).into_iter().skip(1).collect();
+        let res = pipeline.apply(sample, &query);
+        assert_eq!(res.len(), 20);
+
+        let coefficients = query.signal_coefficients();
+
+        for webpage in &res {
+            let explanation = webpage.explain(&coefficients);
+
+            let signals: Vec<_> = explanation.signals.iter().map(|s| s.signal).collect();
+            assert!(signals.contains(&ranking::signals::HostCentrality.into()));
+            assert!(signals.contains(&ranking::signals::MinTitleSlop.into()));
+
+            for signal in &explanation.signals {
+                assert_eq!(signal.contribution, signal.score * signal.coefficient);
+            }
+
+            let sum: f64 = explanation.signals.iter().map(|s| s.contribution).sum();
+            assert!((sum - webpage.unboosted_score()).abs() < 1e-9);
+            assert!(explanation.residual.abs() < 1e-9);
+            assert_eq!(explanation.unboosted_score, webpage.unboosted_score());
+            assert_eq!(explanation.score, webpage.score());
+        }
+    }
+
+    #[test]
+    fn cursor_paging() {
+        let pipeline = pipeline().add_modifier(HostDiversity::new(2, 0.1));
+        let sites: Vec<_> = (0..30).map(|i| i % 4).collect();
+
+        let addresses = |res: Vec<api::ScoredWebpagePointer>| -> Vec<_> {
+            res.into_iter()
+                .map(|w| w.as_ranking().pointer().address)
+                .collect()
+        };
+
+        let offset_pages: Vec<_> = (0..3)
+            .map(|page| {
+                addresses(pipeline.apply(
+                    sample_websites_from_sites(&sites),
+                    &SearchQuery {
+                        page,
+                        num_results: 5,
+                        ..Default::default()
+                    },
+                ))
+            })
+            .collect();
+
+        let mut after = None;
+        let mut cursor_pages = Vec::new();
+
+        for page in 0..3 {
+            let res = pipeline.apply(
+                sample_websites_from_sites(&sites),
+                &SearchQuery {
+                    // the page is ignored when a cursor is given
+                    page: 7,
+                    num_results: 5,
+                    after,
+                    ..Default::default()
+                },
+            );
+
+            // the cursor is passed through the api as an opaque string
+            let cursor = Cursor::new(res.last().unwrap(), (page + 1) * 5).to_string();
+            after = Some(cursor.parse().unwrap());
+
+            cursor_pages.push(addresses(res));
+        }
+
+        assert_eq!(cursor_pages, offset_pages);
+
+        let all = cursor_pages.concat();
+        assert_eq!(all.len(), 15);
+        for (i, address) in all.iter().enumerate() {
+            assert!(!all[i + 1..].contains(address));
+        }
+    }
+
+    #[test]
+    fn cursor_shard() {
+        // the same doc address in two different shards
+        let webpages: Vec<_> = (0..2)
+            .map(|shard| {
+                let mut webpage = sample_websites(2).pop().unwrap();
+                if let api::ScoredWebpagePointer::Normal(pointer) = &mut webpage {
+                    pointer.shard = ShardId::new(shard);
+                }
+                webpage
+            })
+            .collect();
+
+        assert_eq!(Cursor::new(&webpages[0], 1).start(&webpages), 1);
+        assert_eq!(Cursor::new(&webpages[1], 2).start(&webpages), 2);
+
+        let query = SearchQuery {
+            page: 7,
+            after: Some(Cursor::new(&webpages[1], 2)),
+            ..Default::default()
+        };
+        assert_eq!(query.offset(), 2);
+    }
 }
@@ -0,0 +1,54 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+use std::{collections::HashMap, marker::PhantomData};
+
+use crate::{collector::Doc, prehashed::Prehashed, ranking::pipeline::RankableWebpage};
+
+use super::FullModifier;
+
+/// Demotes results from a site once it already has `max_per_site`
+/// higher ranked results, so a single site doesn't dominate the page.
+pub struct HostDiversity<T> {
+    max_per_site: usize,
+    decay: f64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> HostDiversity<T> {
+    /// Results beyond the first `max_per_site` from the same site
+    /// get their boost multiplied by `decay`.
+    pub fn new(max_per_site: usize, decay: f64) -> Self {
+        Self {
+            max_per_site,
+            decay,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> FullModifier for HostDiversity<T>
+where
+    T: RankableWebpage,
+{
+    type Webpage = T;
+
+    fn update_boosts(&self, webpages: &mut [Self::Webpage]) {
+        let mut order: Vec<_> = (0..webpages.len()).collect();
+        order.sort_by(|a, b| webpages[*b].score().total_cmp(&webpages[*a].score()));
+
+        let mut seen: HashMap<Prehashed, usize> = HashMap::new();
+
+        for i in order {
+            let count = seen.entry(webpages[i].hashes().site).or_default();
+            *count += 1;
+
+            if *count > self.max_per_site {
+                let cur_boost = webpages[i].boost();
+                webpages[i].set_boost(cur_boost * self.decay);
+            }
+        }
+    }
+}
@@ -3,9 +3,11 @@
 //
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
+mod host_diversity;
 mod inbound_similarity;
 
 use super::{RankableWebpage, Top};
+pub use host_diversity::HostDiversity;
 pub use inbound_similarity::InboundSimilarity;
 
 pub trait FullModifier: Send + Sync {
@@ -20,6 +20,12 @@ use crate::{
 
 use super::FullRankingStage;
 
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("embeddings have different dimensions: {left} and {right}")]
+    DimensionMismatch { left: usize, right: usize },
+}
+
 pub struct Embedding(Tensor);
 
 impl Embedding {
@@ -41,6 +47,37 @@ impl Embedding {
             .to_vec0()?)
     }
 
+    /// Returns 0 if either embedding is all zeros.
+    pub fn cosine(&self, other: &Self) -> Result<f64> {
+        if self.size() != other.size() {
+            return Err(Error::DimensionMismatch {
+                left: self.size(),
+                right: other.size(),
+            }
+            .into());
+        }
+
+        let a: Vec<f32> = self.0.to_dtype(candle_core::DType::F32)?.to_vec1()?;
+        let b: Vec<f32> = other.0.to_dtype(candle_core::DType::F32)?.to_vec1()?;
+
+        let mut dot = 0.0;
+        let mut norm_a = 0.0;
+        let mut norm_b = 0.0;
+
+        for (a, b) in a.into_iter().zip(b) {
+            let (a, b) = (a as f64, b as f64);
+            dot += a * b;
+            norm_a += a * a;
+            norm_b += b * b;
+        }
+
+        if norm_a == 0.0 || norm_b == 0.0 {
+            return Ok(0.0);
+        }
+
+        Ok(dot / (norm_a.sqrt() * norm_b.sqrt()))
+    }
+
     pub fn size(&self) -> usize {
         self.0.dims1().unwrap()
     }
@@ -57,6 +94,22 @@ impl Embedding {
 
         Ok(Self(tensor))
     }
+
+    /// Infer the dimension from the size of the stored embedding.
+    fn from_stored(stored: &StoredEmbeddings) -> Result<Self> {
+        Self::new(
+            stored,
+            stored.as_slice().len() / candle_core::DType::BF16.size_in_bytes(),
+        )
+    }
+
+    fn from_f32(values: &[f32]) -> Result<Self> {
+        Ok(Self(Tensor::from_slice(
+            values,
+            values.len(),
+            &candle_core::Device::Cpu,
+        )?))
+    }
 }
 
 pub struct EmbeddingScorer<W, E: EmbeddingSignal<W>> {
@@ -125,6 +178,61 @@ impl<E: EmbeddingSignal<ScoredWebpagePointer>> FullRankingStage
     }
 }
 
+/// Scores the results by the cosine similarity between an embedding supplied with the
+/// query and the stored title embedding of each result, falling back to the keyword
+/// embedding for pages without a title embedding. Results without any stored embedding
+/// get a neutral score.
+pub struct QueryEmbeddingScorer {
+    query_emb: Option<Embedding>,
+}
+
+impl QueryEmbeddingScorer {
+    pub fn new(query_embedding: Option<&[f32]>) -> Self {
+        Self {
+            query_emb: query_embedding.and_then(|values| Embedding::from_f32(values).ok()),
+        }
+    }
+
+    /// Fails if the stored embedding has a different dimension than the query embedding.
+    pub fn similarity(&self, webpage: &ScoredWebpagePointer) -> Result<f64> {
+        let Some(query_emb) = &self.query_emb else {
+            return Ok(0.0);
+        };
+
+        let ranking = webpage.as_ranking();
+        let Some(stored) = ranking
+            .title_embedding()
+            .or_else(|| ranking.keyword_embedding())
+        else {
+            return Ok(0.0);
+        };
+
+        query_emb.cosine(&Embedding::from_stored(stored)?)
+    }
+}
+
+impl FullRankingStage for QueryEmbeddingScorer {
+    type Webpage = ScoredWebpagePointer;
+
+    fn compute(&self, webpages: &mut [Self::Webpage]) {
+        if self.query_emb.is_none() {
+            return;
+        }
+
+        for webpage in webpages.iter_mut() {
+            let sim = self.similarity(webpage).unwrap_or_else(|err| {
+                tracing::warn!("failed to compare query embedding: {err}");
+                0.0
+            });
+
+            webpage.as_ranking_mut().signals_mut().insert(
+                ranking::signals::QueryEmbeddingSimilarity.into(),
+                ranking::SignalCalculation::new_symmetrical(sim),
+            );
+        }
+    }
+}
+
 pub struct TitleEmbeddings;
 pub struct KeywordEmbeddings;
 
@@ -181,8 +289,90 @@ impl EmbeddingSignal<ScoredWebpagePointer> for KeywordEmbeddings {
 
 #[cfg(test)]
 mod tests {
+    use crate::{
+        ampc::dht::ShardId,
+        collector::Hashes,
+        enum_map::EnumMap,
+        inverted_index::{DocAddress, WebpagePointer},
+        prehashed::Prehashed,
+        ranking::{
+            bitvec_similarity::BitVec,
+            initial::Score,
+            pipeline::{LocalRecallRankingWebpage, RecallRankingWebpage},
+        },
+    };
+
     use super::*;
 
+    fn webpage(doc_id: u32, title_embedding: Option<&[f32]>) -> ScoredWebpagePointer {
+        let pointer = WebpagePointer {
+            score: Score { total: 0.0 },
+            hashes: Hashes {
+                site: Prehashed(0),
+                title: Prehashed(0),
+                url: Prehashed(0),
+                url_without_tld: Prehashed(0),
+                simhash: 0,
+            },
+            address: DocAddress { segment: 0, doc_id },
+        };
+
+        let mut local = LocalRecallRankingWebpage::new_testing(pointer, EnumMap::new(), 0.0);
+
+        if let Some(emb) = title_embedding {
+            local = local.with_title_embedding(StoredEmbeddings::from_f32(emb));
+        }
+
+        ScoredWebpagePointer::Normal(crate::searcher::distributed::ScoredWebpagePointer {
+            website: RecallRankingWebpage::new(local, BitVec::new(vec![])),
+            shard: ShardId::new(0),
+        })
+    }
+
+    fn query_similarity(webpage: &ScoredWebpagePointer) -> Option<f64> {
+        webpage
+            .signals()
+            .get(ranking::signals::QueryEmbeddingSimilarity.into())
+            .map(|calc| calc.score)
+    }
+
+    #[test]
+    fn query_embedding_similarity() {
+        let mut webpages = vec![
+            webpage(0, Some(&[1.0, 0.0, 0.0])),
+            webpage(1, Some(&[0.0, 1.0, 0.0])),
+            webpage(2, None),
+        ];
+
+        let scorer = QueryEmbeddingScorer::new(Some(&[0.25, 1.0, 0.0]));
+        FullRankingStage::compute(&scorer, &mut webpages);
+
+        let far = query_similarity(&webpages[0]).unwrap();
+        let close = query_similarity(&webpages[1]).unwrap();
+        let missing = query_similarity(&webpages[2]).unwrap();
+
+        assert!(close > far);
+        assert!(far > 0.0);
+        assert!(close < 1.0);
+        assert_eq!(missing, 0.0);
+
+        let mut webpages = vec![webpage(0, Some(&[1.0, 0.0, 0.0]))];
+        FullRankingStage::compute(&QueryEmbeddingScorer::new(None), &mut webpages);
+        assert_eq!(query_similarity(&webpages[0]), None);
+    }
+
+    #[test]
+    fn query_embedding_dimension_mismatch() {
+        let scorer = QueryEmbeddingScorer::new(Some(&[1.0, 0.0]));
+        let webpage = webpage(0, Some(&[1.0, 0.0, 0.0]));
+
+        let err = scorer.similarity(&webpage).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::DimensionMismatch { left: 2, right: 3 })
+        ));
+    }
+
     #[test]
     fn embedding_dot() {
         let emb1 = Embedding(
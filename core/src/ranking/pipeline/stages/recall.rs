@@ -17,7 +17,9 @@ use crate::{
         pipeline::{
             modifiers,
             scorers::{
-                embedding::{EmbeddingScorer, KeywordEmbeddings, TitleEmbeddings},
+                embedding::{
+                    EmbeddingScorer, KeywordEmbeddings, QueryEmbeddingScorer, TitleEmbeddings,
+                },
                 inbound_similarity::InboundScorer,
                 term_distance,
             },
@@ -37,6 +39,17 @@ impl StoredEmbeddings {
     pub fn as_slice(&self) -> &[u8] {
         &self.0
     }
+
+    /// Store the values as bf16 by truncating the lower half of the f32 bits.
+    #[cfg(test)]
+    pub fn from_f32(values: &[f32]) -> Self {
+        Self(
+            values
+                .iter()
+                .flat_map(|v| ((v.to_bits() >> 16) as u16).to_le_bytes())
+                .collect(),
+        )
+    }
 }
 #[derive(Clone, Debug, bincode::Encode, bincode::Decode)]
 pub struct RecallRankingWebpage {
@@ -146,6 +159,12 @@ impl LocalRecallRankingWebpage {
         }
     }
 
+    #[cfg(test)]
+    pub fn with_title_embedding(mut self, title_embedding: StoredEmbeddings) -> Self {
+        self.title_embedding = Some(title_embedding);
+        self
+    }
+
     /// The ranking webpages needs to be constructed in order
     /// of ascending doc_id as they traverse the posting lists from
     /// the index to calculate bm25.
@@ -304,6 +323,7 @@ impl RankingPipeline<api::ScoredWebpagePointer> {
                 api::ScoredWebpagePointer,
                 KeywordEmbeddings,
             >::new(query.text().to_string(), dual_encoder))
+            .add_stage(QueryEmbeddingScorer::new(query.query_embedding.as_deref()))
             .add_stage(InboundScorer::new(inbound))
             .add_modifier(modifiers::InboundSimilarity);
 
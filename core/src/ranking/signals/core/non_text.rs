@@ -19,7 +19,7 @@ use tantivy::DocId;
 use crate::ranking::{CoreSignal, SignalCalculation, SignalComputer};
 use crate::{
     schema::{self, Field},
-    webpage::Webpage,
+    webpage::{safety_classifier, GeoPoint, Webpage},
 };
 
 fn score_timestamp(page_timestamp: usize, signal_computer: &SignalComputer) -> f64 {
@@ -46,6 +46,15 @@ pub fn time_cache_calculation(hours_since_update: f64) -> f64 {
     HALF_LIFE / (hours_since_update + HALF_LIFE)
 }
 
+/// Exponentially decays the score of a page by the time since it was inserted
+/// into the index, halving it every `freshness_half_life_hours`.
+fn score_freshness(inserted_timestamp: usize, signal_computer: &SignalComputer) -> f64 {
+    let current_timestamp = signal_computer.current_timestamp().unwrap_or(0);
+    let hours_since_insert = current_timestamp.saturating_sub(inserted_timestamp) as f64 / 3600.0;
+
+    0.5f64.powf(hours_since_insert / signal_computer.freshness_half_life_hours())
+}
+
 #[inline]
 fn score_rank(rank: f64) -> f64 {
     // 10 groups with log base 8 gives us
@@ -102,6 +111,21 @@ fn score_region(webpage_region: crate::webpage::Region, computer: &SignalCompute
     }
 }
 
+/// Distance in kilometers where a page gets the same score
+/// as a page without coordinates.
+const GEO_DISTANCE_SCALE_KM: f64 = 50.0;
+
+/// Pages close to the query point score close to 1, pages far away close to 0.
+/// Pages without coordinates get a neutral score, and no page is scored
+/// if the query has no point.
+fn score_geo_distance(page: Option<GeoPoint>, query: Option<GeoPoint>) -> f64 {
+    match (page, query) {
+        (_, None) => 0.0,
+        (None, Some(_)) => 0.5,
+        (Some(page), Some(query)) => 1.0 / (1.0 + page.distance_km(&query) / GEO_DISTANCE_SCALE_KM),
+    }
+}
+
 #[derive(
     Debug,
     Clone,
@@ -444,6 +468,77 @@ impl CoreSignal for UpdateTimestamp {
     }
 }
 
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+pub struct Freshness;
+impl CoreSignal for Freshness {
+    // the score is in [0, 1] and decays with age like `UpdateTimestamp`,
+    // so the two recency signals are weighted the same
+    fn default_coefficient(&self) -> f64 {
+        0.5
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Numerical(
+            schema::numerical_field::PublishedDate.into(),
+        ))
+    }
+
+    fn precompute(
+        self,
+        webpage: &Webpage,
+        signal_computer: &SignalComputer,
+    ) -> Option<SignalCalculation> {
+        let timestamp = webpage
+            .html
+            .published_date()
+            .unwrap_or(webpage.inserted_at)
+            .timestamp()
+            .max(0) as usize;
+        let score = score_freshness(timestamp, signal_computer);
+
+        Some(SignalCalculation {
+            value: timestamp as f64,
+            score,
+        })
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> SignalCalculation {
+        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let numericalfield_reader = seg_reader.numericalfield_reader().get_field_reader(doc);
+
+        // pages without a publication date are indexed with 0,
+        // so fall back to when the page was inserted
+        let val = numericalfield_reader
+            .get(self.as_numericalfield().unwrap())
+            .and_then(|v| v.as_u64())
+            .filter(|published| *published > 0)
+            .or_else(|| {
+                numericalfield_reader
+                    .get(schema::numerical_field::InsertedAt.into())
+                    .and_then(|v| v.as_u64())
+            })
+            .unwrap() as usize;
+
+        let score = score_freshness(val, signal_computer);
+
+        SignalCalculation {
+            value: val as f64,
+            score,
+        }
+    }
+}
+
 #[derive(
     Debug,
     Clone,
@@ -762,3 +857,188 @@ impl CoreSignal for HasAds {
         SignalCalculation { value, score }
     }
 }
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+pub struct IsHttps;
+impl CoreSignal for IsHttps {
+    fn default_coefficient(&self) -> f64 {
+        0.01
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Numerical(schema::numerical_field::IsHttps.into()))
+    }
+
+    fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<SignalCalculation> {
+        Some(SignalCalculation::new_symmetrical(
+            webpage.html.is_https().into(),
+        ))
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> SignalCalculation {
+        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let numericalfield_reader = seg_reader.numericalfield_reader().get_field_reader(doc);
+
+        let val = numericalfield_reader
+            .get(self.as_numericalfield().unwrap())
+            .and_then(|v| v.as_bool())
+            .unwrap();
+
+        if val {
+            SignalCalculation::new_symmetrical(1.0)
+        } else {
+            SignalCalculation::new_symmetrical(0.0)
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+pub struct GeoDistance;
+impl CoreSignal for GeoDistance {
+    fn default_coefficient(&self) -> f64 {
+        1.0
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Numerical(
+            schema::numerical_field::GeoCoordinates.into(),
+        ))
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> SignalCalculation {
+        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let numericalfield_reader = seg_reader.numericalfield_reader().get_field_reader(doc);
+
+        let page = numericalfield_reader
+            .get(self.as_numericalfield().unwrap())
+            .and_then(|v| v.as_u64())
+            .and_then(GeoPoint::unpack);
+        let query = signal_computer.query_data().and_then(|q| q.geo_point());
+
+        let value = match (page, query) {
+            (Some(page), Some(query)) => page.distance_km(&query),
+            _ => -1.0,
+        };
+
+        SignalCalculation {
+            value,
+            score: score_geo_distance(page, query),
+        }
+    }
+}
+
+/// Downranks pages that the safety classifier could not confidently label.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+pub struct SafetyUnknown;
+impl CoreSignal for SafetyUnknown {
+    fn default_coefficient(&self) -> f64 {
+        0.01
+    }
+
+    fn as_field(&self) -> Option<Field> {
+        Some(Field::Numerical(
+            schema::numerical_field::SafetyUnknown.into(),
+        ))
+    }
+
+    fn precompute(self, webpage: &Webpage, _: &SignalComputer) -> Option<SignalCalculation> {
+        let unknown = webpage.safety_classification == Some(safety_classifier::Label::Unknown);
+        let value: f64 = unknown.into();
+        let score = if !unknown { 1.0 } else { 0.0 };
+
+        Some(SignalCalculation { value, score })
+    }
+
+    fn compute(&self, doc: DocId, signal_computer: &SignalComputer) -> SignalCalculation {
+        let seg_reader = signal_computer.segment_reader().unwrap().borrow_mut();
+        let numericalfield_reader = seg_reader.numericalfield_reader().get_field_reader(doc);
+
+        let unknown = numericalfield_reader
+            .get(self.as_numericalfield().unwrap())
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let value: f64 = unknown.into();
+        let score = if !unknown { 1.0 } else { 0.0 };
+
+        SignalCalculation { value, score }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freshness_decays_with_age() {
+        let mut computer = SignalComputer::new(None);
+        computer.set_current_timestamp(1_000_000_000);
+        computer.set_freshness_half_life_hours(24.0);
+
+        let now = score_freshness(1_000_000_000, &computer);
+        let day_old = score_freshness(1_000_000_000 - 24 * 3600, &computer);
+        let week_old = score_freshness(1_000_000_000 - 7 * 24 * 3600, &computer);
+
+        assert_eq!(now, 1.0);
+        assert!((day_old - 0.5).abs() < 1e-9);
+        assert!(week_old < day_old);
+
+        // pages inserted after the current timestamp are treated as brand new
+        assert_eq!(score_freshness(1_000_000_001, &computer), 1.0);
+    }
+
+    #[test]
+    fn geo_distance_scoring() {
+        let copenhagen = GeoPoint::new(55.6761, 12.5683).unwrap();
+        let aarhus = GeoPoint::new(56.1629, 10.2039).unwrap();
+        let sydney = GeoPoint::new(-33.8688, 151.2093).unwrap();
+
+        assert_eq!(score_geo_distance(Some(copenhagen), Some(copenhagen)), 1.0);
+
+        let near = score_geo_distance(Some(aarhus), Some(copenhagen));
+        let far = score_geo_distance(Some(sydney), Some(copenhagen));
+        let unknown = score_geo_distance(None, Some(copenhagen));
+
+        assert!(near > far);
+        assert!(near < 1.0);
+        assert!(far > 0.0);
+        assert!(far < unknown && unknown < near);
+
+        // without a query point the signal doesn't affect the ranking
+        assert_eq!(score_geo_distance(Some(copenhagen), None), 0.0);
+        assert_eq!(score_geo_distance(None, None), 0.0);
+    }
+}
@@ -152,6 +152,11 @@ pub enum SignalEnum {
     HasAds,
     MinTitleSlop,
     MinCleanBodySlop,
+    IsHttps,
+    GeoDistance,
+    SafetyUnknown,
+    Freshness,
+    QueryEmbeddingSimilarity,
 }
 
 #[enum_dispatch(CoreSignal)]
@@ -217,6 +222,10 @@ pub enum CoreSignalEnum {
     UrlSlashes,
     LinkDensity,
     HasAds,
+    IsHttps,
+    GeoDistance,
+    SafetyUnknown,
+    Freshness,
 }
 
 // Note to future self: Tried to get the num definitions
@@ -288,6 +297,10 @@ register_signals! {
         UrlSlashes,
         LinkDensity,
         HasAds,
+        IsHttps,
+        GeoDistance,
+        SafetyUnknown,
+        Freshness,
     ],
     rest=[
         QueryCentrality,
@@ -299,6 +312,7 @@ register_signals! {
         CrossEncoderTitle,
         TitleEmbeddingSimilarity,
         KeywordEmbeddingSimilarity,
+        QueryEmbeddingSimilarity,
     ]
 }
 
@@ -409,6 +423,60 @@ pub struct SignalScore {
     pub value: f64,
 }
 
+/// How much a single signal contributed to the score of a result.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+    ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalContribution {
+    pub signal: SignalEnumDiscriminants,
+    /// The raw value of the signal before it was turned into a score.
+    pub value: f64,
+    pub score: f64,
+    pub coefficient: f64,
+    /// `score * coefficient`.
+    pub contribution: f64,
+}
+
+impl SignalContribution {
+    pub fn new(signal: SignalEnum, calc: SignalCalculation, coefficient: f64) -> Self {
+        Self {
+            signal: signal.into(),
+            value: calc.value,
+            score: calc.score,
+            coefficient,
+            contribution: calc.score * coefficient,
+        }
+    }
+}
+
+/// Breakdown of the final score of a result into its signals, with `score = boost * unboosted_score`.
+///
+/// Each contribution is the linear term `score * coefficient` of a signal. Model based
+/// signals (e.g. lambdamart or the cross encoder) only show up as their output, so their
+/// contribution can't be broken down further. The contributions only sum to `unboosted_score`
+/// when the result was scored by a linear stage with the same coefficients, and whatever
+/// is left is reported as `residual`.
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct Explanation {
+    pub score: f64,
+    pub unboosted_score: f64,
+    pub boost: f64,
+    pub signals: Vec<SignalContribution>,
+    /// The part of `unboosted_score` that is not explained by `signals`.
+    pub residual: f64,
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone)]
 pub struct SignalCoefficients {
     map: EnumMap<SignalEnum, f64>,
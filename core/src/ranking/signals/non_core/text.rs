@@ -129,3 +129,22 @@ impl Signal for KeywordEmbeddingSimilarity {
         0.01
     }
 }
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+pub struct QueryEmbeddingSimilarity;
+impl Signal for QueryEmbeddingSimilarity {
+    fn default_coefficient(&self) -> f64 {
+        0.1
+    }
+}
@@ -27,7 +27,7 @@ use crate::{
     enum_dispatch_from_discriminant,
     enum_map::InsertEnumMapKey,
     simhash,
-    webpage::{html::FnCache, url_ext::UrlExt, Html, Webpage},
+    webpage::{html::FnCache, safety_classifier, url_ext::UrlExt, GeoPoint, Html, Webpage},
     Result,
 };
 
@@ -77,6 +77,11 @@ pub trait NumericalField:
         true
     }
 
+    /// Whether the field can be filtered on from the query (e.g. `fetch_time_ms:1000..2000`).
+    fn is_range_queryable(&self) -> bool {
+        false
+    }
+
     fn indexing_option(&self) -> IndexingOption {
         let orientation = self.orientation();
         match self.data_type() {
@@ -172,6 +177,11 @@ pub enum NumericalFieldEnum {
     TitleEmbeddings,
     KeywordEmbeddings,
     SuffixId,
+    IsHttps,
+    GeoCoordinates,
+    SafetyUnknown,
+    PublishedDate,
+    InsertedAt,
 }
 
 enum_dispatch_from_discriminant!(NumericalFieldEnumDiscriminants => NumericalFieldEnum,
@@ -216,6 +226,11 @@ enum_dispatch_from_discriminant!(NumericalFieldEnumDiscriminants => NumericalFie
     TitleEmbeddings,
     KeywordEmbeddings,
     SuffixId,
+    IsHttps,
+    GeoCoordinates,
+    SafetyUnknown,
+    PublishedDate,
+    InsertedAt,
 ]);
 
 impl NumericalFieldEnum {
@@ -431,6 +446,10 @@ impl NumericalField for FetchTimeMs {
     fn name(&self) -> &str {
         "fetch_time_ms"
     }
+
+    fn is_range_queryable(&self) -> bool {
+        true
+    }
     fn add_html_tantivy(
         &self,
         _html: &Html,
@@ -467,6 +486,10 @@ impl NumericalField for LastUpdated {
         "last_updated"
     }
 
+    fn is_range_queryable(&self) -> bool {
+        true
+    }
+
     fn is_stored(&self) -> bool {
         true
     }
@@ -492,6 +515,76 @@ impl NumericalField for LastUpdated {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublishedDate;
+impl NumericalField for PublishedDate {
+    fn name(&self) -> &str {
+        "published_date"
+    }
+
+    fn is_range_queryable(&self) -> bool {
+        true
+    }
+
+    fn add_html_tantivy(
+        &self,
+        html: &Html,
+        _cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        index: &crate::inverted_index::InvertedIndex,
+    ) -> Result<()> {
+        doc.add_u64(
+            self.tantivy_field(index.schema_ref()),
+            html.published_date()
+                .map_or(0, |time| time.timestamp().max(0) as u64),
+        );
+
+        Ok(())
+    }
+}
+
+/// When the page was crawled in seconds since the unix epoch. Like the other
+/// timestamps it can be filtered on with dates (e.g. `inserted_at:2024-01-01..`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InsertedAt;
+impl NumericalField for InsertedAt {
+    fn name(&self) -> &str {
+        "inserted_at"
+    }
+
+    fn is_range_queryable(&self) -> bool {
+        true
+    }
+
+    fn add_html_tantivy(
+        &self,
+        _html: &Html,
+        _cache: &mut FnCache,
+        _doc: &mut TantivyDocument,
+        _index: &crate::inverted_index::InvertedIndex,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn add_webpage_tantivy(
+        &self,
+        webpage: &Webpage,
+        doc: &mut TantivyDocument,
+        index: &crate::inverted_index::InvertedIndex,
+    ) -> Result<()> {
+        doc.add_u64(
+            self.tantivy_field(index.schema_ref()),
+            webpage.inserted_at.timestamp().max(0) as u64,
+        );
+
+        Ok(())
+    }
+
+    fn orientation(&self) -> Orientation {
+        Orientation::ROW
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TrackerScore;
 impl NumericalField for TrackerScore {
@@ -1430,3 +1523,99 @@ impl NumericalField for SuffixId {
         Ok(())
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IsHttps;
+impl NumericalField for IsHttps {
+    fn name(&self) -> &str {
+        "is_https"
+    }
+
+    fn data_type(&self) -> DataType {
+        DataType::Bool
+    }
+
+    fn add_html_tantivy(
+        &self,
+        html: &Html,
+        _cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        index: &crate::inverted_index::InvertedIndex,
+    ) -> Result<()> {
+        doc.add_bool(self.tantivy_field(index.schema_ref()), html.is_https());
+
+        Ok(())
+    }
+}
+
+/// The schema.org coordinates of the page packed by [`GeoPoint::pack`],
+/// or [`GeoPoint::NONE_PACKED`] if the page has none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GeoCoordinates;
+impl NumericalField for GeoCoordinates {
+    fn name(&self) -> &str {
+        "geo_coordinates"
+    }
+
+    fn add_html_tantivy(
+        &self,
+        _html: &Html,
+        cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        index: &crate::inverted_index::InvertedIndex,
+    ) -> Result<()> {
+        doc.add_u64(
+            self.tantivy_field(index.schema_ref()),
+            cache
+                .geo_point()
+                .map_or(GeoPoint::NONE_PACKED, |point| point.pack()),
+        );
+
+        Ok(())
+    }
+
+    fn orientation(&self) -> Orientation {
+        Orientation::ROW
+    }
+}
+
+/// Whether the safety classifier was not confident enough to label the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SafetyUnknown;
+impl NumericalField for SafetyUnknown {
+    fn name(&self) -> &str {
+        "safety_unknown"
+    }
+
+    fn data_type(&self) -> DataType {
+        DataType::Bool
+    }
+
+    fn add_html_tantivy(
+        &self,
+        _html: &Html,
+        _cache: &mut FnCache,
+        _doc: &mut TantivyDocument,
+        _index: &crate::inverted_index::InvertedIndex,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn add_webpage_tantivy(
+        &self,
+        webpage: &Webpage,
+        doc: &mut TantivyDocument,
+        index: &crate::inverted_index::InvertedIndex,
+    ) -> Result<()> {
+        doc.add_bool(
+            self.tantivy_field(index.schema_ref()),
+            webpage.safety_classification == Some(safety_classifier::Label::Unknown),
+        );
+
+        Ok(())
+    }
+
+    fn orientation(&self) -> Orientation {
+        Orientation::ROW
+    }
+}
@@ -21,11 +21,11 @@ use crate::{
     tokenizer::{
         self,
         fields::{
-            BigramTokenizer, FieldTokenizer, Identity, JsonField, NewlineTokenizer,
+            BigramTokenizer, FieldTokenizer, Identity, JsonField, NewlineTokenizer, StopwordPolicy,
             TrigramTokenizer, UrlTokenizer, WordTokenizer,
         },
     },
-    webpage::Html,
+    webpage::{schema_org, Html},
     Result,
 };
 
@@ -61,8 +61,22 @@ pub trait TextField:
         FieldTokenizer::default()
     }
 
-    fn query_tokenizer(&self, lang: Option<&whatlang::Lang>) -> FieldTokenizer {
+    /// Whether the stopwords of the language are removed from the field.
+    /// Fields that are matched as exact phrases should keep them.
+    /// Changing the policy changes the tokenizer name, so existing indexes must be rebuilt.
+    fn stopword_policy(&self) -> StopwordPolicy {
+        StopwordPolicy::Keep
+    }
+
+    /// The tokenizer of the field with its [`StopwordPolicy`] applied.
+    /// This is the tokenizer the field is indexed with.
+    fn field_tokenizer(&self, lang: Option<&whatlang::Lang>) -> FieldTokenizer {
         self.tokenizer(lang)
+            .with_stopword_policy(self.stopword_policy(), lang)
+    }
+
+    fn query_tokenizer(&self, lang: Option<&whatlang::Lang>) -> FieldTokenizer {
+        self.field_tokenizer(lang)
     }
 
     fn ngram_size(&self) -> usize {
@@ -119,7 +133,7 @@ pub trait TextField:
     }
 
     fn indexing_option(&self) -> IndexingOption {
-        let tokenizer = self.tokenizer(None);
+        let tokenizer = self.field_tokenizer(None);
         let option = self.record_option();
 
         let mut opt = TextOptions::default().set_indexing_options(
@@ -176,6 +190,8 @@ pub enum TextFieldEnum {
     DmozDescription,
     SchemaOrgJson,
     FlattenedSchemaOrgJson,
+    SchemaOrgPaths,
+    Language,
     CleanBodyBigrams,
     TitleBigrams,
     CleanBodyTrigrams,
@@ -201,6 +217,8 @@ pub enum TextFieldEnum {
     FirstH1,
     AllH2,
     AllH3,
+    Favicon,
+    OpenGraphJson,
 }
 
 enum_dispatch_from_discriminant!(TextFieldEnumDiscriminants => TextFieldEnum,
@@ -228,6 +246,8 @@ enum_dispatch_from_discriminant!(TextFieldEnumDiscriminants => TextFieldEnum,
     DmozDescription,
     SchemaOrgJson,
     FlattenedSchemaOrgJson,
+    SchemaOrgPaths,
+    Language,
     CleanBodyBigrams,
     TitleBigrams,
     CleanBodyTrigrams,
@@ -252,6 +272,8 @@ enum_dispatch_from_discriminant!(TextFieldEnumDiscriminants => TextFieldEnum,
     FirstH1,
     AllH2,
     AllH3,
+    Favicon,
+    OpenGraphJson,
 ]);
 
 impl TextFieldEnum {
@@ -280,38 +302,8 @@ impl InsertEnumMapKey for TextFieldEnum {
     }
 }
 
-fn stemmer_from_lang(lang: &Lang) -> rust_stemmers::Stemmer {
-    match lang {
-        Lang::Ara => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Arabic),
-        Lang::Dan => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Danish),
-        Lang::Nld => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Dutch),
-        Lang::Fin => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Finnish),
-        Lang::Fra => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::French),
-        Lang::Deu => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::German),
-        Lang::Ell => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Greek),
-        Lang::Hun => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Hungarian),
-        Lang::Ita => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Italian),
-        Lang::Por => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Portuguese),
-        Lang::Ron => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Romanian),
-        Lang::Rus => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Russian),
-        Lang::Spa => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Spanish),
-        Lang::Swe => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Swedish),
-        Lang::Tam => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Tamil),
-        Lang::Tur => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::Turkish),
-        _ => rust_stemmers::Stemmer::create(rust_stemmers::Algorithm::English),
-    }
-}
-
 fn stem_tokens(tokens: &mut [tantivy::tokenizer::Token], lang: Lang) {
-    let stemmer = stemmer_from_lang(&lang);
-    for token in tokens {
-        // TODO remove allocation
-        if let Ok(stemmed_str) = std::panic::catch_unwind(|| stemmer.stem(&token.text).into_owned())
-        {
-            token.text.clear();
-            token.text.push_str(&stemmed_str);
-        }
-    }
+    tokenizer::stemmer::Stemmer::from(lang).stem_tokens(tokens);
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -369,6 +361,10 @@ impl TextField for CleanBody {
         true
     }
 
+    fn stopword_policy(&self) -> StopwordPolicy {
+        StopwordPolicy::Remove
+    }
+
     fn is_searchable(&self) -> bool {
         true
     }
@@ -569,7 +565,7 @@ impl TextField for UrlNoTokenizer {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Identity(Identity {})
+        FieldTokenizer::Identity(Identity::default())
     }
 
     fn query_tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
@@ -620,7 +616,7 @@ impl TextField for UrlForSiteOperator {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Url(UrlTokenizer)
+        FieldTokenizer::Url(UrlTokenizer::default())
     }
 
     fn add_html_tantivy(
@@ -703,7 +699,7 @@ impl TextField for SiteNoTokenizer {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Identity(Identity {})
+        FieldTokenizer::Identity(Identity::default())
     }
 
     fn query_tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
@@ -750,7 +746,7 @@ impl TextField for DomainNoTokenizer {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Identity(Identity {})
+        FieldTokenizer::Identity(Identity::default())
     }
 
     fn query_tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
@@ -797,7 +793,7 @@ impl TextField for DomainNameNoTokenizer {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Identity(Identity {})
+        FieldTokenizer::Identity(Identity::default())
     }
 
     fn query_tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
@@ -844,7 +840,7 @@ impl TextField for SiteIfHomepageNoTokenizer {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Identity(Identity {})
+        FieldTokenizer::Identity(Identity::default())
     }
 
     fn query_tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
@@ -928,7 +924,7 @@ impl TextField for DomainNameIfHomepageNoTokenizer {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Identity(Identity {})
+        FieldTokenizer::Identity(Identity::default())
     }
 
     fn query_tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
@@ -979,7 +975,7 @@ impl TextField for DomainIfHomepageNoTokenizer {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Identity(Identity {})
+        FieldTokenizer::Identity(Identity::default())
     }
 
     fn query_tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
@@ -1190,7 +1186,7 @@ impl TextField for SchemaOrgJson {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Identity(Identity {})
+        FieldTokenizer::Identity(Identity::default())
     }
 
     fn is_stored(&self) -> bool {
@@ -1246,6 +1242,77 @@ impl TextField for FlattenedSchemaOrgJson {
     }
 }
 
+/// The dotted schema.org paths of the page, e.g. `Product.offers.price`.
+/// See [`schema_org::paths`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SchemaOrgPaths;
+impl TextField for SchemaOrgPaths {
+    fn name(&self) -> &str {
+        "schema_org_paths"
+    }
+
+    fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
+        FieldTokenizer::Identity(Identity::default())
+    }
+
+    fn has_freqs(&self) -> bool {
+        false
+    }
+
+    fn add_html_tantivy(
+        &self,
+        _html: &Html,
+        cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        index: &crate::inverted_index::InvertedIndex,
+    ) -> Result<()> {
+        let field = self
+            .tantivy_field(index.schema_ref())
+            .unwrap_or_else(|| panic!("could not find field '{}' in index", self.name()));
+
+        for path in schema_org::paths(cache.schema_org()) {
+            doc.add_text(field, path);
+        }
+
+        Ok(())
+    }
+}
+
+/// The ISO 639-3 code of the detected language of the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Language;
+impl TextField for Language {
+    fn name(&self) -> &str {
+        "language"
+    }
+
+    fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
+        FieldTokenizer::Identity(Identity::default())
+    }
+
+    fn has_freqs(&self) -> bool {
+        false
+    }
+
+    fn add_html_tantivy(
+        &self,
+        html: &Html,
+        _cache: &mut FnCache,
+        doc: &mut TantivyDocument,
+        index: &crate::inverted_index::InvertedIndex,
+    ) -> Result<()> {
+        if let Some(lang) = html.lang() {
+            doc.add_text(
+                self.tantivy_field(index.schema_ref())
+                    .unwrap_or_else(|| panic!("could not find field '{}' in index", self.name())),
+                lang.code(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CleanBodyBigrams;
 impl TextField for CleanBodyBigrams {
@@ -1454,7 +1521,7 @@ impl TextField for SafetyClassification {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Identity(Identity {})
+        FieldTokenizer::Identity(Identity::default())
     }
 
     fn add_html_tantivy(
@@ -1496,7 +1563,7 @@ impl TextField for InsertionTimestamp {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Identity(Identity {})
+        FieldTokenizer::Identity(Identity::default())
     }
 
     fn indexing_option(&self) -> IndexingOption {
@@ -1539,7 +1606,7 @@ impl TextField for RecipeFirstIngredientTagId {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Identity(Identity {})
+        FieldTokenizer::Identity(Identity::default())
     }
 
     fn is_stored(&self) -> bool {
@@ -1575,7 +1642,7 @@ impl TextField for Links {
     }
 
     fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
-        FieldTokenizer::Url(UrlTokenizer)
+        FieldTokenizer::Url(UrlTokenizer::default())
     }
 
     fn add_html_tantivy(
@@ -1802,3 +1869,119 @@ impl TextField for AllH3 {
         Ok(())
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Favicon;
+impl TextField for Favicon {
+    fn name(&self) -> &str {
+        "favicon"
+    }
+
+    fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
+        FieldTokenizer::Identity(Identity::default())
+    }
+
+    fn is_stored(&self) -> bool {
+        true
+    }
+
+    fn add_html_tantivy(
+        &self,
+        html: &Html,
+        _: &mut FnCache,
+        doc: &mut TantivyDocument,
+        index: &crate::inverted_index::InvertedIndex,
+    ) -> Result<()> {
+        doc.add_text(
+            self.tantivy_field(index.schema_ref())
+                .unwrap_or_else(|| panic!("could not find field '{}' in index", self.name())),
+            html.favicon_url()
+                .map(|url| url.to_string())
+                .unwrap_or_default(),
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OpenGraphJson;
+impl TextField for OpenGraphJson {
+    fn name(&self) -> &str {
+        "open_graph_json"
+    }
+
+    fn tokenizer(&self, _: Option<&whatlang::Lang>) -> FieldTokenizer {
+        FieldTokenizer::Identity(Identity::default())
+    }
+
+    fn is_stored(&self) -> bool {
+        true
+    }
+
+    fn add_html_tantivy(
+        &self,
+        html: &Html,
+        _: &mut FnCache,
+        doc: &mut TantivyDocument,
+        index: &crate::inverted_index::InvertedIndex,
+    ) -> Result<()> {
+        let og = html.opengraph();
+
+        let json = if og.is_empty() {
+            String::new()
+        } else {
+            serde_json::to_string(&og)?
+        };
+
+        doc.add_text(
+            self.tantivy_field(index.schema_ref())
+                .unwrap_or_else(|| panic!("could not find field '{}' in index", self.name())),
+            json,
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lending_iter::LendingIterator;
+    use tantivy::tokenizer::Tokenizer as _;
+
+    use super::*;
+
+    fn tokenize<T: TextField>(field: T, text: &str) -> Vec<String> {
+        let mut tokenizer = field.field_tokenizer(Some(&Lang::Eng));
+        let mut stream = tokenizer.token_stream(text);
+        let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
+
+        let mut res = Vec::new();
+        while let Some(token) = it.next() {
+            res.push(token.text.clone());
+        }
+
+        res
+    }
+
+    #[test]
+    fn stopword_policy() {
+        assert_eq!(Title.stopword_policy(), StopwordPolicy::Keep);
+        assert_eq!(CleanBody.stopword_policy(), StopwordPolicy::Remove);
+
+        assert_eq!(tokenize(Title, "the cat"), vec!["the", "cat"]);
+        assert_eq!(tokenize(CleanBody, "the cat"), vec!["cat"]);
+
+        // the query is tokenized the same way as the field
+        let mut tokenizer = CleanBody.query_tokenizer(Some(&Lang::Eng));
+        let mut stream = tokenizer.token_stream("the cat");
+        assert!(tantivy::tokenizer::TokenStream::advance(&mut stream));
+        assert_eq!(tantivy::tokenizer::TokenStream::token(&stream).text, "cat");
+        assert!(!tantivy::tokenizer::TokenStream::advance(&mut stream));
+
+        assert_ne!(
+            Title.field_tokenizer(None).as_str(),
+            CleanBody.field_tokenizer(None).as_str()
+        );
+    }
+}
@@ -20,7 +20,7 @@ use crate::api::search::ReturnBody;
 use crate::{
     highlighted::HighlightedFragment,
     inverted_index::RetrievedWebpage,
-    ranking::{SignalEnumDiscriminants, SignalScore},
+    ranking::{Explanation, SignalEnumDiscriminants, SignalScore},
     searcher::SearchQuery,
     snippet::TextSnippet,
     web_spell::{self, CorrectionTerm},
@@ -51,8 +51,26 @@ pub enum RichSnippet {
         question: StackOverflowQuestion,
         answers: Vec<StackOverflowAnswer>,
     },
+    Faq {
+        pairs: Vec<FaqPair>,
+    },
 }
 
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone, ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct FaqPair {
+    pub question: String,
+    pub answer: String,
+}
+
+const MAX_FAQ_PAIRS: usize = 3;
+const QUESTION_WORDS: [&str; 14] = [
+    "who", "what", "when", "where", "why", "how", "which", "can", "could", "does", "do", "is",
+    "are", "should",
+];
+
 #[derive(
     Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone, ToSchema,
 )]
@@ -60,6 +78,8 @@ pub enum RichSnippet {
 pub struct HighlightedSpellCorrection {
     pub raw: String,
     pub highlighted: Vec<HighlightedFragment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<web_spell::TermAlternatives>,
 }
 
 impl From<web_spell::Correction> for HighlightedSpellCorrection {
@@ -69,10 +89,7 @@ impl From<web_spell::Correction> for HighlightedSpellCorrection {
 
         for term in correction.terms {
             match term {
-                CorrectionTerm::Corrected {
-                    orig: _,
-                    correction,
-                } => {
+                CorrectionTerm::Corrected { correction, .. } => {
                     let mut correction = correction.trim().to_string();
                     correction.push(' ');
 
@@ -95,7 +112,11 @@ impl From<web_spell::Correction> for HighlightedSpellCorrection {
             last.text = last.text.trim_end().to_string();
         }
 
-        Self { raw, highlighted }
+        Self {
+            raw,
+            highlighted,
+            alternatives: correction.alternatives,
+        }
     }
 }
 
@@ -153,7 +174,22 @@ fn generate_snippet(webpage: &RetrievedWebpage) -> Snippet {
     }
 }
 
-fn generate_rich_snippet(webpage: &RetrievedWebpage) -> Option<RichSnippet> {
+/// Whether the query looks like the user is asking a question.
+fn is_question_query(query: &str) -> bool {
+    let query = query.trim();
+
+    if query.ends_with('?') {
+        return true;
+    }
+
+    query
+        .split_whitespace()
+        .next()
+        .map(|word| QUESTION_WORDS.contains(&word.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn generate_rich_snippet(webpage: &RetrievedWebpage, query: &SearchQuery) -> Option<RichSnippet> {
     let url = Url::parse(&webpage.url).unwrap();
 
     if url.root_domain().unwrap_or_default() == "stackoverflow.com"
@@ -167,6 +203,18 @@ fn generate_rich_snippet(webpage: &RetrievedWebpage) -> Option<RichSnippet> {
         }
     }
 
+    if is_question_query(&query.query) {
+        let pairs: Vec<_> = crate::webpage::schema_org::faq_pairs(&webpage.schema_org)
+            .into_iter()
+            .take(MAX_FAQ_PAIRS)
+            .map(|(question, answer)| FaqPair { question, answer })
+            .collect();
+
+        if !pairs.is_empty() {
+            return Some(RichSnippet::Faq { pairs });
+        }
+    }
+
     None
 }
 
@@ -176,15 +224,22 @@ fn generate_rich_snippet(webpage: &RetrievedWebpage) -> Option<RichSnippet> {
 #[serde(rename_all = "camelCase")]
 pub struct DisplayedWebpage {
     pub title: String,
+    /// The title with the query terms highlighted.
+    pub highlighted_title: Vec<HighlightedFragment>,
     pub url: String,
     pub site: String,
     pub domain: String,
     pub pretty_url: String,
+    pub favicon: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
     pub snippet: Snippet,
     #[cfg(feature = "return_body")]
     pub body: Option<String>,
     pub rich_snippet: Option<RichSnippet>,
     pub ranking_signals: Option<HashMap<SignalEnumDiscriminants, SignalScore>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Explanation>,
     pub structured_data: Option<Vec<StructuredData>>,
     pub likely_has_ads: bool,
     pub likely_has_paywall: bool,
@@ -205,7 +260,19 @@ pub struct DisplayedAnswer {
 impl DisplayedWebpage {
     pub fn new(webpage: RetrievedWebpage, query: &SearchQuery) -> Self {
         let snippet = generate_snippet(&webpage);
-        let rich_snippet = generate_rich_snippet(&webpage);
+        let rich_snippet = generate_rich_snippet(&webpage, query);
+
+        // OpenGraph metadata is written by the site for exactly this purpose,
+        // so it takes precedence over what we scraped from the page.
+        let og = webpage.opengraph.clone().unwrap_or_default();
+        let description = og.description.or_else(|| webpage.description().cloned());
+        let image = og.image.map(|url| url.to_string());
+        let title = og.title.unwrap_or_else(|| webpage.title.clone());
+        let highlighted_title = if title == webpage.title {
+            webpage.highlighted_title
+        } else {
+            vec![HighlightedFragment::new_unhighlighted(title.clone())]
+        };
 
         let url = Url::parse(&webpage.url).unwrap();
         let domain = url.root_domain().unwrap_or_default().to_string();
@@ -230,15 +297,20 @@ impl DisplayedWebpage {
         });
 
         Self {
-            title: webpage.title,
+            title,
+            highlighted_title,
             site: url.normalized_host().unwrap_or_default().to_string(),
             url: webpage.url,
             pretty_url,
+            favicon: webpage.favicon,
+            description,
+            image,
             domain,
             snippet,
             #[cfg(feature = "return_body")]
             body,
             ranking_signals: None,
+            explanation: None,
             likely_has_ads: webpage.likely_has_ads,
             likely_has_paywall: webpage.likely_has_paywall,
             rich_snippet,
@@ -282,6 +354,15 @@ mod tests {
         assert_eq!(prettify_date(date), "2 days ago".to_string());
     }
 
+    #[test]
+    fn question_queries() {
+        assert!(is_question_query("how long does composting take"));
+        assert!(is_question_query("What is rust"));
+        assert!(is_question_query("composting time?"));
+        assert!(!is_question_query("composting time"));
+        assert!(!is_question_query(""));
+    }
+
     #[test]
     fn prettify_date_rest() {
         let date = DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
@@ -35,6 +35,7 @@ impl From<crate::OneOrMany<String>> for OneOrManyString {
 pub enum Property {
     String(String),
     Data(StructuredData),
+    Number(f64),
 }
 
 impl From<crate::webpage::schema_org::Property> for Property {
@@ -42,6 +43,7 @@ impl From<crate::webpage::schema_org::Property> for Property {
         match property {
             crate::webpage::schema_org::Property::String(string) => Property::String(string),
             crate::webpage::schema_org::Property::Item(data) => Property::Data(data.into()),
+            crate::webpage::schema_org::Property::Number(n) => Property::Number(n),
         }
     }
 }
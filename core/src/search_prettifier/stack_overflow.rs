@@ -84,6 +84,7 @@ fn parse_so_answer(
         .into_iter()
         .filter_map(|prop| match prop {
             Property::String(s) => Some(CodeOrText::Text(s)),
+            Property::Number(n) => Some(CodeOrText::Text(n.to_string())),
             Property::Item(item) => parse_code(item),
         })
         .collect();
@@ -188,6 +189,7 @@ pub fn stackoverflow_snippet(
                 .into_iter()
                 .map(|prop| match prop {
                     Property::String(s) => CodeOrText::Text(s),
+                    Property::Number(n) => CodeOrText::Text(n.to_string()),
                     Property::Item(item) => parse_code(item).unwrap(),
                 })
                 .collect();
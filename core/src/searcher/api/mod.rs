@@ -27,8 +27,11 @@ use url::Url;
 use ahash::AHashMap as HashMap;
 
 use crate::bangs::{Bang, BangHit};
-use crate::collector::{self, approx_count};
-use crate::config::{ApiConfig, ApiSpellCheck, ApiThresholds, CollectorConfig, WidgetsConfig};
+use crate::collector::{self, approx_count, facets::Facets};
+use crate::config::{
+    ApiConfig, ApiSpellCheck, ApiThresholds, CollectorConfig, CompoundTermsConfig, WidgetsConfig,
+};
+use crate::distributed::member::ShardId;
 use crate::enum_map::EnumMap;
 use crate::image_store::Image;
 use crate::inverted_index::RetrievedWebpage;
@@ -48,17 +51,25 @@ use crate::widgets::{Widget, Widgets};
 use crate::{
     bangs::Bangs,
     collector::BucketCollector,
-    ranking::{models::lambdamart::LambdaMART, pipeline::RankingPipeline},
+    ranking::{models::lambdamart::ReloadableLambdaMART, pipeline::RankingPipeline},
 };
 use crate::{query, webgraph, Result};
 
 use self::sidebar::SidebarManager;
 use self::widget::WidgetManager;
 
-use super::{distributed, live, SearchQuery, SearchResult, WebsitesResult};
+use super::{
+    distributed, live, Cursor, InitialWebsiteResult, SearchQuery, SearchResult, WebsitesResult,
+};
 
 const NUM_PIPELINE_RANKING_RESULTS: usize = 300;
 
+/// Whether the page is past the results that are ranked by the full pipeline.
+/// The offset of a cursor comes from the client, so it can be anything.
+pub(super) fn is_deep_page(query: &SearchQuery) -> bool {
+    query.offset().saturating_add(query.num_results()) > NUM_PIPELINE_RANKING_RESULTS
+}
+
 #[derive(Clone)]
 pub enum ScoredWebpagePointer {
     Normal(distributed::ScoredWebpagePointer),
@@ -102,6 +113,13 @@ impl RankableWebpage for ScoredWebpagePointer {
         self.as_ranking().as_local_recall()
     }
 
+    fn shard(&self) -> Option<ShardId> {
+        match self {
+            ScoredWebpagePointer::Normal(p) => Some(p.shard),
+            ScoredWebpagePointer::Live(p) => Some(p.shard_id),
+        }
+    }
+
     fn signals(&self) -> &EnumMap<SignalEnum, SignalCalculation> {
         match self {
             ScoredWebpagePointer::Normal(p) => p.website.signals(),
@@ -151,11 +169,43 @@ pub fn add_ranking_signals(
     }
 }
 
+/// Convert the ranked webpage into the webpage shown to the user and attach
+/// the score breakdown if the query asked for it.
+fn displayed_webpage(webpage: PrecisionRankingWebpage, query: &SearchQuery) -> DisplayedWebpage {
+    let explanation = query
+        .explain
+        .then(|| webpage.explain(&query.signal_coefficients()));
+
+    let mut displayed = DisplayedWebpage::new(webpage.into_retrieved_webpage(), query);
+    displayed.explanation = explanation;
+
+    displayed
+}
+
+fn merge_facets<'a>(results: impl Iterator<Item = &'a InitialWebsiteResult>) -> Option<Facets> {
+    results
+        .filter_map(|result| result.facets.clone())
+        .reduce(Facets::merge)
+}
+
+fn merge_distinct_sites<'a>(
+    results: impl Iterator<Item = &'a InitialWebsiteResult>,
+) -> Option<approx_count::Count> {
+    results
+        .filter_map(|result| result.distinct_sites.clone())
+        .reduce(|mut acc, sites| {
+            acc.merge(&sites);
+            acc
+        })
+        .map(|sites| sites.count())
+}
+
 #[derive(Default)]
 pub struct Config {
     pub thresholds: ApiThresholds,
     pub widgets: WidgetsConfig,
     pub collector: CollectorConfig,
+    pub compound_terms: CompoundTermsConfig,
     pub spell_check: Option<ApiSpellCheck>,
 }
 
@@ -165,6 +215,7 @@ impl From<ApiConfig> for Config {
             thresholds: conf.thresholds,
             widgets: conf.widgets,
             collector: conf.collector,
+            compound_terms: conf.compound_terms,
             spell_check: conf.spell_check,
         }
     }
@@ -240,10 +291,11 @@ pub struct ApiSearcher<S, L, G> {
     sidebar_manager: SidebarManager<S>,
     live_searcher: Option<L>,
     cross_encoder: Option<Arc<CrossEncoderModel>>,
-    lambda_model: Option<Arc<LambdaMART>>,
+    lambda_model: Option<ReloadableLambdaMART>,
     dual_encoder: Option<Arc<DualEncoder>>,
     bangs: Bangs,
     collector_config: CollectorConfig,
+    compound_terms: CompoundTermsConfig,
     widget_manager: WidgetManager,
     spell_checker: Option<SpellChecker>,
     webgraph: Option<G>,
@@ -275,6 +327,7 @@ where
             dual_encoder: None,
             bangs,
             collector_config: config.collector,
+            compound_terms: config.compound_terms,
             widget_manager,
             spell_checker: config
                 .spell_check
@@ -298,8 +351,17 @@ where
         self
     }
 
-    pub fn with_lambda_model(mut self, lambda_model: LambdaMART) -> Self {
-        self.lambda_model = Some(Arc::new(lambda_model));
+    /// The dimension of the stored embeddings if the searcher has the dual encoder
+    /// that produced them.
+    pub fn embedding_dim(&self) -> Option<usize> {
+        self.dual_encoder
+            .as_ref()
+            .map(|dual_encoder| dual_encoder.hidden_size())
+    }
+
+    /// The model can be reloaded through the given handle while the searcher is running.
+    pub fn with_lambda_model(mut self, lambda_model: ReloadableLambdaMART) -> Self {
+        self.lambda_model = Some(lambda_model);
         self
     }
 
@@ -354,7 +416,13 @@ where
         self.sidebar_manager.sidebar(query).await
     }
 
-    pub fn spell_check(&self, query: &str) -> Option<HighlightedSpellCorrection> {
+    /// Correct the simple terms of the query. Up to `num_alternatives` of the
+    /// best corrections are included for each corrected term.
+    pub fn spell_check(
+        &self,
+        query: &str,
+        num_alternatives: usize,
+    ) -> Option<HighlightedSpellCorrection> {
         let query = query.to_lowercase();
 
         let terms = query::parser::parse(&query).ok()?;
@@ -370,31 +438,34 @@ where
             })
             .join(" ");
 
-        let corrections = self
-            .spell_checker
-            .as_ref()
-            .and_then(|s| s.correct(&simple_query, &whatlang::Lang::Eng))?;
+        let corrections = self.spell_checker.as_ref().and_then(|s| {
+            s.correct_with_alternatives(&simple_query, &whatlang::Lang::Eng, num_alternatives)
+        })?;
 
-        let correction_map: HashMap<String, String> = corrections
+        let correction_map: HashMap<String, (String, f64)> = corrections
             .terms
             .into_iter()
             .filter_map(|t| match t {
-                crate::web_spell::CorrectionTerm::Corrected { orig, correction } => {
-                    Some((orig, correction))
-                }
+                crate::web_spell::CorrectionTerm::Corrected {
+                    orig,
+                    correction,
+                    confidence,
+                } => Some((orig, (correction, confidence))),
                 crate::web_spell::CorrectionTerm::NotCorrected(_) => None,
             })
             .collect();
 
         let mut correction = crate::web_spell::Correction::empty(query);
+        correction.alternatives = corrections.alternatives;
 
         for term in terms {
             match term {
                 query::parser::Term::SimpleOrPhrase(query::parser::SimpleOrPhrase::Simple(t)) => {
-                    if let Some(term_correction) = correction_map.get(t.as_str()) {
+                    if let Some((term_correction, confidence)) = correction_map.get(t.as_str()) {
                         correction.push(crate::web_spell::CorrectionTerm::Corrected {
                             orig: String::from(t),
                             correction: term_correction.to_string(),
+                            confidence: *confidence,
                         });
                     } else {
                         correction.push(crate::web_spell::CorrectionTerm::NotCorrected(
@@ -490,9 +561,9 @@ where
         query: &SearchQuery,
         initial_results: Vec<distributed::InitialSearchResultShard>,
         live_results: Vec<live::InitialSearchResultShard>,
+        num_candidates: usize,
     ) -> (Vec<ScoredWebpagePointer>, bool) {
-        let mut collector =
-            BucketCollector::new(NUM_PIPELINE_RANKING_RESULTS, self.collector_config.clone());
+        let mut collector = BucketCollector::new(num_candidates, self.collector_config.clone());
 
         let initial_host_nodes = initial_results
             .iter()
@@ -558,12 +629,12 @@ where
             }
         }
 
-        let has_more = query.offset() + query.num_results() < num_results;
+        let has_more = query.offset().saturating_add(query.num_results()) < num_results;
 
         let res = collector
             .into_sorted_vec(true)
             .into_iter()
-            .take(NUM_PIPELINE_RANKING_RESULTS)
+            .take(num_candidates)
             .collect::<Vec<_>>();
 
         (res, has_more)
@@ -623,15 +694,26 @@ where
             .map(|result| result.local_result.num_websites)
             .fold(approx_count::Count::Exact(0), |acc, count| acc + count);
 
-        let (combined, _) = self.combine_results(query, results, vec![]).await;
+        let facets = merge_facets(results.iter().map(|result| &result.local_result));
+        let distinct_sites =
+            merge_distinct_sites(results.iter().map(|result| &result.local_result));
+
+        let (combined, _) = self
+            .combine_results(query, results, vec![], NUM_PIPELINE_RANKING_RESULTS)
+            .await;
         let combined: Vec<_> = combined.into_iter().take(query.num_results).collect();
 
+        // the shards only return the documents after the cursor, so the next page
+        // continues from the last document of this one without an offset
+        let next_cursor = combined.last().filter(|_| has_more_results).map(|webpage| {
+            Cursor::new(webpage, query.offset().saturating_add(combined.len())).to_string()
+        });
+
         let mut retrieved_webpages: Vec<_> = self
             .retrieve_webpages(&query.query, &combined)
             .await
             .into_iter()
-            .map(|webpage| webpage.into_retrieved_webpage())
-            .map(|webpage| DisplayedWebpage::new(webpage, query))
+            .map(|webpage| displayed_webpage(webpage, query))
             .collect();
 
         if query.return_ranking_signals {
@@ -649,6 +731,9 @@ where
             webpages: retrieved_webpages,
             search_duration_ms,
             has_more_results,
+            facets,
+            distinct_sites,
+            next_cursor,
         })
     }
 
@@ -659,15 +744,19 @@ where
             return Err(distributed::Error::EmptyQuery.into());
         }
 
-        if query.offset() + query.num_results() > NUM_PIPELINE_RANKING_RESULTS {
+        if is_deep_page(query) {
             // this is most likely a bot
             // let's not spend too much time correctly offsetting+ranking results
             return self.search_websites_approx_offsets(query).await;
         }
 
+        // a cursor pages through the same recall set as the first page
+        let num_candidates = NUM_PIPELINE_RANKING_RESULTS;
+
         let search_query = SearchQuery {
-            num_results: NUM_PIPELINE_RANKING_RESULTS,
+            num_results: num_candidates,
             page: 0,
+            after: None,
             ..query.clone()
         };
 
@@ -686,28 +775,56 @@ where
             }))
             .fold(approx_count::Count::Exact(0), |acc, count| acc + count);
 
+        let local_results = || {
+            initial_results
+                .iter()
+                .map(|result| &result.local_result)
+                .chain(
+                    live_results
+                        .iter()
+                        .flat_map(|results| results.iter().map(|result| &result.local_result)),
+                )
+        };
+        let facets = merge_facets(local_results());
+        let distinct_sites = merge_distinct_sites(local_results());
+
         let (top_websites, has_more_results) = self
-            .combine_results(query, initial_results, live_results.unwrap_or_default())
+            .combine_results(
+                query,
+                initial_results,
+                live_results.unwrap_or_default(),
+                num_candidates,
+            )
             .await;
 
         let inbound_scorer = self.inbound_scorer(query).await;
 
+        // both pipelines should score with the same model even if it's reloaded in the meantime
+        let lambda_model = self.lambda_model.as_ref().map(ReloadableLambdaMART::load);
+
         let pipeline: RankingPipeline<ScoredWebpagePointer> =
             RankingPipeline::<ScoredWebpagePointer>::recall_stage(
                 query,
                 inbound_scorer,
-                self.lambda_model.clone(),
+                lambda_model.clone(),
                 self.dual_encoder.clone(),
             );
 
         let top_websites = pipeline.apply(top_websites, query);
 
+        let next_cursor = top_websites
+            .last()
+            .filter(|_| top_websites.len() == query.num_results())
+            .map(|webpage| Cursor::new(webpage, query.offset() + top_websites.len()).to_string());
+
         let mut retrieved_webpages = self.retrieve_webpages(&query.query, &top_websites).await;
 
         if let Some(cross_encoder) = self.cross_encoder.clone() {
-            if query.page < 2 {
+            // only the first two pages are reranked, also when paging with a cursor
+            if query.offset() < 2 * query.num_results() {
                 let query = SearchQuery {
                     page: 1,
+                    after: None,
                     ..query.clone()
                 };
 
@@ -715,7 +832,7 @@ where
                     RankingPipeline::<PrecisionRankingWebpage>::reranker(
                         &query,
                         cross_encoder,
-                        self.lambda_model.clone(),
+                        lambda_model,
                     );
 
                 retrieved_webpages = reranking_pipeline.apply(retrieved_webpages, &query);
@@ -724,8 +841,7 @@ where
 
         let mut retrieved_webpages: Vec<_> = retrieved_webpages
             .into_iter()
-            .map(|webpage| webpage.into_retrieved_webpage())
-            .map(|webpage| DisplayedWebpage::new(webpage, query))
+            .map(|webpage| displayed_webpage(webpage, query))
             .collect();
 
         if retrieved_webpages.len() != top_websites.len() {
@@ -747,6 +863,9 @@ where
             webpages: retrieved_webpages,
             search_duration_ms,
             has_more_results,
+            facets,
+            distinct_sites,
+            next_cursor,
         })
     }
 
@@ -755,7 +874,12 @@ where
             return Ok(SearchResult::Bang(Box::new(bang)));
         }
 
-        Ok(SearchResult::Websites(self.search_websites(query).await?))
+        let query = SearchQuery {
+            compound_terms: self.compound_terms,
+            ..query.clone()
+        };
+
+        Ok(SearchResult::Websites(self.search_websites(&query).await?))
     }
 
     pub async fn get_webpage(&self, url: &str) -> Result<Option<RetrievedWebpage>> {
@@ -777,6 +901,7 @@ where
         for query in queries {
             self.search_websites(&SearchQuery {
                 query,
+                compound_terms: self.compound_terms,
                 ..Default::default()
             })
             .await
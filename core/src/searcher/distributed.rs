@@ -40,7 +40,7 @@ use crate::{
 use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use fnv::FnvHashMap;
-use futures::future::join_all;
+use futures::{future::join_all, Stream, StreamExt};
 use itertools::Itertools;
 use std::future::Future;
 use thiserror::Error;
@@ -51,6 +51,33 @@ use super::{InitialWebsiteResult, LocalSearcher, SearchQuery};
 
 const CLIENT_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
 
+/// Queries that cost more than this many single term queries get a proportionally longer timeout.
+const BASE_QUERY_TERMS: usize = 8;
+const BASE_SEARCH_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_SEARCH_TIMEOUT: Duration = Duration::from_secs(240);
+
+/// The estimated cost of a query with a single term, which the cost of other queries is relative to.
+static SINGLE_TERM_COST: std::sync::LazyLock<usize> = std::sync::LazyLock::new(|| {
+    crate::query::estimated_cost(&SearchQuery {
+        query: "term".to_string(),
+        ..Default::default()
+    })
+    .unwrap_or(1)
+    .max(1)
+});
+
+/// The per-shard timeout for the query, scaled by its estimated cost.
+fn search_timeout(query: &SearchQuery) -> Duration {
+    let Some(cost) = crate::query::estimated_cost(query) else {
+        return BASE_SEARCH_TIMEOUT;
+    };
+
+    let base_cost = *SINGLE_TERM_COST * BASE_QUERY_TERMS;
+    let factor = (cost as f64 / base_cost as f64).max(1.0);
+
+    BASE_SEARCH_TIMEOUT.mul_f64(factor).min(MAX_SEARCH_TIMEOUT)
+}
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Failed to get search result")]
@@ -120,7 +147,7 @@ impl ReusableClientManager for SearchService {
 
     async fn new_client(cluster: &Cluster) -> ShardedClient<Self::Service, Self::ShardId> {
         let mut shards = HashMap::new();
-        for member in cluster.members().await {
+        for member in cluster.members().await.into_iter().filter(|m| !m.drained) {
             if let Service::Searcher { host, shard } = member.service {
                 shards.entry(shard).or_insert_with(Vec::new).push(host);
             }
@@ -147,7 +174,7 @@ impl ReusableClientManager for entity_search_server::SearchService {
 
     async fn new_client(cluster: &Cluster) -> ShardedClient<Self::Service, Self::ShardId> {
         let mut replicas = Vec::new();
-        for member in cluster.members().await {
+        for member in cluster.members().await.into_iter().filter(|m| !m.drained) {
             if let Service::EntitySearcher { host } = member.service {
                 replicas.push(RemoteClient::new(host));
             }
@@ -172,7 +199,7 @@ impl ReusableClientManager for LiveIndexService {
 
     async fn new_client(cluster: &Cluster) -> ShardedClient<Self::Service, Self::ShardId> {
         let mut shards = HashMap::new();
-        for member in cluster.members().await {
+        for member in cluster.members().await.into_iter().filter(|m| !m.drained) {
             if let Service::LiveIndex { host, shard, state } = member.service {
                 if state == LiveIndexState::Ready {
                     shards.entry(shard).or_insert_with(Vec::new).push(host);
@@ -245,34 +272,39 @@ impl DistributedSearcher {
             _ => vec![],
         }
     }
-}
-
-impl SearchClient for DistributedSearcher {
-    async fn search_initial(&self, query: &SearchQuery) -> Vec<InitialSearchResultShard> {
-        let client = self.conn().await;
-        let mut results = Vec::new();
 
-        if let Ok(res) = client
-            .send(
+    /// Yield the result of each shard as soon as it arrives instead of waiting
+    /// for all shards to respond. Shards that fail or time out are skipped.
+    pub async fn search_initial_stream(
+        &self,
+        query: &SearchQuery,
+    ) -> impl Stream<Item = InitialSearchResultShard> {
+        self.conn()
+            .await
+            .stream(
                 search_server::Search {
                     query: query.clone(),
                 },
                 &AllShardsSelector,
-                &RandomReplicaSelector,
+                RandomReplicaSelector,
+                search_timeout(query),
             )
-            .await
-        {
-            for (shard_id, mut res) in res {
-                if let Some((_, Some(res))) = res.pop() {
-                    results.push(InitialSearchResultShard {
+            .filter_map(|(shard, mut res)| async move {
+                match res.pop() {
+                    Some((_, Some(res))) => Some(InitialSearchResultShard {
                         local_result: res,
-                        shard: shard_id,
-                    });
+                        shard,
+                    }),
+                    _ => None,
                 }
-            }
-        }
+            })
+    }
+}
 
-        results
+impl SearchClient for DistributedSearcher {
+    async fn search_initial(&self, query: &SearchQuery) -> Vec<InitialSearchResultShard> {
+        // a shard that fails only drops its own results rather than those of every shard
+        self.search_initial_stream(query).await.collect().await
     }
 
     async fn retrieve_webpages(
@@ -451,7 +483,9 @@ impl SearchClient for DistributedSearcher {
 /// This should only be used for testing and benchmarks.
 pub struct LocalSearchClient(LocalSearcher<Index>);
 impl From<LocalSearcher<Index>> for LocalSearchClient {
-    fn from(searcher: LocalSearcher<Index>) -> Self {
+    fn from(mut searcher: LocalSearcher<Index>) -> Self {
+        // the results are reported as coming from the first shard
+        searcher.set_shard(ShardId::new(0));
         Self(searcher)
     }
 }
@@ -526,3 +560,36 @@ impl SearchClient for LocalSearchClient {
         self.0.top_key_phrases(top_n)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeout(query: &str) -> Duration {
+        search_timeout(&SearchQuery {
+            query: query.to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn search_timeout_scales_with_cost() {
+        assert_eq!(timeout(""), BASE_SEARCH_TIMEOUT);
+        assert_eq!(timeout("york"), BASE_SEARCH_TIMEOUT);
+        assert_eq!(timeout("new york"), BASE_SEARCH_TIMEOUT);
+
+        let long = (0..crate::query::parser::MAX_TERMS_PER_QUERY)
+            .map(|i| format!("term{i}"))
+            .join(" ");
+        assert!(timeout(&long) > BASE_SEARCH_TIMEOUT);
+        assert!(timeout(&long) <= MAX_SEARCH_TIMEOUT);
+
+        // every word of a phrase is looked up, so long phrases hit the limit
+        let words = (0..30).map(|i| format!("word{i}")).join(" ");
+        let phrases = (0..crate::query::parser::MAX_TERMS_PER_QUERY)
+            .map(|i| format!("\"term{i} {words}\""))
+            .join(" ");
+        assert!(timeout(&phrases) >= timeout(&long));
+        assert_eq!(timeout(&phrases), MAX_SEARCH_TIMEOUT);
+    }
+}
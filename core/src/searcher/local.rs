@@ -20,8 +20,12 @@ use std::sync::{Arc, RwLockReadGuard};
 use itertools::Itertools;
 use url::Url;
 
-use crate::collector::approx_count;
+use crate::collector::{
+    approx_count::{self, DistinctCount},
+    facets::Facets,
+};
 use crate::config::{CollectorConfig, SnippetConfig};
+use crate::distributed::member::ShardId;
 use crate::index::Index;
 use crate::inverted_index::{InvertedIndex, KeyPhrase, RetrievedWebpage};
 use crate::models::dual_encoder::DualEncoder;
@@ -105,6 +109,7 @@ pub struct LocalSearcher<I: SearchableIndex> {
     linear_regression: Option<Arc<LinearRegression>>,
     dual_encoder: Option<Arc<DualEncoder>>,
     collector_config: CollectorConfig,
+    shard: Option<ShardId>,
 }
 
 impl<I> From<I> for LocalSearcher<I>
@@ -119,6 +124,8 @@ where
 struct InvertedIndexResult {
     webpages: Vec<LocalRecallRankingWebpage>,
     num_hits: approx_count::Count,
+    facets: Option<Facets>,
+    distinct_sites: Option<DistinctCount>,
 }
 
 impl<I> LocalSearcher<I>
@@ -131,6 +138,7 @@ where
             linear_regression: None,
             dual_encoder: None,
             collector_config: CollectorConfig::default(),
+            shard: None,
         }
     }
 
@@ -142,6 +150,12 @@ where
         self.dual_encoder = Some(Arc::new(dual_encoder));
     }
 
+    /// The shard the index belongs to, which is needed to tell which of the
+    /// documents come after a cursor that points into another shard.
+    pub fn set_shard(&mut self, shard: ShardId) {
+        self.shard = Some(shard);
+    }
+
     pub fn set_collector_config(&mut self, config: CollectorConfig) {
         self.collector_config = config;
     }
@@ -174,13 +188,18 @@ where
 
         ranker.de_rank_similar(de_rank_similar);
 
-        Ok(ranker
+        let ranker = ranker
             .with_max_docs(
                 self.collector_config.max_docs_considered,
                 guard.inverted_index().num_segments(),
             )
-            .with_num_results(query.num_results())
-            .with_offset(query.offset()))
+            .with_num_results(query.num_results());
+
+        // the offset of a cursor is supplied by the client, so it is never used to collect
+        Ok(match query.after {
+            Some(cursor) => ranker.with_after(cursor, self.shard),
+            None => ranker.with_offset(query.offset()),
+        })
     }
 
     fn search_inverted_index<'a, G: SearchGuard<'a>>(
@@ -203,6 +222,8 @@ where
                 .clone(),
         );
 
+        computer.set_freshness_half_life_hours(self.collector_config.freshness_half_life_hours);
+
         if let Some(model) = self.linear_regression.as_ref() {
             computer.set_linear_model(model.clone());
         }
@@ -227,6 +248,8 @@ where
         Ok(InvertedIndexResult {
             webpages: ranking_websites,
             num_hits: res.num_websites,
+            facets: res.facets,
+            distinct_sites: res.distinct_sites,
         })
     }
 
@@ -247,6 +270,8 @@ where
         Ok(InitialWebsiteResult {
             websites: inverted_index_result.webpages,
             num_websites: inverted_index_result.num_hits,
+            facets: inverted_index_result.facets,
+            distinct_sites: inverted_index_result.distinct_sites,
         })
     }
 
@@ -321,6 +346,10 @@ where
             }
 
             webpage.ranking_signals = Some(ranking_signals);
+
+            if query.explain {
+                webpage.explanation = Some(ranking.explain(&coefficients));
+            }
         }
 
         Ok(WebsitesResult {
@@ -328,7 +357,13 @@ where
             webpages,
             search_duration_ms: start.elapsed().as_millis(),
             has_more_results: (search_result.num_websites.as_u64() as usize)
-                > query.offset() + query.num_results(),
+                > query.offset().saturating_add(query.num_results()),
+            facets: search_result.facets,
+            distinct_sites: search_result
+                .distinct_sites
+                .as_ref()
+                .map(DistinctCount::count),
+            next_cursor: None,
         })
     }
 
@@ -355,7 +390,7 @@ where
 #[cfg(test)]
 mod tests {
     use crate::{
-        searcher::NUM_RESULTS_PER_PAGE,
+        searcher::{api::is_deep_page, Cursor, NUM_RESULTS_PER_PAGE},
         webpage::{Html, Webpage},
     };
 
@@ -419,4 +454,76 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn forged_cursor_offset() {
+        const NUM_WEBSITES: usize = 3 * NUM_RESULTS_PER_PAGE;
+
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        for i in 0..NUM_WEBSITES {
+            index
+                .insert(&Webpage {
+                    html: Html::parse(
+                        r#"
+            <html>
+                <head>
+                    <title>Example website</title>
+                </head>
+                <body>
+                    test
+                </body>
+            </html>
+            "#,
+                        &format!("https://www.{i}.com"),
+                    )
+                    .unwrap(),
+                    host_centrality: (NUM_WEBSITES - i) as f64,
+                    fetch_time_ms: 500,
+                    ..Default::default()
+                })
+                .expect("failed to insert webpage");
+        }
+
+        index.commit().unwrap();
+
+        let searcher = LocalSearcher::new(index);
+        let query = SearchQuery {
+            query: "test".to_string(),
+            ..Default::default()
+        };
+
+        let first_page = searcher.search_initial(&query, true).unwrap();
+
+        // the cursor is decoded from the client, who can put any offset in it
+        let cursor: Cursor = Cursor::new(first_page.websites.last().unwrap(), usize::MAX)
+            .to_string()
+            .parse()
+            .unwrap();
+        assert_eq!(cursor.offset(), usize::MAX);
+
+        let query = SearchQuery {
+            after: Some(cursor),
+            ..query
+        };
+        assert!(is_deep_page(&query));
+
+        // the shards continue after the cursor instead of collecting up to the offset
+        let urls: Vec<_> = searcher
+            .search(&query)
+            .unwrap()
+            .webpages
+            .into_iter()
+            .map(|page| page.url)
+            .collect();
+
+        assert_eq!(urls.len(), NUM_RESULTS_PER_PAGE);
+
+        for (i, url) in urls.into_iter().enumerate() {
+            assert_eq!(
+                url,
+                format!("https://www.{}.com/", i + NUM_RESULTS_PER_PAGE)
+            );
+        }
+    }
 }
@@ -19,6 +19,9 @@ pub mod distributed;
 pub mod live;
 pub mod local;
 
+use std::{fmt, str::FromStr};
+
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
 pub use distributed::*;
 pub use local::*;
 use optics::{HostRankings, Optic};
@@ -29,11 +32,19 @@ use crate::api::search::ReturnBody;
 
 use crate::{
     bangs::BangHit,
-    collector::approx_count::Count,
-    config::defaults,
-    ranking::{pipeline::LocalRecallRankingWebpage, SignalCoefficients},
+    collector::{
+        approx_count::{Count, DistinctCount},
+        facets::Facets,
+    },
+    config::{defaults, CompoundTermsConfig},
+    distributed::member::ShardId,
+    inverted_index::DocAddress,
+    ranking::{
+        pipeline::{LocalRecallRankingWebpage, RankableWebpage},
+        SignalCoefficients,
+    },
     search_prettifier::DisplayedWebpage,
-    webpage::region::Region,
+    webpage::{region::Region, GeoPoint},
 };
 
 pub const NUM_RESULTS_PER_PAGE: usize = 20;
@@ -64,6 +75,112 @@ pub struct WebsitesResult {
     pub num_hits: Count,
     pub search_duration_ms: u128,
     pub has_more_results: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<Facets>,
+    /// Estimated number of distinct sites among all matching documents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distinct_sites: Option<Count>,
+    /// Opaque cursor that fetches the next page when passed as `after`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Points to the last result of a page, so the next page can be fetched as
+/// the results ranked after it. The offset of the next page is kept so the
+/// searcher knows how deep into the recall set the page is.
+///
+/// The cursor is supplied by the client, so the offset can't be trusted to be
+/// anything but a hint. Pages deeper than the recall set are found by letting the
+/// shards only collect the documents they score below `initial_score`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+pub struct Cursor {
+    score: f64,
+    initial_score: f64,
+    shard: Option<ShardId>,
+    address: DocAddress,
+    offset: usize,
+}
+
+impl Cursor {
+    /// `offset` is the number of results ranked before the next page.
+    pub fn new<T: RankableWebpage>(webpage: &T, offset: usize) -> Self {
+        Self {
+            score: webpage.score(),
+            initial_score: webpage.as_local_recall().pointer().score.total,
+            shard: webpage.shard(),
+            address: webpage.as_local_recall().pointer().address,
+            offset,
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn points_to<T: RankableWebpage>(&self, webpage: &T) -> bool {
+        webpage.score() == self.score
+            && webpage.shard() == self.shard
+            && webpage.as_local_recall().pointer().address == self.address
+    }
+
+    fn ranks_before<T: RankableWebpage>(&self, webpage: &T) -> bool {
+        let address = webpage.as_local_recall().pointer().address;
+
+        webpage.score() < self.score
+            || (webpage.score() == self.score
+                && (webpage.shard(), address.segment, address.doc_id)
+                    > (self.shard, self.address.segment, self.address.doc_id))
+    }
+
+    /// Whether a document with the initial `score` on `shard` comes after the cursor
+    /// in the ranking of the shards. Ties are broken the same way as in [`Cursor::start`].
+    pub fn is_after(&self, score: f64, shard: Option<ShardId>, address: DocAddress) -> bool {
+        score < self.initial_score
+            || (score == self.initial_score
+                && (shard, address.segment, address.doc_id)
+                    > (self.shard, self.address.segment, self.address.doc_id))
+    }
+
+    /// Index of the first of the ranked `webpages` that comes after the cursor.
+    pub fn start<T: RankableWebpage>(&self, webpages: &[T]) -> usize {
+        if let Some(pos) = webpages.iter().position(|webpage| self.points_to(webpage)) {
+            return pos + 1;
+        }
+
+        // the webpage is no longer among the results, so continue from the first worse one
+        webpages
+            .iter()
+            .position(|webpage| self.ranks_before(webpage))
+            .unwrap_or(webpages.len())
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes =
+            bincode::encode_to_vec(self, common::bincode_config()).map_err(|_| fmt::Error)?;
+        write!(f, "{}", BASE64_URL_SAFE_NO_PAD.encode(bytes))
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = BASE64_URL_SAFE_NO_PAD.decode(s)?;
+        let (cursor, _) = bincode::decode_from_slice(&bytes, common::bincode_config())?;
+
+        Ok(cursor)
+    }
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone)]
@@ -71,14 +188,22 @@ pub struct SearchQuery {
     pub query: String,
     pub page: usize,
     pub num_results: usize,
+    /// Only return results ranked after the cursor. Takes precedence over `page`.
+    pub after: Option<Cursor>,
     pub selected_region: Option<Region>,
+    pub geo_point: Option<GeoPoint>,
+    pub query_embedding: Option<Vec<f32>>,
     pub optic: Option<Optic>,
     pub host_rankings: Option<HostRankings>,
     pub return_ranking_signals: bool,
+    pub explain: bool,
     pub safe_search: bool,
+    pub secure_only: bool,
     pub count_results_exact: bool,
     pub return_body: Option<ReturnBody>,
     pub return_structured_data: bool,
+    pub return_facets: bool,
+    pub compound_terms: CompoundTermsConfig,
 
     pub signal_coefficients: SignalCoefficients,
 }
@@ -97,6 +222,8 @@ impl From<String> for SearchQuery {
 pub struct InitialWebsiteResult {
     pub num_websites: Count,
     pub websites: Vec<LocalRecallRankingWebpage>,
+    pub facets: Option<Facets>,
+    pub distinct_sites: Option<DistinctCount>,
 }
 
 impl Default for SearchQuery {
@@ -108,14 +235,21 @@ impl Default for SearchQuery {
             query: Default::default(),
             page: Default::default(),
             num_results: NUM_RESULTS_PER_PAGE,
+            after: None,
             selected_region: Default::default(),
+            geo_point: None,
+            query_embedding: None,
             optic: Default::default(),
             host_rankings: Default::default(),
             return_ranking_signals: defaults::SearchQuery::return_ranking_signals(),
+            explain: defaults::SearchQuery::explain(),
             safe_search: defaults::SearchQuery::safe_search(),
+            secure_only: defaults::SearchQuery::secure_only(),
             count_results_exact: defaults::SearchQuery::count_results_exact(),
             return_body: None,
             return_structured_data: defaults::SearchQuery::return_structured_data(),
+            return_facets: defaults::SearchQuery::return_facets(),
+            compound_terms: Default::default(),
             signal_coefficients: Default::default(),
         }
     }
@@ -148,8 +282,12 @@ impl SearchQuery {
         &self.query
     }
 
+    /// The cursor replaces the page when it is set.
     pub fn offset(&self) -> usize {
-        self.page * self.num_results
+        match &self.after {
+            Some(cursor) => cursor.offset(),
+            None => self.page * self.num_results,
+        }
     }
 
     pub fn num_results(&self) -> usize {
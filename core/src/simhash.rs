@@ -75,11 +75,11 @@ struct Block {
 }
 
 impl Block {
-    fn new(block_idx: usize) -> Self {
+    fn new(block_idx: usize, block_size: usize) -> Self {
         Self {
             hashes: Default::default(),
-            mask: (HashType::MAX << (HashType::BITS as usize - BLOCK_SIZE))
-                >> (BLOCK_SIZE * block_idx),
+            mask: (HashType::MAX << (HashType::BITS as usize - block_size))
+                >> (block_size * block_idx),
         }
     }
 }
@@ -91,46 +91,70 @@ impl Block {
         self.hashes.entry(prefix).or_default().push(hash)
     }
 
-    /// true iff. `Block` has indexed a hash that is within `K` distance away from the query hash.
-    fn contains(&self, hash: &HashType) -> bool {
+    /// true iff. `Block` has indexed a hash that is within `k` distance away from the query hash.
+    fn contains(&self, hash: &HashType, k: usize) -> bool {
         let prefix = Prefix(*hash & self.mask);
 
         match self.hashes.get(&prefix) {
             Some(candidates) => candidates
                 .iter()
-                .any(|candidate| hamming_distance(*hash, *candidate) as usize <= K),
+                .any(|candidate| hamming_distance(*hash, *candidate) as usize <= k),
             None => false,
         }
     }
 }
 
-// TODO: When `generic_const_exprs` becomes stable, re-write this to use const-generics
-const K: usize = 3;
-const NUM_BLOCKS: usize = K + 1;
-const BLOCK_SIZE: usize = HashType::BITS as usize / NUM_BLOCKS;
+const DEFAULT_K: usize = 3;
+
+/// The largest distance a [`Table`] can look up. Each of the `k + 1` blocks is then
+/// 8 bits wide, and smaller blocks put so many hashes in each bucket that the lookups
+/// degrade to comparing against every hash in the table.
+pub const MAX_DISTANCE: usize = 7;
 
 pub struct Table {
-    blocks: [Block; NUM_BLOCKS],
+    k: usize,
+    blocks: Vec<Block>,
 }
 
 impl Default for Table {
     fn default() -> Self {
-        Self {
-            blocks: std::array::from_fn(Block::new),
-        }
+        Self::new(DEFAULT_K)
     }
 }
 
 impl Table {
+    /// Create a table that finds hashes within `k` distance of each other.
+    ///
+    /// The hashes are split into `k + 1` blocks, so two hashes within `k` distance
+    /// agree on at least one of the blocks.
+    ///
+    /// # Panics
+    /// If `k` is larger than [`MAX_DISTANCE`].
+    pub fn new(k: usize) -> Self {
+        assert!(
+            k <= MAX_DISTANCE,
+            "simhash distance {k} is larger than {MAX_DISTANCE}"
+        );
+        let num_blocks = k + 1;
+        let block_size = HashType::BITS as usize / num_blocks;
+
+        Self {
+            k,
+            blocks: (0..num_blocks)
+                .map(|block_idx| Block::new(block_idx, block_size))
+                .collect(),
+        }
+    }
+
     pub fn insert(&mut self, hash: HashType) {
         for block in &mut self.blocks {
             block.insert(hash);
         }
     }
 
-    /// true iff. `Table` has indexed a hash that is within `K` distance away from the query hash.
+    /// true iff. `Table` has indexed a hash that is within `k` distance away from the query hash.
     pub fn contains(&self, hash: &HashType) -> bool {
-        self.blocks.iter().any(|block| block.contains(hash))
+        self.blocks.iter().any(|block| block.contains(hash, self.k))
     }
 }
 
@@ -194,4 +218,32 @@ mod tests {
         assert!(table.contains(&h2));
         assert!(!table.contains(&h3));
     }
+
+    #[test]
+    fn table_distance() {
+        let h1 = 0u64 as HashType;
+        let h2 = 0b11111u64 as HashType;
+        let h3 = 0b1111111u64 as HashType;
+
+        let mut table = Table::new(0);
+        table.insert(h1);
+        assert!(table.contains(&h1));
+        assert!(!table.contains(&(h1 | 1)));
+
+        let mut table = Table::new(5);
+        table.insert(h1);
+        assert!(table.contains(&h2));
+        assert!(!table.contains(&(h2 | 0b100000)));
+
+        let mut table = Table::new(MAX_DISTANCE);
+        table.insert(h1);
+        assert!(table.contains(&h3));
+        assert!(!table.contains(&(h3 | 0b10000000)));
+    }
+
+    #[test]
+    #[should_panic(expected = "is larger than")]
+    fn table_distance_is_bounded() {
+        Table::new(MAX_DISTANCE + 1);
+    }
 }
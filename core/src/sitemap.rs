@@ -7,7 +7,7 @@ use chrono::{DateTime, Utc};
 use quick_xml::events::Event;
 use url::Url;
 
-use crate::dated_url::DatedUrl;
+use crate::dated_url::{parse_date, DatedUrl};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SitemapEntry {
@@ -68,8 +68,8 @@ pub fn parse_sitemap(s: &str) -> Vec<SitemapEntry> {
                         current_url = Some(url);
                     }
                 } else if in_url && in_lastmod {
-                    if let Ok(date) = DateTime::parse_from_rfc3339(&e.unescape().unwrap()) {
-                        current_lastmod = Some(date.with_timezone(&Utc));
+                    if let Some(date) = parse_date(&e.unescape().unwrap()) {
+                        current_lastmod = Some(date);
                     }
                 }
             }
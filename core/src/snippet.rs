@@ -19,6 +19,7 @@ use std::ops::Range;
 use crate::config::SnippetConfig;
 use crate::highlighted::{HighlightedFragment, HighlightedKind};
 use crate::query::Query;
+use crate::stopwords;
 use crate::tokenizer::fields::{
     BigramTokenizer, DefaultTokenizer, FieldTokenizer, Stemmed, TrigramTokenizer,
 };
@@ -106,6 +107,24 @@ impl SnippetBuilder {
             .dedup_by(|a, b| a.start == b.start && a.end >= b.end);
     }
 
+    /// Add highlights that may overlap the existing ones. Overlapping
+    /// highlights are merged into one.
+    fn add_highlights(&mut self, highlights: impl IntoIterator<Item = Range<usize>>) {
+        self.highlights.extend(highlights);
+        self.highlights
+            .sort_by(|a, b| a.start.cmp(&b.start).then(a.end.cmp(&b.end)));
+
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(self.highlights.len());
+        for range in self.highlights.drain(..) {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+
+        self.highlights = merged;
+    }
+
     fn build(self) -> TextSnippet {
         let mut fragments = Vec::new();
 
@@ -347,6 +366,81 @@ pub fn generate(query: &Query, text: &str, region: &Region, config: SnippetConfi
     }
 }
 
+/// Word tokens of `text` with their byte offsets in `text`.
+fn word_tokens(text: &str) -> Vec<(String, Range<usize>)> {
+    let mut tokenizer = FieldTokenizer::Default(DefaultTokenizer::default());
+    let mut stream = tantivy::tokenizer::Tokenizer::token_stream(&mut tokenizer, text);
+    let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
+
+    let mut res = Vec::new();
+    while let Some(tok) = it.next() {
+        if tok.text.chars().any(char::is_alphanumeric) {
+            res.push((tok.text.clone(), tok.offset_from..tok.offset_to));
+        }
+    }
+
+    res
+}
+
+/// Highlight the query terms in the title of a result. A phrase from the query is highlighted
+/// as a single fragment where its words appear next to each other in the title. Stopwords that
+/// are only part of a phrase are not highlighted on their own.
+pub fn highlight_title(query: &Query, title: &str, region: &Region) -> Vec<HighlightedFragment> {
+    let lang = region
+        .lang()
+        .or_else(|| whatlang::detect_lang(title))
+        .unwrap_or(Lang::Eng);
+
+    let title_tokens = word_tokens(title);
+
+    let mut phrase_matches = Vec::new();
+    let mut phrase_words = HashSet::new();
+
+    for phrase in query.phrases() {
+        let phrase: Vec<String> = word_tokens(&phrase.join(" "))
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        if phrase.is_empty() {
+            continue;
+        }
+
+        for window in title_tokens.windows(phrase.len()) {
+            if window
+                .iter()
+                .zip(&phrase)
+                .all(|((tok, _), word)| tok == word)
+            {
+                phrase_matches.push(window[0].1.start..window[window.len() - 1].1.end);
+            }
+        }
+
+        phrase_words.extend(phrase);
+    }
+
+    let stopwords = stopwords::get(&lang);
+    let terms: HashSet<String> = query
+        .simple_terms()
+        .iter()
+        .flat_map(|term| word_tokens(term))
+        .map(|(word, _)| word)
+        .filter(|word| {
+            !(phrase_words.contains(word) && stopwords.is_some_and(|s| s.contains(word)))
+        })
+        .collect();
+
+    let mut builder = SnippetBuilder {
+        fragment: title.to_string(),
+        highlights: Vec::new(),
+    };
+
+    builder.highlight(&terms, lang);
+    builder.add_highlights(phrase_matches);
+
+    builder.build().fragments
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -426,6 +520,77 @@ Survey in 2016, 2017, and 2018."#;
         assert_eq!(highlight(result.webpages[0].snippet.clone()), format!("{HIGHLIGHTEN_PREFIX}Rust{HIGHLIGHTEN_POSTFIX} is a systems programming {HIGHLIGHTEN_PREFIX}language{HIGHLIGHTEN_POSTFIX} sponsored by Mozilla which describes it as a \"safe, concurrent, practical {HIGHLIGHTEN_PREFIX}language{HIGHLIGHTEN_POSTFIX}\", supporting functional and imperative-procedural paradigms. {HIGHLIGHTEN_PREFIX}Rust{HIGHLIGHTEN_POSTFIX} is syntactically similar to C++[according to whom?], but its designers intend it to provide better memory safety while still maintaining "));
     }
 
+    fn highlighted_text(fragments: Vec<HighlightedFragment>) -> String {
+        fragments
+            .into_iter()
+            .map(|HighlightedFragment { kind, text }| match kind {
+                HighlightedKind::Normal => text,
+                HighlightedKind::Highlighted => {
+                    format!("{HIGHLIGHTEN_PREFIX}{}{HIGHLIGHTEN_POSTFIX}", text)
+                }
+            })
+            .collect()
+    }
+
+    fn search_title(title: &str, query: &str) -> String {
+        let (mut index, _dir) = Index::temporary().expect("Unable to open index");
+
+        index
+            .insert(
+                &Webpage::test_parse(
+                    &format!(
+                        r#"
+                        <html>
+                            <head>
+                                <title>{title}</title>
+                            </head>
+                            <body>
+                                {title}. {TEST_TEXT}
+                            </body>
+                        </html>
+                    "#
+                    ),
+                    "https://www.example.com",
+                )
+                .unwrap(),
+            )
+            .expect("failed to insert webpage");
+        index.commit().expect("failed to commit index");
+
+        let searcher = LocalSearcher::from(index);
+
+        let result = searcher
+            .search(&SearchQuery {
+                query: query.to_string(),
+                ..Default::default()
+            })
+            .expect("Search failed");
+
+        assert_eq!(result.webpages.len(), 1);
+        highlighted_text(result.webpages[0].highlighted_title.clone())
+    }
+
+    #[test]
+    fn title_highlight() {
+        assert_eq!(
+            search_title("Rust: a language for everyone", "rust language"),
+            format!("{HIGHLIGHTEN_PREFIX}Rust{HIGHLIGHTEN_POSTFIX}: a {HIGHLIGHTEN_PREFIX}language{HIGHLIGHTEN_POSTFIX} for everyone")
+        );
+    }
+
+    #[test]
+    fn title_phrase_highlight() {
+        assert_eq!(
+            search_title(
+                "The Lord of the Rings and the Hobbit",
+                "\"lord of the rings\""
+            ),
+            format!(
+                "The {HIGHLIGHTEN_PREFIX}Lord of the Rings{HIGHLIGHTEN_POSTFIX} and the Hobbit"
+            )
+        );
+    }
+
     #[test]
     fn stemmed_words_snippet_highlight() {
         let (mut index, _dir) = Index::temporary().expect("Unable to open index");
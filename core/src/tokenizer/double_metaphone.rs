@@ -0,0 +1,765 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+//
+// The encoding rules in this file are ported from `DoubleMetaphone` in Apache Commons Codec,
+// which carries the following notice:
+//
+// Licensed to the Apache Software Foundation (ASF) under one or more
+// contributor license agreements.  See the NOTICE file distributed with
+// this work for additional information regarding copyright ownership.
+// The ASF licenses this file to You under the Apache License, Version 2.0
+// (the "License"); you may not use this file except in compliance with
+// the License.  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Double Metaphone phonetic encoding by Lawrence Philips.
+//!
+//! Words that sound alike get the same code, so e.g. "Smith" and "Smyth" both
+//! encode to `SM0`. Each word has a primary code and an alternate code for
+//! names that have a common alternative pronunciation.
+//! The rules follow the implementation in Apache Commons Codec.
+
+const MAX_CODE_LEN: usize = 4;
+
+const L_R_N_M_B_H_F_V_W_SPACE: &[&str] = &["L", "R", "N", "M", "B", "H", "F", "V", "W", " "];
+const ES_EP_EB_EL_EY_IB_IL_IN_IE_EI_ER: &[&str] = &[
+    "ES", "EP", "EB", "EL", "EY", "IB", "IL", "IN", "IE", "EI", "ER",
+];
+const L_T_K_S_N_M_B_Z: &[&str] = &["L", "T", "K", "S", "N", "M", "B", "Z"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Codes {
+    pub primary: String,
+    pub alternate: String,
+}
+
+struct Encoding {
+    primary: String,
+    alternate: String,
+}
+
+impl Encoding {
+    fn new() -> Self {
+        Self {
+            primary: String::with_capacity(MAX_CODE_LEN),
+            alternate: String::with_capacity(MAX_CODE_LEN),
+        }
+    }
+
+    fn append_primary(&mut self, s: &str) {
+        for c in s.chars() {
+            if self.primary.len() < MAX_CODE_LEN {
+                self.primary.push(c);
+            }
+        }
+    }
+
+    fn append_alternate(&mut self, s: &str) {
+        for c in s.chars() {
+            if self.alternate.len() < MAX_CODE_LEN {
+                self.alternate.push(c);
+            }
+        }
+    }
+
+    fn append(&mut self, s: &str) {
+        self.append_primary(s);
+        self.append_alternate(s);
+    }
+
+    fn append_both(&mut self, primary: &str, alternate: &str) {
+        self.append_primary(primary);
+        self.append_alternate(alternate);
+    }
+
+    fn is_complete(&self) -> bool {
+        self.primary.len() >= MAX_CODE_LEN && self.alternate.len() >= MAX_CODE_LEN
+    }
+}
+
+struct Word {
+    chars: Vec<char>,
+    slavo_germanic: bool,
+}
+
+impl Word {
+    fn new(word: &str) -> Self {
+        let value: String = word.trim().to_uppercase();
+        let slavo_germanic = value.contains('W')
+            || value.contains('K')
+            || value.contains("CZ")
+            || value.contains("WITZ");
+
+        Self {
+            chars: value.chars().collect(),
+            slavo_germanic,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    fn char_at(&self, index: isize) -> char {
+        if index < 0 || index as usize >= self.chars.len() {
+            '\0'
+        } else {
+            self.chars[index as usize]
+        }
+    }
+
+    fn is_vowel_at(&self, index: isize) -> bool {
+        matches!(self.char_at(index), 'A' | 'E' | 'I' | 'O' | 'U' | 'Y')
+    }
+
+    /// Whether the `length` chars starting at `start` equal any of the criteria.
+    fn contains(&self, start: isize, length: usize, criteria: &[&str]) -> bool {
+        if start < 0 || start as usize + length > self.chars.len() {
+            return false;
+        }
+
+        let start = start as usize;
+        let target = &self.chars[start..start + length];
+
+        criteria
+            .iter()
+            .any(|c| c.chars().count() == length && c.chars().zip(target).all(|(a, b)| a == *b))
+    }
+
+    fn last(&self) -> isize {
+        self.len() as isize - 1
+    }
+
+    fn is_silent_start(&self) -> bool {
+        self.contains(0, 2, &["GN", "KN", "PN", "WR", "PS"])
+    }
+
+    fn condition_c0(&self, index: isize) -> bool {
+        if self.contains(index, 4, &["CHIA"]) {
+            true
+        } else if index <= 1
+            || self.is_vowel_at(index - 2)
+            || !self.contains(index - 1, 3, &["ACH"])
+        {
+            false
+        } else {
+            let c = self.char_at(index + 2);
+            (c != 'I' && c != 'E') || self.contains(index - 2, 6, &["BACHER", "MACHER"])
+        }
+    }
+
+    fn condition_ch0(&self, index: isize) -> bool {
+        index == 0
+            && (self.contains(index + 1, 5, &["HARAC", "HARIS"])
+                || self.contains(index + 1, 3, &["HOR", "HYM", "HIA", "HEM"]))
+            && !self.contains(0, 5, &["CHORE"])
+    }
+
+    fn condition_ch1(&self, index: isize) -> bool {
+        self.contains(0, 4, &["VAN ", "VON "])
+            || self.contains(0, 3, &["SCH"])
+            || self.contains(index - 2, 6, &["ORCHES", "ARCHIT", "ORCHID"])
+            || self.contains(index + 2, 1, &["T", "S"])
+            || ((self.contains(index - 1, 1, &["A", "O", "U", "E"]) || index == 0)
+                && (self.contains(index + 2, 1, L_R_N_M_B_H_F_V_W_SPACE)
+                    || index + 1 == self.last()))
+    }
+
+    fn condition_l0(&self, index: isize) -> bool {
+        let len = self.len() as isize;
+
+        (index == len - 3 && self.contains(index - 1, 4, &["ILLO", "ILLA", "ALLE"]))
+            || ((self.contains(len - 2, 2, &["AS", "OS"])
+                || self.contains(len - 1, 1, &["A", "O"]))
+                && self.contains(index - 1, 4, &["ALLE"]))
+    }
+
+    fn condition_m0(&self, index: isize) -> bool {
+        if self.char_at(index + 1) == 'M' {
+            return true;
+        }
+
+        self.contains(index - 1, 3, &["UMB"])
+            && (index + 1 == self.last() || self.contains(index + 2, 2, &["ER"]))
+    }
+}
+
+fn handle_aeiouy(result: &mut Encoding, index: isize) -> isize {
+    if index == 0 {
+        result.append("A");
+    }
+
+    index + 1
+}
+
+fn handle_c(word: &Word, result: &mut Encoding, mut index: isize) -> isize {
+    if word.condition_c0(index) {
+        result.append("K");
+        index += 2;
+    } else if index == 0 && word.contains(index, 6, &["CAESAR"]) {
+        result.append("S");
+        index += 2;
+    } else if word.contains(index, 2, &["CH"]) {
+        index = handle_ch(word, result, index);
+    } else if word.contains(index, 2, &["CZ"]) && !word.contains(index - 2, 4, &["WICZ"]) {
+        // "Czerny"
+        result.append_both("S", "X");
+        index += 2;
+    } else if word.contains(index + 1, 3, &["CIA"]) {
+        // "focaccia"
+        result.append("X");
+        index += 3;
+    } else if word.contains(index, 2, &["CC"]) && !(index == 1 && word.char_at(0) == 'M') {
+        // double "cc" but not "McClelland"
+        return handle_cc(word, result, index);
+    } else if word.contains(index, 2, &["CK", "CG", "CQ"]) {
+        result.append("K");
+        index += 2;
+    } else if word.contains(index, 2, &["CI", "CE", "CY"]) {
+        // italian vs. english
+        if word.contains(index, 3, &["CIO", "CIE", "CIA"]) {
+            result.append_both("S", "X");
+        } else {
+            result.append("S");
+        }
+        index += 2;
+    } else {
+        result.append("K");
+        if word.contains(index + 1, 2, &[" C", " Q", " G"]) {
+            // "Mac Caffrey", "Mac Gregor"
+            index += 3;
+        } else if word.contains(index + 1, 1, &["C", "K", "Q"])
+            && !word.contains(index + 1, 2, &["CE", "CI"])
+        {
+            index += 2;
+        } else {
+            index += 1;
+        }
+    }
+
+    index
+}
+
+fn handle_cc(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if word.contains(index + 2, 1, &["I", "E", "H"]) && !word.contains(index + 2, 2, &["HU"]) {
+        // "bellocchio" but not "bacchus"
+        if (index == 1 && word.char_at(index - 1) == 'A')
+            || word.contains(index - 1, 5, &["UCCEE", "UCCES"])
+        {
+            // "accident", "accede", "succeed"
+            result.append("KS");
+        } else {
+            // "bacci", "bertucci", other italian
+            result.append("X");
+        }
+        index + 3
+    } else {
+        // Pierce's rule
+        result.append("K");
+        index + 2
+    }
+}
+
+fn handle_ch(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if index > 0 && word.contains(index, 4, &["CHAE"]) {
+        // "Michael"
+        result.append_both("K", "X");
+    } else if word.condition_ch0(index) || word.condition_ch1(index) {
+        // greek roots ("chemistry", "chorus") and germanic 'ch' for 'kh' sound
+        result.append("K");
+    } else if index > 0 {
+        if word.contains(0, 2, &["MC"]) {
+            result.append("K");
+        } else {
+            result.append_both("X", "K");
+        }
+    } else {
+        result.append("X");
+    }
+
+    index + 2
+}
+
+fn handle_d(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if word.contains(index, 2, &["DG"]) {
+        if word.contains(index + 2, 1, &["I", "E", "Y"]) {
+            // "edge"
+            result.append("J");
+            index + 3
+        } else {
+            // "Edgar"
+            result.append("TK");
+            index + 2
+        }
+    } else if word.contains(index, 2, &["DT", "DD"]) {
+        result.append("T");
+        index + 2
+    } else {
+        result.append("T");
+        index + 1
+    }
+}
+
+fn handle_g(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    let slavo_germanic = word.slavo_germanic;
+
+    if word.char_at(index + 1) == 'H' {
+        handle_gh(word, result, index)
+    } else if word.char_at(index + 1) == 'N' {
+        if index == 1 && word.is_vowel_at(0) && !slavo_germanic {
+            result.append_both("KN", "N");
+        } else if !word.contains(index + 2, 2, &["EY"])
+            && word.char_at(index + 1) != 'Y'
+            && !slavo_germanic
+        {
+            result.append_both("N", "KN");
+        } else {
+            result.append("KN");
+        }
+        index + 2
+    } else if word.contains(index + 1, 2, &["LI"]) && !slavo_germanic {
+        result.append_both("KL", "L");
+        index + 2
+    } else if index == 0
+        && (word.char_at(index + 1) == 'Y'
+            || word.contains(index + 1, 2, ES_EP_EB_EL_EY_IB_IL_IN_IE_EI_ER))
+    {
+        // -ges-, -gep-, -gel-, -gie- at beginning
+        result.append_both("K", "J");
+        index + 2
+    } else if (word.contains(index + 1, 2, &["ER"]) || word.char_at(index + 1) == 'Y')
+        && !word.contains(0, 6, &["DANGER", "RANGER", "MANGER"])
+        && !word.contains(index - 1, 1, &["E", "I"])
+        && !word.contains(index - 1, 3, &["RGY", "OGY"])
+    {
+        // -ger-, -gy-
+        result.append_both("K", "J");
+        index + 2
+    } else if word.contains(index + 1, 1, &["E", "I", "Y"])
+        || word.contains(index - 1, 4, &["AGGI", "OGGI"])
+    {
+        // italian "biaggi"
+        if word.contains(0, 4, &["VAN ", "VON "])
+            || word.contains(0, 3, &["SCH"])
+            || word.contains(index + 1, 2, &["ET"])
+        {
+            // obvious germanic
+            result.append("K");
+        } else if word.contains(index + 1, 3, &["IER"]) {
+            result.append("J");
+        } else {
+            result.append_both("J", "K");
+        }
+        index + 2
+    } else if word.char_at(index + 1) == 'G' {
+        result.append("K");
+        index + 2
+    } else {
+        result.append("K");
+        index + 1
+    }
+}
+
+fn handle_gh(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if index > 0 && !word.is_vowel_at(index - 1) {
+        result.append("K");
+    } else if index == 0 {
+        if word.char_at(index + 2) == 'I' {
+            result.append("J");
+        } else {
+            result.append("K");
+        }
+    } else if (index > 1 && word.contains(index - 2, 1, &["B", "H", "D"]))
+        || (index > 2 && word.contains(index - 3, 1, &["B", "H", "D"]))
+        || (index > 3 && word.contains(index - 4, 1, &["B", "H"]))
+    {
+        // Parker's rule, "hugh"
+    } else if index > 2
+        && word.char_at(index - 1) == 'U'
+        && word.contains(index - 3, 1, &["C", "G", "L", "R", "T"])
+    {
+        // "laugh", "McLaughlin", "cough", "gough", "rough", "tough"
+        result.append("F");
+    } else if index > 0 && word.char_at(index - 1) != 'I' {
+        result.append("K");
+    }
+
+    index + 2
+}
+
+fn handle_h(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    // only keep if first & before vowel or between 2 vowels
+    if (index == 0 || word.is_vowel_at(index - 1)) && word.is_vowel_at(index + 1) {
+        result.append("H");
+        index + 2
+    } else {
+        index + 1
+    }
+}
+
+fn handle_j(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if word.contains(index, 4, &["JOSE"]) || word.contains(0, 4, &["SAN "]) {
+        // obvious spanish, "Jose", "San Jacinto"
+        if (index == 0 && word.char_at(index + 4) == ' ')
+            || word.len() == 4
+            || word.contains(0, 4, &["SAN "])
+        {
+            result.append("H");
+        } else {
+            result.append_both("J", "H");
+        }
+        return index + 1;
+    }
+
+    if index == 0 && !word.contains(index, 4, &["JOSE"]) {
+        result.append_both("J", "A");
+    } else if word.is_vowel_at(index - 1)
+        && !word.slavo_germanic
+        && matches!(word.char_at(index + 1), 'A' | 'O')
+    {
+        result.append_both("J", "H");
+    } else if index == word.last() {
+        result.append_both("J", " ");
+    } else if !word.contains(index + 1, 1, L_T_K_S_N_M_B_Z)
+        && !word.contains(index - 1, 1, &["S", "K", "L"])
+    {
+        result.append("J");
+    }
+
+    if word.char_at(index + 1) == 'J' {
+        index + 2
+    } else {
+        index + 1
+    }
+}
+
+fn handle_l(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if word.char_at(index + 1) == 'L' {
+        if word.condition_l0(index) {
+            result.append_primary("L");
+        } else {
+            result.append("L");
+        }
+        index + 2
+    } else {
+        result.append("L");
+        index + 1
+    }
+}
+
+fn handle_p(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if word.char_at(index + 1) == 'H' {
+        result.append("F");
+        index + 2
+    } else {
+        result.append("P");
+        if word.contains(index + 1, 1, &["P", "B"]) {
+            index + 2
+        } else {
+            index + 1
+        }
+    }
+}
+
+fn handle_r(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if index == word.last()
+        && !word.slavo_germanic
+        && word.contains(index - 2, 2, &["IE"])
+        && !word.contains(index - 4, 2, &["ME", "MA"])
+    {
+        result.append_alternate("R");
+    } else {
+        result.append("R");
+    }
+
+    if word.char_at(index + 1) == 'R' {
+        index + 2
+    } else {
+        index + 1
+    }
+}
+
+fn handle_s(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if word.contains(index - 1, 3, &["ISL", "YSL"]) {
+        // "island", "isle", "carlisle", "carlysle"
+        index + 1
+    } else if index == 0 && word.contains(index, 5, &["SUGAR"]) {
+        result.append_both("X", "S");
+        index + 1
+    } else if word.contains(index, 2, &["SH"]) {
+        if word.contains(index + 1, 4, &["HEIM", "HOEK", "HOLM", "HOLZ"]) {
+            // germanic
+            result.append("S");
+        } else {
+            result.append("X");
+        }
+        index + 2
+    } else if word.contains(index, 3, &["SIO", "SIA"]) || word.contains(index, 4, &["SIAN"]) {
+        // italian and armenian
+        if word.slavo_germanic {
+            result.append("S");
+        } else {
+            result.append_both("S", "X");
+        }
+        index + 3
+    } else if (index == 0 && word.contains(index + 1, 1, &["M", "N", "L", "W"]))
+        || word.contains(index + 1, 1, &["Z"])
+    {
+        // german & anglicisations, e.g. "smith" matches "schmidt" and "snider"
+        // matches "schneider". Also -sz- in slavic languages.
+        result.append_both("S", "X");
+        if word.contains(index + 1, 1, &["Z"]) {
+            index + 2
+        } else {
+            index + 1
+        }
+    } else if word.contains(index, 2, &["SC"]) {
+        handle_sc(word, result, index)
+    } else {
+        if index == word.last() && word.contains(index - 2, 2, &["AI", "OI"]) {
+            // french, e.g. "resnais", "artois"
+            result.append_alternate("S");
+        } else {
+            result.append("S");
+        }
+
+        if word.contains(index + 1, 1, &["S", "Z"]) {
+            index + 2
+        } else {
+            index + 1
+        }
+    }
+}
+
+fn handle_sc(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if word.char_at(index + 2) == 'H' {
+        // Schlesinger's rule
+        if word.contains(index + 3, 2, &["OO", "ER", "EN", "UY", "ED", "EM"]) {
+            // dutch origin, e.g. "school", "schooner"
+            if word.contains(index + 3, 2, &["ER", "EN"]) {
+                // "schermerhorn", "schenker"
+                result.append_both("X", "SK");
+            } else {
+                result.append("SK");
+            }
+        } else if index == 0 && !word.is_vowel_at(3) && word.char_at(3) != 'W' {
+            result.append_both("X", "S");
+        } else {
+            result.append("X");
+        }
+    } else if word.contains(index + 2, 1, &["I", "E", "Y"]) {
+        result.append("S");
+    } else {
+        result.append("SK");
+    }
+
+    index + 3
+}
+
+fn handle_t(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if word.contains(index, 4, &["TION"]) || word.contains(index, 3, &["TIA", "TCH"]) {
+        result.append("X");
+        index + 3
+    } else if word.contains(index, 2, &["TH"]) || word.contains(index, 3, &["TTH"]) {
+        if word.contains(index + 2, 2, &["OM", "AM"])
+            || word.contains(0, 4, &["VAN ", "VON "])
+            || word.contains(0, 3, &["SCH"])
+        {
+            // "thomas", "thames" or germanic
+            result.append("T");
+        } else {
+            result.append_both("0", "T");
+        }
+        index + 2
+    } else {
+        result.append("T");
+        if word.contains(index + 1, 1, &["T", "D"]) {
+            index + 2
+        } else {
+            index + 1
+        }
+    }
+}
+
+fn handle_w(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if word.contains(index, 2, &["WR"]) {
+        // can also be in the middle of a word
+        result.append("R");
+        index + 2
+    } else if index == 0 && (word.is_vowel_at(index + 1) || word.contains(index, 2, &["WH"])) {
+        if word.is_vowel_at(index + 1) {
+            // "Wasserman" should match "Vasserman"
+            result.append_both("A", "F");
+        } else {
+            // "Uomo" should match "Womo"
+            result.append("A");
+        }
+        index + 1
+    } else if (index == word.last() && word.is_vowel_at(index - 1))
+        || word.contains(index - 1, 5, &["EWSKI", "EWSKY", "OWSKI", "OWSKY"])
+        || word.contains(0, 3, &["SCH"])
+    {
+        // "Arnow" should match "Arnoff"
+        result.append_alternate("F");
+        index + 1
+    } else if word.contains(index, 4, &["WICZ", "WITZ"]) {
+        // polish, e.g. "filipowicz"
+        result.append_both("TS", "FX");
+        index + 4
+    } else {
+        index + 1
+    }
+}
+
+fn handle_x(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if index == 0 {
+        result.append("S");
+        return index + 1;
+    }
+
+    if !(index == word.last()
+        && (word.contains(index - 3, 3, &["IAU", "EAU"])
+            || word.contains(index - 2, 2, &["AU", "OU"])))
+    {
+        // not french, e.g. "breaux"
+        result.append("KS");
+    }
+
+    if word.contains(index + 1, 1, &["C", "X"]) {
+        index + 2
+    } else {
+        index + 1
+    }
+}
+
+fn handle_z(word: &Word, result: &mut Encoding, index: isize) -> isize {
+    if word.char_at(index + 1) == 'H' {
+        // chinese pinyin, e.g. "zhao"
+        result.append("J");
+        index + 2
+    } else {
+        if word.contains(index + 1, 2, &["ZO", "ZI", "ZA"])
+            || (word.slavo_germanic && index > 0 && word.char_at(index - 1) != 'T')
+        {
+            result.append_both("S", "TS");
+        } else {
+            result.append("S");
+        }
+
+        if word.char_at(index + 1) == 'Z' {
+            index + 2
+        } else {
+            index + 1
+        }
+    }
+}
+
+fn skip_double(word: &Word, index: isize, c: char) -> isize {
+    if word.char_at(index + 1) == c {
+        index + 2
+    } else {
+        index + 1
+    }
+}
+
+/// Encode a single word. Both codes are empty if the word has no letters.
+pub fn encode(word: &str) -> Codes {
+    let word = Word::new(word);
+    let mut result = Encoding::new();
+
+    let mut index: isize = if word.is_silent_start() { 1 } else { 0 };
+
+    while !result.is_complete() && index <= word.last() {
+        index = match word.char_at(index) {
+            'A' | 'E' | 'I' | 'O' | 'U' | 'Y' => handle_aeiouy(&mut result, index),
+            'B' => {
+                result.append("P");
+                skip_double(&word, index, 'B')
+            }
+            'Ç' => {
+                result.append("S");
+                index + 1
+            }
+            'C' => handle_c(&word, &mut result, index),
+            'D' => handle_d(&word, &mut result, index),
+            'F' => {
+                result.append("F");
+                skip_double(&word, index, 'F')
+            }
+            'G' => handle_g(&word, &mut result, index),
+            'H' => handle_h(&word, &mut result, index),
+            'J' => handle_j(&word, &mut result, index),
+            'K' => {
+                result.append("K");
+                skip_double(&word, index, 'K')
+            }
+            'L' => handle_l(&word, &mut result, index),
+            'M' => {
+                result.append("M");
+                if word.condition_m0(index) {
+                    index + 2
+                } else {
+                    index + 1
+                }
+            }
+            'N' => {
+                result.append("N");
+                skip_double(&word, index, 'N')
+            }
+            'Ñ' => {
+                result.append("N");
+                index + 1
+            }
+            'P' => handle_p(&word, &mut result, index),
+            'Q' => {
+                result.append("K");
+                skip_double(&word, index, 'Q')
+            }
+            'R' => handle_r(&word, &mut result, index),
+            'S' => handle_s(&word, &mut result, index),
+            'T' => handle_t(&word, &mut result, index),
+            'V' => {
+                result.append("F");
+                skip_double(&word, index, 'V')
+            }
+            'W' => handle_w(&word, &mut result, index),
+            'X' => handle_x(&word, &mut result, index),
+            'Z' => handle_z(&word, &mut result, index),
+            _ => index + 1,
+        };
+    }
+
+    Codes {
+        primary: result.primary,
+        alternate: result.alternate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(word: &str) -> (String, String) {
+        let codes = encode(word);
+        (codes.primary, codes.alternate)
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(codes("Smith"), ("SM0".to_string(), "XMT".to_string()));
+        assert_eq!(codes("Smyth"), codes("Smith"));
+        assert_eq!(codes("Schmidt"), ("XMT".to_string(), "SMT".to_string()));
+        assert_eq!(codes("Jose"), ("HS".to_string(), "HS".to_string()));
+        assert_eq!(codes("Knight"), ("NT".to_string(), "NT".to_string()));
+        assert_eq!(codes(""), (String::new(), String::new()));
+    }
+}
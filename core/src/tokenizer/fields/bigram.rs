@@ -3,19 +3,27 @@
 //
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
+use hashbrown::HashSet;
 use tantivy::tokenizer::BoxTokenStream;
+use whatlang::Lang;
+
+use crate::stopwords;
 
 use super::{default::DefaultTokenizer, ngram::NGramTokenStream};
 
 #[derive(Clone)]
 pub struct BigramTokenizer {
     inner_tokenizer: DefaultTokenizer,
+    skip_stopwords: bool,
+    force_language: Option<Lang>,
 }
 
 impl Default for BigramTokenizer {
     fn default() -> Self {
         Self {
             inner_tokenizer: DefaultTokenizer::with_stopwords(vec![]),
+            skip_stopwords: false,
+            force_language: None,
         }
     }
 }
@@ -24,17 +32,77 @@ impl BigramTokenizer {
     pub fn as_str() -> &'static str {
         "bigram_tokenizer"
     }
+
+    /// Drop the stopwords of the language before forming the bigrams, so
+    /// "lord of the rings" gives "lordrings" instead of "lordof", "ofthe" and "therings".
+    /// The language is detected from the text if `lang` is `None`.
+    pub fn skip_stopwords(lang: Option<Lang>) -> Self {
+        Self {
+            skip_stopwords: true,
+            force_language: lang,
+            ..Default::default()
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        if self.skip_stopwords {
+            "bigram_tokenizer_skip_stopwords"
+        } else {
+            Self::as_str()
+        }
+    }
 }
 impl tantivy::tokenizer::Tokenizer for BigramTokenizer {
     type TokenStream<'a> = BoxTokenStream<'a>;
 
     fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
-        let inner_stream = self.inner_tokenizer.token_stream(text);
+        let mut inner_stream = self.inner_tokenizer.token_stream(text);
+
+        if self.skip_stopwords {
+            let stopwords = self
+                .force_language
+                .or_else(|| whatlang::detect_lang(text))
+                .as_ref()
+                .and_then(stopwords::get);
+
+            if let Some(stopwords) = stopwords {
+                inner_stream = BoxTokenStream::new(SkipStopwords {
+                    inner: inner_stream,
+                    stopwords,
+                });
+            }
+        }
+
         let stream: NGramTokenStream<2> = NGramTokenStream::new(inner_stream);
         BoxTokenStream::new(stream)
     }
 }
 
+struct SkipStopwords<'a> {
+    inner: BoxTokenStream<'a>,
+    stopwords: &'static HashSet<String>,
+}
+
+impl<'a> tantivy::tokenizer::TokenStream for SkipStopwords<'a> {
+    fn advance(&mut self) -> bool {
+        while self.inner.advance() {
+            if !self.stopwords.contains(&self.inner.token().text) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn token(&self) -> &tantivy::tokenizer::Token {
+        self.inner.token()
+    }
+
+    fn token_mut(&mut self) -> &mut tantivy::tokenizer::Token {
+        self.inner.token_mut()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use lending_iter::LendingIterator;
@@ -42,8 +110,11 @@ mod tests {
 
     use super::*;
     fn tokenize_bigram(s: &str) -> Vec<String> {
+        tokenize(BigramTokenizer::default(), s)
+    }
+
+    fn tokenize(mut tokenizer: BigramTokenizer, s: &str) -> Vec<String> {
         let mut res = Vec::new();
-        let mut tokenizer = BigramTokenizer::default();
         let mut stream = tokenizer.token_stream(s);
 
         let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
@@ -68,4 +139,28 @@ mod tests {
 
         assert_eq!(tokenize_bigram("this.is"), vec!["this.", ".is"]);
     }
+
+    #[test]
+    fn skip_stopwords() {
+        let skip = |s: &str| tokenize(BigramTokenizer::skip_stopwords(Some(Lang::Eng)), s);
+
+        assert_eq!(
+            tokenize_bigram("lord of the rings"),
+            vec!["lordof", "ofthe", "therings"]
+        );
+        assert_eq!(skip("lord of the rings"), vec!["lordrings"]);
+
+        assert!(skip("").is_empty());
+        assert_eq!(skip("the art"), vec!["art"]);
+        assert_eq!(skip("this is a test"), skip("test"));
+
+        // no stopwords are known for the language, so nothing is skipped
+        assert_eq!(
+            tokenize(
+                BigramTokenizer::skip_stopwords(Some(Lang::Swe)),
+                "lord of the rings"
+            ),
+            tokenize_bigram("lord of the rings")
+        );
+    }
 }
@@ -0,0 +1,254 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use tantivy::tokenizer::BoxTokenStream;
+
+/// Splits identifiers like `getUserName` or `XMLHttpRequest` into their parts.
+///
+/// Each word is emitted in lowercase at its position, followed by its parts
+/// at overlapping positions, so both `xmlhttprequest` and `http` match the word.
+#[derive(Clone, Default)]
+pub struct CamelCaseTokenizer;
+
+impl CamelCaseTokenizer {
+    pub fn as_str() -> &'static str {
+        "camel_case"
+    }
+}
+
+impl tantivy::tokenizer::Tokenizer for CamelCaseTokenizer {
+    type TokenStream<'a> = BoxTokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        BoxTokenStream::new(CamelCaseTokenStream::new(text))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharKind {
+    Lower,
+    Upper,
+    Digit,
+    Other,
+}
+
+impl CharKind {
+    fn of(c: char) -> Self {
+        if c.is_uppercase() {
+            CharKind::Upper
+        } else if c.is_numeric() {
+            CharKind::Digit
+        } else if c.is_alphabetic() {
+            CharKind::Lower
+        } else {
+            CharKind::Other
+        }
+    }
+}
+
+/// Byte ranges of the parts of `word`. A new part starts at a lowercase to
+/// uppercase transition, before the last uppercase letter of an acronym that
+/// is followed by lowercase letters (`HTMLParser` -> `HTML`, `Parser`)
+/// and between letters and digits.
+fn camel_case_parts(word: &str) -> Vec<Range<usize>> {
+    let chars: Vec<(usize, CharKind)> = word
+        .char_indices()
+        .map(|(i, c)| (i, CharKind::of(c)))
+        .collect();
+
+    let mut parts = Vec::new();
+    let mut start = 0;
+
+    for (i, window) in chars.windows(2).enumerate() {
+        let (offset, cur) = window[1];
+        let prev = window[0].1;
+        let next = chars.get(i + 2).map(|(_, kind)| *kind);
+
+        let is_boundary = match (prev, cur) {
+            (CharKind::Lower, CharKind::Upper) => true,
+            (CharKind::Upper, CharKind::Upper) => next == Some(CharKind::Lower),
+            (CharKind::Digit, CharKind::Digit) => false,
+            (CharKind::Digit, _) | (_, CharKind::Digit) => true,
+            _ => false,
+        };
+
+        if is_boundary {
+            parts.push(start..offset);
+            start = offset;
+        }
+    }
+
+    if start < word.len() {
+        parts.push(start..word.len());
+    }
+
+    parts
+}
+
+pub struct CamelCaseTokenStream<'a> {
+    text: &'a str,
+    words: Box<dyn Iterator<Item = Range<usize>> + 'a>,
+    pending: VecDeque<tantivy::tokenizer::Token>,
+    token: tantivy::tokenizer::Token,
+    next_position: usize,
+}
+
+impl<'a> CamelCaseTokenStream<'a> {
+    fn new(text: &'a str) -> Self {
+        let words = text
+            .char_indices()
+            .chain(std::iter::once((text.len(), ' ')))
+            .scan(None, |start, (i, c)| {
+                if c.is_alphanumeric() {
+                    if start.is_none() {
+                        *start = Some(i);
+                    }
+                    Some(None)
+                } else {
+                    Some(start.take().map(|start| start..i))
+                }
+            })
+            .flatten();
+
+        Self {
+            text,
+            words: Box::new(words),
+            pending: VecDeque::new(),
+            token: tantivy::tokenizer::Token::default(),
+            next_position: 0,
+        }
+    }
+
+    fn push_word(&mut self, word: Range<usize>) {
+        let parts = camel_case_parts(&self.text[word.clone()]);
+        let position = self.next_position;
+        self.next_position += parts.len().max(1);
+
+        self.pending.push_back(tantivy::tokenizer::Token {
+            offset_from: word.start,
+            offset_to: word.end,
+            position,
+            text: self.text[word.clone()].to_lowercase(),
+            position_length: parts.len().max(1),
+        });
+
+        if parts.len() > 1 {
+            for (i, part) in parts.into_iter().enumerate() {
+                self.pending.push_back(tantivy::tokenizer::Token {
+                    offset_from: word.start + part.start,
+                    offset_to: word.start + part.end,
+                    position: position + i,
+                    text: self.text[word.start + part.start..word.start + part.end].to_lowercase(),
+                    position_length: 1,
+                });
+            }
+        }
+    }
+}
+
+impl<'a> tantivy::tokenizer::TokenStream for CamelCaseTokenStream<'a> {
+    fn advance(&mut self) -> bool {
+        if self.pending.is_empty() {
+            match self.words.next() {
+                Some(word) => self.push_word(word),
+                None => return false,
+            }
+        }
+
+        match self.pending.pop_front() {
+            Some(token) => {
+                self.token = token;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn token(&self) -> &tantivy::tokenizer::Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut tantivy::tokenizer::Token {
+        &mut self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lending_iter::LendingIterator;
+    use tantivy::tokenizer::Tokenizer;
+
+    use super::*;
+
+    fn tokenize(s: &str) -> Vec<(String, usize)> {
+        let mut res = Vec::new();
+        let mut tokenizer = CamelCaseTokenizer;
+        let mut stream = tokenizer.token_stream(s);
+
+        let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
+        while let Some(token) = it.next() {
+            res.push((token.text.clone(), token.position));
+        }
+
+        res
+    }
+
+    fn parts(word: &str) -> Vec<&str> {
+        camel_case_parts(word)
+            .into_iter()
+            .map(|range| &word[range])
+            .collect()
+    }
+
+    #[test]
+    fn split_parts() {
+        assert_eq!(parts("getUserName"), vec!["get", "User", "Name"]);
+        assert_eq!(parts("HTMLParser"), vec!["HTML", "Parser"]);
+        assert_eq!(parts("XMLHttpRequest"), vec!["XML", "Http", "Request"]);
+        assert_eq!(parts("utf8Decoder"), vec!["utf", "8", "Decoder"]);
+        assert_eq!(parts("base64"), vec!["base", "64"]);
+        assert_eq!(parts("HTML"), vec!["HTML"]);
+        assert_eq!(parts("parser"), vec!["parser"]);
+    }
+
+    #[test]
+    fn overlapping_positions() {
+        assert!(tokenize("").is_empty());
+
+        assert_eq!(
+            tokenize("HTMLParser"),
+            vec![
+                ("htmlparser".to_string(), 0),
+                ("html".to_string(), 0),
+                ("parser".to_string(), 1),
+            ]
+        );
+
+        assert_eq!(
+            tokenize("new XMLHttpRequest()"),
+            vec![
+                ("new".to_string(), 0),
+                ("xmlhttprequest".to_string(), 1),
+                ("xml".to_string(), 1),
+                ("http".to_string(), 2),
+                ("request".to_string(), 3),
+            ]
+        );
+
+        assert_eq!(
+            tokenize("getUserName returns"),
+            vec![
+                ("getusername".to_string(), 0),
+                ("get".to_string(), 0),
+                ("user".to_string(), 1),
+                ("name".to_string(), 2),
+                ("returns".to_string(), 3),
+            ]
+        );
+    }
+}
@@ -3,14 +3,31 @@
 //
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
+use hashbrown::HashSet;
 use tantivy::tokenizer::{BoxTokenStream, StopWordFilter, TextAnalyzer};
+use whatlang::Lang;
 
 use crate::tokenizer::{self, normalizer, Normalize, Tokenize};
 
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct DefaultTokenizer {
     stopwords: Option<Vec<String>>,
     analyzer: Option<TextAnalyzer>,
+    fold_diacritics: bool,
+    remove_lang_stopwords: bool,
+    stopword_language: Option<Lang>,
+}
+
+impl Default for DefaultTokenizer {
+    fn default() -> Self {
+        Self {
+            stopwords: None,
+            analyzer: None,
+            fold_diacritics: true,
+            remove_lang_stopwords: false,
+            stopword_language: None,
+        }
+    }
 }
 
 impl DefaultTokenizer {
@@ -21,7 +38,40 @@ impl DefaultTokenizer {
     pub fn with_stopwords(stopwords: Vec<String>) -> Self {
         Self {
             stopwords: Some(stopwords),
-            analyzer: None,
+            ..Default::default()
+        }
+    }
+
+    /// Keep diacritics in the tokens, so e.g. "café" and "cafe" are different terms.
+    /// By default they are stripped to make search accent-insensitive, but some
+    /// fields hold text where the diacritics carry meaning.
+    ///
+    /// The same tokenizer must be used at index and query time for the field.
+    /// No field uses it yet, so it is not registered with the index; add it to
+    /// `register_tokenizers` before using it on a field.
+    pub fn preserve_diacritics(mut self) -> Self {
+        self.fold_diacritics = false;
+        self
+    }
+
+    /// Remove the stopwords of the language, so "the cat" is tokenized as "cat".
+    /// The language is never detected from the text, since a single query term
+    /// says little about its language, so nothing is removed if `lang` is `None`.
+    ///
+    /// Positions are assigned after the stopwords have been removed, so phrases
+    /// still match when the query is tokenized the same way.
+    pub fn remove_stopwords(mut self, lang: Option<Lang>) -> Self {
+        self.remove_lang_stopwords = true;
+        self.stopword_language = lang;
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        match (self.fold_diacritics, self.remove_lang_stopwords) {
+            (true, false) => Self::as_str(),
+            (false, false) => "tokenizer_preserve_diacritics",
+            (true, true) => "tokenizer_remove_stopwords",
+            (false, true) => "tokenizer_preserve_diacritics_remove_stopwords",
         }
     }
 }
@@ -29,7 +79,18 @@ impl tantivy::tokenizer::Tokenizer for DefaultTokenizer {
     type TokenStream<'a> = BoxTokenStream<'a>;
 
     fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
-        let builder = TextAnalyzer::builder(Normal);
+        let lang_stopwords = if self.remove_lang_stopwords {
+            self.stopword_language
+                .as_ref()
+                .and_then(crate::stopwords::get)
+        } else {
+            None
+        };
+
+        let builder = TextAnalyzer::builder(Normal {
+            fold_diacritics: self.fold_diacritics,
+            stopwords: lang_stopwords,
+        });
 
         self.analyzer = if let Some(stopwords) = &self.stopwords {
             Some(
@@ -46,7 +107,19 @@ impl tantivy::tokenizer::Tokenizer for DefaultTokenizer {
 }
 
 #[derive(Clone)]
-pub struct Normal;
+pub struct Normal {
+    fold_diacritics: bool,
+    stopwords: Option<&'static HashSet<String>>,
+}
+
+impl Default for Normal {
+    fn default() -> Self {
+        Self {
+            fold_diacritics: true,
+            stopwords: None,
+        }
+    }
+}
 
 pub struct NormalTokenStream<'a> {
     stream: Box<dyn Iterator<Item = tokenizer::Token<'a>> + 'a>,
@@ -58,12 +131,20 @@ impl tantivy::tokenizer::Tokenizer for Normal {
     type TokenStream<'a> = BoxTokenStream<'a>;
 
     fn token_stream<'a>(&mut self, text: &'a str) -> Self::TokenStream<'a> {
-        let stream = Box::new(
-            text.tokenize()
-                .normalize(&normalizer::Lowercase)
-                .normalize(&normalizer::UnicodeNFKD)
-                .normalize(&normalizer::UnicodeDiacritics),
-        );
+        let stopwords = self.stopwords;
+        let stream = text
+            .tokenize()
+            .normalize(&normalizer::Lowercase)
+            .filter(move |token| {
+                !stopwords.is_some_and(|stopwords| stopwords.contains(token.text()))
+            })
+            .normalize(&normalizer::UnicodeNFKD);
+
+        let stream: Box<dyn Iterator<Item = tokenizer::Token<'a>> + 'a> = if self.fold_diacritics {
+            Box::new(stream.normalize(&normalizer::UnicodeDiacritics))
+        } else {
+            Box::new(stream)
+        };
 
         BoxTokenStream::new(NormalTokenStream::new_boxed(stream))
     }
@@ -116,8 +197,11 @@ mod tests {
     use tantivy::tokenizer::Tokenizer as _;
 
     fn tokenize_default(s: &str) -> Vec<String> {
+        tokenize(DefaultTokenizer::default(), s)
+    }
+
+    fn tokenize(mut tokenizer: DefaultTokenizer, s: &str) -> Vec<String> {
         let mut res = Vec::new();
-        let mut tokenizer = DefaultTokenizer::default();
         let mut stream = tokenizer.token_stream(s);
         let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
 
@@ -206,6 +290,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn diacritic_folding() {
+        assert_eq!(tokenize_default("Café"), vec!["cafe"]);
+        assert_eq!(tokenize_default("café"), tokenize_default("cafe"));
+        assert_eq!(
+            tokenize_default("naïve Ångström"),
+            vec!["naive", "angstrom"]
+        );
+
+        let preserving = DefaultTokenizer::default().preserve_diacritics();
+        assert_ne!(
+            tokenize(preserving.clone(), "café"),
+            tokenize(preserving.clone(), "cafe")
+        );
+        assert_eq!(
+            tokenize(preserving.clone(), "Café"),
+            tokenize(preserving, "café")
+        );
+    }
+
+    #[test]
+    fn remove_stopwords() {
+        let removing = DefaultTokenizer::default().remove_stopwords(Some(Lang::Eng));
+        assert_eq!(removing.name(), "tokenizer_remove_stopwords");
+        assert_eq!(
+            tokenize(removing.clone(), "The Lord of the Rings"),
+            vec!["lord", "rings"]
+        );
+
+        let mut tokenizer = removing;
+        let mut stream = tokenizer.token_stream("the cat and the dog");
+        let mut positions = Vec::new();
+        while tantivy::tokenizer::TokenStream::advance(&mut stream) {
+            positions.push(tantivy::tokenizer::TokenStream::token(&stream).position);
+        }
+        assert_eq!(positions, vec![0, 1]);
+
+        // languages without a stopword list are left untouched
+        assert_eq!(
+            tokenize(
+                DefaultTokenizer::default().remove_stopwords(Some(Lang::Swe)),
+                "the cat"
+            ),
+            vec!["the", "cat"]
+        );
+
+        // the language is not guessed from the text
+        assert_eq!(
+            tokenize(
+                DefaultTokenizer::default().remove_stopwords(None),
+                "the cat"
+            ),
+            vec!["the", "cat"]
+        );
+
+        assert_eq!(tokenize_default("the cat"), vec!["the", "cat"]);
+    }
+
     proptest! {
         #[test]
         fn prop_default_tokenization(s: String) {
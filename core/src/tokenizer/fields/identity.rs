@@ -5,18 +5,40 @@ use tantivy::tokenizer::BoxTokenStream;
 //
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 #[derive(Clone, Default, Debug)]
-pub struct Identity {}
+pub struct Identity {
+    lowercase: bool,
+}
 
 impl Identity {
     pub fn as_str() -> &'static str {
         "identity_tokenizer"
     }
+
+    /// Lowercase the token so e.g. "Tag" and "tag" are the same term.
+    /// The text is otherwise kept as a single token.
+    pub fn lowercase() -> Self {
+        Self { lowercase: true }
+    }
+
+    pub fn name(&self) -> &'static str {
+        if self.lowercase {
+            "identity_tokenizer_lowercase"
+        } else {
+            Self::as_str()
+        }
+    }
 }
 impl tantivy::tokenizer::Tokenizer for Identity {
     type TokenStream<'a> = BoxTokenStream<'a>;
 
     fn token_stream<'a>(&mut self, text: &'a str) -> Self::TokenStream<'a> {
-        BoxTokenStream::new(IdentityTokenStream::from(text.to_string()))
+        let text = if self.lowercase {
+            text.to_lowercase()
+        } else {
+            text.to_string()
+        };
+
+        BoxTokenStream::new(IdentityTokenStream::from(text))
     }
 }
 pub struct IdentityTokenStream {
@@ -66,8 +88,11 @@ mod tests {
     use tantivy::tokenizer::Tokenizer as _;
 
     fn tokenize_identity(s: &str) -> Vec<String> {
+        tokenize(Identity::default(), s)
+    }
+
+    fn tokenize(mut tokenizer: Identity, s: &str) -> Vec<String> {
         let mut res = Vec::new();
-        let mut tokenizer = Identity {};
         let mut stream = tokenizer.token_stream(s);
         let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
 
@@ -83,4 +108,20 @@ mod tests {
         assert_eq!(tokenize_identity("this is a test"), vec!["this is a test"]);
         assert_eq!(tokenize_identity("a-b"), vec!["a-b"]);
     }
+
+    #[test]
+    fn lowercase_identity() {
+        assert_eq!(tokenize(Identity::lowercase(), "Tag"), vec!["tag"]);
+        assert_eq!(
+            tokenize(Identity::lowercase(), "Tag"),
+            tokenize(Identity::lowercase(), "tag")
+        );
+        assert_eq!(
+            tokenize(Identity::lowercase(), "Rust Programming"),
+            vec!["rust programming"]
+        );
+
+        assert_ne!(tokenize_identity("Tag"), tokenize_identity("tag"));
+        assert_eq!(tokenize_identity("Tag"), vec!["Tag"]);
+    }
 }
@@ -4,13 +4,16 @@
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
 use tantivy::tokenizer::BoxTokenStream;
+use whatlang::Lang;
 
 pub use self::{
-    bigram::BigramTokenizer, default::DefaultTokenizer, identity::Identity, json::FlattenedJson,
-    json::JsonField, split_newlines::NewlineTokenizer, stemmed::Stemmed, trigram::TrigramTokenizer,
+    bigram::BigramTokenizer, camel_case::CamelCaseTokenizer, default::DefaultTokenizer,
+    identity::Identity, json::FlattenedJson, json::JsonField, phonetic::PhoneticTokenizer,
+    split_newlines::NewlineTokenizer, stemmed::Stemmed, trigram::TrigramTokenizer,
     url::UrlTokenizer, words::WordTokenizer,
 };
 
+mod camel_case;
 mod default;
 mod identity;
 mod json;
@@ -22,8 +25,17 @@ mod words;
 
 mod bigram;
 mod ngram;
+mod phonetic;
 mod trigram;
 
+/// Whether the stopwords of the language are removed when a field is tokenized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StopwordPolicy {
+    #[default]
+    Keep,
+    Remove,
+}
+
 #[derive(Clone)]
 pub enum FieldTokenizer {
     Default(DefaultTokenizer),
@@ -35,20 +47,35 @@ pub enum FieldTokenizer {
     Url(UrlTokenizer),
     Newline(NewlineTokenizer),
     Words(WordTokenizer),
+    CamelCase(CamelCaseTokenizer),
+    Phonetic(PhoneticTokenizer),
 }
 
 impl FieldTokenizer {
     pub fn as_str(&self) -> &'static str {
         match self {
-            FieldTokenizer::Default(_) => DefaultTokenizer::as_str(),
+            FieldTokenizer::Default(tokenizer) => tokenizer.name(),
             FieldTokenizer::Stemmed(_) => Stemmed::as_str(),
-            FieldTokenizer::Identity(_) => Identity::as_str(),
-            FieldTokenizer::Bigram(_) => BigramTokenizer::as_str(),
-            FieldTokenizer::Trigram(_) => TrigramTokenizer::as_str(),
+            FieldTokenizer::Identity(tokenizer) => tokenizer.name(),
+            FieldTokenizer::Bigram(tokenizer) => tokenizer.name(),
+            FieldTokenizer::Trigram(tokenizer) => tokenizer.name(),
             FieldTokenizer::Json(_) => JsonField::as_str(),
-            FieldTokenizer::Url(_) => UrlTokenizer::as_str(),
+            FieldTokenizer::Url(tokenizer) => tokenizer.name(),
             FieldTokenizer::Newline(_) => NewlineTokenizer::as_str(),
-            FieldTokenizer::Words(_) => WordTokenizer::as_str(),
+            FieldTokenizer::Words(tokenizer) => tokenizer.name(),
+            FieldTokenizer::CamelCase(_) => CamelCaseTokenizer::as_str(),
+            FieldTokenizer::Phonetic(_) => PhoneticTokenizer::as_str(),
+        }
+    }
+
+    /// Only the default tokenizer can remove stopwords.
+    /// Other tokenizers are returned unchanged.
+    pub fn with_stopword_policy(self, policy: StopwordPolicy, lang: Option<&Lang>) -> Self {
+        match (self, policy) {
+            (FieldTokenizer::Default(tokenizer), StopwordPolicy::Remove) => {
+                FieldTokenizer::Default(tokenizer.remove_stopwords(lang.copied()))
+            }
+            (tokenizer, _) => tokenizer,
         }
     }
 }
@@ -78,6 +105,8 @@ impl tantivy::tokenizer::Tokenizer for FieldTokenizer {
             FieldTokenizer::Url(tokenizer) => tokenizer.token_stream(text),
             FieldTokenizer::Newline(tokenizer) => tokenizer.token_stream(text),
             FieldTokenizer::Words(tokenizer) => tokenizer.token_stream(text),
+            FieldTokenizer::CamelCase(tokenizer) => tokenizer.token_stream(text),
+            FieldTokenizer::Phonetic(tokenizer) => tokenizer.token_stream(text),
         }
     }
 }
@@ -5,6 +5,9 @@
 
 use tantivy::tokenizer::BoxTokenStream;
 
+/// The character [`PaddedCharNGramTokenStream`] puts at the start and end of every word.
+pub const BOUNDARY_MARKER: char = '$';
+
 pub struct NGramTokenStream<'a, const N: usize> {
     inner: BoxTokenStream<'a>,
     token: tantivy::tokenizer::Token,
@@ -79,3 +82,79 @@ impl<'a, const N: usize> tantivy::tokenizer::TokenStream for NGramTokenStream<'a
         &mut self.token
     }
 }
+
+/// Splits every token of the inner stream into character n-grams after padding it
+/// with a [`BOUNDARY_MARKER`] at both ends, so `cat` becomes `$ca`, `cat` and `at$`.
+/// The n-grams at the edges of a word can then be told apart from the ones in the middle.
+pub struct PaddedCharNGramTokenStream<'a, const N: usize> {
+    inner: BoxTokenStream<'a>,
+    token: tantivy::tokenizer::Token,
+    chars: Vec<char>,
+    start: usize,
+    next_pos: usize,
+}
+
+impl<'a, const N: usize> PaddedCharNGramTokenStream<'a, N> {
+    pub fn new(inner: BoxTokenStream<'a>) -> Self {
+        Self {
+            inner,
+            token: tantivy::tokenizer::Token::default(),
+            chars: Vec::new(),
+            start: 0,
+            next_pos: 0,
+        }
+    }
+}
+
+impl<'a, const N: usize> tantivy::tokenizer::TokenStream for PaddedCharNGramTokenStream<'a, N> {
+    fn advance(&mut self) -> bool {
+        loop {
+            if self.start < self.chars.len() {
+                // words shorter than the n-gram are emitted whole
+                let end = (self.start + N).min(self.chars.len());
+
+                if self.start == 0 || end - self.start == N {
+                    self.token.text.clear();
+                    self.token.text.extend(&self.chars[self.start..end]);
+                    self.token.position = self.next_pos;
+                    self.next_pos += 1;
+
+                    self.start = if end - self.start == N {
+                        self.start + 1
+                    } else {
+                        self.chars.len()
+                    };
+
+                    return true;
+                }
+            }
+
+            if !self.inner.advance() {
+                return false;
+            }
+
+            let word = self.inner.token();
+            if word.text.is_empty() {
+                continue;
+            }
+
+            self.chars.clear();
+            self.chars.push(BOUNDARY_MARKER);
+            self.chars.extend(word.text.chars());
+            self.chars.push(BOUNDARY_MARKER);
+            self.start = 0;
+
+            self.token.offset_from = word.offset_from;
+            self.token.offset_to = word.offset_to;
+            self.token.position_length = 1;
+        }
+    }
+
+    fn token(&self) -> &tantivy::tokenizer::Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut tantivy::tokenizer::Token {
+        &mut self.token
+    }
+}
@@ -0,0 +1,123 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+use std::collections::VecDeque;
+
+use tantivy::tokenizer::BoxTokenStream;
+
+use crate::tokenizer::{double_metaphone, Tokenize};
+
+/// Emits the Double Metaphone codes of each word, so spelling variants
+/// of names that sound alike (e.g. "Smith" and "Smyth") get the same tokens.
+/// If a word has an alternate pronunciation, its code is emitted at the same position.
+#[derive(Clone, Default)]
+pub struct PhoneticTokenizer;
+
+impl PhoneticTokenizer {
+    pub fn as_str() -> &'static str {
+        "phonetic"
+    }
+}
+
+impl tantivy::tokenizer::Tokenizer for PhoneticTokenizer {
+    type TokenStream<'a> = BoxTokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        let mut tokens = VecDeque::new();
+        let mut position = 0;
+
+        for word in text.tokenize() {
+            let codes = double_metaphone::encode(word.text());
+            if codes.primary.is_empty() {
+                continue;
+            }
+
+            let span = word.span();
+            let alternate = codes.alternate.trim();
+
+            let alternate = (!alternate.is_empty() && alternate != codes.primary)
+                .then(|| alternate.to_string());
+
+            for code in std::iter::once(codes.primary).chain(alternate) {
+                tokens.push_back(tantivy::tokenizer::Token {
+                    offset_from: span.start,
+                    offset_to: span.end,
+                    position,
+                    text: code,
+                    ..Default::default()
+                });
+            }
+
+            position += 1;
+        }
+
+        BoxTokenStream::new(PhoneticTokenStream {
+            tokens,
+            token: tantivy::tokenizer::Token::default(),
+        })
+    }
+}
+
+pub struct PhoneticTokenStream {
+    tokens: VecDeque<tantivy::tokenizer::Token>,
+    token: tantivy::tokenizer::Token,
+}
+
+impl tantivy::tokenizer::TokenStream for PhoneticTokenStream {
+    fn advance(&mut self) -> bool {
+        match self.tokens.pop_front() {
+            Some(token) => {
+                self.token = token;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn token(&self) -> &tantivy::tokenizer::Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut tantivy::tokenizer::Token {
+        &mut self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lending_iter::LendingIterator;
+    use tantivy::tokenizer::Tokenizer;
+
+    use super::*;
+
+    fn tokenize(s: &str) -> Vec<(String, usize)> {
+        let mut res = Vec::new();
+        let mut tokenizer = PhoneticTokenizer;
+        let mut stream = tokenizer.token_stream(s);
+
+        let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
+        while let Some(token) = it.next() {
+            res.push((token.text.clone(), token.position));
+        }
+
+        res
+    }
+
+    #[test]
+    fn spelling_variants() {
+        assert!(tokenize("").is_empty());
+
+        assert_eq!(tokenize("Smith"), tokenize("Smyth"));
+        assert_eq!(
+            tokenize("John Smith"),
+            vec![
+                ("JN".to_string(), 0),
+                ("AN".to_string(), 0),
+                ("SM0".to_string(), 1),
+                ("XMT".to_string(), 1),
+            ]
+        );
+    }
+}
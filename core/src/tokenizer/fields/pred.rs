@@ -65,7 +65,7 @@ impl<'a> tantivy::tokenizer::TokenStream for PredTokenStream<'a> {
 }
 
 impl<'a> PredTokenStream<'a> {
-    fn new_boxed(
+    pub(super) fn new_boxed(
         stream: Box<dyn Iterator<Item = tokenizer::Token<'a>> + 'a>,
     ) -> BoxTokenStream<'a> {
         BoxTokenStream::new(Self {
@@ -31,7 +31,7 @@ impl tantivy::tokenizer::Tokenizer for Stemmed {
     type TokenStream<'a> = BoxTokenStream<'a>;
 
     fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
-        let builder = TextAnalyzer::builder(Normal).filter(LowerCaser);
+        let builder = TextAnalyzer::builder(Normal::default()).filter(LowerCaser);
 
         let lang = match self.force_language {
             Some(lang) => Some(lang),
@@ -39,10 +39,69 @@ impl tantivy::tokenizer::Tokenizer for Stemmed {
         };
 
         self.analyzer = match lang {
-            Some(lang) => Some(builder.filter(Stemmer::from(lang).into_tantivy()).build()),
+            Some(lang) => Some(builder.filter(Stemmer::from(lang)).build()),
             None => Some(builder.build()),
         };
 
         self.analyzer.as_mut().unwrap().token_stream(text)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use lending_iter::LendingIterator;
+    use tantivy::tokenizer::Tokenizer as _;
+
+    use super::*;
+
+    fn tokenize(tokenizer: &mut Stemmed, s: &str) -> Vec<String> {
+        let mut res = Vec::new();
+        let mut stream = tokenizer.token_stream(s);
+        let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
+
+        while let Some(token) = it.next() {
+            res.push(token.text.clone());
+        }
+
+        res
+    }
+
+    #[test]
+    fn detected_german() {
+        let tokens = tokenize(
+            &mut Stemmed::default(),
+            "Die Kinder laufen jeden Morgen durch den Park, und ich laufe mit ihnen \
+             vorbei an den alten Häusern des Dorfes.",
+        );
+
+        assert_eq!(tokens.iter().filter(|t| *t == "lauf").count(), 2);
+        assert!(tokens.contains(&"haus".to_string()));
+    }
+
+    #[test]
+    fn german_laeuft() {
+        let mut tokenizer = Stemmed::with_forced_language(Lang::Deu);
+
+        // the umlaut is folded, but snowball does not conflate the
+        // vowel change of strong verbs, so `läuft` keeps its own stem
+        assert_eq!(
+            tokenize(&mut tokenizer, "laufen läuft laufe"),
+            vec!["lauf", "lauft", "lauf"]
+        );
+    }
+
+    #[test]
+    fn unsupported_language_falls_back_to_english() {
+        let mut tokenizer = Stemmed::with_forced_language(Lang::Jpn);
+        assert_eq!(
+            tokenize(&mut tokenizer, "running laufen"),
+            vec!["run", "laufen"]
+        );
+
+        let mut tokenizer = Stemmed::with_forced_language(Lang::Eng);
+        assert_eq!(
+            tokenize(&mut tokenizer, "running laufen"),
+            vec!["run", "laufen"]
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use tantivy::tokenizer::BoxTokenStream;
 
-use super::{default::DefaultTokenizer, ngram::NGramTokenStream};
+use super::{
+    default::DefaultTokenizer,
+    ngram::{NGramTokenStream, PaddedCharNGramTokenStream},
+};
 
 // Neos is an open source web search engine.
 // Copyright (C) 2024 Yeonwoo Sung
@@ -9,12 +12,14 @@ use super::{default::DefaultTokenizer, ngram::NGramTokenStream};
 #[derive(Clone)]
 pub struct TrigramTokenizer {
     inner_tokenizer: DefaultTokenizer,
+    pad_boundaries: bool,
 }
 
 impl Default for TrigramTokenizer {
     fn default() -> Self {
         Self {
             inner_tokenizer: DefaultTokenizer::with_stopwords(vec![]),
+            pad_boundaries: false,
         }
     }
 }
@@ -23,14 +28,38 @@ impl TrigramTokenizer {
     pub fn as_str() -> &'static str {
         "trigram_tokenizer"
     }
+
+    /// Instead of trigrams of words, build character trigrams of each word padded
+    /// with a boundary marker at the start and end (e.g. `$ca`, `cat` and `at$`),
+    /// which makes prefix and suffix matches stand out.
+    pub fn with_padding(pad_boundaries: bool) -> Self {
+        Self {
+            pad_boundaries,
+            ..Default::default()
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        if self.pad_boundaries {
+            "trigram_tokenizer_padded"
+        } else {
+            Self::as_str()
+        }
+    }
 }
 impl tantivy::tokenizer::Tokenizer for TrigramTokenizer {
     type TokenStream<'a> = BoxTokenStream<'a>;
 
     fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
         let inner = self.inner_tokenizer.token_stream(text);
-        let stream: NGramTokenStream<3> = NGramTokenStream::new(inner);
-        BoxTokenStream::new(stream)
+
+        if self.pad_boundaries {
+            let stream: PaddedCharNGramTokenStream<3> = PaddedCharNGramTokenStream::new(inner);
+            BoxTokenStream::new(stream)
+        } else {
+            let stream: NGramTokenStream<3> = NGramTokenStream::new(inner);
+            BoxTokenStream::new(stream)
+        }
     }
 }
 
@@ -42,9 +71,16 @@ mod tests {
     use super::*;
 
     fn tokenize_trigram(s: &str) -> Vec<String> {
+        tokenize(TrigramTokenizer::default(), s)
+    }
+
+    fn tokenize_padded_trigram(s: &str) -> Vec<String> {
+        tokenize(TrigramTokenizer::with_padding(true), s)
+    }
+
+    fn tokenize(mut tokenizer: TrigramTokenizer, s: &str) -> Vec<String> {
         let mut res = Vec::new();
 
-        let mut tokenizer = TrigramTokenizer::default();
         let mut stream = tokenizer.token_stream(s);
         let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
 
@@ -67,4 +103,21 @@ mod tests {
             vec!["thisisa", "isatest"]
         );
     }
+
+    #[test]
+    fn padded_trigram_tokenizer() {
+        assert!(tokenize_padded_trigram("").is_empty());
+
+        assert_eq!(tokenize_trigram("cat"), vec!["cat"]);
+        assert_eq!(tokenize_padded_trigram("cat"), vec!["$ca", "cat", "at$"]);
+
+        assert_eq!(
+            tokenize_padded_trigram("this is"),
+            vec!["$th", "thi", "his", "is$", "$is", "is$"]
+        );
+        assert_eq!(
+            tokenize_padded_trigram("a test"),
+            vec!["$a$", "$te", "tes", "est", "st$"]
+        );
+    }
 }
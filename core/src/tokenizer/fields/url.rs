@@ -19,15 +19,31 @@ struct ParsedUrl {
     path: VecDeque<String>,
 }
 
-#[derive(Debug, Clone)]
-pub struct UrlTokenizer;
+#[derive(Debug, Clone, Default)]
+pub struct UrlTokenizer {
+    include_query: bool,
+}
 
 impl UrlTokenizer {
     pub fn as_str() -> &'static str {
         "url_tokenizer"
     }
 
-    fn parse_url(text: &str) -> ParsedUrl {
+    /// Also emit the keys of the query parameters (e.g. `utm_source`) after the
+    /// path segments. Values are never emitted.
+    pub fn with_query(include_query: bool) -> Self {
+        Self { include_query }
+    }
+
+    pub fn name(&self) -> &'static str {
+        if self.include_query {
+            "url_tokenizer_with_query"
+        } else {
+            Self::as_str()
+        }
+    }
+
+    fn parse_url(text: &str, include_query: bool) -> ParsedUrl {
         url::Url::parse(text)
             .or_else(|_| url::Url::parse(&format!("http://{}", text)))
             .map(|url| {
@@ -40,13 +56,28 @@ impl UrlTokenizer {
                         .add_space_last()
                         .collect(),
                 );
-                let path: VecDeque<_> = url
+                let mut path: VecDeque<_> = url
                     .path()
                     .split_preserve(|c| matches!(c, '/' | '-' | '_'))
                     .filter(|s| !(*s).is_empty())
                     .map(|s| s.to_string())
                     .collect();
 
+                if include_query {
+                    let keys: Vec<_> = url
+                        .query()
+                        .unwrap_or_default()
+                        .split('&')
+                        .map(|pair| pair.split_once('=').map_or(pair, |(key, _)| key))
+                        .filter(|key| !key.is_empty())
+                        .collect();
+
+                    for (i, key) in keys.into_iter().enumerate() {
+                        path.push_back(if i == 0 { "?" } else { "&" }.to_string());
+                        path.push_back(key.to_string());
+                    }
+                }
+
                 if matches!(url.scheme(), "http" | "https") {
                     ParsedUrl {
                         protocol: None,
@@ -78,7 +109,7 @@ impl tantivy::tokenizer::Tokenizer for UrlTokenizer {
             .split('\n')
             .filter(|s| !s.is_empty())
             .map(|s| s.to_lowercase())
-            .map(|s| Self::parse_url(&s))
+            .map(|s| Self::parse_url(&s, self.include_query))
             .collect();
 
         BoxTokenStream::new(SiteOperatorUrlTokenStream::new(urls))
@@ -193,8 +224,11 @@ mod tests {
     use tantivy::tokenizer::Tokenizer as _;
 
     fn tokenize_url(s: &str) -> Vec<String> {
+        tokenize_with(UrlTokenizer::default(), s)
+    }
+
+    fn tokenize_with(mut tokenizer: UrlTokenizer, s: &str) -> Vec<String> {
         let mut res = Vec::new();
-        let mut tokenizer = UrlTokenizer;
         let mut stream = tokenizer.token_stream(s);
         let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
 
@@ -248,6 +282,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn query_keys() {
+        assert_eq!(
+            tokenize_url("https://www.example.com/test?utm_source=abc&id=2"),
+            vec!["example", ".", "com ", "/", "test"]
+        );
+
+        let with_query = |s: &str| tokenize_with(UrlTokenizer::with_query(true), s);
+
+        assert_eq!(
+            with_query("https://www.example.com/test?utm_source=abc&id=2"),
+            vec![
+                "example",
+                ".",
+                "com ",
+                "/",
+                "test",
+                "?",
+                "utm_source",
+                "&",
+                "id"
+            ]
+        );
+        assert_eq!(
+            with_query("https://www.example.com/?flag&&b="),
+            vec!["example", ".", "com ", "/", "?", "flag", "&", "b"]
+        );
+        assert_eq!(
+            with_query("https://www.example.com/test"),
+            vec!["example", ".", "com ", "/", "test"]
+        );
+    }
+
     proptest! {
         #![proptest_config(ProptestConfig::with_cases(4096))]
 
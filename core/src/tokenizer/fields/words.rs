@@ -5,23 +5,69 @@
 
 use tantivy::tokenizer::{BoxTokenStream, TextAnalyzer};
 
-use super::pred::PredTokenizer;
+use crate::tokenizer::{self, split_with_range::SplitWithRange};
+
+use super::pred::{PredTokenStream, PredTokenizer};
 
 #[derive(Clone, Default)]
 pub struct WordTokenizer {
     analyzer: Option<TextAnalyzer>,
+    hyphenated_words: bool,
 }
 
 impl WordTokenizer {
     pub fn as_str() -> &'static str {
         "word"
     }
+
+    /// Split on all characters that are not part of a word instead of only
+    /// on whitespace. Hyphens and apostrophes inside a word are kept, so
+    /// "e-mail," gives "e-mail" and "don't" stays a single token.
+    /// Like the default mode, the tokens are not normalized.
+    pub fn with_hyphenated_words(hyphenated_words: bool) -> Self {
+        Self {
+            hyphenated_words,
+            ..Default::default()
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        if self.hyphenated_words {
+            "word_hyphenated"
+        } else {
+            Self::as_str()
+        }
+    }
+}
+
+fn is_joiner(c: char) -> bool {
+    matches!(c, '-' | '\'' | '\u{2019}')
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || is_joiner(c)
 }
 
 impl tantivy::tokenizer::Tokenizer for WordTokenizer {
     type TokenStream<'a> = BoxTokenStream<'a>;
 
     fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        if self.hyphenated_words {
+            let stream = Box::new(text.split_with_range(|c| !is_word_char(c)).filter_map(
+                |(s, range)| {
+                    // hyphens and apostrophes only join words, so they are
+                    // trimmed from the start and end of the token
+                    let start = range.start + s.len() - s.trim_start_matches(is_joiner).len();
+                    let word = s.trim_matches(is_joiner);
+
+                    (!word.is_empty())
+                        .then(|| tokenizer::Token::new(word, start..start + word.len()))
+                },
+            ));
+
+            return PredTokenStream::new_boxed(stream);
+        }
+
         let builder = TextAnalyzer::builder(PredTokenizer(|c| c.is_whitespace()));
 
         self.analyzer = Some(builder.build());
@@ -37,8 +83,11 @@ mod tests {
     use tantivy::tokenizer::Tokenizer as _;
 
     fn tokenize(s: &str) -> Vec<String> {
+        tokenize_with(WordTokenizer::default(), s)
+    }
+
+    fn tokenize_with(mut tokenizer: WordTokenizer, s: &str) -> Vec<String> {
         let mut res = Vec::new();
-        let mut tokenizer = WordTokenizer::default();
         let mut stream = tokenizer.token_stream(s);
         let mut it = tantivy::tokenizer::TokenStream::iter(&mut stream);
 
@@ -57,4 +106,24 @@ mod tests {
         assert_eq!(tokenize(" a b "), vec!["a", "b"]);
         assert_eq!(tokenize("a b c"), vec!["a", "b", "c"]);
     }
+
+    #[test]
+    fn hyphenated_words() {
+        assert_eq!(
+            tokenize("e-mail, state-of-the-art!"),
+            vec!["e-mail,", "state-of-the-art!"]
+        );
+
+        let hyphenated = WordTokenizer::with_hyphenated_words(true);
+        assert_eq!(hyphenated.name(), "word_hyphenated");
+        assert_eq!(
+            tokenize_with(hyphenated.clone(), "e-mail, state-of-the-art!"),
+            vec!["e-mail", "state-of-the-art"]
+        );
+        assert_eq!(
+            tokenize_with(hyphenated.clone(), "Twenty-five (don't) -dash- 'quoted'"),
+            vec!["Twenty-five", "don't", "dash", "quoted"]
+        );
+        assert!(tokenize_with(hyphenated, "-- !").is_empty());
+    }
 }
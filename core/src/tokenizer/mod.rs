@@ -4,6 +4,7 @@
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
 mod add_space_last;
+mod double_metaphone;
 pub mod fields;
 pub mod normalizer;
 mod script;
@@ -11,7 +12,7 @@ mod script_tokenizer;
 mod segmenter;
 mod split_preserve;
 mod split_with_range;
-mod stemmer;
+pub mod stemmer;
 
 use std::borrow::{Borrow, Cow};
 
@@ -3,67 +3,133 @@
 //
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
+use std::{borrow::Cow, mem, panic::AssertUnwindSafe};
+
+use rust_stemmers::Algorithm;
+use tantivy::tokenizer::{TokenFilter, TokenStream, Tokenizer};
 use whatlang::Lang;
 
-pub struct Stemmer(tantivy::tokenizer::Stemmer);
+/// The stemming algorithm for a detected language.
+/// Languages that `rust-stemmers` does not support are stemmed as English.
+pub fn algorithm(lang: &Lang) -> Algorithm {
+    match lang {
+        Lang::Ara => Algorithm::Arabic,
+        Lang::Dan => Algorithm::Danish,
+        Lang::Nld => Algorithm::Dutch,
+        Lang::Fin => Algorithm::Finnish,
+        Lang::Fra => Algorithm::French,
+        Lang::Deu => Algorithm::German,
+        Lang::Ell => Algorithm::Greek,
+        Lang::Hun => Algorithm::Hungarian,
+        Lang::Ita => Algorithm::Italian,
+        Lang::Nob => Algorithm::Norwegian,
+        Lang::Por => Algorithm::Portuguese,
+        Lang::Ron => Algorithm::Romanian,
+        Lang::Rus => Algorithm::Russian,
+        Lang::Spa => Algorithm::Spanish,
+        Lang::Swe => Algorithm::Swedish,
+        Lang::Tam => Algorithm::Tamil,
+        Lang::Tur => Algorithm::Turkish,
+        _ => Algorithm::English,
+    }
+}
 
-impl Stemmer {
-    pub fn into_tantivy(self) -> tantivy::tokenizer::Stemmer {
-        self.0
+/// Stem `text` in place. Some of the stemmers panic on unusual input,
+/// in which case the text is left as is.
+fn stem(stemmer: &rust_stemmers::Stemmer, text: &mut String, buffer: &mut String) {
+    match std::panic::catch_unwind(AssertUnwindSafe(|| stemmer.stem(text))) {
+        Ok(Cow::Owned(stemmed)) => *text = stemmed,
+        Ok(Cow::Borrowed(stemmed)) => {
+            buffer.clear();
+            buffer.push_str(stemmed);
+            mem::swap(text, buffer);
+        }
+        Err(_) => {}
     }
 }
 
+/// Token filter that stems the tokens using the algorithm for a language.
+/// Tokens are expected to be lowercased beforehand.
+#[derive(Clone)]
+pub struct Stemmer {
+    algorithm: Algorithm,
+}
+
 impl From<Lang> for Stemmer {
     fn from(lang: Lang) -> Self {
-        match lang {
-            Lang::Dan => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Danish,
-            )),
-            Lang::Ara => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Arabic,
-            )),
-            Lang::Nld => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Dutch,
-            )),
-            Lang::Fin => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Finnish,
-            )),
-            Lang::Fra => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::French,
-            )),
-            Lang::Deu => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::German,
-            )),
-            Lang::Hun => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Hungarian,
-            )),
-            Lang::Ita => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Italian,
-            )),
-            Lang::Por => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Portuguese,
-            )),
-            Lang::Ron => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Romanian,
-            )),
-            Lang::Rus => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Russian,
-            )),
-            Lang::Spa => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Spanish,
-            )),
-            Lang::Swe => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Swedish,
-            )),
-            Lang::Tam => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Tamil,
-            )),
-            Lang::Tur => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::Turkish,
-            )),
-            _ => Stemmer(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::English,
-            )),
+        Self {
+            algorithm: algorithm(&lang),
         }
     }
 }
+
+impl Stemmer {
+    /// Stem tokens that have already been produced by a tokenizer.
+    pub fn stem_tokens(&self, tokens: &mut [tantivy::tokenizer::Token]) {
+        let stemmer = rust_stemmers::Stemmer::create(self.algorithm);
+        let mut buffer = String::new();
+
+        for token in tokens {
+            stem(&stemmer, &mut token.text, &mut buffer);
+        }
+    }
+}
+
+impl TokenFilter for Stemmer {
+    type Tokenizer<T: Tokenizer> = StemmerFilter<T>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> StemmerFilter<T> {
+        StemmerFilter {
+            algorithm: self.algorithm,
+            inner: tokenizer,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct StemmerFilter<T> {
+    algorithm: Algorithm,
+    inner: T,
+}
+
+impl<T: Tokenizer> Tokenizer for StemmerFilter<T> {
+    type TokenStream<'a> = StemmerTokenStream<T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        StemmerTokenStream {
+            tail: self.inner.token_stream(text),
+            stemmer: rust_stemmers::Stemmer::create(self.algorithm),
+            buffer: String::new(),
+        }
+    }
+}
+
+pub struct StemmerTokenStream<T> {
+    tail: T,
+    stemmer: rust_stemmers::Stemmer,
+    buffer: String,
+}
+
+impl<T: TokenStream> TokenStream for StemmerTokenStream<T> {
+    fn advance(&mut self) -> bool {
+        if !self.tail.advance() {
+            return false;
+        }
+
+        stem(
+            &self.stemmer,
+            &mut self.tail.token_mut().text,
+            &mut self.buffer,
+        );
+
+        true
+    }
+
+    fn token(&self) -> &tantivy::tokenizer::Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut tantivy::tokenizer::Token {
+        self.tail.token_mut()
+    }
+}
@@ -16,24 +16,32 @@
 
 use crate::distributed::retry_strategy::ExponentialBackoff;
 use crate::{config::S3Config, config::WarcSource, Error, Result};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Cursor, Read, Seek, Write};
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::thread::sleep;
 use std::time::Duration;
 
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
 use flate2::read::MultiGzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use fnv::FnvHashSet;
+use itertools::Itertools;
 #[cfg(test)]
 use proptest::prelude::*;
 
 use tracing::{debug, trace};
 
+/// Maximum number of body bytes kept around for `revisit` records to refer to.
+const MAX_REVISIT_CACHE_BYTES: usize = 256 * 1024 * 1024;
+
+const SUPPORTED_VERSIONS: [&str; 2] = ["WARC/1.0", "WARC/1.1"];
+
 pub struct WarcFile {
     bytes: Vec<u8>,
 }
@@ -42,6 +50,21 @@ fn rtrim(s: &mut String) {
     s.truncate(s.trim_end().len());
 }
 
+/// Parse a `WARC-Date`. WARC/1.0 dates have second precision,
+/// while WARC/1.1 allows fractional seconds.
+fn parse_warc_date(date: &str) -> Option<DateTime<Utc>> {
+    let date = date.trim();
+
+    DateTime::parse_from_rfc3339(date)
+        .map(|date| date.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.f")
+                .ok()
+                .map(|date| date.and_utc())
+        })
+}
+
 fn decode_string(raw: &[u8]) -> String {
     if let Ok(res) = String::from_utf8(raw.to_owned()) {
         res
@@ -77,11 +100,28 @@ impl WarcFile {
         Ok(Self::new(bytes))
     }
 
+    /// Iterate the records of the file at `path` without reading it into memory.
+    /// Records are decoded lazily as the iterator advances.
+    pub fn open_streaming<P: AsRef<Path>>(path: P) -> Result<RecordIterator<File>> {
+        Self::stream_file(File::open(path)?)
+    }
+
+    /// Iterate the records of a gzip or zstd compressed warc file. The
+    /// compression is detected from the magic bytes at the start of the file.
     pub fn records(&self) -> RecordIterator<&[u8]> {
-        RecordIterator {
-            reader: BufReader::new(MultiGzDecoder::new(&self.bytes[..])),
-            num_reads: 0,
-        }
+        // creating a zstd decoder only fails if it can't allocate its context
+        RecordIterator::new(&self.bytes[..], Codec::detect(&self.bytes))
+            .expect("failed to create decoder")
+    }
+
+    fn stream_file(mut file: File) -> Result<RecordIterator<File>> {
+        let mut magic = Vec::with_capacity(ZSTD_MAGIC.len());
+        file.by_ref()
+            .take(ZSTD_MAGIC.len() as u64)
+            .read_to_end(&mut magic)?;
+        file.rewind()?;
+
+        RecordIterator::new(file, Codec::detect(&magic))
     }
 
     pub(crate) fn download(source: &WarcSource, warc_path: &str) -> Result<Self> {
@@ -95,6 +135,24 @@ impl WarcFile {
         Ok(Self::new(buf))
     }
 
+    /// Like [`WarcFile::download`], but the records are streamed from disk.
+    /// Local files are read in place, while remote files are first downloaded
+    /// into an anonymous temporary file.
+    pub(crate) fn download_streaming(
+        source: &WarcSource,
+        warc_path: &str,
+    ) -> Result<RecordIterator<File>> {
+        if let WarcSource::Local(config) = source {
+            return Self::open_streaming(Path::new(&config.folder).join(warc_path));
+        }
+
+        let mut file = tempfile::tempfile()?;
+        Self::download_into_buf(source, warc_path, &mut file)?;
+        file.rewind()?;
+
+        Self::stream_file(file)
+    }
+
     pub(crate) fn download_into_buf<W: Write + Seek>(
         source: &WarcSource,
         warc_path: &str,
@@ -213,6 +271,9 @@ pub struct WarcRecord {
     pub request: Request,
     pub response: Response,
     pub metadata: Metadata,
+    /// When the response was captured (`WARC-Date`).
+    /// [`WarcWriter`] writes WARC/1.0 records, so it truncates the date to whole seconds.
+    pub date: Option<DateTime<Utc>>,
 }
 
 #[cfg(test)]
@@ -221,11 +282,18 @@ impl Arbitrary for WarcRecord {
     type Strategy = BoxedStrategy<Self>;
 
     fn arbitrary_with(_args: ()) -> Self::Strategy {
-        (any::<Request>(), any::<Response>(), any::<Metadata>())
-            .prop_map(|(request, response, metadata)| Self {
+        (
+            any::<Request>(),
+            any::<Response>(),
+            any::<Metadata>(),
+            // the writer only keeps whole seconds
+            proptest::option::of(0..4_102_444_800i64),
+        )
+            .prop_map(|(request, response, metadata, date)| Self {
                 request,
                 response,
                 metadata,
+                date: date.and_then(|secs| DateTime::from_timestamp(secs, 0)),
             })
             .boxed()
     }
@@ -313,23 +381,78 @@ impl Display for PayloadType {
     }
 }
 
-#[derive(Debug)]
-#[cfg_attr(test, derive(Clone, PartialEq))]
+#[derive(Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
 pub struct Response {
-    pub body: String,
+    /// Shared with the revisit records that refer to this response.
+    pub body: Arc<str>,
     pub payload_type: Option<PayloadType>,
+    /// The status code from the http status line (`HTTP/1.1 200 OK`).
+    pub status_code: Option<u16>,
+    /// The http response headers with lowercase names. Repeated headers
+    /// are joined by `, `.
+    pub headers: BTreeMap<String, String>,
+}
+
+/// Headers that describe how the http body was transferred. The body of a [`Response`]
+/// is stored decoded, so the writer leaves them out as they would no longer match it.
+const TRANSFER_HEADERS: [&str; 3] = ["content-encoding", "transfer-encoding", "content-length"];
+
+/// Parse the status code from the status line of an http header block.
+fn parse_status_code(block: &str) -> Option<u16> {
+    let status_line = block.lines().next()?;
+
+    if !status_line.starts_with("HTTP/") {
+        return None;
+    }
+
+    status_line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Parse the header fields of an http header block. The status line is skipped.
+fn parse_http_headers(block: &str) -> BTreeMap<String, String> {
+    let mut headers: BTreeMap<String, String> = BTreeMap::new();
+
+    for line in block.lines() {
+        if line.starts_with("HTTP/") {
+            continue;
+        }
+
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        let name = name.trim().to_lowercase();
+        if name.is_empty() {
+            continue;
+        }
+
+        let value = value.trim();
+
+        headers
+            .entry(name)
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+
+    headers
 }
 
 impl Response {
     fn from_raw(record: RawWarcRecord) -> Result<Self> {
         let content = decode_string(&record.content[..]);
 
-        let (_header, content) = content
+        let (header, content) = content
             .split_once("\r\n\r\n")
             .ok_or(Error::WarcParse("Invalid http body".to_string()))?;
 
         Ok(Self {
-            body: content.to_string(),
+            body: content.into(),
+            status_code: parse_status_code(header),
+            headers: parse_http_headers(header),
             payload_type: record
                 .header
                 .get("WARC-IDENTIFIED-PAYLOAD-TYPE")
@@ -344,8 +467,23 @@ impl Arbitrary for Response {
     type Strategy = BoxedStrategy<Self>;
 
     fn arbitrary_with(_args: ()) -> Self::Strategy {
-        (".+", any::<Option<PayloadType>>())
-            .prop_map(|(body, payload_type)| Self { body, payload_type })
+        (
+            ".+",
+            any::<Option<PayloadType>>(),
+            prop::option::of(100..600u16),
+            prop::collection::btree_map("[a-z][a-z0-9-]{0,15}", "[!-~]([ -~]{0,30}[!-~])?", 0..4),
+        )
+            .prop_map(|(body, payload_type, status_code, mut headers)| {
+                // these are not written, so they would not survive a round trip
+                headers.retain(|name, _| !TRANSFER_HEADERS.contains(&name.as_str()));
+
+                Self {
+                    body: Arc::from(body),
+                    payload_type,
+                    status_code,
+                    headers,
+                }
+            })
             .boxed()
     }
 }
@@ -390,12 +528,150 @@ impl Arbitrary for Metadata {
     }
 }
 
+/// Responses that have been read from the file so that later `revisit` records,
+/// which do not store the body themselves, can be resolved to the original body.
+/// Bodies are shared with the returned records, so caching a response does not copy it.
+/// The oldest responses are evicted once the cache grows beyond [`MAX_REVISIT_CACHE_BYTES`].
+#[derive(Default)]
+struct RevisitCache {
+    responses: HashMap<String, Response>,
+    record_by_uri: HashMap<String, String>,
+    order: VecDeque<(String, Option<String>)>,
+    num_bytes: usize,
+}
+
+impl RevisitCache {
+    fn insert(&mut self, record_id: String, uri: Option<String>, response: &Response) {
+        self.num_bytes += response.body.len();
+        self.responses.insert(record_id.clone(), response.clone());
+
+        if let Some(uri) = &uri {
+            self.record_by_uri.insert(uri.clone(), record_id.clone());
+        }
+
+        self.order.push_back((record_id, uri));
+
+        while self.num_bytes > MAX_REVISIT_CACHE_BYTES {
+            let Some((record_id, uri)) = self.order.pop_front() else {
+                break;
+            };
+
+            if let Some(response) = self.responses.remove(&record_id) {
+                self.num_bytes -= response.body.len();
+            }
+
+            if let Some(uri) = uri {
+                if self.record_by_uri.get(&uri) == Some(&record_id) {
+                    self.record_by_uri.remove(&uri);
+                }
+            }
+        }
+    }
+
+    /// Resolve a revisit record to the body of the response it refers to,
+    /// either by `WARC-Refers-To` or by `WARC-Refers-To-Target-URI`.
+    fn resolve(&self, revisit: &RawWarcRecord) -> Result<Response> {
+        let record_id = revisit
+            .header
+            .get("WARC-REFERS-TO")
+            .or_else(|| {
+                revisit
+                    .header
+                    .get("WARC-REFERS-TO-TARGET-URI")
+                    .and_then(|uri| self.record_by_uri.get(uri))
+            })
+            .ok_or(Error::WarcParse(
+                "Revisit record does not refer to a record in this file".to_string(),
+            ))?;
+
+        let original = self.responses.get(record_id).ok_or_else(|| {
+            Error::WarcParse(format!(
+                "Revisit record refers to {record_id} which is not available in this file"
+            ))
+        })?;
+
+        // a revisit usually stores the http headers of the new capture
+        let content = decode_string(&revisit.content[..]);
+        let header = content
+            .split_once("\r\n\r\n")
+            .map_or(content.as_str(), |(header, _)| header);
+        let headers = parse_http_headers(header);
+
+        Ok(Response {
+            body: Arc::clone(&original.body),
+            status_code: parse_status_code(header).or(original.status_code),
+            payload_type: revisit
+                .header
+                .get("WARC-IDENTIFIED-PAYLOAD-TYPE")
+                .and_then(|p| PayloadType::from_str(p).ok())
+                .or(original.payload_type),
+            headers: if headers.is_empty() {
+                original.headers.clone()
+            } else {
+                headers
+            },
+        })
+    }
+}
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    Gzip,
+    Zstd,
+}
+
+impl Codec {
+    /// Detect the codec from the magic bytes at the start of the file.
+    /// Anything that isn't zstd (`28 b5 2f fd`) is assumed to be gzip (`1f 8b`).
+    fn detect(magic: &[u8]) -> Self {
+        if magic.starts_with(&ZSTD_MAGIC) {
+            Codec::Zstd
+        } else {
+            Codec::Gzip
+        }
+    }
+}
+
+enum Decoder<R: Read> {
+    Gzip(MultiGzDecoder<R>),
+    Zstd(zstd::Decoder<'static, BufReader<R>>),
+}
+
+impl<R: Read> Decoder<R> {
+    fn new(reader: R, codec: Codec) -> Result<Self> {
+        Ok(match codec {
+            Codec::Gzip => Decoder::Gzip(MultiGzDecoder::new(reader)),
+            Codec::Zstd => Decoder::Zstd(zstd::Decoder::new(reader)?),
+        })
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Decoder::Gzip(decoder) => decoder.read(buf),
+            Decoder::Zstd(decoder) => decoder.read(buf),
+        }
+    }
+}
+
 pub struct RecordIterator<R: Read> {
-    reader: BufReader<MultiGzDecoder<R>>,
+    reader: BufReader<Decoder<R>>,
     num_reads: usize,
+    revisits: RevisitCache,
 }
 
 impl<R: Read> RecordIterator<R> {
+    fn new(reader: R, codec: Codec) -> Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(Decoder::new(reader, codec)?),
+            num_reads: 0,
+            revisits: RevisitCache::default(),
+        })
+    }
+
     fn next_raw(&mut self) -> Option<Result<RawWarcRecord>> {
         let mut version = String::new();
 
@@ -409,7 +685,7 @@ impl<R: Read> RecordIterator<R> {
 
         rtrim(&mut version);
 
-        if !version.to_uppercase().starts_with("WARC/1.") {
+        if !SUPPORTED_VERSIONS.contains(&version.to_uppercase().as_str()) {
             return Some(Err(
                 Error::WarcParse("Unknown WARC version".to_string()).into()
             ));
@@ -503,6 +779,9 @@ impl<R: Read> Iterator for RecordIterator<R> {
         let mut request = None;
         let mut response = None;
         let mut metadata = None;
+        let mut unresolved_revisit = None;
+        let mut request_date = None;
+        let mut response_date = None;
 
         while let Some(item) = self.next_raw() {
             if item.is_err() {
@@ -520,6 +799,11 @@ impl<R: Read> Iterator for RecordIterator<R> {
                         .into()));
                     }
 
+                    request_date = item
+                        .header
+                        .get("WARC-DATE")
+                        .and_then(|d| parse_warc_date(d));
+
                     match Request::from_raw(item) {
                         Ok(req) => {
                             request = Some(req);
@@ -533,21 +817,42 @@ impl<R: Read> Iterator for RecordIterator<R> {
                         }
                     }
 
-                    if response.is_some() {
+                    if response.is_some() || unresolved_revisit.is_some() {
                         return Some(Err(Error::WarcParse(
                             "Already have a response but got another.".to_string(),
                         )
                         .into()));
                     }
 
-                    match Response::from_raw(item) {
-                        Ok(res) => {
-                            response = Some(res);
-                        }
-                        Err(err) => {
-                            return Some(Err(Error::WarcParse(err.to_string()).into()));
+                    response_date = item
+                        .header
+                        .get("WARC-DATE")
+                        .and_then(|d| parse_warc_date(d));
+
+                    if warc_type.as_str() == "revisit" {
+                        // the rest of the record group is still consumed so the
+                        // next record starts at the correct position
+                        match self.revisits.resolve(&item) {
+                            Ok(res) => response = Some(res),
+                            Err(err) => unresolved_revisit = Some(err),
                         }
-                    };
+                    } else {
+                        let record_id = item.header.get("WARC-RECORD-ID").cloned();
+                        let uri = item.header.get("WARC-TARGET-URI").cloned();
+
+                        match Response::from_raw(item) {
+                            Ok(res) => {
+                                if let Some(record_id) = record_id {
+                                    self.revisits.insert(record_id, uri, &res);
+                                }
+
+                                response = Some(res);
+                            }
+                            Err(err) => {
+                                return Some(Err(Error::WarcParse(err.to_string()).into()));
+                            }
+                        };
+                    }
                 } else if warc_type.as_str() == "metadata" {
                     if let Some(content_type) = item.header.get("CONTENT-TYPE") {
                         if !content_type.starts_with("application/warc-fields") {
@@ -571,15 +876,23 @@ impl<R: Read> Iterator for RecordIterator<R> {
                 }
             }
 
-            if request.is_some() && response.is_some() && metadata.is_some() {
+            if request.is_some()
+                && (response.is_some() || unresolved_revisit.is_some())
+                && metadata.is_some()
+            {
                 break;
             }
         }
 
+        if let Some(err) = unresolved_revisit {
+            return Some(Err(err));
+        }
+
         Some(Ok(WarcRecord {
             request: request?,
             response: response?,
             metadata: metadata?,
+            date: response_date.or(request_date),
         }))
     }
 }
@@ -674,19 +987,45 @@ impl WarcWriter {
         self.writer
             .write_all("WARC-Type: response\r\n".as_bytes())?;
 
+        // WARC/1.0 does not allow fractional seconds
+        if let Some(date) = &record.date {
+            self.writer.write_all(
+                format!(
+                    "WARC-Date: {}\r\n",
+                    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+                )
+                .as_bytes(),
+            )?;
+        }
+
         if let Some(payload_type) = &record.response.payload_type {
             self.writer.write_all(
                 format!("WARC-Identified-Payload-Type: {payload_type}\r\n").as_bytes(),
             )?;
         }
 
+        let http_header = record
+            .response
+            .status_code
+            .map(|status_code| format!("HTTP/1.1 {status_code}"))
+            .into_iter()
+            .chain(
+                record
+                    .response
+                    .headers
+                    .iter()
+                    .filter(|(name, _)| !TRANSFER_HEADERS.contains(&name.as_str()))
+                    .map(|(name, value)| format!("{name}: {value}")),
+            )
+            .join("\r\n");
+
         let body = record.response.body.as_bytes();
-        let content_len = body.len() + 4; // +4 is for the \r\n\r\n between http header and body
+        let content_len = http_header.len() + 4 + body.len(); // +4 is for the \r\n\r\n between http header and body
         self.writer
             .write_all(format!("Content-Length: {content_len}\r\n").as_bytes())?;
 
         self.writer.write_all("\r\n".as_bytes())?;
-        // write the http-header here if we want to in the future
+        self.writer.write_all(http_header.as_bytes())?;
         self.writer.write_all("\r\n\r\n".as_bytes())?;
 
         self.writer.write_all(body)?;
@@ -777,10 +1116,292 @@ mod tests {
 
         assert_eq!(records.len(), 1);
         assert_eq!(&records[0].request.url, "http://0575ls.cn/news-52300.htm");
-        assert_eq!(&records[0].response.body, "body of response");
+        assert_eq!(&*records[0].response.body, "body of response");
         assert_eq!(records[0].metadata.fetch_time_ms, 937);
     }
 
+    fn raw_record(header: &[(&str, &str)], content: &str) -> String {
+        let mut record = "WARC/1.0\r\n".to_string();
+
+        for (key, value) in header {
+            record.push_str(&format!("{key}: {value}\r\n"));
+        }
+
+        record.push_str(&format!("Content-Length: {}\r\n\r\n", content.len()));
+        record.push_str(content);
+        record.push_str("\r\n\r\n");
+
+        record
+    }
+
+    fn raw_group(url: &str, response: (&str, &[(&str, &str)], &str)) -> String {
+        let (warc_type, header, content) = response;
+        let mut response_header = vec![("WARC-Type", warc_type)];
+        response_header.extend_from_slice(header);
+
+        raw_record(&[("WARC-Type", "request"), ("WARC-Target-URI", url)], "")
+            + &raw_record(&response_header, content)
+            + &raw_record(&[("WARC-Type", "metadata")], "fetchTimeMs: 1")
+    }
+
+    #[test]
+    fn revisit_records() {
+        let raw = raw_record(&[("WARC-Type", "warcinfo")], "ISPARTOF: test")
+            + &raw_group(
+                "https://a.com",
+                (
+                    "response",
+                    &[
+                        ("WARC-Record-ID", "<urn:uuid:1>"),
+                        ("WARC-Target-URI", "https://a.com"),
+                    ],
+                    "HTTP/1.1 200 OK\r\n\r\nbody of a",
+                ),
+            )
+            + &raw_group(
+                "https://a.com",
+                (
+                    "revisit",
+                    &[
+                        ("WARC-Record-ID", "<urn:uuid:2>"),
+                        ("WARC-Refers-To", "<urn:uuid:1>"),
+                    ],
+                    "HTTP/1.1 200 OK\r\n\r\n",
+                ),
+            )
+            + &raw_group(
+                "https://a.com/index.html",
+                (
+                    "revisit",
+                    &[("WARC-Refers-To-Target-URI", "https://a.com")],
+                    "",
+                ),
+            )
+            + &raw_group(
+                "https://b.com",
+                (
+                    "revisit",
+                    &[("WARC-Refers-To", "<urn:uuid:unknown>")],
+                    "HTTP/1.1 200 OK\r\n\r\n",
+                ),
+            )
+            + &raw_group(
+                "https://c.com",
+                ("response", &[], "HTTP/1.1 200 OK\r\n\r\nbody of c"),
+            );
+
+        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+        e.write_all(raw.as_bytes()).unwrap();
+        let compressed = e.finish().unwrap();
+
+        let records: Vec<_> = WarcFile::new(compressed).records().collect();
+        assert_eq!(records.len(), 5);
+
+        let (ok, err): (Vec<_>, Vec<_>) = records.into_iter().partition(|r| r.is_ok());
+        let ok: Vec<_> = ok.into_iter().map(|r| r.unwrap()).collect();
+
+        assert_eq!(ok.len(), 4);
+        assert_eq!(
+            ok.iter()
+                .map(|r| (r.request.url.as_str(), &*r.response.body))
+                .collect::<Vec<_>>(),
+            vec![
+                ("https://a.com", "body of a"),
+                ("https://a.com", "body of a"),
+                ("https://a.com/index.html", "body of a"),
+                ("https://c.com", "body of c"),
+            ]
+        );
+
+        // revisits share the body of the original response instead of copying it
+        assert!(Arc::ptr_eq(&ok[0].response.body, &ok[1].response.body));
+        assert!(Arc::ptr_eq(&ok[0].response.body, &ok[2].response.body));
+
+        assert_eq!(err.len(), 1);
+        assert!(err[0]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("<urn:uuid:unknown>"));
+    }
+
+    #[test]
+    fn warc_1_1_dates() {
+        let raw = raw_record(&[("WARC-Type", "warcinfo")], "ISPARTOF: test")
+            + &raw_group(
+                "https://a.com",
+                (
+                    "response",
+                    &[("WARC-Date", "2024-03-01T12:34:56.789123Z")],
+                    "HTTP/1.1 200 OK\r\n\r\nbody of a",
+                ),
+            )
+            + &raw_group(
+                "https://b.com",
+                (
+                    "response",
+                    &[("WARC-Date", "2024-03-01T12:34:56Z")],
+                    "HTTP/1.1 200 OK\r\n\r\nbody of b",
+                ),
+            );
+        let raw = raw.replace("WARC/1.0", "WARC/1.1")
+            + &raw_group(
+                "https://c.com",
+                ("response", &[], "HTTP/1.1 200 OK\r\n\r\nbody of c"),
+            );
+
+        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+        e.write_all(raw.as_bytes()).unwrap();
+        let compressed = e.finish().unwrap();
+
+        let records: Vec<WarcRecord> = WarcFile::new(compressed)
+            .records()
+            .map(|res| res.unwrap())
+            .collect();
+
+        assert_eq!(
+            records
+                .iter()
+                .map(|r| (r.request.url.as_str(), r.date))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "https://a.com",
+                    Some(
+                        DateTime::parse_from_rfc3339("2024-03-01T12:34:56.789123Z")
+                            .unwrap()
+                            .with_timezone(&Utc)
+                    )
+                ),
+                (
+                    "https://b.com",
+                    Some(
+                        DateTime::parse_from_rfc3339("2024-03-01T12:34:56Z")
+                            .unwrap()
+                            .with_timezone(&Utc)
+                    )
+                ),
+                ("https://c.com", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn http_headers() {
+        let raw = raw_record(&[("WARC-Type", "warcinfo")], "ISPARTOF: test")
+            + &raw_group(
+                "https://a.com",
+                (
+                    "response",
+                    &[],
+                    "HTTP/1.1 200 OK\r\n\
+                     Content-Language: da\r\n\
+                     Last-Modified:  Wed, 21 Oct 2015 07:28:00 GMT \r\n\
+                     Link: <https://a.com/canonical>; rel=\"canonical\"\r\n\
+                     LINK: <https://a.com/style.css>; rel=\"preload\"\r\n\
+                     \r\n\
+                     body of a",
+                ),
+            );
+
+        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+        e.write_all(raw.as_bytes()).unwrap();
+        let compressed = e.finish().unwrap();
+
+        let records: Vec<WarcRecord> = WarcFile::new(compressed)
+            .records()
+            .map(|res| res.unwrap())
+            .collect();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(&*records[0].response.body, "body of a");
+
+        assert_eq!(records[0].response.status_code, Some(200));
+
+        let headers = &records[0].response.headers;
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers["content-language"], "da");
+        assert_eq!(headers["last-modified"], "Wed, 21 Oct 2015 07:28:00 GMT");
+        assert_eq!(
+            headers["link"],
+            "<https://a.com/canonical>; rel=\"canonical\", <https://a.com/style.css>; rel=\"preload\""
+        );
+
+        let mut writer = WarcWriter::new();
+        writer.write(&records[0]).unwrap();
+
+        let written: Vec<WarcRecord> = WarcFile::new(writer.finish().unwrap())
+            .records()
+            .map(|res| res.unwrap())
+            .collect();
+
+        assert_eq!(written, records);
+    }
+
+    #[test]
+    fn transfer_headers_are_not_written() {
+        let raw = raw_record(&[("WARC-Type", "warcinfo")], "ISPARTOF: test")
+            + &raw_group(
+                "https://a.com",
+                (
+                    "response",
+                    &[],
+                    "HTTP/1.1 200 OK\r\n\
+                     Content-Type: text/html\r\n\
+                     Content-Encoding: gzip\r\n\
+                     Transfer-Encoding: chunked\r\n\
+                     Content-Length: 1337\r\n\
+                     \r\n\
+                     body of a",
+                ),
+            );
+
+        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+        e.write_all(raw.as_bytes()).unwrap();
+        let compressed = e.finish().unwrap();
+
+        let records: Vec<WarcRecord> = WarcFile::new(compressed)
+            .records()
+            .map(|res| res.unwrap())
+            .collect();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].response.headers.len(), 4);
+
+        let mut writer = WarcWriter::new();
+        writer.write(&records[0]).unwrap();
+
+        let written: Vec<WarcRecord> = WarcFile::new(writer.finish().unwrap())
+            .records()
+            .map(|res| res.unwrap())
+            .collect();
+
+        assert_eq!(written.len(), 1);
+        assert_eq!(&*written[0].response.body, "body of a");
+        assert_eq!(written[0].response.status_code, Some(200));
+
+        let headers = &written[0].response.headers;
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["content-type"], "text/html");
+    }
+
+    #[test]
+    fn unknown_version() {
+        let raw = raw_record(&[("WARC-Type", "warcinfo")], "ISPARTOF: test")
+            + &raw_group(
+                "https://a.com",
+                ("response", &[], "HTTP/1.1 200 OK\r\n\r\nbody of a"),
+            )
+            .replace("WARC/1.0", "WARC/2.0");
+
+        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+        e.write_all(raw.as_bytes()).unwrap();
+        let compressed = e.finish().unwrap();
+
+        let first = WarcFile::new(compressed).records().next().unwrap();
+        assert!(first.is_err());
+    }
+
     #[test]
     fn internet_archive_parse() {
         let data_path = Path::new("../../data/internet_archive.warc.gz");
@@ -809,12 +1430,15 @@ mod tests {
                 url: "https://a.com".to_string(),
             },
             response: Response {
-                body: "body of a".to_string(),
+                body: "body of a".into(),
                 payload_type: Some(PayloadType::Html),
+                status_code: Some(200),
+                headers: BTreeMap::new(),
             },
             metadata: Metadata {
                 fetch_time_ms: 1337,
             },
+            date: DateTime::from_timestamp(1_700_000_000, 123_000_000),
         };
         writer.write(&record1).unwrap();
 
@@ -823,12 +1447,15 @@ mod tests {
                 url: "https://b.com".to_string(),
             },
             response: Response {
-                body: "body of b".to_string(),
+                body: "body of b".into(),
                 payload_type: None,
+                status_code: Some(200),
+                headers: BTreeMap::new(),
             },
             metadata: Metadata {
                 fetch_time_ms: 4242,
             },
+            date: None,
         };
         writer.write(&record2).unwrap();
 
@@ -841,12 +1468,111 @@ mod tests {
 
         assert_eq!(records.len(), 2);
         assert_eq!(&records[0].request.url, "https://a.com");
-        assert_eq!(&records[0].response.body, "body of a");
+        assert_eq!(&*records[0].response.body, "body of a");
         assert_eq!(records[0].metadata.fetch_time_ms, 1337);
+        assert_eq!(records[0].date, DateTime::from_timestamp(1_700_000_000, 0));
 
         assert_eq!(&records[1].request.url, "https://b.com");
-        assert_eq!(&records[1].response.body, "body of b");
+        assert_eq!(&*records[1].response.body, "body of b");
         assert_eq!(records[1].metadata.fetch_time_ms, 4242);
+        assert_eq!(records[1].date, None);
+    }
+
+    #[test]
+    fn open_streaming() {
+        let mut writer = WarcWriter::new();
+
+        for i in 0..3 {
+            writer
+                .write(&WarcRecord {
+                    request: Request {
+                        url: format!("https://{i}.com"),
+                    },
+                    response: Response {
+                        body: format!("body of {i}").into(),
+                        payload_type: Some(PayloadType::Html),
+                        status_code: Some(200),
+                        headers: BTreeMap::new(),
+                    },
+                    metadata: Metadata { fetch_time_ms: i },
+                    date: None,
+                })
+                .unwrap();
+        }
+
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = dir.as_ref().join("streamed.warc.gz");
+        std::fs::write(&path, writer.finish().unwrap()).unwrap();
+
+        let streamed: Vec<WarcRecord> = WarcFile::open_streaming(&path)
+            .unwrap()
+            .map(|res| res.unwrap())
+            .collect();
+        let in_memory: Vec<WarcRecord> = WarcFile::open(&path)
+            .unwrap()
+            .records()
+            .map(|res| res.unwrap())
+            .collect();
+
+        assert_eq!(streamed.len(), 3);
+        assert_eq!(&streamed[2].request.url, "https://2.com");
+        assert_eq!(&*streamed[2].response.body, "body of 2");
+        assert_eq!(streamed, in_memory);
+    }
+
+    #[test]
+    fn zstd_records() {
+        let mut writer = WarcWriter::new();
+        writer
+            .write(&WarcRecord {
+                request: Request {
+                    url: "https://a.com".to_string(),
+                },
+                response: Response {
+                    body: "body of a".into(),
+                    payload_type: Some(PayloadType::Html),
+                    status_code: Some(200),
+                    headers: BTreeMap::new(),
+                },
+                metadata: Metadata {
+                    fetch_time_ms: 1337,
+                },
+                date: None,
+            })
+            .unwrap();
+        let gzip = writer.finish().unwrap();
+
+        let mut raw = Vec::new();
+        MultiGzDecoder::new(&gzip[..])
+            .read_to_end(&mut raw)
+            .unwrap();
+        let zstd = zstd::encode_all(&raw[..], 0).unwrap();
+
+        assert_eq!(Codec::detect(&gzip), Codec::Gzip);
+        assert_eq!(Codec::detect(&zstd), Codec::Zstd);
+
+        let from_gzip: Vec<WarcRecord> = WarcFile::new(gzip)
+            .records()
+            .map(|res| res.unwrap())
+            .collect();
+
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = dir.as_ref().join("records.warc.zst");
+        std::fs::write(&path, &zstd).unwrap();
+
+        let from_zstd: Vec<WarcRecord> = WarcFile::new(zstd)
+            .records()
+            .map(|res| res.unwrap())
+            .collect();
+        let streamed: Vec<WarcRecord> = WarcFile::open_streaming(&path)
+            .unwrap()
+            .map(|res| res.unwrap())
+            .collect();
+
+        assert_eq!(from_zstd.len(), 1);
+        assert_eq!(&from_zstd[0].request.url, "https://a.com");
+        assert_eq!(from_zstd, from_gzip);
+        assert_eq!(streamed, from_gzip);
     }
 
     #[test]
@@ -859,10 +1585,13 @@ mod tests {
                 url: "https://a.com".to_string(),
             },
             response: Response {
-                body: utf8.to_string(),
+                body: utf8.into(),
                 payload_type: Some(PayloadType::Html),
+                status_code: Some(200),
+                headers: BTreeMap::new(),
             },
             metadata: Metadata { fetch_time_ms: 0 },
+            date: None,
         };
         writer.write(&record).unwrap();
 
@@ -874,7 +1603,7 @@ mod tests {
 
         assert_eq!(records.len(), 1);
         assert_eq!(&records[0].request.url, "https://a.com");
-        assert_eq!(&records[0].response.body, utf8);
+        assert_eq!(&*records[0].response.body, utf8);
         assert_eq!(records[0].metadata.fetch_time_ms, 0);
     }
 
@@ -891,10 +1620,13 @@ mod tests {
                 url: "https://a.com".to_string(),
             },
             response: Response {
-                body: body.to_string(),
+                body: body.into(),
                 payload_type: Some(PayloadType::Html),
+                status_code: Some(200),
+                headers: BTreeMap::new(),
             },
             metadata: Metadata { fetch_time_ms: 0 },
+            date: None,
         };
         writer.write(&record).unwrap();
 
@@ -906,7 +1638,7 @@ mod tests {
 
         assert_eq!(records.len(), 1);
         assert_eq!(&records[0].request.url, "https://a.com");
-        assert_eq!(&records[0].response.body, body);
+        assert_eq!(&*records[0].response.body, body);
         assert_eq!(records[0].metadata.fetch_time_ms, 0);
     }
 
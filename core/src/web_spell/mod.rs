@@ -36,6 +36,7 @@ use std::ops::Range;
 
 use crate::ceil_char_boundary;
 use itertools::intersperse;
+use utoipa::ToSchema;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -60,44 +61,72 @@ pub enum Error {
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+#[derive(
+    PartialEq, Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone,
+)]
+pub struct Correction {
+    original: String,
+    pub terms: Vec<CorrectionTerm>,
+    /// The runner-up corrections of the corrected terms. Only filled
+    /// when alternatives are requested.
+    pub alternatives: Vec<TermAlternatives>,
+}
+
+#[derive(
+    PartialEq, Debug, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Clone,
+)]
+pub enum CorrectionTerm {
+    Corrected {
+        orig: String,
+        correction: String,
+        /// How much more likely the correction is than the original term
+        /// (the difference in log probability). Higher is more confident.
+        confidence: f64,
+    },
+    NotCorrected(String),
+}
+
+/// A possible correction of a term. The score is the log probability
+/// of the correction given the context, so higher is better.
 #[derive(
     PartialEq,
-    Eq,
     Debug,
     serde::Serialize,
     serde::Deserialize,
     bincode::Encode,
     bincode::Decode,
     Clone,
+    ToSchema,
 )]
-pub struct Correction {
-    original: String,
-    pub terms: Vec<CorrectionTerm>,
+#[serde(rename_all = "camelCase")]
+pub struct ScoredCorrection {
+    pub correction: String,
+    pub score: f64,
 }
 
+/// The highest scoring corrections of a term sorted by descending score.
+/// The original term is never one of the alternatives.
 #[derive(
     PartialEq,
-    Eq,
     Debug,
     serde::Serialize,
     serde::Deserialize,
     bincode::Encode,
     bincode::Decode,
     Clone,
+    ToSchema,
 )]
-pub enum CorrectionTerm {
-    Corrected { orig: String, correction: String },
-    NotCorrected(String),
+#[serde(rename_all = "camelCase")]
+pub struct TermAlternatives {
+    pub orig: String,
+    pub alternatives: Vec<ScoredCorrection>,
 }
 
 impl From<Correction> for String {
     fn from(correction: Correction) -> Self {
         intersperse(
             correction.terms.into_iter().map(|term| match term {
-                CorrectionTerm::Corrected {
-                    orig: _,
-                    correction,
-                } => correction,
+                CorrectionTerm::Corrected { correction, .. } => correction,
                 CorrectionTerm::NotCorrected(orig) => orig,
             }),
             " ".to_string(),
@@ -111,6 +140,7 @@ impl Correction {
         Self {
             original,
             terms: Vec::new(),
+            alternatives: Vec::new(),
         }
     }
 
@@ -25,7 +25,13 @@ use crate::{
     web_spell::stupid_backoff::{IntoMiddle, LeftToRight, RightToLeft},
 };
 
-use super::{error_model, Correction, CorrectionTerm, Error, ErrorModel, StupidBackoff, TermDict};
+use super::{
+    error_model, Correction, CorrectionTerm, Error, ErrorModel, ScoredCorrection, StupidBackoff,
+    TermAlternatives, TermDict,
+};
+
+/// The maximum number of passes over the text when correcting it.
+const MAX_CORRECTION_PASSES: usize = 3;
 
 struct LangSpellChecker {
     term_dict: TermDict,
@@ -76,14 +82,16 @@ impl LangSpellChecker {
         }
     }
 
-    fn score_candidates(
+    /// Score each candidate in the context of the term. The original term is
+    /// skipped and the candidates are returned sorted by descending score.
+    fn scored_candidates(
         &self,
         term: &str,
         candidates: &[String],
         context: Vec<String>,
         term_idx: usize,
-    ) -> Option<(String, f64)> {
-        let mut best_term: Option<(String, f64)> = None;
+    ) -> Vec<ScoredCorrection> {
+        let mut scored = Vec::with_capacity(candidates.len());
         let mut context = context;
 
         for candidate in candidates {
@@ -112,22 +120,36 @@ impl LangSpellChecker {
 
             let score = scaled_lm_log_prob + error_log_prob;
 
-            if best_term.is_none() || score > best_term.as_ref().unwrap().1 {
-                best_term = Some((candidate.clone(), score));
-            }
+            scored.push(ScoredCorrection {
+                correction: candidate.clone(),
+                score,
+            });
         }
 
-        best_term
-    }
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
 
-    fn correct_once(&self, text: &str) -> Option<Correction> {
-        let orig_terms = super::tokenize(text);
-        let mut terms = orig_terms.clone();
+        scored
+    }
 
-        let mut corrections = Vec::new();
+    /// Correct the terms in place, skipping the terms that are already corrected.
+    /// The confidence of each new correction is stored in `corrected` and the
+    /// `num_alternatives` best candidates in `alternatives`.
+    /// Returns whether any term was corrected.
+    fn correct_once(
+        &self,
+        terms: &mut [String],
+        corrected: &mut [Option<f64>],
+        alternatives: &mut [Vec<ScoredCorrection>],
+        num_alternatives: usize,
+    ) -> bool {
+        let mut changed = false;
 
         let num_terms = terms.len();
         for i in 0..num_terms {
+            if corrected[i].is_some() {
+                continue;
+            }
+
             let term = &terms[i];
             let candidates = self.candidates(term);
 
@@ -159,32 +181,76 @@ impl LangSpellChecker {
 
             tracing::debug!(?term, ?term_log_prob, ?scaled_term_log_prob);
 
-            if let Some((best_term, score)) =
-                self.score_candidates(term, &candidates, context, this_term_context_idx)
-            {
-                let diff = score - scaled_term_log_prob;
-                tracing::debug!(?best_term, ?score, ?diff);
+            let mut scored =
+                self.scored_candidates(term, &candidates, context, this_term_context_idx);
+
+            if let Some(best) = scored.first() {
+                let diff = best.score - scaled_term_log_prob;
+                tracing::debug!(?best, ?diff);
                 if diff.is_finite() && diff > self.config.correction_threshold {
-                    corrections.push((i, best_term.clone()));
-                    terms[i] = best_term; // make sure the next terms use the corrected context
+                    // make sure the next terms use the corrected context
+                    terms[i].clone_from(&best.correction);
+                    corrected[i] = Some(diff);
+                    scored.truncate(num_alternatives);
+                    alternatives[i] = scored;
+                    changed = true;
                 }
             }
         }
 
-        if corrections.is_empty() {
+        changed
+    }
+
+    /// Correct the text and keep up to `num_alternatives` of the best
+    /// candidates for each corrected term.
+    fn correct_with_alternatives(&self, text: &str, num_alternatives: usize) -> Option<Correction> {
+        let text = text.to_lowercase();
+        let orig_terms = super::tokenize(&text);
+        let mut terms = orig_terms.clone();
+
+        // a correction can give a better context for the terms before it,
+        // so we run multiple passes. each term is only corrected once
+        // so we don't get corrections to the corrections.
+        let mut corrected = vec![None; terms.len()];
+        let mut alternatives = vec![Vec::new(); terms.len()];
+
+        for _ in 0..MAX_CORRECTION_PASSES {
+            if !self.correct_once(
+                &mut terms,
+                &mut corrected,
+                &mut alternatives,
+                num_alternatives,
+            ) {
+                break;
+            }
+        }
+
+        if corrected.iter().all(Option::is_none) {
             return None;
         }
 
-        let mut res = Correction::empty(text.to_string());
+        let mut res = Correction::empty(text);
 
-        for (orig, possible_correction) in orig_terms.into_iter().zip(terms.into_iter()) {
-            if orig == possible_correction {
-                res.push(CorrectionTerm::NotCorrected(orig));
-            } else {
-                res.push(CorrectionTerm::Corrected {
+        for (((orig, possible_correction), confidence), term_alternatives) in orig_terms
+            .into_iter()
+            .zip(terms.into_iter())
+            .zip(corrected.into_iter())
+            .zip(alternatives.into_iter())
+        {
+            if !term_alternatives.is_empty() {
+                res.alternatives.push(TermAlternatives {
+                    orig: orig.clone(),
+                    alternatives: term_alternatives,
+                });
+            }
+
+            match confidence {
+                Some(confidence) => res.push(CorrectionTerm::Corrected {
                     orig,
                     correction: possible_correction,
-                });
+                    confidence,
+                }),
+                None => res.push(CorrectionTerm::NotCorrected(orig)),
             }
         }
 
@@ -192,16 +258,13 @@ impl LangSpellChecker {
     }
 
     fn correct(&self, text: &str) -> Option<Correction> {
-        // TODO:
-        // sometimes the text should be corrected more than once.
-        // we should make sure to only correct each term once so we don't
-        // get corrections to the corrections.
-        self.correct_once(text.to_lowercase().as_str())
+        self.correct_with_alternatives(text, 0)
     }
 }
 
 pub struct SpellChecker {
     lang_spell_checkers: FnvHashMap<Lang, LangSpellChecker>,
+    fallback_lang: Option<Lang>,
 }
 
 impl SpellChecker {
@@ -243,12 +306,33 @@ impl SpellChecker {
 
         Ok(Self {
             lang_spell_checkers,
+            fallback_lang: config.use_fallback_lang.then_some(config.fallback_lang),
+        })
+    }
+
+    /// The spell checker for `lang`, or the fallback language if enabled
+    /// and there is no spell checker for `lang`.
+    fn lang_spell_checker(&self, lang: &Lang) -> Option<&LangSpellChecker> {
+        self.lang_spell_checkers.get(lang).or_else(|| {
+            self.fallback_lang
+                .and_then(|fallback| self.lang_spell_checkers.get(&fallback))
         })
     }
+
     pub fn correct(&self, text: &str, lang: &Lang) -> Option<Correction> {
-        self.lang_spell_checkers
-            .get(lang)
-            .and_then(|s| s.correct(text))
+        self.lang_spell_checker(lang).and_then(|s| s.correct(text))
+    }
+
+    /// Like [`SpellChecker::correct`], but also returns up to `num_alternatives`
+    /// of the best corrections for each corrected term.
+    pub fn correct_with_alternatives(
+        &self,
+        text: &str,
+        lang: &Lang,
+        num_alternatives: usize,
+    ) -> Option<Correction> {
+        self.lang_spell_checker(lang)
+            .and_then(|s| s.correct_with_alternatives(text, num_alternatives))
     }
 }
 
@@ -269,12 +353,35 @@ mod tests {
                 res.push(CorrectionTerm::Corrected {
                     orig,
                     correction: corrected,
+                    confidence: 0.0,
                 });
             }
         }
 
         res
     }
+
+    /// Zero the confidences so the corrections can be compared with [`correction`].
+    fn without_confidence(mut correction: Correction) -> Correction {
+        for term in &mut correction.terms {
+            if let CorrectionTerm::Corrected { confidence, .. } = term {
+                *confidence = 0.0;
+            }
+        }
+
+        correction
+    }
+
+    fn max_confidence(correction: &Correction) -> f64 {
+        correction
+            .terms
+            .iter()
+            .filter_map(|term| match term {
+                CorrectionTerm::Corrected { confidence, .. } => Some(*confidence),
+                CorrectionTerm::NotCorrected(_) => None,
+            })
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
     #[test]
     fn simple() {
         let path = Path::new("../data/web_spell/checker");
@@ -292,8 +399,128 @@ mod tests {
 
         assert_eq!(spell_checker.correct("hello", &Lang::Eng), None);
         assert_eq!(
-            spell_checker.correct("dudw", &Lang::Eng),
+            spell_checker
+                .correct("dudw", &Lang::Eng)
+                .map(without_confidence),
+            Some(correction("dudw", "dude"))
+        );
+    }
+
+    #[test]
+    fn multiple_passes() {
+        let path = Path::new("../data/web_spell/checker");
+
+        if !path.exists() {
+            return;
+        }
+
+        let conf = CorrectionConfig {
+            correction_threshold: 16.0,
+            ..Default::default()
+        };
+
+        let spell_checker = SpellChecker::open(path, conf).unwrap();
+
+        // 'barak' is only corrected once 'obma' has been corrected to 'obama'
+        assert_eq!(
+            spell_checker
+                .correct("barak obma", &Lang::Eng)
+                .map(without_confidence),
+            Some(correction("barak obma", "barack obama"))
+        );
+    }
+
+    #[test]
+    fn confidence() {
+        let path = Path::new("../data/web_spell/checker");
+
+        if !path.exists() {
+            return;
+        }
+
+        let conf = CorrectionConfig {
+            correction_threshold: 0.0,
+            ..Default::default()
+        };
+
+        let spell_checker = SpellChecker::open(path, conf).unwrap();
+
+        let clear = spell_checker.correct("dudw", &Lang::Eng).unwrap();
+        // 'form' is a valid word, so correcting it to 'from' is borderline
+        let borderline = spell_checker
+            .correct("he came form the city", &Lang::Eng)
+            .unwrap();
+
+        assert!(max_confidence(&clear) > 16.0);
+        assert!(max_confidence(&borderline) > 0.0);
+        assert!(max_confidence(&clear) > max_confidence(&borderline));
+    }
+
+    #[test]
+    fn fallback_lang() {
+        let path = Path::new("../data/web_spell/checker");
+
+        if !path.exists() {
+            return;
+        }
+
+        let conf = CorrectionConfig {
+            correction_threshold: 16.0,
+            ..Default::default()
+        };
+
+        let spell_checker = SpellChecker::open(path, conf).unwrap();
+        assert_eq!(spell_checker.correct("dudw", &Lang::Jpn), None);
+
+        let conf = CorrectionConfig {
+            correction_threshold: 16.0,
+            use_fallback_lang: true,
+            fallback_lang: Lang::Eng,
+            ..Default::default()
+        };
+
+        let spell_checker = SpellChecker::open(path, conf).unwrap();
+        assert_eq!(
+            spell_checker
+                .correct("dudw", &Lang::Jpn)
+                .map(without_confidence),
             Some(correction("dudw", "dude"))
         );
     }
+
+    #[test]
+    fn alternatives() {
+        let path = Path::new("../data/web_spell/checker");
+
+        if !path.exists() {
+            return;
+        }
+
+        let conf = CorrectionConfig {
+            correction_threshold: 16.0,
+            ..Default::default()
+        };
+
+        let spell_checker = SpellChecker::open(path, conf).unwrap();
+
+        let correction = spell_checker.correct("dudw", &Lang::Eng).unwrap();
+        assert!(correction.alternatives.is_empty());
+
+        let correction = spell_checker
+            .correct_with_alternatives("dudw", &Lang::Eng, 3)
+            .unwrap();
+
+        assert_eq!(correction.alternatives.len(), 1);
+        let term = &correction.alternatives[0];
+        assert_eq!(term.orig, "dudw");
+
+        assert!(!term.alternatives.is_empty());
+        assert!(term.alternatives.len() <= 3);
+        assert_eq!(term.alternatives[0].correction, "dude");
+        assert!(term.alternatives.iter().all(|a| a.correction != "dudw"));
+        assert!(term
+            .alternatives
+            .windows(2)
+            .all(|w| w[0].score >= w[1].score));
+    }
 }
@@ -20,7 +20,7 @@ use dashmap::DashMap;
 use indicatif::ParallelProgressIterator;
 use rayon::prelude::*;
 
-use crate::webgraph::{NodeID, ShortestPaths, Webgraph};
+use crate::webgraph::{EdgeLimit, NodeID, ShortestPaths, Webgraph, DEFAULT_EDGE_WEIGHT};
 
 const EPSILON: f64 = 0.3;
 
@@ -60,6 +60,14 @@ impl ApproxHarmonic {
 
                 *centralities.entry(target).or_default() += (1.0 / dist) * norm;
             }
+
+            // direct links contribute their weight instead of 1
+            for edge in graph.raw_outgoing_edges(&source, EdgeLimit::Unlimited) {
+                if edge.weight() != DEFAULT_EDGE_WEIGHT && edge.to.node() != source {
+                    *centralities.entry(edge.to.node()).or_default() +=
+                        (edge.weight() - DEFAULT_EDGE_WEIGHT) * norm;
+                }
+            }
         });
 
         let mut res = Self {
@@ -27,7 +27,7 @@ use tracing::info;
 use crate::{
     hyperloglog::HyperLogLog,
     kahan_sum::KahanSum,
-    webgraph::{EdgeLimit, NodeID, Webgraph},
+    webgraph::{Edge, EdgeLimit, NodeID, Webgraph, DEFAULT_EDGE_WEIGHT},
     webpage::html::links::RelFlags,
 };
 
@@ -112,12 +112,14 @@ fn update_changed_counters(
     has_changes.load(Ordering::Relaxed)
 }
 
+/// If `edge_weights` is given, the extra weight of every edge is added to it.
 fn update_all_counters(
     graph: &Webgraph,
     mut exact_changed_nodes: Option<&mut BTreeSet<NodeID>>,
     counters: &mut Counters,
     changed_nodes: &U64BloomFilter,
     new_changed_nodes: &mut U64BloomFilter,
+    mut edge_weights: Option<&mut BTreeMap<NodeID, KahanSum>>,
 ) -> bool {
     let has_changes = AtomicBool::new(false);
 
@@ -129,6 +131,16 @@ fn update_all_counters(
         .edges()
         .filter(|e| !e.rel_flags().intersects(*SKIPPED_REL))
         .for_each(|edge| {
+            if let Some(edge_weights) = &mut edge_weights {
+                let extra = extra_edge_weight(&edge);
+
+                if extra != 0.0 {
+                    if let Some(score) = edge_weights.get_mut(&edge.to.node()) {
+                        *score += extra;
+                    }
+                }
+            }
+
             if changed_nodes.contains(edge.from.node().as_u64()) {
                 if let (Some(counter_to), Some(counter_from)) = (
                     counters.new.get_mut(&edge.to.node()),
@@ -194,6 +206,20 @@ fn normalize_centralities(
         .collect()
 }
 
+/// A direct link contributes its weight to the centrality of the node it points to,
+/// where the unweighted harmonic centrality would count it as 1. Nodes further away
+/// still contribute `1 / distance`. This returns what the link adds on top of the 1.
+///
+/// A link is only stored once per segment, so a link that is found in several
+/// segments adds its extra weight once for each of them.
+pub fn extra_edge_weight(edge: &Edge<()>) -> f64 {
+    if edge.from.node() == edge.to.node() {
+        return 0.0;
+    }
+
+    (edge.weight() - DEFAULT_EDGE_WEIGHT) as f64
+}
+
 struct Counters {
     old: Counter,
     new: Counter,
@@ -257,14 +283,18 @@ fn calculate_centrality(graph: &Webgraph) -> BTreeMap<NodeID, f64> {
                 &mut counters,
                 &changed_nodes,
                 &mut new_changed_nodes,
+                (t == 0).then_some(&mut centralities),
             );
         } else {
+            // every node has changed in the first round, so all edges are visited
+            // and their weights can be added in the same pass
             has_changes = update_all_counters(
                 graph,
                 None,
                 &mut counters,
                 &changed_nodes,
                 &mut new_changed_nodes,
+                (t == 0).then_some(&mut centralities),
             );
         }
 
@@ -479,6 +509,38 @@ mod tests {
         assert_eq!(centrality.get(&Node::from("D").id()), None);
     }
 
+    #[test]
+    fn weighted_harmonic_centrality() {
+        let temp_dir = crate::gen_temp_dir().unwrap();
+        let mut writer = WebgraphWriter::new(
+            temp_dir.as_ref().join("test"),
+            crate::executor::Executor::single_thread(),
+            crate::webgraph::Compression::default(),
+            None,
+        );
+
+        for (from, to, label) in test_edges() {
+            if from == Node::from("D") {
+                writer.insert_with_weight(from, to, label, RelFlags::default(), 4.0);
+            } else {
+                writer.insert(from, to, label, RelFlags::default());
+            }
+        }
+
+        let weighted_graph = writer.finalize();
+        let (graph, _temp_dir) = test_graph();
+
+        let weighted = HarmonicCentrality::calculate(&weighted_graph);
+        let unweighted = HarmonicCentrality::calculate(&graph);
+
+        let c = Node::from("C").id();
+        let a = Node::from("A").id();
+
+        assert!(weighted.get(&c).unwrap() > unweighted.get(&c).unwrap());
+        assert!((weighted.get(&c).unwrap() - unweighted.get(&c).unwrap() - 1.0).abs() < 1e-6);
+        assert_eq!(weighted.get(&a), unweighted.get(&a));
+    }
+
     #[test]
     fn additional_edges_ignored() {
         let (graph, _temp_dir) = test_graph();
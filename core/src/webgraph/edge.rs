@@ -22,6 +22,9 @@ use super::{FullNodeID, Node, NodeDatum, NodeID};
 
 pub const MAX_LABEL_LENGTH: usize = 1024;
 
+/// The weight of edges that were inserted without one.
+pub const DEFAULT_EDGE_WEIGHT: f32 = 1.0;
+
 pub trait EdgeLabel
 where
     Self: Send + Sync + Sized,
@@ -50,7 +53,7 @@ impl EdgeLabel for () {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, PartialEq, bincode::Encode, bincode::Decode)]
 pub struct Edge<L>
 where
     L: EdgeLabel,
@@ -59,6 +62,7 @@ where
     pub to: NodeDatum,
     pub rel: RelFlags,
     pub label: L,
+    pub weight: f32,
 }
 
 impl<L> Edge<L>
@@ -68,9 +72,13 @@ where
     pub fn rel_flags(&self) -> RelFlags {
         self.rel
     }
+
+    pub fn weight(&self) -> f32 {
+        self.weight
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, bincode::Encode, bincode::Decode)]
+#[derive(Debug, Clone, PartialEq, bincode::Encode, bincode::Decode)]
 pub struct InsertableEdge<L>
 where
     L: EdgeLabel,
@@ -79,6 +87,7 @@ where
     pub to: FullNodeID,
     pub rel: RelFlags,
     pub label: L,
+    pub weight: f32,
 }
 
 #[cfg(test)]
@@ -92,6 +101,7 @@ where
             to: NodeDatum::new(edge.to.id, u64::MAX),
             rel: edge.rel,
             label: edge.label,
+            weight: edge.weight,
         }
     }
 }
@@ -124,6 +134,7 @@ where
     pub to: NodeDatum,
     pub rel: RelFlags,
     pub label: L,
+    pub weight: f32,
 }
 
 impl<L> From<SegmentEdge<L>> for Edge<L>
@@ -136,6 +147,7 @@ where
             to: edge.to,
             rel: edge.rel,
             label: edge.label,
+            weight: edge.weight,
         }
     }
 }
@@ -151,6 +163,7 @@ where
             to: edge.to,
             rel: edge.rel,
             label: edge.label,
+            weight: edge.weight,
         }
     }
 }
@@ -166,6 +179,7 @@ where
             to: NodeDatum::new(edge.to.id, u64::MAX),
             rel: edge.rel,
             label: edge.label,
+            weight: edge.weight,
         }
     }
 }
@@ -175,6 +189,7 @@ pub struct StoredEdge<L = ()> {
     pub other: NodeDatum,
     pub rel: RelFlags,
     pub label: L,
+    pub weight: f32,
 }
 
 impl StoredEdge<()> {
@@ -183,6 +198,7 @@ impl StoredEdge<()> {
             other,
             rel,
             label: (),
+            weight: DEFAULT_EDGE_WEIGHT,
         }
     }
 }
@@ -193,9 +209,14 @@ impl<L> StoredEdge<L> {
             other: self.other,
             rel: self.rel,
             label,
+            weight: self.weight,
         }
     }
 
+    pub fn with_weight(self, weight: f32) -> Self {
+        Self { weight, ..self }
+    }
+
     #[inline]
     pub fn label(&self) -> &L {
         &self.label
@@ -215,4 +236,9 @@ impl<L> StoredEdge<L> {
     pub fn other_host_rank(&self) -> u64 {
         self.other.host_rank()
     }
+
+    #[inline]
+    pub fn weight(&self) -> f32 {
+        self.weight
+    }
 }
@@ -5,6 +5,7 @@
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::{fs, io};
 
@@ -98,6 +99,106 @@ impl EdgeLimit {
     }
 }
 
+/// Number of merged nodes between two progress reports of a segment merge.
+const DEFAULT_NODES_PER_REPORT: usize = 1_000_000;
+
+/// The progress of a merge. [`Webgraph::merge_with`] counts the segments that
+/// have been moved, while [`Webgraph::merge_all_segments_with`] counts the nodes
+/// that have been merged in both edge directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeProgress {
+    pub merged: usize,
+    pub total: usize,
+}
+
+/// Progress reporting and cancellation for long running merges.
+///
+/// A cancelled merge always leaves the graph in a consistent state. Moving segments
+/// stops at the next segment, and merging segments is aborted without replacing
+/// any of the segments.
+#[derive(Clone)]
+pub struct MergeControl {
+    progress: Option<Arc<dyn Fn(MergeProgress) + Send + Sync>>,
+    nodes_per_report: usize,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Default for MergeControl {
+    fn default() -> Self {
+        Self {
+            progress: None,
+            nodes_per_report: DEFAULT_NODES_PER_REPORT,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl MergeControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_progress<F>(mut self, progress: F) -> Self
+    where
+        F: Fn(MergeProgress) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Report the progress of a segment merge every `nodes_per_report` merged nodes.
+    pub fn with_nodes_per_report(mut self, nodes_per_report: usize) -> Self {
+        self.nodes_per_report = nodes_per_report.max(1);
+        self
+    }
+
+    /// Stop the merge as soon as possible.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn report(&self, merged: usize, total: usize) {
+        if let Some(progress) = &self.progress {
+            progress(MergeProgress { merged, total });
+        }
+    }
+}
+
+/// Counts the nodes merged by the edge stores of a segment merge, which are merged
+/// concurrently, and reports them to the [`MergeControl`].
+pub(crate) struct MergeTracker {
+    control: MergeControl,
+    merged: AtomicUsize,
+    total: usize,
+}
+
+impl MergeTracker {
+    fn new(control: MergeControl, total: usize) -> Self {
+        Self {
+            control,
+            merged: AtomicUsize::new(0),
+            total,
+        }
+    }
+
+    /// Record that `num_nodes` nodes have been merged.
+    /// Returns false if the merge has been cancelled.
+    pub(crate) fn advance(&self, num_nodes: usize) -> bool {
+        let prev = self.merged.fetch_add(num_nodes, Ordering::Relaxed);
+        let merged = prev + num_nodes;
+
+        if prev / self.control.nodes_per_report != merged / self.control.nodes_per_report {
+            self.control.report(merged, self.total);
+        }
+
+        !self.control.is_cancelled()
+    }
+}
+
 pub struct Webgraph {
     path: String,
     segments: Vec<Segment>,
@@ -149,11 +250,27 @@ impl Webgraph {
     }
 
     pub fn merge(&mut self, other: Webgraph) -> io::Result<()> {
+        self.merge_with(other, &MergeControl::default())
+    }
+
+    /// Move the segments of `other` into this graph. If the merge is cancelled,
+    /// the segments that have not been moved yet are left in `other`.
+    pub fn merge_with(&mut self, mut other: Webgraph, control: &MergeControl) -> io::Result<()> {
         let other_folder = other.path.clone();
         self.id2node.merge(other.id2node);
         self.id2node.flush();
 
-        for segment in other.segments {
+        let total = other.segments.len();
+        control.report(0, total);
+
+        let mut segments = other.segments.into_iter();
+        let mut merged = 0;
+
+        while !control.is_cancelled() {
+            let Some(segment) = segments.next() else {
+                break;
+            };
+
             let id = segment.id();
             let new_path = Path::new(&self.path).join("segments");
             std::fs::rename(segment.path(), new_path.join(segment.id())).unwrap();
@@ -161,22 +278,57 @@ impl Webgraph {
             self.meta.comitted_segments.push(segment.id());
             drop(segment);
             self.segments.push(Segment::open(new_path, id));
+            self.save_metadata();
+
+            merged += 1;
+            control.report(merged, total);
         }
 
-        fs::remove_dir_all(other_folder)?;
+        let remaining: Vec<_> = segments.map(|segment| segment.id()).collect();
 
-        self.save_metadata();
+        if remaining.is_empty() {
+            fs::remove_dir_all(other_folder)?;
+        } else {
+            other.meta.comitted_segments = remaining;
+            other
+                .meta
+                .save(Path::new(&other_folder).join("metadata.json"));
+        }
 
         Ok(())
     }
 
     pub fn merge_all_segments(&mut self, compression: Compression) -> Result<()> {
+        self.merge_all_segments_with(compression, &MergeControl::default())
+    }
+
+    /// Merge all segments into a single segment.
+    ///
+    /// Progress is reported from inside the merge. If the merge is cancelled,
+    /// the partially merged segment is discarded and the graph keeps its segments.
+    pub fn merge_all_segments_with(
+        &mut self,
+        compression: Compression,
+        control: &MergeControl,
+    ) -> Result<()> {
+        let path = Path::new(&self.path).join("segments");
         let segments = std::mem::take(&mut self.segments);
+        let ids: Vec<_> = segments.iter().map(|segment| segment.id()).collect();
+
+        let total = segments.iter().map(|segment| segment.num_nodes()).sum();
+        let tracker = Arc::new(MergeTracker::new(control.clone(), total));
+        control.report(0, total);
 
         let id = Uuid::new_v4().to_string();
-        let path = Path::new(&self.path).join("segments");
 
-        Segment::merge(segments, compression, &path, id.clone())?;
+        if !Segment::merge(segments, compression, &path, id.clone(), tracker)? {
+            self.segments = ids.into_iter().map(|id| Segment::open(&path, id)).collect();
+
+            return Ok(());
+        }
+
+        control.report(total, total);
+
         let new_segment = Segment::open(path, id.clone());
 
         self.segments.push(new_segment);
@@ -252,6 +404,7 @@ impl Webgraph {
                 to: e.to,
                 label: e.label,
                 rel: e.rel,
+                weight: e.weight,
             })
             .collect()
     }
@@ -276,6 +429,7 @@ impl Webgraph {
                 to: e.to,
                 label: e.label,
                 rel: e.rel,
+                weight: e.weight,
             })
             .collect()
     }
@@ -300,6 +454,7 @@ impl Webgraph {
                 to: e.to,
                 label: e.label,
                 rel: e.rel,
+                weight: e.weight,
             })
             .collect()
     }
@@ -354,6 +509,7 @@ impl Webgraph {
                 to: e.to,
                 label: e.label,
                 rel: e.rel,
+                weight: e.weight,
             })
             .collect()
     }
@@ -467,6 +623,26 @@ pub mod tests {
         assert_eq!(distances.get(&Node::from("B")), Some(&3));
     }
 
+    #[test]
+    #[should_panic(expected = "edge weight must be positive")]
+    fn non_positive_edge_weight() {
+        let temp_dir = crate::gen_temp_dir().unwrap();
+        let mut graph = WebgraphWriter::new(
+            &temp_dir,
+            Executor::single_thread(),
+            Compression::default(),
+            None,
+        );
+
+        graph.insert_with_weight(
+            Node::from("A"),
+            Node::from("B"),
+            String::new(),
+            RelFlags::default(),
+            0.0,
+        );
+    }
+
     #[test]
     fn nonexisting_node() {
         let (graph, _temp_dir) = test_graph();
@@ -917,6 +1093,143 @@ pub mod tests {
         proptest_case(&nodes);
     }
 
+    fn segmented_graph(dir: &TempDir, edges: &[(&str, &str)]) -> Webgraph {
+        let mut graphs: Vec<_> = edges
+            .iter()
+            .map(|(from, to)| {
+                let mut wrt = WebgraphWriter::new(
+                    dir.as_ref().join(uuid::Uuid::new_v4().to_string()),
+                    Executor::single_thread(),
+                    Compression::default(),
+                    None,
+                );
+                wrt.insert(
+                    Node::from(*from),
+                    Node::from(*to),
+                    String::new(),
+                    RelFlags::default(),
+                );
+                wrt.finalize()
+            })
+            .collect();
+
+        let mut graph = graphs.pop().unwrap();
+        for other in graphs {
+            graph.merge(other).unwrap();
+        }
+
+        graph
+    }
+
+    fn assert_has_edges(graph: &Webgraph, edges: &[(&str, &str)]) {
+        for (from, to) in edges {
+            let outgoing = graph.outgoing_edges(Node::from(*from), EdgeLimit::Unlimited);
+            let ingoing = graph.ingoing_edges(Node::from(*to), EdgeLimit::Unlimited);
+
+            assert!(outgoing.iter().any(|e| e.to == Node::from(*to)));
+            assert!(ingoing.iter().any(|e| e.from == Node::from(*from)));
+        }
+    }
+
+    #[test]
+    fn cancel_merge_all_segments() {
+        let edges = [("a", "b"), ("b", "c"), ("c", "d"), ("d", "a")];
+        let temp_dir = crate::gen_temp_dir().unwrap();
+        let mut graph = segmented_graph(&temp_dir, &edges);
+        assert_eq!(graph.segments.len(), 4);
+
+        let reported = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let control = MergeControl::new().with_nodes_per_report(1);
+        let control = control.clone().with_progress({
+            let reported = reported.clone();
+            let control = control.clone();
+            move |progress| {
+                reported.lock().unwrap().push(progress);
+
+                if progress.merged >= 1 {
+                    control.cancel();
+                }
+            }
+        });
+
+        graph
+            .merge_all_segments_with(Compression::default(), &control)
+            .unwrap();
+
+        // each segment has one node in each direction
+        assert!(control.is_cancelled());
+        assert_eq!(
+            reported.lock().unwrap()[0],
+            MergeProgress {
+                merged: 0,
+                total: 8
+            }
+        );
+        assert_eq!(graph.segments.len(), 4);
+        assert_has_edges(&graph, &edges);
+
+        // the cancelled merge should not have touched the graph on disk
+        let path = graph.path();
+        drop(graph);
+        let mut graph = Webgraph::builder(&path).single_threaded().open();
+        assert_eq!(graph.segments.len(), 4);
+        assert_has_edges(&graph, &edges);
+
+        let reported = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let control = MergeControl::new().with_nodes_per_report(1).with_progress({
+            let reported = reported.clone();
+            move |progress| reported.lock().unwrap().push(progress)
+        });
+
+        graph
+            .merge_all_segments_with(Compression::default(), &control)
+            .unwrap();
+
+        // the progress is reported from inside the single merge of all segments
+        let reported = reported.lock().unwrap();
+        assert!(reported.len() > 2);
+        assert_eq!(
+            reported.last(),
+            Some(&MergeProgress {
+                merged: 8,
+                total: 8
+            })
+        );
+        assert_eq!(graph.segments.len(), 1);
+        assert_has_edges(&graph, &edges);
+    }
+
+    #[test]
+    fn cancel_merge() {
+        let temp_dir = crate::gen_temp_dir().unwrap();
+        let mut graph = segmented_graph(&temp_dir, &[("a", "b")]);
+        let other = segmented_graph(&temp_dir, &[("b", "c"), ("c", "d"), ("d", "a")]);
+        let other_path = other.path();
+
+        let control = MergeControl::new();
+        let control = control.clone().with_progress({
+            let control = control.clone();
+            move |progress| {
+                if progress.merged == 1 {
+                    control.cancel();
+                }
+            }
+        });
+
+        graph.merge_with(other, &control).unwrap();
+
+        assert_eq!(graph.segments.len(), 2);
+
+        // the remaining segments are left in the other graph
+        let other = Webgraph::builder(&other_path).single_threaded().open();
+        assert_eq!(graph.segments.len() + other.segments.len(), 4);
+
+        graph.merge(other).unwrap();
+        graph.merge_all_segments(Compression::default()).unwrap();
+
+        assert_has_edges(&graph, &[("a", "b"), ("b", "c"), ("c", "d"), ("d", "a")]);
+    }
+
     #[test]
     fn node_lowercase_name() {
         let n = Node::from("TEST".to_string());
@@ -10,8 +10,8 @@ use std::{
 };
 
 use super::{
-    store::EdgeStore, store_writer::EdgeStoreWriter, Compression, InsertableEdge, NodeID,
-    SegmentEdge,
+    store::EdgeStore, store_writer::EdgeStoreWriter, Compression, InsertableEdge, MergeTracker,
+    NodeID, SegmentEdge,
 };
 use crate::Result;
 
@@ -101,20 +101,30 @@ impl Segment {
         }
     }
 
-    pub fn merge<P: AsRef<Path>>(
+    /// The number of nodes in the adjacency and reversed adjacency stores.
+    pub fn num_nodes(&self) -> usize {
+        self.adjacency.num_nodes() + self.reversed_adjacency.num_nodes()
+    }
+
+    /// Merge the segments into a new segment `id` in `folder` and remove the old segments.
+    ///
+    /// Returns false if the merge was cancelled through the `tracker`. The partially
+    /// merged segment is then removed and the old segments are left untouched.
+    pub(super) fn merge<P: AsRef<Path>>(
         segments: Vec<Self>,
         label_compression: Compression,
         folder: P,
         id: String,
-    ) -> Result<()> {
+        tracker: Arc<MergeTracker>,
+    ) -> Result<bool> {
         if segments.is_empty() {
-            return Ok(());
+            return Ok(true);
         }
 
         if segments.len() == 1 {
             let segment = &segments[0];
             std::fs::rename(segment.path(), folder.as_ref().join(&id))?;
-            return Ok(());
+            return Ok(true);
         }
 
         let old_paths = segments.iter().map(|s| s.path()).collect::<Vec<_>>();
@@ -125,8 +135,10 @@ impl Segment {
             .unzip();
 
         let adjacency_path = folder.as_ref().join(&id).join(ADJACENCY_STORE);
-        let adjacency =
-            thread::spawn(move || EdgeStore::merge(adjacency, label_compression, adjacency_path));
+        let adjacency = thread::spawn({
+            let tracker = Arc::clone(&tracker);
+            move || EdgeStore::merge(adjacency, label_compression, adjacency_path, &tracker)
+        });
 
         let reversed_adjacency_path = folder.as_ref().join(&id).join(REVERSED_ADJACENCY_STORE);
         let reversed_adjacency = thread::spawn(move || {
@@ -134,11 +146,21 @@ impl Segment {
                 reversed_adjacency,
                 label_compression,
                 reversed_adjacency_path,
+                &tracker,
             )
         });
 
-        adjacency.join().unwrap()?;
-        reversed_adjacency.join().unwrap()?;
+        let adjacency = adjacency.join().unwrap()?;
+        let reversed_adjacency = reversed_adjacency.join().unwrap()?;
+
+        if !adjacency || !reversed_adjacency {
+            let new_path = folder.as_ref().join(&id);
+            if new_path.exists() {
+                std::fs::remove_dir_all(new_path)?;
+            }
+
+            return Ok(false);
+        }
 
         for path in old_paths {
             if Path::new(&path).exists() {
@@ -146,7 +168,7 @@ impl Segment {
             }
         }
 
-        Ok(())
+        Ok(true)
     }
 
     pub fn outgoing_edges_with_label<'a>(
@@ -254,24 +276,28 @@ mod test {
             to: b.clone(),
             label: String::new(),
             rel: RelFlags::default(),
+            weight: 1.0,
         });
         edges.push(InsertableEdge {
             from: b.clone(),
             to: c.clone(),
             label: String::new(),
             rel: RelFlags::default(),
+            weight: 1.0,
         });
         edges.push(InsertableEdge {
             from: c.clone(),
             to: a.clone(),
             label: String::new(),
             rel: RelFlags::default(),
+            weight: 1.0,
         });
         edges.push(InsertableEdge {
             from: a.clone(),
             to: c.clone(),
             label: String::new(),
             rel: RelFlags::default(),
+            weight: 1.0,
         });
 
         for edge in &edges {
@@ -291,6 +317,7 @@ mod test {
                     to: NodeDatum::new(b.id, u64::MAX),
                     label: (),
                     rel: RelFlags::default(),
+                    weight: 1.0,
                 }
                 .into(),
                 Edge {
@@ -298,6 +325,7 @@ mod test {
                     to: NodeDatum::new(c.id, u64::MAX),
                     label: (),
                     rel: RelFlags::default(),
+                    weight: 1.0,
                 }
                 .into(),
             ]
@@ -312,6 +340,7 @@ mod test {
                 to: NodeDatum::new(c.id, u64::MAX),
                 label: (),
                 rel: RelFlags::default(),
+                weight: 1.0,
             }
             .into(),]
         );
@@ -325,6 +354,7 @@ mod test {
                 to: NodeDatum::new(a.id, u64::MAX),
                 label: (),
                 rel: RelFlags::default(),
+                weight: 1.0,
             }
             .into(),]
         );
@@ -337,6 +367,7 @@ mod test {
                 to: NodeDatum::new(a.id, u64::MAX),
                 label: (),
                 rel: RelFlags::default(),
+                weight: 1.0,
             }
             .into(),]
         );
@@ -349,6 +380,7 @@ mod test {
                 to: NodeDatum::new(b.id, u64::MAX),
                 label: (),
                 rel: RelFlags::default(),
+                weight: 1.0,
             }
             .into(),]
         );
@@ -363,6 +395,7 @@ mod test {
                     to: NodeDatum::new(c.id, u64::MAX),
                     label: (),
                     rel: RelFlags::default(),
+                    weight: 1.0,
                 }
                 .into(),
                 Edge {
@@ -370,6 +403,7 @@ mod test {
                     to: NodeDatum::new(c.id, u64::MAX),
                     label: (),
                     rel: RelFlags::default(),
+                    weight: 1.0,
                 }
                 .into(),
             ]
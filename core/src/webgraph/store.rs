@@ -23,7 +23,8 @@ use itertools::Itertools;
 
 use super::{
     merge::{MergeNode, MergeSegmentOrd},
-    Compression, FullNodeID, NodeDatum, NodeID, SegmentEdge, StoredEdge,
+    Compression, FullNodeID, MergeTracker, NodeDatum, NodeID, SegmentEdge, StoredEdge,
+    DEFAULT_EDGE_WEIGHT,
 };
 
 pub struct HostDb {
@@ -222,19 +223,33 @@ impl ConstSerializable for EdgeRange {
     }
 }
 
+/// The weight is stored in what used to be the padding after the rel flags.
+/// The padding was always written as zeros, so a zero weight means that the graph
+/// was written before edges had weights.
+const STORED_EDGE_WEIGHT_OFFSET: usize = NodeDatum::BYTES + RelFlags::BYTES;
+
 impl ConstSerializable for StoredEdge {
     const BYTES: usize = std::mem::size_of::<StoredEdge>();
 
     fn serialize(&self, buf: &mut [u8]) {
         self.other.serialize(&mut buf[..NodeDatum::BYTES]);
-        self.rel.serialize(&mut buf[NodeDatum::BYTES..]);
+        self.rel
+            .serialize(&mut buf[NodeDatum::BYTES..STORED_EDGE_WEIGHT_OFFSET]);
+        self.weight
+            .to_bits()
+            .serialize(&mut buf[STORED_EDGE_WEIGHT_OFFSET..]);
     }
 
     fn deserialize(buf: &[u8]) -> Self {
         let other = NodeDatum::deserialize(&buf[..NodeDatum::BYTES]);
-        let rel = RelFlags::deserialize(&buf[NodeDatum::BYTES..]);
+        let rel = RelFlags::deserialize(&buf[NodeDatum::BYTES..STORED_EDGE_WEIGHT_OFFSET]);
+
+        let weight = match u32::deserialize(&buf[STORED_EDGE_WEIGHT_OFFSET..]) {
+            0 => DEFAULT_EDGE_WEIGHT,
+            bits => f32::from_bits(bits),
+        };
 
-        Self::new(other, rel)
+        Self::new(other, rel).with_weight(weight)
     }
 }
 
@@ -331,11 +346,13 @@ impl EdgeStore {
         EdgeMerger::new(edges)
     }
 
+    /// Returns `None` if the merge was cancelled.
     fn merge_postings<P: AsRef<Path>>(
         stores: &[EdgeStore],
         label_compression: Compression,
         folder: P,
-    ) -> Result<Self> {
+        tracker: &MergeTracker,
+    ) -> Result<Option<Self>> {
         let reversed = stores[0].reversed;
         let mut ranges = RangesDb::open(folder.as_ref().join("ranges"));
 
@@ -372,6 +389,10 @@ impl EdgeStore {
                 continue;
             }
 
+            if !tracker.advance(buf.len()) {
+                return Ok(None);
+            }
+
             let edges = Self::merge_postings_for_node(&buf, stores);
 
             // write postings
@@ -443,16 +464,19 @@ impl EdgeStore {
             labels_store.flush().unwrap();
         }
 
-        Ok(Self::open(folder, reversed))
+        Ok(Some(Self::open(folder, reversed)))
     }
 
+    /// Merge the stores into a new store at `path`.
+    /// Returns false if the merge was cancelled through the `tracker`.
     pub fn merge<P: AsRef<Path>>(
         stores: Vec<EdgeStore>,
         label_compression: Compression,
         path: P,
-    ) -> Result<()> {
+        tracker: &MergeTracker,
+    ) -> Result<bool> {
         if stores.is_empty() {
-            return Ok(());
+            return Ok(true);
         }
 
         if !path.as_ref().exists() {
@@ -464,7 +488,9 @@ impl EdgeStore {
             bail!("Cannot merge stores with different reversed flags");
         }
 
-        let mut res = Self::merge_postings(&stores, label_compression, path)?;
+        let Some(mut res) = Self::merge_postings(&stores, label_compression, path, tracker)? else {
+            return Ok(false);
+        };
 
         for store in stores {
             res.hosts.merge(store.hosts);
@@ -472,7 +498,11 @@ impl EdgeStore {
 
         res.optimize_read();
 
-        Ok(())
+        Ok(true)
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.ranges.edges.len()
     }
 
     pub fn degree(&self, node: &NodeID) -> u64 {
@@ -516,6 +546,7 @@ impl EdgeStore {
                             to: NodeDatum::new(*node, node_range.host_rank),
                             rel: edge.rel,
                             label,
+                            weight: edge.weight,
                         }
                     } else {
                         SegmentEdge {
@@ -523,6 +554,7 @@ impl EdgeStore {
                             to: edge.other,
                             rel: edge.rel,
                             label,
+                            weight: edge.weight,
                         }
                     }
                 }))
@@ -550,6 +582,7 @@ impl EdgeStore {
                             to: NodeDatum::new(*node, edge_range.host_rank),
                             rel: edge.rel,
                             label: (),
+                            weight: edge.weight,
                         }
                     } else {
                         SegmentEdge {
@@ -557,6 +590,7 @@ impl EdgeStore {
                             to: edge.other,
                             rel: edge.rel,
                             label: (),
+                            weight: edge.weight,
                         }
                     }
                 }))
@@ -588,6 +622,7 @@ impl EdgeStore {
                         to: NodeDatum::new(node, edge_range.host_rank),
                         rel: edge.rel,
                         label: (),
+                        weight: edge.weight,
                     }
                 } else {
                     SegmentEdge {
@@ -595,6 +630,7 @@ impl EdgeStore {
                         to: edge.other,
                         rel: edge.rel,
                         label: (),
+                        weight: edge.weight,
                     }
                 }
             })
@@ -627,6 +663,7 @@ mod tests {
             },
             label: "test".to_string(),
             rel: RelFlags::default(),
+            weight: 1.0,
         };
 
         kv.put(e.clone());
@@ -647,6 +684,20 @@ mod tests {
         assert_eq!(edges.len(), 1);
     }
 
+    #[test]
+    fn stored_edge_weight() {
+        let edge = StoredEdge::new(NodeDatum::new(NodeID::from(1_u64), 0), RelFlags::default())
+            .with_weight(2.5);
+
+        let mut buf = vec![0; StoredEdge::BYTES];
+        edge.serialize(&mut buf);
+        assert_eq!(StoredEdge::deserialize(&buf).weight(), 2.5);
+
+        // graphs written before edges had weights have zeros in the weight bytes
+        buf[STORED_EDGE_WEIGHT_OFFSET..].fill(0);
+        assert_eq!(StoredEdge::deserialize(&buf).weight(), DEFAULT_EDGE_WEIGHT);
+    }
+
     #[test]
     fn test_reversed() {
         let temp_dir = crate::gen_temp_dir().unwrap();
@@ -664,6 +715,7 @@ mod tests {
             },
             label: "test".to_string(),
             rel: RelFlags::default(),
+            weight: 1.0,
         };
 
         kv.put(e.clone());
@@ -708,6 +760,7 @@ mod tests {
             },
             label: "1".to_string(),
             rel: RelFlags::default(),
+            weight: 1.0,
         };
 
         let e2 = InsertableEdge {
@@ -721,6 +774,7 @@ mod tests {
             },
             label: "2".to_string(),
             rel: RelFlags::default(),
+            weight: 1.0,
         };
 
         let e3 = InsertableEdge {
@@ -734,6 +788,7 @@ mod tests {
             },
             label: "3".to_string(),
             rel: RelFlags::default(),
+            weight: 1.0,
         };
 
         kv.put(e1.clone());
@@ -777,6 +832,7 @@ mod tests {
             },
             label: "1".to_string(),
             rel: RelFlags::default(),
+            weight: 1.0,
         };
 
         kv.put(e1.clone());
@@ -311,7 +311,7 @@ impl FinalEdgeStoreWriter {
 
             let datum = NodeDatum::new(node, host_rank);
             let rel = edge.rel;
-            stored_edges.push(StoredEdge::new(datum, rel));
+            stored_edges.push(StoredEdge::new(datum, rel).with_weight(edge.weight));
         }
 
         let edge_labels: Vec<_> = edge_labels
@@ -20,7 +20,7 @@ use crate::{executor::Executor, webpage::html::links::RelFlags};
 
 use super::{
     id_node_db::Id2NodeDb, segment::SegmentWriter, Compression, FullNodeID, InsertableEdge, Meta,
-    Node, NodeID, Webgraph, MAX_LABEL_LENGTH,
+    Node, NodeID, Webgraph, DEFAULT_EDGE_WEIGHT, MAX_LABEL_LENGTH,
 };
 
 pub struct WebgraphWriter {
@@ -86,6 +86,25 @@ impl WebgraphWriter {
     }
 
     pub fn insert(&mut self, from: Node, to: Node, label: String, rel: RelFlags) {
+        self.insert_with_weight(from, to, label, rel, DEFAULT_EDGE_WEIGHT)
+    }
+
+    /// Insert an edge that counts `weight` times as much as a normal link,
+    /// e.g. because the link appears multiple times or is very prominent on the page.
+    /// The weight must be positive and finite.
+    pub fn insert_with_weight(
+        &mut self,
+        from: Node,
+        to: Node,
+        label: String,
+        rel: RelFlags,
+        weight: f32,
+    ) {
+        assert!(
+            weight.is_finite() && weight > 0.0,
+            "edge weight must be positive and finite, got {weight}"
+        );
+
         if from == to {
             return;
         }
@@ -100,6 +119,7 @@ impl WebgraphWriter {
             to: to_id,
             label: label.chars().take(MAX_LABEL_LENGTH).collect(),
             rel,
+            weight,
         };
 
         self.segment.insert(edge);
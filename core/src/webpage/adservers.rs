@@ -14,38 +14,95 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::collections::HashSet;
+use std::{collections::HashSet, path::Path, sync::Arc};
+
+use arc_swap::ArcSwap;
+
+use crate::Result;
 
 const LIST: &str = include_str!("adservers.txt");
-pub static AD_SERVERS: std::sync::LazyLock<AdServers> = std::sync::LazyLock::new(AdServers::new);
+
+/// The list used when cleaning webpages. It starts out as the built-in list
+/// and can be replaced at runtime with [`AdServers::reload`].
+pub static AD_SERVERS: std::sync::LazyLock<ArcSwap<AdServers>> =
+    std::sync::LazyLock::new(|| ArcSwap::from_pointee(AdServers::new()));
 
 pub struct AdServers {
     servers: HashSet<String>,
 }
 
 impl AdServers {
+    /// The built-in list.
     pub fn new() -> Self {
-        let servers = LIST
+        Self::parse(LIST)
+    }
+
+    /// Parse a list with one host per line. Lines starting with `#` are comments.
+    pub fn parse(list: &str) -> Self {
+        let servers = list
             .lines()
             .filter(|line| !line.starts_with('#'))
             .map(|line| line.trim().to_lowercase())
             .map(|line| line.trim_end_matches(',').to_string())
+            .filter(|line| !line.is_empty())
             .collect();
 
         Self { servers }
     }
 
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// The list currently used when cleaning webpages.
+    pub fn current() -> Arc<Self> {
+        AD_SERVERS.load_full()
+    }
+
+    /// Read the list at `path` and atomically replace the current list with it.
+    /// The current list is kept if the new one can't be read.
+    pub fn reload<P: AsRef<Path>>(path: P) -> Result<()> {
+        AD_SERVERS.store(Arc::new(Self::from_path(path)?));
+        Ok(())
+    }
+
     pub fn is_adserver(&self, host: &str) -> bool {
         self.servers.contains(host)
     }
 }
 
+impl Default for AdServers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     #[test]
     fn adserver() {
-        assert!(AD_SERVERS.is_adserver("doubleclick.net"));
-        assert!(!AD_SERVERS.is_adserver("google.com"));
+        assert!(AdServers::current().is_adserver("doubleclick.net"));
+        assert!(!AdServers::current().is_adserver("google.com"));
+    }
+
+    #[test]
+    fn custom_list() {
+        let temp_dir = crate::gen_temp_dir().unwrap();
+        let path = temp_dir.as_ref().join("adservers.txt");
+        std::fs::write(
+            &path,
+            "# custom list\nAds.Example.com,\n\ntracker.example.org\n",
+        )
+        .unwrap();
+
+        let servers = AdServers::from_path(&path).unwrap();
+
+        assert!(servers.is_adserver("ads.example.com"));
+        assert!(servers.is_adserver("tracker.example.org"));
+        assert!(!servers.is_adserver("example.com"));
+        assert!(!servers.is_adserver("doubleclick.net"));
+
+        assert!(AdServers::from_path(temp_dir.as_ref().join("missing.txt")).is_err());
     }
 }
@@ -0,0 +1,118 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+use utoipa::ToSchema;
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Each coordinate is stored as a fraction of its range in 32 bits.
+/// The largest value is reserved so a packed point never equals [`GeoPoint::NONE_PACKED`].
+const PACKED_SCALE: f64 = (u32::MAX - 1) as f64;
+
+/// A latitude/longitude pair in degrees.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+    ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    /// The packed value of pages without coordinates.
+    pub const NONE_PACKED: u64 = u64::MAX;
+
+    /// Returns `None` if the coordinates are out of range.
+    pub fn new(lat: f64, lon: f64) -> Option<Self> {
+        if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) {
+            Some(Self { lat, lon })
+        } else {
+            None
+        }
+    }
+
+    /// Pack the point into a single `u64` with the latitude in the upper 32 bits.
+    /// The precision is a few millimeters.
+    pub fn pack(&self) -> u64 {
+        let lat = ((self.lat + 90.0) / 180.0 * PACKED_SCALE).round() as u64;
+        let lon = ((self.lon + 180.0) / 360.0 * PACKED_SCALE).round() as u64;
+
+        (lat << 32) | lon
+    }
+
+    pub fn unpack(packed: u64) -> Option<Self> {
+        if packed == Self::NONE_PACKED {
+            return None;
+        }
+
+        let lat = (packed >> 32) as f64 / PACKED_SCALE * 180.0 - 90.0;
+        let lon = (packed & u32::MAX as u64) as f64 / PACKED_SCALE * 360.0 - 180.0;
+
+        Self::new(lat, lon)
+    }
+
+    /// Great-circle distance in kilometers using the Haversine formula.
+    pub fn distance_km(&self, other: &GeoPoint) -> f64 {
+        let lat1 = self.lat.to_radians();
+        let lat2 = other.lat.to_radians();
+        let dlat = (other.lat - self.lat).to_radians();
+        let dlon = (other.lon - self.lon).to_radians();
+
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+
+        2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_roundtrip() {
+        for (lat, lon) in [
+            (0.0, 0.0),
+            (55.6761, 12.5683),
+            (-33.8688, 151.2093),
+            (90.0, 180.0),
+            (-90.0, -180.0),
+        ] {
+            let point = GeoPoint::new(lat, lon).unwrap();
+            let packed = point.pack();
+            assert_ne!(packed, GeoPoint::NONE_PACKED);
+
+            let unpacked = GeoPoint::unpack(packed).unwrap();
+            assert!((unpacked.lat - lat).abs() < 1e-6);
+            assert!((unpacked.lon - lon).abs() < 1e-6);
+        }
+
+        assert_eq!(GeoPoint::unpack(GeoPoint::NONE_PACKED), None);
+        assert_eq!(GeoPoint::new(91.0, 0.0), None);
+        assert_eq!(GeoPoint::new(0.0, -181.0), None);
+    }
+
+    #[test]
+    fn haversine_distance() {
+        let copenhagen = GeoPoint::new(55.6761, 12.5683).unwrap();
+        let aarhus = GeoPoint::new(56.1629, 10.2039).unwrap();
+
+        assert_eq!(copenhagen.distance_km(&copenhagen), 0.0);
+        assert!((copenhagen.distance_km(&aarhus) - 157.0).abs() < 1.0);
+        assert!((copenhagen.distance_km(&aarhus) - aarhus.distance_km(&copenhagen)).abs() < 1e-9);
+
+        let north = GeoPoint::new(90.0, 0.0).unwrap();
+        let south = GeoPoint::new(-90.0, 0.0).unwrap();
+        assert!((north.distance_km(&south) - std::f64::consts::PI * EARTH_RADIUS_KM).abs() < 1e-6);
+    }
+}
@@ -3,11 +3,14 @@
 //
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
-use crate::{webpage::schema_org, Result};
+use crate::{
+    webpage::{schema_org, GeoPoint},
+    Result,
+};
 use lending_iter::LendingIterator;
 use tantivy::tokenizer::PreTokenizedString;
 
-use super::{find_recipe_first_ingredient_tag_id, Html};
+use super::{find_recipe_first_ingredient_tag_id, into_tantivy::geo_point, Html};
 
 macro_rules! cache {
     ($($fn:ident -> $res:ty),*$(,)?) => {
@@ -93,6 +96,10 @@ impl<'a> FnCache<'a> {
         self.schema_json.as_ref().unwrap()
     }
 
+    pub fn geo_point(&mut self) -> Option<GeoPoint> {
+        geo_point(self.schema_org())
+    }
+
     pub fn pretokenized_schema_json(&mut self) -> &PreTokenizedString {
         if self.pretokenized_schema_json.is_none() {
             self.pretokenized_schema_json =
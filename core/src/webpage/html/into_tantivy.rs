@@ -13,7 +13,7 @@ use crate::{
         TextFieldEnum,
     },
     tokenizer,
-    webpage::url_ext::UrlExt,
+    webpage::{schema_org, url_ext::UrlExt, GeoPoint},
     Error, Result,
 };
 use bloom::split_u128;
@@ -64,7 +64,7 @@ impl Html {
     pub fn pretokenize_url_for_site_operator(&self) -> PreTokenizedString {
         self.pretokenize_string_with(
             self.url().to_string(),
-            tokenizer::FieldTokenizer::Url(tokenizer::fields::UrlTokenizer),
+            tokenizer::FieldTokenizer::Url(tokenizer::fields::UrlTokenizer::default()),
         )
     }
 
@@ -106,7 +106,7 @@ impl Html {
     }
 
     fn pretokenize_string(&self, text: String, field: TextFieldEnum) -> PreTokenizedString {
-        self.pretokenize_string_with(text, field.tokenizer(self.lang()))
+        self.pretokenize_string_with(text, field.field_tokenizer(self.lang()))
     }
 
     fn pretokenize_string_with(
@@ -189,6 +189,11 @@ impl Html {
         split_u128(hash(self.title().unwrap_or_default()).0)
     }
 
+    /// The coordinates of the page from its schema.org `GeoCoordinates`.
+    pub fn geo_point(&self) -> Option<GeoPoint> {
+        geo_point(&self.schema_org())
+    }
+
     pub fn as_tantivy(
         &self,
         index: &crate::inverted_index::InvertedIndex,
@@ -211,6 +216,13 @@ impl Html {
     }
 }
 
+/// The first valid coordinates in the schema.org items.
+pub(super) fn geo_point(schemas: &[schema_org::Item]) -> Option<GeoPoint> {
+    schema_org::geo_coordinates(schemas)
+        .into_iter()
+        .find_map(|(lat, lon)| GeoPoint::new(lat, lon))
+}
+
 #[cfg(test)]
 mod tests {
     use url::Url;
@@ -249,4 +261,71 @@ mod tests {
         assert_eq!(html.domain_name(), "");
         assert_eq!(html.root_domain(), "");
     }
+
+    #[test]
+    fn geo_point_from_schema_org() {
+        let html = Html::parse_without_text(
+            r#"
+            <html>
+                <head>
+                    <script type="application/ld+json">
+                    {
+                        "@context": "https://schema.org",
+                        "@type": "Restaurant",
+                        "name": "Example",
+                        "geo": {
+                            "@type": "GeoCoordinates",
+                            "latitude": 55.6761,
+                            "longitude": "12.5683"
+                        }
+                    }
+                    </script>
+                </head>
+            </html>
+            "#,
+            "https://www.example.com",
+        )
+        .unwrap();
+
+        assert_eq!(
+            html.geo_point(),
+            Some(GeoPoint {
+                lat: 55.6761,
+                lon: 12.5683
+            })
+        );
+
+        let html = Html::parse_without_text(
+            r#"
+            <html>
+                <body>
+                    <div itemscope itemtype="https://schema.org/Place">
+                        <div itemprop="geo" itemscope itemtype="https://schema.org/GeoCoordinates">
+                            <meta itemprop="latitude" content="-33.8688" />
+                            <meta itemprop="longitude" content="151.2093" />
+                        </div>
+                    </div>
+                </body>
+            </html>
+            "#,
+            "https://www.example.com",
+        )
+        .unwrap();
+
+        assert_eq!(
+            html.geo_point(),
+            Some(GeoPoint {
+                lat: -33.8688,
+                lon: 151.2093
+            })
+        );
+
+        let html = Html::parse_without_text(
+            "<html><body>No coordinates</body></html>",
+            "https://www.example.com",
+        )
+        .unwrap();
+
+        assert_eq!(html.geo_point(), None);
+    }
 }
@@ -30,6 +30,12 @@ pub struct FaviconLink {
     image_type: Option<String>,
 }
 
+impl FaviconLink {
+    fn area(&self) -> u64 {
+        self.width.unwrap_or_default() as u64 * self.height.unwrap_or_default() as u64
+    }
+}
+
 #[derive(PartialEq, Eq, Debug)]
 pub struct ImageLink {
     pub url: Url,
@@ -69,6 +75,7 @@ impl RelFlags {
                     "tag" => res |= RelFlags::TAG,
                     "terms-of-service" => res |= RelFlags::TERMS_OF_SERVICE,
                     "sponsored" => res |= RelFlags::SPONSORED,
+                    "ugc" => res |= RelFlags::UGC,
                     _ => {}
                 }
             }
@@ -122,6 +129,7 @@ bitflags! {
         const SCRIPT_TAG = 1 << 19;
         const META_TAG = 1 << 20;
         const SAME_ICANN_DOMAIN = 1 << 21;
+        const UGC = 1 << 22;
     }
 }
 
@@ -166,61 +174,126 @@ bitflags! {
 }
 
 impl Html {
+    /// The declared favicon of the page. If the page declares multiple
+    /// icons, the one with the largest declared size is preferred.
     pub fn favicon(&self) -> Option<FaviconLink> {
+        let mut best: Option<FaviconLink> = None;
+
         for node in self.root.select("link").unwrap() {
-            if !matches!(node.attributes.borrow().get("rel"), Some("icon")) {
+            let attributes = node.attributes.borrow();
+
+            let is_icon = attributes
+                .get("rel")
+                .map(|rel| {
+                    rel.split_whitespace()
+                        .any(|r| r.eq_ignore_ascii_case("icon"))
+                })
+                .unwrap_or(false);
+
+            if !is_icon {
                 continue;
             }
 
-            if let Some(link) = node.attributes.borrow().get("href") {
-                let (width, height) = match node.attributes.borrow().get("sizes") {
-                    Some(size) => {
-                        if let Some((width, height)) = size.split_once('x') {
-                            (width.parse().ok(), height.parse().ok())
-                        } else {
-                            (None, None)
-                        }
-                    }
-                    _ => (None, None),
-                };
+            let Some(link) = attributes
+                .get("href")
+                .and_then(|href| Url::parse_with_base_url(self.base_url(), href).ok())
+            else {
+                continue;
+            };
+
+            // `sizes` can contain multiple space separated sizes (e.g. "16x16 32x32").
+            let (width, height) = attributes
+                .get("sizes")
+                .map(|sizes| {
+                    sizes
+                        .split_whitespace()
+                        .filter_map(|size| size.split_once('x'))
+                        .map(|(width, height)| {
+                            (width.parse::<u32>().ok(), height.parse::<u32>().ok())
+                        })
+                        .max_by_key(|(width, height)| {
+                            width.unwrap_or_default() as u64 * height.unwrap_or_default() as u64
+                        })
+                        .unwrap_or((None, None))
+                })
+                .unwrap_or((None, None));
+
+            let image_type = attributes.get("type").map(|t| t.to_string());
+
+            let favicon = FaviconLink {
+                link,
+                width,
+                height,
+                image_type,
+            };
+
+            if best
+                .as_ref()
+                .map(|best| favicon.area() > best.area())
+                .unwrap_or(true)
+            {
+                best = Some(favicon);
+            }
+        }
 
-                let image_type = node.attributes.borrow().get("type").map(|t| t.to_string());
-                let link = Url::parse_with_base_url(self.base_url(), link).ok()?;
+        best
+    }
 
-                let favicon = FaviconLink {
-                    link,
-                    width,
-                    height,
-                    image_type,
-                };
+    /// The url of the favicon for the page. Falls back to `/favicon.ico`
+    /// on the host if the page doesn't declare any icons.
+    pub fn favicon_url(&self) -> Option<Url> {
+        self.favicon()
+            .map(|favicon| favicon.link)
+            .or_else(|| self.url().join("/favicon.ico").ok())
+    }
+
+    /// The translations of the page declared with `<link rel="alternate" hreflang="..">`
+    /// as pairs of language code and absolute url. Pages usually also list themselves.
+    pub fn hreflang_alternates(&self) -> Vec<(String, Url)> {
+        let mut alternates = Vec::new();
+
+        for node in self.root.select("link").unwrap() {
+            let attributes = node.attributes.borrow();
+
+            let is_alternate = attributes
+                .get("rel")
+                .map(|rel| {
+                    rel.split_whitespace()
+                        .any(|r| r.eq_ignore_ascii_case("alternate"))
+                })
+                .unwrap_or(false);
 
-                return Some(favicon);
+            if !is_alternate {
+                continue;
+            }
+
+            let Some(lang) = attributes
+                .get("hreflang")
+                .map(|lang| lang.trim())
+                .filter(|lang| !lang.is_empty())
+            else {
+                continue;
+            };
+
+            if let Some(url) = attributes
+                .get("href")
+                .and_then(|href| Url::parse_with_base_url(self.base_url(), href).ok())
+            {
+                alternates.push((lang.to_string(), url));
             }
         }
 
-        None
+        alternates
     }
 
     fn og_image(&self) -> Option<ImageLink> {
-        self.metadata()
-            .into_iter()
-            .find(|metadata| {
-                if let Some(property) = metadata.get("property") {
-                    property == &String::from("og:image")
-                } else {
-                    false
-                }
-            })
-            .and_then(|metadata| {
-                metadata
-                    .get("content")
-                    .and_then(|link| Url::parse_with_base_url(self.base_url(), link).ok())
-            })
-            .map(|url| ImageLink {
-                url,
-                title: self.og_title(),
-                description: self.description(),
-            })
+        let og = self.opengraph();
+
+        og.image.map(|url| ImageLink {
+            url,
+            title: og.title,
+            description: og.description.or_else(|| self.metadata_description()),
+        })
     }
 
     pub fn primary_image(&self) -> Option<ImageLink> {
@@ -581,6 +654,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn largest_favicon_preferred() {
+        let raw = r#"
+            <html>
+                <head>
+                    <link rel="icon" sizes="16x16" href="/favicon-16.png" />
+                    <link rel="shortcut icon" sizes="32x32 96x96" href="/favicon-96.png" />
+                    <link rel="icon" sizes="48x48" href="/favicon-48.png" />
+                    <link rel="stylesheet" href="/style.css" />
+                </head>
+            </html>
+        "#;
+
+        let webpage = Html::parse(raw, "https://www.example.com/test/page").unwrap();
+        assert_eq!(
+            webpage.favicon(),
+            Some(FaviconLink {
+                link: Url::parse("https://www.example.com/favicon-96.png").unwrap(),
+                width: Some(96),
+                height: Some(96),
+                image_type: None
+            })
+        );
+    }
+
+    #[test]
+    fn favicon_url() {
+        let raw = r#"
+            <html>
+                <head>
+                    <link rel="icon" href="static/icon.png" />
+                </head>
+            </html>
+        "#;
+
+        let webpage = Html::parse(raw, "https://www.example.com/test/").unwrap();
+        assert_eq!(
+            webpage.favicon_url(),
+            Some(Url::parse("https://www.example.com/test/static/icon.png").unwrap())
+        );
+
+        let raw = r#"
+            <html>
+                <head>
+                    <title>No icon</title>
+                </head>
+            </html>
+        "#;
+
+        let webpage = Html::parse(raw, "https://www.example.com/test/page?q=1").unwrap();
+        assert_eq!(webpage.favicon(), None);
+        assert_eq!(
+            webpage.favicon_url(),
+            Some(Url::parse("https://www.example.com/favicon.ico").unwrap())
+        );
+    }
+
+    #[test]
+    fn hreflang_alternates() {
+        let raw = r#"
+            <html>
+                <head>
+                    <link rel="alternate" hreflang="en" href="https://www.example.com/en/page" />
+                    <link rel="alternate" hreflang="de" href="/de/page" />
+                    <link rel="alternate" hreflang="fr" href="../fr/page" />
+                    <link rel="alternate" type="application/rss+xml" href="/feed.xml" />
+                    <link rel="canonical" href="https://www.example.com/en/page" />
+                </head>
+            </html>
+        "#;
+
+        let webpage = Html::parse(raw, "https://www.example.com/en/page").unwrap();
+
+        assert_eq!(
+            webpage.hreflang_alternates(),
+            vec![
+                (
+                    "en".to_string(),
+                    Url::parse("https://www.example.com/en/page").unwrap()
+                ),
+                (
+                    "de".to_string(),
+                    Url::parse("https://www.example.com/de/page").unwrap()
+                ),
+                (
+                    "fr".to_string(),
+                    Url::parse("https://www.example.com/fr/page").unwrap()
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn primary_image() {
         let html = r#"
@@ -3,7 +3,7 @@
 //
 // This code is originated from Stract, which is licensed under the GNU Affero General Public License.
 
-use crate::{enum_map::EnumSet, Result};
+use crate::{dated_url::parse_date, enum_map::EnumSet, Result};
 use chrono::{DateTime, FixedOffset, Utc};
 use itertools::Itertools;
 use kuchiki::{traits::TendrilSink, NodeRef};
@@ -14,11 +14,13 @@ use whatlang::Lang;
 
 use self::robots_meta::RobotsMeta;
 
-use super::{adservers::AD_SERVERS, schema_org, Meta, Script};
+use super::{adservers::AdServers, schema_org, Meta, Script};
 
 use super::url_ext::UrlExt;
 
 pub use fn_cache::FnCache;
+pub use opengraph::OpenGraph;
+pub use robots_meta::RobotsDirectives;
 
 mod feeds;
 mod fn_cache;
@@ -26,6 +28,7 @@ mod headings;
 mod into_tantivy;
 pub mod links;
 mod microformats;
+mod opengraph;
 mod parse_text;
 mod robots_meta;
 
@@ -290,6 +293,8 @@ impl Html {
     }
 
     pub fn likely_has_ads(&self) -> bool {
+        let ad_servers = AdServers::current();
+
         for script in self.scripts() {
             if let Some(url) = script
                 .attributes
@@ -301,13 +306,13 @@ impl Html {
                 }
 
                 if let Some(domain) = url.root_domain() {
-                    if AD_SERVERS.is_adserver(domain) {
+                    if ad_servers.is_adserver(domain) {
                         return true;
                     }
                 }
 
                 if let Some(host) = url.host_str() {
-                    if AD_SERVERS.is_adserver(host) {
+                    if ad_servers.is_adserver(host) {
                         return true;
                     }
                 }
@@ -327,13 +332,13 @@ impl Html {
                 }
 
                 if let Some(domain) = url.root_domain() {
-                    if AD_SERVERS.is_adserver(domain) {
+                    if ad_servers.is_adserver(domain) {
                         return true;
                     }
                 }
 
                 if let Some(host) = url.host_str() {
-                    if AD_SERVERS.is_adserver(host) {
+                    if ad_servers.is_adserver(host) {
                         return true;
                     }
                 }
@@ -343,6 +348,10 @@ impl Html {
         false
     }
 
+    pub fn faq_pairs(&self) -> Vec<(String, String)> {
+        schema_org::faq_pairs(&self.schema_org())
+    }
+
     pub fn likely_has_paywall(&self) -> bool {
         for item in self.schema_org().into_iter().filter(|item| {
             item.types_contains("NewsArticle")
@@ -398,6 +407,42 @@ impl Html {
             })
     }
 
+    fn schema_org_published_date(&self) -> Option<DateTime<Utc>> {
+        self.schema_org()
+            .into_iter()
+            .flat_map(|item| {
+                let graph: Vec<_> = item
+                    .properties
+                    .get("@graph")
+                    .cloned()
+                    .map(|graph| graph.many())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|prop| prop.try_into_item())
+                    .collect();
+
+                std::iter::once(item).chain(graph)
+            })
+            .find_map(|item| {
+                item.properties
+                    .get("datePublished")
+                    .and_then(|value| value.clone().one().and_then(|v| v.try_into_string()))
+                    .and_then(|date| parse_date(&date))
+            })
+    }
+
+    fn meta_published_date(&self) -> Option<DateTime<Utc>> {
+        self.metadata()
+            .into_iter()
+            .find(|metadata| {
+                matches!(
+                    metadata.get("property").map(String::as_str),
+                    Some("article:published_time" | "og:published_time")
+                )
+            })
+            .and_then(|metadata| metadata.get("content").and_then(|time| parse_date(time)))
+    }
+
     fn schema_org_images(&self) -> Vec<Url> {
         self.schema_org()
             .into_iter()
@@ -433,17 +478,16 @@ impl Html {
         }
     }
 
+    /// The date the page was first published. JSON-LD `datePublished` takes
+    /// precedence over the OpenGraph `article:published_time` meta tag.
+    pub fn published_date(&self) -> Option<DateTime<Utc>> {
+        self.schema_org_published_date()
+            .or_else(|| self.meta_published_date())
+            .filter(|time| *time <= Utc::now())
+    }
+
     pub fn og_description(&self) -> Option<String> {
-        self.metadata()
-            .into_iter()
-            .find(|metadata| {
-                if let Some(property) = metadata.get("property") {
-                    property.as_str() == "og:description"
-                } else {
-                    false
-                }
-            })
-            .and_then(|metadata| metadata.get("content").cloned())
+        self.opengraph().description
     }
 
     pub fn metadata_description(&self) -> Option<String> {
@@ -465,21 +509,16 @@ impl Html {
     }
 
     pub fn og_title(&self) -> Option<String> {
-        self.metadata()
-            .into_iter()
-            .find(|metadata| {
-                if let Some(property) = metadata.get("property") {
-                    property == &String::from("og:title")
-                } else {
-                    false
-                }
-            })
-            .and_then(|metadata| metadata.get("content").cloned())
+        self.opengraph().title
     }
 
     pub fn is_homepage(&self) -> bool {
         self.url().is_homepage()
     }
+
+    pub fn is_https(&self) -> bool {
+        self.url().scheme() == "https"
+    }
 }
 
 fn find_recipe_first_ingredient_tag_id(
@@ -859,6 +898,154 @@ mod tests {
         assert_eq!(html.updated_time(), None);
     }
 
+    #[test]
+    fn content_density() {
+        let raw = format!(
+            r#"
+            <html>
+                <head>
+                    <title>Best website</title>
+                </head>
+                <body>
+                    <nav><a href="/">Home</a> <a href="/about">About</a></nav>
+                    <p>{CONTENT}</p>
+                </body>
+            </html>
+        "#
+        );
+
+        let webpage = Html::parse(&raw, "https://www.example.com/whatever").unwrap();
+        assert!(webpage.content_density() > 0.8);
+
+        let links = (0..50)
+            .map(|i| format!(r#"<li><a href="/category/{i}">Category number {i}</a></li>"#))
+            .join("\n");
+
+        let raw = format!(
+            r#"
+            <html>
+                <head>
+                    <title>Categories</title>
+                </head>
+                <body>
+                    <nav><ul>{links}</ul></nav>
+                    <p>Welcome to our site.</p>
+                </body>
+            </html>
+        "#
+        );
+
+        let webpage = Html::parse(&raw, "https://www.example.com/").unwrap();
+        assert!(!webpage.empty_all_text());
+        assert!(webpage.content_density() < 0.1);
+
+        let webpage = Html::parse("<html></html>", "https://www.example.com/").unwrap();
+        assert_eq!(webpage.content_density(), 0.0);
+    }
+
+    #[test]
+    fn json_ld_published_date() {
+        let html = r#"
+    <html>
+        <head>
+            <script type="application/ld+json">
+                {
+                    "@context": "https://schema.org",
+                    "@type": "NewsArticle",
+                    "datePublished": "2021-03-04T05:06:07+01:00"
+                }
+            </script>
+            <meta property="article:published_time" content="2020-01-01T00:00:00+00:00" />
+        </head>
+        <body>
+        </body>
+    </html>
+        "#;
+        let html = Html::parse(html, "https://example.com").unwrap();
+
+        assert_eq!(
+            html.published_date(),
+            Some(
+                DateTime::parse_from_rfc3339("2021-03-04T04:06:07+00:00")
+                    .unwrap()
+                    .with_timezone(&Utc)
+            )
+        );
+
+        let html = r#"
+    <html>
+        <head>
+            <script type="application/ld+json">
+                {
+                    "@context": "https://schema.org",
+                    "@graph": [
+                        {
+                            "@type": "BlogPosting",
+                            "datePublished": "2019-05-06"
+                        }
+                    ]
+                }
+            </script>
+        </head>
+        <body>
+        </body>
+    </html>
+        "#;
+        let html = Html::parse(html, "https://example.com").unwrap();
+
+        assert_eq!(
+            html.published_date(),
+            Some(
+                DateTime::parse_from_rfc3339("2019-05-06T00:00:00+00:00")
+                    .unwrap()
+                    .with_timezone(&Utc)
+            )
+        );
+    }
+
+    #[test]
+    fn meta_published_date() {
+        let html = r#"
+    <html>
+        <head>
+            <meta property="article:published_time" content="2015-10-25T17:02:35.000Z" />
+        </head>
+        <body>
+        </body>
+    </html>
+        "#;
+        let html = Html::parse(html, "https://example.com").unwrap();
+
+        assert_eq!(
+            html.published_date(),
+            Some(
+                DateTime::parse_from_rfc3339("2015-10-25T17:02:35+00:00")
+                    .unwrap()
+                    .with_timezone(&Utc)
+            )
+        );
+
+        let html = r#"
+    <html>
+        <head>
+            <script type="application/ld+json">
+                {
+                    "@context": "https://schema.org",
+                    "@type": "NewsArticle",
+                    "datePublished": "last tuesday"
+                }
+            </script>
+            <meta property="article:published_time" content="2ss015-10-25T17:02:35Z" />
+        </head>
+        <body>
+        </body>
+    </html>
+        "#;
+        let html = Html::parse(html, "https://example.com").unwrap();
+
+        assert_eq!(html.published_date(), None);
+    }
+
     #[test]
     fn future_updated_time_none() {
         let html = r#"
@@ -1198,6 +1385,115 @@ mod tests {
         assert!(html.likely_has_paywall());
     }
 
+    #[test]
+    fn faq_page() {
+        let html = Html::parse(
+            include_str!("../../../testcases/schema_org/faq_page.html"),
+            "https://www.example.com/faq",
+        )
+        .unwrap();
+
+        assert_eq!(
+            html.faq_pairs(),
+            vec![
+                (
+                    "How long does composting take?".to_string(),
+                    "A well maintained compost pile is usually ready in two to three months."
+                        .to_string()
+                ),
+                (
+                    "Can I compost citrus peels?".to_string(),
+                    "Yes, but only in small amounts as they are quite acidic.".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn faq_page_in_graph() {
+        let html = Html::parse(
+            r#"
+            <html>
+                <head>
+                    <script type="application/ld+json">
+                    {
+                        "@context": "https://schema.org",
+                        "@graph": [
+                            {
+                                "@type": "WebSite",
+                                "name": "Example"
+                            },
+                            {
+                                "@type": "FAQPage",
+                                "mainEntity": {
+                                    "@type": "Question",
+                                    "name": "Is this wrapped in a graph?",
+                                    "acceptedAnswer": {
+                                        "@type": "Answer",
+                                        "text": "Yes it is."
+                                    }
+                                }
+                            }
+                        ]
+                    }
+                    </script>
+                </head>
+                <body></body>
+            </html>
+            "#,
+            "https://www.example.com/",
+        )
+        .unwrap();
+
+        assert_eq!(
+            html.faq_pairs(),
+            vec![(
+                "Is this wrapped in a graph?".to_string(),
+                "Yes it is.".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn qa_page() {
+        let html = Html::parse(
+            r#"
+            <html>
+                <head>
+                    <script type="application/ld+json">
+                    {
+                        "@context": "https://schema.org",
+                        "@type": "QAPage",
+                        "mainEntity": {
+                            "@type": "Question",
+                            "name": "How do I reverse a string?",
+                            "text": "I have a string and want it reversed.",
+                            "suggestedAnswer": [
+                                {
+                                    "@type": "Answer",
+                                    "text": "Iterate over the characters backwards."
+                                }
+                            ]
+                        }
+                    }
+                    </script>
+                </head>
+                <body></body>
+            </html>
+            "#,
+            "https://www.example.com/",
+        )
+        .unwrap();
+
+        assert_eq!(
+            html.faq_pairs(),
+            vec![(
+                "How do I reverse a string?".to_string(),
+                "Iterate over the characters backwards.".to_string()
+            )]
+        );
+    }
+
     #[test]
     fn test_base_url() {
         let html = Html::parse(
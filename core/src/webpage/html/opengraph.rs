@@ -0,0 +1,176 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+use url::Url;
+
+use crate::webpage::url_ext::UrlExt;
+
+use super::Html;
+
+/// The OpenGraph (`og:*`) metadata declared by a page.
+///
+/// See <https://ogp.me/> for the protocol. Only the fields we use for
+/// result cards are extracted; fields the page doesn't declare are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct OpenGraph {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<Url>,
+    pub kind: Option<String>,
+    pub site_name: Option<String>,
+}
+
+impl OpenGraph {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.image.is_none()
+            && self.kind.is_none()
+            && self.site_name.is_none()
+    }
+}
+
+impl Html {
+    /// Extract the OpenGraph metadata of the page. If a property is declared
+    /// multiple times, the first declaration wins. Relative `og:image` urls
+    /// are resolved against the base url of the page.
+    pub fn opengraph(&self) -> OpenGraph {
+        let mut og = OpenGraph::default();
+
+        for metadata in self.metadata() {
+            let (Some(property), Some(content)) =
+                (metadata.get("property"), metadata.get("content"))
+            else {
+                continue;
+            };
+
+            let content = content.trim();
+            if content.is_empty() {
+                continue;
+            }
+
+            match property.as_str() {
+                "og:title" if og.title.is_none() => og.title = Some(content.to_string()),
+                "og:description" if og.description.is_none() => {
+                    og.description = Some(content.to_string())
+                }
+                "og:image" | "og:image:url" if og.image.is_none() => {
+                    og.image = Url::parse_with_base_url(self.base_url(), content).ok()
+                }
+                "og:type" if og.kind.is_none() => og.kind = Some(content.to_string()),
+                "og:site_name" if og.site_name.is_none() => {
+                    og.site_name = Some(content.to_string())
+                }
+                _ => {}
+            }
+        }
+
+        og
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_opengraph_block() {
+        let html = Html::parse(
+            r#"
+            <html>
+                <head>
+                    <title>Scraped title</title>
+                    <meta property="og:title" content="OG title" />
+                    <meta property="og:description" content="OG description" />
+                    <meta property="og:image" content="/images/cover.png" />
+                    <meta property="og:type" content="article" />
+                    <meta property="og:site_name" content="Example" />
+                </head>
+                <body></body>
+            </html>
+            "#,
+            "https://www.example.com/posts/1",
+        )
+        .unwrap();
+
+        assert_eq!(
+            html.opengraph(),
+            OpenGraph {
+                title: Some("OG title".to_string()),
+                description: Some("OG description".to_string()),
+                image: Some(Url::parse("https://www.example.com/images/cover.png").unwrap()),
+                kind: Some("article".to_string()),
+                site_name: Some("Example".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn missing_fields() {
+        let html = Html::parse(
+            r#"
+            <html>
+                <head>
+                    <meta property="og:title" content="OG title" />
+                    <meta property="og:description" content="" />
+                    <meta name="description" content="Not opengraph" />
+                </head>
+                <body></body>
+            </html>
+            "#,
+            "https://www.example.com/",
+        )
+        .unwrap();
+
+        let og = html.opengraph();
+
+        assert_eq!(og.title, Some("OG title".to_string()));
+        assert_eq!(og.description, None);
+        assert_eq!(og.image, None);
+        assert_eq!(og.kind, None);
+        assert_eq!(og.site_name, None);
+        assert!(!og.is_empty());
+
+        let html = Html::parse("<html><head></head></html>", "https://www.example.com/").unwrap();
+        assert!(html.opengraph().is_empty());
+    }
+
+    #[test]
+    fn relative_image() {
+        let html = Html::parse(
+            r#"
+            <html>
+                <head>
+                    <meta property="og:image" content="../images/cover.png" />
+                    <meta property="og:site_name" content="Example" />
+                </head>
+                <body></body>
+            </html>
+            "#,
+            "https://www.example.com/posts/2024/hello",
+        )
+        .unwrap();
+
+        let og = html.opengraph();
+
+        assert_eq!(
+            og.image,
+            Some(Url::parse("https://www.example.com/posts/images/cover.png").unwrap())
+        );
+        assert_eq!(og.site_name, Some("Example".to_string()));
+        assert_eq!(og.description, None);
+    }
+
+    #[test]
+    fn stored_without_site_name() {
+        let og: OpenGraph = serde_json::from_str(
+            r#"{"title":"OG title","description":null,"image":null,"kind":null}"#,
+        )
+        .unwrap();
+
+        assert_eq!(og.title, Some("OG title".to_string()));
+        assert_eq!(og.site_name, None);
+    }
+}
@@ -41,6 +41,20 @@ impl Html {
         self.clean_text = Html::calculate_clean_text(&paragraphs, &self.lang.unwrap_or(Lang::Eng));
     }
 
+    /// The fraction of the extracted text that is kept as clean text. Pages that
+    /// are mostly navigation and other boilerplate have a low density.
+    pub fn content_density(&self) -> f64 {
+        let all_len = self.all_text.as_ref().map(|text| text.len()).unwrap_or(0);
+
+        if all_len == 0 {
+            return 0.0;
+        }
+
+        let clean_len = self.clean_text.as_ref().map(|text| text.len()).unwrap_or(0);
+
+        (clean_len as f64 / all_len as f64).min(1.0)
+    }
+
     fn calculate_clean_text(paragraphs: &[Paragraph], lang: &Lang) -> Option<String> {
         let text = JustText::default().extract_from_paragraphs(paragraphs, lang);
 
@@ -27,6 +27,18 @@ use super::Html;
 pub enum RobotsMeta {
     NoIndex,
     NoFollow,
+    NoArchive,
+}
+
+/// The directives a page gives crawlers in its `<meta name="robots">` tags.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RobotsDirectives {
+    /// The page should not be indexed.
+    pub noindex: bool,
+    /// The links on the page should not be followed or added to the webgraph.
+    pub nofollow: bool,
+    /// A cached copy of the page should not be shown.
+    pub noarchive: bool,
 }
 
 impl FromStr for RobotsMeta {
@@ -36,6 +48,7 @@ impl FromStr for RobotsMeta {
         match s {
             "noindex" => Ok(RobotsMeta::NoIndex),
             "nofollow" => Ok(RobotsMeta::NoFollow),
+            "noarchive" => Ok(RobotsMeta::NoArchive),
             _ => Err(Error::UnknownRobotsMetaTag.into()),
         }
     }
@@ -46,6 +59,7 @@ impl InsertEnumMapKey for RobotsMeta {
         match self {
             RobotsMeta::NoIndex => 0,
             RobotsMeta::NoFollow => 1,
+            RobotsMeta::NoArchive => 2,
         }
     }
 }
@@ -57,11 +71,16 @@ impl Html {
         for node in self.root.select("meta").unwrap() {
             if let Some(element) = node.as_node().as_element() {
                 if let Some(name) = element.attributes.borrow().get("name") {
-                    if name == "robots" {
+                    if name.eq_ignore_ascii_case("robots") {
                         if let Some(content) = element.attributes.borrow().get("content") {
                             for part in content.split(',') {
-                                let part = part.trim();
-                                if let Ok(meta) = part.parse::<RobotsMeta>() {
+                                let part = part.trim().to_ascii_lowercase();
+
+                                // `none` is shorthand for `noindex, nofollow`
+                                if part == "none" {
+                                    robots.insert(RobotsMeta::NoIndex);
+                                    robots.insert(RobotsMeta::NoFollow);
+                                } else if let Ok(meta) = part.parse::<RobotsMeta>() {
                                     robots.insert(meta);
                                 }
                             }
@@ -78,6 +97,14 @@ impl Html {
         }
     }
 
+    pub fn robots_directives(&self) -> RobotsDirectives {
+        RobotsDirectives {
+            noindex: self.is_no_index(),
+            nofollow: self.is_no_follow(),
+            noarchive: self.is_no_archive(),
+        }
+    }
+
     pub fn is_no_index(&self) -> bool {
         self.robots
             .as_ref()
@@ -91,6 +118,13 @@ impl Html {
             .map(|robots| robots.contains(RobotsMeta::NoFollow))
             .unwrap_or(false)
     }
+
+    pub fn is_no_archive(&self) -> bool {
+        self.robots
+            .as_ref()
+            .map(|robots| robots.contains(RobotsMeta::NoArchive))
+            .unwrap_or(false)
+    }
 }
 
 #[cfg(test)]
@@ -183,4 +217,72 @@ mod tests {
         assert!(!html.is_no_index());
         assert!(!html.is_no_follow());
     }
+
+    #[test]
+    fn robots_directives() {
+        let directives = |content: &str| {
+            Html::parse(
+                &format!(
+                    r#"
+                    <html>
+                        <head>
+                            <meta name="robots" content="{content}" />
+                        </head>
+                        <body>
+                        </body>
+                    </html>
+                "#
+                ),
+                "https://www.example.com/whatever",
+            )
+            .unwrap()
+            .robots_directives()
+        };
+
+        assert_eq!(
+            directives("noindex"),
+            RobotsDirectives {
+                noindex: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            directives("nofollow"),
+            RobotsDirectives {
+                nofollow: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            directives("noarchive"),
+            RobotsDirectives {
+                noarchive: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            directives("NOINDEX, NoArchive"),
+            RobotsDirectives {
+                noindex: true,
+                nofollow: false,
+                noarchive: true,
+            }
+        );
+        assert_eq!(
+            directives("none"),
+            RobotsDirectives {
+                noindex: true,
+                nofollow: true,
+                noarchive: false,
+            }
+        );
+        assert_eq!(directives("index, follow"), RobotsDirectives::default());
+
+        let html = Html::parse(
+            "<html><head></head><body></body></html>",
+            "https://www.example.com/whatever",
+        )
+        .unwrap();
+        assert_eq!(html.robots_directives(), RobotsDirectives::default());
+    }
 }
@@ -17,7 +17,8 @@ use std::collections::HashMap;
 use tantivy::TantivyDocument;
 use url::Url;
 
-mod adservers;
+pub mod adservers;
+pub mod geo;
 pub mod html;
 mod just_text;
 pub mod region;
@@ -27,6 +28,8 @@ pub mod url_ext;
 use self::html::links::RelFlags;
 pub use self::html::Html;
 
+pub use adservers::AdServers;
+pub use geo::GeoPoint;
 pub use region::Region;
 
 #[derive(Debug)]
@@ -58,6 +61,7 @@ pub struct Webpage {
     pub keywords: Vec<String>,
     pub title_embedding: Option<Tensor>,
     pub keyword_embedding: Option<Tensor>,
+    pub region_override: Option<Region>,
 }
 
 #[cfg(test)]
@@ -80,6 +84,7 @@ impl Default for Webpage {
             keywords: Default::default(),
             title_embedding: Default::default(),
             keyword_embedding: Default::default(),
+            region_override: None,
         }
     }
 }
@@ -103,6 +108,7 @@ impl From<Html> for Webpage {
             keywords: Default::default(),
             title_embedding: Default::default(),
             keyword_embedding: Default::default(),
+            region_override: None,
         }
     }
 }
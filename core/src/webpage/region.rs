@@ -20,7 +20,7 @@ use utoipa::ToSchema;
 
 use crate::{Error, Result};
 
-use super::Webpage;
+use super::{url_ext::UrlExt, Webpage};
 
 #[derive(
     Debug,
@@ -105,17 +105,44 @@ impl Region {
         }
     }
 
+    /// The region of the country code in `tld`. Only the last label is used,
+    /// so `com.es` is Spain while generic tlds like `com` are unknown.
+    pub fn from_tld(tld: &str) -> Result<Self> {
+        match tld.rsplit('.').next().unwrap_or_default() {
+            "dk" => Ok(Region::Denmark),
+            "fr" => Ok(Region::France),
+            "de" => Ok(Region::Germany),
+            "es" => Ok(Region::Spain),
+            "us" => Ok(Region::US),
+            _ => Err(Error::UnknownRegion.into()),
+        }
+    }
+
+    /// The region of the webpage. An override for the host always wins,
+    /// otherwise the region is detected from the language of the page
+    /// and finally from the tld of the url.
     pub fn guess_from(webpage: &Webpage) -> Result<Self> {
-        match webpage.html.lang() {
-            Some(lang) => match lang {
-                whatlang::Lang::Eng => Ok(Region::US),
-                whatlang::Lang::Spa => Ok(Region::Spain),
-                whatlang::Lang::Fra => Ok(Region::France),
-                whatlang::Lang::Deu => Ok(Region::Germany),
-                whatlang::Lang::Dan => Ok(Region::Denmark),
-                _ => Err(Error::UnknownRegion.into()),
-            },
-            None => Err(Error::UnknownRegion.into()),
+        if let Some(region) = webpage.region_override {
+            return Ok(region);
+        }
+
+        let from_lang = match webpage.html.lang() {
+            Some(whatlang::Lang::Eng) => Some(Region::US),
+            Some(whatlang::Lang::Spa) => Some(Region::Spain),
+            Some(whatlang::Lang::Fra) => Some(Region::France),
+            Some(whatlang::Lang::Deu) => Some(Region::Germany),
+            Some(whatlang::Lang::Dan) => Some(Region::Denmark),
+            _ => None,
+        };
+
+        match from_lang {
+            Some(region) => Ok(region),
+            None => webpage
+                .html
+                .url()
+                .tld()
+                .ok_or_else(|| Error::UnknownRegion.into())
+                .and_then(Region::from_tld),
         }
     }
 
@@ -135,6 +162,30 @@ impl Region {
     }
 }
 
+/// Regions that are assigned to hosts regardless of what would be detected
+/// from their pages, e.g. for a `.co.uk` site that serves global content.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct RegionOverrides(HashMap<String, Region>);
+
+impl RegionOverrides {
+    pub fn get(&self, host: &str) -> Option<Region> {
+        self.0.get(host).copied()
+    }
+
+    pub fn apply(&self, webpage: &mut Webpage) {
+        if let Some(host) = webpage.html.url().host_str() {
+            webpage.region_override = self.get(host);
+        }
+    }
+}
+
+impl FromIterator<(String, Region)> for RegionOverrides {
+    fn from_iter<T: IntoIterator<Item = (String, Region)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
 #[derive(
     serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Default, Clone,
 )]
@@ -254,4 +305,47 @@ mod tests {
         assert_eq!(a.score(&Region::Denmark), 0.4);
         assert_eq!(a.score(&Region::France), 0.0);
     }
+
+    #[test]
+    fn from_tld() {
+        assert_eq!(Region::from_tld("dk").unwrap(), Region::Denmark);
+        assert_eq!(Region::from_tld("com.es").unwrap(), Region::Spain);
+        assert!(Region::from_tld("com").is_err());
+        assert!(Region::from_tld("co.uk").is_err());
+    }
+
+    #[test]
+    fn host_override() {
+        let html = r#"
+            <html>
+                <head>
+                    <title>Nyheder</title>
+                </head>
+                <body>
+                    Dette er en dansk side med nyheder fra hele Danmark og resten af verden.
+                    Her kan du læse om politik, sport, kultur og meget mere hver eneste dag.
+                </body>
+            </html>
+        "#;
+
+        let overrides: RegionOverrides = toml::from_str(
+            r#"
+            "www.global.dk" = "US"
+            "www.example.co.uk" = "All"
+            "#,
+        )
+        .unwrap();
+
+        let mut webpage = Webpage::test_parse(html, "https://www.global.dk/").unwrap();
+        overrides.apply(&mut webpage);
+        assert_eq!(Region::guess_from(&webpage).unwrap(), Region::US);
+
+        let mut webpage = Webpage::test_parse(html, "https://www.example.co.uk/").unwrap();
+        overrides.apply(&mut webpage);
+        assert_eq!(Region::guess_from(&webpage).unwrap(), Region::All);
+
+        let mut webpage = Webpage::test_parse(html, "https://www.other.dk/").unwrap();
+        overrides.apply(&mut webpage);
+        assert_eq!(Region::guess_from(&webpage).unwrap(), Region::Denmark);
+    }
 }
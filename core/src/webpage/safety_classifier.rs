@@ -26,6 +26,22 @@ use crate::Result;
 
 const MAX_NUM_WORDS: usize = 100;
 
+/// Written at the start of every saved model, followed by the little endian [`FORMAT_VERSION`].
+const MAGIC: &[u8; 8] = b"NEOSSCLF";
+
+/// Version of the on-disk model format. Bump this whenever the
+/// encoding of [`Model`] (or the naive bayes pipeline it wraps) changes.
+const FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Incompatible model version: the model was saved with format version {found}, but version {expected} is required")]
+    IncompatibleVersion { found: u32, expected: u32 },
+
+    #[error("Model file is truncated: missing format version")]
+    MissingVersion,
+}
+
 #[derive(
     Debug,
     Clone,
@@ -43,6 +59,9 @@ const MAX_NUM_WORDS: usize = 100;
 pub enum Label {
     SFW,
     NSFW,
+    /// The model was not confident enough to classify the page.
+    /// It is never used as a label in the training data.
+    Unknown,
 }
 
 impl Display for Label {
@@ -50,6 +69,7 @@ impl Display for Label {
         let label = match self {
             Label::SFW => "SFW",
             Label::NSFW => "NSFW",
+            Label::Unknown => "Unknown",
         };
         write!(f, "{label}")
     }
@@ -62,6 +82,7 @@ impl TryFrom<&str> for Label {
         match value {
             "SFW" => Ok(Label::SFW),
             "NSFW" => Ok(Label::NSFW),
+            "Unknown" => Ok(Label::Unknown),
             _ => Err(format!("invalid label: {}", value)),
         }
     }
@@ -75,11 +96,18 @@ pub struct Datapoint {
     pub text: String,
 }
 
+/// Datapoints labelled [`Label::Unknown`] are rejected, since the label only
+/// describes predictions the model was not confident about.
 pub fn load_dataset<P: AsRef<Path>>(path: P) -> Result<Vec<Datapoint>> {
     let mut datapoints = Vec::new();
     let mut reader = csv::Reader::from_path(path)?;
     for result in reader.deserialize() {
         let datapoint: Datapoint = result?;
+
+        if datapoint.label == Label::Unknown {
+            anyhow::bail!("{} is not a valid training label", Label::Unknown);
+        }
+
         datapoints.push(datapoint);
     }
     Ok(datapoints)
@@ -92,22 +120,56 @@ fn normalize(text: &str) -> String {
         .to_lowercase()
 }
 
+/// Datapoints labelled [`Label::Unknown`] are never learned from.
+fn normalized(datapoints: &[Datapoint]) -> Vec<(String, Label)> {
+    datapoints
+        .iter()
+        .filter(|datapoint| datapoint.label != Label::Unknown)
+        .map(|datapoint| (normalize(&datapoint.text), datapoint.label))
+        .collect()
+}
+
 pub fn page_text(page: &crate::webpage::Webpage) -> String {
     page.html.title().unwrap_or_default()
         + " "
         + page.html.clean_text().cloned().unwrap_or_default().as_str()
 }
 
+/// Predictions that are [`Label::Unknown`] are only counted in `num_unknown`,
+/// so the other metrics describe the pages the model was confident about.
 pub struct Evaluation {
     pub accuracy: f64,
     pub precision: f64,
     pub recall: f64,
     pub f1: f64,
+    pub num_unknown: usize,
 }
 
-#[derive(bincode::Encode, bincode::Decode)]
 pub struct Model {
     pipeline: naive_bayes::Pipeline<Label>,
+    /// Predictions with a confidence below this are [`Label::Unknown`].
+    /// This is a setting of the indexer and is therefore not saved with the model.
+    unknown_threshold: Option<f32>,
+}
+
+impl bincode::Encode for Model {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        bincode::Encode::encode(&self.pipeline, encoder)
+    }
+}
+
+impl bincode::Decode for Model {
+    fn decode<D: bincode::de::Decoder>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self {
+            pipeline: bincode::Decode::decode(decoder)?,
+            unknown_threshold: None,
+        })
+    }
 }
 
 impl Default for Model {
@@ -119,20 +181,58 @@ impl Default for Model {
 impl Model {
     pub fn new() -> Self {
         let pipeline = naive_bayes::Pipeline::new();
-        Self { pipeline }
+        Self {
+            pipeline,
+            unknown_threshold: None,
+        }
+    }
+
+    pub fn with_unknown_threshold(mut self, threshold: f32) -> Self {
+        self.unknown_threshold = Some(threshold);
+        self
     }
 
     pub fn fit(&mut self, datapoints: &[Datapoint]) {
-        let datapoints: Vec<_> = datapoints
-            .iter()
-            .map(|datapoint| (normalize(&datapoint.text), datapoint.label))
-            .collect();
-        self.pipeline.fit(&datapoints);
+        self.pipeline.fit(&normalized(datapoints));
+    }
+
+    /// Fold new labelled datapoints into the model without
+    /// discarding what it has previously been trained on.
+    /// Models saved in the baseline format must be refitted with [`Model::fit`] first.
+    pub fn update(&mut self, datapoints: &[Datapoint]) -> Result<()> {
+        self.pipeline.update(&normalized(datapoints))
+    }
+
+    /// Same as [`Model::update`].
+    pub fn partial_fit(&mut self, datapoints: &[Datapoint]) -> Result<()> {
+        self.update(datapoints)
+    }
+
+    /// Remove previously learned datapoints from the model.
+    /// Mislabeled datapoints can be corrected by forgetting them
+    /// and updating the model with the correct label.
+    pub fn forget(&mut self, datapoints: &[Datapoint]) -> Result<()> {
+        self.pipeline.forget(&normalized(datapoints))
+    }
+
+    /// Decay everything the model has learned so far by `factor`, which must
+    /// be in `(0, 1]`. Calling this before [`Model::update`] lets recent
+    /// datapoints outweigh old ones without having to keep the old datapoints around.
+    pub fn decay(&mut self, factor: f32) -> Result<()> {
+        self.pipeline.decay(factor)
     }
 
     pub fn predict_text(&self, text: &str) -> naive_bayes::Prediction<Label> {
-        let text = normalize(text);
-        self.pipeline.predict(&text)
+        let text = normalize(text).unwrap();
+        let mut pred = self.pipeline.predict(&text);
+
+        if let Some(threshold) = self.unknown_threshold {
+            if pred.confidence < threshold {
+                pred.label = Label::Unknown;
+            }
+        }
+
+        pred
     }
 
     pub fn predict(&self, page: &crate::webpage::Webpage) -> naive_bayes::Prediction<Label> {
@@ -145,6 +245,7 @@ impl Model {
         let mut false_positives = 0;
         let mut true_negatives = 0;
         let mut false_negatives = 0;
+        let mut num_unknown = 0;
 
         for datapoint in datapoints {
             let pred = self.predict_text(&datapoint.text);
@@ -154,6 +255,8 @@ impl Model {
                 (Label::NSFW, Label::SFW) => false_positives += 1,
                 (Label::SFW, Label::SFW) => true_negatives += 1,
                 (Label::SFW, Label::NSFW) => false_negatives += 1,
+                (Label::Unknown, _) => num_unknown += 1,
+                (_, Label::Unknown) => continue,
             }
 
             if pred.label != datapoint.label {
@@ -167,7 +270,8 @@ impl Model {
             }
         }
 
-        let accuracy = (true_positives + true_negatives) as f64 / datapoints.len() as f64;
+        let num_classified = true_positives + false_positives + true_negatives + false_negatives;
+        let accuracy = (true_positives + true_negatives) as f64 / num_classified as f64;
         let precision = true_positives as f64 / (true_positives + false_positives) as f64;
         let recall = true_positives as f64 / (true_positives + false_negatives) as f64;
         let f1 = 2.0 * (precision * recall) / (precision + recall);
@@ -177,6 +281,7 @@ impl Model {
             precision,
             recall,
             f1,
+            num_unknown,
         }
     }
 
@@ -187,6 +292,8 @@ impl Model {
             .truncate(true)
             .open(path)?;
 
+        file.write_all(MAGIC)?;
+        file.write_all(&FORMAT_VERSION.to_le_bytes())?;
         bincode::encode_into_std_write(&self, &mut file, common::bincode_config())?;
         file.flush()?;
 
@@ -194,11 +301,379 @@ impl Model {
     }
 
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let file = OpenOptions::new().read(true).open(path)?;
-        let mut reader = std::io::BufReader::new(file);
+        let bytes = std::fs::read(path)?;
+
+        let Some(rest) = bytes.strip_prefix(MAGIC.as_slice()) else {
+            // models saved before the header was introduced
+            let (pipeline, _): (naive_bayes::PipelineV0<Label>, _) =
+                bincode::decode_from_slice(&bytes, common::bincode_config())?;
 
-        let model = bincode::decode_from_std_read(&mut reader, common::bincode_config())?;
+            return Ok(Self {
+                pipeline: pipeline.into(),
+                unknown_threshold: None,
+            });
+        };
+
+        let version: [u8; 4] = rest
+            .get(..4)
+            .and_then(|version| version.try_into().ok())
+            .ok_or(Error::MissingVersion)?;
+        let version = u32::from_le_bytes(version);
 
+        if version != FORMAT_VERSION {
+            return Err(Error::IncompatibleVersion {
+                found: version,
+                expected: FORMAT_VERSION,
+            }
+            .into());
+        }
+
+        let (model, _) = bincode::decode_from_slice(&rest[4..], common::bincode_config())?;
         Ok(model)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datapoint(text: &str, label: Label) -> Datapoint {
+        Datapoint {
+            label,
+            text: text.to_string(),
+        }
+    }
+
+    fn initial_dataset() -> Vec<Datapoint> {
+        vec![
+            datapoint("the weather is nice today", Label::SFW),
+            datapoint("recipes for a healthy breakfast", Label::SFW),
+            datapoint("learn to program in rust", Label::SFW),
+            datapoint("beach pictures from our holiday", Label::SFW),
+            datapoint("explicit adult content here", Label::NSFW),
+            datapoint("adult videos and explicit pictures", Label::NSFW),
+        ]
+    }
+
+    fn new_dataset() -> Vec<Datapoint> {
+        vec![
+            datapoint("hot beach videos with explicit adult content", Label::NSFW),
+            datapoint("explicit beach pictures", Label::NSFW),
+        ]
+    }
+
+    const QUERIES: [&str; 5] = [
+        "beach videos",
+        "beach pictures",
+        "explicit adult videos",
+        "learn rust",
+        "healthy breakfast",
+    ];
+
+    #[test]
+    fn fit_equals_update() {
+        let dataset = initial_dataset();
+
+        let mut fitted = Model::new();
+        fitted.fit(&dataset);
+
+        let mut updated = Model::new();
+        updated.update(&dataset).unwrap();
+
+        let mut partially_fitted = Model::new();
+        partially_fitted.partial_fit(&dataset).unwrap();
+
+        for query in QUERIES {
+            let a = fitted.predict_text(query);
+
+            for b in [
+                updated.predict_text(query),
+                partially_fitted.predict_text(query),
+            ] {
+                assert_eq!(a.label, b.label);
+                assert!((a.confidence - b.confidence).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn update_changes_borderline_prediction() {
+        let mut model = Model::new();
+        model.fit(&initial_dataset());
+
+        assert_eq!(model.predict_text("beach videos").label, Label::SFW);
+        assert_eq!(model.predict_text("learn rust").label, Label::SFW);
+
+        model.update(&new_dataset()).unwrap();
+
+        assert_eq!(model.predict_text("beach videos").label, Label::NSFW);
+        assert_eq!(model.predict_text("learn rust").label, Label::SFW);
+
+        let mut refitted = Model::new();
+        refitted.fit(
+            &initial_dataset()
+                .into_iter()
+                .chain(new_dataset())
+                .collect::<Vec<_>>(),
+        );
+
+        for query in QUERIES {
+            assert_eq!(
+                model.predict_text(query).label,
+                refitted.predict_text(query).label
+            );
+        }
+    }
+
+    #[test]
+    fn update_strongly_nsfw_example() {
+        let mut model = Model::new();
+        model.fit(&initial_dataset());
+
+        let before = model.predict_text("beach videos");
+        assert_eq!(before.label, Label::SFW);
+
+        model
+            .update(&[datapoint(
+                "explicit adult beach videos with explicit adult beach content",
+                Label::NSFW,
+            )])
+            .unwrap();
+
+        assert_eq!(model.predict_text("beach videos").label, Label::NSFW);
+        assert_eq!(model.predict_text("learn rust").label, Label::SFW);
+    }
+
+    #[test]
+    fn decay_favours_recent_datapoints() {
+        let relabelled = vec![
+            datapoint("beach videos from our holiday", Label::SFW),
+            datapoint("beach videos with the family", Label::SFW),
+        ];
+
+        let mut model = Model::new();
+        model.fit(&initial_dataset());
+        model.update(&new_dataset()).unwrap();
+        assert_eq!(model.predict_text("beach videos").label, Label::NSFW);
+
+        let mut decayed = Model::new();
+        decayed.fit(&initial_dataset());
+        decayed.update(&new_dataset()).unwrap();
+
+        model.update(&relabelled).unwrap();
+        decayed.decay(0.1).unwrap();
+        decayed.update(&relabelled).unwrap();
+
+        let nsfw_confidence = |pred: naive_bayes::Prediction<Label>| match pred.label {
+            Label::NSFW => pred.confidence,
+            _ => 1.0 - pred.confidence,
+        };
+
+        assert!(
+            nsfw_confidence(decayed.predict_text("explicit beach pictures"))
+                < nsfw_confidence(model.predict_text("explicit beach pictures"))
+        );
+        assert_eq!(decayed.predict_text("beach videos").label, Label::SFW);
+    }
+
+    #[test]
+    fn decay_rejects_invalid_factor() {
+        let mut model = Model::new();
+        model.fit(&initial_dataset());
+
+        assert!(model.decay(0.0).is_err());
+        assert!(model.decay(-0.5).is_err());
+        assert!(model.decay(1.5).is_err());
+        assert!(model.decay(f32::NAN).is_err());
+        assert!(model.decay(1.0).is_ok());
+    }
+
+    #[test]
+    fn save_and_open() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = dir.as_ref().join("model.bin");
+
+        let mut model = Model::new();
+        model.fit(&initial_dataset());
+        let expected: Vec<_> = QUERIES
+            .iter()
+            .map(|query| model.predict_text(query))
+            .collect();
+
+        model.save(&path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.starts_with(MAGIC));
+
+        let model = Model::open(&path).unwrap();
+
+        for (query, expected) in QUERIES.iter().zip(expected) {
+            let pred = model.predict_text(query);
+            assert_eq!(pred.label, expected.label);
+            assert!((pred.confidence - expected.confidence).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn open_baseline_format() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = dir.as_ref().join("model.bin");
+
+        // fitted on `initial_dataset` and saved by the pipeline from before
+        // it could be updated incrementally
+        std::fs::write(
+            &path,
+            include_bytes!("../../testcases/safety_classifier/baseline_model.bin"),
+        )
+        .unwrap();
+
+        let model = Model::open(&path).unwrap();
+
+        assert_eq!(model.predict_text("beach videos").label, Label::SFW);
+        assert_eq!(model.predict_text("beach pictures").label, Label::SFW);
+        assert_eq!(
+            model.predict_text("explicit adult videos").label,
+            Label::NSFW
+        );
+        assert_eq!(model.predict_text("learn rust").label, Label::SFW);
+        assert_eq!(model.predict_text("healthy breakfast").label, Label::SFW);
+    }
+
+    #[test]
+    fn baseline_format_must_be_refitted() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = dir.as_ref().join("model.bin");
+
+        std::fs::write(
+            &path,
+            include_bytes!("../../testcases/safety_classifier/baseline_model.bin"),
+        )
+        .unwrap();
+
+        let mut model = Model::open(&path).unwrap();
+        let expected: Vec<_> = QUERIES
+            .iter()
+            .map(|query| model.predict_text(query))
+            .collect();
+
+        assert!(model.update(&new_dataset()).is_err());
+        assert!(model.partial_fit(&new_dataset()).is_err());
+        assert!(model.forget(&initial_dataset()).is_err());
+        assert!(model.decay(0.5).is_err());
+
+        // the failed calls must leave the baseline model untouched
+        for (query, expected) in QUERIES.iter().zip(expected) {
+            let pred = model.predict_text(query);
+            assert_eq!(pred.label, expected.label);
+            assert!((pred.confidence - expected.confidence).abs() < 1e-5);
+        }
+
+        model.fit(&initial_dataset());
+        model.update(&new_dataset()).unwrap();
+        model.decay(0.5).unwrap();
+        model.forget(&new_dataset()).unwrap();
+        assert_eq!(model.predict_text("learn rust").label, Label::SFW);
+    }
+
+    #[test]
+    fn open_incompatible_version() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = dir.as_ref().join("model.bin");
+
+        let mut model = Model::new();
+        model.fit(&initial_dataset());
+        model.save(&path).unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        std::fs::write(&path, bytes).unwrap();
+
+        let err = Model::open(&path)
+            .err()
+            .expect("version mismatch should fail");
+
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::IncompatibleVersion { found, expected })
+                if *found == FORMAT_VERSION + 1 && *expected == FORMAT_VERSION
+        ));
+        assert!(err.to_string().contains("Incompatible model version"));
+    }
+
+    #[test]
+    fn unknown_below_threshold() {
+        let mut model = Model::new();
+        model.fit(&initial_dataset());
+
+        let pred = model.predict_text("beach videos");
+        assert_eq!(pred.label, Label::SFW);
+
+        // a confidence exactly at the threshold is still classified
+        let model = model.with_unknown_threshold(pred.confidence);
+        assert_eq!(model.predict_text("beach videos").label, Label::SFW);
+
+        let model = model.with_unknown_threshold(pred.confidence + 1e-4);
+        assert_eq!(model.predict_text("beach videos").label, Label::Unknown);
+
+        let dataset = vec![
+            datapoint("beach videos", Label::NSFW),
+            datapoint("explicit adult videos", Label::NSFW),
+        ];
+        let evaluation = model.evaluate(&dataset);
+
+        // the unknown prediction is neither a true nor a false positive
+        assert_eq!(evaluation.num_unknown, 1);
+        assert_eq!(evaluation.precision, 1.0);
+        assert_eq!(evaluation.accuracy, 1.0);
+    }
+
+    #[test]
+    fn unknown_is_not_a_training_label() {
+        let dir = crate::gen_temp_dir().unwrap();
+        let path = dir.as_ref().join("dataset.csv");
+
+        std::fs::write(&path, "label,text\nSFW,the weather is nice today\n").unwrap();
+        assert_eq!(load_dataset(&path).unwrap().len(), 1);
+
+        std::fs::write(
+            &path,
+            "label,text\nSFW,the weather is nice today\nUnknown,beach videos\n",
+        )
+        .unwrap();
+        assert!(load_dataset(&path).is_err());
+
+        let mut model = Model::new();
+        model.fit(&initial_dataset());
+
+        let mut with_unknown = Model::new();
+        let mut dataset = initial_dataset();
+        dataset.push(datapoint("beach videos", Label::Unknown));
+        with_unknown.fit(&dataset);
+
+        assert_eq!(
+            model.predict_text("beach videos").confidence,
+            with_unknown.predict_text("beach videos").confidence
+        );
+    }
+
+    #[test]
+    fn forget_reverts_update() {
+        let mut model = Model::new();
+        model.fit(&initial_dataset());
+
+        let before: Vec<_> = QUERIES
+            .iter()
+            .map(|query| model.predict_text(query))
+            .collect();
+
+        model.update(&new_dataset()).unwrap();
+        model.forget(&new_dataset()).unwrap();
+
+        for (query, before) in QUERIES.iter().zip(before) {
+            let after = model.predict_text(query);
+
+            assert_eq!(after.label, before.label);
+            assert!((after.confidence - before.confidence).abs() < 1e-5);
+        }
+    }
+}
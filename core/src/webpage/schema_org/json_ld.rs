@@ -18,14 +18,12 @@ use kuchiki::NodeRef;
 
 use super::RawItem;
 
-pub fn convert_all_to_strings(json: &str) -> Result<String, serde_json::Error> {
+/// Booleans are converted to strings. Numbers are kept as numbers.
+pub fn convert_booleans_to_strings(json: &str) -> Result<String, serde_json::Error> {
     use serde_json::Value;
 
     fn convert_recursively(json: &mut Value) {
         match json {
-            Value::Number(n) if n.is_u64() || n.is_i64() => {
-                *json = Value::String(n.to_string());
-            }
             Value::Bool(b) => {
                 *json = Value::String(b.to_string());
             }
@@ -53,7 +51,7 @@ pub(crate) fn parse(root: NodeRef) -> Vec<RawItem> {
         let text_contens = node.text_contents();
         let content = text_contens.trim();
 
-        match convert_all_to_strings(content) {
+        match convert_booleans_to_strings(content) {
             Ok(schema) => match serde_json::from_str(&schema) {
                 Ok(schema) => {
                     res.push(schema);
@@ -148,7 +146,7 @@ mod tests {
     }
 
     #[test]
-    fn numbers_as_strings() {
+    fn numbers() {
         let root = kuchiki::parse_html().one(
             r#"
     <html>
@@ -157,7 +155,8 @@ mod tests {
                 {
                 "@context": "https://schema.org",
                 "@type": "test",
-                "cost": 123
+                "cost": 123,
+                "rating": 4.5
                 }
             </script>
         </head>
@@ -177,7 +176,8 @@ mod tests {
                 itemtype: Some(RawOneOrMany::One("test".to_string())),
                 properties: hashmap! {
                     "@context".to_string() => RawOneOrMany::One(RawProperty::String("https://schema.org".to_string())),
-                    "cost".to_string() => RawOneOrMany::One(RawProperty::String("123".to_string())),
+                    "cost".to_string() => RawOneOrMany::One(RawProperty::Number(123.into())),
+                    "rating".to_string() => RawOneOrMany::One(RawProperty::Number(serde_json::Number::from_f64(4.5).unwrap())),
                 }
             }]
         );
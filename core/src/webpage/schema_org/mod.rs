@@ -30,33 +30,41 @@ mod microdata;
 pub const TYPE_PREFIX: char = '$';
 
 #[derive(
-    Debug,
-    Clone,
-    PartialEq,
-    Eq,
-    serde::Serialize,
-    serde::Deserialize,
-    bincode::Encode,
-    bincode::Decode,
+    Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode,
 )]
 pub enum Property {
     String(String),
     Item(Item),
+    /// A number from JSON-LD. Microdata values are always strings.
+    Number(f64),
 }
+
+// numbers are parsed from json, so they are never NaN
+impl Eq for Property {}
+
 impl Property {
     pub(crate) fn try_into_string(&self) -> Option<String> {
         match self {
             Property::String(s) => Some(s.clone()),
             Property::Item(_) => None,
+            Property::Number(n) => Some(n.to_string()),
         }
     }
 
     pub(crate) fn try_into_item(&self) -> Option<Item> {
         match self {
-            Property::String(_) => None,
+            Property::String(_) | Property::Number(_) => None,
             Property::Item(it) => Some(it.clone()),
         }
     }
+
+    fn try_into_number(&self) -> Option<f64> {
+        match self {
+            Property::String(s) => s.trim().parse().ok(),
+            Property::Item(_) => None,
+            Property::Number(n) => Some(*n),
+        }
+    }
 }
 
 #[derive(
@@ -79,6 +87,7 @@ impl From<Property> for FlattenedJsonMap {
     fn from(value: Property) -> Self {
         match value {
             Property::String(s) => FlattenedJsonMap::Leaf(s),
+            Property::Number(n) => FlattenedJsonMap::Leaf(n.to_string()),
             Property::Item(item) => {
                 let mut res = HashMap::new();
 
@@ -145,12 +154,13 @@ pub struct RawItem {
 enum RawProperty {
     String(String),
     Item(RawItem),
+    Number(serde_json::Number),
 }
 impl RawProperty {
     #[cfg(test)]
     fn try_into_item(&self) -> Option<RawItem> {
         match self {
-            RawProperty::String(_) => None,
+            RawProperty::String(_) | RawProperty::Number(_) => None,
             RawProperty::Item(it) => Some(it.clone()),
         }
     }
@@ -159,7 +169,7 @@ impl RawProperty {
     fn try_into_string(&self) -> Option<String> {
         match self {
             RawProperty::String(s) => Some(s.clone()),
-            RawProperty::Item(_) => None,
+            RawProperty::Item(_) | RawProperty::Number(_) => None,
         }
     }
 }
@@ -169,6 +179,7 @@ impl From<RawProperty> for Property {
         match value {
             RawProperty::String(s) => Self::String(s),
             RawProperty::Item(it) => Self::Item(Item::from(it)),
+            RawProperty::Number(n) => Self::Number(n.as_f64().unwrap_or_default()),
         }
     }
 }
@@ -268,6 +279,182 @@ pub fn parse(root: NodeRef) -> Vec<Item> {
     res.into_iter().map(Item::from).collect()
 }
 
+/// Extract question/answer pairs from `FAQPage` and `QAPage` items.
+/// Items wrapped in a JSON-LD `@graph` are also considered.
+pub fn faq_pairs(items: &[Item]) -> Vec<(String, String)> {
+    let mut res = Vec::new();
+
+    for item in items {
+        if let Some(graph) = item.properties.get("@graph") {
+            let graph: Vec<_> = graph
+                .clone()
+                .many()
+                .into_iter()
+                .filter_map(|prop| prop.try_into_item())
+                .collect();
+
+            res.extend(faq_pairs(&graph));
+        }
+
+        if !item.types_contains("FAQPage") && !item.types_contains("QAPage") {
+            continue;
+        }
+
+        let questions = item
+            .properties
+            .get("mainEntity")
+            .cloned()
+            .map(|entity| entity.many())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|prop| prop.try_into_item())
+            .filter(|item| item.types_contains("Question"));
+
+        for question in questions {
+            let Some(q) = first_string(&question, &["name", "text"]) else {
+                continue;
+            };
+
+            // prefer the accepted answer, but fall back to the first suggested answer
+            let answer = ["acceptedAnswer", "suggestedAnswer"]
+                .into_iter()
+                .filter_map(|key| question.properties.get(key))
+                .flat_map(|answers| answers.clone().many())
+                .filter_map(|prop| match prop {
+                    Property::String(s) => Some(s),
+                    Property::Number(n) => Some(n.to_string()),
+                    Property::Item(answer) => first_string(&answer, &["text"]),
+                })
+                .next();
+
+            if let Some(a) = answer {
+                res.push((q, a));
+            }
+        }
+    }
+
+    res
+}
+
+fn first_string(item: &Item, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|key| item.properties.get(*key))
+        .filter_map(|prop| prop.clone().one().and_then(|prop| prop.try_into_string()))
+        .map(|s| s.trim().to_string())
+        .find(|s| !s.is_empty())
+}
+
+/// Nested items deeper than this are not followed when resolving paths.
+const MAX_PATH_DEPTH: usize = 8;
+
+/// All dotted paths through the items, e.g. `Product`, `Product.offers` and `Product.offers.price`.
+///
+/// A path starts at an itemtype and follows the property names into nested items,
+/// without the types of the nested items. Nested items are also the start of their own paths,
+/// so `Offer.price` is included as well if the offer has a type.
+pub fn paths(items: &[Item]) -> Vec<String> {
+    let mut res = Vec::new();
+
+    for item in items {
+        item_paths(item, 0, &mut res);
+    }
+
+    res.sort();
+    res.dedup();
+
+    res
+}
+
+fn item_paths(item: &Item, depth: usize, res: &mut Vec<String>) {
+    if depth > MAX_PATH_DEPTH {
+        return;
+    }
+
+    let itemtypes = match &item.itemtype {
+        Some(OneOrMany::One(itemtype)) => std::slice::from_ref(itemtype),
+        Some(OneOrMany::Many(itemtypes)) => itemtypes.as_slice(),
+        None => &[],
+    };
+
+    for itemtype in itemtypes {
+        res.push(itemtype.clone());
+        property_paths(item, itemtype, 0, res);
+    }
+
+    for prop in item.properties.values().flat_map(properties) {
+        if let Property::Item(nested) = prop {
+            item_paths(nested, depth + 1, res);
+        }
+    }
+}
+
+fn property_paths(item: &Item, prefix: &str, depth: usize, res: &mut Vec<String>) {
+    if depth >= MAX_PATH_DEPTH {
+        return;
+    }
+
+    for (key, values) in &item.properties {
+        // skip json-ld keywords like '@context' and '@id'
+        if key.starts_with('@') {
+            continue;
+        }
+
+        let path = format!("{prefix}.{key}");
+
+        for prop in properties(values) {
+            if let Property::Item(nested) = prop {
+                property_paths(nested, &path, depth + 1, res);
+            }
+        }
+
+        res.push(path);
+    }
+}
+
+/// The `latitude`/`longitude` pairs of all `GeoCoordinates` items, including
+/// those nested in other items (e.g. the `geo` property of a `Place`).
+pub fn geo_coordinates(items: &[Item]) -> Vec<(f64, f64)> {
+    let mut res = Vec::new();
+
+    for item in items {
+        item_geo_coordinates(item, 0, &mut res);
+    }
+
+    res
+}
+
+fn item_geo_coordinates(item: &Item, depth: usize, res: &mut Vec<(f64, f64)>) {
+    if depth > MAX_PATH_DEPTH {
+        return;
+    }
+
+    if item.types_contains("GeoCoordinates") {
+        let coordinate = |key: &str| {
+            item.properties
+                .get(key)
+                .and_then(|values| properties(values).first())
+                .and_then(|prop| prop.try_into_number())
+        };
+
+        if let (Some(lat), Some(lon)) = (coordinate("latitude"), coordinate("longitude")) {
+            res.push((lat, lon));
+        }
+    }
+
+    for prop in item.properties.values().flat_map(properties) {
+        if let Property::Item(nested) = prop {
+            item_geo_coordinates(nested, depth + 1, res);
+        }
+    }
+}
+
+fn properties(values: &OneOrMany<Property>) -> &[Property] {
+    match values {
+        OneOrMany::One(one) => std::slice::from_ref(one),
+        OneOrMany::Many(many) => many.as_slice(),
+    }
+}
+
 pub(crate) fn flattened_json(schemas: Vec<Item>) -> Result<FlattenedJson> {
     let single_maps: Vec<_> = schemas
         .into_iter()
@@ -570,4 +757,42 @@ mod tests {
 
         assert_eq!(&instructions, "Helt enkelt som navnet antyder, så kom alle ingredienserne i en stor gryde på én gang. Kog retten op, rør godt rundt i gryden og skru ned for varmen. Lad det simrekoge under låg i 10-12 minutter, til spaghettien er perfekt kogt – al dente med lidt bid i. Server med revet parmesan og basilikum.");
     }
+
+    #[test]
+    fn dotted_paths() {
+        let html = r#"
+        <html>
+            <head>
+                <script type="application/ld+json">
+                {
+                    "@context": "https://schema.org",
+                    "@type": "Product",
+                    "name": "Running shoe",
+                    "offers": {
+                        "@type": "Offer",
+                        "price": "55.00",
+                        "priceCurrency": "EUR"
+                    }
+                }
+                </script>
+            </head>
+        </html>
+        "#;
+
+        let items = parse(kuchiki::parse_html().one(html));
+
+        assert_eq!(
+            paths(&items),
+            vec![
+                "Offer",
+                "Offer.price",
+                "Offer.priceCurrency",
+                "Product",
+                "Product.name",
+                "Product.offers",
+                "Product.offers.price",
+                "Product.offers.priceCurrency",
+            ]
+        );
+    }
 }
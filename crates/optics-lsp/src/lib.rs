@@ -282,6 +282,54 @@ fn err_to_diagnostic(err: optics::Error, source: &str) -> Diagnostic {
                 ..Default::default()
             }
         }
+        optics::Error::InvalidRegex {
+            token: (start, tok, end),
+        } => {
+            let message = format!("\"{tok}\" is not a valid regex");
+            Diagnostic {
+                range: Range {
+                    start: offset_to_pos(start, source)
+                        .expect("start offset should be within bounds of source"),
+                    end: offset_to_pos(end, source)
+                        .expect("end offset should be within bounds of source"),
+                },
+                severity: Some(DiagnosticSeverity::ERROR),
+                message,
+                ..Default::default()
+            }
+        }
+        optics::Error::UnknownLanguage {
+            token: (start, tok, end),
+        } => {
+            let message = format!("\"{tok}\" is not a known language code");
+            Diagnostic {
+                range: Range {
+                    start: offset_to_pos(start, source)
+                        .expect("start offset should be within bounds of source"),
+                    end: offset_to_pos(end, source)
+                        .expect("end offset should be within bounds of source"),
+                },
+                severity: Some(DiagnosticSeverity::ERROR),
+                message,
+                ..Default::default()
+            }
+        }
+        optics::Error::InvalidSchemaPath {
+            token: (start, tok, end),
+        } => {
+            let message = format!("\"{tok}\" is not a valid schema.org path");
+            Diagnostic {
+                range: Range {
+                    start: offset_to_pos(start, source)
+                        .expect("start offset should be within bounds of source"),
+                    end: offset_to_pos(end, source)
+                        .expect("end offset should be within bounds of source"),
+                },
+                severity: Some(DiagnosticSeverity::ERROR),
+                message,
+                ..Default::default()
+            }
+        }
         optics::Error::Unknown(start, end) => {
             let message = "We encountered an unknown error".to_string();
             Diagnostic {
@@ -29,27 +29,35 @@ pub static PARSER: std::sync::LazyLock<parser::BlocksParser> =
 pub struct RawOptic {
     pub rules: Vec<RawRule>,
     pub host_preferences: Vec<RawHostPreference>,
+    pub language_preferences: Vec<RawLanguagePreference>,
     pub discard_non_matching: bool,
+    pub secure_only: bool,
 }
 
 impl From<Vec<RawOpticBlock>> for RawOptic {
     fn from(blocks: Vec<RawOpticBlock>) -> Self {
         let mut rules = Vec::new();
         let mut host_preferences = Vec::new();
+        let mut language_preferences = Vec::new();
         let mut discard_non_matching = false;
+        let mut secure_only = false;
 
         for block in blocks {
             match block {
                 RawOpticBlock::Rule(rule) => rules.push(rule),
                 RawOpticBlock::HostPreference(pref) => host_preferences.push(pref),
+                RawOpticBlock::LanguagePreference(pref) => language_preferences.push(pref),
                 RawOpticBlock::DiscardNonMatching => discard_non_matching = true,
+                RawOpticBlock::SecureOnly => secure_only = true,
             }
         }
 
         RawOptic {
             rules,
             host_preferences,
+            language_preferences,
             discard_non_matching,
+            secure_only,
         }
     }
 }
@@ -58,7 +66,9 @@ impl From<Vec<RawOpticBlock>> for RawOptic {
 pub enum RawOpticBlock {
     Rule(RawRule),
     HostPreference(RawHostPreference),
+    LanguagePreference(RawLanguagePreference),
     DiscardNonMatching,
+    SecureOnly,
 }
 
 #[derive(Debug, PartialEq)]
@@ -73,6 +83,12 @@ pub enum RawHostPreference {
     Dislike(String),
 }
 
+/// A preferred language, stored as its ISO 639-3 code.
+#[derive(Debug, PartialEq)]
+pub enum RawLanguagePreference {
+    Prefer(String),
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct RawMatchBlock(pub Vec<RawMatchPart>);
 
@@ -80,12 +96,14 @@ pub struct RawMatchBlock(pub Vec<RawMatchPart>);
 pub enum RawMatchPart {
     Site(String),
     Url(String),
+    UrlRegex(String),
     Domain(String),
     Title(String),
     Description(String),
     Content(String),
     MicroformatTag(String),
     Schema(String),
+    SchemaPath(String),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -169,7 +187,9 @@ mod tests {
                     },
                 ],
                 host_preferences: vec![],
+                language_preferences: vec![],
                 discard_non_matching: false,
+                secure_only: false,
             }
         );
     }
@@ -212,7 +232,9 @@ mod tests {
                     },
                 ],
                 host_preferences: vec![],
+                language_preferences: vec![],
                 discard_non_matching: false,
+                secure_only: false,
             }
         );
     }
@@ -256,11 +278,171 @@ mod tests {
                     },
                 ],
                 host_preferences: vec![],
+                language_preferences: vec![],
                 discard_non_matching: true,
+                secure_only: false,
             }
         );
     }
 
+    #[test]
+    fn secure_only() {
+        let optic = parse(
+            r#"
+            SecureOnly;
+            Rule {
+                Matches {
+                    Site("example.com"),
+                },
+                Action(Boost(2))
+            };
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            optic,
+            RawOptic {
+                rules: vec![RawRule {
+                    matches: vec![RawMatchBlock(vec![RawMatchPart::Site(
+                        "example.com".to_string()
+                    )])],
+                    action: Some(RawAction::Boost(2)),
+                }],
+                host_preferences: vec![],
+                language_preferences: vec![],
+                discard_non_matching: false,
+                secure_only: true,
+            }
+        );
+    }
+
+    #[test]
+    fn url_regex() {
+        let optic = parse(
+            r#"
+            Rule {
+                Matches {
+                    UrlRegex("^https://[a-z]+\.example\.com/posts/\d+$")
+                },
+                Action(Boost(2))
+            };
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            optic,
+            RawOptic {
+                rules: vec![RawRule {
+                    matches: vec![RawMatchBlock(vec![RawMatchPart::UrlRegex(
+                        r"^https://[a-z]+\.example\.com/posts/\d+$".to_string()
+                    )])],
+                    action: Some(RawAction::Boost(2)),
+                },],
+                host_preferences: vec![],
+                language_preferences: vec![],
+                discard_non_matching: false,
+                secure_only: false,
+            }
+        );
+
+        assert!(matches!(
+            parse(
+                r#"
+                Rule {
+                    Matches {
+                        UrlRegex("/posts/(\d+")
+                    }
+                };
+            "#,
+            ),
+            Err(Error::InvalidRegex { .. })
+        ));
+    }
+
+    #[test]
+    fn schema_paths() {
+        let optic = parse(
+            r#"
+            Rule {
+                Matches {
+                    Schema("Recipe")
+                },
+                Matches {
+                    SchemaPath("Product.offers.price")
+                },
+                Action(Boost(3))
+            };
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            optic,
+            RawOptic {
+                rules: vec![RawRule {
+                    matches: vec![
+                        RawMatchBlock(vec![RawMatchPart::Schema("Recipe".to_string())]),
+                        RawMatchBlock(vec![RawMatchPart::SchemaPath(
+                            "Product.offers.price".to_string()
+                        )]),
+                    ],
+                    action: Some(RawAction::Boost(3)),
+                }],
+                host_preferences: vec![],
+                language_preferences: vec![],
+                discard_non_matching: false,
+                secure_only: false,
+            }
+        );
+
+        assert!(matches!(
+            parse(
+                r#"
+                Rule {
+                    Matches {
+                        SchemaPath("Product..price")
+                    }
+                };
+            "#,
+            ),
+            Err(Error::InvalidSchemaPath { .. })
+        ));
+    }
+
+    #[test]
+    fn language_preferences() {
+        let optic = parse(
+            r#"
+            Prefer(Language("de"));
+            Prefer(Language("fra"));
+            Like(Site("example.com"));
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            optic,
+            RawOptic {
+                rules: vec![],
+                host_preferences: vec![RawHostPreference::Like("example.com".to_string())],
+                language_preferences: vec![
+                    RawLanguagePreference::Prefer("deu".to_string()),
+                    RawLanguagePreference::Prefer("fra".to_string()),
+                ],
+                discard_non_matching: false,
+                secure_only: false,
+            }
+        );
+
+        // unknown language codes are rejected instead of silently never matching
+        assert!(matches!(
+            parse(r#"Prefer(Language("xx"));"#),
+            Err(Error::UnknownLanguage { .. })
+        ));
+    }
+
     #[test]
     fn quickstart_parse() {
         assert!(parse(include_str!("../testcases/samples/quickstart.optic")).is_ok());
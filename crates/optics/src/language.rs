@@ -0,0 +1,118 @@
+// Neos is an open source web search engine.
+// Copyright (C) 2024 Yeonwoo Sung
+//
+// This code is originated from Stract, which is licensed under the GNU Affero General Public License.
+
+use whatlang::Lang;
+
+/// ISO 639-1 aliases for the languages that can be detected on a page.
+/// The ISO 639-3 codes themselves come from [`whatlang::Lang`], which is what
+/// detects (and stores) the language of each page.
+const ISO_639_1: &[(&str, Lang)] = &[
+    ("af", Lang::Afr),
+    ("ak", Lang::Aka),
+    ("am", Lang::Amh),
+    ("ar", Lang::Ara),
+    ("az", Lang::Aze),
+    ("be", Lang::Bel),
+    ("bg", Lang::Bul),
+    ("bn", Lang::Ben),
+    ("ca", Lang::Cat),
+    ("cs", Lang::Ces),
+    ("da", Lang::Dan),
+    ("de", Lang::Deu),
+    ("el", Lang::Ell),
+    ("en", Lang::Eng),
+    ("eo", Lang::Epo),
+    ("es", Lang::Spa),
+    ("et", Lang::Est),
+    ("fa", Lang::Pes),
+    ("fi", Lang::Fin),
+    ("fr", Lang::Fra),
+    ("gu", Lang::Guj),
+    ("he", Lang::Heb),
+    ("hi", Lang::Hin),
+    ("hr", Lang::Hrv),
+    ("hu", Lang::Hun),
+    ("hy", Lang::Hye),
+    ("id", Lang::Ind),
+    ("it", Lang::Ita),
+    ("ja", Lang::Jpn),
+    ("jv", Lang::Jav),
+    ("ka", Lang::Kat),
+    ("km", Lang::Khm),
+    ("kn", Lang::Kan),
+    ("ko", Lang::Kor),
+    ("la", Lang::Lat),
+    ("lt", Lang::Lit),
+    ("lv", Lang::Lav),
+    ("mk", Lang::Mkd),
+    ("ml", Lang::Mal),
+    ("mr", Lang::Mar),
+    ("my", Lang::Mya),
+    ("nb", Lang::Nob),
+    ("ne", Lang::Nep),
+    ("nl", Lang::Nld),
+    ("or", Lang::Ori),
+    ("pa", Lang::Pan),
+    ("pl", Lang::Pol),
+    ("pt", Lang::Por),
+    ("ro", Lang::Ron),
+    ("ru", Lang::Rus),
+    ("si", Lang::Sin),
+    ("sk", Lang::Slk),
+    ("sl", Lang::Slv),
+    ("sn", Lang::Sna),
+    ("sr", Lang::Srp),
+    ("sv", Lang::Swe),
+    ("ta", Lang::Tam),
+    ("te", Lang::Tel),
+    ("th", Lang::Tha),
+    ("tk", Lang::Tuk),
+    ("tl", Lang::Tgl),
+    ("tr", Lang::Tur),
+    ("uk", Lang::Ukr),
+    ("ur", Lang::Urd),
+    ("uz", Lang::Uzb),
+    ("vi", Lang::Vie),
+    ("yi", Lang::Yid),
+    ("zh", Lang::Cmn),
+    ("zu", Lang::Zul),
+];
+
+/// The ISO 639-3 code of a language given either its ISO 639-1 or ISO 639-3 code.
+/// Returns `None` if the language is unknown or cannot be detected on pages.
+pub fn iso_639_3(code: &str) -> Option<&'static str> {
+    let code = code.trim().to_ascii_lowercase();
+
+    Lang::from_code(&code)
+        .or_else(|| {
+            ISO_639_1
+                .iter()
+                .find(|(two, _)| *two == code)
+                .map(|(_, lang)| *lang)
+        })
+        .map(|lang| lang.code())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes() {
+        assert_eq!(iso_639_3("de"), Some("deu"));
+        assert_eq!(iso_639_3("DE"), Some("deu"));
+        assert_eq!(iso_639_3("deu"), Some("deu"));
+        assert_eq!(iso_639_3("zh"), Some("cmn"));
+        assert_eq!(iso_639_3("xx"), None);
+        assert_eq!(iso_639_3(""), None);
+    }
+
+    #[test]
+    fn every_detectable_language_is_known() {
+        for lang in Lang::all() {
+            assert_eq!(iso_639_3(lang.code()), Some(lang.code()));
+        }
+    }
+}
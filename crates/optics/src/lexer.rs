@@ -30,6 +30,7 @@ pub enum Token<'a> {
     CloseParenthesis,
 
     DiscardNonMatching,
+    SecureOnly,
     Rule,
     RankingPipeline,
     Ranking,
@@ -39,18 +40,22 @@ pub enum Token<'a> {
     Matches,
     Site,
     Url,
+    UrlRegex,
     Domain,
     Title,
     Description,
     Content,
     MicroformatTag,
     Schema,
+    SchemaPath,
     Action,
     Boost,
     Downrank,
     Discard,
     Like,
     Dislike,
+    Prefer,
+    Language,
 
     String(&'a str),
     Number(&'a str),
@@ -66,6 +71,7 @@ impl<'a> Display for Token<'a> {
             Token::OpenParenthesis => f.write_str("("),
             Token::CloseParenthesis => f.write_str(")"),
             Token::DiscardNonMatching => f.write_str("DiscardNonMatching"),
+            Token::SecureOnly => f.write_str("SecureOnly"),
             Token::Rule => f.write_str("Rule"),
             Token::RankingPipeline => f.write_str("RankingPipeline"),
             Token::Ranking => f.write_str("Ranking"),
@@ -75,18 +81,22 @@ impl<'a> Display for Token<'a> {
             Token::Matches => f.write_str("Matches"),
             Token::Site => f.write_str("Site"),
             Token::Url => f.write_str("Url"),
+            Token::UrlRegex => f.write_str("UrlRegex"),
             Token::Domain => f.write_str("Domain"),
             Token::Title => f.write_str("Title"),
             Token::Description => f.write_str("Description"),
             Token::Content => f.write_str("Content"),
             Token::MicroformatTag => f.write_str("MicroformatTag"),
             Token::Schema => f.write_str("Schema"),
+            Token::SchemaPath => f.write_str("SchemaPath"),
             Token::Action => f.write_str("Action"),
             Token::Boost => f.write_str("Boost"),
             Token::Downrank => f.write_str("Downrank"),
             Token::Discard => f.write_str("Discard"),
             Token::Like => f.write_str("Like"),
             Token::Dislike => f.write_str("Dislike"),
+            Token::Prefer => f.write_str("Prefer"),
+            Token::Language => f.write_str("Language"),
             Token::String(s) => write!(f, "\"{s}\""),
             Token::Number(n) => write!(f, "{n}"),
         }
@@ -120,6 +130,8 @@ enum Outer<'a> {
 
     #[token("DiscardNonMatching")]
     DiscardNonMatching,
+    #[token("SecureOnly")]
+    SecureOnly,
     #[token("Rule")]
     Rule,
     #[token("RankingPipeline")]
@@ -138,6 +150,8 @@ enum Outer<'a> {
     Site,
     #[token("Url")]
     Url,
+    #[token("UrlRegex")]
+    UrlRegex,
     #[token("Domain")]
     Domain,
     #[token("Title")]
@@ -150,6 +164,8 @@ enum Outer<'a> {
     MicroformatTag,
     #[token("Schema")]
     Schema,
+    #[token("SchemaPath")]
+    SchemaPath,
     #[token("Action")]
     Action,
     #[token("Boost")]
@@ -162,6 +178,10 @@ enum Outer<'a> {
     Like,
     #[token("Dislike")]
     Dislike,
+    #[token("Prefer")]
+    Prefer,
+    #[token("Language")]
+    Language,
 
     #[regex(r"[+-]?([0-9]*[.])?[0-9]+", |lex| lex.slice())]
     Number(&'a str),
@@ -193,6 +213,9 @@ enum QuotedString<'a> {
     #[token(r#"\""#)]
     EscapedQuote,
 
+    #[token(r"\")]
+    Backslash,
+
     #[token("\"")]
     EndString,
 }
@@ -268,6 +291,7 @@ impl<'source> Iterator for LexerBridge<'source> {
                     }
                     Ok(QuotedString::Text(t)) => res.push_str(t),
                     Ok(QuotedString::EscapedQuote) => res.push('"'),
+                    Ok(QuotedString::Backslash) => res.push('\\'),
                     Ok(QuotedString::EndString) => break,
                 }
             }
@@ -312,20 +336,25 @@ impl<'source> Iterator for LexerBridge<'source> {
                 Outer::Matches => Some(Ok((s.start, Token::Matches, s.end))),
                 Outer::Site => Some(Ok((s.start, Token::Site, s.end))),
                 Outer::Url => Some(Ok((s.start, Token::Url, s.end))),
+                Outer::UrlRegex => Some(Ok((s.start, Token::UrlRegex, s.end))),
                 Outer::Domain => Some(Ok((s.start, Token::Domain, s.end))),
                 Outer::Title => Some(Ok((s.start, Token::Title, s.end))),
                 Outer::Description => Some(Ok((s.start, Token::Description, s.end))),
                 Outer::Content => Some(Ok((s.start, Token::Content, s.end))),
                 Outer::MicroformatTag => Some(Ok((s.start, Token::MicroformatTag, s.end))),
                 Outer::Schema => Some(Ok((s.start, Token::Schema, s.end))),
+                Outer::SchemaPath => Some(Ok((s.start, Token::SchemaPath, s.end))),
                 Outer::Action => Some(Ok((s.start, Token::Action, s.end))),
                 Outer::Boost => Some(Ok((s.start, Token::Boost, s.end))),
                 Outer::Downrank => Some(Ok((s.start, Token::Downrank, s.end))),
                 Outer::Discard => Some(Ok((s.start, Token::Discard, s.end))),
                 Outer::Like => Some(Ok((s.start, Token::Like, s.end))),
                 Outer::Dislike => Some(Ok((s.start, Token::Dislike, s.end))),
+                Outer::Prefer => Some(Ok((s.start, Token::Prefer, s.end))),
+                Outer::Language => Some(Ok((s.start, Token::Language, s.end))),
                 Outer::Number(n) => Some(Ok((s.start, Token::Number(n), s.end))),
                 Outer::DiscardNonMatching => Some(Ok((s.start, Token::DiscardNonMatching, s.end))),
+                Outer::SecureOnly => Some(Ok((s.start, Token::SecureOnly, s.end))),
             }
         } else {
             None
@@ -15,6 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 pub mod ast;
+mod language;
 mod lexer;
 
 use itertools::Itertools;
@@ -53,6 +54,15 @@ pub enum Error {
     #[error("Could not parse as a number")]
     NumberParse { token: (usize, String, usize) },
 
+    #[error("Invalid regex")]
+    InvalidRegex { token: (usize, String, usize) },
+
+    #[error("Unknown language code")]
+    UnknownLanguage { token: (usize, String, usize) },
+
+    #[error("Invalid schema.org path")]
+    InvalidSchemaPath { token: (usize, String, usize) },
+
     #[error("Unknown parse error")]
     Unknown(usize, usize),
 
@@ -69,6 +79,13 @@ pub fn parse(optic: &str) -> Result<Optic> {
     Optic::try_from(raw_optic)
 }
 
+/// A schema.org path is one or more dot separated itemtypes or properties,
+/// e.g. `Product` or `Product.offers.price`.
+pub(crate) fn is_schema_path(path: &str) -> bool {
+    path.split('.')
+        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_alphanumeric() || c == '_'))
+}
+
 impl TryFrom<RawOptic> for Optic {
     type Error = Error;
 
@@ -98,9 +115,19 @@ impl TryFrom<RawOptic> for Optic {
             }
         }
 
+        let preferred_languages = raw
+            .language_preferences
+            .into_iter()
+            .map(|pref| match pref {
+                ast::RawLanguagePreference::Prefer(lang) => lang,
+            })
+            .collect();
+
         Ok(Self {
             rules,
+            preferred_languages,
             discard_non_matching: raw.discard_non_matching,
+            secure_only: raw.secure_only,
             host_rankings: HostRankings {
                 liked: liked_hosts,
                 disliked: disliked_hosts,
@@ -162,12 +189,15 @@ impl Display for Matching {
         let s = match self.location {
             MatchLocation::Site => "Site",
             MatchLocation::Url => "Url",
+            MatchLocation::UrlRegex => "UrlRegex",
             MatchLocation::Domain => "Domain",
             MatchLocation::Title => "Title",
             MatchLocation::Description => "Description",
             MatchLocation::Content => "Content",
             MatchLocation::MicroformatTag => "MicroformatTag",
             MatchLocation::Schema => "Schema",
+            MatchLocation::Language => "Language",
+            MatchLocation::SchemaPath => "SchemaPath",
         };
         write!(f, "{s}(\"")?;
 
@@ -186,12 +216,26 @@ impl TryFrom<RawMatchPart> for Matching {
         let (s, loc) = match raw {
             RawMatchPart::Site(s) => (s, MatchLocation::Site),
             RawMatchPart::Url(s) => (s, MatchLocation::Url),
+            RawMatchPart::UrlRegex(regex) => {
+                // the regex is kept as-is since '*' and '|' are part of its syntax
+                return Ok(Self {
+                    location: MatchLocation::UrlRegex,
+                    pattern: vec![PatternPart::Raw(regex)],
+                });
+            }
             RawMatchPart::Domain(s) => (s, MatchLocation::Domain),
             RawMatchPart::Title(s) => (s, MatchLocation::Title),
             RawMatchPart::Description(s) => (s, MatchLocation::Description),
             RawMatchPart::Content(s) => (s, MatchLocation::Content),
             RawMatchPart::MicroformatTag(s) => (s, MatchLocation::MicroformatTag),
             RawMatchPart::Schema(s) => (s, MatchLocation::Schema),
+            RawMatchPart::SchemaPath(path) => {
+                // the path is matched exactly, so it is never split into a pattern
+                return Ok(Self {
+                    location: MatchLocation::SchemaPath,
+                    pattern: vec![PatternPart::Raw(path)],
+                });
+            }
         };
 
         let mut pattern = Vec::new();
@@ -318,6 +362,13 @@ pub enum MatchLocation {
     Content,
     MicroformatTag,
     Schema,
+    /// The ISO 639-3 code of the detected language of the page.
+    /// Only created from language preferences.
+    Language,
+    /// A regex that is matched against the full url.
+    UrlRegex,
+    /// An exact schema.org path like `Product.offers.price`.
+    SchemaPath,
 }
 
 #[derive(
@@ -364,13 +415,46 @@ impl Display for Action {
 pub struct Optic {
     pub host_rankings: HostRankings,
     pub rules: Vec<Rule>,
+    /// ISO 639-3 codes of the languages to boost.
+    #[serde(default)]
+    pub preferred_languages: Vec<String>,
     pub discard_non_matching: bool,
+    /// Only return pages served over https.
+    #[serde(default)]
+    pub secure_only: bool,
 }
 
+/// The boost given to pages in one of the preferred languages.
+const PREFERRED_LANGUAGE_BOOST: u64 = 2;
+
 impl Optic {
     pub fn parse(optic: &str) -> Result<Self> {
         parse(optic)
     }
+
+    /// A rule boosting the pages in one of the preferred languages.
+    #[must_use]
+    pub fn language_rule(&self) -> Option<Rule> {
+        if self.preferred_languages.is_empty() {
+            return None;
+        }
+
+        let matches = self
+            .preferred_languages
+            .iter()
+            .map(|lang| {
+                vec![Matching {
+                    pattern: vec![PatternPart::Raw(lang.clone())],
+                    location: MatchLocation::Language,
+                }]
+            })
+            .collect();
+
+        Some(Rule {
+            matches,
+            action: Action::Boost(PREFERRED_LANGUAGE_BOOST),
+        })
+    }
 }
 
 impl Display for Optic {
@@ -379,10 +463,18 @@ impl Display for Optic {
             writeln!(f, "DiscardNonMatching;")?;
         }
 
+        if self.secure_only {
+            writeln!(f, "SecureOnly;")?;
+        }
+
         for rule in &self.rules {
             write!(f, "{rule}")?;
         }
 
+        for lang in &self.preferred_languages {
+            writeln!(f, "Prefer(Language(\"{lang}\"));")?;
+        }
+
         write!(f, "{}", self.host_rankings)
     }
 }
@@ -616,7 +708,9 @@ mod tests {
                 }]],
                 action: Action::Boost(0),
             }],
+            preferred_languages: vec!["deu".to_string()],
             discard_non_matching: true,
+            secure_only: true,
         };
 
         let exported = optic.to_string();
@@ -8,6 +8,7 @@ use itertools::Itertools;
 use measure_time::debug_time;
 
 use crate::columnfield::ColumnFieldNotAvailableError;
+use crate::common::BitSet;
 use crate::directory::WritePtr;
 use crate::docset::{DocSet, TERMINATED};
 use crate::error::DataCorruption;
@@ -16,7 +17,7 @@ use crate::index::{Segment, SegmentComponent, SegmentReader};
 use crate::indexer::doc_id_mapping::{MappingType, SegmentDocIdMapping};
 use crate::indexer::SegmentSerializer;
 use crate::postings::{InvertedIndexSerializer, Postings, SegmentPostings};
-use crate::schema::{value_type_to_column_type, Field, FieldType, Schema};
+use crate::schema::{value_type_to_column_type, Field, FieldType, IndexRecordOption, Schema};
 use crate::store::StoreWriter;
 use crate::termdict::{TermMerger, TermOrdinal};
 use crate::{
@@ -47,6 +48,8 @@ pub struct IndexMerger {
     index_settings: IndexSettings,
     schema: Schema,
     pub(crate) readers: Vec<SegmentReader>,
+    /// The documents to keep from each reader. `None` keeps every document.
+    alive_bitsets: Vec<Option<BitSet>>,
     max_doc: u32,
 }
 
@@ -117,18 +120,49 @@ impl IndexMerger {
         index_settings: IndexSettings,
         segments: &[Segment],
     ) -> crate::Result<IndexMerger> {
+        let alive_bitsets = segments.iter().map(|_| None).collect();
+        Self::open_with_custom_alive_set(schema, index_settings, segments, alive_bitsets)
+    }
+
+    /// Creates a merger that only keeps the documents contained in the alive bitset of their
+    /// segment. `alive_bitsets` must have one entry per segment, where `None` keeps every
+    /// document of the segment.
+    pub fn open_with_custom_alive_set(
+        schema: Schema,
+        index_settings: IndexSettings,
+        segments: &[Segment],
+        alive_bitsets: Vec<Option<BitSet>>,
+    ) -> crate::Result<IndexMerger> {
+        if segments.len() != alive_bitsets.len() {
+            return Err(crate::TantivyError::InvalidArgument(format!(
+                "Got {} alive bitsets for {} segments",
+                alive_bitsets.len(),
+                segments.len()
+            )));
+        }
+
         let mut readers = vec![];
-        for segment in segments {
+        for (segment, alive_bitset) in segments.iter().zip(alive_bitsets) {
             if segment.meta().num_docs() > 0 {
                 let reader = SegmentReader::open(segment)?;
-                readers.push(reader);
+                readers.push((reader, alive_bitset));
             }
         }
 
-        let max_doc = readers.iter().map(|reader| reader.num_docs()).sum();
+        let max_doc = readers
+            .iter()
+            .map(|(reader, alive_bitset)| match alive_bitset {
+                Some(alive_bitset) => reader
+                    .doc_ids()
+                    .filter(|doc| alive_bitset.contains(*doc))
+                    .count() as u32,
+                None => reader.num_docs(),
+            })
+            .sum();
         if let Some(sort_by_field) = index_settings.sort_by_field.as_ref() {
             readers = Self::sort_readers_by_min_sort_field(readers, sort_by_field)?;
         }
+        let (readers, alive_bitsets) = readers.into_iter().unzip();
         // sort segments by their natural sort setting
         if max_doc >= MAX_DOC_LIMIT {
             let err_msg = format!(
@@ -141,20 +175,21 @@ impl IndexMerger {
             index_settings,
             schema,
             readers,
+            alive_bitsets,
             max_doc,
         })
     }
 
     fn sort_readers_by_min_sort_field(
-        readers: Vec<SegmentReader>,
+        readers: Vec<(SegmentReader, Option<BitSet>)>,
         sort_by_field: &IndexSortByField,
-    ) -> crate::Result<Vec<SegmentReader>> {
+    ) -> crate::Result<Vec<(SegmentReader, Option<BitSet>)>> {
         // presort the readers by their min_values, so that when they are disjunct, we can use
         // the regular merge logic (implicitly sorted)
         let mut readers_with_min_sort_values = readers
             .into_iter()
             .map(|reader| {
-                let accessor = Self::get_sort_field_accessor(&reader, sort_by_field)?;
+                let accessor = Self::get_sort_field_accessor(&reader.0, sort_by_field)?;
                 Ok((reader, accessor.min_value()))
             })
             .collect::<crate::Result<Vec<_>>>()?;
@@ -169,6 +204,20 @@ impl IndexMerger {
             .collect())
     }
 
+    /// Whether some documents are dropped by the merge.
+    fn has_filtered_docs(&self) -> bool {
+        self.alive_bitsets.iter().any(Option::is_some)
+    }
+
+    /// The documents of the reader at `segment_ord` that are kept by the merge.
+    fn alive_doc_ids(&self, segment_ord: usize) -> impl Iterator<Item = DocId> + '_ {
+        let alive_bitset = self.alive_bitsets[segment_ord].as_ref();
+
+        self.readers[segment_ord]
+            .doc_ids()
+            .filter(move |doc| alive_bitset.is_none_or(|alive| alive.contains(*doc)))
+    }
+
     fn write_fieldnorms(
         &self,
         mut fieldnorms_serializer: FieldNormsSerializer,
@@ -236,6 +285,7 @@ impl IndexMerger {
                     .iter_old_doc_addrs()
                     .map(|addr| crate::roworder::MergeAddr {
                         segment_ord: addr.segment_ord as usize,
+                        row_id: addr.doc_id as usize,
                     })
                     .collect(),
             },
@@ -321,19 +371,11 @@ impl IndexMerger {
             reader_ordinal_and_field_accessors
                 .iter()
                 .map(|(reader_ord, ff_reader)| {
-                    let reader = &self.readers[*reader_ord as usize];
-                    reader
-                        .doc_ids()
+                    self.alive_doc_ids(*reader_ord as usize)
                         .map(move |doc_id| (doc_id, reader_ord, ff_reader))
                 });
 
-        let total_num_new_docs = self
-            .readers
-            .iter()
-            .map(|reader| reader.num_docs() as usize)
-            .sum();
-
-        let mut sorted_doc_ids: Vec<DocAddress> = Vec::with_capacity(total_num_new_docs);
+        let mut sorted_doc_ids: Vec<DocAddress> = Vec::with_capacity(self.max_doc as usize);
 
         // create iterator tuple of (old doc_id, reader) in order of the new doc_ids
         sorted_doc_ids.extend(
@@ -363,27 +405,23 @@ impl IndexMerger {
     /// Creates a mapping if the segments are stacked. this is helpful to merge codelines between
     /// index sorting and the others
     pub(crate) fn get_doc_id_from_concatenated_data(&self) -> crate::Result<SegmentDocIdMapping> {
-        let total_num_new_docs = self
-            .readers
-            .iter()
-            .map(|reader| reader.num_docs() as usize)
-            .sum();
+        let mut mapping: Vec<DocAddress> = Vec::with_capacity(self.max_doc as usize);
 
-        let mut mapping: Vec<DocAddress> = Vec::with_capacity(total_num_new_docs);
-
-        mapping.extend(
-            self.readers
-                .iter()
-                .enumerate()
-                .flat_map(|(segment_ord, reader)| {
-                    reader.doc_ids().map(move |doc_id| DocAddress {
-                        segment_ord: segment_ord as u32,
-                        doc_id,
-                    })
-                }),
-        );
+        mapping.extend((0..self.readers.len()).flat_map(|segment_ord| {
+            self.alive_doc_ids(segment_ord)
+                .map(move |doc_id| DocAddress {
+                    segment_ord: segment_ord as u32,
+                    doc_id,
+                })
+        }));
 
-        let mapping_type = MappingType::Stacked;
+        // the stacked fast paths copy entire segments, so dropped documents
+        // require the addresses to be followed one by one.
+        let mapping_type = if self.has_filtered_docs() {
+            MappingType::Shuffled
+        } else {
+            MappingType::Stacked
+        };
         Ok(SegmentDocIdMapping::new(mapping, mapping_type))
     }
 
@@ -473,7 +511,23 @@ impl IndexMerger {
                 let inverted_index: &InvertedIndexReader = &field_readers[segment_ord];
                 let segment_postings = inverted_index
                     .read_postings_from_terminfo(&term_info, segment_postings_option)?;
-                let doc_freq = segment_postings.doc_freq();
+                let doc_freq = if self.alive_bitsets[segment_ord].is_some() {
+                    // only the documents that are kept by the merge count towards the doc freq.
+                    let mut postings = inverted_index
+                        .read_postings_from_terminfo(&term_info, IndexRecordOption::Basic)?;
+                    let old_to_new_doc_id = &merged_doc_id_map[segment_ord];
+                    let mut doc_freq = 0u32;
+                    let mut doc = postings.doc();
+                    while doc != TERMINATED {
+                        if old_to_new_doc_id[doc as usize].is_some() {
+                            doc_freq += 1;
+                        }
+                        doc = postings.advance();
+                    }
+                    doc_freq
+                } else {
+                    segment_postings.doc_freq()
+                };
                 if doc_freq > 0u32 {
                     total_doc_freq += doc_freq;
                     segment_postings_containing_the_term.push((segment_ord, segment_postings));
@@ -628,10 +682,17 @@ impl IndexMerger {
 
             let mut document_iterators: Vec<_> =
                 store_readers.iter().map(|store| store.iter_raw()).collect();
+            // the next doc id of each iterator. documents of a segment are always visited in
+            // increasing order, so the ones that are dropped by the merge can simply be skipped.
+            let mut next_doc_ids: Vec<DocId> = vec![0; document_iterators.len()];
 
             for old_doc_addr in doc_id_mapping.iter_old_doc_addrs() {
-                let doc_bytes_it = &mut document_iterators[old_doc_addr.segment_ord as usize];
-                if let Some(doc_bytes_res) = doc_bytes_it.next() {
+                let segment_ord = old_doc_addr.segment_ord as usize;
+                let doc_bytes_it = &mut document_iterators[segment_ord];
+                let num_skipped = old_doc_addr.doc_id - next_doc_ids[segment_ord];
+                next_doc_ids[segment_ord] = old_doc_addr.doc_id + 1;
+
+                if let Some(doc_bytes_res) = doc_bytes_it.nth(num_skipped as usize) {
                     let doc_bytes = doc_bytes_res?;
                     store_writer.store_bytes(&doc_bytes)?;
                 } else {
@@ -706,6 +767,8 @@ mod tests {
     use crate::collector::tests::{
         BytesColumnFieldTestCollector, ColumnFieldTestCollector, TEST_COLLECTOR_WITH_SCORE,
     };
+    use crate::common::BitSet;
+    use crate::directory::RamDirectory;
     use crate::index::{Index, SegmentId};
     use crate::query::{BooleanQuery, EnableScoring, Scorer, TermQuery};
     use crate::schema::{
@@ -1354,4 +1417,110 @@ mod tests {
         assert_eq!(1, index.searchable_segments()?.len());
         Ok(())
     }
+
+    #[test]
+    fn test_index_merger_with_alive_bitsets() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_fieldtype = schema::TextOptions::default()
+            .set_indexing_options(
+                TextFieldIndexing::default().set_index_option(IndexRecordOption::WithFreqs),
+            )
+            .set_stored();
+        let text_field = schema_builder.add_text_field("text", text_fieldtype);
+        let score_field = schema_builder.add_u64_field("score", COLUMN);
+        let row_field = schema_builder.add_u64_field("row", schema::INDEXED | schema::ROW_ORDER);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            for (text, score) in [("a b", 1u64), ("a c", 2), ("a d", 3)] {
+                index_writer.add_document(doc!(
+                    text_field => text,
+                    score_field => score,
+                    row_field => score
+                ))?;
+            }
+            index_writer.commit()?;
+            for (text, score) in [("a e", 4u64), ("a f", 5)] {
+                index_writer.add_document(doc!(
+                    text_field => text,
+                    score_field => score,
+                    row_field => score
+                ))?;
+            }
+            index_writer.commit()?;
+        }
+
+        let segments = index.searchable_segments()?;
+        assert_eq!(segments.len(), 2);
+
+        let alive_bitsets = segments
+            .iter()
+            .map(|segment| {
+                let mut alive = BitSet::with_max_value(segment.meta().max_doc());
+                // keep the documents with an odd score
+                if segment.meta().max_doc() == 3 {
+                    alive.insert(0);
+                    alive.insert(2);
+                } else {
+                    alive.insert(1);
+                }
+                Some(alive)
+            })
+            .collect();
+
+        let merged = crate::indexer::merge_filtered_segments(
+            &segments,
+            index.settings().clone(),
+            alive_bitsets,
+            RamDirectory::default(),
+        )?;
+
+        let reader = merged.reader()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.num_docs(), 3);
+
+        let count = |text: &str| {
+            let query = TermQuery::new(
+                Term::from_field_text(text_field, text),
+                IndexRecordOption::Basic,
+            );
+            searcher.search(&query, &crate::collector::Count)
+        };
+        assert_eq!(count("a")?, 3);
+        assert_eq!(count("b")?, 1);
+        assert_eq!(count("c")?, 0);
+        assert_eq!(count("e")?, 0);
+        assert_eq!(count("f")?, 1);
+
+        let query = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::Basic,
+        );
+        let mut scores = searcher.search(&query, &ColumnFieldTestCollector::for_field("score"))?;
+        scores.sort_unstable();
+        assert_eq!(scores, vec![1, 3, 5]);
+
+        let segment_reader = searcher.segment_reader(0);
+        let row_index = segment_reader.row_fields().row_index();
+        let mut texts = Vec::new();
+        for doc in segment_reader.doc_ids() {
+            let stored = searcher.doc::<TantivyDocument>(DocAddress::new(0, doc))?;
+            let text = stored.get_first(text_field).unwrap().as_str().unwrap();
+            let row = row_index.get_row(doc as usize).unwrap();
+
+            texts.push((text.to_string(), row.get_u64(&row_field)));
+        }
+        texts.sort();
+
+        assert_eq!(
+            texts,
+            vec![
+                ("a b".to_string(), Some(1)),
+                ("a d".to_string(), Some(3)),
+                ("a f".to_string(), Some(5)),
+            ]
+        );
+
+        Ok(())
+    }
 }
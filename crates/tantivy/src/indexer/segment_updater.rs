@@ -9,6 +9,7 @@ use std::sync::{Arc, RwLock};
 use rayon::{ThreadPool, ThreadPoolBuilder};
 
 use super::segment_manager::SegmentManager;
+use crate::common::BitSet;
 use crate::core::META_FILEPATH;
 use crate::directory::{Directory, DirectoryClone, GarbageCollectionResult};
 use crate::index::{Index, IndexMeta, IndexSettings, Segment, SegmentId, SegmentMeta};
@@ -152,11 +153,13 @@ pub fn merge_indices<T: Into<Box<dyn Directory>>>(
         segments.extend(index.searchable_segments()?);
     }
 
-    merge_filtered_segments(&segments, target_settings, output_directory)
+    let non_filter = segments.iter().map(|_| None).collect::<Vec<_>>();
+    merge_filtered_segments(&segments, target_settings, non_filter, output_directory)
 }
 
 /// Advanced: Merges a list of segments from different indices in a new index.
-/// Additional you can provide a delete bitset for each segment to ignore doc_ids.
+/// Additional you can provide an alive bitset for each segment, where the doc_ids
+/// that are not in the bitset are left out of the merged index.
 ///
 /// Returns `TantivyError` if the indices list is empty or their
 /// schemas don't match.
@@ -171,6 +174,7 @@ pub fn merge_indices<T: Into<Box<dyn Directory>>>(
 pub fn merge_filtered_segments<T: Into<Box<dyn Directory>>>(
     segments: &[Segment],
     target_settings: IndexSettings,
+    filter_doc_ids: Vec<Option<BitSet>>,
     output_directory: T,
 ) -> crate::Result<Index> {
     if segments.is_empty() {
@@ -200,10 +204,11 @@ pub fn merge_filtered_segments<T: Into<Box<dyn Directory>>>(
     )?;
     let merged_segment = merged_index.new_segment();
     let merged_segment_id = merged_segment.id();
-    let merger: IndexMerger = IndexMerger::open(
+    let merger: IndexMerger = IndexMerger::open_with_custom_alive_set(
         merged_index.schema(),
         merged_index.settings().clone(),
         segments,
+        filter_doc_ids,
     )?;
     let segment_serializer = SegmentSerializer::for_segment(merged_segment, true)?;
     let num_docs = merger.write(segment_serializer)?;
@@ -235,6 +240,45 @@ pub fn merge_filtered_segments<T: Into<Box<dyn Directory>>>(
     Ok(merged_index)
 }
 
+/// Advanced: Merges a list of segments of `index` into a new segment of the same index.
+/// Additional you can provide an alive bitset for each segment, where the doc_ids
+/// that are not in the bitset are left out of the merged segment.
+///
+/// The segment files are written through the `ManagedDirectory` of `index`, so they
+/// are garbage collected like any other segment once they are no longer used.
+/// The new segment is NOT added to the index meta. It is up to the caller to save
+/// metas that replace the merged segments with the returned segment meta.
+///
+/// # Warning
+/// This function does NOT check or take the `IndexWriter` is running. It is not
+/// meant to work if you have an `IndexWriter` running for `index`, as its garbage
+/// collection could remove the new segment before it is added to the index meta.
+#[doc(hidden)]
+pub fn merge_filtered_segments_into(
+    index: &Index,
+    segments: &[Segment],
+    filter_doc_ids: Vec<Option<BitSet>>,
+) -> crate::Result<SegmentMeta> {
+    if segments.is_empty() {
+        return Err(crate::TantivyError::InvalidArgument(
+            "No segments given to merge".to_string(),
+        ));
+    }
+
+    let merged_segment = index.new_segment();
+    let merged_segment_id = merged_segment.id();
+    let merger: IndexMerger = IndexMerger::open_with_custom_alive_set(
+        index.schema(),
+        index.settings().clone(),
+        segments,
+        filter_doc_ids,
+    )?;
+    let segment_serializer = SegmentSerializer::for_segment(merged_segment, true)?;
+    let num_docs = merger.write(segment_serializer)?;
+
+    Ok(index.new_segment_meta(merged_segment_id, num_docs))
+}
+
 pub(crate) struct InnerSegmentUpdater {
     // we keep a copy of the current active IndexMeta to
     // avoid loading the file every time we need it in the
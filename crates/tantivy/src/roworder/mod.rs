@@ -496,6 +496,7 @@ impl RowIndex {
 #[derive(Debug, Clone)]
 pub struct MergeAddr {
     pub segment_ord: usize,
+    pub row_id: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -539,10 +540,8 @@ where
             }
         }
         MergeRowOrder::Shuffled { addrs } => {
-            let mut iters: Vec<_> = indexes.iter().map(|index| index.borrow().iter()).collect();
-
             for addr in addrs {
-                if let Some(row) = iters[addr.segment_ord].next() {
+                if let Some(row) = indexes[addr.segment_ord].borrow().get_row(addr.row_id) {
                     writer.write_row(row.values.as_slice())
                 }
             }
@@ -771,10 +770,13 @@ mod tests {
         let buf = merge(
             &indexes,
             MergeRowOrder::Shuffled {
-                addrs: (0..indexes.len())
-                    .map(|i| MergeAddr { segment_ord: i })
-                    .cycle()
-                    .take(indexes.len() * rows.len())
+                addrs: (0..rows.len())
+                    .flat_map(|row_id| {
+                        (0..indexes.len()).map(move |segment_ord| MergeAddr {
+                            segment_ord,
+                            row_id,
+                        })
+                    })
                     .collect(),
             },
             buf,
@@ -828,9 +830,18 @@ mod tests {
 
         let order = MergeRowOrder::Shuffled {
             addrs: vec![
-                MergeAddr { segment_ord: 0 },
-                MergeAddr { segment_ord: 1 },
-                MergeAddr { segment_ord: 0 },
+                MergeAddr {
+                    segment_ord: 0,
+                    row_id: 0,
+                },
+                MergeAddr {
+                    segment_ord: 1,
+                    row_id: 0,
+                },
+                MergeAddr {
+                    segment_ord: 0,
+                    row_id: 1,
+                },
             ],
         };
         let buf = Vec::new();